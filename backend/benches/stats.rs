@@ -0,0 +1,67 @@
+//! Latency check for the statistics behind the performance and risk endpoints.
+//!
+//! Run with `cargo bench --bench stats`. Simulates 5 years of daily closes for the
+//! largest correlation request (50 series) and fails if P99 exceeds the 100ms budget.
+
+#[path = "../src/utils/stats.rs"]
+mod stats;
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use stats::{correlation_matrix, risk_stats, CloseSeries};
+
+const YEARS: i64 = 5;
+const SERIES: usize = 50;
+const ITERATIONS: usize = 200;
+const P99_BUDGET: Duration = Duration::from_millis(100);
+
+/// Deterministic random walk so runs are comparable
+fn synthetic_closes(seed: u64, days: i64, skip_weekends: bool) -> BTreeMap<String, f64> {
+    let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    let mut next = || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    let start = chrono::NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+    let mut price = 100.0;
+    let mut closes = BTreeMap::new();
+    for day in 0..days {
+        let date = start + chrono::Duration::days(day);
+        if skip_weekends && matches!(chrono::Datelike::weekday(&date), chrono::Weekday::Sat | chrono::Weekday::Sun) {
+            continue;
+        }
+        price *= 1.0 + (next() - 0.5) * 0.04;
+        closes.insert(date.format("%Y-%m-%d").to_string(), price);
+    }
+    closes
+}
+
+fn main() {
+    let days = YEARS * 365;
+    // Mix of 7-day (crypto) and weekday (stock) calendars, as in real portfolios
+    let daily: Vec<BTreeMap<String, f64>> = (0..SERIES)
+        .map(|i| synthetic_closes(i as u64 + 1, days, i % 2 == 0))
+        .collect();
+
+    let mut timings = Vec::with_capacity(ITERATIONS);
+    for _ in 0..ITERATIONS {
+        let started = Instant::now();
+        let series: Vec<CloseSeries> = daily.iter().map(CloseSeries::from).collect();
+        let matrix = correlation_matrix(&series);
+        let risk: Vec<_> = series.iter().map(|s| risk_stats(&s.closes, 252.0)).collect();
+        std::hint::black_box((matrix, risk));
+        timings.push(started.elapsed());
+    }
+    timings.sort();
+
+    let percentile = |p: f64| timings[((timings.len() as f64 * p).ceil() as usize).saturating_sub(1)];
+    let (p50, p99) = (percentile(0.50), percentile(0.99));
+    println!(
+        "stats: {} series x {} days, {} iterations: p50 {:?}, p99 {:?}, max {:?}",
+        SERIES, days, ITERATIONS, p50, p99, timings[timings.len() - 1]
+    );
+    if p99 > P99_BUDGET {
+        eprintln!("P99 {:?} exceeds the {:?} budget", p99, P99_BUDGET);
+        std::process::exit(1);
+    }
+}
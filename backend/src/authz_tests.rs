@@ -0,0 +1,575 @@
+//! Authorization matrix for the HTTP API.
+//!
+//! Every route registered in `api_routes` is called anonymously, as a regular user and as an
+//! admin, and the status is checked against the access level listed in `matrix()`. PocketBase
+//! and all upstream providers point at a closed port, so handlers that get past the auth check
+//! fail fast with a non-auth error instead of touching the network.
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::Router;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tower::Service;
+
+use crate::config::Config;
+use crate::services::{
+    AlertService, AuthService, BalanceSyncService, CredentialStore, ExchangeRateService, ExchangeSyncService,
+    JobScheduler, NotificationService, ExportService, OrderWatcher, PocketBaseClient, PriceRefresher, PriceService, RateLimiter, SnapshotCache,
+    ClientModes, PublicStatusService, RealtimeBridge, SymbolHeat, SymbolsService,
+};
+use crate::AppState;
+
+/// Nothing listens here; connections are refused immediately
+const CLOSED_PORT_URL: &str = "http://127.0.0.1:9";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const TEST_PASSWORD: &str = "secret123";
+const USER_EMAIL: &str = "user@authz.test";
+const ADMIN_EMAIL: &str = "admin@authz.test";
+/// Replaced with a valid user token in request bodies (also for anonymous calls, since such
+/// endpoints take the token as input rather than as credentials)
+const TOKEN_PLACEHOLDER: &str = "$TOKEN";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Access {
+    /// No credentials needed
+    Public,
+    /// Any signed-in user
+    User,
+    /// Signed-in admin only
+    Admin,
+    /// Admin only, or any signed-in user when OPS_READ_ACCESS_FOR_USERS is enabled
+    OpsRead,
+}
+
+struct Case {
+    method: Method,
+    /// Path as registered (`:param` segments are filled in by `path_param` when calling)
+    route: &'static str,
+    /// Query string appended when calling, for handlers with required query params
+    query: &'static str,
+    access: Access,
+    /// Request body; must deserialize so anonymous calls reach the auth check
+    body: Value,
+}
+
+fn case(method: Method, route: &'static str, access: Access) -> Case {
+    Case { method, route, query: "", access, body: json!({}) }
+}
+
+impl Case {
+    fn query(mut self, query: &'static str) -> Self {
+        self.query = query;
+        self
+    }
+
+    fn body(mut self, body: Value) -> Self {
+        self.body = body;
+        self
+    }
+
+    fn uri(&self) -> String {
+        let path: Vec<&str> = self.route.split('/')
+            .map(|segment| segment.strip_prefix(':').map(path_param).unwrap_or(segment))
+            .collect();
+        format!("/api/v1{}{}", path.join("/"), self.query)
+    }
+}
+
+/// A value the path extractor accepts, so the request reaches the handler
+fn path_param(name: &str) -> &'static str {
+    match name {
+        "asset_type" => "stock",
+        "market" => "set",
+        "provider" => "google",
+        "symbol" => "PTT",
+        "api_name" => "coingecko",
+        "channel" => "email",
+        _ => "abc123def456ghi",
+    }
+}
+
+fn transaction_body() -> Value {
+    json!({
+        "asset_type": "stock",
+        "symbol": "AAPL",
+        "action": "buy",
+        "quantity": 1.0,
+        "price": 100.0,
+        "timestamp": "2024-01-01T00:00:00Z",
+    })
+}
+
+/// Expected access level of every API route
+fn matrix() -> Vec<Case> {
+    use Access::*;
+    vec![
+        case(Method::GET, "/status", Public),
+        case(Method::GET, "/status/public", Public),
+
+        // Auth
+        case(Method::GET, "/auth/providers", Public),
+        case(Method::POST, "/auth/guest", Public),
+        case(Method::GET, "/auth/google", Public),
+        case(Method::GET, "/auth/google/callback", Public).query("?code=x&state=y"),
+        case(Method::GET, "/auth/oidc", Public),
+        case(Method::GET, "/auth/oidc/callback", Public).query("?code=x&state=y"),
+        case(Method::GET, "/auth/me", User),
+        case(Method::POST, "/auth/logout", Public),
+        case(Method::POST, "/auth/verify", Public).body(json!({ "token": TOKEN_PLACEHOLDER })),
+        case(Method::GET, "/auth/linked-providers", User),
+        case(Method::DELETE, "/auth/unlink/:provider", User),
+        case(Method::POST, "/auth/local/login", Public).body(json!({ "email": USER_EMAIL, "password": TEST_PASSWORD })),
+        case(Method::POST, "/auth/local/register", Public).body(json!({ "email": "new@example.com", "password": "secret123" })),
+        case(Method::POST, "/auth/local/verify", Public).body(json!({ "token": "x" })),
+        case(Method::POST, "/auth/local/resend-verification", Public).body(json!({ "email": "x@example.com" })),
+        case(Method::POST, "/auth/logout-all", User),
+        case(Method::GET, "/auth/sessions", User),
+        // Same password again, so later cases can still log in
+        case(Method::POST, "/auth/change-password", User).body(json!({ "old_password": TEST_PASSWORD, "new_password": TEST_PASSWORD })),
+        case(Method::GET, "/settings", User),
+        case(Method::PUT, "/settings", User).body(json!({ "locale": "th-TH" })),
+
+        // Transactions
+        case(Method::GET, "/transactions", User),
+        case(Method::POST, "/transactions", User).body(transaction_body()),
+        case(Method::GET, "/transactions/:id", User),
+        case(Method::PUT, "/transactions/:id", User),
+        case(Method::DELETE, "/transactions/:id", User),
+        case(Method::GET, "/transactions/type/:asset_type", User),
+        case(Method::GET, "/transactions/export", User),
+        case(Method::POST, "/transactions/bulk-delete", User).body(json!({ "ids": [] })),
+        case(Method::POST, "/transactions/bulk-delete/:batch_id/restore", User),
+        case(Method::POST, "/transactions/bulk", User).body(json!([transaction_body()])),
+        case(Method::GET, "/custom-fields", User),
+        case(Method::POST, "/custom-fields", User).body(json!({ "key": "k", "label": "K", "field_type": "text" })),
+        case(Method::PUT, "/custom-fields/:id", User),
+        case(Method::DELETE, "/custom-fields/:id", User),
+        case(Method::GET, "/dashboards", User),
+        case(Method::POST, "/dashboards", User).body(json!({ "name": "Main" })),
+        case(Method::GET, "/dashboards/:id", User),
+        case(Method::PUT, "/dashboards/:id", User).body(json!({ "name": "Main" })),
+        case(Method::DELETE, "/dashboards/:id", User),
+        case(Method::POST, "/import/crypto", User),
+        case(Method::POST, "/transactions/import", User),
+        case(Method::POST, "/import/:id/reconcile", User).body(json!({ "balances": { "USD": 100.0 } })),
+
+        // Portfolio
+        case(Method::GET, "/portfolio", User),
+        case(Method::GET, "/portfolio/summary", User),
+        case(Method::POST, "/portfolio/refresh", User),
+        case(Method::GET, "/portfolio/refresh/:job_id", User),
+        case(Method::GET, "/portfolio/type/:asset_type", User),
+        case(Method::GET, "/portfolio/market/:market", User),
+        case(Method::GET, "/portfolio/movers", User),
+        case(Method::GET, "/portfolio/heatmap", User),
+        case(Method::GET, "/portfolio/correlations", User),
+        case(Method::POST, "/portfolio/rebalance/plan", User).body(json!({
+            "targets": [{ "symbol": "PTT", "asset_type": "stock", "weight_percent": 100.0 }],
+        })),
+        case(Method::GET, "/portfolio/currency-reconciliation", User),
+        case(Method::GET, "/portfolio/reconcile", User),
+        case(Method::GET, "/portfolio/realized/monthly", User),
+        case(Method::GET, "/portfolio/allocation", User),
+        case(Method::GET, "/portfolio/performance/benchmark", User),
+        case(Method::GET, "/insights", User),
+        case(Method::GET, "/performance", User),
+        case(Method::GET, "/performance/nav", User),
+        case(Method::GET, "/analytics/trades", User),
+
+        // Prices
+        case(Method::GET, "/prices/:symbol", Public),
+        case(Method::GET, "/prices/history/:symbol", Public),
+        case(Method::POST, "/prices/batch", Public),
+        case(Method::GET, "/prices/:symbol/history", Public).query("?asset_type=crypto"),
+        case(Method::GET, "/assets/:symbol/chart", User).query("?asset_type=stock&range=1y"),
+        case(Method::GET, "/ws/prices", User),
+        case(Method::GET, "/ws/changes", User),
+        case(Method::PUT, "/clients/mode", User).body(json!({ "client_id": "tab-4f9c2a1b", "mode": "background" })),
+        case(Method::POST, "/prices/cache/clear", Admin),
+        case(Method::GET, "/prices/heat", Admin),
+        case(Method::GET, "/prices/thai-gold", Public),
+        case(Method::GET, "/prices/streams", Public),
+        case(Method::GET, "/prices/quarantine", Admin),
+        case(Method::POST, "/prices/quarantine/release", Admin).body(json!({ "cache_key": "stock:PTT" })),
+
+        // Exchange rates
+        case(Method::GET, "/exchange-rate", Public),
+        case(Method::GET, "/exchange-rate/:base", Public),
+        case(Method::GET, "/exchange-rate/convert", Public),
+        case(Method::POST, "/exchange-rate/convert/batch", Public).body(json!({ "items": [] })),
+        case(Method::POST, "/exchange-rate/cache/clear", Admin),
+
+        // Accounts
+        case(Method::PUT, "/accounts/reorder", User).body(json!([])),
+        case(Method::GET, "/accounts/summary", User),
+        case(Method::GET, "/accounts", User),
+        case(Method::POST, "/accounts", User).body(json!({ "name": "Broker" })),
+        case(Method::GET, "/accounts/:id", User),
+        case(Method::PUT, "/accounts/:id", User),
+        case(Method::DELETE, "/accounts/:id", User),
+        case(Method::POST, "/accounts/:id/archive", User),
+        case(Method::POST, "/accounts/:id/unarchive", User),
+        case(Method::GET, "/accounts/:id/summary", User),
+        case(Method::PUT, "/accounts/:id/target-allocation", User)
+            .body(json!({ "targets": [{ "key": "stock", "weight_percent": 100.0 }] })),
+        case(Method::DELETE, "/accounts/:id/target-allocation", User),
+
+        // Liabilities
+        case(Method::GET, "/liabilities", User),
+        case(Method::POST, "/liabilities", User).body(json!({ "name": "Mortgage", "currency": "THB", "principal": 1000.0 })),
+        case(Method::GET, "/liabilities/:id", User),
+        case(Method::PUT, "/liabilities/:id", User),
+        case(Method::DELETE, "/liabilities/:id", User),
+        case(Method::GET, "/liabilities/:id/transactions", User),
+        case(Method::POST, "/liabilities/:id/transactions", User).body(json!({ "kind": "repayment", "amount": 10.0 })),
+        case(Method::GET, "/orders", User),
+        case(Method::POST, "/orders", User).body(json!({ "symbol": "BTC", "asset_type": "crypto", "action": "buy", "quantity": 1.0, "price": 1.0 })),
+        case(Method::GET, "/orders/:id", User),
+        case(Method::PUT, "/orders/:id", User).body(json!({})),
+        case(Method::DELETE, "/orders/:id", User),
+        case(Method::POST, "/orders/:id/fill", User).body(json!({})),
+        case(Method::GET, "/dividends", User),
+        case(Method::GET, "/dividends/summary", User),
+        case(Method::GET, "/dividends/projection", User),
+        case(Method::GET, "/net-worth", User),
+
+        // Equity compensation
+        case(Method::GET, "/equity-grants", User),
+        case(Method::POST, "/equity-grants", User).body(json!({
+            "grant_type": "rsu",
+            "symbol": "AAPL",
+            "asset_type": "foreign_stock",
+            "grant_date": "2024-01-01",
+            "total_quantity": 100.0,
+        })),
+        case(Method::GET, "/equity-grants/:id", User),
+        case(Method::DELETE, "/equity-grants/:id", User),
+        case(Method::POST, "/equity-grants/:id/vest", User),
+
+        // Symbols
+        case(Method::GET, "/symbols/thai-stocks", Public),
+        case(Method::GET, "/symbols/tfex", Public),
+        case(Method::GET, "/symbols/crypto", Public),
+        case(Method::GET, "/symbols/foreign-stocks", Public),
+        case(Method::POST, "/symbols/seed", Admin),
+        case(Method::GET, "/symbols/delisted", Public),
+        case(Method::POST, "/symbols/:asset_type/:symbol/delist", Admin).body(json!({ "final_price": 1.0 })),
+        case(Method::DELETE, "/symbols/:asset_type/:symbol/delist", Admin),
+        case(Method::PUT, "/symbols/:asset_type/:symbol/refresh-interval", Admin).body(json!({ "seconds": 60 })),
+        case(Method::DELETE, "/symbols/:asset_type/:symbol/refresh-interval", Admin),
+
+        // Jobs
+        case(Method::GET, "/jobs", OpsRead),
+        case(Method::POST, "/jobs", Admin).body(json!({ "name": "Prices", "job_type": "price_update" })),
+        case(Method::GET, "/jobs/overview", OpsRead),
+        case(Method::POST, "/jobs/reload", Admin),
+        case(Method::GET, "/jobs/:id", OpsRead),
+        case(Method::PUT, "/jobs/:id", Admin),
+        case(Method::DELETE, "/jobs/:id", Admin),
+        case(Method::POST, "/jobs/:id/run", Admin),
+        case(Method::POST, "/jobs/:id/toggle", Admin),
+        case(Method::GET, "/jobs/:id/runs", OpsRead),
+        case(Method::GET, "/jobs/:id/runs/:run_id", OpsRead),
+        case(Method::POST, "/jobs/exports", User).body(json!({ "kind": "transactions_csv" })),
+        case(Method::GET, "/jobs/exports", User),
+        case(Method::GET, "/jobs/exports/:id", User),
+        case(Method::GET, "/exports/download/:token", Public),
+
+        // Admin
+        case(Method::GET, "/admin/users", Admin),
+        case(Method::POST, "/admin/users", Admin).body(json!({ "email": "made@example.com", "password": "secret123" })),
+        case(Method::GET, "/admin/users/:id", Admin),
+        case(Method::PATCH, "/admin/users/:id", Admin),
+        case(Method::DELETE, "/admin/users/:id", Admin),
+        case(Method::POST, "/admin/users/:id/reset-password", Admin).body(json!({ "new_password": "secret123" })),
+        case(Method::POST, "/admin/rate-limits/:api_name/override", Admin),
+        case(Method::DELETE, "/admin/rate-limits/:api_name/override", Admin),
+        case(Method::GET, "/admin/maintenance/orphans", Admin),
+        case(Method::POST, "/admin/maintenance/orphans/cleanup", Admin),
+        case(Method::GET, "/admin/invites", Admin),
+        case(Method::POST, "/admin/invites", Admin),
+        case(Method::DELETE, "/admin/invites/:id", Admin),
+        case(Method::GET, "/admin/diagnostics", Admin),
+        case(Method::GET, "/admin/plugins", Admin),
+
+        // Snapshots
+        case(Method::GET, "/snapshots", User),
+        case(Method::GET, "/events", User),
+        case(Method::GET, "/snapshots/export.csv", User),
+        case(Method::POST, "/snapshots/now", User),
+
+        // Rate limits
+        case(Method::GET, "/rate-limits", Public),
+
+        // API providers and logs
+        case(Method::GET, "/providers", OpsRead),
+        case(Method::POST, "/providers", Admin).body(json!({
+            "market_id": "crypto",
+            "provider_name": "Binance",
+            "provider_type": "binance",
+            "priority": 1,
+        })),
+        case(Method::PUT, "/providers/:id", Admin),
+        case(Method::DELETE, "/providers/:id", Admin),
+        case(Method::POST, "/providers/:id/test-connectivity", Admin),
+        case(Method::GET, "/providers/market/:market_id", OpsRead),
+        case(Method::PUT, "/providers/market/:market_id/reorder", Admin).body(json!({ "provider_ids": ["a"] })),
+        case(Method::GET, "/logs", OpsRead),
+        case(Method::GET, "/logs/stats", OpsRead),
+
+        // Seed data (the export includes users, so it is never opened up)
+        case(Method::GET, "/seed/export", Admin),
+        case(Method::POST, "/seed/upload", Admin),
+
+        // Alerts and notifications
+        case(Method::GET, "/alerts", User),
+        case(Method::POST, "/alerts", User).body(json!({
+            "name": "PTT above 40",
+            "alert_type": "price_above",
+            "symbol": "PTT",
+            "asset_type": "stock",
+            "threshold": 40.0,
+            "comparison": "above",
+            "channels": ["in_app"],
+        })),
+        case(Method::GET, "/alerts/history", User),
+        case(Method::POST, "/alerts/evaluate", Admin),
+        case(Method::GET, "/alerts/:id", User),
+        case(Method::PUT, "/alerts/:id", User),
+        case(Method::DELETE, "/alerts/:id", User),
+        case(Method::POST, "/alerts/:id/snooze", User),
+        case(Method::DELETE, "/alerts/:id/snooze", User),
+        case(Method::GET, "/notifications", User),
+        case(Method::POST, "/notifications/read-all", User),
+        case(Method::POST, "/notifications/test", User),
+        case(Method::GET, "/notifications/channels", User),
+        case(Method::PUT, "/notifications/channels/:channel", User).body(json!({ "target": "me@example.com" })),
+        case(Method::DELETE, "/notifications/channels/:channel", User),
+        case(Method::POST, "/notifications/channels/:channel/test", User),
+        case(Method::POST, "/notifications/:id/read", User),
+        case(Method::POST, "/push/subscribe", User).body(json!({ "endpoint": "https://push.example.com", "p256dh": "k", "auth": "a" })),
+        case(Method::GET, "/integrations/exchange-keys", User),
+        case(Method::POST, "/integrations/exchange-keys", User)
+            .body(json!({ "exchange": "binance", "api_key": "key", "api_secret": "secret" })),
+        case(Method::DELETE, "/integrations/exchange-keys/:id", User),
+        case(Method::GET, "/integrations/exchange-keys/:id/balances", User),
+        case(Method::POST, "/integrations/exchange-keys/:id/sync", User).body(json!({})),
+        case(Method::GET, "/integrations/exchange-keys/:id/staged", User),
+        case(Method::POST, "/integrations/exchange-keys/:id/staged/import", User).body(json!({ "ids": [] })),
+        case(Method::POST, "/integrations/exchange-keys/:id/staged/dismiss", User).body(json!({ "ids": [] })),
+    ]
+}
+
+fn test_config() -> Config {
+    // reqwest honours these, so clients with hardcoded provider URLs fail fast as well
+    std::env::set_var("HTTP_PROXY", CLOSED_PORT_URL);
+    std::env::set_var("HTTPS_PROXY", CLOSED_PORT_URL);
+
+    let mut config = Config::from_env();
+    config.pocketbase_url = CLOSED_PORT_URL.to_string();
+    config.coingecko_api_url = CLOSED_PORT_URL.to_string();
+    config.settrade_api_url = CLOSED_PORT_URL.to_string();
+    config.yahoo_finance_service_url = CLOSED_PORT_URL.to_string();
+    config.finnhub_api_url = CLOSED_PORT_URL.to_string();
+    config.goldtraders_url = CLOSED_PORT_URL.to_string();
+    config.goldapi_api_url = CLOSED_PORT_URL.to_string();
+    config.admin_email = None;
+    config.admin_password = None;
+    config.pb_admin_email = None;
+    config.pb_admin_password = None;
+    config.local_auth_enabled = Some(true);
+    config.require_email_verification = false;
+    config.registration_invite_only = false;
+    config.ops_read_access_for_users = false;
+    config.jwt_secret = "authz-matrix-test-secret".to_string();
+    config.export_dir = std::env::temp_dir().join("authz-matrix-exports").display().to_string();
+    config
+}
+
+async fn test_state(config: &Config) -> AppState {
+    let db = PocketBaseClient::new(config.clone());
+    let rate_limiter = RateLimiter::new(db.clone(), config.pocketbase_url.clone());
+    let price_service = PriceService::with_rate_limiter(config.clone(), rate_limiter.clone());
+    let exchange_rate_service = ExchangeRateService::new(config.clone());
+    let auth_service = AuthService::new(config.clone(), db.clone()).await;
+    let symbol_heat = SymbolHeat::new(config);
+    let job_scheduler = JobScheduler::new(config.clone(), db.clone(), price_service.clone(), symbol_heat.clone());
+    let symbols_service = SymbolsService::new(config.pocketbase_url.clone(), db.clone());
+    let notification_service = NotificationService::new(config.clone(), db.clone());
+    let snapshot_cache = SnapshotCache::new(config);
+    let alert_service = AlertService::new(config.clone(), db.clone(), notification_service.clone(), price_service.clone());
+    let price_refresher = PriceRefresher::new(config, price_service.clone(), symbol_heat.clone());
+    let order_watcher = OrderWatcher::new(db.clone(), notification_service.clone(), price_refresher.clone());
+    let export_service = ExportService::new(config);
+    let credential_store = CredentialStore::new(db.clone(), config);
+    let balance_sync = BalanceSyncService::new(credential_store.clone(), price_service.clone());
+    let exchange_sync = ExchangeSyncService::new(db.clone(), credential_store, price_service.clone());
+    let realtime = RealtimeBridge::new(config, db.clone(), price_service.clone());
+
+    AppState {
+        db,
+        price_service,
+        exchange_rate_service,
+        auth_service,
+        job_scheduler: Arc::new(job_scheduler),
+        symbols_service,
+        rate_limiter,
+        notification_service,
+        alert_service,
+        symbol_heat,
+        snapshot_cache,
+        price_refresher,
+        order_watcher: Arc::new(order_watcher),
+        export_service,
+        balance_sync: Arc::new(balance_sync),
+        exchange_sync: Arc::new(exchange_sync),
+        public_status: Arc::new(PublicStatusService::new(config)),
+        realtime: Arc::new(realtime),
+        client_modes: Arc::new(ClientModes::new(config)),
+        plugins: Arc::new(crate::plugins::Plugins::default()),
+        config: Arc::new(config.clone()),
+    }
+}
+
+async fn register(state: &AppState, email: &str, is_admin: bool) -> String {
+    state.auth_service
+        .register_local_user(email, TEST_PASSWORD, None, is_admin, false)
+        .await
+        .expect("register test user")
+        .id
+}
+
+/// Token issued now, so cases that bump the token version (logout-all, change-password)
+/// don't invalidate the ones after them
+async fn fresh_token(state: &AppState, user_id: &str) -> String {
+    let user = state.auth_service.get_user(user_id).await.expect("test user");
+    state.auth_service.create_jwt(&user).expect("create test token")
+}
+
+/// Status of one call, or None if the handler was still waiting when the timeout hit
+async fn call(app: &Router, case: &Case, token: Option<&str>, body_token: &str) -> Option<StatusCode> {
+    let mut request = Request::builder()
+        .method(case.method.clone())
+        .uri(case.uri())
+        .header("content-type", "application/json");
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    let body = case.body.to_string().replace(TOKEN_PLACEHOLDER, body_token);
+    let request = request.body(Body::from(body)).unwrap();
+
+    tokio::time::timeout(REQUEST_TIMEOUT, app.clone().call(request))
+        .await
+        .ok()
+        .map(|response| response.unwrap().status())
+}
+
+fn is_auth_error(status: StatusCode) -> bool {
+    status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN
+}
+
+/// `(METHOD, path)` pairs registered in `api_routes`, read from the source so a new route
+/// without a matrix entry fails the test
+fn registered_routes() -> HashSet<(String, String)> {
+    let source = include_str!("main.rs");
+    let start = source.find("fn api_routes").expect("api_routes in main.rs");
+    let mut routes = HashSet::new();
+    for line in source[start..].lines().map(str::trim).filter(|l| l.starts_with(".route(\"")) {
+        let path = line[8..].split('"').next().unwrap().to_string();
+        for method in ["get", "post", "put", "delete", "patch"] {
+            if line.contains(&format!("{}(", method)) {
+                routes.insert((method.to_uppercase(), path.clone()));
+            }
+        }
+    }
+    routes
+}
+
+#[test]
+fn matrix_covers_every_route() {
+    let listed: HashSet<(String, String)> = matrix().iter()
+        .map(|c| (c.method.to_string(), c.route.to_string()))
+        .collect();
+    let registered = registered_routes();
+
+    let mut missing: Vec<_> = registered.difference(&listed).collect();
+    missing.sort();
+    assert!(missing.is_empty(), "routes without an authorization matrix entry: {:?}", missing);
+
+    let mut stale: Vec<_> = listed.difference(&registered).collect();
+    stale.sort();
+    assert!(stale.is_empty(), "matrix entries for routes that no longer exist: {:?}", stale);
+}
+
+/// Call every case as anonymous, user and admin and describe each mismatch with its access level
+async fn check_matrix(config: &Config, cases: Vec<Case>) -> Vec<String> {
+    let state = test_state(config).await;
+    let user_id = register(&state, USER_EMAIL, false).await;
+    let admin_id = register(&state, ADMIN_EMAIL, true).await;
+    let app: Router = crate::versioning::versioned_api(crate::api_routes(config), config).with_state(state.clone());
+
+    let mut failures = Vec::new();
+    for case in cases {
+        let label = format!("{} {}", case.method, case.route);
+        let user_token = fresh_token(&state, &user_id).await;
+        let admin_token = fresh_token(&state, &admin_id).await;
+        let anonymous = call(&app, &case, None, &user_token).await;
+        let user = call(&app, &case, Some(&user_token), &user_token).await;
+        let admin = call(&app, &case, Some(&admin_token), &user_token).await;
+
+        // A timeout means the handler got past auth and is waiting on a backend
+        let allowed = |status: Option<StatusCode>| status.is_none_or(|s| !is_auth_error(s));
+        let access = match case.access {
+            Access::OpsRead if config.ops_read_access_for_users => Access::User,
+            Access::OpsRead => Access::Admin,
+            access => access,
+        };
+        let expectations = match access {
+            Access::Public => [
+                ("anonymous", allowed(anonymous)),
+                ("user", allowed(user)),
+                ("admin", allowed(admin)),
+            ],
+            Access::User => [
+                ("anonymous", anonymous == Some(StatusCode::UNAUTHORIZED)),
+                ("user", allowed(user)),
+                ("admin", allowed(admin)),
+            ],
+            Access::Admin | Access::OpsRead => [
+                ("anonymous", anonymous == Some(StatusCode::UNAUTHORIZED)),
+                ("user", user == Some(StatusCode::FORBIDDEN)),
+                ("admin", allowed(admin)),
+            ],
+        };
+        for (role, ok) in expectations {
+            if !ok {
+                failures.push(format!(
+                    "{} expected {:?} access, {} got {:?}/{:?}/{:?} (anonymous/user/admin)",
+                    label, access, role, anonymous, user, admin,
+                ));
+            }
+        }
+    }
+    failures
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn routes_enforce_expected_access() {
+    let failures = check_matrix(&test_config(), matrix()).await;
+    assert!(failures.is_empty(), "authorization matrix mismatches:\n{}", failures.join("\n"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ops_read_access_opens_reads_to_users_only() {
+    let mut config = test_config();
+    config.ops_read_access_for_users = true;
+    // Reads open up to users; the writes next to them must stay admin-only
+    let cases = matrix().into_iter()
+        .filter(|c| ["/jobs", "/providers", "/logs", "/seed"].iter().any(|prefix| c.route.starts_with(prefix)))
+        .collect();
+    let failures = check_matrix(&config, cases).await;
+    assert!(failures.is_empty(), "authorization matrix mismatches with OPS_READ_ACCESS_FOR_USERS=true:\n{}", failures.join("\n"));
+}
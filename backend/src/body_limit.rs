@@ -0,0 +1,121 @@
+use std::pin::Pin;
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::{DefaultBodyLimit, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use crate::config::Config;
+use crate::error::AppError;
+
+/// Body size limit for a group of routes
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimit {
+    pub group: &'static str,
+    pub max_bytes: usize,
+    /// What the client can do instead, included in the 413 message
+    pub hint: &'static str,
+}
+
+impl BodyLimit {
+    pub fn default_group(config: &Config) -> Self {
+        Self {
+            group: "default",
+            max_bytes: config.body_limit_default_bytes,
+            hint: "Use the bulk or import endpoints for large payloads.",
+        }
+    }
+
+    /// Bulk JSON endpoints (batch transactions, seed upload)
+    pub fn bulk(config: &Config) -> Self {
+        Self {
+            group: "bulk",
+            max_bytes: config.body_limit_bulk_bytes,
+            hint: "Split the batch into smaller requests, or send CSV files to /api/v1/import/crypto as text/csv.",
+        }
+    }
+
+    /// Streamed CSV imports
+    pub fn import(config: &Config) -> Self {
+        Self {
+            group: "import",
+            max_bytes: config.body_limit_import_bytes,
+            hint: "Split the export into several files (e.g. one per year) and import them one at a time.",
+        }
+    }
+
+    pub fn too_large(&self, received: Option<usize>) -> AppError {
+        let received = received
+            .map(|n| format!(" (received {})", format_bytes(n)))
+            .unwrap_or_default();
+        AppError::PayloadTooLarge(format!(
+            "Request body exceeds the {} limit of {}{}. {}",
+            self.group,
+            format_bytes(self.max_bytes),
+            received,
+            self.hint
+        ))
+    }
+}
+
+fn format_bytes(n: usize) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    if n as f64 >= MB {
+        format!("{:.1} MB", n as f64 / MB)
+    } else {
+        format!("{:.0} KB", (n as f64 / 1024.0).ceil())
+    }
+}
+
+/// Apply a body limit to every route currently in `router`
+pub fn limit_body<S>(router: Router<S>, limit: BodyLimit) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router
+        .layer(DefaultBodyLimit::max(limit.max_bytes))
+        .layer(middleware::from_fn_with_state(limit, enforce_body_limit))
+}
+
+/// Reject oversized uploads from Content-Length before reading them, and turn the
+/// extractors' plain-text 413 (chunked bodies) into the same helpful JSON error
+async fn enforce_body_limit(State(limit): State<BodyLimit>, request: Request, next: Next) -> Response {
+    let content_length = request.headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if let Some(len) = content_length.filter(|len| *len > limit.max_bytes) {
+        tracing::warn!("🚫 Rejected {} byte body on {} ({} limit)", len, request.uri().path(), limit.group);
+        return limit.too_large(Some(len)).into_response();
+    }
+
+    let response = next.run(request).await;
+    let is_json = response.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return limit.too_large(None).into_response();
+    }
+    response
+}
+
+/// Next data chunk of a streamed body, failing once more than `max_bytes` have been read
+pub async fn next_chunk(body: &mut Body, read: &mut usize, limit: &BodyLimit) -> Result<Option<Bytes>, AppError> {
+    loop {
+        let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut *body).poll_frame(cx)).await else {
+            return Ok(None);
+        };
+        let frame = frame.map_err(|e| AppError::BadRequest(format!("Failed to read request body: {}", e)))?;
+        // Skip trailers
+        if let Ok(data) = frame.into_data() {
+            *read += data.len();
+            if *read > limit.max_bytes {
+                return Err(limit.too_large(None));
+            }
+            return Ok(Some(data));
+        }
+    }
+}
@@ -1,4 +1,10 @@
 use std::env;
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::client_ip::{parse_trusted_proxies, Cidr};
+use crate::services::exchange_rate::{parse_fx_pairs, FxPair};
+use crate::services::http_client::parse_provider_timeouts;
+use crate::services::lot_engine::DustPolicy;
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -8,7 +14,33 @@ pub struct Config {
     pub coingecko_api_url: String,
     pub settrade_api_url: String,
     pub yahoo_finance_service_url: String,
-    pub price_cache_ttl_seconds: u64,
+    // Finnhub quotes for foreign stocks when Yahoo Finance fails (skipped without an API key)
+    pub finnhub_api_url: String,
+    pub finnhub_api_key: Option<String>,
+    // Thai gold bar prices are scraped from the Gold Traders Association site
+    pub goldtraders_url: String,
+    // GoldAPI.io spot prices back up the association quote (skipped without an API key)
+    pub goldapi_api_url: String,
+    pub goldapi_api_key: Option<String>,
+    // Max % change vs last cached price before a fetched price is quarantined (0 = disabled)
+    pub price_max_deviation_percent: f64,
+    // Consecutive fetches agreeing on a quarantined price before it is accepted as genuine (0 = never)
+    pub price_quarantine_auto_accept: u32,
+    // Holdings worth at least this much are valued via multi-provider consensus in snapshots (0 = disabled)
+    pub price_consensus_min_value: f64,
+    // Max % a provider quote may differ from the consensus median before a divergence warning
+    pub price_consensus_max_divergence_percent: f64,
+    // Flag stock positions larger than this % of the symbol's average daily volume (0 = disabled)
+    pub liquidity_warning_percent: f64,
+    // Background refresh intervals for warm / cold symbols (hot symbols refresh on every price_fetch run)
+    pub price_warm_refresh_seconds: u64,
+    pub price_cold_refresh_seconds: u64,
+    // Fresh prices are also written to asset_price_history, at most once per symbol per interval (0 = off)
+    pub price_history_interval_seconds: u64,
+    // Fail instead of serving static mock prices/FX rates when no provider answers
+    pub strict_price_data: bool,
+    // Stream held Binance/OKX/Bitkub prices over websockets instead of polling their REST tickers
+    pub crypto_price_streams: bool,
     // OAuth configuration
     pub oauth_enabled: bool,
     pub google_client_id: Option<String>,
@@ -35,6 +67,90 @@ pub struct Config {
     pub pb_admin_password: Option<String>,
     // CORS configuration
     pub cors_allowed_origins: Vec<String>,
+    // Date (YYYY-MM-DD) sent in the Sunset header on the unversioned /api paths
+    pub api_legacy_sunset: String,
+    // Max request body size per route group, in bytes
+    pub body_limit_default_bytes: usize,
+    pub body_limit_bulk_bytes: usize,
+    pub body_limit_import_bytes: usize,
+    // How long a user's snapshot series stays in memory before it is re-read from PocketBase
+    pub snapshot_cache_ttl_seconds: u64,
+    // How often cached users, transactions and accounts are re-checked against PocketBase (0 = never)
+    pub cache_revalidate_seconds: u64,
+    // Apply transaction, account and price changes pushed by PocketBase's realtime API as they happen
+    pub pocketbase_realtime: bool,
+    // How often the background refresher re-checks the prices of held symbols
+    pub price_refresh_interval_seconds: u64,
+    // Identical notifications to the same user within this window are dropped
+    pub notification_dedup_window_seconds: u64,
+    // Email notifications over SMTP; skipped unless host and sender are set.
+    // SMTP_SECURITY is "starttls" (default), "tls" (implicit, usually port 465) or "none"
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_security: String,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub smtp_from: Option<String>,
+    // Telegram bot that messages users at the chat ID they register (skipped without a token)
+    pub telegram_bot_token: Option<String>,
+    pub telegram_api_url: String,
+    pub line_notify_api_url: String,
+    // Attempts per email/Telegram/LINE delivery, with exponential backoff between them
+    pub notification_retry_attempts: u32,
+    // When true the orphan_cleanup job only reports orphaned records instead of deleting them
+    pub orphan_cleanup_dry_run: bool,
+    // Scheduled jobs allowed to run at the same time; a job never overlaps its own previous run
+    pub job_max_concurrency: usize,
+    // Symbols priced and saved at the same time by price_fetch; each call still passes the rate limiter
+    pub price_fetch_concurrency: usize,
+    // Users whose daily snapshot is computed and written at the same time
+    pub snapshot_concurrency: usize,
+    // Names of compiled-in plugins to leave unloaded
+    pub disabled_plugins: Vec<String>,
+    // Local registrations stay inactive until the emailed verification link is confirmed
+    pub require_email_verification: bool,
+    // Only admin-generated invite codes can create new accounts (local or OAuth)
+    pub registration_invite_only: bool,
+    // Anyone can open a read-only session on GUEST_USER_EMAIL's (demo) data via /auth/guest
+    pub guest_login_enabled: bool,
+    pub guest_user_email: Option<String>,
+    pub guest_session_hours: u64,
+    // Let non-admin users view jobs, API providers and API logs (changes stay admin-only)
+    pub ops_read_access_for_users: bool,
+    // Reverse proxies whose X-Forwarded-For header is believed when resolving client IPs
+    pub trusted_proxies: Vec<Cidr>,
+    // Header a trusted proxy sets to the client's ISO country (e.g. CF-IPCountry), for login alerts
+    pub client_country_header: Option<String>,
+    // Per-asset-type quantities below which a sold-down position is closed (remaining cost realized)
+    pub dust_policy: DustPolicy,
+    // Where finished export files are written, and how long their download links work
+    pub export_dir: String,
+    pub export_link_ttl_hours: u64,
+    // Optional S3-compatible object storage (AWS S3, MinIO); used when endpoint, bucket and keys are set
+    pub s3_endpoint: Option<String>,
+    pub s3_region: String,
+    pub s3_bucket: Option<String>,
+    pub s3_access_key_id: Option<String>,
+    pub s3_secret_access_key: Option<String>,
+    pub s3_path_style: bool,
+    // 32-byte key (64 hex chars) sealing users' exchange API keys; they can't be added without it
+    pub credentials_encryption_key: Option<String>,
+    // Requests per minute per client IP to the unauthenticated /status/public endpoint
+    pub public_status_rate_limit: u32,
+    // Shared outbound HTTP clients: connection pool, timeouts, HTTP/2 keepalive (0 = off)
+    pub http_pool_max_idle_per_host: usize,
+    pub http_pool_idle_timeout_seconds: u64,
+    pub http_connect_timeout_seconds: u64,
+    pub http_timeout_seconds: u64,
+    pub http2_keepalive_seconds: u64,
+    // Per-provider request timeouts in seconds, keyed by rate limiter bucket (e.g. yahoo_finance, binance)
+    pub provider_timeouts: HashMap<String, u64>,
+    // Proxy for calls to providers and notification services (not PocketBase), e.g. http://proxy:3128
+    pub outbound_proxy: Option<String>,
+    // Currency pairs live rates may be requested for (either direction, * matches any); empty allows all
+    pub fx_allowed_pairs: Vec<FxPair>,
+    // Currencies listed by GET /exchange-rate/:base
+    pub fx_currencies: Vec<String>,
 }
 
 impl Config {
@@ -59,10 +175,54 @@ impl Config {
                 .unwrap_or_else(|_| "https://open-api.settrade.com/api".to_string()),
             yahoo_finance_service_url: env::var("YAHOO_FINANCE_SERVICE_URL")
                 .unwrap_or_else(|_| "http://yahoo-finance:8000".to_string()),
-            price_cache_ttl_seconds: env::var("PRICE_CACHE_TTL")
-                .unwrap_or_else(|_| "60".to_string())
+            finnhub_api_url: env::var("FINNHUB_API_URL")
+                .unwrap_or_else(|_| "https://finnhub.io/api/v1".to_string()),
+            finnhub_api_key: env::var("FINNHUB_API_KEY").ok().filter(|s| !s.trim().is_empty()),
+            goldtraders_url: env::var("GOLDTRADERS_URL")
+                .unwrap_or_else(|_| "https://www.goldtraders.or.th".to_string()),
+            goldapi_api_url: env::var("GOLDAPI_API_URL")
+                .unwrap_or_else(|_| "https://www.goldapi.io/api".to_string()),
+            goldapi_api_key: env::var("GOLDAPI_API_KEY").ok().filter(|s| !s.trim().is_empty()),
+            price_max_deviation_percent: env::var("PRICE_MAX_DEVIATION_PERCENT")
+                .unwrap_or_else(|_| "50".to_string())
+                .parse()
+                .expect("PRICE_MAX_DEVIATION_PERCENT must be a number"),
+            price_quarantine_auto_accept: env::var("PRICE_QUARANTINE_AUTO_ACCEPT")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .expect("PRICE_QUARANTINE_AUTO_ACCEPT must be a number"),
+            price_consensus_min_value: env::var("PRICE_CONSENSUS_MIN_VALUE")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .expect("PRICE_CONSENSUS_MIN_VALUE must be a number"),
+            liquidity_warning_percent: env::var("LIQUIDITY_WARNING_PERCENT")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .expect("LIQUIDITY_WARNING_PERCENT must be a number"),
+            price_consensus_max_divergence_percent: env::var("PRICE_CONSENSUS_MAX_DIVERGENCE_PERCENT")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .expect("PRICE_CONSENSUS_MAX_DIVERGENCE_PERCENT must be a number"),
+            price_warm_refresh_seconds: env::var("PRICE_WARM_REFRESH_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .expect("PRICE_WARM_REFRESH_SECONDS must be a number"),
+            price_cold_refresh_seconds: env::var("PRICE_COLD_REFRESH_SECONDS")
+                .unwrap_or_else(|_| "86400".to_string())
                 .parse()
-                .expect("PRICE_CACHE_TTL must be a number"),
+                .expect("PRICE_COLD_REFRESH_SECONDS must be a number"),
+            strict_price_data: env::var("STRICT_PRICE_DATA")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            crypto_price_streams: env::var("CRYPTO_PRICE_STREAMS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            price_history_interval_seconds: env::var("PRICE_HISTORY_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .expect("PRICE_HISTORY_INTERVAL_SECONDS must be a number"),
             // OAuth configuration
             oauth_enabled: env::var("OAUTH_ENABLED")
                 .unwrap_or_else(|_| "true".to_string())
@@ -105,9 +265,164 @@ impl Config {
                 .split(',')
                 .map(|s| s.trim().to_string())
                 .collect(),
+            api_legacy_sunset: env::var("API_LEGACY_SUNSET")
+                .unwrap_or_else(|_| "2027-06-30".to_string()),
+            body_limit_default_bytes: env::var("BODY_LIMIT_DEFAULT_BYTES")
+                .unwrap_or_else(|_| "2097152".to_string())
+                .parse()
+                .expect("BODY_LIMIT_DEFAULT_BYTES must be a number"),
+            body_limit_bulk_bytes: env::var("BODY_LIMIT_BULK_BYTES")
+                .unwrap_or_else(|_| "10485760".to_string())
+                .parse()
+                .expect("BODY_LIMIT_BULK_BYTES must be a number"),
+            body_limit_import_bytes: env::var("BODY_LIMIT_IMPORT_BYTES")
+                .unwrap_or_else(|_| "52428800".to_string())
+                .parse()
+                .expect("BODY_LIMIT_IMPORT_BYTES must be a number"),
+            snapshot_cache_ttl_seconds: env::var("SNAPSHOT_CACHE_TTL_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .expect("SNAPSHOT_CACHE_TTL_SECONDS must be a number"),
+            cache_revalidate_seconds: env::var("CACHE_REVALIDATE_SECONDS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("CACHE_REVALIDATE_SECONDS must be a number"),
+            pocketbase_realtime: env::var("POCKETBASE_REALTIME")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            price_refresh_interval_seconds: env::var("PRICE_REFRESH_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("PRICE_REFRESH_INTERVAL_SECONDS must be a number"),
+            notification_dedup_window_seconds: env::var("NOTIFICATION_DEDUP_WINDOW_SECONDS")
+                .unwrap_or_else(|_| "900".to_string())
+                .parse()
+                .expect("NOTIFICATION_DEDUP_WINDOW_SECONDS must be a number"),
+            smtp_host: env::var("SMTP_HOST").ok().filter(|v| !v.trim().is_empty()),
+            smtp_port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse()
+                .expect("SMTP_PORT must be a number"),
+            smtp_security: env::var("SMTP_SECURITY").unwrap_or_else(|_| "starttls".to_string()).to_lowercase(),
+            smtp_username: env::var("SMTP_USERNAME").ok().filter(|v| !v.is_empty()),
+            smtp_password: env::var("SMTP_PASSWORD").ok().filter(|v| !v.is_empty()),
+            smtp_from: env::var("SMTP_FROM").ok().filter(|v| !v.trim().is_empty()),
+            telegram_bot_token: env::var("TELEGRAM_BOT_TOKEN").ok().filter(|v| !v.trim().is_empty()),
+            telegram_api_url: env::var("TELEGRAM_API_URL")
+                .unwrap_or_else(|_| "https://api.telegram.org".to_string()),
+            line_notify_api_url: env::var("LINE_NOTIFY_API_URL")
+                .unwrap_or_else(|_| "https://notify-api.line.me/api/notify".to_string()),
+            notification_retry_attempts: env::var("NOTIFICATION_RETRY_ATTEMPTS")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .expect("NOTIFICATION_RETRY_ATTEMPTS must be a number"),
+            orphan_cleanup_dry_run: env::var("ORPHAN_CLEANUP_DRY_RUN")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            job_max_concurrency: env::var("JOB_MAX_CONCURRENCY")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .expect("JOB_MAX_CONCURRENCY must be a number"),
+            price_fetch_concurrency: env::var("PRICE_FETCH_CONCURRENCY")
+                .unwrap_or_else(|_| "8".to_string())
+                .parse()
+                .expect("PRICE_FETCH_CONCURRENCY must be a number"),
+            snapshot_concurrency: env::var("SNAPSHOT_CONCURRENCY")
+                .unwrap_or_else(|_| "4".to_string())
+                .parse()
+                .expect("SNAPSHOT_CONCURRENCY must be a number"),
+            disabled_plugins: env::var("DISABLED_PLUGINS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            require_email_verification: env::var("REQUIRE_EMAIL_VERIFICATION")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            registration_invite_only: env::var("REGISTRATION_INVITE_ONLY")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            guest_login_enabled: env::var("GUEST_LOGIN_ENABLED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            guest_user_email: env::var("GUEST_USER_EMAIL").ok().filter(|s| !s.trim().is_empty()),
+            guest_session_hours: env::var("GUEST_SESSION_HOURS")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .unwrap_or(2),
+            ops_read_access_for_users: env::var("OPS_READ_ACCESS_FOR_USERS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            trusted_proxies: parse_trusted_proxies(&env::var("TRUSTED_PROXIES").unwrap_or_default()),
+            client_country_header: env::var("CLIENT_COUNTRY_HEADER").ok()
+                .map(|h| h.trim().to_lowercase())
+                .filter(|h| !h.is_empty()),
+            dust_policy: DustPolicy::parse(
+                &env::var("DUST_THRESHOLDS").unwrap_or_else(|_| "crypto=0.000001".to_string())
+            ),
+            export_dir: env::var("EXPORT_DIR").unwrap_or_else(|_| "./data/exports".to_string()),
+            export_link_ttl_hours: env::var("EXPORT_LINK_TTL_HOURS")
+                .unwrap_or_else(|_| "24".to_string())
+                .parse()
+                .expect("EXPORT_LINK_TTL_HOURS must be a number"),
+            s3_endpoint: env::var("S3_ENDPOINT").ok().filter(|v| !v.is_empty()),
+            s3_region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            s3_bucket: env::var("S3_BUCKET").ok().filter(|v| !v.is_empty()),
+            s3_access_key_id: env::var("S3_ACCESS_KEY_ID").ok().filter(|v| !v.is_empty()),
+            s3_secret_access_key: env::var("S3_SECRET_ACCESS_KEY").ok().filter(|v| !v.is_empty()),
+            s3_path_style: env::var("S3_PATH_STYLE")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            credentials_encryption_key: env::var("CREDENTIALS_ENCRYPTION_KEY").ok().filter(|v| !v.is_empty()),
+            public_status_rate_limit: env::var("PUBLIC_STATUS_RATE_LIMIT")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .expect("PUBLIC_STATUS_RATE_LIMIT must be a number"),
+            http_pool_max_idle_per_host: env::var("HTTP_POOL_MAX_IDLE_PER_HOST")
+                .unwrap_or_else(|_| "16".to_string())
+                .parse()
+                .expect("HTTP_POOL_MAX_IDLE_PER_HOST must be a number"),
+            http_pool_idle_timeout_seconds: env::var("HTTP_POOL_IDLE_TIMEOUT_SECONDS")
+                .unwrap_or_else(|_| "90".to_string())
+                .parse()
+                .expect("HTTP_POOL_IDLE_TIMEOUT_SECONDS must be a number"),
+            http_connect_timeout_seconds: env::var("HTTP_CONNECT_TIMEOUT_SECONDS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .expect("HTTP_CONNECT_TIMEOUT_SECONDS must be a number"),
+            http_timeout_seconds: env::var("HTTP_TIMEOUT_SECONDS")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse()
+                .expect("HTTP_TIMEOUT_SECONDS must be a number"),
+            http2_keepalive_seconds: env::var("HTTP2_KEEPALIVE_SECONDS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .expect("HTTP2_KEEPALIVE_SECONDS must be a number"),
+            provider_timeouts: parse_provider_timeouts(&env::var("PROVIDER_TIMEOUTS").unwrap_or_default()),
+            outbound_proxy: env::var("OUTBOUND_PROXY").ok().filter(|v| !v.is_empty()),
+            fx_allowed_pairs: parse_fx_pairs(&env::var("FX_ALLOWED_PAIRS").unwrap_or_default()),
+            fx_currencies: env::var("FX_CURRENCIES")
+                .unwrap_or_else(|_| "USD,THB,BTC,EUR,GBP,JPY,XAU,USDT".to_string())
+                .split(',')
+                .map(|s| s.trim().to_uppercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
         }
     }
 
+    /// Request timeout for one provider's rate limiter bucket
+    pub fn provider_timeout(&self, api: &str) -> Duration {
+        Duration::from_secs(self.provider_timeouts.get(api).copied().unwrap_or(self.http_timeout_seconds))
+    }
+
     pub fn server_addr(&self) -> String {
         format!("{}:{}", self.server_host, self.server_port)
     }
@@ -1,6 +1,7 @@
 use axum::{http::StatusCode, response::IntoResponse, Json};
 use serde_json::json;
 use thiserror::Error;
+use crate::services::rate_limiter::RateLimitInfo;
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -34,11 +35,20 @@ pub enum AppError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Unprocessable entity: {0}")]
+    Unprocessable(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 
     #[error("External service error: {0}")]
     External(String),
+
+    #[error("{} rate limit exceeded. Please wait {} seconds.", .0.provider, .0.retry_after_seconds)]
+    RateLimited(RateLimitInfo),
 }
 
 impl IntoResponse for AppError {
@@ -54,16 +64,28 @@ impl IntoResponse for AppError {
             AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
             AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg.clone()),
             AppError::Conflict(msg) => (StatusCode::CONFLICT, msg.clone()),
+            AppError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg.clone()),
+            AppError::Unprocessable(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg.clone()),
             AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
             AppError::External(msg) => (StatusCode::BAD_GATEWAY, msg.clone()),
+            AppError::RateLimited(_) => (StatusCode::TOO_MANY_REQUESTS, self.to_string()),
         };
 
-        let body = Json(json!({
+        // Errors often quote the URL a request failed on; keep internal hosts and secrets out
+        let message = crate::services::diagnostics::sanitize(&message).into_owned();
+        let body = json!({
             "error": message,
             "status": status.as_u16()
-        }));
+        });
+
+        // Tell clients which provider limit was hit and when it resets
+        if let AppError::RateLimited(info) = &self {
+            let mut body = body;
+            body["rate_limit"] = json!(info);
+            return (status, info.headers(), Json(body)).into_response();
+        }
 
-        (status, body).into_response()
+        (status, Json(body)).into_response()
     }
 }
 
@@ -0,0 +1,48 @@
+use axum::{
+    async_trait,
+    extract::{rejection::{PathRejection, QueryRejection}, FromRequestParts},
+    http::request::Parts,
+};
+use serde::de::DeserializeOwned;
+use crate::error::AppError;
+
+/// `axum::extract::Query` that rejects malformed or invalid values (e.g. an unknown
+/// asset type) with a 422 JSON error listing what is allowed, instead of a plain-text 400
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        axum::extract::Query::<T>::from_request_parts(parts, state)
+            .await
+            .map(|axum::extract::Query(value)| Query(value))
+            .map_err(|rejection: QueryRejection| AppError::Unprocessable(rejection.body_text()))
+    }
+}
+
+/// `axum::extract::Path` with the same 422 rejection as [`Query`]
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Path(value)),
+            // A missing match is a routing bug, not bad client input
+            Err(rejection @ PathRejection::MissingPathParams(_)) => Err(AppError::InternalError(rejection.body_text())),
+            Err(rejection) => Err(AppError::Unprocessable(rejection.body_text())),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Read-only enforcement for guest (demo) sessions.
+//!
+//! Guest tokens carry `read_only` in their claims. Handlers authenticate them like any other
+//! token, so the guard sits in front of every API route and turns away whatever could change
+//! data before it reaches a handler.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, Method},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use axum_extra::extract::cookie::CookieJar;
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::Claims;
+
+/// Writes a guest may still make: dropping their own session cookie
+const GUEST_WRITE_ALLOWLIST: [&str; 1] = ["/auth/logout"];
+
+#[derive(Clone)]
+struct GuestGuard {
+    jwt_secret: String,
+}
+
+/// Reject non-read requests made with a guest token on every route currently in `router`
+pub fn read_only<S>(router: Router<S>, config: &Config) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let guard = GuestGuard { jwt_secret: config.jwt_secret.clone() };
+    router.layer(middleware::from_fn_with_state(guard, enforce_read_only))
+}
+
+async fn enforce_read_only(State(guard): State<GuestGuard>, request: Request, next: Next) -> Response {
+    let method = request.method();
+    if method == Method::GET || method == Method::HEAD || method == Method::OPTIONS
+        || GUEST_WRITE_ALLOWLIST.iter().any(|path| request.uri().path().ends_with(path))
+    {
+        return next.run(request).await;
+    }
+
+    let bearer = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let token = bearer.or_else(|| {
+        CookieJar::from_headers(request.headers()).get(crate::handlers::auth::AUTH_COOKIE_NAME).map(|c| c.value().to_string())
+    });
+    let is_guest = token.is_some_and(|token| {
+        decode::<Claims>(&token, &DecodingKey::from_secret(guard.jwt_secret.as_bytes()), &Validation::default())
+            .is_ok_and(|data| data.claims.read_only)
+    });
+    if is_guest {
+        tracing::debug!("👀 Blocked guest {} {}", method, request.uri().path());
+        return AppError::Forbidden("Guest sessions are read-only".to_string()).into_response();
+    }
+    next.run(request).await
+}
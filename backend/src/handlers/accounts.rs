@@ -1,10 +1,19 @@
 use axum::{
-    extract::{Path, State},
+    extract::State,
     http::HeaderMap,
     Json,
 };
+use std::collections::BTreeMap;
+use chrono::NaiveDate;
+use serde::Serialize;
 use crate::error::AppError;
-use crate::models::{Account, CreateAccountRequest, UpdateAccountRequest};
+use crate::extract::{Path, Query};
+use crate::handlers::portfolio::{get_portfolio, parse_benchmark, PortfolioQuery};
+use crate::models::{
+    Account, AccountRank, AccountsSummaryQuery, AllocationDimension, AssetType, CreateAccountRequest, ListAccountsQuery,
+    ReorderAccountsRequest, ReorderAccountsResponse, TargetAllocation, TradeAction, UpdateAccountRequest,
+};
+use crate::services::exchange_rate::{ConversionTrail, ExchangeRate};
 use crate::AppState;
 
 /// Extract user_id from Authorization header JWT
@@ -22,21 +31,72 @@ fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppE
     Ok(claims.sub)
 }
 
-/// List all accounts for the logged-in user
+/// Validate APR and maturity date for savings / fixed deposit accounts
+fn validate_interest_settings(apr_percent: Option<f64>, maturity_date: Option<&str>) -> Result<(), AppError> {
+    if let Some(apr) = apr_percent {
+        if !(0.0..=100.0).contains(&apr) {
+            return Err(AppError::BadRequest("apr_percent must be between 0 and 100".to_string()));
+        }
+    }
+    if let Some(date) = maturity_date {
+        if chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            return Err(AppError::BadRequest("maturity_date must be YYYY-MM-DD".to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Uppercase a currency code and check it looks like one (e.g. THB, USD, USDT)
+fn normalize_currency_code(code: &str) -> Result<String, AppError> {
+    let code = code.trim().to_uppercase();
+    if !(3..=5).contains(&code.len()) || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AppError::BadRequest(format!("'{}' is not a valid currency code", code)));
+    }
+    Ok(code)
+}
+
+/// Canonical "asset_type:SYMBOL" form of a benchmark; "" stays "" so updates can clear it
+fn normalize_benchmark(spec: &str) -> Result<String, AppError> {
+    if spec.trim().is_empty() {
+        return Ok(String::new());
+    }
+    let (symbol, asset_type) = parse_benchmark(spec)?;
+    if asset_type == AssetType::Cash {
+        return Err(AppError::BadRequest("Cash cannot be used as a benchmark".to_string()));
+    }
+    Ok(format!("{}:{}", asset_type, symbol))
+}
+
+/// Reject writes into an archived account. Unknown IDs are left to the caller's own checks.
+pub(crate) async fn ensure_account_open(state: &AppState, account_id: Option<&str>) -> Result<(), AppError> {
+    let Some(account_id) = account_id.filter(|id| !id.is_empty()) else {
+        return Ok(());
+    };
+    match state.db.get_account(account_id).await {
+        Ok(account) if account.archived => Err(AppError::Conflict(format!(
+            "Account '{}' is archived; reopen it before adding transactions",
+            account.name
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// List accounts for the logged-in user (archived ones only with ?include_archived=true)
 pub async fn list_accounts(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(query): Query<ListAccountsQuery>,
 ) -> Result<Json<Vec<Account>>, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
     let accounts = state.db.list_accounts(&user_id).await?;
-    Ok(Json(accounts))
+    Ok(Json(accounts.into_iter().filter(|a| query.include_archived || !a.archived).collect()))
 }
 
 /// Create a new account for the logged-in user
 pub async fn create_account(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(req): Json<CreateAccountRequest>,
+    Json(mut req): Json<CreateAccountRequest>,
 ) -> Result<Json<Account>, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
     
@@ -52,6 +112,12 @@ pub async fn create_account(
         }
     }
 
+    validate_interest_settings(req.apr_percent, req.maturity_date.as_deref())?;
+    req.base_currency = normalize_currency_code(&req.base_currency)?;
+    if let Some(spec) = req.benchmark.as_deref() {
+        req.benchmark = Some(normalize_benchmark(spec)?).filter(|b| !b.is_empty());
+    }
+
     let account = state.db.create_account(req, &user_id).await?;
     Ok(Json(account))
 }
@@ -78,7 +144,7 @@ pub async fn update_account(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<String>,
-    Json(req): Json<UpdateAccountRequest>,
+    Json(mut req): Json<UpdateAccountRequest>,
 ) -> Result<Json<Account>, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
     
@@ -102,6 +168,19 @@ pub async fn update_account(
         }
     }
 
+    validate_interest_settings(req.apr_percent, req.maturity_date.as_deref())?;
+    if let Some(code) = req.base_currency.as_deref() {
+        req.base_currency = Some(normalize_currency_code(code)?);
+    }
+    if let Some(spec) = req.benchmark.as_deref() {
+        req.benchmark = Some(normalize_benchmark(spec)?);
+    }
+
+    // Interest owed under the old rate is credited before the change restarts accrual
+    if req.changes_accrual(&existing) {
+        state.job_scheduler.settle_account_interest(&id).await?;
+    }
+
     let account = state.db.update_account(&id, req).await?;
     Ok(Json(account))
 }
@@ -127,36 +206,424 @@ pub async fn delete_account(
     })))
 }
 
-/// Reorder accounts
+/// Reorder accounts, either from a full list of ids or by moving one account to an index.
+/// All ranks are written or none are; the response is the resulting order.
 pub async fn reorder_accounts(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(ids): Json<Vec<String>>,
-) -> Result<Json<serde_json::Value>, AppError> {
+    Json(req): Json<ReorderAccountsRequest>,
+) -> Result<Json<ReorderAccountsResponse>, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
     
-    // Get all user accounts to verify ownership efficiently
-    let user_accounts = state.db.list_accounts(&user_id).await?;
-    let owned_ids: std::collections::HashSet<String> = user_accounts.into_iter().map(|a| a.id).collect();
-    
-    // Update ranks
-    for (index, id) in ids.iter().enumerate() {
-        if owned_ids.contains(id) {
-            let req = UpdateAccountRequest {
-                name: None,
-                description: None,
-                color: None,
-                target_value: None,
-                target_currency: None,
-                rank: Some(index as i32),
-            };
-            // Ignore errors for individual updates to avoid breaking the whole batch
-            // In a real app, might want better error handling
-            let _ = state.db.update_account(id, req).await;
+    // Current order, already sorted by rank
+    let accounts = state.db.list_accounts(&user_id).await?;
+    let current: Vec<&str> = accounts.iter().map(|a| a.id.as_str()).collect();
+    let is_owned = |id: &str| current.contains(&id);
+
+    let order: Vec<&str> = match &req {
+        ReorderAccountsRequest::Order(ids) => {
+            let mut seen = std::collections::HashSet::new();
+            for id in ids {
+                if !is_owned(id) {
+                    return Err(AppError::NotFound(format!("Account {} not found", id)));
+                }
+                if !seen.insert(id.as_str()) {
+                    return Err(AppError::BadRequest(format!("Account {} listed more than once", id)));
+                }
+            }
+            ids.iter().map(String::as_str)
+                .chain(current.iter().copied().filter(|id| !seen.contains(id)))
+                .collect()
+        }
+        ReorderAccountsRequest::Move { account_id, index } => {
+            if !is_owned(account_id) {
+                return Err(AppError::NotFound(format!("Account {} not found", account_id)));
+            }
+            let mut order: Vec<&str> = current.iter().copied().filter(|id| *id != account_id).collect();
+            order.insert((*index).min(order.len()), account_id);
+            order
         }
+    };
+
+    // Only write accounts whose rank actually changes
+    let changes: Vec<(String, i32)> = order.iter().enumerate()
+        .filter(|(index, id)| accounts.iter().any(|a| a.id == **id && a.rank != *index as i32))
+        .map(|(index, id)| (id.to_string(), index as i32))
+        .collect();
+    if !changes.is_empty() {
+        state.db.set_account_ranks(&changes).await?;
     }
-    
-    Ok(Json(serde_json::json!({
-        "message": "Accounts reordered successfully"
-    })))
+
+    let accounts = order.iter().enumerate()
+        .filter_map(|(index, id)| accounts.iter().find(|a| a.id == *id).map(|a| AccountRank {
+            id: a.id.clone(),
+            name: a.name.clone(),
+            rank: index as i32,
+        }))
+        .collect();
+    Ok(Json(ReorderAccountsResponse {
+        message: "Accounts reordered successfully".to_string(),
+        accounts,
+    }))
+}
+
+async fn set_archived(state: &AppState, headers: &HeaderMap, id: &str, archived: bool) -> Result<Account, AppError> {
+    let user_id = extract_user_id(state, headers)?;
+    let existing = state.db.get_account(id).await?;
+    if existing.user_id != user_id {
+        return Err(AppError::NotFound(format!("Account {} not found", id)));
+    }
+    if existing.archived == archived {
+        return Ok(existing);
+    }
+    state.db.set_account_archived(id, archived).await
+}
+
+/// POST /api/accounts/:id/archive - Close an account without deleting its history
+pub async fn archive_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Account>, AppError> {
+    Ok(Json(set_archived(&state, &headers, &id, true).await?))
+}
+
+/// POST /api/accounts/:id/unarchive - Reopen an archived account
+pub async fn unarchive_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Account>, AppError> {
+    Ok(Json(set_archived(&state, &headers, &id, false).await?))
+}
+
+/// Check a target allocation model and normalize its keys to the allocation endpoint's
+fn normalize_target_allocation(mut model: TargetAllocation) -> Result<TargetAllocation, AppError> {
+    if model.targets.is_empty() {
+        return Err(AppError::BadRequest("At least one target is required".to_string()));
+    }
+    if !(0.0..=100.0).contains(&model.band_percent) {
+        return Err(AppError::BadRequest("band_percent must be between 0 and 100".to_string()));
+    }
+    let mut total_weight = 0.0;
+    for i in 0..model.targets.len() {
+        let target = &mut model.targets[i];
+        let key = target.key.trim();
+        target.key = match model.dimension {
+            AllocationDimension::AssetType => key.parse::<AssetType>().map_err(AppError::BadRequest)?.to_string(),
+            AllocationDimension::Currency => normalize_currency_code(key)?,
+            AllocationDimension::Market | AllocationDimension::Sector => key.to_lowercase(),
+        };
+        if target.key.is_empty() {
+            return Err(AppError::BadRequest("Target keys cannot be empty".to_string()));
+        }
+        if !(0.0..=100.0).contains(&target.weight_percent) {
+            return Err(AppError::BadRequest(format!("Target weight for {} must be between 0 and 100", target.key)));
+        }
+        total_weight += target.weight_percent;
+        let key = model.targets[i].key.clone();
+        if model.targets[..i].iter().any(|t| t.key.eq_ignore_ascii_case(&key)) {
+            return Err(AppError::BadRequest(format!("Duplicate target for {}", key)));
+        }
+    }
+    if (total_weight - 100.0).abs() > 0.01 {
+        return Err(AppError::BadRequest(format!("Target weights add up to {:.2}%, not 100%", total_weight)));
+    }
+    Ok(model)
+}
+
+/// PUT /api/accounts/:id/target-allocation - Set the mix the account is rebalanced toward
+pub async fn set_target_allocation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<TargetAllocation>,
+) -> Result<Json<Account>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let existing = state.db.get_account(&id).await?;
+    if existing.user_id != user_id {
+        return Err(AppError::NotFound(format!("Account {} not found", id)));
+    }
+    let model = normalize_target_allocation(req)?;
+    Ok(Json(state.db.set_account_target_allocation(&id, Some(model)).await?))
+}
+
+/// DELETE /api/accounts/:id/target-allocation - Remove the account's target allocation
+pub async fn clear_target_allocation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Account>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let existing = state.db.get_account(&id).await?;
+    if existing.user_id != user_id {
+        return Err(AppError::NotFound(format!("Account {} not found", id)));
+    }
+    Ok(Json(state.db.set_account_target_allocation(&id, None).await?))
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountSummary {
+    /// None for transactions not booked to any account
+    pub account_id: Option<String>,
+    pub name: String,
+    pub base_currency: String,
+    pub total_invested: f64,
+    pub total_current_value: f64,
+    pub total_unrealized_pnl: f64,
+    pub total_unrealized_pnl_percent: f64,
+    pub assets_count: usize,
+    /// Only for accounts with a benchmark whose price history could be loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub benchmark: Option<BenchmarkComparison>,
+}
+
+/// Account return against its benchmark, both measured on the account's own cash flows
+#[derive(Debug, Serialize)]
+pub struct BenchmarkComparison {
+    pub benchmark: String,
+    /// (holdings value + money taken out - money put in) / money put in, excluding cash
+    pub account_return_percent: f64,
+    /// Same formula had every buy and sell gone into the benchmark on the same day
+    pub benchmark_return_percent: f64,
+    /// Account minus benchmark, in percentage points
+    pub tracking_difference_percent: f64,
+    /// First trade of the account (YYYY-MM-DD)
+    pub since: String,
+    /// True when trades predate the available history and were priced at its first close
+    pub estimated: bool,
+}
+
+/// Longest benchmark history requested, in days
+const MAX_BENCHMARK_DAYS: i64 = 3650;
+
+#[derive(Debug, Serialize)]
+pub struct AccountSummaryResponse {
+    #[serde(flatten)]
+    pub summary: AccountSummary,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConvertedAccountSummary {
+    #[serde(flatten)]
+    pub summary: AccountSummary,
+    /// Figures below are in the combined base currency
+    pub invested_in_base: f64,
+    pub value_in_base: f64,
+    pub unrealized_pnl_in_base: f64,
+    pub weight_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountsSummaryResponse {
+    pub base_currency: String,
+    pub accounts: Vec<ConvertedAccountSummary>,
+    pub total_invested: f64,
+    pub total_current_value: f64,
+    pub total_unrealized_pnl: f64,
+    pub total_unrealized_pnl_percent: f64,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// Holdings of one account (or of unassigned transactions) valued in `base_currency`
+async fn summarize_account(
+    state: &AppState,
+    headers: &HeaderMap,
+    account: Option<&Account>,
+    base_currency: &str,
+    conversions: &mut ConversionTrail,
+) -> Result<AccountSummary, AppError> {
+    let query = PortfolioQuery {
+        account_id: Some(account.map(|a| a.id.clone()).unwrap_or_else(|| "unassigned".to_string())),
+        ..Default::default()
+    };
+    let portfolio = get_portfolio(State(state.clone()), headers.clone(), Query(query)).await?.0;
+    conversions.extend(&portfolio.conversions);
+
+    let mut summary = AccountSummary {
+        account_id: account.map(|a| a.id.clone()),
+        name: account.map(|a| a.name.clone()).unwrap_or_else(|| "Unassigned".to_string()),
+        base_currency: base_currency.to_string(),
+        total_invested: 0.0,
+        total_current_value: 0.0,
+        total_unrealized_pnl: 0.0,
+        total_unrealized_pnl_percent: 0.0,
+        assets_count: portfolio.assets.len(),
+        benchmark: None,
+    };
+    let mut invested_value = 0.0;
+    for asset in &portfolio.assets {
+        let fx = state.exchange_rate_service.get_rate_recorded(&asset.currency, base_currency, conversions).await?;
+        summary.total_invested += asset.total_cost * fx;
+        summary.total_current_value += asset.current_value * fx;
+        summary.total_unrealized_pnl += asset.unrealized_pnl * fx;
+        if asset.asset_type != AssetType::Cash {
+            invested_value += asset.current_value * fx;
+        }
+    }
+    if summary.total_invested > 0.0 {
+        summary.total_unrealized_pnl_percent = summary.total_unrealized_pnl / summary.total_invested * 100.0;
+    }
+    if let Some(account) = account.filter(|a| a.benchmark.is_some()) {
+        summary.benchmark = compare_with_benchmark(state, account, invested_value, conversions).await?;
+    }
+    Ok(summary)
+}
+
+/// Replay the account's trades into its benchmark and compare the outcomes.
+/// Cash movements are left out on both sides, so deposits waiting to be invested don't count.
+async fn compare_with_benchmark(
+    state: &AppState,
+    account: &Account,
+    invested_value: f64,
+    conversions: &mut ConversionTrail,
+) -> Result<Option<BenchmarkComparison>, AppError> {
+    let Some(spec) = account.benchmark.as_deref() else { return Ok(None) };
+    let (symbol, asset_type) = parse_benchmark(spec)?;
+
+    // (day, amount in the account currency): buys positive, sales negative
+    let mut flows: Vec<(NaiveDate, f64)> = Vec::new();
+    for tx in state.db.list_transactions(&account.user_id).await? {
+        if tx.account_id.as_deref() != Some(account.id.as_str()) || tx.asset_type == AssetType::Cash {
+            continue;
+        }
+        let amount = match tx.action {
+            TradeAction::Buy | TradeAction::Long => tx.quantity * tx.price + tx.all_fees(),
+            TradeAction::Sell | TradeAction::CloseLong | TradeAction::LiquidateLong => -(tx.quantity * tx.price - tx.all_fees()),
+            _ => continue,
+        };
+        let tx_currency = tx.currency.clone()
+            .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
+            .unwrap_or_else(|| "THB".to_string());
+        let fx = state.exchange_rate_service.get_rate_recorded(&tx_currency, &account.base_currency, conversions).await?;
+        flows.push((tx.timestamp.date_naive(), amount * fx));
+    }
+    flows.sort_by_key(|(day, _)| *day);
+    let put_in: f64 = flows.iter().map(|(_, a)| a.max(0.0)).sum();
+    let Some(&(since, _)) = flows.first() else { return Ok(None) };
+    if put_in <= 0.0 {
+        return Ok(None);
+    }
+
+    let days = ((chrono::Utc::now().date_naive() - since).num_days() + 7).clamp(30, MAX_BENCHMARK_DAYS) as u32;
+    let history = match state.price_service.get_price_history(&symbol, &asset_type, None, days).await {
+        Ok(history) => history,
+        Err(e) => {
+            tracing::warn!("⚠️ No history for benchmark {} of account {}: {}", spec, account.id, e);
+            return Ok(None);
+        }
+    };
+    let closes: BTreeMap<NaiveDate, f64> = history.into_iter()
+        .filter(|h| h.price > 0.0)
+        .filter_map(|h| NaiveDate::parse_from_str(h.date.get(..10).unwrap_or(&h.date), "%Y-%m-%d").ok().map(|d| (d, h.price)))
+        .collect();
+    let (Some((_, &first_close)), Some((_, &last_close))) = (closes.first_key_value(), closes.last_key_value()) else {
+        tracing::warn!("⚠️ Empty history for benchmark {} of account {}", spec, account.id);
+        return Ok(None);
+    };
+
+    // The benchmark is bought and sold in the same currency amounts, so FX cancels out of its return
+    let mut units = 0.0;
+    let mut estimated = false;
+    for (day, amount) in &flows {
+        let close = match closes.range(..=*day).next_back() {
+            Some((_, &close)) => close,
+            None => {
+                estimated = true;
+                first_close
+            }
+        };
+        units += amount / close;
+    }
+    let net_flow: f64 = flows.iter().map(|(_, a)| a).sum();
+    let account_return_percent = (invested_value - net_flow) / put_in * 100.0;
+    let benchmark_return_percent = (units * last_close - net_flow) / put_in * 100.0;
+
+    Ok(Some(BenchmarkComparison {
+        benchmark: spec.to_string(),
+        account_return_percent,
+        benchmark_return_percent,
+        tracking_difference_percent: account_return_percent - benchmark_return_percent,
+        since: since.format("%Y-%m-%d").to_string(),
+        estimated,
+    }))
+}
+
+/// GET /api/accounts/:id/summary - Account holdings totalled in the account's base currency
+pub async fn get_account_summary(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<AccountSummaryResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let account = state.db.get_account(&id).await?;
+    if account.user_id != user_id {
+        return Err(AppError::NotFound(format!("Account {} not found", id)));
+    }
+
+    let mut conversions = ConversionTrail::default();
+    let summary = summarize_account(&state, &headers, Some(&account), &account.base_currency, &mut conversions).await?;
+    Ok(Json(AccountSummaryResponse {
+        summary,
+        conversions: conversions.into_vec(),
+    }))
+}
+
+/// GET /api/accounts/summary - Every account in its own base currency, plus combined totals
+/// converted into `base_currency`
+pub async fn get_accounts_summary(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<AccountsSummaryQuery>,
+) -> Result<Json<AccountsSummaryResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let base_currency = normalize_currency_code(query.base_currency.as_deref().unwrap_or("THB"))?;
+    let accounts: Vec<Account> = state.db.list_accounts(&user_id).await?
+        .into_iter()
+        .filter(|a| query.include_archived || !a.archived)
+        .collect();
+
+    let mut conversions = ConversionTrail::default();
+    let mut summaries = Vec::new();
+    for account in &accounts {
+        summaries.push(summarize_account(&state, &headers, Some(account), &account.base_currency, &mut conversions).await?);
+    }
+    let unassigned = summarize_account(&state, &headers, None, &base_currency, &mut conversions).await?;
+    if unassigned.assets_count > 0 {
+        summaries.push(unassigned);
+    }
+
+    let mut converted = Vec::new();
+    for summary in summaries {
+        let fx = state.exchange_rate_service
+            .get_rate_recorded(&summary.base_currency, &base_currency, &mut conversions)
+            .await?;
+        converted.push(ConvertedAccountSummary {
+            invested_in_base: summary.total_invested * fx,
+            value_in_base: summary.total_current_value * fx,
+            unrealized_pnl_in_base: summary.total_unrealized_pnl * fx,
+            weight_percent: 0.0,
+            summary,
+        });
+    }
+
+    let total_invested: f64 = converted.iter().map(|a| a.invested_in_base).sum();
+    let total_current_value: f64 = converted.iter().map(|a| a.value_in_base).sum();
+    let total_unrealized_pnl: f64 = converted.iter().map(|a| a.unrealized_pnl_in_base).sum();
+    for account in &mut converted {
+        if total_current_value > 0.0 {
+            account.weight_percent = account.value_in_base / total_current_value * 100.0;
+        }
+    }
+
+    Ok(Json(AccountsSummaryResponse {
+        base_currency,
+        accounts: converted,
+        total_invested,
+        total_current_value,
+        total_unrealized_pnl,
+        total_unrealized_pnl_percent: if total_invested > 0.0 { total_unrealized_pnl / total_invested * 100.0 } else { 0.0 },
+        conversions: conversions.into_vec(),
+    }))
 }
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State, Query},
+    extract::State,
     http::StatusCode,
     Json,
 };
@@ -7,10 +7,11 @@ use serde::Deserialize;
 
 use crate::AppState;
 use crate::error::AppError;
+use crate::extract::{Path, Query};
 use crate::models::{
-    CreateAlertRequest, UpdateAlertRequest, SubscribePushRequest,
+    CreateAlertRequest, UpdateAlertRequest, SubscribePushRequest, SnoozeAlertRequest,
+    NotificationChannel, NotificationTarget, UpdateNotificationTargetRequest,
 };
-use crate::services::AuthService;
 
 /// Extract user_id from JWT token in Authorization header
 async fn get_user_id_from_request(
@@ -101,12 +102,41 @@ pub async fn delete_alert(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// POST /api/alerts/:id/snooze - Snooze an alert for `minutes`, or mute it when omitted
+pub async fn snooze_alert(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<SnoozeAlertRequest>,
+) -> Result<Json<crate::models::AlertRule>, AppError> {
+    let auth_header = headers.get("authorization").and_then(|v| v.to_str().ok());
+    let user_id = get_user_id_from_request(&state, auth_header).await?;
+
+    let alert = state.alert_service.snooze_alert(&id, &user_id, req.minutes).await?;
+    Ok(Json(alert))
+}
+
+/// DELETE /api/alerts/:id/snooze - Clear a snooze or mute
+pub async fn unsnooze_alert(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<crate::models::AlertRule>, AppError> {
+    let auth_header = headers.get("authorization").and_then(|v| v.to_str().ok());
+    let user_id = get_user_id_from_request(&state, auth_header).await?;
+
+    let alert = state.alert_service.unsnooze_alert(&id, &user_id).await?;
+    Ok(Json(alert))
+}
+
 // ==================== Alert History Handlers ====================
 
 #[derive(Deserialize)]
 pub struct HistoryQuery {
     #[serde(default = "default_limit")]
     pub limit: u32,
+    /// Only this alert's triggers
+    pub alert_id: Option<String>,
 }
 
 fn default_limit() -> u32 {
@@ -122,7 +152,7 @@ pub async fn get_alert_history(
     let auth_header = headers.get("authorization").and_then(|v| v.to_str().ok());
     let user_id = get_user_id_from_request(&state, auth_header).await?;
 
-    let history = state.alert_service.get_alert_history(&user_id, query.limit).await?;
+    let history = state.alert_service.get_alert_history(&user_id, query.alert_id.as_deref(), query.limit).await?;
     Ok(Json(history))
 }
 
@@ -165,6 +195,77 @@ pub async fn mark_all_notifications_read(
     Ok(Json(serde_json::json!({ "marked_read": count })))
 }
 
+// ==================== Notification Channel Handlers ====================
+
+/// GET /api/notifications/channels - Email, Telegram and LINE: whether the server can send
+/// on each and where the user receives it
+pub async fn list_notification_channels(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let auth_header = headers.get("authorization").and_then(|v| v.to_str().ok());
+    let user_id = get_user_id_from_request(&state, auth_header).await?;
+
+    let delivery = state.notification_service.channels();
+    let mut targets = delivery.targets(&user_id).await?;
+    let channels: Vec<serde_json::Value> = NotificationChannel::EXTERNAL.iter()
+        .map(|channel| {
+            let target = targets.iter().position(|t| t.channel == *channel).map(|i| targets.swap_remove(i));
+            serde_json::json!({
+                "channel": channel,
+                "available": delivery.is_available(*channel),
+                "target": target,
+            })
+        })
+        .collect();
+    Ok(Json(serde_json::json!({ "channels": channels })))
+}
+
+/// PUT /api/notifications/channels/:channel - Register or change where a channel is delivered
+pub async fn set_notification_channel(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(channel): Path<NotificationChannel>,
+    Json(req): Json<UpdateNotificationTargetRequest>,
+) -> Result<Json<NotificationTarget>, AppError> {
+    let auth_header = headers.get("authorization").and_then(|v| v.to_str().ok());
+    let user_id = get_user_id_from_request(&state, auth_header).await?;
+
+    let target = state.notification_service.channels().set_target(&user_id, channel, req).await?;
+    Ok(Json(target))
+}
+
+/// DELETE /api/notifications/channels/:channel - Stop delivering on a channel
+pub async fn delete_notification_channel(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(channel): Path<NotificationChannel>,
+) -> Result<StatusCode, AppError> {
+    let auth_header = headers.get("authorization").and_then(|v| v.to_str().ok());
+    let user_id = get_user_id_from_request(&state, auth_header).await?;
+
+    state.notification_service.channels().remove_target(&user_id, channel).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/notifications/channels/:channel/test - Send a test message to the registered target
+pub async fn test_notification_channel(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(channel): Path<NotificationChannel>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let auth_header = headers.get("authorization").and_then(|v| v.to_str().ok());
+    let user_id = get_user_id_from_request(&state, auth_header).await?;
+
+    let target = state.notification_service.channels()
+        .send_test(&user_id, channel, "🔔 ทดสอบการแจ้งเตือน", "นี่คือการแจ้งเตือนทดสอบจาก Portfolio Tracker")
+        .await?;
+    Ok(Json(serde_json::json!({
+        "success": target.last_error.is_none(),
+        "target": target,
+    })))
+}
+
 // ==================== Push Subscription Handlers ====================
 
 /// POST /api/push/subscribe - Subscribe to web push notifications
@@ -193,13 +294,12 @@ pub async fn evaluate_alerts(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    let auth_header = headers.get("authorization").and_then(|v| v.to_str().ok());
-    let _user_id = get_user_id_from_request(&state, auth_header).await?;
-
-    // TODO: Check if user is admin
+    // Evaluates every user's alerts and may send Email/Telegram/LINE notifications
+    let admin_id = crate::handlers::users::extract_admin_user_id(&state, &headers)?;
 
     let result = state.alert_service.evaluate_all_alerts().await
-        .map_err(|e| AppError::Internal(e))?;
+        .map_err(AppError::Internal)?;
+    tracing::info!("🔔 Alert evaluation triggered by admin {}", admin_id);
     
     Ok(Json(result))
 }
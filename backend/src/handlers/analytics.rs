@@ -0,0 +1,98 @@
+use axum::{extract::State, http::HeaderMap, Json};
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::extract::Query;
+use crate::models::Transaction;
+use crate::services::exchange_rate::{ConversionTrail, ExchangeRate};
+use crate::services::lot_engine;
+use crate::services::trade_analytics::{matches_filter, trade_stats, ClosedTrade, TradeStats};
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TradeAnalyticsQuery {
+    /// Trades whose opening or closing transaction has this tag
+    pub tag: Option<String>,
+    /// Trades whose opening or closing transaction has this `strategy` custom field
+    pub strategy: Option<String>,
+    /// Replay only this account's transactions
+    pub account_id: Option<String>,
+    /// Closing time range
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Currency P&L is converted into at today's rates (default THB)
+    pub base_currency: Option<String>,
+    /// List the trades the statistics cover
+    #[serde(default)]
+    pub include_trades: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TradeAnalyticsResponse {
+    pub base_currency: String,
+    #[serde(flatten)]
+    pub stats: TradeStats,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub closed_trades: Vec<ClosedTrade>,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// GET /api/analytics/trades - Win rate, average win/loss, expectancy, profit factor and holding
+/// time distribution of closed trades from the lot engine. Positions closed as dust are left out.
+pub async fn get_trade_analytics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<TradeAnalyticsQuery>,
+) -> Result<Json<TradeAnalyticsResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let base_currency = query.base_currency
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+
+    let mut transactions = state.db.list_transactions(&user_id).await?;
+    if let Some(account_id) = query.account_id.as_deref().filter(|id| !id.is_empty()) {
+        transactions.retain(|t| t.account_id.as_deref() == Some(account_id));
+    }
+    let replay = lot_engine::replay(&transactions, &state.config.dust_policy);
+    let by_id: HashMap<&str, &Transaction> = transactions.iter().map(|t| (t.id.as_str(), t)).collect();
+    let (tag, strategy) = (query.tag.as_deref(), query.strategy.as_deref());
+    let selected = |id: Option<&String>| id
+        .and_then(|id| by_id.get(id.as_str()))
+        .is_some_and(|tx| matches_filter(tx, tag, strategy));
+
+    let mut conversions = ConversionTrail::default();
+    let mut closed_trades = Vec::new();
+    for trade in replay.realized_trades.iter().filter(|t| !t.dust) {
+        if query.from.is_some_and(|from| trade.timestamp < from) || query.to.is_some_and(|to| trade.timestamp > to) {
+            continue;
+        }
+        if !selected(Some(&trade.transaction_id)) && !selected(trade.opened_by.as_ref()) {
+            continue;
+        }
+        let fx = state.exchange_rate_service.get_rate_recorded(&trade.currency, &base_currency, &mut conversions).await?;
+        closed_trades.push(ClosedTrade::new(trade, fx));
+    }
+
+    Ok(Json(TradeAnalyticsResponse {
+        base_currency,
+        stats: trade_stats(&closed_trades),
+        closed_trades: if query.include_trades { closed_trades } else { Vec::new() },
+        conversions: conversions.into_vec(),
+    }))
+}
@@ -1,14 +1,34 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::State,
+    http::HeaderMap,
     Json,
 };
 use serde::Deserialize;
 use crate::error::AppError;
+use crate::extract::{Path, Query};
 use crate::models::{
     ApiProvider, CreateApiProviderRequest, UpdateApiProviderRequest, 
-    ReorderProvidersRequest, ApiCallStats
+    ReorderProvidersRequest, ApiCallStats, ProviderConnectivity
 };
+use crate::services::http_client;
 use crate::AppState;
+use super::users::{extract_admin_user_id, extract_ops_reader_id};
+
+#[derive(Deserialize)]
+pub struct ConnectivityQuery {
+    /// Try this proxy instead of the saved one, e.g. before saving it
+    pub proxy_url: Option<String>,
+}
+
+/// Reject proxy URLs the HTTP client cannot use; empty means "no per-provider proxy"
+fn validate_proxy_url(proxy_url: Option<&str>) -> Result<(), AppError> {
+    match proxy_url.map(str::trim) {
+        Some(url) if !url.is_empty() && !url.eq_ignore_ascii_case(http_client::DIRECT) => {
+            http_client::parse_proxy(url).map(|_| ()).map_err(AppError::BadRequest)
+        }
+        _ => Ok(()),
+    }
+}
 
 #[derive(Deserialize)]
 pub struct LogsQuery {
@@ -19,7 +39,9 @@ pub struct LogsQuery {
 /// List all API providers
 pub async fn list_providers(
     State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> Result<Json<Vec<ApiProvider>>, AppError> {
+    extract_ops_reader_id(&state, &headers)?;
     let providers = state.db.list_all_providers().await?;
     Ok(Json(providers))
 }
@@ -27,8 +49,10 @@ pub async fn list_providers(
 /// Get API providers for a specific market
 pub async fn get_providers_by_market(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(market_id): Path<String>,
 ) -> Result<Json<Vec<ApiProvider>>, AppError> {
+    extract_ops_reader_id(&state, &headers)?;
     let providers = state.db.get_providers_by_market(&market_id).await?;
     Ok(Json(providers))
 }
@@ -36,8 +60,11 @@ pub async fn get_providers_by_market(
 /// Create a new API provider
 pub async fn create_provider(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<CreateApiProviderRequest>,
 ) -> Result<Json<ApiProvider>, AppError> {
+    extract_admin_user_id(&state, &headers)?;
+
     // Validate required fields
     if req.market_id.trim().is_empty() {
         return Err(AppError::BadRequest("market_id is required".to_string()));
@@ -51,51 +78,135 @@ pub async fn create_provider(
     if req.priority < 1 {
         return Err(AppError::BadRequest("priority must be >= 1".to_string()));
     }
+    validate_proxy_url(req.proxy_url.as_deref())?;
     
     let provider = state.db.create_provider(req).await?;
+    state.price_service.refresh_provider_settings().await;
     Ok(Json(provider))
 }
 
 /// Update an API provider
 pub async fn update_provider(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
     Json(req): Json<UpdateApiProviderRequest>,
 ) -> Result<Json<ApiProvider>, AppError> {
+    extract_admin_user_id(&state, &headers)?;
+
     // Validate priority if provided
     if let Some(priority) = req.priority {
         if priority < 1 {
             return Err(AppError::BadRequest("priority must be >= 1".to_string()));
         }
     }
+    validate_proxy_url(req.proxy_url.as_deref())?;
     
     let provider = state.db.update_provider(&id, req).await?;
+    state.price_service.refresh_provider_settings().await;
     Ok(Json(provider))
 }
 
 /// Delete an API provider
 pub async fn delete_provider(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    extract_admin_user_id(&state, &headers)?;
     state.db.delete_provider(&id).await?;
+    state.price_service.refresh_provider_settings().await;
     Ok(Json(serde_json::json!({
         "message": "Provider deleted successfully",
         "id": id
     })))
 }
 
+/// Check that a provider's API URL is reachable through the proxy its requests would use
+pub async fn test_provider_connectivity(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<ConnectivityQuery>,
+) -> Result<Json<ProviderConnectivity>, AppError> {
+    extract_admin_user_id(&state, &headers)?;
+    let override_proxy = query.proxy_url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    validate_proxy_url(override_proxy.as_deref())?;
+
+    let provider = state.db.list_all_providers().await?
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("Provider {} not found", id)))?;
+    if provider.api_url.trim().is_empty() {
+        return Err(AppError::BadRequest("Provider has no api_url to test".to_string()));
+    }
+
+    let (proxy_source, proxy, client) = match override_proxy.or(provider.proxy_url.clone()) {
+        Some(url) => {
+            let client = http_client::proxied(&state.config, &url).map_err(AppError::BadRequest)?;
+            if url.eq_ignore_ascii_case(http_client::DIRECT) {
+                ("direct", None, client)
+            } else {
+                ("provider", Some(http_client::redact_proxy_url(&url)), client)
+            }
+        }
+        None => {
+            let client = http_client::clients(&state.config).providers.clone();
+            match state.config.outbound_proxy.as_deref() {
+                Some(url) => ("global", Some(http_client::redact_proxy_url(url)), client),
+                None => ("direct", None, client),
+            }
+        }
+    };
+
+    let start = std::time::Instant::now();
+    let result = client.get(&provider.api_url)
+        .timeout(std::time::Duration::from_millis(provider.timeout_ms.max(1000)))
+        .send()
+        .await;
+    let elapsed_ms = start.elapsed().as_millis() as u64;
+    let (status, error) = match result {
+        Ok(response) => (Some(response.status().as_u16()), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    tracing::info!(
+        "🔌 Connectivity test for {} via {} proxy: {}",
+        provider.provider_name,
+        proxy_source,
+        status.map(|s| s.to_string()).unwrap_or_else(|| "unreachable".to_string())
+    );
+
+    Ok(Json(ProviderConnectivity {
+        provider_id: provider.id,
+        provider_type: provider.provider_type,
+        url: provider.api_url,
+        proxy_source: proxy_source.to_string(),
+        proxy,
+        reachable: status.is_some(),
+        status,
+        elapsed_ms,
+        error,
+    }))
+}
+
 /// Reorder providers for a market
 pub async fn reorder_providers(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(market_id): Path<String>,
     Json(req): Json<ReorderProvidersRequest>,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    extract_admin_user_id(&state, &headers)?;
     if req.provider_ids.is_empty() {
         return Err(AppError::BadRequest("provider_ids cannot be empty".to_string()));
     }
     
     state.db.reorder_providers(&market_id, req.provider_ids).await?;
+    // Priority decides whose proxy wins among providers of the same type
+    state.price_service.refresh_provider_settings().await;
     Ok(Json(serde_json::json!({
         "message": "Providers reordered successfully",
         "market_id": market_id
@@ -105,8 +216,10 @@ pub async fn reorder_providers(
 /// Get API call logs with pagination
 pub async fn get_api_logs(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Query(query): Query<LogsQuery>,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    extract_ops_reader_id(&state, &headers)?;
     let page = query.page.unwrap_or(1);
     let per_page = query.per_page.unwrap_or(50).min(200);
     
@@ -122,7 +235,9 @@ pub async fn get_api_logs(
 /// Get API call statistics
 pub async fn get_api_stats(
     State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> Result<Json<Vec<ApiCallStats>>, AppError> {
+    extract_ops_reader_id(&state, &headers)?;
     let stats = state.db.get_api_stats().await?;
     Ok(Json(stats))
 }
@@ -0,0 +1,97 @@
+use axum::{extract::State, http::HeaderMap, Json};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use crate::error::AppError;
+use crate::extract::{Path, Query};
+use crate::models::{AssetType, Market};
+use crate::services::asset_chart::{backfill_points, chart_candles, merge_points, trade_markers, AssetChart, ChartRange};
+use crate::services::price_history::{check_range, load_points, CandleInterval};
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChartQuery {
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+    #[serde(default)]
+    pub range: ChartRange,
+    /// Defaults to one that suits the range
+    pub interval: Option<CandleInterval>,
+}
+
+/// GET /api/assets/:symbol/chart - Candles for ?range= (1d, 5d, 1m, 3m, 6m, ytd, 1y, 5y) with the
+/// user's trades as markers. Recorded history comes first; provider daily closes fill the range
+/// before recording started and the cached live price extends the last candle.
+pub async fn get_asset_chart(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(symbol): Path<String>,
+    Query(query): Query<ChartQuery>,
+) -> Result<Json<AssetChart>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let to = Utc::now();
+    let from = query.range.start(to);
+    let interval = query.interval.unwrap_or(query.range.default_interval());
+    check_range(interval, from, to)?;
+    let market = query.market.as_ref();
+    if state.client_modes.mode_for(&headers).await.is_active() {
+        state.symbol_heat.record_view(&symbol, &query.asset_type).await;
+    }
+
+    let (currency, recorded) = load_points(&state.db, &symbol, &query.asset_type, market, from, to).await?;
+    let first_recorded = recorded.first().map(|(at, _)| *at);
+
+    // Provider history is daily, so it only helps day and week charts that start before recording did
+    let daily = matches!(interval, CandleInterval::Day | CandleInterval::Week);
+    let backfill = if daily && first_recorded.is_none_or(|first| first - from > Duration::days(2)) {
+        let days = (to - from).num_days().max(1) as u32;
+        match state.price_service.get_price_history(&symbol, &query.asset_type, market, days).await {
+            Ok(history) => backfill_points(&history, first_recorded, from),
+            Err(e) => {
+                tracing::warn!("No provider history for {} chart: {}", symbol, e);
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    let live = state.price_service.cached_price(&symbol, &query.asset_type, market).await.map(|lookup| lookup.entry);
+    let currency = currency.or_else(|| live.as_ref().map(|l| l.currency.clone()));
+    let recorded_points = recorded.len();
+    let provider_points = backfill.len();
+    let points = merge_points(backfill, recorded, currency.as_deref(), live.as_ref());
+
+    let transactions = state.db.list_transactions(&user_id).await?;
+    let markers = trade_markers(&transactions, &symbol, &query.asset_type, market, from, to);
+
+    Ok(Json(AssetChart {
+        symbol: symbol.to_uppercase(),
+        asset_type: query.asset_type,
+        market: query.market,
+        range: query.range,
+        interval,
+        currency,
+        from,
+        to,
+        candles: chart_candles(&points, interval),
+        markers,
+        last_price: live,
+        recorded_points,
+        provider_points,
+    }))
+}
@@ -1,19 +1,23 @@
 use axum::{
-    extract::{Query, State, Path},
+    extract::State,
     http::{StatusCode, HeaderMap},
-    response::{IntoResponse, Redirect},
+    response::{IntoResponse, Redirect, Response},
     Json,
 };
 use axum_extra::extract::cookie::{Cookie, CookieJar};
 use oauth2::{PkceCodeVerifier, TokenResponse};
 use serde::{Deserialize, Serialize};
 
+use crate::client_ip::ClientIp;
 use crate::error::AppError;
-use crate::models::{User, UserResponse, OAuthAccount, OAuthProvider, LinkedProvider, AuthResponse};
+use crate::extract::{Path, Query};
+use crate::models::{
+    Claims, User, UserResponse, OAuthAccount, OAuthProvider, LinkedProvider, AuthResponse, LoginContext, Session,
+};
 use crate::services::auth::OAuthCallbackParams;
 use crate::AppState;
 
-const AUTH_COOKIE_NAME: &str = "auth_token";
+pub(crate) const AUTH_COOKIE_NAME: &str = "auth_token";
 
 /// Query params for OAuth login (optional redirect_uri for apps)
 #[derive(Debug, Deserialize)]
@@ -27,6 +31,12 @@ pub struct AuthProvidersResponse {
     pub google: bool,
     pub oidc: Option<OidcProviderInfo>,
     pub local: bool,
+    /// New accounts need an admin-generated invite code
+    pub invite_only: bool,
+    /// Local registrations must confirm their email before logging in
+    pub email_verification: bool,
+    /// A read-only demo session is available via /auth/guest
+    pub guest: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -51,6 +61,9 @@ pub async fn get_available_providers(
             None
         },
         local: auth.is_local_auth_enabled(),
+        invite_only: auth.is_invite_only(),
+        email_verification: auth.requires_email_verification(),
+        guest: auth.is_guest_login_enabled(),
     })
 }
 
@@ -60,6 +73,8 @@ pub async fn get_available_providers(
 pub async fn local_login(
     State(state): State<AppState>,
     jar: CookieJar,
+    client_ip: ClientIp,
+    headers: HeaderMap,
     Json(req): Json<crate::models::LocalAuthRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     let auth = &state.auth_service;
@@ -69,10 +84,14 @@ pub async fn local_login(
     }
     
     // Verify credentials
-    let user = auth.verify_local_user(&req.email, &req.password).await?;
+    let user = auth.verify_local_user(&req.email, &req.password).await
+        .inspect_err(|_| tracing::warn!("🔐 Failed login for {} from {}", req.email, client_ip))?;
+    if user.pending_verification && auth.requires_email_verification() {
+        return Err(AppError::Forbidden("Email address not verified. Check your inbox for the verification link".to_string()));
+    }
     
     // Create JWT
-    let jwt = auth.create_jwt(&user)?;
+    let jwt = issue_session_token(&state, &user, login_context(&state, &headers, client_ip)).await?;
     
     // Set cookie
     let cookie = Cookie::build((AUTH_COOKIE_NAME, jwt.clone()))
@@ -91,12 +110,33 @@ pub async fn local_login(
     ))
 }
 
+/// POST /api/auth/guest - Read-only session on the demo account (GUEST_LOGIN_ENABLED)
+pub async fn guest_login(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    client_ip: ClientIp,
+) -> Result<impl IntoResponse, AppError> {
+    let session = state.auth_service.create_guest_session().await?;
+    tracing::info!("👀 Guest session opened from {}", client_ip);
+
+    let cookie = Cookie::build((AUTH_COOKIE_NAME, session.token.clone()))
+        .path("/")
+        .http_only(true)
+        .secure(false)
+        .max_age(time::Duration::hours(state.config.guest_session_hours.max(1) as i64))
+        .build();
+
+    Ok((jar.add(cookie), Json(session)))
+}
+
 /// POST /api/auth/local/register - Register new local user
 pub async fn local_register(
     State(state): State<AppState>,
     jar: CookieJar,
+    client_ip: ClientIp,
+    headers: HeaderMap,
     Json(req): Json<crate::models::LocalAuthRequest>,
-) -> Result<impl IntoResponse, AppError> {
+) -> Result<Response, AppError> {
     let auth = &state.auth_service;
     
     if !auth.is_local_auth_enabled() {
@@ -113,11 +153,44 @@ pub async fn local_register(
         return Err(AppError::BadRequest("Password must be at least 6 characters".to_string()));
     }
     
+    // Invitation-only instances need a valid, unused code for this email, held until it is redeemed
+    let invite = if auth.is_invite_only() {
+        let code = req.invite_code.as_deref().map(str::trim).filter(|c| !c.is_empty())
+            .ok_or_else(|| AppError::Forbidden("Registration is by invitation only".to_string()))?;
+        Some(auth.claim_invite(code, &req.email).await?)
+    } else {
+        None
+    };
+    
     // Register user (new registrations are always regular users, not admins)
-    let user = auth.register_local_user(&req.email, &req.password, req.name.clone(), false).await?;
+    let pending_verification = auth.requires_email_verification();
+    let user = auth.register_local_user(&req.email, &req.password, req.name.clone(), false, pending_verification).await?;
+    tracing::info!("📝 Local registration {} from {}", user.email, client_ip);
+    
+    // An invite that can't be marked used would stay redeemable, so the registration is undone
+    if let Some(claim) = invite {
+        if let Err(e) = auth.redeem_invite(&claim.invite, &user.id).await {
+            tracing::warn!("⚠️ Could not mark invite {} as used, removing user {}: {}", claim.invite.code, user.email, e);
+            if let Err(e) = auth.delete_user(&user.id).await {
+                tracing::error!("❌ Could not remove user {} after a failed invite redemption: {}", user.id, e);
+            }
+            return Err(e);
+        }
+    }
+    
+    // No session until the emailed link is confirmed
+    if pending_verification {
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({
+                "message": "Registration received. Check your email to verify your address before logging in",
+                "user": UserResponse::from(&user),
+            })),
+        ).into_response());
+    }
     
     // Create JWT
-    let jwt = auth.create_jwt(&user)?;
+    let jwt = issue_session_token(&state, &user, login_context(&state, &headers, client_ip)).await?;
     
     // Set cookie
     let cookie = Cookie::build((AUTH_COOKIE_NAME, jwt.clone()))
@@ -134,7 +207,40 @@ pub async fn local_register(
             token: jwt,
             user: UserResponse::from(&user),
         }),
-    ))
+    ).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResendVerificationRequest {
+    pub email: String,
+}
+
+/// POST /api/auth/local/verify - Confirm the token from the verification email
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(req): Json<VerifyEmailRequest>,
+) -> Result<Json<UserResponse>, AppError> {
+    let user = state.auth_service.confirm_email_verification(&req.token).await?;
+    Ok(Json(UserResponse::from(&user)))
+}
+
+/// POST /api/auth/local/resend-verification - Send the verification email again.
+/// Always answers the same way so it cannot be used to probe which emails are registered.
+pub async fn resend_verification(
+    State(state): State<AppState>,
+    Json(req): Json<ResendVerificationRequest>,
+) -> Json<serde_json::Value> {
+    if let Err(e) = state.auth_service.request_email_verification(&req.email).await {
+        tracing::warn!("⚠️ Could not resend verification email to {}: {}", req.email, e);
+    }
+    Json(serde_json::json!({
+        "message": "If the address belongs to an unverified account, a verification email has been sent",
+    }))
 }
 
 // ==================== Google OAuth ====================
@@ -164,6 +270,8 @@ pub async fn google_callback(
     Query(params): Query<OAuthCallbackParams>,
     State(state): State<AppState>,
     jar: CookieJar,
+    client_ip: ClientIp,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
     let auth = &state.auth_service;
     
@@ -206,7 +314,7 @@ pub async fn google_callback(
     auth.link_oauth_account(oauth_account).await?;
     
     // Create JWT
-    let jwt = auth.create_jwt(&user)?;
+    let jwt = issue_session_token(&state, &user, login_context(&state, &headers, client_ip)).await?;
     
     // Set cookie and redirect to frontend or custom redirect_uri
     let cookie = Cookie::build((AUTH_COOKIE_NAME, jwt.clone()))
@@ -251,6 +359,8 @@ pub async fn oidc_callback(
     Query(params): Query<OAuthCallbackParams>,
     State(state): State<AppState>,
     jar: CookieJar,
+    client_ip: ClientIp,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
     let auth = &state.auth_service;
     
@@ -300,7 +410,7 @@ pub async fn oidc_callback(
     auth.link_oauth_account(oauth_account).await?;
     
     // Create JWT
-    let jwt = auth.create_jwt(&user)?;
+    let jwt = issue_session_token(&state, &user, login_context(&state, &headers, client_ip)).await?;
     
     // Set cookie and redirect to frontend or custom redirect_uri
     let cookie = Cookie::build((AUTH_COOKIE_NAME, jwt.clone()))
@@ -344,6 +454,18 @@ pub async fn logout(
     (jar.remove(cookie), Json(serde_json::json!({"message": "Logged out"})))
 }
 
+/// GET /api/auth/sessions - Devices the user is signed in on, newest first
+/// (`current` marks the one making the request)
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    jar: CookieJar,
+) -> Result<Json<Vec<Session>>, AppError> {
+    let (user, claims) = extract_session(&state, &jar, &headers).await?;
+    let sessions = state.auth_service.list_sessions(&user, claims.sid.as_deref()).await?;
+    Ok(Json(sessions))
+}
+
 /// GET /api/auth/linked-providers - Get linked OAuth providers for current user
 pub async fn get_linked_providers(
     State(state): State<AppState>,
@@ -444,13 +566,59 @@ pub async fn verify_token(
 
 // ==================== Helper Functions ====================
 
+/// Device details of a login request
+fn login_context(state: &AppState, headers: &HeaderMap, client_ip: ClientIp) -> LoginContext {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim).filter(|v| !v.is_empty());
+    LoginContext {
+        user_agent: header("user-agent").unwrap_or_default().to_string(),
+        ip: client_ip.0.map(|ip| ip.to_string()),
+        // Cloudflare sends XX/T1 for unknown and Tor clients
+        country: state.config.client_country_header.as_deref()
+            .and_then(header)
+            .map(str::to_uppercase)
+            .filter(|c| c.len() == 2 && c != "XX" && c != "T1"),
+    }
+}
+
+/// Issue a token for a successful login, recording the session and alerting the user
+/// when it comes from a device or country they have not used before
+async fn issue_session_token(state: &AppState, user: &User, context: LoginContext) -> Result<String, AppError> {
+    let (token, novelty) = state.auth_service.start_session(user, &context).await?;
+    if novelty.is_suspicious() {
+        let device = context.device();
+        let location = match (&novelty.new_country, &context.country) {
+            (Some(country), _) => format!("a new country ({})", country),
+            (None, Some(country)) => country.clone(),
+            (None, None) => "an unknown location".to_string(),
+        };
+        let body = format!(
+            "New sign-in on {} from {} (IP {}). If this was not you, change your password and log out all devices",
+            device, location, context.ip.as_deref().unwrap_or("unknown"),
+        );
+        tracing::warn!("🚨 Unfamiliar login for {}: {} from {}", user.email, device, location);
+
+        let notifications = state.notification_service.clone();
+        let user_id = user.id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = notifications.send_security_alert(&user_id, "New sign-in to your account", &body).await {
+                tracing::error!("Failed to send login alert: {}", e);
+            }
+        });
+    }
+    Ok(token)
+}
+
 /// Extract user from cookie or Authorization header
 async fn extract_user(state: &AppState, jar: &CookieJar, headers: &axum::http::HeaderMap) -> Result<User, AppError> {
+    extract_session(state, jar, headers).await.map(|(user, _)| user)
+}
+
+/// Extract user and token claims from cookie or Authorization header
+async fn extract_session(state: &AppState, jar: &CookieJar, headers: &axum::http::HeaderMap) -> Result<(User, Claims), AppError> {
     // Try Authorization header first
     if let Some(auth_header) = headers.get("Authorization") {
         if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..];
+            if let Some(token) = auth_str.strip_prefix("Bearer ") {
                 let claims = state.auth_service.verify_jwt(token)?;
                 let user = state.auth_service.get_user(&claims.sub).await?;
                 
@@ -458,7 +626,7 @@ async fn extract_user(state: &AppState, jar: &CookieJar, headers: &axum::http::H
                 if claims.token_version != user.token_version {
                      return Err(AppError::Unauthorized("Token expired (version mismatch)".to_string()));
                 }
-                return Ok(user);
+                return Ok((user, claims));
             }
         }
     }
@@ -475,5 +643,5 @@ async fn extract_user(state: &AppState, jar: &CookieJar, headers: &axum::http::H
          return Err(AppError::Unauthorized("Token expired (version mismatch)".to_string()));
     }
 
-    Ok(user)
+    Ok((user, claims))
 }
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        FromRequest, Request, State,
+    },
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use crate::error::AppError;
+use crate::extract::Query;
+use crate::services::realtime_bridge::RecordChange;
+use crate::AppState;
+
+/// Keeps idle connections open through proxies that drop silent sockets
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// How often held-back changes of a background client are checked for sending
+const BATCH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeStreamQuery {
+    /// Browsers can't set headers on a WebSocket handshake, so the JWT may come here instead
+    pub token: Option<String>,
+    /// Id the client declares its power mode under (PUT /api/clients/mode)
+    pub client_id: Option<String>,
+}
+
+/// Extract user_id from the Authorization header JWT, or the `token` query parameter
+fn extract_user_id(state: &AppState, headers: &HeaderMap, query: &ChangeStreamQuery) -> Result<String, AppError> {
+    let token = match headers.get("Authorization").and_then(|h| h.to_str().ok()) {
+        Some(auth_header) => auth_header
+            .strip_prefix("Bearer ")
+            .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?,
+        None => query.token.as_deref()
+            .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?,
+    };
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+/// GET /api/ws/changes - WebSocket pushing `{collection, action, id}` whenever one of the
+/// user's transactions or accounts, or a stored price, changes in PocketBase. Clients refetch
+/// what they show from that collection.
+pub async fn stream_changes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ChangeStreamQuery>,
+    request: Request,
+) -> Result<Response, AppError> {
+    // Authenticate before the upgrade, so anonymous handshakes get a 401 rather than a 426
+    let user_id = extract_user_id(&state, &headers, &query)?;
+    let upgrade = match WebSocketUpgrade::from_request(request, &state).await {
+        Ok(upgrade) => upgrade,
+        Err(rejection) => return Ok(rejection.into_response()),
+    };
+
+    tracing::info!("📻 Change stream opened for {}", user_id);
+    Ok(upgrade.on_upgrade(move |socket| run_stream(state, user_id, query.client_id, socket)))
+}
+
+async fn send_change(socket: &mut WebSocket, change: &RecordChange) -> bool {
+    let Ok(text) = serde_json::to_string(change) else { return true };
+    socket.send(Message::Text(text)).await.is_ok()
+}
+
+async fn run_stream(state: AppState, user_id: String, client_id: Option<String>, mut socket: WebSocket) {
+    let mut changes = state.realtime.subscribe();
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    // Latest held-back change per record while the client is in a low-power mode
+    let mut batched: HashMap<(&'static str, String), RecordChange> = HashMap::new();
+    let mut last_sent = tokio::time::Instant::now();
+    let mut batch_check = tokio::time::interval(BATCH_CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            change = changes.recv() => match change {
+                Ok(change) => {
+                    if !change.visible_to(&user_id) {
+                        continue;
+                    }
+                    if state.client_modes.mode(client_id.as_deref()).await.push_interval().is_some() {
+                        batched.insert((change.collection, change.id.clone()), change);
+                        continue;
+                    }
+                    if !send_change(&mut socket, &change).await {
+                        break;
+                    }
+                    last_sent = tokio::time::Instant::now();
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("⚠️ Change stream for {} fell behind, skipped {} changes", user_id, skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                // Clients only listen; pings are answered by the socket itself
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = ping.tick() => {
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+            _ = batch_check.tick(), if !batched.is_empty() => {
+                // A client back in the foreground gets what was held back right away
+                let due = match state.client_modes.mode(client_id.as_deref()).await.push_interval() {
+                    Some(every) => last_sent.elapsed() >= every,
+                    None => true,
+                };
+                if due {
+                    let mut closed = false;
+                    for (_, change) in batched.drain() {
+                        if !send_change(&mut socket, &change).await {
+                            closed = true;
+                            break;
+                        }
+                    }
+                    if closed {
+                        break;
+                    }
+                    last_sent = tokio::time::Instant::now();
+                }
+            }
+        }
+    }
+    tracing::info!("📻 Change stream closed for {}", user_id);
+}
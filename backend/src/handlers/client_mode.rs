@@ -0,0 +1,64 @@
+use axum::{extract::State, http::HeaderMap, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::services::client_modes::{ClientMode, PollingHints, MODE_TTL_SECONDS};
+use crate::services::ClientModes;
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientModeRequest {
+    /// Random id the client generated once and also sends as X-Client-Id / `client_id`
+    pub client_id: String,
+    pub mode: ClientMode,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClientModeResponse {
+    pub client_id: String,
+    pub mode: ClientMode,
+    /// The mode falls back to foreground after this unless declared again
+    pub expires_at: DateTime<Utc>,
+    pub ttl_seconds: i64,
+    pub polling: PollingHints,
+}
+
+/// PUT /api/clients/mode - Declare a client foreground, background or mobile-low-power. The
+/// client's WebSocket streams then batch their pushes, requests carrying its X-Client-Id stop
+/// heating symbols, and the answer says how often to poll.
+pub async fn set_client_mode(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<ClientModeRequest>,
+) -> Result<Json<ClientModeResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    if !ClientModes::valid_client_id(&req.client_id) {
+        return Err(AppError::BadRequest(
+            "client_id must be 8-64 letters, digits, '-' or '_'".to_string(),
+        ));
+    }
+    let expires_at = state.client_modes.declare(&user_id, &req.client_id, req.mode).await?;
+    tracing::debug!("🔋 Client {} of {} is now {:?}", req.client_id, user_id, req.mode);
+    Ok(Json(ClientModeResponse {
+        polling: state.client_modes.polling_hints(req.mode),
+        client_id: req.client_id,
+        mode: req.mode,
+        expires_at,
+        ttl_seconds: MODE_TTL_SECONDS,
+    }))
+}
@@ -0,0 +1,187 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use std::collections::BTreeMap;
+use crate::error::AppError;
+use crate::extract::Path;
+use crate::models::{
+    CreateCustomFieldRequest, CustomFieldDefinition, CustomFieldType, UpdateCustomFieldRequest,
+    UpdateTransactionRequest, CUSTOM_FIELDS_COLLECTION,
+};
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+pub(crate) async fn list_definitions(state: &AppState, user_id: &str) -> Result<Vec<CustomFieldDefinition>, AppError> {
+    state.db
+        .list_records(CUSTOM_FIELDS_COLLECTION, Some(format!("user_id='{}'", user_id)), "key")
+        .await
+}
+
+/// Validate transaction custom field values against the user's definitions, normalizing them in place.
+/// Null values are kept when `allow_null` is set (updates use them to clear a field).
+pub(crate) async fn normalize_custom_fields(
+    state: &AppState,
+    user_id: &str,
+    values: &mut BTreeMap<String, serde_json::Value>,
+    allow_null: bool,
+) -> Result<(), AppError> {
+    if values.is_empty() {
+        return Ok(());
+    }
+    let definitions = list_definitions(state, user_id).await?;
+    for (key, value) in values.iter_mut() {
+        let definition = definitions.iter()
+            .find(|d| &d.key == key)
+            .ok_or_else(|| AppError::BadRequest(format!("Unknown custom field '{}'", key)))?;
+        if value.is_null() {
+            if !allow_null {
+                return Err(AppError::BadRequest(format!("Custom field '{}' cannot be null", key)));
+            }
+            continue;
+        }
+        *value = definition.normalize(value).map_err(AppError::BadRequest)?;
+    }
+    Ok(())
+}
+
+fn validate_options(field_type: &CustomFieldType, options: &[String]) -> Result<(), AppError> {
+    if *field_type == CustomFieldType::Select {
+        if options.is_empty() || options.iter().any(|o| o.trim().is_empty()) {
+            return Err(AppError::BadRequest("Select fields need at least one non-empty option".to_string()));
+        }
+    } else if !options.is_empty() {
+        return Err(AppError::BadRequest("options only apply to select fields".to_string()));
+    }
+    Ok(())
+}
+
+async fn get_owned_definition(state: &AppState, id: &str, user_id: &str) -> Result<CustomFieldDefinition, AppError> {
+    let definition: CustomFieldDefinition = state.db.get_record(CUSTOM_FIELDS_COLLECTION, id).await?;
+    if definition.user_id != user_id {
+        return Err(AppError::NotFound(format!("Custom field {} not found", id)));
+    }
+    Ok(definition)
+}
+
+/// GET /api/custom-fields - List the user's custom field definitions
+pub async fn list_custom_fields(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<CustomFieldDefinition>>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    Ok(Json(list_definitions(&state, &user_id).await?))
+}
+
+/// POST /api/custom-fields - Define a new custom field for transactions
+pub async fn create_custom_field(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateCustomFieldRequest>,
+) -> Result<Json<CustomFieldDefinition>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+
+    let key = req.key.trim().to_lowercase();
+    let valid_key = !key.is_empty()
+        && key.len() <= 64
+        && key.starts_with(|c: char| c.is_ascii_lowercase())
+        && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid_key {
+        return Err(AppError::BadRequest(
+            "key must start with a letter and contain only a-z, 0-9 and _ (max 64 chars)".to_string(),
+        ));
+    }
+    if req.label.trim().is_empty() {
+        return Err(AppError::BadRequest("label is required".to_string()));
+    }
+    validate_options(&req.field_type, &req.options)?;
+
+    let existing = list_definitions(&state, &user_id).await?;
+    if existing.iter().any(|d| d.key == key) {
+        return Err(AppError::Conflict(format!("Custom field '{}' already exists", key)));
+    }
+
+    let body = serde_json::json!({
+        "user_id": user_id,
+        "key": key,
+        "label": req.label.trim(),
+        "field_type": req.field_type,
+        "options": req.options,
+    });
+    let definition: CustomFieldDefinition = state.db.create_record(CUSTOM_FIELDS_COLLECTION, &body).await?;
+    tracing::info!("🏷️ Created custom field '{}' ({:?}) for {}", definition.key, definition.field_type, user_id);
+    Ok(Json(definition))
+}
+
+/// PUT /api/custom-fields/:id - Rename a field or change its select options
+pub async fn update_custom_field(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateCustomFieldRequest>,
+) -> Result<Json<CustomFieldDefinition>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let definition = get_owned_definition(&state, &id, &user_id).await?;
+
+    let mut body = serde_json::Map::new();
+    if let Some(label) = req.label {
+        if label.trim().is_empty() {
+            return Err(AppError::BadRequest("label cannot be empty".to_string()));
+        }
+        body.insert("label".to_string(), serde_json::json!(label.trim()));
+    }
+    if let Some(options) = req.options {
+        validate_options(&definition.field_type, &options)?;
+        body.insert("options".to_string(), serde_json::json!(options));
+    }
+
+    let updated: CustomFieldDefinition = state.db
+        .update_record(CUSTOM_FIELDS_COLLECTION, &id, &serde_json::Value::Object(body))
+        .await?;
+    Ok(Json(updated))
+}
+
+/// DELETE /api/custom-fields/:id - Remove a field definition and its values from the user's transactions
+pub async fn delete_custom_field(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let definition = get_owned_definition(&state, &id, &user_id).await?;
+
+    let transactions = state.db.list_transactions(&user_id).await?;
+    let mut cleared = 0;
+    for tx in transactions.iter().filter(|t| t.custom_fields.contains_key(&definition.key)) {
+        let mut removal = BTreeMap::new();
+        removal.insert(definition.key.clone(), serde_json::Value::Null);
+        let req = UpdateTransactionRequest {
+            custom_fields: Some(removal),
+            ..Default::default()
+        };
+        state.db.update_transaction(&tx.id, req).await?;
+        cleared += 1;
+    }
+
+    state.db.delete_record(CUSTOM_FIELDS_COLLECTION, &id).await?;
+    tracing::info!("🗑️ Deleted custom field '{}' ({} transactions cleared)", definition.key, cleared);
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "transactions_cleared": cleared
+    })))
+}
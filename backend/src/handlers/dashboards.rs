@@ -0,0 +1,221 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use std::collections::HashSet;
+use crate::error::AppError;
+use crate::extract::Path;
+use crate::models::{
+    CreateDashboardRequest, Dashboard, DashboardWidget, UpdateDashboardRequest, DASHBOARDS_COLLECTION,
+};
+use crate::AppState;
+
+/// Dashboards a user can keep
+const MAX_DASHBOARDS: usize = 20;
+const MAX_WIDGETS: usize = 50;
+/// Width of the layout grid in cells
+const GRID_COLUMNS: u32 = 24;
+const MAX_WIDGET_HEIGHT: u32 = 48;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+async fn list_dashboards_for(state: &AppState, user_id: &str) -> Result<Vec<Dashboard>, AppError> {
+    state.db
+        .list_records(DASHBOARDS_COLLECTION, Some(format!("user_id='{}'", user_id)), "created")
+        .await
+}
+
+async fn get_owned_dashboard(state: &AppState, id: &str, user_id: &str) -> Result<Dashboard, AppError> {
+    let dashboard: Dashboard = state.db.get_record(DASHBOARDS_COLLECTION, id).await?;
+    if dashboard.user_id != user_id {
+        return Err(AppError::NotFound(format!("Dashboard {} not found", id)));
+    }
+    Ok(dashboard)
+}
+
+fn validate_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err(AppError::BadRequest("name is required (max 100 chars)".to_string()));
+    }
+    Ok(name.to_string())
+}
+
+/// Widgets may only point at API paths: no other hosts, no path tricks, parameters kept apart
+fn validate_endpoint(endpoint: &str) -> bool {
+    endpoint.starts_with('/')
+        && endpoint.len() <= 200
+        && !endpoint.contains("..")
+        && !endpoint.contains("//")
+        && endpoint.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.'))
+}
+
+fn validate_widgets(widgets: &[DashboardWidget]) -> Result<(), AppError> {
+    if widgets.len() > MAX_WIDGETS {
+        return Err(AppError::BadRequest(format!("A dashboard holds at most {} widgets", MAX_WIDGETS)));
+    }
+    let mut ids = HashSet::new();
+    for widget in widgets {
+        if widget.id.trim().is_empty() || !ids.insert(widget.id.as_str()) {
+            return Err(AppError::BadRequest(format!("Widget ids must be non-empty and unique ('{}')", widget.id)));
+        }
+        if widget.widget_type.trim().is_empty() {
+            return Err(AppError::BadRequest(format!("Widget '{}' needs a widget_type", widget.id)));
+        }
+        let layout = &widget.layout;
+        if layout.w == 0 || layout.h == 0 || layout.x + layout.w > GRID_COLUMNS || layout.h > MAX_WIDGET_HEIGHT {
+            return Err(AppError::BadRequest(format!(
+                "Widget '{}' must fit the {}-column grid with a non-zero size",
+                widget.id, GRID_COLUMNS
+            )));
+        }
+        if !validate_endpoint(&widget.query.endpoint) {
+            return Err(AppError::BadRequest(format!(
+                "Widget '{}' endpoint must be an API path such as /portfolio/movers, got '{}'",
+                widget.id, widget.query.endpoint
+            )));
+        }
+        if let Some((key, _)) = widget.query.params.iter().find(|(_, v)| v.is_object() || v.is_array()) {
+            return Err(AppError::BadRequest(format!(
+                "Widget '{}' parameter '{}' must be a string, number or boolean",
+                widget.id, key
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Unset is_default on the user's other dashboards
+async fn clear_other_defaults(state: &AppState, user_id: &str, keep: &str) -> Result<(), AppError> {
+    for other in list_dashboards_for(state, user_id).await?.iter().filter(|d| d.is_default && d.id != keep) {
+        state.db
+            .update_record::<serde_json::Value>(DASHBOARDS_COLLECTION, &other.id, &serde_json::json!({ "is_default": false }))
+            .await?;
+    }
+    Ok(())
+}
+
+/// GET /api/dashboards - List the user's dashboards with their widgets
+pub async fn list_dashboards(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Dashboard>>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    Ok(Json(list_dashboards_for(&state, &user_id).await?))
+}
+
+/// GET /api/dashboards/:id - One dashboard
+pub async fn get_dashboard(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Dashboard>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    Ok(Json(get_owned_dashboard(&state, &id, &user_id).await?))
+}
+
+/// POST /api/dashboards - Save a new dashboard; the user's first one becomes the default
+pub async fn create_dashboard(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateDashboardRequest>,
+) -> Result<Json<Dashboard>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let name = validate_name(&req.name)?;
+    validate_widgets(&req.widgets)?;
+
+    let existing = list_dashboards_for(&state, &user_id).await?;
+    if existing.len() >= MAX_DASHBOARDS {
+        return Err(AppError::BadRequest(format!("At most {} dashboards can be saved", MAX_DASHBOARDS)));
+    }
+    let is_default = req.is_default || existing.is_empty();
+
+    let body = serde_json::json!({
+        "user_id": user_id,
+        "name": name,
+        "is_default": is_default,
+        "widgets": req.widgets,
+    });
+    let dashboard: Dashboard = state.db.create_record(DASHBOARDS_COLLECTION, &body).await?;
+    if is_default {
+        clear_other_defaults(&state, &user_id, &dashboard.id).await?;
+    }
+    tracing::info!("🧩 Created dashboard '{}' ({} widgets) for {}", dashboard.name, dashboard.widgets.len(), user_id);
+    Ok(Json(dashboard))
+}
+
+/// PUT /api/dashboards/:id - Rename, re-layout or make default. With `expected_updated`, a
+/// version saved meanwhile from another client is a 409 instead of being overwritten.
+pub async fn update_dashboard(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateDashboardRequest>,
+) -> Result<Json<Dashboard>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let dashboard = get_owned_dashboard(&state, &id, &user_id).await?;
+    if let Some(expected) = &req.expected_updated {
+        if dashboard.updated.as_ref().is_some_and(|updated| updated != expected) {
+            return Err(AppError::Conflict(format!(
+                "Dashboard {} was changed on another device; reload it before saving",
+                id
+            )));
+        }
+    }
+
+    let mut body = serde_json::Map::new();
+    if let Some(name) = &req.name {
+        body.insert("name".to_string(), serde_json::json!(validate_name(name)?));
+    }
+    if let Some(widgets) = &req.widgets {
+        validate_widgets(widgets)?;
+        body.insert("widgets".to_string(), serde_json::json!(widgets));
+    }
+    if let Some(is_default) = req.is_default {
+        body.insert("is_default".to_string(), serde_json::json!(is_default));
+    }
+
+    let updated: Dashboard = state.db
+        .update_record(DASHBOARDS_COLLECTION, &id, &serde_json::Value::Object(body))
+        .await?;
+    if req.is_default == Some(true) {
+        clear_other_defaults(&state, &user_id, &id).await?;
+    }
+    Ok(Json(updated))
+}
+
+/// DELETE /api/dashboards/:id - Remove a dashboard; if it was the default, the oldest remaining one takes over
+pub async fn delete_dashboard(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let dashboard = get_owned_dashboard(&state, &id, &user_id).await?;
+    state.db.delete_record(DASHBOARDS_COLLECTION, &id).await?;
+
+    if dashboard.is_default {
+        let remaining = list_dashboards_for(&state, &user_id).await?;
+        if let Some(next) = remaining.first() {
+            state.db
+                .update_record::<serde_json::Value>(DASHBOARDS_COLLECTION, &next.id, &serde_json::json!({ "is_default": true }))
+                .await?;
+        }
+    }
+    tracing::info!("🗑️ Deleted dashboard '{}' for {}", dashboard.name, user_id);
+    Ok(Json(serde_json::json!({ "success": true })))
+}
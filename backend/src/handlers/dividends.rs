@@ -0,0 +1,204 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::extract::Query;
+use crate::models::{DividendPayment, DividendPeriod, DividendPeriodTotal, DividendProjection};
+use crate::services::dividends::{dividend_payments, income_holdings, period_totals};
+use crate::services::exchange_rate::{ConversionTrail, ExchangeRate};
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DividendsQuery {
+    pub year: Option<i32>,
+    pub symbol: Option<String>,
+    pub account_id: Option<String>,
+    /// Include interest credited to savings/cash accounts (default true)
+    pub include_interest: Option<bool>,
+    /// Summary grouping
+    #[serde(default)]
+    pub period: DividendPeriod,
+    /// Summary currency (default THB)
+    pub base_currency: Option<String>,
+}
+
+impl DividendsQuery {
+    fn matches(&self, payment: &DividendPayment) -> bool {
+        self.year.is_none_or(|y| payment.paid_at.year() == y)
+            && self.symbol.as_deref().is_none_or(|s| payment.symbol.eq_ignore_ascii_case(s))
+            && self.account_id.as_deref().is_none_or(|a| payment.account_id.as_deref() == Some(a))
+            && (self.include_interest.unwrap_or(true) || !payment.interest)
+    }
+}
+
+/// The user's dividend payments matching the filters, oldest first
+async fn load_payments(state: &AppState, user_id: &str, query: &DividendsQuery) -> Result<Vec<DividendPayment>, AppError> {
+    let transactions = state.db.list_transactions(user_id).await?;
+    // Replay everything so per-share amounts see the full holding history
+    Ok(dividend_payments(&transactions).into_iter().filter(|p| query.matches(p)).collect())
+}
+
+/// GET /api/dividends - Dividend and interest payments, newest first
+/// (filter with ?year=, ?symbol=, ?account_id=, ?include_interest=false)
+pub async fn list_dividends(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DividendsQuery>,
+) -> Result<Json<Vec<DividendPayment>>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let mut payments = load_payments(&state, &user_id, &query).await?;
+    payments.reverse();
+    Ok(Json(payments))
+}
+
+#[derive(Debug, Serialize)]
+pub struct DividendSummaryResponse {
+    pub base_currency: String,
+    pub total: f64,
+    pub payments: usize,
+    pub periods: Vec<DividendPeriodTotal>,
+    /// Current rates used for the base-currency totals
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// GET /api/dividends/summary - Income per year or month (?period=year|month) in the base currency,
+/// with the same filters as the list
+pub async fn get_dividend_summary(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DividendsQuery>,
+) -> Result<Json<DividendSummaryResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let base_currency = query.base_currency.as_deref()
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+    let payments = load_payments(&state, &user_id, &query).await?;
+
+    let mut conversions = ConversionTrail::default();
+    let mut amounts = Vec::with_capacity(payments.len());
+    for payment in &payments {
+        let fx = state.exchange_rate_service.get_rate_recorded(&payment.currency, &base_currency, &mut conversions).await?;
+        amounts.push(payment.amount * fx);
+    }
+
+    Ok(Json(DividendSummaryResponse {
+        base_currency,
+        total: amounts.iter().sum(),
+        payments: payments.len(),
+        periods: period_totals(&payments, &amounts, query.period),
+        conversions: conversions.into_vec(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DividendProjectionQuery {
+    pub base_currency: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DividendProjectionResponse {
+    pub base_currency: String,
+    pub projected_annual_income: f64,
+    pub projected_monthly_income: f64,
+    /// Projected income over the current value of the dividend-paying holdings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yield_percent: Option<f64>,
+    pub holdings: Vec<DividendProjection>,
+    pub generated_at: DateTime<Utc>,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// GET /api/dividends/projection - Expected annual income from current holdings,
+/// based on each symbol's dividends over the last 12 months
+pub async fn get_dividend_projection(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DividendProjectionQuery>,
+) -> Result<Json<DividendProjectionResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let base_currency = query.base_currency
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+    let transactions = state.db.list_transactions(&user_id).await?;
+    let now = Utc::now();
+
+    let mut conversions = ConversionTrail::default();
+    let mut holdings = Vec::new();
+    let mut total_income = 0.0;
+    let mut priced_income = 0.0;
+    let mut priced_value = 0.0;
+    for holding in income_holdings(&transactions, now) {
+        let fx = state.exchange_rate_service.get_rate_recorded(&holding.currency, &base_currency, &mut conversions).await?;
+        let projected_annual = holding.trailing_per_share * holding.quantity;
+        let projected_annual_base = projected_annual * fx;
+
+        let current_value_base = match state.price_service
+            .get_price(&holding.symbol, &holding.asset_type, holding.market.as_ref())
+            .await
+        {
+            Ok(entry) => {
+                let price_fx = state.exchange_rate_service
+                    .get_rate_recorded(&entry.currency, &base_currency, &mut conversions)
+                    .await
+                    .ok();
+                price_fx.map(|rate| holding.quantity * entry.price * rate)
+            }
+            Err(e) => {
+                tracing::warn!("No price for {}: {}, yield left out", holding.symbol, e);
+                None
+            }
+        };
+        let yield_percent = current_value_base.filter(|v| *v > 0.0).map(|v| projected_annual_base / v * 100.0);
+
+        total_income += projected_annual_base;
+        if let Some(value) = current_value_base.filter(|v| *v > 0.0) {
+            priced_income += projected_annual_base;
+            priced_value += value;
+        }
+        holdings.push(DividendProjection {
+            symbol: holding.symbol,
+            asset_type: holding.asset_type,
+            market: holding.market,
+            currency: holding.currency,
+            quantity: holding.quantity,
+            trailing_per_share: holding.trailing_per_share,
+            payments_per_year: holding.payments_per_year,
+            projected_annual,
+            projected_annual_base,
+            current_value_base,
+            yield_percent,
+        });
+    }
+    holdings.sort_by(|a, b| {
+        b.projected_annual_base.partial_cmp(&a.projected_annual_base).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    Ok(Json(DividendProjectionResponse {
+        base_currency,
+        projected_annual_income: total_income,
+        projected_monthly_income: total_income / 12.0,
+        yield_percent: (priced_value > 0.0).then(|| priced_income / priced_value * 100.0),
+        holdings,
+        generated_at: now,
+        conversions: conversions.into_vec(),
+    }))
+}
@@ -0,0 +1,164 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use chrono::NaiveDate;
+use crate::error::AppError;
+use crate::extract::Path;
+use crate::models::{build_vesting_schedule, CreateEquityGrantRequest, EquityGrant, GrantType};
+use crate::services::equity_vesting::{vest_due_tranches, EQUITY_GRANTS_COLLECTION};
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+/// Load a grant and make sure the caller owns it
+async fn get_owned_grant(state: &AppState, id: &str, user_id: &str) -> Result<EquityGrant, AppError> {
+    let grant: EquityGrant = state.db.get_record(EQUITY_GRANTS_COLLECTION, id).await?;
+    if grant.user_id != user_id {
+        return Err(AppError::NotFound(format!("Equity grant {} not found", id)));
+    }
+    Ok(grant)
+}
+
+/// GET /api/equity-grants - List the user's RSU/ESPP grants
+pub async fn list_equity_grants(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<EquityGrant>>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let grants = state.db
+        .list_records(EQUITY_GRANTS_COLLECTION, Some(format!("user_id='{}'", user_id)), "grant_date")
+        .await?;
+    Ok(Json(grants))
+}
+
+/// POST /api/equity-grants - Create a grant, generating its vesting schedule unless one is given
+pub async fn create_equity_grant(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateEquityGrantRequest>,
+) -> Result<Json<EquityGrant>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+
+    if req.symbol.trim().is_empty() {
+        return Err(AppError::BadRequest("symbol is required".to_string()));
+    }
+    if req.total_quantity <= 0.0 {
+        return Err(AppError::BadRequest("total_quantity must be positive".to_string()));
+    }
+    if !(0.0..100.0).contains(&req.espp_discount_percent) {
+        return Err(AppError::BadRequest("espp_discount_percent must be between 0 and 100".to_string()));
+    }
+    if req.grant_type == GrantType::Rsu && req.espp_discount_percent > 0.0 {
+        return Err(AppError::BadRequest("espp_discount_percent only applies to ESPP grants".to_string()));
+    }
+    let grant_date = NaiveDate::parse_from_str(&req.grant_date, "%Y-%m-%d")
+        .map_err(|_| AppError::BadRequest("grant_date must be YYYY-MM-DD".to_string()))?;
+
+    let tranches = match req.tranches {
+        Some(tranches) => {
+            if tranches.is_empty() {
+                return Err(AppError::BadRequest("tranches cannot be empty".to_string()));
+            }
+            for tranche in &tranches {
+                NaiveDate::parse_from_str(&tranche.date, "%Y-%m-%d").map_err(|_| {
+                    AppError::BadRequest(format!("Invalid tranche date {}, expected YYYY-MM-DD", tranche.date))
+                })?;
+                if tranche.quantity <= 0.0 {
+                    return Err(AppError::BadRequest("tranche quantity must be positive".to_string()));
+                }
+            }
+            let scheduled: f64 = tranches.iter().map(|t| t.quantity).sum();
+            if (scheduled - req.total_quantity).abs() > 1e-9 {
+                return Err(AppError::BadRequest(format!(
+                    "tranche quantities ({}) must add up to total_quantity ({})",
+                    scheduled, req.total_quantity
+                )));
+            }
+            tranches
+        }
+        None => {
+            if req.cliff_months > req.vesting_months {
+                return Err(AppError::BadRequest("cliff_months cannot exceed vesting_months".to_string()));
+            }
+            build_vesting_schedule(grant_date, req.total_quantity, req.cliff_months, req.vesting_months, &req.frequency)
+        }
+    };
+
+    let body = serde_json::json!({
+        "user_id": user_id,
+        "grant_type": req.grant_type,
+        "symbol": req.symbol.trim().to_uppercase(),
+        "asset_type": req.asset_type,
+        "market": req.market,
+        "currency": req.currency,
+        "grant_date": req.grant_date,
+        "total_quantity": req.total_quantity,
+        "espp_discount_percent": req.espp_discount_percent,
+        "account_id": req.account_id,
+        "tranches": tranches,
+        "notes": req.notes,
+    });
+    let grant: EquityGrant = state.db.create_record(EQUITY_GRANTS_COLLECTION, &body).await?;
+    tracing::info!("📜 Created {:?} grant {} for {} ({} tranches)", grant.grant_type, grant.id, grant.symbol, grant.tranches.len());
+    Ok(Json(grant))
+}
+
+/// GET /api/equity-grants/:id
+pub async fn get_equity_grant(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<EquityGrant>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let grant = get_owned_grant(&state, &id, &user_id).await?;
+    Ok(Json(grant))
+}
+
+/// DELETE /api/equity-grants/:id - Remove the grant (transactions from past vests are kept)
+pub async fn delete_equity_grant(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    get_owned_grant(&state, &id, &user_id).await?;
+    state.db.delete_record(EQUITY_GRANTS_COLLECTION, &id).await?;
+    Ok(Json(serde_json::json!({
+        "message": "Equity grant deleted successfully",
+        "id": id
+    })))
+}
+
+/// POST /api/equity-grants/:id/vest - Vest any due tranches now instead of waiting for the job
+pub async fn vest_equity_grant(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<EquityGrant>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let mut grant = get_owned_grant(&state, &id, &user_id).await?;
+    let outcome = vest_due_tranches(&state.db, &state.price_service, &mut grant).await?;
+    if !outcome.errors.is_empty() {
+        // Tranches vested before the failure stay vested; calling again retries the rest
+        return Err(AppError::ExternalApiError(format!(
+            "Vested {} tranche(s), {} failed: {}",
+            outcome.vested, outcome.errors.len(), outcome.errors.join("; ")
+        )));
+    }
+    Ok(Json(grant))
+}
@@ -0,0 +1,53 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use serde::Deserialize;
+use crate::error::AppError;
+use crate::extract::Query;
+use crate::models::EventsPage;
+use crate::AppState;
+
+const DEFAULT_EVENTS_LIMIT: u32 = 200;
+const MAX_EVENTS_LIMIT: u32 = 500;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// `next_cursor` of the previous page; omit on first sync
+    pub cursor: Option<u64>,
+    pub limit: Option<u32>,
+}
+
+/// GET /api/events?cursor= - Transaction, account and snapshot changes since the cursor, for
+/// incremental sync. A `reset` page means the client should re-fetch the full lists first.
+pub async fn list_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<EventsQuery>,
+) -> Result<Json<EventsPage>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let limit = query.limit.unwrap_or(DEFAULT_EVENTS_LIMIT).clamp(1, MAX_EVENTS_LIMIT);
+
+    let page = state.db.list_events(&user_id, query.cursor, limit).await?;
+    tracing::debug!(
+        "🔁 Events for {} after {:?}: {} (next {}, reset {})",
+        user_id, query.cursor, page.events.len(), page.next_cursor, page.reset
+    );
+    Ok(Json(page))
+}
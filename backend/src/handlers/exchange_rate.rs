@@ -1,10 +1,14 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::State,
+    http::HeaderMap,
     Json,
 };
-use serde::Deserialize;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use crate::error::AppError;
-use crate::services::exchange_rate::ExchangeRatesResponse;
+use crate::extract::{Path, Query};
+use crate::services::exchange_rate::{ConversionTrail, ExchangeRate, ExchangeRatesResponse};
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -20,16 +24,16 @@ pub async fn get_exchange_rate(
     Query(query): Query<ConvertQuery>,
 ) -> Result<Json<serde_json::Value>, AppError> {
     let amount = query.amount.unwrap_or(1.0);
-    let rate = state.exchange_rate_service.get_rate(&query.from, &query.to).await?;
-    let converted = amount * rate;
+    let quote = state.exchange_rate_service.get_quote(&query.from, &query.to).await?;
 
     Ok(Json(serde_json::json!({
-        "from": query.from.to_uppercase(),
-        "to": query.to.to_uppercase(),
-        "rate": rate,
+        "from": quote.from_currency,
+        "to": quote.to_currency,
+        "rate": quote.rate,
         "amount": amount,
-        "converted": converted,
-        "updated_at": chrono::Utc::now()
+        "converted": amount * quote.rate,
+        "updated_at": quote.updated_at,
+        "source": quote.source
     })))
 }
 
@@ -48,23 +52,129 @@ pub async fn convert_currency(
     Query(query): Query<ConvertQuery>,
 ) -> Result<Json<serde_json::Value>, AppError> {
     let amount = query.amount.unwrap_or(1.0);
-    let converted = state.exchange_rate_service.convert(amount, &query.from, &query.to).await?;
-    let rate = state.exchange_rate_service.get_rate(&query.from, &query.to).await?;
+    let quote = state.exchange_rate_service.get_quote(&query.from, &query.to).await?;
 
     Ok(Json(serde_json::json!({
-        "from": query.from.to_uppercase(),
-        "to": query.to.to_uppercase(),
+        "from": quote.from_currency,
+        "to": quote.to_currency,
         "amount": amount,
-        "converted": converted,
-        "rate": rate
+        "converted": amount * quote.rate,
+        "rate": quote.rate,
+        "conversions": [quote]
     })))
 }
 
+/// Most items one batch conversion may contain
+const MAX_BATCH_CONVERSIONS: usize = 1000;
+/// Most distinct (pair, date) historical lookups one batch may make; each can be an upstream call
+const MAX_BATCH_HISTORICAL_LOOKUPS: usize = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct ConvertItem {
+    pub amount: f64,
+    pub from: String,
+    pub to: String,
+    /// Convert at this day's rate instead of the current one
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchConvertRequest {
+    pub items: Vec<ConvertItem>,
+}
+
+/// One converted item, in request order; failed items carry `error` instead of a rate
+#[derive(Debug, Serialize)]
+pub struct ConvertResult {
+    pub amount: f64,
+    pub from: String,
+    pub to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate: Option<ExchangeRate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchConvertResponse {
+    pub results: Vec<ConvertResult>,
+    pub failed: usize,
+    /// Distinct rates used across all items
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// POST /api/exchange-rate/convert/batch - Convert many amounts in one call.
+/// Each distinct pair (and date) is looked up once; a failed item does not fail the batch.
+/// Dated items beyond MAX_BATCH_HISTORICAL_LOOKUPS distinct lookups fail without a lookup.
+pub async fn convert_currency_batch(
+    State(state): State<AppState>,
+    Json(req): Json<BatchConvertRequest>,
+) -> Result<Json<BatchConvertResponse>, AppError> {
+    if req.items.len() > MAX_BATCH_CONVERSIONS {
+        return Err(AppError::BadRequest(format!(
+            "Too many items ({}); at most {} per batch",
+            req.items.len(), MAX_BATCH_CONVERSIONS
+        )));
+    }
+
+    let mut quotes: HashMap<(String, String, Option<NaiveDate>), Result<ExchangeRate, String>> = HashMap::new();
+    let mut conversions = ConversionTrail::default();
+    let mut results = Vec::with_capacity(req.items.len());
+    let mut historical_lookups = 0;
+    for item in req.items {
+        let key = (item.from.to_uppercase(), item.to.to_uppercase(), item.date);
+        if !quotes.contains_key(&key) {
+            let quote = match item.date {
+                Some(_) if historical_lookups >= MAX_BATCH_HISTORICAL_LOOKUPS => Err(AppError::BadRequest(format!(
+                    "Too many dated conversions; at most {} distinct pair/date lookups per batch",
+                    MAX_BATCH_HISTORICAL_LOOKUPS
+                ))),
+                Some(date) => {
+                    historical_lookups += 1;
+                    state.exchange_rate_service.get_historical_quote(&key.0, &key.1, date).await
+                }
+                None => state.exchange_rate_service.get_quote(&key.0, &key.1).await,
+            };
+            if let Ok(quote) = &quote {
+                conversions.record(quote);
+            }
+            quotes.insert(key.clone(), quote.map_err(|e| e.to_string()));
+        }
+
+        let (converted, rate, error) = match &quotes[&key] {
+            Ok(quote) => (Some(item.amount * quote.rate), Some(quote.clone()), None),
+            Err(e) => (None, None, Some(e.clone())),
+        };
+        results.push(ConvertResult {
+            amount: item.amount,
+            from: key.0,
+            to: key.1,
+            date: item.date,
+            converted,
+            rate,
+            error,
+        });
+    }
+
+    Ok(Json(BatchConvertResponse {
+        failed: results.iter().filter(|r| r.error.is_some()).count(),
+        results,
+        conversions: conversions.into_vec(),
+    }))
+}
+
 /// Clear exchange rate cache
 pub async fn clear_exchange_rate_cache(
     State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    let admin_id = super::users::extract_admin_user_id(&state, &headers)?;
     state.exchange_rate_service.clear_cache().await;
+    tracing::info!("🧹 Exchange rate cache cleared by admin {}", admin_id);
     Ok(Json(serde_json::json!({
         "message": "Exchange rate cache cleared"
     })))
@@ -0,0 +1,126 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use crate::error::AppError;
+use crate::extract::Path;
+use crate::handlers::transactions::{transactions_csv, ListTransactionsQuery};
+use crate::services::exports::{ExportDownload, ExportFile, ExportJob, ExportKind, ExportProgress};
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateExportRequest {
+    pub kind: ExportKind,
+}
+
+/// POST /api/jobs/exports - Start a background export; poll the returned job for the download link
+pub async fn create_export(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateExportRequest>,
+) -> Result<(StatusCode, Json<ExportJob>), AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let work_state = state.clone();
+    let work_user = user_id.clone();
+    let job = state.export_service.start_job(&user_id, req.kind, move |progress| async move {
+        match req.kind {
+            ExportKind::TransactionsCsv => export_transactions_csv(&work_state, &work_user).await,
+            ExportKind::FullHistoryJson => export_full_history(&work_state, &work_user, &progress).await,
+        }
+    }).await;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn export_transactions_csv(state: &AppState, user_id: &str) -> Result<ExportFile, AppError> {
+    let (csv, rows) = transactions_csv(state, user_id, &ListTransactionsQuery::default()).await?;
+    Ok(ExportFile {
+        file_name: format!("transactions-{}.csv", Utc::now().format("%Y%m%d")),
+        content_type: "text/csv; charset=utf-8".to_string(),
+        bytes: csv.into_bytes(),
+        rows,
+    })
+}
+
+async fn export_full_history(state: &AppState, user_id: &str, progress: &ExportProgress) -> Result<ExportFile, AppError> {
+    let accounts = state.db.list_accounts(user_id).await?;
+    progress.set(10).await;
+    let transactions = state.db.list_transactions(user_id).await?;
+    progress.set(40).await;
+    let snapshots: Vec<serde_json::Value> = state.db
+        .list_all_records("portfolio_snapshots", Some(format!("user_id='{}'", user_id)))
+        .await?;
+    progress.set(80).await;
+
+    let rows = accounts.len() + transactions.len() + snapshots.len();
+    let document = serde_json::json!({
+        "exported_at": Utc::now(),
+        "accounts": accounts,
+        "transactions": transactions,
+        "snapshots": snapshots,
+    });
+    let bytes = serde_json::to_vec_pretty(&document)
+        .map_err(|e| AppError::Internal(format!("Could not serialize export: {}", e)))?;
+    Ok(ExportFile {
+        file_name: format!("portfolio-history-{}.json", Utc::now().format("%Y%m%d")),
+        content_type: "application/json".to_string(),
+        bytes,
+        rows,
+    })
+}
+
+/// GET /api/jobs/exports - The user's export jobs, newest first
+pub async fn list_exports(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ExportJob>>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    Ok(Json(state.export_service.list_jobs(&user_id).await))
+}
+
+/// GET /api/jobs/exports/:id - Progress of an export job
+pub async fn get_export(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ExportJob>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    state.export_service.get_job(&user_id, &id).await
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Export job {} not found", id)))
+}
+
+/// GET /api/exports/download/:token - Finished export file; the token is the credential
+pub async fn download_export(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Response, AppError> {
+    match state.export_service.download(&token).await? {
+        ExportDownload::File { file_name, content_type, bytes } => Ok((
+            [
+                (header::CONTENT_TYPE, content_type),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name)),
+            ],
+            bytes,
+        ).into_response()),
+        ExportDownload::Redirect(url) => Ok(Redirect::temporary(&url).into_response()),
+    }
+}
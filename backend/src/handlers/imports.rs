@@ -0,0 +1,415 @@
+use axum::{
+    body::Body,
+    extract::{FromRequest, Multipart, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use std::collections::HashMap;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::extract::{Path, Query};
+use crate::models::{AssetType, CreateTransactionRequest};
+use crate::body_limit::{next_chunk, BodyLimit};
+use crate::handlers::accounts::ensure_account_open;
+use crate::services::broker_import::{parse_statement, BrokerFormat, StatementRow};
+use crate::services::crypto_import::{
+    parse_export, to_transactions, Amount, CsvReader, ExportParser, ImportFormat, ImportRow, SkippedRow,
+};
+use crate::services::import_reconcile::{import_tag, new_import_id, reconcile, ReconcileReport, ReconcileRequest};
+use crate::services::market_rules::check_transaction;
+use crate::services::price_service::HistoryEntry;
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+// Bounds the rows (not bytes) held in memory while planning transactions
+const MAX_IMPORT_ROWS: usize = 50_000;
+
+/// Imports may only target the user's own open accounts
+pub(crate) async fn ensure_import_account(state: &AppState, user_id: &str, account_id: &Option<String>) -> Result<(), AppError> {
+    let Some(account_id) = account_id else {
+        return Ok(());
+    };
+    let accounts = state.db.list_accounts(user_id).await?;
+    if !accounts.iter().any(|a| &a.id == account_id) {
+        return Err(AppError::NotFound(format!("Account {} not found", account_id)));
+    }
+    ensure_account_open(state, Some(account_id)).await
+}
+
+/// Options for a streamed `text/csv` upload, passed as query parameters
+#[derive(Debug, Deserialize)]
+pub struct CryptoImportQuery {
+    pub format: Option<ImportFormat>,
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// JSON upload with the CSV inline (bounded by the bulk body limit)
+#[derive(Debug, Deserialize)]
+pub struct CryptoImportRequest {
+    /// Raw CSV export
+    pub csv: String,
+    /// Detected from the header row when omitted
+    pub format: Option<ImportFormat>,
+    pub account_id: Option<String>,
+    /// Parse and map only, without creating transactions
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CryptoImportResponse {
+    /// Identifies the created transactions for `/import/:id/reconcile` (not set for dry runs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import_id: Option<String>,
+    pub format: ImportFormat,
+    pub dry_run: bool,
+    pub rows_parsed: usize,
+    pub transactions_created: usize,
+    /// Rows valued from market history because the export had no fiat value
+    pub rows_priced_from_history: usize,
+    pub skipped: Vec<SkippedRow>,
+    /// Market rule notes (lot sizes, minimum order value); rows are still imported
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transactions: Vec<CreateTransactionRequest>,
+}
+
+/// USD close of a crypto on the row's date, from a history fetched once per symbol
+async fn historical_usd_value(
+    state: &AppState,
+    histories: &mut HashMap<String, Vec<HistoryEntry>>,
+    asset: &Amount,
+    date: &str,
+    days: u32,
+) -> Option<Amount> {
+    if !histories.contains_key(&asset.currency) {
+        let history = state.price_service
+            .get_price_history(&asset.currency, &AssetType::Crypto, None, days)
+            .await
+            .unwrap_or_default();
+        histories.insert(asset.currency.clone(), history);
+    }
+    histories.get(&asset.currency)?
+        .iter()
+        .filter(|h| h.date.as_str() <= date)
+        .max_by(|a, b| a.date.cmp(&b.date))
+        .map(|h| Amount { quantity: h.price * asset.quantity, currency: "USD".to_string() })
+}
+
+/// Parse a streamed CSV body chunk by chunk, so only normalized rows are held in memory
+async fn parse_streamed_csv(
+    mut body: Body,
+    format: Option<ImportFormat>,
+    limit: &BodyLimit,
+) -> Result<(ImportFormat, Vec<ImportRow>, Vec<SkippedRow>), AppError> {
+    let mut reader = CsvReader::default();
+    let mut parser = ExportParser::new(format);
+    let mut records = Vec::new();
+    // Bytes of a UTF-8 character split across chunks
+    let mut carry: Vec<u8> = Vec::new();
+    let mut read = 0;
+
+    while let Some(chunk) = next_chunk(&mut body, &mut read, limit).await? {
+        carry.extend_from_slice(&chunk);
+        let valid = match std::str::from_utf8(&carry) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(AppError::BadRequest("CSV upload is not valid UTF-8".to_string())),
+        };
+        let text = std::str::from_utf8(&carry[..valid]).unwrap_or_default();
+        reader.push(text, &mut records);
+        carry.drain(..valid);
+
+        for record in records.drain(..) {
+            parser.push_record(&record)?;
+        }
+        if parser.row_count() > MAX_IMPORT_ROWS {
+            return Err(AppError::BadRequest(format!(
+                "Import exceeds limit ({} rows), split the export into smaller files",
+                MAX_IMPORT_ROWS
+            )));
+        }
+    }
+    if !carry.is_empty() {
+        return Err(AppError::BadRequest("CSV upload is not valid UTF-8".to_string()));
+    }
+
+    reader.finish(&mut records);
+    for record in &records {
+        parser.push_record(record)?;
+    }
+    tracing::info!("📥 Streamed {} bytes of CSV", read);
+    parser.finish()
+}
+
+/// POST /api/import/crypto - Import a Koinly or CoinTracking CSV export.
+/// Send the file as `text/csv` (streamed, options in the query string) or as JSON with the CSV inline.
+pub async fn import_crypto_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<CryptoImportQuery>,
+    body: Body,
+) -> Result<Json<CryptoImportResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+
+    let is_json = headers.get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+
+    let (account_id, dry_run, (format, rows, mut skipped)) = if is_json {
+        // Inline CSV is buffered, so it gets the smaller bulk limit
+        let limit = BodyLimit::bulk(&state.config);
+        let bytes = axum::body::to_bytes(body, limit.max_bytes)
+            .await
+            .map_err(|_| limit.too_large(None))?;
+        let req: CryptoImportRequest = serde_json::from_slice(&bytes)?;
+        let parsed = parse_export(&req.csv, req.format)?;
+        (req.account_id, req.dry_run, parsed)
+    } else {
+        let limit = BodyLimit::import(&state.config);
+        let parsed = parse_streamed_csv(body, query.format, &limit).await?;
+        (query.account_id, query.dry_run, parsed)
+    };
+
+    ensure_import_account(&state, &user_id, &account_id).await?;
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(AppError::BadRequest(format!("Import exceeds limit ({} rows)", MAX_IMPORT_ROWS)));
+    }
+    tracing::info!("📥 Importing {} {} rows for {}", rows.len(), format.as_str(), user_id);
+
+    let oldest_days = rows.iter()
+        .map(|r| (Utc::now() - r.timestamp).num_days().max(1) as u32 + 1)
+        .max()
+        .unwrap_or(1);
+    let mut histories = HashMap::new();
+    let mut priced_from_history = 0;
+    let mut planned = Vec::new();
+
+    for row in &rows {
+        let mut value = row.known_value();
+        if value.is_none() && row.needs_value() {
+            if let Some(asset) = row.valuation_symbol() {
+                let date = row.timestamp.format("%Y-%m-%d").to_string();
+                value = historical_usd_value(&state, &mut histories, asset, &date, oldest_days).await;
+                if value.is_some() {
+                    priced_from_history += 1;
+                }
+            }
+        }
+
+        match to_transactions(row, format, value.as_ref(), &account_id) {
+            Ok(txs) => planned.extend(txs),
+            Err(reason) => skipped.push(SkippedRow { row: row.row, reason }),
+        }
+    }
+    skipped.sort_by_key(|s| s.row);
+
+    let mut warnings = Vec::new();
+    for tx in &mut planned {
+        for violation in check_transaction(tx) {
+            warnings.push(format!("{} {} {}: {}", tx.symbol, tx.quantity, tx.timestamp.format("%Y-%m-%d"), violation.message));
+        }
+    }
+
+    let mut created = 0;
+    let import_id = (!dry_run).then(new_import_id);
+    if let Some(import_id) = &import_id {
+        let tag = import_tag(import_id);
+        for tx in &mut planned {
+            tx.tags.push(tag.clone());
+        }
+        for tx in &planned {
+            match state.db.create_transaction(tx.clone(), &user_id).await {
+                Ok(_) => created += 1,
+                Err(e) => skipped.push(SkippedRow {
+                    row: 0,
+                    reason: format!("{} {} {}: {}", tx.symbol, tx.quantity, tx.timestamp, e),
+                }),
+            }
+        }
+        tracing::info!("✅ Imported {} transactions as {} ({} rows skipped)", created, import_id, skipped.len());
+    }
+
+    Ok(Json(CryptoImportResponse {
+        import_id,
+        format,
+        dry_run,
+        rows_parsed: rows.len(),
+        transactions_created: created,
+        rows_priced_from_history: priced_from_history,
+        skipped,
+        warnings,
+        transactions: if dry_run { planned } else { Vec::new() },
+    }))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementImportMode {
+    /// Map and validate only
+    #[default]
+    Preview,
+    /// Create the transactions
+    Commit,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatementImportResponse {
+    /// Tags the created transactions, see `/import/:id/reconcile` (commit only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import_id: Option<String>,
+    pub format: BrokerFormat,
+    pub mode: StatementImportMode,
+    pub rows_parsed: usize,
+    pub valid_rows: usize,
+    pub invalid_rows: usize,
+    pub transactions_created: usize,
+    /// Every row in preview; rows with errors or warnings after a commit
+    pub rows: Vec<StatementRow>,
+}
+
+/// Form fields of a statement upload
+#[derive(Default)]
+struct StatementUpload {
+    csv: Option<String>,
+    format: Option<BrokerFormat>,
+    account_id: Option<String>,
+    mode: StatementImportMode,
+    skip_invalid: bool,
+}
+
+async fn read_statement_upload(mut multipart: Multipart) -> Result<StatementUpload, AppError> {
+    let invalid = |name: &str, value: &str| AppError::BadRequest(format!("Invalid {}: {}", name, value));
+    let mut upload = StatementUpload::default();
+    while let Some(field) = multipart.next_field().await
+        .map_err(|e| AppError::BadRequest(format!("Invalid multipart upload: {}", e)))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        let value = field.text().await
+            .map_err(|e| AppError::BadRequest(format!("Could not read field {}: {}", name, e)))?;
+        let trimmed = value.trim();
+        match name.as_str() {
+            "file" => upload.csv = Some(value),
+            "format" if !trimmed.is_empty() => {
+                upload.format = Some(serde_json::from_value(serde_json::json!(trimmed)).map_err(|_| invalid("format", trimmed))?);
+            }
+            "account_id" if !trimmed.is_empty() => upload.account_id = Some(trimmed.to_string()),
+            "mode" if !trimmed.is_empty() => {
+                upload.mode = serde_json::from_value(serde_json::json!(trimmed)).map_err(|_| invalid("mode", trimmed))?;
+            }
+            "skip_invalid" => upload.skip_invalid = matches!(trimmed, "true" | "1" | "on"),
+            _ => {}
+        }
+    }
+    Ok(upload)
+}
+
+/// POST /api/transactions/import - Upload a broker statement as multipart/form-data (`file`, plus
+/// optional `format`, `account_id`, `mode` = preview|commit and `skip_invalid`). Preview maps and
+/// validates every row; commit creates the transactions, and refuses with 422 while any row has
+/// errors unless `skip_invalid` is set.
+pub async fn import_broker_statement(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    request: Request,
+) -> Result<(StatusCode, Json<StatementImportResponse>), AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let multipart = Multipart::from_request(request, &state).await
+        .map_err(|e| AppError::BadRequest(e.body_text()))?;
+    let upload = read_statement_upload(multipart).await?;
+    let csv = upload.csv
+        .ok_or_else(|| AppError::BadRequest("Missing file field with the statement CSV".to_string()))?;
+    ensure_import_account(&state, &user_id, &upload.account_id).await?;
+
+    let (format, mut rows) = parse_statement(&csv, upload.format, &upload.account_id, MAX_IMPORT_ROWS)?;
+    let valid_rows = rows.iter().filter(|r| r.is_valid()).count();
+    let invalid_rows = rows.len() - valid_rows;
+    tracing::info!(
+        "📥 {} statement from {}: {} rows, {} invalid ({:?})",
+        format.as_str(), user_id, rows.len(), invalid_rows, upload.mode
+    );
+
+    let mut response = StatementImportResponse {
+        import_id: None,
+        format,
+        mode: upload.mode,
+        rows_parsed: rows.len(),
+        valid_rows,
+        invalid_rows,
+        transactions_created: 0,
+        rows: Vec::new(),
+    };
+    if upload.mode == StatementImportMode::Preview {
+        response.rows = rows;
+        return Ok((StatusCode::OK, Json(response)));
+    }
+    if invalid_rows > 0 && !upload.skip_invalid {
+        response.rows = rows.into_iter().filter(|r| !r.is_valid()).collect();
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(response)));
+    }
+
+    let import_id = new_import_id();
+    let tag = import_tag(&import_id);
+    for row in &mut rows {
+        let Some(tx) = row.transaction.as_mut() else { continue };
+        tx.tags.push(tag.clone());
+        match state.db.create_transaction(tx.clone(), &user_id).await {
+            Ok(_) => response.transactions_created += 1,
+            Err(e) => row.errors.push(format!("could not create transaction: {}", e)),
+        }
+    }
+    tracing::info!("✅ Imported {} transactions as {}", response.transactions_created, import_id);
+    response.import_id = Some(import_id);
+    response.rows = rows.into_iter().filter(|r| !r.errors.is_empty() || !r.warnings.is_empty()).collect();
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// POST /api/import/:id/reconcile - Compare the broker's reported end-of-period holdings and
+/// cash balances with holdings computed from the import's account, flagging imported
+/// transactions that may explain each difference
+pub async fn reconcile_import(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(import_id): Path<String>,
+    Json(req): Json<ReconcileRequest>,
+) -> Result<Json<ReconcileReport>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    if req.holdings.is_empty() && req.balances.is_empty() {
+        return Err(AppError::BadRequest("Provide the statement's holdings and/or balances".to_string()));
+    }
+
+    let transactions = state.db.list_transactions(&user_id).await?;
+    let tag = import_tag(&import_id);
+    let imported: Vec<_> = transactions.iter().filter(|tx| tx.tags.contains(&tag)).collect();
+    let Some(last) = imported.iter().map(|tx| tx.timestamp).max() else {
+        return Err(AppError::NotFound(format!("Import {} not found", import_id)));
+    };
+    let account_id = imported.iter()
+        .find_map(|tx| tx.account_id.clone())
+        .filter(|id| !id.is_empty());
+
+    let report = reconcile(&import_id, account_id, req.as_of.unwrap_or(last), &transactions, &req);
+    tracing::info!(
+        "🧾 Reconciled import {} for {}: {} matched, {} differences",
+        import_id, user_id, report.matched, report.differences
+    );
+    Ok(Json(report))
+}
@@ -0,0 +1,121 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::extract::Query;
+use crate::handlers::portfolio::{get_portfolio, PortfolioQuery};
+use crate::models::AssetType;
+use crate::services::exchange_rate::{ConversionTrail, ExchangeRate};
+use crate::services::insights::{generate_insights, Insight, InsightHolding, InsightThresholds};
+use crate::AppState;
+
+/// Threshold overrides; anything omitted uses the engine defaults
+#[derive(Debug, Deserialize)]
+pub struct InsightsQuery {
+    pub concentration_percent: Option<f64>,
+    pub fee_drag_percent: Option<f64>,
+    pub idle_cash_percent: Option<f64>,
+    pub drawdown_percent: Option<f64>,
+    pub stale_price_hours: Option<f64>,
+    /// Defaults to LIQUIDITY_WARNING_PERCENT
+    pub daily_volume_percent: Option<f64>,
+    pub base_currency: Option<String>,
+}
+
+impl InsightsQuery {
+    fn thresholds(&self, liquidity_warning_percent: f64) -> Result<InsightThresholds, AppError> {
+        let defaults = InsightThresholds::default();
+        let pick = |name: &str, value: Option<f64>, default: f64| match value {
+            Some(v) if !v.is_finite() || v <= 0.0 => {
+                Err(AppError::BadRequest(format!("{} must be a positive number", name)))
+            }
+            Some(v) => Ok(v),
+            None => Ok(default),
+        };
+        Ok(InsightThresholds {
+            concentration_percent: pick("concentration_percent", self.concentration_percent, defaults.concentration_percent)?,
+            fee_drag_percent: pick("fee_drag_percent", self.fee_drag_percent, defaults.fee_drag_percent)?,
+            idle_cash_percent: pick("idle_cash_percent", self.idle_cash_percent, defaults.idle_cash_percent)?,
+            drawdown_percent: pick("drawdown_percent", self.drawdown_percent, defaults.drawdown_percent)?,
+            stale_price_hours: pick("stale_price_hours", self.stale_price_hours, defaults.stale_price_hours)?,
+            daily_volume_percent: pick("daily_volume_percent", self.daily_volume_percent, liquidity_warning_percent)?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct InsightsResponse {
+    pub insights: Vec<Insight>,
+    pub thresholds: InsightThresholds,
+    pub base_currency: String,
+    pub generated_at: DateTime<Utc>,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// GET /api/insights - Rule-based observations about the portfolio (no external AI involved)
+pub async fn get_insights(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<InsightsQuery>,
+) -> Result<Json<InsightsResponse>, AppError> {
+    let thresholds = query.thresholds(state.config.liquidity_warning_percent)?;
+    let base_currency = query.base_currency.as_deref()
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(PortfolioQuery::default())).await?;
+
+    let now = Utc::now();
+    let mut conversions = ConversionTrail::from(portfolio.conversions.clone());
+    let mut holdings = Vec::new();
+    for asset in &portfolio.assets {
+        let fx = state.exchange_rate_service.get_rate_recorded(&asset.currency, &base_currency, &mut conversions).await?;
+        // Served from the price cache populated by get_portfolio, with its original fetch time
+        let price_age_hours = if asset.asset_type == AssetType::Cash {
+            Some(0.0)
+        } else {
+            state.price_service
+                .get_price(&asset.symbol, &asset.asset_type, asset.market.as_ref())
+                .await
+                .ok()
+                .map(|p| (now - p.updated_at).num_minutes().max(0) as f64 / 60.0)
+        };
+
+        // The portfolio only sizes against cached volumes; fetch any it lacked
+        let daily_volume_percent = match asset.daily_volume_percent {
+            Some(percent) => Some(percent),
+            None => state.price_service
+                .average_daily_volume(&asset.symbol, &asset.asset_type)
+                .await
+                .ok()
+                .flatten()
+                .filter(|volume| *volume > 0.0)
+                .map(|volume| asset.quantity.abs() / volume * 100.0),
+        };
+
+        holdings.push(InsightHolding {
+            symbol: asset.symbol.clone(),
+            asset_type: asset.asset_type.clone(),
+            value: asset.current_value * fx,
+            cost: asset.total_cost * fx,
+            fees: asset.total_fees * fx,
+            pnl_percent: asset.unrealized_pnl_percent,
+            price_age_hours,
+            daily_volume_percent,
+        });
+    }
+
+    let insights = generate_insights(&holdings, &thresholds);
+    tracing::info!("💡 Generated {} insights from {} holdings", insights.len(), holdings.len());
+
+    Ok(Json(InsightsResponse {
+        insights,
+        thresholds,
+        base_currency,
+        generated_at: now,
+        conversions: conversions.into_vec(),
+    }))
+}
@@ -0,0 +1,127 @@
+use axum::{extract::State, http::HeaderMap, Json};
+use serde::Deserialize;
+use crate::error::AppError;
+use crate::extract::{Path, Query};
+use crate::handlers::imports::ensure_import_account;
+use crate::models::{CreateExchangeKeyRequest, ExchangeKey, StagedTrade, StagedTradeStatus, StagedTradesRequest};
+use crate::services::balance_sync::AccountBalances;
+use crate::services::exchange_sync::{StagedImportReport, SyncReport, SyncRequest};
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+/// GET /api/integrations/exchange-keys - The user's exchange API keys (secrets are never returned)
+pub async fn list_exchange_keys(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ExchangeKey>>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    Ok(Json(state.balance_sync.store().list(&user_id).await?))
+}
+
+/// POST /api/integrations/exchange-keys - Register a Binance or Bitkub API key. The key is
+/// tried against the exchange first and stored encrypted; read-only keys are enough.
+pub async fn create_exchange_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateExchangeKeyRequest>,
+) -> Result<Json<ExchangeKey>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let key = state.balance_sync.register(&user_id, req).await?;
+    tracing::info!("🔑 User {} added a {} API key", user_id, key.exchange);
+    Ok(Json(key))
+}
+
+/// DELETE /api/integrations/exchange-keys/:id
+pub async fn delete_exchange_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    state.balance_sync.store().remove(&user_id, &id).await?;
+    Ok(Json(serde_json::json!({
+        "message": "Exchange key deleted successfully",
+        "id": id
+    })))
+}
+
+/// GET /api/integrations/exchange-keys/:id/balances - Current wallet on the exchange, valued at
+/// cached prices
+pub async fn get_exchange_balances(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<AccountBalances>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    Ok(Json(state.balance_sync.balances(&user_id, &id).await?))
+}
+
+/// POST /api/integrations/exchange-keys/:id/sync - Stage the Binance trades made since the last
+/// sync for review. Pairs default to every listed pair of an asset in the wallet or traded on
+/// Binance before; `account_id` books the transactions to one of the user's accounts.
+pub async fn sync_exchange_trades(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<SyncRequest>,
+) -> Result<Json<SyncReport>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    ensure_import_account(&state, &user_id, &req.account_id).await?;
+    Ok(Json(state.exchange_sync.sync(&user_id, &id, req).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StagedTradesQuery {
+    pub status: Option<StagedTradeStatus>,
+}
+
+/// GET /api/integrations/exchange-keys/:id/staged - Synced trades, oldest first, with the
+/// transactions each becomes (filter with ?status=pending)
+pub async fn list_staged_trades(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<StagedTradesQuery>,
+) -> Result<Json<Vec<StagedTrade>>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    Ok(Json(state.exchange_sync.staged(&user_id, &id, query.status).await?))
+}
+
+/// POST /api/integrations/exchange-keys/:id/staged/import - Create the transactions of the given
+/// pending trades (all pending ones when `ids` is empty) and reconcile them with the wallet
+pub async fn import_staged_trades(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<StagedTradesRequest>,
+) -> Result<Json<StagedImportReport>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    Ok(Json(state.exchange_sync.import(&user_id, &id, &req.ids).await?))
+}
+
+/// POST /api/integrations/exchange-keys/:id/staged/dismiss - Leave pending trades out of the
+/// portfolio; they are not staged again by later syncs
+pub async fn dismiss_staged_trades(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<StagedTradesRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let dismissed = state.exchange_sync.dismiss(&user_id, &id, &req.ids).await?;
+    Ok(Json(serde_json::json!({ "dismissed": dismissed })))
+}
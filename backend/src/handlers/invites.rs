@@ -0,0 +1,77 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{Duration, Utc};
+use rand::Rng;
+use crate::AppState;
+use crate::error::AppError;
+use crate::extract::Path;
+use crate::models::{CreateInviteRequest, Invite, INVITES_COLLECTION};
+use super::users::extract_admin_user_id;
+
+/// Unambiguous characters only (no 0/O, 1/I/L) since codes are often typed in by hand
+const INVITE_CODE_CHARSET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const INVITE_CODE_LENGTH: usize = 12;
+
+fn generate_invite_code() -> String {
+    let mut rng = rand::rng();
+    (0..INVITE_CODE_LENGTH)
+        .map(|_| INVITE_CODE_CHARSET[rng.random_range(0..INVITE_CODE_CHARSET.len())] as char)
+        .collect()
+}
+
+/// GET /api/admin/invites - List invite codes, newest first (admin only)
+pub async fn list_invites(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Invite>>, AppError> {
+    extract_admin_user_id(&state, &headers)?;
+    let invites = state.db.list_records::<Invite>(INVITES_COLLECTION, None, "-created").await?;
+    Ok(Json(invites))
+}
+
+/// POST /api/admin/invites - Generate a single-use invite code (admin only)
+pub async fn create_invite(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateInviteRequest>,
+) -> Result<(StatusCode, Json<Invite>), AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+
+    let email = req.email.map(|e| e.trim().to_string()).filter(|e| !e.is_empty());
+    if email.as_deref().is_some_and(|e| !e.contains('@')) {
+        return Err(AppError::BadRequest("Invalid email format".to_string()));
+    }
+    let expires_at = match req.expires_in_days {
+        Some(days) if days <= 0 => {
+            return Err(AppError::BadRequest("expires_in_days must be positive".to_string()));
+        }
+        Some(days) => Some((Utc::now() + Duration::days(days)).to_rfc3339()),
+        None => None,
+    };
+
+    let body = serde_json::json!({
+        "code": generate_invite_code(),
+        "email": email.unwrap_or_default(),
+        "created_by": admin_id,
+        "expires_at": expires_at.unwrap_or_default(),
+    });
+    let invite: Invite = state.db.create_record(INVITES_COLLECTION, &body).await?;
+
+    tracing::info!("🎟️ Admin {} created invite {}", admin_id, invite.code);
+    Ok((StatusCode::CREATED, Json(invite)))
+}
+
+/// DELETE /api/admin/invites/:id - Revoke an invite code (admin only)
+pub async fn delete_invite(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    state.db.delete_record(INVITES_COLLECTION, &id).await?;
+    tracing::info!("🗑️ Admin {} revoked invite {}", admin_id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
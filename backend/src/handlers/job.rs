@@ -1,38 +1,94 @@
 use axum::{
-    extract::{Path, State},
+    extract::State,
+    http::HeaderMap,
     Json,
 };
+use serde::Deserialize;
 use serde_json::json;
 
 use crate::AppState;
 use crate::error::AppError;
-use crate::models::UpdateJobRequest;
+use crate::extract::{Path, Query};
+use crate::models::{CreateJobRequest, JobConfig, JobRun, JobRunsPage, JobStatus, SchedulerOverview, UpdateJobRequest};
+use crate::services::job_scheduler::JOB_TYPES;
+use super::users::{extract_admin_user_id, extract_ops_reader_id};
 
 /// List all jobs
 pub async fn list_jobs(
     State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    extract_ops_reader_id(&state, &headers)?;
     let jobs = state.job_scheduler.get_jobs().await;
     Ok(Json(json!({ "jobs": jobs })))
 }
 
+/// Scheduler health: loop heartbeat, overdue jobs, run drift and recent failures
+pub async fn jobs_overview(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<SchedulerOverview>, AppError> {
+    extract_ops_reader_id(&state, &headers)?;
+    Ok(Json(state.job_scheduler.overview().await))
+}
+
 /// Get a specific job
 pub async fn get_job(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    extract_ops_reader_id(&state, &headers)?;
     match state.job_scheduler.get_job(&id).await {
         Some(job) => Ok(Json(json!(job))),
         None => Err(AppError::NotFound(format!("Job {} not found", id))),
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct JobRunsQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    /// Only runs with this status ("running", "success" or "failed")
+    pub status: Option<JobStatus>,
+}
+
+/// GET /api/jobs/:id/runs - The job's run history, newest first
+pub async fn list_job_runs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<JobRunsQuery>,
+) -> Result<Json<JobRunsPage>, AppError> {
+    extract_ops_reader_id(&state, &headers)?;
+    if state.job_scheduler.get_job(&id).await.is_none() {
+        return Err(AppError::NotFound(format!("Job {} not found", id)));
+    }
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(50).clamp(1, 200);
+    Ok(Json(state.job_scheduler.list_runs(&id, query.status, page, per_page).await?))
+}
+
+/// GET /api/jobs/:id/runs/:run_id - One run with its full result or error
+pub async fn get_job_run(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, run_id)): Path<(String, String)>,
+) -> Result<Json<JobRun>, AppError> {
+    extract_ops_reader_id(&state, &headers)?;
+    Ok(Json(state.job_scheduler.get_run(&id, &run_id).await?))
+}
+
 /// Update job configuration
 pub async fn update_job(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
     Json(req): Json<UpdateJobRequest>,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    tracing::info!("🛠️ Admin {} updating job {}", admin_id, id);
+
     // Parse schedule_times from Value
     let schedule_times = match req.schedule_times {
         Some(serde_json::Value::Null) => Some(None), // Explicit null -> None (clear)
@@ -47,22 +103,119 @@ pub async fn update_job(
         None => None, // Missing -> ignore
     };
 
-    match state.job_scheduler.update_job(&id, req.interval_seconds, req.enabled, schedule_times).await {
+    match state.job_scheduler.update_job(&id, req.interval_seconds, req.enabled, schedule_times, req.catch_up).await {
         Ok(job) => Ok(Json(json!(job))),
         Err(e) => Err(AppError::Internal(e)),
     }
 }
 
-/// Run a job immediately
+/// Run a job immediately; 409 while a run of it is already in progress
 pub async fn run_job(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    match state.job_scheduler.run_job_now(&id).await {
-        Ok(result) => Ok(Json(json!({
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    tracing::info!("▶️ Admin {} running job {}", admin_id, id);
+
+    let result = state.job_scheduler.run_job_now(&id).await?;
+    Ok(Json(json!({
+        "success": true,
+        "result": result
+    })))
+}
+
+/// Create a job; it is scheduled right away
+pub async fn create_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateJobRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+
+    if req.name.trim().is_empty() {
+        return Err(AppError::BadRequest("Job name cannot be empty".to_string()));
+    }
+    if !JOB_TYPES.contains(&req.job_type.as_str()) {
+        return Err(AppError::BadRequest(format!(
+            "Unknown job type: {}. Must be one of: {}", req.job_type, JOB_TYPES.join(", ")
+        )));
+    }
+    if req.interval_seconds < 60 {
+        return Err(AppError::BadRequest("interval_seconds must be at least 60".to_string()));
+    }
+    if let Some(times) = &req.schedule_times {
+        if let Some(bad) = times.iter().find(|t| chrono::NaiveTime::parse_from_str(t, "%H:%M").is_err()) {
+            return Err(AppError::BadRequest(format!("Invalid schedule time '{}', expected HH:MM", bad)));
+        }
+    }
+    tracing::info!("🛠️ Admin {} creating {} job {}", admin_id, req.job_type, req.name);
+
+    let job = JobConfig {
+        name_en: req.name_en.unwrap_or_else(|| req.name.clone()),
+        name: req.name,
+        job_type: req.job_type,
+        interval_seconds: req.interval_seconds,
+        enabled: req.enabled,
+        schedule_times: req.schedule_times,
+        catch_up: req.catch_up,
+        ..Default::default()
+    };
+    match state.job_scheduler.create_job(job).await {
+        Ok(job) => Ok(Json(json!(job))),
+        Err(e) => Err(AppError::Internal(e)),
+    }
+}
+
+/// Delete a job and stop scheduling it
+pub async fn delete_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    if state.job_scheduler.get_job(&id).await.is_none() {
+        return Err(AppError::NotFound(format!("Job {} not found", id)));
+    }
+    tracing::info!("🗑️ Admin {} deleting job {}", admin_id, id);
+
+    match state.job_scheduler.delete_job(&id).await {
+        Ok(job) => Ok(Json(json!({
             "success": true,
-            "result": result
+            "deleted": job
         }))),
         Err(e) => Err(AppError::Internal(e)),
     }
 }
+
+/// Enable a disabled job or disable an enabled one
+pub async fn toggle_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    if state.job_scheduler.get_job(&id).await.is_none() {
+        return Err(AppError::NotFound(format!("Job {} not found", id)));
+    }
+    tracing::info!("🔀 Admin {} toggling job {}", admin_id, id);
+
+    match state.job_scheduler.toggle_job(&id).await {
+        Ok(job) => Ok(Json(json!(job))),
+        Err(e) => Err(AppError::Internal(e)),
+    }
+}
+
+/// Reload jobs from the database after editing them there directly
+pub async fn reload_jobs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    tracing::info!("🔄 Admin {} reloading jobs", admin_id);
+
+    match state.job_scheduler.reload_jobs().await {
+        Ok(jobs) => Ok(Json(json!({ "jobs": jobs }))),
+        Err(e) => Err(AppError::Internal(e)),
+    }
+}
@@ -0,0 +1,291 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::extract::{Path, Query};
+use crate::models::{
+    CreateLiabilityRequest, CreateLiabilityTransactionRequest, Liability, LiabilityTransaction,
+    LiabilityTransactionKind, UpdateLiabilityRequest,
+};
+use crate::services::exchange_rate::{ConversionTrail, ExchangeRate};
+use crate::AppState;
+use super::portfolio::{get_portfolio, PortfolioQuery};
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+/// Load a liability and make sure the caller owns it
+async fn get_owned_liability(state: &AppState, id: &str, user_id: &str) -> Result<Liability, AppError> {
+    let liability = state.db.get_liability(id).await?;
+    if liability.user_id != user_id {
+        return Err(AppError::NotFound(format!("Liability {} not found", id)));
+    }
+    Ok(liability)
+}
+
+fn validate_apr(apr_percent: f64) -> Result<(), AppError> {
+    if !(0.0..=100.0).contains(&apr_percent) {
+        return Err(AppError::BadRequest("apr_percent must be between 0 and 100".to_string()));
+    }
+    Ok(())
+}
+
+/// GET /api/liabilities - List the user's loans
+pub async fn list_liabilities(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Liability>>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let liabilities = state.db.list_liabilities(&user_id).await?;
+    Ok(Json(liabilities))
+}
+
+/// POST /api/liabilities - Record a new loan
+pub async fn create_liability(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateLiabilityRequest>,
+) -> Result<Json<Liability>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+
+    if req.name.trim().is_empty() {
+        return Err(AppError::BadRequest("Liability name cannot be empty".to_string()));
+    }
+    if req.currency.trim().is_empty() {
+        return Err(AppError::BadRequest("currency is required".to_string()));
+    }
+    if req.principal < 0.0 {
+        return Err(AppError::BadRequest("principal cannot be negative".to_string()));
+    }
+    validate_apr(req.apr_percent)?;
+
+    let liability = state.db.create_liability(req, &user_id).await?;
+    Ok(Json(liability))
+}
+
+/// GET /api/liabilities/:id
+pub async fn get_liability(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Liability>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let liability = get_owned_liability(&state, &id, &user_id).await?;
+    Ok(Json(liability))
+}
+
+/// PUT /api/liabilities/:id - Update loan terms (balance only changes via transactions)
+pub async fn update_liability(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateLiabilityRequest>,
+) -> Result<Json<Liability>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    get_owned_liability(&state, &id, &user_id).await?;
+
+    let mut body = serde_json::Map::new();
+    if let Some(name) = req.name {
+        if name.trim().is_empty() {
+            return Err(AppError::BadRequest("Liability name cannot be empty".to_string()));
+        }
+        body.insert("name".to_string(), serde_json::json!(name));
+    }
+    if let Some(liability_type) = req.liability_type {
+        body.insert("liability_type".to_string(), serde_json::json!(liability_type));
+    }
+    if let Some(apr_percent) = req.apr_percent {
+        validate_apr(apr_percent)?;
+        body.insert("apr_percent".to_string(), serde_json::json!(apr_percent));
+    }
+    if let Some(compounding) = req.compounding {
+        body.insert("compounding".to_string(), serde_json::json!(compounding));
+    }
+    if let Some(account_id) = req.account_id {
+        body.insert("account_id".to_string(), serde_json::json!(account_id));
+    }
+    if let Some(notes) = req.notes {
+        body.insert("notes".to_string(), serde_json::json!(notes));
+    }
+
+    let liability = state.db.update_liability(&id, serde_json::Value::Object(body)).await?;
+    Ok(Json(liability))
+}
+
+/// DELETE /api/liabilities/:id
+pub async fn delete_liability(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    get_owned_liability(&state, &id, &user_id).await?;
+    state.db.delete_liability(&id).await?;
+    Ok(Json(serde_json::json!({
+        "message": "Liability deleted successfully",
+        "id": id
+    })))
+}
+
+/// GET /api/liabilities/:id/transactions - Draws, repayments and capitalized interest
+pub async fn list_liability_transactions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<LiabilityTransaction>>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    get_owned_liability(&state, &id, &user_id).await?;
+    let transactions = state.db.list_liability_transactions(&id).await?;
+    Ok(Json(transactions))
+}
+
+/// POST /api/liabilities/:id/transactions - Record a draw or repayment and update the balance
+pub async fn create_liability_transaction(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<CreateLiabilityTransactionRequest>,
+) -> Result<Json<Liability>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let liability = get_owned_liability(&state, &id, &user_id).await?;
+
+    if req.amount <= 0.0 {
+        return Err(AppError::BadRequest("amount must be positive".to_string()));
+    }
+    let balance = match req.kind {
+        LiabilityTransactionKind::Draw => liability.balance + req.amount,
+        LiabilityTransactionKind::Repayment => {
+            if req.amount > liability.balance + 0.005 {
+                return Err(AppError::BadRequest(format!(
+                    "Repayment {} exceeds outstanding balance {}",
+                    req.amount, liability.balance
+                )));
+            }
+            (liability.balance - req.amount).max(0.0)
+        }
+        LiabilityTransactionKind::Interest => {
+            return Err(AppError::BadRequest("Interest is recorded by the accrual job".to_string()));
+        }
+    };
+
+    let tx = LiabilityTransaction {
+        id: String::new(),
+        liability_id: id.clone(),
+        user_id,
+        kind: req.kind,
+        amount: req.amount,
+        timestamp: req.timestamp,
+        notes: req.notes,
+    };
+    let created = state.db.create_liability_transaction(&tx).await?;
+
+    // Take the transaction back out if the balance can't follow, so the two stay in step
+    match state.db.update_liability(&id, serde_json::json!({ "balance": balance })).await {
+        Ok(updated) => Ok(Json(updated)),
+        Err(e) => {
+            if let Err(undo) = state.db.delete_record("liability_transactions", &created.id).await {
+                tracing::error!("❌ Liability {} transaction {} kept without its balance update: {}", id, created.id, undo);
+            }
+            Err(e)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NetWorthQuery {
+    pub base_currency: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetWorthLiability {
+    pub id: String,
+    pub name: String,
+    pub currency: String,
+    pub balance: f64,
+    pub balance_in_base: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetWorthResponse {
+    pub base_currency: String,
+    pub total_assets: f64,
+    pub total_liabilities: f64,
+    pub net_worth: f64,
+    /// Liabilities as a share of assets, in percent
+    pub leverage_percent: f64,
+    pub liabilities: Vec<NetWorthLiability>,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// GET /api/net-worth - Current asset value minus outstanding liabilities, in one currency
+pub async fn get_net_worth(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<NetWorthQuery>,
+) -> Result<Json<NetWorthResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let base_currency = query.base_currency
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+
+    let portfolio = get_portfolio(
+        State(state.clone()),
+        headers,
+        Query(PortfolioQuery::default()),
+    ).await?.0;
+
+    let mut conversions = ConversionTrail::from(portfolio.conversions.clone());
+    let mut total_assets = 0.0;
+    for asset in &portfolio.assets {
+        total_assets += asset.current_value * state.exchange_rate_service
+            .get_rate_recorded(&asset.currency, &base_currency, &mut conversions)
+            .await?;
+    }
+
+    let mut liabilities = Vec::new();
+    let mut total_liabilities = 0.0;
+    for liability in state.db.list_liabilities(&user_id).await? {
+        let balance_in_base = liability.balance * state.exchange_rate_service
+            .get_rate_recorded(&liability.currency, &base_currency, &mut conversions)
+            .await?;
+        total_liabilities += balance_in_base;
+        liabilities.push(NetWorthLiability {
+            id: liability.id,
+            name: liability.name,
+            currency: liability.currency,
+            balance: liability.balance,
+            balance_in_base,
+        });
+    }
+
+    let leverage_percent = if total_assets > 0.0 {
+        total_liabilities / total_assets * 100.0
+    } else {
+        0.0
+    };
+
+    Ok(Json(NetWorthResponse {
+        base_currency,
+        total_assets,
+        total_liabilities,
+        net_worth: total_assets - total_liabilities,
+        leverage_percent,
+        liabilities,
+        conversions: conversions.into_vec(),
+    }))
+}
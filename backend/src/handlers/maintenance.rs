@@ -0,0 +1,41 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use serde::Deserialize;
+use crate::AppState;
+use crate::error::AppError;
+use crate::extract::Query;
+use crate::services::orphans::{clean_orphans, OrphanReport};
+use super::users::extract_admin_user_id;
+
+#[derive(Debug, Deserialize)]
+pub struct OrphanCleanupQuery {
+    /// Defaults to ORPHAN_CLEANUP_DRY_RUN
+    pub dry_run: Option<bool>,
+}
+
+/// GET /api/admin/maintenance/orphans - Report orphaned records without deleting anything (admin only)
+pub async fn get_orphan_report(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<OrphanReport>, AppError> {
+    extract_admin_user_id(&state, &headers)?;
+    let report = clean_orphans(&state.db, true).await?;
+    Ok(Json(report))
+}
+
+/// POST /api/admin/maintenance/orphans/cleanup - Delete orphaned records now (admin only).
+/// Pass `?dry_run=false` to delete when the instance default is a dry run.
+pub async fn cleanup_orphans(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<OrphanCleanupQuery>,
+) -> Result<Json<OrphanReport>, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    let dry_run = query.dry_run.unwrap_or(state.config.orphan_cleanup_dry_run);
+    tracing::info!("🧹 Orphan cleanup requested by {} (dry run: {})", admin_id, dry_run);
+    let report = clean_orphans(&state.db, dry_run).await?;
+    Ok(Json(report))
+}
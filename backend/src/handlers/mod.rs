@@ -12,6 +12,27 @@ pub mod rate_limit;
 pub mod api_providers;
 pub mod seed;
 pub mod alerts;
+pub mod performance;
+pub mod liabilities;
+pub mod equity;
+pub mod imports;
+pub mod insights;
+pub mod custom_fields;
+pub mod maintenance;
+pub mod invites;
+pub mod exports;
+pub mod events;
+pub mod settings;
+pub mod price_stream;
+pub mod change_stream;
+pub mod orders;
+pub mod dividends;
+pub mod assets;
+pub mod integrations;
+pub mod analytics;
+pub mod status;
+pub mod dashboards;
+pub mod client_mode;
 
 pub use transactions::*;
 pub use portfolio::*;
@@ -27,4 +48,25 @@ pub use rate_limit::*;
 pub use api_providers::*;
 pub use seed::*;
 pub use alerts::*;
+pub use performance::*;
+pub use liabilities::*;
+pub use equity::*;
+pub use imports::*;
+pub use insights::*;
+pub use custom_fields::*;
+pub use maintenance::*;
+pub use invites::*;
+pub use exports::*;
+pub use events::*;
+pub use settings::*;
+pub use price_stream::*;
+pub use change_stream::*;
+pub use orders::*;
+pub use dividends::*;
+pub use assets::*;
+pub use integrations::*;
+pub use analytics::*;
+pub use status::*;
+pub use dashboards::*;
+pub use client_mode::*;
 
@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::extract::{Path, Query};
+use crate::handlers::accounts::ensure_account_open;
+use crate::models::{
+    CreateOrderRequest, CreateTransactionRequest, FillOrderRequest, Order, OrderStatus,
+    TradeAction, Transaction, UpdateOrderRequest, ORDERS_COLLECTION,
+};
+use crate::services::market_rules::check_transaction;
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+/// Load an order and make sure the caller owns it
+async fn get_owned_order(state: &AppState, id: &str, user_id: &str) -> Result<Order, AppError> {
+    let order: Order = state.db.get_record(ORDERS_COLLECTION, id).await?;
+    if order.user_id != user_id {
+        return Err(AppError::NotFound(format!("Order {} not found", id)));
+    }
+    Ok(order)
+}
+
+/// The open-order watcher keeps its own list; a failed reload only delays matching
+async fn reload_watcher(state: &AppState) {
+    if let Err(e) = state.order_watcher.reload().await {
+        tracing::warn!("⚠️ Failed to reload open orders: {}", e);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListOrdersQuery {
+    pub status: Option<OrderStatus>,
+}
+
+/// GET /api/orders - List the user's orders, newest first (filter with ?status=open)
+pub async fn list_orders(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListOrdersQuery>,
+) -> Result<Json<Vec<Order>>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let mut filter = format!("user_id='{}'", user_id);
+    if let Some(status) = query.status {
+        filter.push_str(&format!(" && status='{}'", serde_json::json!(status).as_str().unwrap_or_default()));
+    }
+    let orders = state.db.list_records(ORDERS_COLLECTION, Some(filter), "-created").await?;
+    Ok(Json(orders))
+}
+
+/// POST /api/orders - Record a limit or stop order placed at a broker
+pub async fn create_order(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateOrderRequest>,
+) -> Result<Json<Order>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+
+    if req.symbol.trim().is_empty() {
+        return Err(AppError::BadRequest("symbol is required".to_string()));
+    }
+    if req.quantity <= 0.0 {
+        return Err(AppError::BadRequest("Quantity must be positive".to_string()));
+    }
+    if req.price <= 0.0 {
+        return Err(AppError::BadRequest("Price must be positive".to_string()));
+    }
+    if matches!(req.action, TradeAction::Dividend | TradeAction::Deposit | TradeAction::Withdraw) {
+        return Err(AppError::BadRequest("Orders must be trades, not dividends or cash movements".to_string()));
+    }
+    ensure_account_open(&state, req.account_id.as_deref()).await?;
+
+    let body = serde_json::json!({
+        "user_id": user_id,
+        "symbol": req.symbol.trim().to_uppercase(),
+        "asset_type": req.asset_type,
+        "market": req.market,
+        "action": req.action,
+        "order_type": req.order_type,
+        "quantity": req.quantity,
+        "price": req.price,
+        "currency": req.currency.map(|c| c.to_uppercase()),
+        "account_id": req.account_id,
+        "notes": req.notes,
+        "status": OrderStatus::Open,
+    });
+    let order: Order = state.db.create_record(ORDERS_COLLECTION, &body).await?;
+    reload_watcher(&state).await;
+    Ok(Json(order))
+}
+
+/// GET /api/orders/:id
+pub async fn get_order(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Order>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let order = get_owned_order(&state, &id, &user_id).await?;
+    Ok(Json(order))
+}
+
+/// PUT /api/orders/:id - Amend an order, cancel it, or re-open a triggered one that did not fill
+pub async fn update_order(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateOrderRequest>,
+) -> Result<Json<Order>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let existing = get_owned_order(&state, &id, &user_id).await?;
+    if existing.status == OrderStatus::Filled {
+        return Err(AppError::Conflict(format!("Order {} is already filled", id)));
+    }
+
+    let mut body = serde_json::Map::new();
+    if let Some(order_type) = req.order_type {
+        body.insert("order_type".to_string(), serde_json::json!(order_type));
+    }
+    if let Some(quantity) = req.quantity {
+        if quantity <= 0.0 {
+            return Err(AppError::BadRequest("Quantity must be positive".to_string()));
+        }
+        body.insert("quantity".to_string(), serde_json::json!(quantity));
+    }
+    if let Some(price) = req.price {
+        if price <= 0.0 {
+            return Err(AppError::BadRequest("Price must be positive".to_string()));
+        }
+        body.insert("price".to_string(), serde_json::json!(price));
+    }
+    if let Some(account_id) = req.account_id {
+        ensure_account_open(&state, Some(&account_id)).await?;
+        body.insert("account_id".to_string(), serde_json::json!(account_id));
+    }
+    if let Some(notes) = req.notes {
+        body.insert("notes".to_string(), serde_json::json!(notes));
+    }
+    match req.status {
+        Some(OrderStatus::Open) => {
+            body.insert("status".to_string(), serde_json::json!(OrderStatus::Open));
+            body.insert("triggered_price".to_string(), serde_json::Value::Null);
+            body.insert("triggered_at".to_string(), serde_json::json!(""));
+        }
+        Some(OrderStatus::Cancelled) => {
+            body.insert("status".to_string(), serde_json::json!(OrderStatus::Cancelled));
+        }
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "status can only be set to open or cancelled; use /orders/:id/fill to book a fill".to_string(),
+            ));
+        }
+        None => {}
+    }
+
+    let order: Order = state.db.update_record(ORDERS_COLLECTION, &id, &serde_json::Value::Object(body)).await?;
+    reload_watcher(&state).await;
+    Ok(Json(order))
+}
+
+/// DELETE /api/orders/:id
+pub async fn delete_order(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    get_owned_order(&state, &id, &user_id).await?;
+    state.db.delete_record(ORDERS_COLLECTION, &id).await?;
+    reload_watcher(&state).await;
+    Ok(Json(serde_json::json!({
+        "message": "Order deleted successfully",
+        "id": id
+    })))
+}
+
+#[derive(Debug, Serialize)]
+pub struct FillOrderResponse {
+    pub order: Order,
+    pub transaction: Transaction,
+    /// Non-blocking market rule notes, e.g. odd-lot quantities
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// POST /api/orders/:id/fill - Book an order as a transaction and mark it filled.
+/// Price, quantity and time default to the order and its trigger. A partial fill
+/// lowers the order to the quantity still unfilled and re-arms it as open.
+pub async fn fill_order(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(fill): Json<FillOrderRequest>,
+) -> Result<Json<FillOrderResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    // Two fills of the same order can't both pass the status check
+    let Some(_filling) = state.order_watcher.claim_fill(&id) else {
+        return Err(AppError::Conflict(format!("Order {} is already being filled", id)));
+    };
+    let order = get_owned_order(&state, &id, &user_id).await?;
+    match order.status {
+        OrderStatus::Filled => return Err(AppError::Conflict(format!("Order {} is already filled", id))),
+        OrderStatus::Cancelled => return Err(AppError::Conflict(format!("Order {} is cancelled", id))),
+        OrderStatus::Open | OrderStatus::Triggered => {}
+    }
+
+    let quantity = fill.quantity.unwrap_or(order.quantity);
+    if quantity <= 0.0 || quantity > order.quantity {
+        return Err(AppError::BadRequest(format!("Filled quantity must be between 0 and {}", order.quantity)));
+    }
+    let price = fill.price.unwrap_or(order.price);
+    if price <= 0.0 {
+        return Err(AppError::BadRequest("Price must be positive".to_string()));
+    }
+    if fill.fees < 0.0 {
+        return Err(AppError::BadRequest("Fees cannot be negative".to_string()));
+    }
+    ensure_account_open(&state, order.account_id.as_deref()).await?;
+
+    let mut req = CreateTransactionRequest {
+        asset_type: order.asset_type.clone(),
+        symbol: order.symbol.clone(),
+        symbol_name: None,
+        action: order.action.clone(),
+        quantity,
+        price,
+        fees: fill.fees,
+        network_fee: 0.0,
+        timestamp: fill.timestamp.or(order.triggered_at).unwrap_or_else(Utc::now),
+        market: order.market.clone(),
+        currency: order.currency.clone(),
+        notes: order.notes.clone(),
+        account_id: order.account_id.clone(),
+        tags: Vec::new(),
+        leverage: None,
+        initial_margin: None,
+        unit: None,
+        custom_fields: BTreeMap::new(),
+    };
+    let (blocking, warnings): (Vec<_>, Vec<_>) = check_transaction(&mut req).into_iter().partition(|v| v.blocking);
+    if !blocking.is_empty() {
+        let messages: Vec<String> = blocking.into_iter().map(|v| v.message).collect();
+        return Err(AppError::BadRequest(messages.join("; ")));
+    }
+
+    // Claim the quantity before booking it, so a failed update can't leave a booked fill open
+    let remaining = order.quantity - quantity;
+    let claim = match remaining > 1e-9 {
+        true => serde_json::json!({
+            "quantity": remaining,
+            "status": OrderStatus::Open,
+            "triggered_price": null,
+            "triggered_at": "",
+        }),
+        false => serde_json::json!({ "status": OrderStatus::Filled }),
+    };
+    let claimed: Order = state.db.update_record(ORDERS_COLLECTION, &id, &claim).await?;
+    let transaction = match state.db.create_transaction(req, &user_id).await {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            let undo = serde_json::json!({
+                "status": order.status,
+                "quantity": order.quantity,
+                "triggered_price": order.triggered_price,
+                "triggered_at": order.triggered_at.map(|at| at.to_rfc3339()).unwrap_or_default(),
+            });
+            if let Err(undo) = state.db.update_record::<Order>(ORDERS_COLLECTION, &id, &undo).await {
+                tracing::error!("❌ Order {} claimed without its transaction: {}", id, undo);
+            }
+            reload_watcher(&state).await;
+            return Err(e);
+        }
+    };
+    let mut transaction_ids = order.transaction_ids.clone();
+    transaction_ids.push(transaction.id.clone());
+    let order = match state.db
+        .update_record(ORDERS_COLLECTION, &id, &serde_json::json!({ "transaction_ids": transaction_ids }))
+        .await
+    {
+        Ok(order) => order,
+        Err(e) => {
+            tracing::warn!("⚠️ Order {} filled as transaction {} but not linked to it: {}", id, transaction.id, e);
+            claimed
+        }
+    };
+    reload_watcher(&state).await;
+    tracing::info!("✅ Order {} filled as transaction {}", id, transaction.id);
+
+    Ok(Json(FillOrderResponse {
+        order,
+        transaction,
+        warnings: warnings.into_iter().map(|v| v.message).collect(),
+    }))
+}
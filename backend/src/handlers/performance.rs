@@ -0,0 +1,730 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::error::AppError;
+use crate::extract::Query;
+use crate::models::{AssetType, Market, TradeAction};
+use crate::services::benchmarks;
+use crate::services::dividends::{self, DripModel};
+use crate::services::price_history::price_as_of;
+use crate::services::valuation::{canonical_currency, same_currency};
+use crate::services::exchange_rate::{ConversionTrail, ExchangeRate};
+use crate::utils::stats::{risk_stats, RiskStats};
+use crate::AppState;
+
+/// Which return figures to report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReturnView {
+    /// Actual returns in the base currency, including FX moves
+    #[default]
+    Unhedged,
+    /// Returns with FX held constant at the purchase rates
+    Hedged,
+    /// Both views side by side, plus the FX contribution
+    Compare,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PerformanceQuery {
+    #[serde(default)]
+    pub view: ReturnView,
+    pub base_currency: Option<String>,
+    /// Also model dividends reinvested at the ex-date price (DRIP) next to taking them as cash
+    #[serde(default)]
+    pub drip: bool,
+}
+
+/// Return figures for a position or the whole portfolio (amounts in base currency)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReturnBreakdown {
+    pub cost_basis: f64,
+    pub current_value: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unhedged_pnl: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unhedged_return_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hedged_pnl: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hedged_return_percent: Option<f64>,
+    /// Part of the unhedged P&L caused by currency moves
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fx_pnl: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fx_return_percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssetPerformance {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+    pub currency: String,
+    pub quantity: f64,
+    pub cost_basis_local: f64,
+    pub current_value_local: f64,
+    /// Average FX rate (base per local unit) paid when the position was built
+    pub purchase_fx_rate: f64,
+    pub current_fx_rate: f64,
+    /// True if a historical rate was unavailable and the current rate was used instead
+    pub fx_estimated: bool,
+    #[serde(flatten)]
+    pub returns: ReturnBreakdown,
+}
+
+/// One account's share of the portfolio return. Weight is the account's share of total
+/// cost basis, so contributions add up to the summary return.
+#[derive(Debug, Serialize)]
+pub struct AccountContribution {
+    /// "unassigned" for transactions without an account
+    pub account_id: String,
+    pub account_name: String,
+    pub weight_percent: f64,
+    /// weight × the account's own return (hedged return for the hedged view, unhedged otherwise)
+    pub contribution_percent: f64,
+    #[serde(flatten)]
+    pub returns: ReturnBreakdown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PerformanceResponse {
+    pub base_currency: String,
+    pub summary: ReturnBreakdown,
+    pub assets: Vec<AssetPerformance>,
+    /// Per-account split of the summary return, largest contribution first
+    pub attribution: Vec<AccountContribution>,
+    pub generated_at: DateTime<Utc>,
+    /// Trade-date and current rates used for the base-currency figures
+    pub conversions: Vec<ExchangeRate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dividend_reinvestment: Option<DividendReinvestment>,
+}
+
+/// Dividend-paying assets with dividends taken as cash versus reinvested (`drip=true`)
+#[derive(Debug, Serialize)]
+pub struct DividendReinvestment {
+    /// Totals over `assets` in the base currency, at current rates
+    pub invested: f64,
+    pub cash_total_return: f64,
+    pub cash_return_percent: f64,
+    pub reinvested_total_return: f64,
+    pub reinvested_return_percent: f64,
+    /// What reinvesting added over taking the cash
+    pub reinvestment_gain: f64,
+    /// Per asset, in its own currency, with the total-return series of both scenarios
+    pub assets: Vec<DripModel>,
+}
+
+/// Position state while replaying transactions
+struct PositionState {
+    account_id: Option<String>,
+    asset_type: AssetType,
+    market: Option<Market>,
+    symbol: String,
+    currency: String,
+    quantity: f64,
+    cost_local: f64,
+    cost_base: f64,
+    fx_estimated: bool,
+}
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+/// GET /api/performance - Returns of open long/spot positions in the base currency,
+/// split into the asset's own (hedged) return and the effect of currency moves. With
+/// `drip=true`, dividend-paying assets are also modelled with dividends reinvested.
+pub async fn get_performance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<PerformanceQuery>,
+) -> Result<Json<PerformanceResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let base_currency = query.base_currency
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+
+    let mut transactions = state.db.list_transactions(&user_id).await?;
+    transactions.sort_by_key(|t| t.timestamp);
+
+    // Replay transactions, tracking cost both in local currency and in base currency at trade-date FX
+    let mut positions: HashMap<String, PositionState> = HashMap::new();
+    let mut conversions = ConversionTrail::default();
+    for tx in &transactions {
+        let is_open = matches!(tx.action, TradeAction::Buy | TradeAction::Long | TradeAction::Deposit);
+        let is_close = matches!(
+            tx.action,
+            TradeAction::Sell | TradeAction::CloseLong | TradeAction::LiquidateLong | TradeAction::Withdraw
+        );
+        if !is_open && !is_close {
+            continue;
+        }
+
+        let market_key = tx.market.as_ref().map(|m| m.to_string()).unwrap_or_default();
+        let account_key = tx.account_id.as_deref().unwrap_or_default();
+        let key = format!("{}:{}:{}:{}", account_key, tx.asset_type, market_key, tx.symbol);
+        let (tx_quantity, _) = crate::utils::units::normalize_quantity(tx.quantity, tx.unit.as_deref(), &tx.asset_type, &tx.symbol);
+        let tx_price = crate::utils::units::normalize_price(tx.price, tx.unit.as_deref(), &tx.asset_type, &tx.symbol);
+
+        let position = positions.entry(key).or_insert_with(|| PositionState {
+            account_id: tx.account_id.clone(),
+            asset_type: tx.asset_type.clone(),
+            market: tx.market.clone(),
+            symbol: tx.symbol.clone(),
+            currency: tx.currency.clone()
+                .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
+                .unwrap_or_else(|| "THB".to_string()),
+            quantity: 0.0,
+            cost_local: 0.0,
+            cost_base: 0.0,
+            fx_estimated: false,
+        });
+
+        if is_open {
+            let amount_local = tx_quantity * tx_price + tx.all_fees();
+            let fx = match state.exchange_rate_service
+                .get_historical_quote(&position.currency, &base_currency, tx.timestamp.date_naive())
+                .await
+            {
+                Ok(quote) => {
+                    conversions.record(&quote);
+                    quote.rate
+                }
+                Err(e) => {
+                    tracing::warn!("⚠️ No historical FX for {} on {}: {}, using current rate", position.currency, tx.timestamp.date_naive(), e);
+                    position.fx_estimated = true;
+                    state.exchange_rate_service.get_rate_recorded(&position.currency, &base_currency, &mut conversions).await?
+                }
+            };
+            position.quantity += tx_quantity;
+            position.cost_local += amount_local;
+            position.cost_base += amount_local * fx;
+        } else if position.quantity > 0.0 {
+            // Reduce cost proportionally (average cost method)
+            let ratio = (tx_quantity / position.quantity).min(1.0);
+            position.cost_local -= position.cost_local * ratio;
+            position.cost_base -= position.cost_base * ratio;
+            position.quantity -= position.quantity * ratio;
+        }
+    }
+
+    // Positions are per account; assets merge them back together (with their hedged value)
+    let mut assets: HashMap<String, (AssetPerformance, f64)> = HashMap::new();
+    let mut accounts: HashMap<String, AccountTotals> = HashMap::new();
+    let mut summary = ReturnBreakdown::default();
+    let mut total_hedged_value = 0.0;
+
+    for position in positions.into_values().filter(|p| p.quantity > 0.00000001 && p.cost_local > 0.0) {
+        let price_entry = state.price_service
+            .get_price(&position.symbol, &position.asset_type, position.market.as_ref())
+            .await;
+        let current_value_local = match price_entry {
+            Ok(entry) => {
+                // Price may be quoted in a different currency than the position (e.g. USDT vs USD)
+                let price_fx = state.exchange_rate_service
+                    .get_rate_recorded(&entry.currency, &position.currency, &mut conversions)
+                    .await
+                    .unwrap_or(1.0);
+                position.quantity * entry.price * price_fx
+            }
+            Err(e) => {
+                tracing::warn!("No price for {}: {}, using cost basis", position.symbol, e);
+                position.cost_local
+            }
+        };
+
+        let current_fx = state.exchange_rate_service.get_rate_recorded(&position.currency, &base_currency, &mut conversions).await?;
+        let purchase_fx = position.cost_base / position.cost_local;
+
+        let current_value = current_value_local * current_fx;
+        // Hedged: the same local value converted at the rate paid when buying
+        let hedged_value = current_value_local * purchase_fx;
+
+        summary.cost_basis += position.cost_base;
+        summary.current_value += current_value;
+        total_hedged_value += hedged_value;
+
+        let account = accounts
+            .entry(position.account_id.clone().unwrap_or_else(|| "unassigned".to_string()))
+            .or_default();
+        account.cost_basis += position.cost_base;
+        account.current_value += current_value;
+        account.hedged_value += hedged_value;
+
+        let market_key = position.market.as_ref().map(|m| m.to_string()).unwrap_or_default();
+        let (asset, asset_hedged_value) = assets
+            .entry(format!("{}:{}:{}", position.asset_type, market_key, position.symbol))
+            .or_insert_with(|| (AssetPerformance {
+                symbol: position.symbol.clone(),
+                asset_type: position.asset_type.clone(),
+                market: position.market.clone(),
+                currency: position.currency.clone(),
+                quantity: 0.0,
+                cost_basis_local: 0.0,
+                current_value_local: 0.0,
+                purchase_fx_rate: 0.0,
+                current_fx_rate: current_fx,
+                fx_estimated: false,
+                returns: ReturnBreakdown::default(),
+            }, 0.0));
+        asset.quantity += position.quantity;
+        asset.cost_basis_local += position.cost_local;
+        asset.current_value_local += current_value_local;
+        asset.fx_estimated |= position.fx_estimated;
+        asset.returns.cost_basis += position.cost_base;
+        asset.returns.current_value += current_value;
+        *asset_hedged_value += hedged_value;
+    }
+
+    let mut assets: Vec<AssetPerformance> = assets.into_values()
+        .map(|(mut asset, hedged_value)| {
+            asset.purchase_fx_rate = asset.returns.cost_basis / asset.cost_basis_local;
+            asset.returns = build_breakdown(query.view, asset.returns.cost_basis, asset.returns.current_value, hedged_value);
+            asset
+        })
+        .collect();
+    assets.sort_by(|a, b| {
+        b.returns.current_value.partial_cmp(&a.returns.current_value).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let account_names: HashMap<String, String> = state.db.list_accounts(&user_id).await?
+        .into_iter()
+        .map(|a| (a.id, a.name))
+        .collect();
+    let mut attribution: Vec<AccountContribution> = accounts.into_iter()
+        .map(|(account_id, totals)| {
+            let returns = build_breakdown(query.view, totals.cost_basis, totals.current_value, totals.hedged_value);
+            let pnl = match query.view {
+                ReturnView::Hedged => totals.hedged_value - totals.cost_basis,
+                ReturnView::Unhedged | ReturnView::Compare => totals.current_value - totals.cost_basis,
+            };
+            let (weight_percent, contribution_percent) = if summary.cost_basis > 0.0 {
+                (totals.cost_basis / summary.cost_basis * 100.0, pnl / summary.cost_basis * 100.0)
+            } else {
+                (0.0, 0.0)
+            };
+            AccountContribution {
+                account_name: account_names.get(&account_id).cloned()
+                    .unwrap_or_else(|| if account_id == "unassigned" { "Unassigned".to_string() } else { account_id.clone() }),
+                account_id,
+                weight_percent,
+                contribution_percent,
+                returns,
+            }
+        })
+        .collect();
+    attribution.sort_by(|a, b| {
+        b.contribution_percent.partial_cmp(&a.contribution_percent).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let summary = build_breakdown(query.view, summary.cost_basis, summary.current_value, total_hedged_value);
+    let dividend_reinvestment = if query.drip {
+        Some(dividend_reinvestment(&state, &transactions, &base_currency, &mut conversions).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(PerformanceResponse {
+        base_currency,
+        summary,
+        assets,
+        attribution,
+        generated_at: Utc::now(),
+        conversions: conversions.into_vec(),
+        dividend_reinvestment,
+    }))
+}
+
+/// Price each dividend's ex-date from recorded price history and each asset now, then model
+/// both scenarios and total them in the base currency
+async fn dividend_reinvestment(
+    state: &AppState,
+    transactions: &[crate::models::Transaction],
+    base_currency: &str,
+    conversions: &mut ConversionTrail,
+) -> Result<DividendReinvestment, AppError> {
+    let payments: Vec<_> = dividends::dividend_payments(transactions).into_iter()
+        .filter(|p| !p.interest)
+        .collect();
+
+    let mut ex_date_prices: HashMap<String, f64> = HashMap::new();
+    let mut current_prices: HashMap<String, f64> = HashMap::new();
+    for payment in &payments {
+        let point = price_as_of(&state.db, &payment.symbol, &payment.asset_type, payment.market.as_ref(), payment.ex_date).await?;
+        if let Some(point) = point {
+            let currency = point.currency.as_deref().unwrap_or(&payment.currency);
+            let fx = state.exchange_rate_service.get_rate_recorded(currency, &payment.currency, conversions).await.unwrap_or(1.0);
+            ex_date_prices.insert(payment.transaction_id.clone(), point.price * fx);
+        }
+
+        let key = dividends::asset_key(&payment.symbol, &payment.asset_type, payment.market.as_ref());
+        if current_prices.contains_key(&key) {
+            continue;
+        }
+        match state.price_service.get_price(&payment.symbol, &payment.asset_type, payment.market.as_ref()).await {
+            Ok(entry) => {
+                let fx = state.exchange_rate_service.get_rate_recorded(&entry.currency, &payment.currency, conversions).await.unwrap_or(1.0);
+                current_prices.insert(key, entry.price * fx);
+            }
+            Err(e) => tracing::warn!("No price for {}: {}, using last trade price", payment.symbol, e),
+        }
+    }
+
+    let assets = dividends::model_reinvestment(transactions, &ex_date_prices, &current_prices, Utc::now());
+    let (mut invested, mut cash_total_return, mut reinvested_total_return) = (0.0, 0.0, 0.0);
+    for asset in &assets {
+        let fx = state.exchange_rate_service.get_rate_recorded(&asset.currency, base_currency, conversions).await?;
+        invested += asset.invested * fx;
+        cash_total_return += asset.cash.total_return * fx;
+        reinvested_total_return += asset.reinvested.total_return * fx;
+    }
+    let percent = |value: f64| if invested > 0.0 { value / invested * 100.0 } else { 0.0 };
+
+    Ok(DividendReinvestment {
+        invested,
+        cash_total_return,
+        cash_return_percent: percent(cash_total_return),
+        reinvested_total_return,
+        reinvested_return_percent: percent(reinvested_total_return),
+        reinvestment_gain: reinvested_total_return - cash_total_return,
+        assets,
+    })
+}
+
+/// Base-currency totals of one account's open positions
+#[derive(Default)]
+struct AccountTotals {
+    cost_basis: f64,
+    current_value: f64,
+    hedged_value: f64,
+}
+
+/// Fill in the figures requested by the view
+fn build_breakdown(view: ReturnView, cost_basis: f64, current_value: f64, hedged_value: f64) -> ReturnBreakdown {
+    let percent = |pnl: f64| if cost_basis > 0.0 { pnl / cost_basis * 100.0 } else { 0.0 };
+    let unhedged_pnl = current_value - cost_basis;
+    let hedged_pnl = hedged_value - cost_basis;
+    let fx_pnl = unhedged_pnl - hedged_pnl;
+
+    let mut breakdown = ReturnBreakdown {
+        cost_basis,
+        current_value,
+        ..Default::default()
+    };
+    if matches!(view, ReturnView::Unhedged | ReturnView::Compare) {
+        breakdown.unhedged_pnl = Some(unhedged_pnl);
+        breakdown.unhedged_return_percent = Some(percent(unhedged_pnl));
+    }
+    if matches!(view, ReturnView::Hedged | ReturnView::Compare) {
+        breakdown.hedged_pnl = Some(hedged_pnl);
+        breakdown.hedged_return_percent = Some(percent(hedged_pnl));
+    }
+    if view == ReturnView::Compare {
+        breakdown.fx_pnl = Some(fx_pnl);
+        breakdown.fx_return_percent = Some(percent(fx_pnl));
+    }
+    breakdown
+}
+
+// ==================== NAV Unitization ====================
+
+/// Starting NAV per unit, like a newly launched fund
+const INITIAL_NAV: f64 = 100.0;
+
+#[derive(Debug, Deserialize)]
+pub struct NavQuery {
+    pub days: Option<i32>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// One point of the unitized portfolio series
+#[derive(Debug, Serialize)]
+pub struct NavPoint {
+    pub date: String,
+    pub total_value: f64,
+    /// Net external cash flow since the previous point (buys in, sales/withdrawals out)
+    pub net_flow: f64,
+    pub units_issued: f64,
+    pub units: f64,
+    pub nav_per_unit: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NavResponse {
+    pub currency: String,
+    pub start_nav: f64,
+    pub end_nav: f64,
+    /// Time-weighted return over the period, unaffected by deposits/withdrawals
+    pub return_percent: f64,
+    /// Volatility, drawdown and Sharpe of the NAV per unit (None with fewer than 3 points)
+    pub risk: Option<RiskStats>,
+    pub series: Vec<NavPoint>,
+    /// Rates used to express cash flows in the snapshot currency
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// GET /api/performance/nav - Portfolio NAV per unit from daily snapshots.
+/// Units are issued/redeemed at the prior NAV on every external cash flow,
+/// so the NAV only moves with investment performance.
+pub async fn get_nav_series(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<NavQuery>,
+) -> Result<Json<NavResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    Ok(Json(nav_series(&state, &user_id, query).await?))
+}
+
+async fn nav_series(state: &AppState, user_id: &str, query: NavQuery) -> Result<NavResponse, AppError> {
+    let snapshots = crate::handlers::snapshot::load_snapshots(
+        state,
+        user_id,
+        &crate::handlers::snapshot::SnapshotQuery {
+            days: query.days,
+            from: query.from,
+            to: query.to,
+        },
+    ).await?;
+
+    let currency = snapshots.first()
+        .map(|s| s.currency.clone())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "THB".to_string());
+
+    // Net external flow per day, converted to the snapshot currency
+    let transactions = state.db.list_transactions(user_id).await?;
+    let mut flows_by_date: std::collections::BTreeMap<chrono::NaiveDate, f64> = std::collections::BTreeMap::new();
+    let mut conversions = ConversionTrail::default();
+    for tx in &transactions {
+        // Credited interest is part of the return, not a contribution
+        if tx.is_interest_credit() {
+            continue;
+        }
+        let signed_amount = match tx.action {
+            TradeAction::Buy | TradeAction::Long | TradeAction::Deposit => tx.quantity * tx.price + tx.all_fees(),
+            TradeAction::Sell | TradeAction::CloseLong | TradeAction::LiquidateLong | TradeAction::Withdraw => {
+                -(tx.quantity * tx.price - tx.all_fees())
+            }
+            _ => continue,
+        };
+        let tx_currency = tx.currency.clone()
+            .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
+            .unwrap_or_else(|| "THB".to_string());
+        let date = tx.timestamp.date_naive();
+        let fx = flow_rate(state, &tx_currency, &currency, date, &mut conversions).await;
+        *flows_by_date.entry(date).or_insert(0.0) += signed_amount * fx;
+    }
+
+    let mut series: Vec<NavPoint> = Vec::new();
+    let mut units = 0.0;
+    let mut nav = INITIAL_NAV;
+    let mut prev_date: Option<chrono::NaiveDate> = None;
+
+    for snapshot in &snapshots {
+        let Some(date) = snapshot.date.get(..10).and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()) else {
+            continue;
+        };
+        let value = snapshot.total_current_value;
+
+        // The first snapshot seeds the fund; later flows are those after the previous snapshot
+        let net_flow = match prev_date {
+            Some(prev) => flows_by_date.range(prev.succ_opt().unwrap_or(prev)..=date).map(|(_, v)| v).sum(),
+            None => 0.0,
+        };
+
+        let units_issued = if units <= 0.0 {
+            // (Re)start the fund at the last known NAV
+            if value > 0.0 { value / nav } else { 0.0 }
+        } else {
+            // Issue/redeem at the NAV before today's flow took effect
+            let nav_before_flow = (value - net_flow) / units;
+            if nav_before_flow > 0.0 { net_flow / nav_before_flow } else { 0.0 }
+        };
+        units = (units + units_issued).max(0.0);
+        if units > 0.0 {
+            nav = value / units;
+        }
+
+        series.push(NavPoint {
+            date: date.format("%Y-%m-%d").to_string(),
+            total_value: value,
+            net_flow,
+            units_issued,
+            units,
+            nav_per_unit: nav,
+        });
+        prev_date = Some(date);
+    }
+
+    let start_nav = series.first().map(|p| p.nav_per_unit).unwrap_or(INITIAL_NAV);
+    let end_nav = series.last().map(|p| p.nav_per_unit).unwrap_or(INITIAL_NAV);
+    let return_percent = if start_nav > 0.0 { (end_nav / start_nav - 1.0) * 100.0 } else { 0.0 };
+    // Snapshots are taken every calendar day
+    let nav_column: Vec<f64> = series.iter().map(|p| p.nav_per_unit).collect();
+    let risk = tokio::task::spawn_blocking(move || risk_stats(&nav_column, 365.0))
+        .await
+        .map_err(|e| AppError::Internal(format!("Risk computation failed: {}", e)))?;
+
+    Ok(NavResponse {
+        currency,
+        start_nav,
+        end_nav,
+        return_percent,
+        risk,
+        series,
+        conversions: conversions.into_vec(),
+    })
+}
+
+/// Rate for a cash flow booked on `date`, taken from that day like the portfolio's dated
+/// prices. Stablecoins count as the fiat they track; the latest rate stands in when no
+/// rate for the day is available.
+async fn flow_rate(state: &AppState, from: &str, to: &str, date: chrono::NaiveDate, conversions: &mut ConversionTrail) -> f64 {
+    if same_currency(from, to) {
+        return 1.0;
+    }
+    let (from, to) = (canonical_currency(from), canonical_currency(to));
+    match state.exchange_rate_service.get_historical_quote(&from, &to, date).await {
+        Ok(quote) => {
+            conversions.record(&quote);
+            quote.rate
+        }
+        Err(e) => {
+            tracing::debug!("No {}->{} rate on {}: {}, using the latest", from, to, date, e);
+            state.exchange_rate_service.get_rate_recorded(&from, &to, conversions).await.unwrap_or(1.0)
+        }
+    }
+}
+
+// ==================== Benchmark Comparison ====================
+
+/// Days of benchmark levels loaded before the first NAV date, so a series starting on a
+/// weekend or holiday still has a close to start from
+const BENCHMARK_LOOKBACK_DAYS: i64 = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct BenchmarkQuery {
+    /// Comma separated benchmark codes (SET, GSPC, BTC); all of them when omitted
+    pub benchmarks: Option<String>,
+    pub days: Option<i32>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchmarkSummary {
+    pub code: String,
+    pub name: String,
+    /// Benchmark returns are in the index's own currency
+    pub currency: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_percent: Option<f64>,
+    /// Portfolio minus benchmark, in percentage points
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excess_return_percent: Option<f64>,
+    /// Stored closes in the range
+    pub observations: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchmarkComparisonPoint {
+    pub date: String,
+    pub nav_per_unit: f64,
+    pub portfolio_return_percent: f64,
+    /// Cumulative return per benchmark code; null before the benchmark's first stored close
+    pub benchmarks: std::collections::BTreeMap<String, Option<f64>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchmarkComparisonResponse {
+    pub currency: String,
+    pub portfolio_return_percent: f64,
+    pub benchmarks: Vec<BenchmarkSummary>,
+    pub series: Vec<BenchmarkComparisonPoint>,
+}
+
+/// GET /api/portfolio/performance/benchmark - The time-weighted portfolio return (NAV per unit)
+/// next to the cumulative return of stored benchmark closes on the same dates. Benchmarks are
+/// carried forward over days their market was closed.
+pub async fn get_benchmark_comparison(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BenchmarkQuery>,
+) -> Result<Json<BenchmarkComparisonResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+
+    let mut benchmarks = Vec::new();
+    for code in query.benchmarks.as_deref().unwrap_or_default().split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let benchmark = benchmarks::find_benchmark(code).ok_or_else(|| {
+            let known: Vec<&str> = benchmarks::BENCHMARKS.iter().map(|b| b.code).collect();
+            AppError::BadRequest(format!("Unknown benchmark '{}'. Must be one of: {}", code, known.join(", ")))
+        })?;
+        if !benchmarks.iter().any(|b: &&benchmarks::Benchmark| b.code == benchmark.code) {
+            benchmarks.push(benchmark);
+        }
+    }
+    if benchmarks.is_empty() {
+        benchmarks = benchmarks::BENCHMARKS.iter().collect();
+    }
+
+    let nav = nav_series(&state, &user_id, NavQuery { days: query.days, from: query.from, to: query.to }).await?;
+    let dates: Vec<chrono::NaiveDate> = nav.series.iter()
+        .filter_map(|p| chrono::NaiveDate::parse_from_str(&p.date, "%Y-%m-%d").ok())
+        .collect();
+    let portfolio_return_percent = nav.return_percent;
+
+    let mut summaries = Vec::new();
+    let mut returns_by_code = Vec::new();
+    for benchmark in benchmarks {
+        let from = dates.first().map(|d| *d - chrono::Duration::days(BENCHMARK_LOOKBACK_DAYS));
+        let levels = benchmarks::load_levels(&state.db, benchmark, from, dates.last().copied()).await?;
+        let returns = benchmarks::aligned_returns(&levels, &dates);
+        let return_percent = returns.last().copied().flatten();
+        summaries.push(BenchmarkSummary {
+            code: benchmark.code.to_string(),
+            name: benchmark.name.to_string(),
+            currency: benchmark.currency.to_string(),
+            return_percent,
+            excess_return_percent: return_percent.map(|r| portfolio_return_percent - r),
+            observations: levels.iter().filter(|(d, _)| dates.first().is_some_and(|first| d >= first)).count(),
+        });
+        returns_by_code.push((benchmark.code.to_string(), returns));
+    }
+
+    let series = nav.series.iter().enumerate()
+        .map(|(i, point)| BenchmarkComparisonPoint {
+            date: point.date.clone(),
+            nav_per_unit: point.nav_per_unit,
+            portfolio_return_percent: if nav.start_nav > 0.0 { (point.nav_per_unit / nav.start_nav - 1.0) * 100.0 } else { 0.0 },
+            benchmarks: returns_by_code.iter()
+                .map(|(code, returns)| (code.clone(), returns.get(i).copied().flatten()))
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(BenchmarkComparisonResponse {
+        currency: nav.currency,
+        portfolio_return_percent,
+        benchmarks: summaries,
+        series,
+    }))
+}
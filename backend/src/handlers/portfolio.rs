@@ -1,24 +1,108 @@
 use axum::{
-    extract::{Path, State},
-    http::HeaderMap,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use std::collections::HashMap;
+use std::time::Instant;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::Serialize;
 use crate::error::AppError;
-use crate::models::{PortfolioAsset, PortfolioSummary, TradeAction, AssetType, Market};
+use crate::extract::{Path, Query};
+use crate::models::{AllocationDimension, PortfolioAsset, PortfolioSummary, AssetType, Market};
+use crate::services::price_refresher::{HeldSymbol, RefreshJob};
+use crate::services::price_service::stored_price_source;
+use crate::services::price_history::price_as_of;
+use crate::services::lot_engine::{self, DustCleanup, LotReplay};
+use crate::services::equity_vesting::{unvested_holdings, UnvestedGrant};
+use crate::services::movers::{compute_movers, MoverHolding, MoversReport};
+use crate::services::export_format::export_locale;
+use crate::services::rebalance::{plan_rebalance, plan_to_csv, PlanPosition, RebalancePlan, RebalanceTarget, TradeSide};
+use crate::services::allocation::{allocation, drift, AllocationDrift, AllocationHolding, AllocationSlice};
+use crate::services::exchange_rate::{ConversionTrail, ExchangeRate};
+use crate::utils::stats::{correlation_matrix, CloseSeries};
+use crate::services::valuation::{
+    canonical_currency, price_in_cost_currency, price_in_cost_currency_on, round_money, rounding_tolerance, same_currency,
+    MismatchKind, MONEY_DECIMALS,
+};
+use crate::handlers::users::extract_admin_user_id;
 use crate::AppState;
 
+/// Currency of the summary's liability and net worth totals; the frontend reads summary totals as THB
+const SUMMARY_CURRENCY: &str = "THB";
+
 #[derive(Debug, Serialize)]
 pub struct PortfolioResponse {
     pub summary: PortfolioSummary,
     pub assets: Vec<PortfolioAsset>,
+    /// Equity grants not yet vested, tracked apart from held assets
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unvested: Vec<UnvestedGrant>,
+    /// Exchange rates applied to reach these figures
+    pub conversions: Vec<ExchangeRate>,
+    /// Remainders below the dust threshold that were treated as closed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dust_cleaned: Vec<DustCleanup>,
+    /// Per-asset price timing, only with ?debug_timing=true (admins)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<PortfolioTiming>,
+    /// Day the portfolio was valued at, for ?as_of= requests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<NaiveDate>,
+    /// Holdings valued at static mock prices because no provider answered (STRICT_PRICE_DATA=true
+    /// refuses mocks instead)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mock_priced: Vec<String>,
+    /// Stock positions above LIQUIDITY_WARNING_PERCENT of their average daily volume
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub illiquid: Vec<String>,
 }
 
-#[derive(Debug, serde::Deserialize)]
+/// Where the time of a portfolio load went
+#[derive(Debug, Serialize)]
+pub struct PortfolioTiming {
+    pub total_ms: u64,
+    /// Loading and replaying transactions
+    pub transactions_ms: u64,
+    /// Stored price lookups plus provider fetches for all assets
+    pub prices_ms: u64,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    /// Slowest first
+    pub assets: Vec<AssetTiming>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssetTiming {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    /// "provider_cache", "stale_cache", "stored" (asset_prices), "history" (asset_price_history,
+    /// for ?as_of=) or "avg_cost" when nothing was found
+    pub source: &'static str,
+    /// None when the cache was not consulted (stored price used first)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_hit: Option<bool>,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
 pub struct PortfolioQuery {
     #[serde(default)]
     pub include_closed: bool,
+    /// Include transactions booked to archived accounts
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Only this account's transactions; "unassigned" selects those without an account
+    pub account_id: Option<String>,
+    /// Include a per-asset timing breakdown (admins only)
+    #[serde(default)]
+    pub debug_timing: bool,
+    /// Replay transactions up to the end of this day and value them with recorded prices and
+    /// that day's FX rates. Liabilities and unvested grants are left out.
+    pub as_of: Option<NaiveDate>,
 }
 
 /// Extract user_id from Authorization header JWT
@@ -40,243 +124,36 @@ fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppE
 pub async fn get_portfolio(
     State(state): State<AppState>,
     headers: HeaderMap,
-    axum::extract::Query(query): axum::extract::Query<PortfolioQuery>,
+    Query(query): Query<PortfolioQuery>,
 ) -> Result<Json<PortfolioResponse>, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
-    let transactions = state.db.list_transactions(&user_id).await?;
-    
-    // Sort transactions by timestamp ascending (oldest first) for correct P&L calculation
-    let mut sorted_transactions = transactions.clone();
-    sorted_transactions.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-    
-    // Group transactions by symbol+type+market and calculate holdings
-    let mut holdings: HashMap<String, PortfolioAsset> = HashMap::new();
-    let mut realized_pnl = 0.0; // Keep for backward compatibility (sum of all raw values)
-    let mut total_dividend = 0.0; // Track total dividends across all assets (active + closed)
-    let mut realized_pnl_breakdown: HashMap<String, f64> = HashMap::new();
-    
-    for tx in &sorted_transactions {
-        // Determine position "bucket" to support Hedge Mode (separating Spot, Long, Short)
-        let position_bucket = match tx.action {
-            TradeAction::Buy | TradeAction::Sell => "spot",
-            TradeAction::Long | TradeAction::CloseLong | TradeAction::LiquidateLong => "long",
-            TradeAction::Short | TradeAction::CloseShort | TradeAction::LiquidateShort => "short",
-            TradeAction::Dividend => "spot",
-            TradeAction::Deposit | TradeAction::Withdraw => "spot",
-        };
-        
-        let market_key = tx.market.as_ref().map(|m| m.to_string()).unwrap_or_default();
-        // Key now includes position_bucket to separate distinct positions (Hedge Mode)
-        let key = format!("{}:{}:{}:{}", tx.asset_type, market_key, tx.symbol, position_bucket);
-        
-        // Normalize quantity and price to base unit (e.g. Oz for gold)
-        let (tx_quantity, base_unit) = crate::utils::units::normalize_quantity(tx.quantity, tx.unit.as_deref(), &tx.asset_type, &tx.symbol);
-        let tx_price = crate::utils::units::normalize_price(tx.price, tx.unit.as_deref(), &tx.asset_type, &tx.symbol);
-
-        let asset = holdings.entry(key.clone()).or_insert_with(|| {
-            let currency = tx.currency.clone()
-                .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
-                .unwrap_or_else(|| "THB".to_string());
-            let mut new_asset = PortfolioAsset::new(
-                tx.symbol.clone(), 
-                tx.asset_type.clone(),
-                tx.market.clone(),
-                currency,
-            );
-            
-            // Set unit if not standard share
-            if base_unit != "share" {
-                new_asset.unit = Some(base_unit);
-            }
-
-            new_asset.position_type = position_bucket.to_string(); // Set the position type
-            
-            // Set leverage from first transaction
-            if let Some(lev) = tx.leverage {
-                new_asset.leverage = lev;
-            }
-            new_asset
-        });
-        
-        // Update leverage if transaction has one (take the latest)
-        if let Some(lev) = tx.leverage {
-            asset.leverage = lev;
+    if query.debug_timing {
+        extract_admin_user_id(&state, &headers)?;
+    }
+    if query.as_of.is_some_and(|d| d > Utc::now().date_naive()) {
+        return Err(AppError::BadRequest("as_of cannot be in the future".to_string()));
+    }
+    let started = Instant::now();
+    let mut transactions = state.db.list_transactions(&user_id).await?;
+    if let Some(account_id) = query.account_id.as_deref() {
+        if account_id == "unassigned" {
+            transactions.retain(|t| t.account_id.as_deref().is_none_or(str::is_empty));
+        } else {
+            transactions.retain(|t| t.account_id.as_deref() == Some(account_id));
         }
-        
-        match tx.action {
-            TradeAction::Buy | TradeAction::Long | TradeAction::Deposit => {
-                // Buy, Long, Deposit - increase position
-                let new_quantity = asset.quantity + tx_quantity;
-                
-                // Update Weighted Average Cost (PRICE)
-                // We MUST use Price for avg_cost to ensure P/L calculations are correct.
-                let current_notional = asset.quantity.abs() * asset.avg_cost;
-                let new_notional = tx_quantity * tx_price;
-                
-                if new_quantity.abs() > 0.0 {
-                    asset.avg_cost = (current_notional + new_notional) / new_quantity.abs();
-                } else {
-                    asset.avg_cost = tx.price;
-                }
-                
-                // Update Total Cost (Invested Amount / Margin)
-                // Use initial_margin ONLY for Leveraged assets (TFEX, Futures)
-                let use_margin = tx.asset_type == AssetType::Tfex || 
-                               (tx.asset_type == AssetType::Crypto && tx.leverage.unwrap_or(1.0) > 1.0);
-
-                let invest_amount = if use_margin && tx.initial_margin.is_some() {
-                    tx.initial_margin.unwrap()
-                } else {
-                    tx_quantity * tx_price
-                };
-                
-                // Add new investment + fees to total cost basis
-                asset.total_cost += invest_amount + tx.fees;
-                
-                // Track fees separately
-                asset.total_fees += tx.fees;
-                asset.quantity = new_quantity;
-            }
-            TradeAction::Short => {
-                // Open Short - create/increase negative position
-                let new_quantity = asset.quantity - tx_quantity;  // Goes more negative
-                
-                // Update Weighted Average Cost (PRICE)
-                let current_notional = asset.quantity.abs() * asset.avg_cost;
-                let new_notional = tx_quantity * tx_price;
-                
-                if new_quantity.abs() > 0.0 {
-                    asset.avg_cost = (current_notional + new_notional) / new_quantity.abs();
-                } else {
-                    asset.avg_cost = tx.price;
-                }
-                
-                // Update Total Cost (Invested Amount / Margin)
-                let use_margin = tx.asset_type == AssetType::Tfex || 
-                               (tx.asset_type == AssetType::Crypto && tx.leverage.unwrap_or(1.0) > 1.0);
-
-                let invest_amount = if use_margin && tx.initial_margin.is_some() {
-                    tx.initial_margin.unwrap()
-                } else {
-                    tx_quantity * tx_price
-                };
-                
-                asset.total_cost += invest_amount + tx.fees;
-                
-                asset.total_fees += tx.fees;
-                asset.quantity = new_quantity;
-            }
-            TradeAction::Sell | TradeAction::CloseLong | TradeAction::LiquidateLong => {
-                // Sell, CloseLong - close long position
-                if asset.quantity > 0.0 {
-                    // Realized PnL Calculation
-                    // Realized PnL Calculation
-                    // For TFEX/Futures, we must apply the multiplier (stored in asset.leverage)
-                    let multiplier = if asset.asset_type == AssetType::Tfex { asset.leverage } else { 1.0 };
-                    
-                    // Value = Price * Qty * Multiplier
-                    let sell_value = (tx_quantity * tx_price * multiplier) - tx.fees;
-                    let cost_basis = tx_quantity * asset.avg_cost * multiplier; // Notional Cost
-                    
-                    // Reduce quantity
-                    let ratio = tx_quantity / asset.quantity;
-                    
-                    // Reduce Fees proportionally
-                    let fee_portion = asset.total_fees * ratio;
-                    asset.total_fees -= fee_portion;
-                    
-                    // Reduce Total Cost (Invested) proportionally
-                    // This correctly handles both Spot (Notional) and Futures (Margin)
-                    asset.total_cost -= asset.total_cost * ratio;
-                    
-                    // PnL = (Sell Value - Fees) - (Cost Basis + Historical Buy Fees)
-                    // Note: sell_value already extracted tx.fees. We subtract historical fees.
-                    // For Futures: PnL = (Exit - Entry) * Qty - Fees
-                    // This formula: (Price * Qty - SellFees) - (AvgPrice * Qty - BuyFees)
-                    // = (Price - AvgPrice) * Qty - (SellFees + BuyFees). Correct.
-                    let pnl = sell_value - cost_basis - fee_portion;
-
-                    realized_pnl += pnl;
-                    
-                    // Accumulate breakdown by currency
-                    let currency = tx.currency.clone()
-                        .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
-                        .unwrap_or_else(|| "THB".to_string());
-                    *realized_pnl_breakdown.entry(currency).or_insert(0.0) += pnl;
-                    
-                    asset.realized_pnl += pnl;
-                    asset.quantity -= ratio * asset.quantity.abs(); // reduce towards 0
-                }
-            }
-            TradeAction::Withdraw => {
-                // Withdraw - reduce position like Sell, but NO Realized PnL
-                if asset.quantity > 0.0 {
-                    // Reduce quantity
-                    let ratio = tx_quantity / asset.quantity;
-                    
-                    // Reduce Fees proportionally
-                    // Fees paid for withdrawal are expenses, but not necessarily "trade PnL"
-                    // However, we subtract them from total_fees to keep bookkeeping clean
-                    let fee_portion = asset.total_fees * ratio;
-                    asset.total_fees -= fee_portion;
-                    
-                    // Reduce Total Cost (Invested) proportionally
-                    // This is key: getting money OUT reduces your invested principal
-                    asset.total_cost -= asset.total_cost * ratio;
-                    
-                    // No PnL calculation for Withdraw
-                    // Just reduce the asset size
-                    asset.quantity -= ratio * asset.quantity.abs(); 
-                }
-            }
-            TradeAction::CloseShort | TradeAction::LiquidateShort => {
-                // CloseShort - buy to close short position
-                if asset.quantity < 0.0 {
-                    let multiplier = if asset.asset_type == AssetType::Tfex { asset.leverage } else { 1.0 };
-                    
-                    let buy_cost = (tx_quantity * tx_price * multiplier) + tx.fees; // Cost to close
-                    let short_value = tx_quantity * asset.avg_cost * multiplier;  // Price we sold at * qty
-                    
-                    // Reduce negative quantity (towards 0)
-                    let ratio = tx_quantity / asset.quantity.abs();
-                    
-                    let fee_portion = asset.total_fees * ratio;
-                    asset.total_fees -= fee_portion;
-                    
-                    // Reduce Total Cost (Invested) proportionally
-                    asset.total_cost -= asset.total_cost * ratio;
-
-                    // PnL = (Short Value) - (Buy Cost + Fees) - Historical Fees
-                    // = (AvgPrice * Qty) - (ExitPrice * Qty + CloseFees) - BuyFees
-                    // = (AvgPrice - ExitPrice) * Qty - TotalFees. Correct.
-                    let pnl = short_value - buy_cost - fee_portion;
-
-                    realized_pnl += pnl;
-                    
-                    // Accumulate breakdown by currency
-                    let currency = tx.currency.clone()
-                        .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
-                        .unwrap_or_else(|| "THB".to_string());
-                    *realized_pnl_breakdown.entry(currency).or_insert(0.0) += pnl;
- 
-                    asset.realized_pnl += pnl;
-                    asset.quantity += tx_quantity;
-                }
-            }
-            TradeAction::Dividend => {
-                // Dividend - pure income, does not affect position size or cost basis
-                // We use 'price' field to store the Dividend Amount
-                // We typically set quantity to 1.0 or ignore it
-                let amount = tx.price; 
-                
-                asset.realized_dividend += amount;
-                
-                // Dividends are technically realized gains, but we track them separate from Capital Gains PnL
-                // If we want total return, we sum them up in UI
-                total_dividend += amount;
-            }
+    } else if !query.include_archived {
+        let archived = state.db.archived_account_ids(&user_id).await?;
+        if !archived.is_empty() {
+            transactions.retain(|t| t.account_id.as_ref().is_none_or(|id| !archived.contains(id)));
         }
     }
+    if let Some(as_of) = query.as_of {
+        let end = (as_of + chrono::Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+        transactions.retain(|t| t.timestamp < end);
+    }
+    
+    let LotReplay { holdings, realized_pnl, total_dividend, realized_pnl_breakdown, dust_cleaned, .. } =
+        lot_engine::replay(&transactions, &state.config.dust_policy);
     
     // Filter out zero holdings unless include_closed is true
     // Use abs() to include both long (positive) and short (negative) positions
@@ -293,10 +170,34 @@ pub async fn get_portfolio(
     // Fetch current prices for all holdings
     // For SET/TFEX: check PocketBase first (Settrade API not available)
     // For others: check price_service (API) first, then PocketBase fallback
-    let http_client = reqwest::Client::new();
+    let http_client = state.db.http();
     let pb_url = &state.config.pocketbase_url;
     
+    let mut conversions = ConversionTrail::default();
+    let transactions_ms = started.elapsed().as_millis() as u64;
+    let mut asset_timings = Vec::new();
+    let mut held_symbols = Vec::new();
+    let mut uncached_symbols = Vec::new();
+    // A backgrounded client reloading the portfolio isn't someone watching these symbols
+    let active_client = state.client_modes.mode_for(&headers).await.is_active();
     for asset in &mut active_holdings {
+        let asset_started = Instant::now();
+        if let Some(as_of) = query.as_of {
+            asset_timings.push(AssetTiming {
+                symbol: asset.symbol.clone(),
+                asset_type: asset.asset_type.clone(),
+                market: asset.market.clone(),
+                source: value_as_of(&state, asset, as_of, &mut conversions).await,
+                cache_hit: None,
+                elapsed_ms: asset_started.elapsed().as_millis() as u64,
+            });
+            continue;
+        }
+        let mut price_source = "avg_cost";
+        let mut cache_hit = None;
+        if active_client && asset.quantity.abs() > 0.00000001 {
+            state.symbol_heat.record_holding(&asset.symbol, &asset.asset_type).await;
+        }
         let asset_type_str = match asset.asset_type {
             crate::models::AssetType::Stock => "stock",
             crate::models::AssetType::Tfex => "tfex",
@@ -304,6 +205,7 @@ pub async fn get_portfolio(
             crate::models::AssetType::Crypto => "crypto",
             crate::models::AssetType::Gold => "gold",
             crate::models::AssetType::Commodity => "commodity",
+            crate::models::AssetType::Cash => "cash",
         };
         
         let market_filter = if let Some(m) = &asset.market {
@@ -332,7 +234,13 @@ pub async fn get_portfolio(
                     if let Ok(data) = response.json::<serde_json::Value>().await {
                         if let Some(items) = data.get("items").and_then(|i| i.as_array()) {
                             if let Some(first) = items.first() {
-                                return first.get("price").and_then(|p| p.as_f64());
+                                let price = first.get("price").and_then(|p| p.as_f64())?;
+                                let stored = stored_price_source(first);
+                                // Mock prices saved before strict mode was turned on are not quotes
+                                if state.config.strict_price_data && stored.0 == "mock" {
+                                    return None;
+                                }
+                                return Some((price, stored));
                             }
                         }
                     }
@@ -349,37 +257,62 @@ pub async fn get_portfolio(
         );
         
         let mut found_price = false;
+        let frozen = state.price_service.frozen_price(&asset.symbol, &asset.asset_type).await;
         
-        if use_pb_first {
+        if let Some(price_entry) = frozen {
+            // Delisted: value at the final price and leave it out of price refreshes
+            price_source = "delisted";
+            asset.delisted_at = Some(price_entry.updated_at);
+            let price = price_in_cost_currency(&state.exchange_rate_service, asset, price_entry.price, &price_entry.currency, &mut conversions).await;
+            asset.calculate_pnl(price);
+            asset.attribute_price(price_source, Some(price_entry.updated_at));
+            found_price = true;
+        } else if use_pb_first {
             // Thai stocks/TFEX/Foreign stocks: PocketBase first, then API fallback
-            if let Some(price) = pb_price {
+            if let Some((price, (source, fetched_at))) = &pb_price {
                 tracing::debug!("📊 Using PB price for {}: {}", asset.symbol, price);
-                asset.calculate_pnl(price);
+                asset.calculate_pnl(*price);
+                asset.attribute_price(source, *fetched_at);
                 found_price = true;
+                price_source = "stored";
             } else {
-                // Try API as fallback
-                if let Ok(price_entry) = state.price_service.get_price(&asset.symbol, &asset.asset_type, asset.market.as_ref()).await {
-                    tracing::debug!("📊 API price for {}: {} {}", asset.symbol, price_entry.price, price_entry.currency);
-                    asset.calculate_pnl(price_entry.price);
-                    asset.currency = price_entry.currency.clone();  // Update currency to match price source
+                // Cached provider price as fallback
+                if let Some(lookup) = state.price_service.cached_price(&asset.symbol, &asset.asset_type, asset.market.as_ref()).await {
+                    let price_entry = lookup.entry;
+                    cache_hit = Some(true);
+                    price_source = if lookup.stale { "stale_cache" } else { "provider_cache" };
+                    tracing::debug!("📊 Cached price for {}: {} {}", asset.symbol, price_entry.price, price_entry.currency);
+                    let price = price_in_cost_currency(&state.exchange_rate_service, asset, price_entry.price, &price_entry.currency, &mut conversions).await;
+                    asset.calculate_pnl(price);
+                    asset.attribute_price(price_entry.source.as_deref().unwrap_or(price_source), Some(price_entry.updated_at));
                     found_price = true;
+                } else {
+                    cache_hit = Some(false);
                 }
             }
         } else {
-            // Other assets: API first, then PocketBase fallback
-            match state.price_service.get_price(&asset.symbol, &asset.asset_type, asset.market.as_ref()).await {
-                Ok(price_entry) => {
-                    tracing::debug!("📊 API price for {}: {} {}", asset.symbol, price_entry.price, price_entry.currency);
-                    asset.calculate_pnl(price_entry.price);
-                    asset.currency = price_entry.currency.clone();  // Update currency to match price source
+            // Other assets: cached provider price first, then PocketBase fallback.
+            // Providers are never called here; the price refresher keeps the cache warm.
+            match state.price_service.cached_price(&asset.symbol, &asset.asset_type, asset.market.as_ref()).await {
+                Some(lookup) => {
+                    let price_entry = lookup.entry;
+                    cache_hit = Some(true);
+                    price_source = if lookup.stale { "stale_cache" } else { "provider_cache" };
+                    tracing::debug!("📊 Cached price for {}: {} {}", asset.symbol, price_entry.price, price_entry.currency);
+                    let price = price_in_cost_currency(&state.exchange_rate_service, asset, price_entry.price, &price_entry.currency, &mut conversions).await;
+                    asset.calculate_pnl(price);
+                    asset.attribute_price(price_entry.source.as_deref().unwrap_or(price_source), Some(price_entry.updated_at));
                     found_price = true;
                 }
-                Err(e) => {
-                    tracing::warn!("API price fetch failed for {}: {}, trying PB", asset.symbol, e);
-                    if let Some(price) = pb_price {
+                None => {
+                    tracing::debug!("No cached price for {}, trying PB", asset.symbol);
+                    cache_hit = Some(false);
+                    if let Some((price, (source, fetched_at))) = &pb_price {
                         tracing::debug!("📊 Using PB price for {}: {}", asset.symbol, price);
-                        asset.calculate_pnl(price);
+                        asset.calculate_pnl(*price);
+                        asset.attribute_price(source, *fetched_at);
                         found_price = true;
+                        price_source = "stored";
                     }
                 }
             }
@@ -389,8 +322,32 @@ pub async fn get_portfolio(
         if !found_price {
             tracing::warn!("No price found for {}, using avg_cost as fallback", asset.symbol);
             asset.calculate_pnl(asset.avg_cost);
+            asset.attribute_price(price_source, None);
+        }
+
+        asset_timings.push(AssetTiming {
+            symbol: asset.symbol.clone(),
+            asset_type: asset.asset_type.clone(),
+            market: asset.market.clone(),
+            source: price_source,
+            cache_hit,
+            elapsed_ms: asset_started.elapsed().as_millis() as u64,
+        });
+        if asset.delisted_at.is_some() {
+            continue;
+        }
+        let held = HeldSymbol {
+            symbol: asset.symbol.clone(),
+            asset_type: asset.asset_type.clone(),
+            market: asset.market.clone(),
+        };
+        if cache_hit == Some(false) {
+            uncached_symbols.push(held.clone());
         }
+        held_symbols.push(held);
     }
+    let prices_ms = started.elapsed().as_millis() as u64 - transactions_ms;
+    state.price_refresher.track(held_symbols, uncached_symbols).await;
     
     // Sort by current value descending
     active_holdings.sort_by(|a, b| {
@@ -405,133 +362,1392 @@ pub async fn get_portfolio(
     summary.assets_count = active_holdings.len();
     
     for asset in &active_holdings {
-        summary.total_invested += asset.total_cost;
-        summary.total_current_value += asset.current_value;
-        summary.total_unrealized_pnl += asset.unrealized_pnl;
         // Dividend already calculated globally
+        summary.add_asset(asset);
     }
     
+    // Subtract outstanding loans so leveraged equity is not overstated.
+    // Only current balances are known, so past valuations leave them out.
+    let liabilities = match query.as_of {
+        Some(_) => Ok(Vec::new()),
+        None => state.db.list_liabilities(&user_id).await,
+    };
+    match liabilities {
+        Ok(liabilities) => {
+            for liability in liabilities {
+                let fx = state.exchange_rate_service
+                    .get_rate_recorded(&liability.currency, SUMMARY_CURRENCY, &mut conversions)
+                    .await?;
+                summary.total_liabilities += liability.balance * fx;
+                *summary.liabilities_breakdown.entry(liability.currency.to_uppercase()).or_insert(0.0) += liability.balance;
+            }
+        }
+        Err(e) => tracing::warn!("⚠️ Could not load liabilities for {}: {}", user_id, e),
+    }
+    summary.net_worth = summary.total_current_value - summary.total_liabilities;
+    
+    // Unvested equity compensation is reported separately and not counted as held value
+    let unvested = match query.as_of {
+        Some(_) => Ok(Vec::new()),
+        None => unvested_holdings(&state.db, &state.price_service, &user_id).await,
+    };
+    let unvested = match unvested {
+        Ok(unvested) => unvested,
+        Err(e) => {
+            tracing::warn!("⚠️ Could not load equity grants for {}: {}", user_id, e);
+            Vec::new()
+        }
+    };
+    summary.total_unvested_value = unvested.iter().map(|u| u.unvested_value).sum();
+    
     summary.calculate_percent();
+
+    let timing = query.debug_timing.then(|| {
+        asset_timings.sort_by_key(|t| std::cmp::Reverse(t.elapsed_ms));
+        PortfolioTiming {
+            total_ms: started.elapsed().as_millis() as u64,
+            transactions_ms,
+            prices_ms,
+            cache_hits: asset_timings.iter().filter(|t| t.cache_hit == Some(true)).count(),
+            cache_misses: asset_timings.iter().filter(|t| t.cache_hit == Some(false)).count(),
+            assets: asset_timings,
+        }
+    });
+    if let Some(timing) = &timing {
+        tracing::info!(
+            "⏱️ Portfolio for {} loaded in {}ms ({}ms prices, {} cache hits, {} misses)",
+            user_id, timing.total_ms, timing.prices_ms, timing.cache_hits, timing.cache_misses
+        );
+    }
     
+    if query.as_of.is_none() {
+        size_against_volume(&state, &mut active_holdings).await;
+    }
+    let mock_priced = mock_priced(&active_holdings);
+    let illiquid = illiquid(&active_holdings, state.config.liquidity_warning_percent);
     Ok(Json(PortfolioResponse {
         summary,
+        mock_priced,
+        illiquid,
         assets: active_holdings,
+        unvested,
+        conversions: conversions.into_vec(),
+        dust_cleaned,
+        timing,
+        as_of: query.as_of,
     }))
 }
 
-/// Get portfolio summary only
-pub async fn get_portfolio_summary(
+/// Value a holding at the last price recorded by the end of `as_of`, converted at that day's
+/// rate. Falls back to average cost when no recent enough price was recorded.
+async fn value_as_of(state: &AppState, asset: &mut PortfolioAsset, as_of: NaiveDate, conversions: &mut ConversionTrail) -> &'static str {
+    let end_of_day = as_of.and_hms_opt(23, 59, 59).unwrap_or_default().and_utc();
+    let point = match price_as_of(&state.db, &asset.symbol, &asset.asset_type, asset.market.as_ref(), end_of_day).await {
+        Ok(point) => point,
+        Err(e) => {
+            tracing::warn!("⚠️ Could not load recorded price of {} on {}: {}", asset.symbol, as_of, e);
+            None
+        }
+    };
+    match point {
+        Some(point) => {
+            let currency = point.currency.clone().unwrap_or_else(|| asset.currency.clone());
+            let price = price_in_cost_currency_on(&state.exchange_rate_service, asset, point.price, &currency, as_of, conversions).await;
+            asset.calculate_pnl(price);
+            asset.attribute_price(point.source.as_deref().unwrap_or("history"), point.recorded_at);
+            "history"
+        }
+        None => {
+            tracing::warn!("No recorded price for {} on {}, using avg_cost as fallback", asset.symbol, as_of);
+            asset.calculate_pnl(asset.avg_cost);
+            asset.attribute_price("avg_cost", None);
+            "avg_cost"
+        }
+    }
+}
+
+/// POST /api/portfolio/refresh - Fetch fresh prices for every held asset in the background.
+/// Returns a job handle; poll GET /api/portfolio/refresh/:job_id and reload the portfolio when done.
+pub async fn refresh_portfolio_prices(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<PortfolioSummary>, AppError> {
-    let portfolio = get_portfolio(State(state), headers, axum::extract::Query(PortfolioQuery { include_closed: false })).await?;
-    Ok(Json(portfolio.summary.clone()))
+) -> Result<(StatusCode, Json<RefreshJob>), AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(PortfolioQuery::default())).await?;
+    let symbols: Vec<HeldSymbol> = portfolio.assets.iter()
+        .filter(|a| a.asset_type != AssetType::Cash)
+        .map(|a| HeldSymbol {
+            symbol: a.symbol.clone(),
+            asset_type: a.asset_type.clone(),
+            market: a.market.clone(),
+        })
+        .collect();
+
+    let job = state.price_refresher.refresh_now(&user_id, symbols).await;
+    tracing::info!("🔄 Started price refresh job {} for {} ({} symbols)", job.id, user_id, job.total);
+    Ok((StatusCode::ACCEPTED, Json(job)))
 }
 
-/// Get holdings by asset type (for logged-in user)
-pub async fn get_portfolio_by_type(
+/// GET /api/portfolio/refresh/:job_id - Progress of a refresh job
+pub async fn get_refresh_job(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Path(asset_type): Path<String>,
-) -> Result<Json<PortfolioResponse>, AppError> {
-    let asset_type_enum = parse_asset_type(&asset_type)?;
-    
-    let portfolio = get_portfolio(State(state), headers, axum::extract::Query(PortfolioQuery { include_closed: false })).await?;
-    
-    let filtered_assets: Vec<PortfolioAsset> = portfolio.assets
-        .iter()
-        .filter(|a| a.asset_type == asset_type_enum)
-        .cloned()
+    Path(job_id): Path<String>,
+) -> Result<Json<RefreshJob>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    state.price_refresher.get_job(&user_id, &job_id).await
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Refresh job {} not found", job_id)))
+}
+
+/// Dimension the summary endpoint can break totals down by
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortfolioGroupBy {
+    Account,
+    Market,
+    AssetType,
+    /// Transaction tags; an asset whose transactions carry several tags counts in each group
+    Tag,
+    Sector,
+    Currency,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PortfolioSummaryQuery {
+    #[serde(default)]
+    pub include_closed: bool,
+    #[serde(default)]
+    pub include_archived: bool,
+    pub account_id: Option<String>,
+    pub group_by: Option<PortfolioGroupBy>,
+    /// Currency the group totals are converted into (default THB)
+    pub base_currency: Option<String>,
+}
+
+/// Totals and P&L of one group, in the response's base currency
+#[derive(Debug, Serialize)]
+pub struct PortfolioGroup {
+    pub key: String,
+    pub name: String,
+    pub assets_count: usize,
+    pub total_invested: f64,
+    pub total_current_value: f64,
+    pub total_unrealized_pnl: f64,
+    pub total_unrealized_pnl_percent: f64,
+    pub total_realized_pnl: f64,
+    pub total_dividend: f64,
+    pub weight_percent: f64,
+}
+
+impl PortfolioGroup {
+    fn new(key: String, name: String) -> Self {
+        Self {
+            key,
+            name,
+            assets_count: 0,
+            total_invested: 0.0,
+            total_current_value: 0.0,
+            total_unrealized_pnl: 0.0,
+            total_unrealized_pnl_percent: 0.0,
+            total_realized_pnl: 0.0,
+            total_dividend: 0.0,
+            weight_percent: 0.0,
+        }
+    }
+
+    fn add(&mut self, asset: &PortfolioAsset, fx: f64) {
+        if asset.quantity != 0.0 {
+            self.assets_count += 1;
+        }
+        self.total_invested += asset.total_cost * fx;
+        self.total_current_value += asset.current_value * fx;
+        self.total_unrealized_pnl += asset.unrealized_pnl * fx;
+        self.total_realized_pnl += asset.realized_pnl * fx;
+        self.total_dividend += asset.realized_dividend * fx;
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PortfolioSummaryResponse {
+    #[serde(flatten)]
+    pub summary: PortfolioSummary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<PortfolioGroupBy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_currency: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<PortfolioGroup>>,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// Get portfolio summary, optionally with totals per account, market, asset type, tag,
+/// sector or currency (?group_by=...)
+pub async fn get_portfolio_summary(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<PortfolioSummaryQuery>,
+) -> Result<Json<PortfolioSummaryResponse>, AppError> {
+    let portfolio_query = PortfolioQuery {
+        include_closed: query.include_closed,
+        include_archived: query.include_archived,
+        account_id: query.account_id.clone(),
+        debug_timing: false,
+        as_of: None,
+    };
+    let portfolio = get_portfolio(State(state.clone()), headers.clone(), Query(portfolio_query)).await?.0;
+    let Some(group_by) = query.group_by else {
+        return Ok(Json(PortfolioSummaryResponse {
+            summary: portfolio.summary,
+            group_by: None,
+            base_currency: None,
+            groups: None,
+            conversions: portfolio.conversions,
+        }));
+    };
+
+    let base_currency = query.base_currency
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+    let mut conversions = ConversionTrail::from(portfolio.conversions.clone());
+    let mut groups: HashMap<String, PortfolioGroup> = HashMap::new();
+
+    if group_by == PortfolioGroupBy::Account {
+        let user_id = extract_user_id(&state, &headers)?;
+        let accounts: Vec<(String, String)> = match query.account_id.as_deref() {
+            Some("unassigned") => Vec::new(),
+            Some(id) => vec![(id.to_string(), state.db.get_account(id).await.map(|a| a.name).unwrap_or_else(|_| id.to_string()))],
+            None => state.db.list_accounts(&user_id).await?
+                .into_iter()
+                .filter(|a| query.include_archived || !a.archived)
+                .map(|a| (a.id, a.name))
+                .collect(),
+        };
+        let mut sources: Vec<(String, String)> = accounts;
+        if query.account_id.as_deref().is_none_or(|id| id == "unassigned") {
+            sources.push(("unassigned".to_string(), "Unassigned".to_string()));
+        }
+        for (account_id, name) in sources {
+            let account_query = PortfolioQuery {
+                include_closed: query.include_closed,
+                include_archived: true,
+                account_id: Some(account_id.clone()),
+                debug_timing: false,
+                as_of: None,
+            };
+            let account_portfolio = get_portfolio(State(state.clone()), headers.clone(), Query(account_query)).await?.0;
+            conversions.extend(&account_portfolio.conversions);
+            if account_portfolio.assets.is_empty() {
+                continue;
+            }
+            let mut group = PortfolioGroup::new(account_id.clone(), name);
+            for asset in &account_portfolio.assets {
+                let fx = state.exchange_rate_service.get_rate_recorded(&asset.currency, &base_currency, &mut conversions).await?;
+                group.add(asset, fx);
+            }
+            groups.insert(account_id, group);
+        }
+    } else {
+        // Tags live on transactions; an asset carries every tag used on its transactions
+        let holding_key = |symbol: &str, asset_type: &AssetType, market: &Option<Market>| {
+            format!("{}:{}:{}", asset_type, market.as_ref().map(|m| m.to_string()).unwrap_or_default(), symbol.to_uppercase())
+        };
+        let mut asset_tags: HashMap<String, Vec<String>> = HashMap::new();
+        if group_by == PortfolioGroupBy::Tag {
+            let user_id = extract_user_id(&state, &headers)?;
+            for tx in state.db.list_transactions(&user_id).await? {
+                let tags = asset_tags.entry(holding_key(&tx.symbol, &tx.asset_type, &tx.market)).or_default();
+                for tag in tx.tags {
+                    let tag = tag.trim().to_lowercase();
+                    if !tag.is_empty() && !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+            }
+        }
+
+        for asset in &portfolio.assets {
+            let keys: Vec<(String, String)> = match group_by {
+                PortfolioGroupBy::AssetType => vec![(asset.asset_type.to_string(), asset.asset_type.to_string())],
+                PortfolioGroupBy::Market => {
+                    let market = asset.market.as_ref().map(|m| m.to_string()).unwrap_or_else(|| "other".to_string());
+                    vec![(market.clone(), market)]
+                }
+                PortfolioGroupBy::Currency => vec![(asset.currency.to_uppercase(), asset.currency.to_uppercase())],
+                PortfolioGroupBy::Sector => {
+                    let sector = state.symbols_service.lookup_symbol(&asset.symbol).await
+                        .and_then(|s| s.sector.or(s.category))
+                        .filter(|s| !s.trim().is_empty())
+                        .unwrap_or_else(|| "Unclassified".to_string());
+                    vec![(sector.to_lowercase(), sector)]
+                }
+                PortfolioGroupBy::Tag => {
+                    let tags = asset_tags
+                        .get(&holding_key(&asset.symbol, &asset.asset_type, &asset.market))
+                        .filter(|t| !t.is_empty())
+                        .cloned()
+                        .unwrap_or_else(|| vec!["untagged".to_string()]);
+                    tags.into_iter().map(|t| (t.clone(), t)).collect()
+                }
+                PortfolioGroupBy::Account => unreachable!("account groups are built per account above"),
+            };
+            let fx = state.exchange_rate_service.get_rate_recorded(&asset.currency, &base_currency, &mut conversions).await?;
+            for (key, name) in keys {
+                groups.entry(key.clone())
+                    .or_insert_with(|| PortfolioGroup::new(key, name))
+                    .add(asset, fx);
+            }
+        }
+    }
+
+    // Weights are of the whole portfolio, so tag groups can add up to more than 100%
+    let mut total_value = 0.0;
+    for asset in &portfolio.assets {
+        let fx = state.exchange_rate_service.get_rate_recorded(&asset.currency, &base_currency, &mut conversions).await?;
+        total_value += asset.current_value * fx;
+    }
+    let mut groups: Vec<PortfolioGroup> = groups.into_values()
+        .map(|mut g| {
+            if g.total_invested > 0.0 {
+                g.total_unrealized_pnl_percent = g.total_unrealized_pnl / g.total_invested * 100.0;
+            }
+            if total_value > 0.0 {
+                g.weight_percent = g.total_current_value / total_value * 100.0;
+            }
+            g
+        })
         .collect();
-    
+    groups.sort_by(|a, b| b.total_current_value.partial_cmp(&a.total_current_value).unwrap_or(std::cmp::Ordering::Equal));
+
+    Ok(Json(PortfolioSummaryResponse {
+        summary: portfolio.summary,
+        group_by: Some(group_by),
+        base_currency: Some(base_currency),
+        groups: Some(groups),
+        conversions: conversions.into_vec(),
+    }))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct AllocationQuery {
+    /// Only this account's holdings, measured against its target allocation if it has one
+    pub account_id: Option<String>,
+    /// Defaults to the account's base currency, else THB
+    pub base_currency: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AllocationModelReport {
+    pub account_id: String,
+    pub dimension: AllocationDimension,
+    pub band_percent: f64,
+    /// Some group drifted further from its target than the band allows
+    pub needs_rebalance: bool,
+    pub total_buy: f64,
+    pub total_sell: f64,
+    pub groups: Vec<AllocationDrift>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AllocationResponse {
+    pub base_currency: String,
+    pub total_value: f64,
+    pub asset_type: Vec<AllocationSlice>,
+    pub market: Vec<AllocationSlice>,
+    pub sector: Vec<AllocationSlice>,
+    pub currency: Vec<AllocationSlice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<AllocationModelReport>,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// GET /api/portfolio/allocation - Holdings by asset type, market, sector and currency as % of
+/// total value. With an account that has a target allocation, also the drift from it and the
+/// amounts to buy or sell per group to rebalance.
+pub async fn get_portfolio_allocation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<AllocationQuery>,
+) -> Result<Json<AllocationResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let account = match query.account_id.as_deref().filter(|id| *id != "unassigned") {
+        Some(id) => {
+            let account = state.db.get_account(id).await?;
+            if account.user_id != user_id {
+                return Err(AppError::NotFound(format!("Account {} not found", id)));
+            }
+            Some(account)
+        }
+        None => None,
+    };
+    let base_currency = query.base_currency
+        .or_else(|| account.as_ref().map(|a| a.base_currency.clone()))
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+
+    let portfolio_query = PortfolioQuery {
+        account_id: query.account_id.clone(),
+        include_archived: account.is_some(),
+        ..Default::default()
+    };
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(portfolio_query)).await?.0;
+    let mut conversions = ConversionTrail::from(portfolio.conversions.clone());
+    let mut holdings = Vec::new();
+    for asset in portfolio.assets.iter().filter(|a| a.current_value > 0.0) {
+        let fx = state.exchange_rate_service.get_rate_recorded(&asset.currency, &base_currency, &mut conversions).await?;
+        let sector = if asset.asset_type == AssetType::Cash {
+            "Cash".to_string()
+        } else {
+            state.symbols_service.lookup_symbol(&asset.symbol).await
+                .and_then(|s| s.sector.or(s.category))
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| "Unclassified".to_string())
+        };
+        holdings.push(AllocationHolding {
+            value: asset.current_value * fx,
+            asset_type: asset.asset_type.to_string(),
+            market: asset.market.as_ref().map(|m| m.to_string()).unwrap_or_else(|| "other".to_string()),
+            sector: (sector.to_lowercase(), sector),
+            currency: asset.currency.to_uppercase(),
+        });
+    }
+
+    let target = account.as_ref().and_then(|a| Some((a.id.clone(), a.target_allocation.clone()?)))
+        .map(|(account_id, model)| {
+            let groups = drift(&allocation(&holdings, model.dimension), &model);
+            let total_of = |side: TradeSide| groups.iter()
+                .filter(|g| g.side == Some(side))
+                .map(|g| g.suggested_amount)
+                .sum();
+            AllocationModelReport {
+                account_id,
+                dimension: model.dimension,
+                band_percent: model.band_percent,
+                needs_rebalance: groups.iter().any(|g| g.out_of_band),
+                total_buy: total_of(TradeSide::Buy),
+                total_sell: total_of(TradeSide::Sell),
+                groups,
+            }
+        });
+
+    Ok(Json(AllocationResponse {
+        base_currency,
+        total_value: holdings.iter().map(|h| h.value).sum(),
+        asset_type: allocation(&holdings, AllocationDimension::AssetType),
+        market: allocation(&holdings, AllocationDimension::Market),
+        sector: allocation(&holdings, AllocationDimension::Sector),
+        currency: allocation(&holdings, AllocationDimension::Currency),
+        target,
+        conversions: conversions.into_vec(),
+    }))
+}
+
+/// Symbols priced from static mock data
+fn mock_priced(assets: &[PortfolioAsset]) -> Vec<String> {
+    assets.iter()
+        .filter(|a| a.price_source.as_deref() == Some("mock"))
+        .map(|a| a.symbol.clone())
+        .collect()
+}
+
+/// Set each stock's size against its average daily volume. Only cached volumes are used;
+/// missing ones are fetched in the background so the next load has them.
+async fn size_against_volume(state: &AppState, assets: &mut [PortfolioAsset]) {
+    let mut missing = Vec::new();
+    for asset in assets.iter_mut().filter(|a| matches!(a.asset_type, AssetType::Stock | AssetType::ForeignStock)) {
+        match state.price_service.cached_average_daily_volume(&asset.symbol, &asset.asset_type).await {
+            Some(volume) if volume > 0.0 => asset.daily_volume_percent = Some(asset.quantity.abs() / volume * 100.0),
+            Some(_) => {}
+            None => missing.push((asset.symbol.clone(), asset.asset_type.clone())),
+        }
+    }
+    if missing.is_empty() {
+        return;
+    }
+    let price_service = state.price_service.clone();
+    tokio::spawn(async move {
+        for (symbol, asset_type) in missing {
+            if let Err(e) = price_service.average_daily_volume(&symbol, &asset_type).await {
+                tracing::debug!("No average volume for {}: {}", symbol, e);
+            }
+        }
+    });
+}
+
+/// Symbols whose position exceeds `threshold_percent` of average daily volume
+fn illiquid(assets: &[PortfolioAsset], threshold_percent: f64) -> Vec<String> {
+    if threshold_percent <= 0.0 {
+        return Vec::new();
+    }
+    assets.iter()
+        .filter(|a| a.daily_volume_percent.is_some_and(|p| p > threshold_percent))
+        .map(|a| a.symbol.clone())
+        .collect()
+}
+
+/// Totals of a filtered set of holdings (in their own currencies, like the full summary)
+fn summarize_assets(assets: &[PortfolioAsset]) -> PortfolioSummary {
     let mut summary = PortfolioSummary::new();
-    summary.assets_count = filtered_assets.len();
-    
-    for asset in &filtered_assets {
-        summary.total_invested += asset.total_cost;
-        summary.total_current_value += asset.current_value;
-        summary.total_unrealized_pnl += asset.unrealized_pnl;
+    summary.assets_count = assets.len();
+    for asset in assets {
+        summary.add_asset(asset);
     }
-    
     summary.calculate_percent();
+    summary
+}
+
+/// Get holdings by asset type (for logged-in user).
+/// Superseded by /portfolio/summary?group_by=asset_type; kept for existing clients.
+pub async fn get_portfolio_by_type(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(asset_type): Path<AssetType>,
+) -> Result<Json<PortfolioResponse>, AppError> {
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(PortfolioQuery::default())).await?.0;
+    let assets: Vec<PortfolioAsset> = portfolio.assets
+        .into_iter()
+        .filter(|a| a.asset_type == asset_type)
+        .collect();
     
     Ok(Json(PortfolioResponse {
-        summary,
-        assets: filtered_assets,
+        summary: summarize_assets(&assets),
+        mock_priced: mock_priced(&assets),
+        illiquid: illiquid(&assets, state.config.liquidity_warning_percent),
+        assets,
+        unvested: Vec::new(),
+        conversions: portfolio.conversions,
+        dust_cleaned: Vec::new(),
+        timing: None,
+        as_of: None,
     }))
 }
 
-/// Get holdings by market (for logged-in user)
+/// Get holdings by market (for logged-in user).
+/// Superseded by /portfolio/summary?group_by=market; kept for existing clients.
 pub async fn get_portfolio_by_market(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Path(market): Path<String>,
+    Path(market): Path<Market>,
 ) -> Result<Json<PortfolioResponse>, AppError> {
-    let market_enum = parse_market(&market)?;
-    
-    let portfolio = get_portfolio(State(state), headers, axum::extract::Query(PortfolioQuery { include_closed: false })).await?;
-    
-    let filtered_assets: Vec<PortfolioAsset> = portfolio.assets
-        .iter()
-        .filter(|a| a.market.as_ref() == Some(&market_enum))
-        .cloned()
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(PortfolioQuery::default())).await?.0;
+    let assets: Vec<PortfolioAsset> = portfolio.assets
+        .into_iter()
+        .filter(|a| a.market.as_ref() == Some(&market))
         .collect();
     
-    let mut summary = PortfolioSummary::new();
-    summary.assets_count = filtered_assets.len();
-    
-    for asset in &filtered_assets {
-        summary.total_invested += asset.total_cost;
-        summary.total_current_value += asset.current_value;
-        summary.total_unrealized_pnl += asset.unrealized_pnl;
-    }
-    
-    summary.calculate_percent();
-    
     Ok(Json(PortfolioResponse {
-        summary,
-        assets: filtered_assets,
+        summary: summarize_assets(&assets),
+        mock_priced: mock_priced(&assets),
+        illiquid: illiquid(&assets, state.config.liquidity_warning_percent),
+        assets,
+        unvested: Vec::new(),
+        conversions: portfolio.conversions,
+        dust_cleaned: Vec::new(),
+        timing: None,
+        as_of: None,
     }))
 }
 
-fn parse_asset_type(s: &str) -> Result<AssetType, AppError> {
-    match s.to_lowercase().as_str() {
-        "stock" => Ok(AssetType::Stock),
-        "tfex" => Ok(AssetType::Tfex),
-        "crypto" => Ok(AssetType::Crypto),
-        "foreign_stock" | "foreignstock" => Ok(AssetType::ForeignStock),
-        "gold" => Ok(AssetType::Gold),
-        "commodity" => Ok(AssetType::Commodity),
-        _ => Err(AppError::BadRequest(format!(
-            "Invalid asset type: {}. Must be one of: stock, tfex, crypto, foreign_stock, gold, commodity",
-            s
-        ))),
-    }
-}
-
-fn parse_market(s: &str) -> Result<Market, AppError> {
-    match s.to_lowercase().as_str() {
-        "set" => Ok(Market::Set),
-        "mai" => Ok(Market::Mai),
-        "tfex" => Ok(Market::Tfex),
-        "nyse" => Ok(Market::Nyse),
-        "nasdaq" => Ok(Market::Nasdaq),
-        "amex" => Ok(Market::Amex),
-        "lse" => Ok(Market::Lse),
-        "euronext" => Ok(Market::Euronext),
-        "xetra" => Ok(Market::Xetra),
-        "hkex" => Ok(Market::Hkex),
-        "tse" => Ok(Market::Tse),
-        "sgx" => Ok(Market::Sgx),
-        "krx" => Ok(Market::Krx),
-        "binance" => Ok(Market::Binance),
-        "coinbase" => Ok(Market::Coinbase),
-        "bitkub" => Ok(Market::Bitkub),
-        "okx" => Ok(Market::Okx),
-        "htx" => Ok(Market::Htx),
-        "kucoin" => Ok(Market::Kucoin),
-        "comex" => Ok(Market::Comex),
-        "lbma" => Ok(Market::Lbma),
-        _ => Err(AppError::BadRequest(format!("Invalid market: {}", s))),
+#[derive(Debug, serde::Deserialize)]
+pub struct MoversQuery {
+    /// Gainers/losers per window (default 5)
+    pub top: Option<usize>,
+}
+
+/// GET /api/portfolio/movers - Top gainers and losers among held assets over 1d/7d/30d
+pub async fn get_portfolio_movers(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<MoversQuery>,
+) -> Result<Json<MoversReport>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(PortfolioQuery::default())).await?;
+
+    let holdings: Vec<MoverHolding> = portfolio.assets.iter()
+        .filter(|a| a.asset_type != AssetType::Cash)
+        .map(|a| MoverHolding {
+            symbol: a.symbol.clone(),
+            asset_type: a.asset_type.clone(),
+            market: a.market.clone(),
+            quantity: a.quantity,
+            current_price: a.current_price,
+        })
+        .collect();
+
+    let top = query.top.unwrap_or(5).clamp(1, 50);
+    let report = compute_movers(&state.db, &state.price_service, &user_id, &holdings, top).await?;
+    Ok(Json(report))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatmapGroupBy {
+    Sector,
+    Market,
+    #[default]
+    AssetType,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct HeatmapQuery {
+    #[serde(default)]
+    pub group_by: HeatmapGroupBy,
+    pub base_currency: Option<String>,
+}
+
+/// Leaf of the treemap: one held asset
+#[derive(Debug, Serialize)]
+pub struct HeatmapCell {
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Market value in the base currency (tile size)
+    pub value: f64,
+    pub weight_percent: f64,
+    /// Unrealized P&L % (tile colour)
+    pub pnl_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeatmapGroup {
+    pub name: String,
+    pub value: f64,
+    pub weight_percent: f64,
+    /// Value-weighted P&L % of the group
+    pub pnl_percent: f64,
+    pub children: Vec<HeatmapCell>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeatmapResponse {
+    pub group_by: HeatmapGroupBy,
+    pub base_currency: String,
+    pub total_value: f64,
+    pub groups: Vec<HeatmapGroup>,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// GET /api/portfolio/heatmap - Treemap of holdings (group → asset → value, pnl %) in one currency
+pub async fn get_portfolio_heatmap(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<HeatmapQuery>,
+) -> Result<Json<HeatmapResponse>, AppError> {
+    let base_currency = query.base_currency
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(PortfolioQuery::default())).await?;
+
+    // group name -> (cost in base, cells)
+    let mut grouped: HashMap<String, (f64, Vec<HeatmapCell>)> = HashMap::new();
+    let mut conversions = ConversionTrail::from(portfolio.conversions.clone());
+    for asset in &portfolio.assets {
+        let fx = state.exchange_rate_service.get_rate_recorded(&asset.currency, &base_currency, &mut conversions).await?;
+        let value = asset.current_value * fx;
+        if value <= 0.0 {
+            continue;
+        }
+
+        let symbol_info = state.symbols_service.lookup_symbol(&asset.symbol).await;
+        let group = match query.group_by {
+            HeatmapGroupBy::AssetType => asset.asset_type.to_string(),
+            HeatmapGroupBy::Market => asset.market.as_ref()
+                .map(|m| m.to_string())
+                .unwrap_or_else(|| "other".to_string()),
+            HeatmapGroupBy::Sector => symbol_info.as_ref()
+                .and_then(|s| s.sector.clone().or_else(|| s.category.clone()))
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| "Unclassified".to_string()),
+        };
+
+        let entry = grouped.entry(group).or_insert_with(|| (0.0, Vec::new()));
+        entry.0 += asset.total_cost * fx;
+        entry.1.push(HeatmapCell {
+            symbol: asset.symbol.clone(),
+            name: symbol_info.map(|s| s.name),
+            value,
+            weight_percent: 0.0,
+            pnl_percent: asset.unrealized_pnl_percent,
+        });
+    }
+
+    let total_value: f64 = grouped.values().flat_map(|(_, cells)| cells.iter().map(|c| c.value)).sum();
+    let weight = |v: f64| if total_value > 0.0 { v / total_value * 100.0 } else { 0.0 };
+
+    let mut groups: Vec<HeatmapGroup> = grouped.into_iter()
+        .map(|(name, (cost, mut children))| {
+            let value: f64 = children.iter().map(|c| c.value).sum();
+            for cell in &mut children {
+                cell.weight_percent = weight(cell.value);
+            }
+            children.sort_by(|a, b| b.value.partial_cmp(&a.value).unwrap_or(std::cmp::Ordering::Equal));
+            HeatmapGroup {
+                name,
+                value,
+                weight_percent: weight(value),
+                pnl_percent: if cost > 0.0 { (value - cost) / cost * 100.0 } else { 0.0 },
+                children,
+            }
+        })
+        .collect();
+    groups.sort_by(|a, b| b.value.partial_cmp(&a.value).unwrap_or(std::cmp::Ordering::Equal));
+
+    Ok(Json(HeatmapResponse {
+        group_by: query.group_by,
+        base_currency,
+        total_value,
+        groups,
+        conversions: conversions.into_vec(),
+    }))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CorrelationsQuery {
+    /// Look-back window in days (default 90)
+    pub days: Option<u32>,
+    /// Extra series to compare against, e.g. "BTC,foreign_stock:SPY" (bare symbols are crypto)
+    pub benchmarks: Option<String>,
+    /// Largest holdings to include (default 20)
+    pub max_assets: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CorrelationSeries {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    /// Share of portfolio value (holdings only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight_percent: Option<f64>,
+    pub is_benchmark: bool,
+    /// Daily closes available in the window
+    pub observations: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CorrelatedPair {
+    pub a: String,
+    pub b: String,
+    pub correlation: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CorrelationsResponse {
+    pub days: u32,
+    pub series: Vec<CorrelationSeries>,
+    /// matrix[i][j] = correlation of daily returns of series i and j (null when not enough overlap)
+    pub matrix: Vec<Vec<Option<f64>>>,
+    /// Weight-averaged pairwise correlation between holdings (1.0 = no diversification)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_correlation: Option<f64>,
+    /// Pairs correlated at 0.8 or more
+    pub highly_correlated: Vec<CorrelatedPair>,
+    /// Series with no price history
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+}
+
+const HIGH_CORRELATION: f64 = 0.8;
+
+/// Benchmark spec "asset_type:SYMBOL" (bare symbols are crypto) as (SYMBOL, asset type)
+pub(crate) fn parse_benchmark(spec: &str) -> Result<(String, AssetType), AppError> {
+    let spec = spec.trim();
+    let (asset_type, symbol) = match spec.split_once(':') {
+        Some((t, sym)) => (t.parse::<AssetType>().map_err(AppError::Unprocessable)?, sym.trim()),
+        None => (AssetType::Crypto, spec),
+    };
+    if symbol.is_empty() {
+        return Err(AppError::Unprocessable(format!("Benchmark '{}' has no symbol", spec)));
+    }
+    Ok((symbol.to_uppercase(), asset_type))
+}
+
+/// GET /api/portfolio/correlations - Pairwise daily-return correlations of held assets
+pub async fn get_portfolio_correlations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<CorrelationsQuery>,
+) -> Result<Json<CorrelationsResponse>, AppError> {
+    // Daily candles only come back for windows over a week
+    let days = query.days.unwrap_or(90).clamp(30, 365);
+    let max_assets = query.max_assets.unwrap_or(20).clamp(2, 50);
+
+    let mut benchmarks = Vec::new();
+    for item in query.benchmarks.as_deref().unwrap_or_default().split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        benchmarks.push(parse_benchmark(item)?);
+    }
+
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(PortfolioQuery::default())).await?;
+    let total_value: f64 = portfolio.assets.iter().map(|a| a.current_value).sum();
+    let mut holdings: Vec<&PortfolioAsset> = portfolio.assets.iter()
+        .filter(|a| a.asset_type != AssetType::Cash && a.current_value > 0.0)
+        .collect();
+    holdings.truncate(max_assets); // assets are sorted by value
+    // A benchmark that is also held is already in the matrix
+    benchmarks.retain(|(symbol, asset_type)| {
+        !holdings.iter().any(|a| a.symbol.eq_ignore_ascii_case(symbol) && &a.asset_type == asset_type)
+    });
+
+    // (series info, market, daily closes)
+    let mut series = Vec::new();
+    let mut closes = Vec::new();
+    let mut missing = Vec::new();
+    let candidates = holdings.iter()
+        .map(|a| (a.symbol.clone(), a.asset_type.clone(), a.market.clone(), Some(a.current_value)))
+        .chain(benchmarks.into_iter().map(|(symbol, asset_type)| (symbol, asset_type, None, None)));
+    for (symbol, asset_type, market, value) in candidates {
+        let history = match state.price_service.get_price_history(&symbol, &asset_type, market.as_ref(), days).await {
+            Ok(history) => history,
+            Err(e) => {
+                tracing::warn!("⚠️ No history for {} correlations: {}", symbol, e);
+                Vec::new()
+            }
+        };
+        // Last close per day
+        let daily: std::collections::BTreeMap<String, f64> = history.into_iter()
+            .map(|h| (h.date.get(..10).unwrap_or(&h.date).to_string(), h.price))
+            .collect();
+        if daily.len() < 3 {
+            missing.push(symbol);
+            continue;
+        }
+        series.push(CorrelationSeries {
+            symbol,
+            asset_type,
+            weight_percent: value.map(|v| if total_value > 0.0 { v / total_value * 100.0 } else { 0.0 }),
+            is_benchmark: value.is_none(),
+            observations: daily.len(),
+        });
+        closes.push(CloseSeries::from(&daily));
+    }
+
+    let n = series.len();
+    let matrix = tokio::task::spawn_blocking(move || correlation_matrix(&closes))
+        .await
+        .map_err(|e| AppError::Internal(format!("Correlation computation failed: {}", e)))?;
+    let mut highly_correlated = Vec::new();
+    let mut weighted_sum = 0.0;
+    let mut weight_total = 0.0;
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let Some(c) = matrix[i][j] else { continue };
+            if c >= HIGH_CORRELATION {
+                highly_correlated.push(CorrelatedPair {
+                    a: series[i].symbol.clone(),
+                    b: series[j].symbol.clone(),
+                    correlation: c,
+                });
+            }
+            if let (Some(wa), Some(wb)) = (series[i].weight_percent, series[j].weight_percent) {
+                weighted_sum += wa * wb * c;
+                weight_total += wa * wb;
+            }
+        }
+    }
+    highly_correlated.sort_by(|a, b| b.correlation.partial_cmp(&a.correlation).unwrap_or(std::cmp::Ordering::Equal));
+
+    Ok(Json(CorrelationsResponse {
+        days,
+        series,
+        matrix,
+        average_correlation: (weight_total > 0.0).then(|| weighted_sum / weight_total),
+        highly_correlated,
+        missing,
+    }))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RebalancePlanRequest {
+    pub targets: Vec<RebalanceTarget>,
+    pub base_currency: Option<String>,
+    /// Sell holdings that have no target instead of leaving them as they are
+    #[serde(default)]
+    pub sell_untargeted: bool,
+    /// Fee rate overrides as a fraction of notional, keyed by asset type (e.g. {"stock": 0.001})
+    #[serde(default)]
+    pub fee_rates: HashMap<String, f64>,
+    /// Allow fractional foreign shares instead of whole-share lots
+    #[serde(default)]
+    pub fractional_shares: bool,
+    /// New money to invest, in the base currency
+    #[serde(default)]
+    pub cash_to_add: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RebalancePlanResponse {
+    #[serde(flatten)]
+    pub plan: RebalancePlan,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RebalancePlanQuery {
+    /// "json" (default) or "csv" for a printable execution checklist
+    pub format: Option<String>,
+}
+
+/// POST /api/portfolio/rebalance/plan - Lot-aligned trade list that moves holdings toward target weights
+pub async fn create_rebalance_plan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<RebalancePlanQuery>,
+    Json(req): Json<RebalancePlanRequest>,
+) -> Result<Response, AppError> {
+    let as_csv = match query.format.as_deref().map(|f| f.to_lowercase()) {
+        None => false,
+        Some(f) if f == "json" => false,
+        Some(f) if f == "csv" => true,
+        Some(f) => return Err(AppError::BadRequest(format!("Invalid format: {} (expected json or csv)", f))),
+    };
+    let user_id = extract_user_id(&state, &headers)?;
+
+    if req.targets.is_empty() {
+        return Err(AppError::BadRequest("At least one target is required".to_string()));
+    }
+    let mut total_weight = 0.0;
+    for (i, target) in req.targets.iter().enumerate() {
+        if !(0.0..=100.0).contains(&target.weight_percent) {
+            return Err(AppError::BadRequest(format!("Target weight for {} must be between 0 and 100", target.symbol)));
+        }
+        if req.targets[..i].iter().any(|t| t.symbol.eq_ignore_ascii_case(&target.symbol) && t.asset_type == target.asset_type) {
+            return Err(AppError::BadRequest(format!("Duplicate target for {}", target.symbol)));
+        }
+        total_weight += target.weight_percent;
     }
+    if total_weight > 100.0 + 1e-6 {
+        return Err(AppError::BadRequest(format!("Target weights add up to {:.2}%, more than 100%", total_weight)));
+    }
+    if let Some((asset_type, rate)) = req.fee_rates.iter().find(|(_, r)| !(0.0..1.0).contains(*r)) {
+        return Err(AppError::BadRequest(format!("Fee rate for {} must be a fraction between 0 and 1, got {}", asset_type, rate)));
+    }
+
+    let base_currency = req.base_currency
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(PortfolioQuery::default())).await?;
+
+    let find_target = |symbol: &str, asset_type: &AssetType| req.targets.iter()
+        .find(|t| t.symbol.eq_ignore_ascii_case(symbol) && &t.asset_type == asset_type);
+
+    let mut conversions = ConversionTrail::from(portfolio.conversions.clone());
+    let mut cash = req.cash_to_add;
+    let mut positions = Vec::new();
+    for asset in &portfolio.assets {
+        let fx = state.exchange_rate_service.get_rate_recorded(&asset.currency, &base_currency, &mut conversions).await?;
+        if asset.asset_type == AssetType::Cash {
+            cash += asset.current_value * fx;
+            continue;
+        }
+        let target_percent = find_target(&asset.symbol, &asset.asset_type)
+            .map(|t| t.weight_percent)
+            .or(req.sell_untargeted.then_some(0.0));
+        positions.push(PlanPosition {
+            symbol: asset.symbol.clone(),
+            asset_type: asset.asset_type.clone(),
+            market: asset.market.clone(),
+            currency: asset.currency.clone(),
+            quantity: asset.quantity,
+            price: asset.current_price,
+            fx,
+            multiplier: if asset.asset_type == AssetType::Tfex { asset.leverage } else { 1.0 },
+            target_percent,
+        });
+    }
+
+    // Targets not held yet start from zero at the live price
+    for target in &req.targets {
+        if positions.iter().any(|p| p.symbol.eq_ignore_ascii_case(&target.symbol) && p.asset_type == target.asset_type) {
+            continue;
+        }
+        if target.asset_type == AssetType::Cash {
+            return Err(AppError::BadRequest("Cash is what remains after trades and cannot be a target".to_string()));
+        }
+        let price = state.price_service
+            .get_price(&target.symbol, &target.asset_type, target.market.as_ref())
+            .await?;
+        let fx = state.exchange_rate_service.get_rate_recorded(&price.currency, &base_currency, &mut conversions).await?;
+        positions.push(PlanPosition {
+            symbol: target.symbol.to_uppercase(),
+            asset_type: target.asset_type.clone(),
+            market: target.market.clone(),
+            currency: price.currency,
+            quantity: 0.0,
+            price: price.price,
+            fx,
+            multiplier: target.multiplier.unwrap_or(1.0),
+            target_percent: Some(target.weight_percent),
+        });
+    }
+
+    let plan = plan_rebalance(&base_currency, &positions, cash, &req.fee_rates, req.fractional_shares);
+    tracing::info!("⚖️ Rebalance plan: {} trades, est. fees {:.2} {}", plan.trades.len(), plan.total_fees, base_currency);
+
+    if as_csv {
+        let locale = export_locale(&state.db, &user_id).await?;
+        let filename = format!("rebalance-plan-{}.csv", chrono::Utc::now().format("%Y%m%d"));
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+            ],
+            plan_to_csv(&plan, &locale),
+        ).into_response());
+    }
+    Ok(Json(RebalancePlanResponse { plan, conversions: conversions.into_vec() }).into_response())
+}
+
+#[derive(Debug, Serialize)]
+pub struct CurrencyMismatch {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    /// Distinct currencies on the holding's transactions (market default when unset)
+    pub transaction_currencies: Vec<String>,
+    /// Transactions with neither a currency nor a market
+    pub implicit_transactions: usize,
+    /// Currency the portfolio values this holding in
+    pub valuation_currency: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_currency: Option<String>,
+    /// Rate applied to the quoted price, when it was converted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fx_rate: Option<f64>,
+    pub issues: Vec<MismatchKind>,
+    pub details: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CurrencyReconciliationResponse {
+    pub holdings_checked: usize,
+    /// Mismatches whose figures may be wrong (mixed, implicit or unconvertible currencies)
+    pub problems: usize,
+    pub mismatches: Vec<CurrencyMismatch>,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// GET /api/portfolio/currency-reconciliation - Holdings whose transaction, price and valuation
+/// currencies disagree, and how each was resolved
+pub async fn get_currency_reconciliation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<CurrencyReconciliationResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let archived = state.db.archived_account_ids(&user_id).await?;
+    let transactions: Vec<_> = state.db.list_transactions(&user_id).await?
+        .into_iter()
+        .filter(|t| t.account_id.as_ref().is_none_or(|a| !archived.contains(a)))
+        .collect();
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(PortfolioQuery::default())).await?;
+
+    let mut mismatches = Vec::new();
+    for asset in portfolio.assets.iter().filter(|a| a.asset_type != AssetType::Cash) {
+        let mut currencies: Vec<String> = Vec::new();
+        let mut implicit = 0;
+        for tx in transactions.iter().filter(|t| t.symbol == asset.symbol && t.asset_type == asset.asset_type && t.market == asset.market) {
+            match tx.currency.clone().or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string())) {
+                Some(c) if !currencies.iter().any(|x| x.eq_ignore_ascii_case(&c)) => currencies.push(c.to_uppercase()),
+                Some(_) => {}
+                None => implicit += 1,
+            }
+        }
+        // Served from the price cache populated by get_portfolio
+        let price_currency = state.price_service
+            .get_price(&asset.symbol, &asset.asset_type, asset.market.as_ref())
+            .await
+            .ok()
+            .map(|p| p.currency.to_uppercase());
+
+        let mut issues = Vec::new();
+        let mut details = Vec::new();
+        let mut fx_rate = None;
+
+        let mut distinct: Vec<String> = currencies.iter().map(|c| canonical_currency(c)).collect();
+        distinct.sort();
+        distinct.dedup();
+        if distinct.len() > 1 {
+            issues.push(MismatchKind::MixedTransactionCurrencies);
+            details.push(format!(
+                "Transactions are booked in {}; the cost basis adds these amounts without conversion",
+                currencies.join(", ")
+            ));
+        }
+        if implicit > 0 {
+            issues.push(MismatchKind::ImplicitCurrency);
+            details.push(format!(
+                "{} transaction(s) have no currency or market; cost is assumed to be in {}",
+                implicit, asset.currency
+            ));
+        }
+        if let Some(alias) = currencies.iter().chain(price_currency.iter()).find(|c| canonical_currency(c) != **c) {
+            issues.push(MismatchKind::StablecoinAlias);
+            details.push(format!("{} is valued 1:1 as {}", alias, canonical_currency(alias)));
+        }
+        if let (Some(quoted), Some(cost_currency)) = (&price_currency, currencies.first()) {
+            if !same_currency(quoted, cost_currency) {
+                if asset.price_currency.is_some() {
+                    fx_rate = portfolio.conversions.iter()
+                        .find(|c| c.from_currency == canonical_currency(quoted) && c.to_currency == canonical_currency(&asset.currency))
+                        .map(|c| c.rate);
+                    issues.push(MismatchKind::PriceCurrencyConverted);
+                    details.push(format!(
+                        "Price quoted in {} is converted to {} before comparing with cost",
+                        quoted, asset.currency
+                    ));
+                } else {
+                    issues.push(MismatchKind::NoExchangeRate);
+                    details.push(format!(
+                        "No {}->{} rate; value is in {} while cost was entered in {}",
+                        quoted, cost_currency, quoted, cost_currency
+                    ));
+                }
+            }
+        }
+
+        if !issues.is_empty() {
+            mismatches.push(CurrencyMismatch {
+                symbol: asset.symbol.clone(),
+                asset_type: asset.asset_type.clone(),
+                market: asset.market.clone(),
+                transaction_currencies: currencies,
+                implicit_transactions: implicit,
+                valuation_currency: asset.currency.clone(),
+                price_currency,
+                fx_rate,
+                issues,
+                details,
+            });
+        }
+    }
+
+    let problems = mismatches.iter().filter(|m| m.issues.iter().any(|i| i.is_problem())).count();
+    tracing::info!("💱 Currency reconciliation for {}: {} mismatches, {} problems", user_id, mismatches.len(), problems);
+
+    Ok(Json(CurrencyReconciliationResponse {
+        holdings_checked: portfolio.assets.len(),
+        problems,
+        mismatches,
+        conversions: portfolio.conversions.clone(),
+    }))
+}
+
+/// One summary figure checked against the parts it is built from
+#[derive(Debug, Serialize)]
+pub struct TotalCheck {
+    pub field: &'static str,
+    /// Figure reported in the portfolio summary
+    pub reported: f64,
+    /// The same figure rebuilt from its parts at full precision
+    pub summed: f64,
+    pub difference: f64,
+    pub parts: usize,
+    /// Rounded figure minus the sum of the parts rounded one by one, as a client adding up
+    /// displayed values would see it. Expected, as long as it stays within the tolerance.
+    pub rounding_residual: f64,
+    pub rounding_tolerance: f64,
+    pub reconciled: bool,
+}
+
+impl TotalCheck {
+    fn new(field: &'static str, reported: f64, parts: &[f64]) -> Self {
+        let summed: f64 = parts.iter().sum();
+        let difference = reported - summed;
+        // Adding the same values in another order may move the last bits, nothing more
+        let precision = f64::EPSILON * parts.iter().map(|p| p.abs()).sum::<f64>().max(1.0) * (parts.len() as f64 + 1.0);
+        let rounding_residual = round_money(reported) - parts.iter().map(|p| round_money(*p)).sum::<f64>();
+        let rounding_tolerance = rounding_tolerance(parts.len());
+        Self {
+            field,
+            reported,
+            summed,
+            difference,
+            parts: parts.len(),
+            rounding_residual,
+            rounding_tolerance,
+            reconciled: reported.is_finite()
+                && difference.abs() <= precision
+                && rounding_residual.abs() <= rounding_tolerance + precision,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PortfolioReconciliationResponse {
+    /// True when every total matches its parts and no asset has broken figures
+    pub reconciled: bool,
+    /// Decimal places money figures are rounded to for display
+    pub money_decimals: i32,
+    pub assets_checked: usize,
+    pub checks: Vec<TotalCheck>,
+    /// Problems found, e.g. a total off from its parts or an asset valued at NaN
+    pub discrepancies: Vec<String>,
+}
+
+/// GET /api/portfolio/reconcile - Check that the summary totals are the sums of the per-asset
+/// figures at full precision, and how far rounding each figure for display moves them apart.
+/// Accepts the same filters as GET /api/portfolio.
+pub async fn get_portfolio_reconciliation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<PortfolioQuery>,
+) -> Result<Json<PortfolioReconciliationResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let portfolio = get_portfolio(State(state.clone()), headers, Query(query)).await?.0;
+    let summary = &portfolio.summary;
+    let assets = &portfolio.assets;
+
+    let mut discrepancies = Vec::new();
+    for asset in assets {
+        let figures = [
+            ("total_cost", asset.total_cost),
+            ("current_value", asset.current_value),
+            ("unrealized_pnl", asset.unrealized_pnl),
+        ];
+        for (name, value) in figures.iter().filter(|(_, v)| !v.is_finite()) {
+            discrepancies.push(format!("{} ({}) has {} = {}", asset.symbol, asset.asset_type, name, value));
+        }
+    }
+    if summary.assets_count != assets.len() {
+        discrepancies.push(format!("assets_count is {} but {} assets are listed", summary.assets_count, assets.len()));
+    }
+
+    let values = |figure: fn(&PortfolioAsset) -> f64| assets.iter().map(figure).collect::<Vec<f64>>();
+    let mut conversions = ConversionTrail::from(portfolio.conversions.clone());
+    let mut liabilities = Vec::new();
+    for (currency, balance) in &summary.liabilities_breakdown {
+        let fx = state.exchange_rate_service.get_rate_recorded(currency, SUMMARY_CURRENCY, &mut conversions).await?;
+        liabilities.push(balance * fx);
+    }
+    let checks = vec![
+        TotalCheck::new("total_invested", summary.total_invested, &values(|a| a.total_cost)),
+        TotalCheck::new("total_current_value", summary.total_current_value, &values(|a| a.current_value)),
+        TotalCheck::new("total_unrealized_pnl", summary.total_unrealized_pnl, &values(|a| a.unrealized_pnl)),
+        TotalCheck::new("total_liabilities", summary.total_liabilities, &liabilities),
+        TotalCheck::new("net_worth", summary.net_worth, &[summary.total_current_value, -summary.total_liabilities]),
+    ];
+    for check in checks.iter().filter(|c| !c.reconciled) {
+        discrepancies.push(format!(
+            "{} is {} but its {} parts add up to {} (rounding residual {})",
+            check.field, check.reported, check.parts, check.summed, check.rounding_residual
+        ));
+    }
+
+    let reconciled = discrepancies.is_empty();
+    if !reconciled {
+        tracing::warn!("⚠️ Portfolio of {} does not reconcile: {}", user_id, discrepancies.join("; "));
+    }
+    Ok(Json(PortfolioReconciliationResponse {
+        reconciled,
+        money_decimals: MONEY_DECIMALS,
+        assets_checked: assets.len(),
+        checks,
+        discrepancies,
+    }))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RealizedMonthlyQuery {
+    /// Calendar months to return, ending with the current one (default 24, max 120)
+    pub months: Option<u32>,
+    /// Currency the amounts are converted into at today's rates (default THB)
+    pub base_currency: Option<String>,
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RealizedMonth {
+    /// YYYY-MM
+    pub month: String,
+    pub realized_pnl: f64,
+    /// Trading fees on every transaction booked in the month (opening and closing)
+    pub fees: f64,
+    /// On-chain gas / network fees booked in the month
+    pub network_fees: f64,
+    /// Trades that realized a gain or loss
+    pub closing_trades: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RealizedMonthlyResponse {
+    pub base_currency: String,
+    /// Oldest first, months without activity included as zeros
+    pub months: Vec<RealizedMonth>,
+    pub total_realized_pnl: f64,
+    pub total_fees: f64,
+    pub total_network_fees: f64,
+    pub conversions: Vec<ExchangeRate>,
+}
+
+/// GET /api/portfolio/realized/monthly - Realized P&L and fees per calendar month from the
+/// lot engine, without pricing current holdings
+pub async fn get_realized_monthly(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<RealizedMonthlyQuery>,
+) -> Result<Json<RealizedMonthlyResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let base_currency = query.base_currency
+        .map(|c| c.to_uppercase())
+        .unwrap_or_else(|| "THB".to_string());
+    let month_count = query.months.unwrap_or(24).clamp(1, 120) as i32;
+
+    let mut transactions = state.db.list_transactions(&user_id).await?;
+    if !query.include_archived {
+        let archived = state.db.archived_account_ids(&user_id).await?;
+        if !archived.is_empty() {
+            transactions.retain(|t| t.account_id.as_ref().is_none_or(|id| !archived.contains(id)));
+        }
+    }
+    let replay = lot_engine::replay(&transactions, &state.config.dust_policy);
+
+    // Month keys from oldest to the current month
+    let now = Utc::now();
+    let current = now.year() * 12 + now.month0() as i32;
+    let mut months: Vec<RealizedMonth> = (current - month_count + 1..=current)
+        .map(|m| RealizedMonth {
+            month: format!("{:04}-{:02}", m.div_euclid(12), m.rem_euclid(12) + 1),
+            realized_pnl: 0.0,
+            fees: 0.0,
+            network_fees: 0.0,
+            closing_trades: 0,
+        })
+        .collect();
+    let slot = |ts: &DateTime<Utc>| {
+        let index = ts.year() * 12 + ts.month0() as i32 - (current - month_count + 1);
+        usize::try_from(index).ok().filter(|i| *i < months.len())
+    };
+
+    let mut conversions = ConversionTrail::default();
+    let mut updates = Vec::new();
+    for trade in &replay.realized_trades {
+        if let Some(i) = slot(&trade.timestamp) {
+            let fx = state.exchange_rate_service.get_rate_recorded(&trade.currency, &base_currency, &mut conversions).await?;
+            updates.push((i, trade.pnl * fx, 0.0, 0.0, 1));
+        }
+    }
+    for tx in transactions.iter().filter(|t| t.fees != 0.0 || t.network_fee != 0.0) {
+        if let Some(i) = slot(&tx.timestamp) {
+            let currency = tx.currency.clone()
+                .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
+                .unwrap_or_else(|| "THB".to_string());
+            let fx = state.exchange_rate_service.get_rate_recorded(&currency, &base_currency, &mut conversions).await?;
+            updates.push((i, 0.0, tx.fees * fx, tx.network_fee * fx, 0));
+        }
+    }
+    for (i, pnl, fees, network_fees, trades) in updates {
+        months[i].realized_pnl += pnl;
+        months[i].fees += fees;
+        months[i].network_fees += network_fees;
+        months[i].closing_trades += trades;
+    }
+
+    Ok(Json(RealizedMonthlyResponse {
+        base_currency,
+        total_realized_pnl: months.iter().map(|m| m.realized_pnl).sum(),
+        total_fees: months.iter().map(|m| m.fees).sum(),
+        total_network_fees: months.iter().map(|m| m.network_fees).sum(),
+        months,
+        conversions: conversions.into_vec(),
+    }))
 }
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        FromRequest, Request, State,
+    },
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use crate::error::AppError;
+use crate::extract::Query;
+use crate::models::{AssetType, Market};
+use crate::services::lot_engine;
+use crate::services::price_service::PriceUpdate;
+use crate::AppState;
+
+/// Keeps idle connections open through proxies that drop silent sockets
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the subscribed symbols are re-read, so new holdings start streaming
+const HOLDINGS_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+/// How often held-back updates of a background client are checked for sending
+const BATCH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+pub struct PriceStreamQuery {
+    /// Browsers can't set headers on a WebSocket handshake, so the JWT may come here instead
+    pub token: Option<String>,
+    /// Id the client declares its power mode under (PUT /api/clients/mode)
+    pub client_id: Option<String>,
+}
+
+/// Extract user_id from the Authorization header JWT, or the `token` query parameter
+fn extract_user_id(state: &AppState, headers: &HeaderMap, query: &PriceStreamQuery) -> Result<String, AppError> {
+    let token = match headers.get("Authorization").and_then(|h| h.to_str().ok()) {
+        Some(auth_header) => auth_header
+            .strip_prefix("Bearer ")
+            .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?,
+        None => query.token.as_deref()
+            .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?,
+    };
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+/// A position the stream pushes prices for
+struct HeldPrice {
+    symbol: String,
+    asset_type: AssetType,
+    market: Option<Market>,
+}
+
+impl HeldPrice {
+    fn matches(&self, update: &PriceUpdate) -> bool {
+        self.asset_type == update.asset_type
+            && self.symbol.eq_ignore_ascii_case(&update.entry.symbol)
+            && (self.market.is_none() || update.market.is_none() || self.market == update.market)
+    }
+}
+
+/// Open positions in the user's portfolio
+async fn held_prices(state: &AppState, user_id: &str) -> Result<Vec<HeldPrice>, AppError> {
+    let transactions = state.db.list_transactions(user_id).await?;
+    let replay = lot_engine::replay(&transactions, &state.config.dust_policy);
+    let mut held: Vec<HeldPrice> = Vec::new();
+    for asset in replay.holdings.into_values().filter(|a| a.quantity.abs() > 0.00000001) {
+        let seen = held.iter().any(|h| {
+            h.asset_type == asset.asset_type && h.market == asset.market && h.symbol == asset.symbol
+        });
+        if !seen {
+            held.push(HeldPrice { symbol: asset.symbol, asset_type: asset.asset_type, market: asset.market });
+        }
+    }
+    Ok(held)
+}
+
+async fn send_update(socket: &mut WebSocket, update: &PriceUpdate) -> bool {
+    let Ok(text) = serde_json::to_string(update) else {
+        return true;
+    };
+    socket.send(Message::Text(text)).await.is_ok()
+}
+
+/// GET /api/ws/prices - WebSocket pushing a JSON `PriceUpdate` whenever a price of a symbol
+/// in the user's portfolio is fetched into the cache. Cached prices are sent on connect.
+pub async fn stream_prices(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<PriceStreamQuery>,
+    request: Request,
+) -> Result<Response, AppError> {
+    // Authenticate before the upgrade, so anonymous handshakes get a 401 rather than a 426
+    let user_id = extract_user_id(&state, &headers, &query)?;
+    let held = held_prices(&state, &user_id).await?;
+    let upgrade = match WebSocketUpgrade::from_request(request, &state).await {
+        Ok(upgrade) => upgrade,
+        Err(rejection) => return Ok(rejection.into_response()),
+    };
+
+    tracing::info!("📡 Price stream opened for {} ({} symbols)", user_id, held.len());
+    Ok(upgrade.on_upgrade(move |socket| run_stream(state, user_id, query.client_id, held, socket)))
+}
+
+async fn run_stream(state: AppState, user_id: String, client_id: Option<String>, mut held: Vec<HeldPrice>, mut socket: WebSocket) {
+    // Subscribe first so nothing fetched while the cached prices are sent is missed
+    let mut updates = state.price_service.subscribe();
+
+    for position in &held {
+        let cached = state.price_service
+            .cached_price(&position.symbol, &position.asset_type, position.market.as_ref())
+            .await;
+        if let Some(lookup) = cached {
+            let update = PriceUpdate {
+                asset_type: position.asset_type.clone(),
+                market: position.market.clone(),
+                entry: lookup.entry,
+            };
+            if !send_update(&mut socket, &update).await {
+                return;
+            }
+        }
+    }
+
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    let mut refresh = tokio::time::interval(HOLDINGS_REFRESH_INTERVAL);
+    refresh.reset();
+    // Latest held-back update per symbol while the client is in a low-power mode
+    let mut batched: HashMap<String, PriceUpdate> = HashMap::new();
+    let mut last_sent = tokio::time::Instant::now();
+    let mut batch_check = tokio::time::interval(BATCH_CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => {
+                    if !held.iter().any(|h| h.matches(&update)) {
+                        continue;
+                    }
+                    if state.client_modes.mode(client_id.as_deref()).await.push_interval().is_some() {
+                        let key = format!("{}:{:?}:{}", update.asset_type, update.market, update.entry.symbol.to_uppercase());
+                        batched.insert(key, update);
+                        continue;
+                    }
+                    if !send_update(&mut socket, &update).await {
+                        break;
+                    }
+                    last_sent = tokio::time::Instant::now();
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("⚠️ Price stream for {} fell behind, skipped {} updates", user_id, skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                // Clients only listen; pings are answered by the socket itself
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = ping.tick() => {
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+            _ = refresh.tick() => match held_prices(&state, &user_id).await {
+                Ok(positions) => held = positions,
+                Err(e) => tracing::warn!("⚠️ Could not refresh streamed symbols for {}: {}", user_id, e),
+            },
+            _ = batch_check.tick(), if !batched.is_empty() => {
+                // A client back in the foreground gets what was held back right away
+                let due = match state.client_modes.mode(client_id.as_deref()).await.push_interval() {
+                    Some(every) => last_sent.elapsed() >= every,
+                    None => true,
+                };
+                if due {
+                    let mut closed = false;
+                    for (_, update) in batched.drain() {
+                        if !send_update(&mut socket, &update).await {
+                            closed = true;
+                            break;
+                        }
+                    }
+                    if closed {
+                        break;
+                    }
+                    last_sent = tokio::time::Instant::now();
+                }
+            }
+        }
+    }
+    tracing::info!("📡 Price stream closed for {}", user_id);
+}
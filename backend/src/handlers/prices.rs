@@ -1,18 +1,22 @@
-use axum::{
-    extract::{Path, Query, State},
-    Json,
-};
+use axum::{extract::State, http::HeaderMap, Json};
 use serde::Deserialize;
 use std::collections::HashMap;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use crate::error::AppError;
+use crate::extract::{Path, Query};
 use crate::models::{AssetType, Market};
-use crate::services::price_service::PriceEntry;
+use crate::services::price_history::{load_candles, CandleInterval, CandleSeries};
+use crate::services::providers::thai_gold::ThaiGoldQuote;
+use crate::services::price_service::{stored_price_source, PriceEntry, PriceIncident};
+use crate::services::rate_limiter::RateLimitInfo;
+use crate::services::symbol_heat::SymbolHeatEntry;
+use crate::services::ticker_feeds::FeedStatus;
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
 pub struct GetPriceQuery {
-    pub asset_type: String,
-    pub market: Option<String>,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,22 +33,57 @@ pub struct BatchPriceSymbol {
 
 #[derive(Debug, Deserialize)]
 pub struct GetHistoryQuery {
-    pub asset_type: String,
-    pub market: Option<String>,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
     pub days: Option<u32>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CandleQuery {
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+    #[serde(default)]
+    pub interval: CandleInterval,
+    /// YYYY-MM-DD or RFC 3339; defaults to a span that suits the interval
+    pub from: Option<String>,
+    /// YYYY-MM-DD (whole day) or RFC 3339; defaults to now
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HeatQuery {
+    pub limit: Option<usize>,
+}
+
+/// Count a view towards a symbol's heat. Background clients polling prices shouldn't keep
+/// symbols hot, and anonymous callers only count for symbols in the symbols collection.
+async fn record_view(state: &AppState, headers: &HeaderMap, symbol: &str, asset_type: &AssetType) {
+    if !state.client_modes.mode_for(headers).await.is_active() {
+        return;
+    }
+    let signed_in = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .is_some_and(|token| state.auth_service.verify_jwt(token).is_ok());
+    if signed_in || state.symbols_service.is_listed(symbol, &asset_type.to_string()).await {
+        state.symbol_heat.record_view(symbol, asset_type).await;
+    }
+}
 
 /// Get current price for a single symbol
-/// First tries external API, saves to PocketBase, then falls back to manual price if API fails
+/// First tries external API, saves to PocketBase, then falls back to manual price if API fails.
+/// When the provider is rate limited the X-RateLimit-* headers say when a live price is available again.
 pub async fn get_price(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(symbol): Path<String>,
     Query(query): Query<GetPriceQuery>,
-) -> Result<Json<PriceEntry>, AppError> {
-    let asset_type = parse_asset_type(&query.asset_type)?;
-    let market = query.market.as_ref().map(|m| parse_market(m)).transpose()?;
+) -> Result<(HeaderMap, Json<PriceEntry>), AppError> {
+    let GetPriceQuery { asset_type, market } = query;
     let pb_url = &state.config.pocketbase_url;
+    record_view(&state, &headers, &symbol, &asset_type).await;
+    let mut rate_limit: Option<RateLimitInfo> = None;
     
     // First, try to get price from external API
     match state.price_service.get_price(&symbol, &asset_type, market.as_ref()).await {
@@ -55,15 +94,20 @@ pub async fn get_price(
             // Normalize market to lowercase to prevent duplicates
             let market_str = market.as_ref().map(|m| m.to_string().to_lowercase()).unwrap_or_default();
             let _ = save_price_to_pocketbase(
+                &state.db.http(),
                 pb_url,
                 &symbol.to_uppercase(),
-                &query.asset_type.to_lowercase(),
+                &asset_type.to_string(),
                 &market_str,
                 price_entry.price,
                 &price_entry.currency,
             ).await;
             
-            return Ok(Json(price_entry));
+            return Ok((HeaderMap::new(), Json(price_entry)));
+        }
+        Err(AppError::RateLimited(info)) => {
+            tracing::warn!("API price fetch for {} rate limited by {}, trying manual price", symbol, info.provider);
+            rate_limit = Some(info);
         }
         Err(e) => {
             tracing::warn!("API price fetch failed for {}: {}, trying manual price", symbol, e);
@@ -76,36 +120,41 @@ pub async fn get_price(
     } else {
         String::new()
     };
-    let filter = format!("symbol='{}' && asset_type='{}'{}", symbol.to_uppercase(), query.asset_type.to_lowercase(), market_filter);
+    let filter = format!("symbol='{}' && asset_type='{}'{}", symbol.to_uppercase(), asset_type, market_filter);
     let check_url = format!(
         "{}/api/collections/asset_prices/records?filter={}",
         pb_url,
         urlencoding::encode(&filter)
     );
     
-    if let Ok(response) = reqwest::Client::new().get(&check_url).send().await {
+    if let Ok(response) = state.db.http().get(&check_url).send().await {
         if response.status().is_success() {
             if let Ok(data) = response.json::<serde_json::Value>().await {
                 if let Some(items) = data.get("items").and_then(|i| i.as_array()) {
                     if let Some(first) = items.first() {
                         if let Some(price) = first.get("price").and_then(|p| p.as_f64()) {
+                            let (source, fetched_at) = stored_price_source(first);
                             // Sanity check for Thai Gold: Ignore price if > 1M (likely unit conversion error stored previously)
                             if symbol.to_uppercase() == "GOLD96.5" && price > 1_000_000.0 {
                                 tracing::warn!("⚠️ Found invalid/stale price for {}: {}. Ignoring fallback.", symbol, price);
+                            } else if state.config.strict_price_data && source == "mock" {
+                                tracing::warn!("⚠️ Stored price for {} is mock data, ignored under STRICT_PRICE_DATA", symbol);
                             } else {
                                 let currency = first.get("currency")
                                     .and_then(|c| c.as_str())
                                     .unwrap_or("THB")
                                     .to_string();
                                 
-                                tracing::debug!("📊 Using manual price for {}: {} {}", symbol, price, currency);
+                                tracing::debug!("📊 Using manual price for {}: {} {} ({})", symbol, price, currency, source);
                                 
-                                return Ok(Json(PriceEntry {
+                                let headers = rate_limit.as_ref().map(RateLimitInfo::headers).unwrap_or_default();
+                                return Ok((headers, Json(PriceEntry {
                                     symbol: symbol.clone(),
                                     price,
                                     currency,
-                                    updated_at: chrono::Utc::now(),
-                                }));
+                                    updated_at: fetched_at.unwrap_or_else(chrono::Utc::now),
+                                    source: Some(source),
+                                })));
                             }
                         }
                     }
@@ -114,11 +163,15 @@ pub async fn get_price(
         }
     }
     
-    Err(AppError::ExternalApiError(format!("Could not get price for {}", symbol)))
+    match rate_limit {
+        Some(info) => Err(AppError::RateLimited(info)),
+        None => Err(AppError::ExternalApiError(format!("Could not get price for {}", symbol))),
+    }
 }
 
 /// Save price to PocketBase asset_prices collection
 async fn save_price_to_pocketbase(
+    client: &reqwest::Client,
     pb_url: &str,
     symbol: &str,
     asset_type: &str,
@@ -126,8 +179,7 @@ async fn save_price_to_pocketbase(
     price: f64,
     currency: &str,
 ) -> Result<(), String> {
-    let client = reqwest::Client::new();
-    
+
     // Check if record exists
     let filter = if market.is_empty() {
         format!("symbol='{}' && asset_type='{}'", symbol, asset_type)
@@ -181,30 +233,32 @@ async fn save_price_to_pocketbase(
     Ok(())
 }
 
-/// Get prices for multiple symbols in batch
+/// Get prices for multiple symbols in batch.
+/// Rate-limited symbols carry a `rate_limit` object, and the X-RateLimit-* headers describe
+/// the limit that resets last (when every symbol can be fetched again).
 pub async fn get_prices_batch(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<BatchPriceRequest>,
-) -> Result<Json<HashMap<String, serde_json::Value>>, AppError> {
+) -> Result<(HeaderMap, Json<HashMap<String, serde_json::Value>>), AppError> {
     let mut results = HashMap::new();
+    let mut latest_limit: Option<RateLimitInfo> = None;
     
     for item in req.symbols {
-        let asset_type = match parse_asset_type(&item.asset_type) {
+        // Invalid items are reported per symbol rather than failing the whole batch
+        let asset_type = match item.asset_type.parse::<AssetType>() {
             Ok(t) => t,
             Err(e) => {
                 results.insert(
                     item.symbol.clone(),
-                    serde_json::json!({ "error": e.to_string() }),
+                    serde_json::json!({ "error": e }),
                 );
                 continue;
             }
         };
         
-        let market = item.market.as_ref()
-            .map(|m| parse_market(m))
-            .transpose()
-            .ok()
-            .flatten();
+        let market = item.market.as_ref().and_then(|m| m.parse::<Market>().ok());
+        record_view(&state, &headers, &item.symbol, &asset_type).await;
         
         match state.price_service.get_price(&item.symbol, &asset_type, market.as_ref()).await {
             Ok(price) => {
@@ -213,6 +267,15 @@ pub async fn get_prices_batch(
                     serde_json::to_value(price).unwrap_or_default(),
                 );
             }
+            Err(AppError::RateLimited(info)) => {
+                results.insert(
+                    item.symbol.clone(),
+                    serde_json::json!({ "error": AppError::RateLimited(info.clone()).to_string(), "rate_limit": info }),
+                );
+                if latest_limit.as_ref().is_none_or(|l| info.resets_at > l.resets_at) {
+                    latest_limit = Some(info);
+                }
+            }
             Err(e) => {
                 results.insert(
                     item.symbol.clone(),
@@ -222,74 +285,118 @@ pub async fn get_prices_batch(
         }
     }
     
-    Ok(Json(results))
+    let headers = latest_limit.as_ref().map(RateLimitInfo::headers).unwrap_or_default();
+    Ok((headers, Json(results)))
 }
 
 /// Get price history for a symbol
 pub async fn get_price_history(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(symbol): Path<String>,
     Query(query): Query<GetHistoryQuery>,
 ) -> Result<Json<Vec<crate::services::price_service::HistoryEntry>>, AppError> {
-    let asset_type = parse_asset_type(&query.asset_type)?;
-    let market = query.market.as_ref().map(|m| parse_market(m)).transpose()?;
-    let days = query.days.unwrap_or(30);
+    let GetHistoryQuery { asset_type, market, days } = query;
+    let days = days.unwrap_or(30);
+    record_view(&state, &headers, &symbol, &asset_type).await;
 
     let history = state.price_service.get_price_history(&symbol, &asset_type, market.as_ref(), days).await?;
     
     Ok(Json(history))
 }
 
+/// A `from`/`to` bound; plain dates start at midnight UTC, or end at the next midnight for `to`
+fn parse_bound(value: &str, end_of_day: bool) -> Result<DateTime<Utc>, AppError> {
+    if let Ok(at) = value.parse::<DateTime<Utc>>() {
+        return Ok(at);
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::BadRequest(format!("Invalid date '{}', expected YYYY-MM-DD or RFC 3339", value)))?;
+    let date = if end_of_day { date + Duration::days(1) } else { date };
+    Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+}
+
+/// GET /api/prices/:symbol/history - OHLC candles from the recorded price history
+pub async fn get_price_candles(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(symbol): Path<String>,
+    Query(query): Query<CandleQuery>,
+) -> Result<Json<CandleSeries>, AppError> {
+    let to = match query.to.as_deref() {
+        Some(to) => parse_bound(to, true)?,
+        None => Utc::now(),
+    };
+    let from = match query.from.as_deref() {
+        Some(from) => parse_bound(from, false)?,
+        None => to - query.interval.default_span(),
+    };
+    record_view(&state, &headers, &symbol, &query.asset_type).await;
+
+    let series = load_candles(&state.db, &symbol, &query.asset_type, query.market.as_ref(), query.interval, from, to).await?;
+    Ok(Json(series))
+}
 
 /// Clear price cache
 pub async fn clear_price_cache(
     State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    let admin_id = super::users::extract_admin_user_id(&state, &headers)?;
     state.price_service.clear_cache().await;
+    tracing::info!("🧹 Price cache cleared by admin {}", admin_id);
     Ok(Json(serde_json::json!({
         "message": "Price cache cleared"
     })))
 }
 
-fn parse_asset_type(s: &str) -> Result<AssetType, AppError> {
-    match s.to_lowercase().as_str() {
-        "stock" => Ok(AssetType::Stock),
-        "tfex" => Ok(AssetType::Tfex),
-        "crypto" => Ok(AssetType::Crypto),
-        "foreign_stock" | "foreignstock" => Ok(AssetType::ForeignStock),
-        "gold" => Ok(AssetType::Gold),
-        "commodity" => Ok(AssetType::Commodity),
-        _ => Err(AppError::BadRequest(format!(
-            "Invalid asset type: {}. Must be one of: stock, tfex, crypto, foreign_stock, gold, commodity",
-            s
-        ))),
-    }
+/// Symbols ranked by recent view/holding activity (drives background refresh frequency)
+pub async fn get_symbol_heat(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<HeatQuery>,
+) -> Result<Json<Vec<SymbolHeatEntry>>, AppError> {
+    super::users::extract_admin_user_id(&state, &headers)?;
+    let limit = query.limit.unwrap_or(100).min(1000);
+    Ok(Json(state.symbol_heat.ranking(limit).await))
 }
 
-fn parse_market(s: &str) -> Result<Market, AppError> {
-    match s.to_lowercase().as_str() {
-        "set" => Ok(Market::Set),
-        "mai" => Ok(Market::Mai),
-        "tfex" => Ok(Market::Tfex),
-        "nyse" => Ok(Market::Nyse),
-        "nasdaq" => Ok(Market::Nasdaq),
-        "amex" => Ok(Market::Amex),
-        "lse" => Ok(Market::Lse),
-        "euronext" => Ok(Market::Euronext),
-        "xetra" => Ok(Market::Xetra),
-        "hkex" => Ok(Market::Hkex),
-        "tse" => Ok(Market::Tse),
-        "sgx" => Ok(Market::Sgx),
-        "krx" => Ok(Market::Krx),
-        "binance" => Ok(Market::Binance),
-        "coinbase" => Ok(Market::Coinbase),
-        "bitkub" => Ok(Market::Bitkub),
-        "okx" => Ok(Market::Okx),
-        "htx" => Ok(Market::Htx),
-        "kucoin" => Ok(Market::Kucoin),
-        "comex" => Ok(Market::Comex),
-        "lbma" => Ok(Market::Lbma),
-        "local" => Ok(Market::Local),
-        _ => Err(AppError::BadRequest(format!("Invalid market: {}", s))),
-    }
+/// Connection state of the crypto exchange ticker feeds (empty unless CRYPTO_PRICE_STREAMS is on)
+pub async fn get_ticker_feeds(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<FeedStatus>>, AppError> {
+    Ok(Json(state.price_service.ticker_feed_status().await))
+}
+
+/// Thai 96.5% gold bar buy/sell prices in THB per baht-weight
+pub async fn get_thai_gold_quote(
+    State(state): State<AppState>,
+) -> Result<Json<ThaiGoldQuote>, AppError> {
+    Ok(Json(state.price_service.thai_gold_quote().await?))
+}
+
+/// Get prices held back by anomaly detection
+pub async fn get_price_quarantine(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PriceIncident>>, AppError> {
+    super::users::extract_admin_user_id(&state, &headers)?;
+    Ok(Json(state.price_service.get_quarantined().await))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseQuarantineRequest {
+    pub cache_key: String,
+}
+
+/// Accept a quarantined price as genuine and put it into the cache
+pub async fn release_price_quarantine(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<ReleaseQuarantineRequest>,
+) -> Result<Json<PriceEntry>, AppError> {
+    let admin_id = super::users::extract_admin_user_id(&state, &headers)?;
+    let entry = state.price_service.release_quarantined(&payload.cache_key).await?;
+    tracing::info!("🔓 Quarantined price {} released by admin {}", payload.cache_key, admin_id);
+    Ok(Json(entry))
 }
@@ -1,10 +1,15 @@
 use axum::{
     extract::State,
+    http::HeaderMap,
     Json,
 };
 use serde::Serialize;
 use crate::AppState;
+use crate::client_ip::ClientIp;
 use crate::error::AppError;
+use crate::extract::Path;
+use crate::services::rate_limiter::{RateLimitConfig, RateLimitOverride, RateLimitOverrideRequest};
+use super::users::extract_admin_user_id;
 
 #[derive(Debug, Serialize)]
 pub struct RateLimitStatusResponse {
@@ -18,15 +23,12 @@ pub struct RateLimitStatusResponse {
     pub is_blocked: bool,
     pub blocked_until: Option<String>,
     pub last_request_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_override: Option<RateLimitOverride>,
 }
 
-/// GET /api/rate-limits - Get all rate limit statuses
-pub async fn get_rate_limits(
-    State(state): State<AppState>,
-) -> Result<Json<Vec<RateLimitStatusResponse>>, AppError> {
-    let limits = state.rate_limiter.get_all_limits().await;
-    
-    let response: Vec<RateLimitStatusResponse> = limits.into_iter().map(|l| {
+impl From<RateLimitConfig> for RateLimitStatusResponse {
+    fn from(l: RateLimitConfig) -> Self {
         let minute_remaining = (l.requests_per_minute - l.current_minute_count).max(0);
         let day_remaining = l.requests_per_day.map(|d| (d - l.current_day_count).max(0));
         
@@ -41,8 +43,43 @@ pub async fn get_rate_limits(
             is_blocked: l.is_blocked,
             blocked_until: l.blocked_until,
             last_request_at: l.last_request_at,
+            active_override: l.override_state,
         }
-    }).collect();
+    }
+}
+
+/// GET /api/rate-limits - Get all rate limit statuses
+pub async fn get_rate_limits(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<RateLimitStatusResponse>>, AppError> {
+    let limits = state.rate_limiter.get_all_limits().await;
+    
+    let response: Vec<RateLimitStatusResponse> = limits.into_iter().map(RateLimitStatusResponse::from).collect();
     
     Ok(Json(response))
 }
+
+/// POST /api/admin/rate-limits/:api_name/override - Temporarily raise limits or grant burst tokens (admin only)
+pub async fn override_rate_limit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    client_ip: ClientIp,
+    Path(api_name): Path<String>,
+    Json(payload): Json<RateLimitOverrideRequest>,
+) -> Result<Json<RateLimitStatusResponse>, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    let config = state.rate_limiter.apply_override(&api_name, payload, &admin_id, client_ip).await?;
+    Ok(Json(config.into()))
+}
+
+/// DELETE /api/admin/rate-limits/:api_name/override - Restore original limits now (admin only)
+pub async fn clear_rate_limit_override(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    client_ip: ClientIp,
+    Path(api_name): Path<String>,
+) -> Result<Json<RateLimitStatusResponse>, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    let config = state.rate_limiter.clear_override(&api_name, &admin_id, client_ip).await?;
+    Ok(Json(config.into()))
+}
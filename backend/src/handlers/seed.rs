@@ -1,10 +1,13 @@
 use axum::{
     extract::State,
+    http::HeaderMap,
     Json,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::error::AppError;
 use crate::AppState;
+use super::users::extract_admin_user_id;
 
 /// Seed data structure (matches seed-data.json format)
 #[derive(Debug, Deserialize)]
@@ -39,14 +42,17 @@ pub struct SeedResponse {
     pub results: Vec<SeedResult>,
 }
 
-/// POST /api/seed/upload - Upload and process seed data
+/// POST /api/seed/upload - Upload and process seed data (admin only)
 pub async fn upload_seed(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(seed_data): Json<SeedData>,
-) -> Json<SeedResponse> {
-    tracing::info!("🌱 Starting seed upload process...");
+) -> Result<Json<SeedResponse>, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    tracing::info!("🌱 Starting seed upload process (admin {})...", admin_id);
     
     let pb_url = &state.config.pocketbase_url;
+    let client = state.db.http();
     let mut results = Vec::new();
     
     // Collection order matters for dependencies
@@ -67,6 +73,7 @@ pub async fn upload_seed(
         tracing::info!("📥 Seeding {} ({} records)...", collection_name, records.len());
         
         let result = seed_collection(
+            &client,
             pb_url,
             collection_name,
             records,
@@ -83,23 +90,23 @@ pub async fn upload_seed(
     tracing::info!("🎉 Seed complete: {} created, {} skipped, {} errors", 
         total_created, total_skipped, total_errors);
     
-    Json(SeedResponse {
+    Ok(Json(SeedResponse {
         success: total_errors == 0,
         message: format!(
             "Seed complete: {} created, {} skipped, {} errors",
             total_created, total_skipped, total_errors
         ),
         results,
-    })
+    }))
 }
 
 async fn seed_collection(
+    client: &reqwest::Client,
     pb_url: &str,
     collection_name: &str,
     records: &[serde_json::Value],
     unique_fields: &[&str],
 ) -> SeedResult {
-    let client = reqwest::Client::new();
     let mut created = 0;
     let mut skipped = 0;
     let mut errors = 0;
@@ -222,14 +229,16 @@ pub struct ExportStats {
     pub asset_prices: usize,
 }
 
-/// GET /api/seed/export - Export current seed data
+/// GET /api/seed/export - Export current seed data (admin only, includes users)
 pub async fn export_seed(
     State(state): State<AppState>,
-) -> Json<ExportResponse> {
-    tracing::info!("📤 Starting seed export process...");
+    headers: HeaderMap,
+) -> Result<Json<ExportResponse>, AppError> {
+    let admin_id = extract_admin_user_id(&state, &headers)?;
+    tracing::info!("📤 Starting seed export process (admin {})...", admin_id);
     
     let pb_url = &state.config.pocketbase_url;
-    let client = reqwest::Client::new();
+    let client = state.db.http();
     
     // Collections to export (same order as upload)
     let collections = vec![
@@ -282,12 +291,12 @@ pub async fn export_seed(
     
     tracing::info!("📤 Export complete: {} total records", total);
     
-    Json(ExportResponse {
+    Ok(Json(ExportResponse {
         success: true,
         message: format!("Exported {} records", total),
         data: Some(export_data),
         stats,
-    })
+    }))
 }
 
 async fn fetch_collection(
@@ -0,0 +1,97 @@
+use axum::{extract::State, http::HeaderMap, Json};
+use chrono::Utc;
+use serde::Serialize;
+use crate::error::AppError;
+use crate::models::{UserSettings, USER_SETTINGS_COLLECTION};
+use crate::services::digest::{parse_digest_time, parse_utc_offset};
+use crate::services::export_format::{load_user_settings, ExportLocale};
+use crate::AppState;
+
+/// Extract user_id from Authorization header JWT
+fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::Unauthorized("Invalid Authorization header format".to_string()))?;
+
+    let claims = state.auth_service.verify_jwt(token)?;
+    Ok(claims.sub)
+}
+
+/// How a sample value looks in the user's exports
+#[derive(Debug, Serialize)]
+pub struct ExportPreview {
+    pub number: String,
+    pub date: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserSettingsResponse {
+    #[serde(flatten)]
+    pub settings: UserSettings,
+    /// The settings merged with their locale preset, as exports apply them
+    pub export: ExportLocale,
+    pub export_preview: ExportPreview,
+}
+
+fn settings_response(settings: UserSettings, export: ExportLocale) -> UserSettingsResponse {
+    let now = Utc::now();
+    let export_preview = ExportPreview {
+        number: export.number(1234.56),
+        date: export.date(now.date_naive()),
+        timestamp: export.timestamp(now),
+    };
+    UserSettingsResponse { settings, export, export_preview }
+}
+
+/// GET /api/settings - The user's settings and the export format they produce
+pub async fn get_user_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<UserSettingsResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let settings = load_user_settings(&state.db, &user_id).await?;
+    let export = ExportLocale::from_settings(&settings).unwrap_or_default();
+    Ok(Json(settings_response(settings, export)))
+}
+
+/// PUT /api/settings - Replace the user's settings; omitted or empty fields use the locale preset
+pub async fn update_user_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<UserSettings>,
+) -> Result<Json<UserSettingsResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let export = ExportLocale::from_settings(&req).map_err(AppError::BadRequest)?;
+    let timezone = req.timezone.as_deref().map(str::trim).unwrap_or_default();
+    if !timezone.is_empty() && parse_utc_offset(timezone).is_none() {
+        return Err(AppError::BadRequest(format!("Invalid timezone '{}', expected a UTC offset such as +07:00", timezone)));
+    }
+    let digest_time = req.digest_time.as_deref().map(str::trim).unwrap_or_default();
+    if !digest_time.is_empty() && parse_digest_time(digest_time).is_none() {
+        return Err(AppError::BadRequest(format!("Invalid digest_time '{}', expected HH:MM", digest_time)));
+    }
+
+    let body = serde_json::json!({
+        "user_id": user_id,
+        "locale": req.locale.as_deref().map(str::trim).unwrap_or_default(),
+        "decimal_separator": req.decimal_separator.as_deref().map(str::trim).unwrap_or_default(),
+        "calendar": req.calendar.as_deref().map(str::trim).unwrap_or_default(),
+        "date_pattern": req.date_pattern.as_deref().map(str::trim).unwrap_or_default(),
+        "timezone": timezone,
+        "digest_time": digest_time,
+    });
+    let existing = load_user_settings(&state.db, &user_id).await?;
+    let saved: UserSettings = if existing.id.is_empty() {
+        state.db.create_record(USER_SETTINGS_COLLECTION, &body).await?
+    } else {
+        state.db.update_record(USER_SETTINGS_COLLECTION, &existing.id, &body).await?
+    };
+    tracing::info!("⚙️ Saved settings for {}", user_id);
+    Ok(Json(settings_response(saved, export)))
+}
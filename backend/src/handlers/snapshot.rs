@@ -1,10 +1,17 @@
+use std::convert::Infallible;
 use axum::{
-    extract::{Query, State},
-    http::HeaderMap,
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use crate::error::AppError;
+use crate::extract::Query;
+use crate::models::PortfolioSnapshot;
+use crate::services::export_format::{export_locale, ExportLocale};
+use crate::services::snapshot_cache::CachedSeries;
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -14,40 +21,11 @@ pub struct SnapshotQuery {
     pub to: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PortfolioSnapshot {
-    #[serde(default)]
-    pub id: String,
-    #[serde(default)]
-    pub user_id: String,
-    #[serde(default)]
-    pub account_id: Option<String>,
-    #[serde(default)]
-    pub date: String,
-    #[serde(default)]
-    pub total_invested: f64,
-    #[serde(default)]
-    pub total_current_value: f64,
-    #[serde(default)]
-    pub total_unrealized_pnl: f64,
-    #[serde(default)]
-    pub total_unrealized_pnl_percent: f64,
-    #[serde(default)]
-    pub total_realized_pnl: f64,
-    #[serde(default)]
-    pub assets_count: serde_json::Value, // Flexible type - can be number or null
-    #[serde(default)]
-    pub currency: String,
-    #[serde(default)]
-    pub assets: Option<serde_json::Value>,
-    // Catch any other fields from PocketBase
-    #[serde(flatten)]
-    pub extra: std::collections::HashMap<String, serde_json::Value>,
-}
-
 #[derive(Debug, Deserialize)]
 struct PocketBaseResponse {
     items: Vec<PortfolioSnapshot>,
+    #[serde(rename = "totalPages", default)]
+    total_pages: u32,
 }
 
 /// Extract user_id from Authorization header JWT
@@ -65,48 +43,199 @@ fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppE
     Ok(claims.sub)
 }
 
-/// GET /api/snapshots - Get portfolio snapshots for the logged-in user
+/// Read every snapshot of a user from PocketBase, page by page
+async fn fetch_snapshots(state: &AppState, user_id: &str) -> Result<Vec<PortfolioSnapshot>, AppError> {
+    let filter = format!("user_id='{}'", user_id);
+    let token = state.db.get_token().await;
+    let client = state.db.http();
+
+    let mut snapshots = Vec::new();
+    let mut page = 1;
+    loop {
+        let url = format!(
+            "{}/api/collections/portfolio_snapshots/records?filter={}&sort=date&perPage=500&page={}",
+            state.config.pocketbase_url,
+            urlencoding::encode(&filter),
+            page
+        );
+        let req = client.get(&url);
+        let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
+
+        let response = req.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch snapshots: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(AppError::Internal("Failed to fetch snapshots".to_string()));
+        }
+        let data: PocketBaseResponse = response.json().await
+            .map_err(|e| AppError::Internal(format!("Failed to parse snapshots: {}", e)))?;
+
+        snapshots.extend(data.items);
+        if page >= data.total_pages {
+            break;
+        }
+        page += 1;
+    }
+    Ok(snapshots)
+}
+
+/// The user's full snapshot series, from memory when possible
+async fn snapshot_series(state: &AppState, user_id: &str) -> Result<CachedSeries, AppError> {
+    if let Some(series) = state.snapshot_cache.get(user_id).await {
+        return Ok(series);
+    }
+    let snapshots = fetch_snapshots(state, user_id).await?;
+    tracing::debug!("📸 Loaded {} snapshots for {} into cache", snapshots.len(), user_id);
+    Ok(state.snapshot_cache.put(user_id, snapshots).await)
+}
+
+/// Inclusive date bounds (YYYY-MM-DD) for a snapshot query
+fn query_range(query: &SnapshotQuery) -> (Option<String>, Option<String>) {
+    let day = |d: &String| d.get(..10).unwrap_or(d).to_string();
+    if query.from.is_some() || query.to.is_some() {
+        (query.from.as_ref().map(day), query.to.as_ref().map(day))
+    } else if let Some(days) = query.days {
+        let from_date = chrono::Utc::now() - chrono::Duration::days(days as i64);
+        (Some(from_date.format("%Y-%m-%d").to_string()), None)
+    } else {
+        (None, None)
+    }
+}
+
+/// Snapshots of a user within the query's date range
+pub(crate) async fn load_snapshots(state: &AppState, user_id: &str, query: &SnapshotQuery) -> Result<Vec<PortfolioSnapshot>, AppError> {
+    let series = snapshot_series(state, user_id).await?;
+    let (from, to) = query_range(query);
+    Ok(series.range(from.as_deref(), to.as_deref()).to_vec())
+}
+
+/// GET /api/snapshots - Get portfolio snapshots for the logged-in user.
+/// Served from the in-memory series with an ETag; `If-None-Match` gets a 304.
 pub async fn get_snapshots(
     State(state): State<AppState>,
     headers: HeaderMap,
     Query(query): Query<SnapshotQuery>,
-) -> Result<Json<Vec<PortfolioSnapshot>>, AppError> {
+) -> Result<Response, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
-    
-    // Build filter
-    let mut filter = format!("user_id='{}'", user_id);
-    
-    // Date filtering
-    if let (Some(from), Some(to)) = (&query.from, &query.to) {
-        filter.push_str(&format!(" && date >= '{}' && date <= '{}'", from, to));
-    } else if let Some(days) = query.days {
-        let from_date = chrono::Utc::now() - chrono::Duration::days(days as i64);
-        let from_str = from_date.format("%Y-%m-%d").to_string();
-        filter.push_str(&format!(" && date >= '{}'", from_str));
+    let series = snapshot_series(&state, &user_id).await?;
+    let (from, to) = query_range(&query);
+    let etag = series.etag(&user_id, from.as_deref(), to.as_deref());
+
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, "private, no-cache".to_string()),
+    ];
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|t| t.trim() == etag || t.trim() == "*"));
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
     }
-    
-    let url = format!(
-        "{}/api/collections/portfolio_snapshots/records?filter={}&sort=date&perPage=500",
-        state.config.pocketbase_url,
-        urlencoding::encode(&filter)
+
+    let snapshots = series.range(from.as_deref(), to.as_deref());
+    Ok((cache_headers, Json(snapshots)).into_response())
+}
+
+/// Row layout of the snapshot CSV export
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotCsvColumns {
+    /// One row per snapshot with the portfolio totals
+    #[default]
+    Totals,
+    /// One row per asset held in each snapshot
+    Assets,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotExportQuery {
+    pub days: Option<i32>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    #[serde(default)]
+    pub columns: SnapshotCsvColumns,
+}
+
+const TOTALS_HEADER: &str = "date,account_id,currency,total_invested,total_current_value,total_unrealized_pnl,total_unrealized_pnl_percent,total_realized_pnl,assets_count\n";
+const ASSETS_HEADER: &str = "date,account_id,currency,symbol,asset_type,market,quantity,avg_cost,current_price,current_value,unrealized_pnl,unrealized_pnl_percent,price_source,price_updated_at\n";
+
+fn totals_row(snapshot: &PortfolioSnapshot, locale: &ExportLocale) -> String {
+    locale.csv_line(&[
+        locale.date_str(snapshot.day()),
+        snapshot.account_id.clone().unwrap_or_default(),
+        snapshot.currency.clone(),
+        locale.number(snapshot.total_invested),
+        locale.number(snapshot.total_current_value),
+        locale.number(snapshot.total_unrealized_pnl),
+        locale.number(snapshot.total_unrealized_pnl_percent),
+        locale.number(snapshot.total_realized_pnl),
+        match &snapshot.assets_count {
+            serde_json::Value::Null => String::new(),
+            count => count.to_string(),
+        },
+    ])
+}
+
+fn asset_rows(snapshot: &PortfolioSnapshot, locale: &ExportLocale) -> String {
+    let Some(assets) = snapshot.assets.as_ref().and_then(|a| a.as_array()) else {
+        return String::new();
+    };
+    let field = |asset: &serde_json::Value, key: &str| locale.value(asset.get(key));
+    assets.iter()
+        .map(|asset| locale.csv_line(&[
+            locale.date_str(snapshot.day()),
+            snapshot.account_id.clone().unwrap_or_default(),
+            snapshot.currency.clone(),
+            field(asset, "symbol"),
+            field(asset, "asset_type"),
+            field(asset, "market"),
+            field(asset, "quantity"),
+            field(asset, "avg_cost"),
+            field(asset, "current_price"),
+            field(asset, "current_value"),
+            field(asset, "unrealized_pnl"),
+            field(asset, "unrealized_pnl_percent"),
+            field(asset, "price_source"),
+            asset.get("price_updated_at")
+                .and_then(|v| v.as_str()?.parse::<chrono::DateTime<chrono::Utc>>().ok())
+                .map(|at| locale.timestamp(at))
+                .unwrap_or_default(),
+        ]))
+        .collect()
+}
+
+/// GET /api/snapshots/export.csv - Snapshot history as CSV, totals or one row per asset, in the
+/// user's export number and date format. Rows are formatted as the body streams, so multi-year
+/// ranges don't build one big string.
+pub async fn export_snapshots_csv(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SnapshotExportQuery>,
+) -> Result<Response, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let range = SnapshotQuery { days: query.days, from: query.from, to: query.to };
+    let snapshots = load_snapshots(&state, &user_id, &range).await?;
+    let locale = export_locale(&state.db, &user_id).await?;
+    tracing::info!("📤 Streaming {} snapshots as CSV ({:?}) for {}", snapshots.len(), query.columns, user_id);
+
+    let (header_line, row): (&str, fn(&PortfolioSnapshot, &ExportLocale) -> String) = match query.columns {
+        SnapshotCsvColumns::Totals => (TOTALS_HEADER, totals_row),
+        SnapshotCsvColumns::Assets => (ASSETS_HEADER, asset_rows),
+    };
+    let header_line = locale.header(header_line);
+    let rows = snapshots.into_iter().map(move |snapshot| Ok::<_, Infallible>(row(&snapshot, &locale)));
+    let body = futures_util::stream::iter(
+        std::iter::once(Ok(header_line)).chain(rows)
     );
-    
-    let token = state.db.get_token().await;
-    let client = reqwest::Client::new();
-    let req = client.get(&url);
-    let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
-    
-    let response = req.send().await
-        .map_err(|e| AppError::Internal(format!("Failed to fetch snapshots: {}", e)))?;
-    
-    if !response.status().is_success() {
-        return Err(AppError::Internal("Failed to fetch snapshots".to_string()));
-    }
-    
-    let data: PocketBaseResponse = response.json().await
-        .map_err(|e| AppError::Internal(format!("Failed to parse snapshots: {}", e)))?;
-    
-    Ok(Json(data.items))
+
+    let filename = format!("snapshots-{}.csv", chrono::Utc::now().format("%Y%m%d"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        Body::from_stream(body),
+    ).into_response())
 }
 
 /// POST /api/snapshots/now - Trigger a manual snapshot for the current user
@@ -0,0 +1,146 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use crate::client_ip::ClientIp;
+use crate::error::AppError;
+use crate::models::ApiStatusCheckResult;
+use crate::plugins::PluginInfo;
+use crate::services::diagnostics::{self, ConfigEntry};
+use crate::services::public_status::{HealthState, PublicStatus};
+use crate::AppState;
+
+/// Snapshots are taken daily; older than this and the instance is reported degraded
+const STALE_SNAPSHOT_HOURS: i64 = 48;
+
+/// GET /api/status/public - Sanitized health for status pages: no login, rate limited per IP.
+/// Answers 503 while the database is unreachable so uptime monitors notice.
+pub async fn get_public_status(
+    State(state): State<AppState>,
+    client_ip: ClientIp,
+) -> Result<Response, AppError> {
+    state.public_status.check_rate(client_ip.0).await.map_err(AppError::RateLimited)?;
+
+    let status = match state.public_status.cached().await {
+        Some(status) => status,
+        None => {
+            let status = build_public_status(&state).await;
+            state.public_status.store(status.clone()).await;
+            status
+        }
+    };
+    let code = if status.status == HealthState::Down { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+    Ok((code, Json(status)).into_response())
+}
+
+#[derive(Debug, Serialize)]
+pub struct DatabaseDiagnostics {
+    /// With any credentials masked
+    pub url: String,
+    pub connected: bool,
+    /// Whether the backend holds a PocketBase superuser token
+    pub authenticated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticsResponse {
+    pub health: PublicStatus,
+    pub database: DatabaseDiagnostics,
+    pub config: Vec<ConfigEntry>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// GET /api/admin/diagnostics - Health checked afresh plus the redacted configuration, for
+/// admins only. Secrets are reported as set or not, never by value.
+pub async fn get_diagnostics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<DiagnosticsResponse>, AppError> {
+    crate::handlers::users::extract_admin_user_id(&state, &headers)?;
+
+    let health = build_public_status(&state).await;
+    let connected = health.database != HealthState::Down;
+    let authenticated = connected && !state.db.get_token().await.is_empty();
+    Ok(Json(DiagnosticsResponse {
+        health,
+        database: DatabaseDiagnostics {
+            url: diagnostics::redact_url(&state.config.pocketbase_url),
+            connected,
+            authenticated,
+        },
+        config: diagnostics::config_summary(&state.config),
+        checked_at: Utc::now(),
+    }))
+}
+
+/// GET /api/admin/plugins - Plugins loaded in this build and what each contributes
+pub async fn list_plugins(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PluginInfo>>, AppError> {
+    crate::handlers::users::extract_admin_user_id(&state, &headers)?;
+    Ok(Json(state.plugins.info()))
+}
+
+async fn build_public_status(state: &AppState) -> PublicStatus {
+    let now = Utc::now();
+    let database = if state.db.is_healthy().await { HealthState::Operational } else { HealthState::Down };
+
+    // Providers that failed the last api_status_check, are blocked by an upstream 429 or
+    // whose ticker feed lost its connection
+    let mut degraded_providers: Vec<String> = Vec::new();
+    let last_check = state.job_scheduler.get_jobs().await.into_iter()
+        .filter(|job| job.job_type == "api_status_check")
+        .filter_map(|job| serde_json::from_value::<ApiStatusCheckResult>(job.last_result?).ok())
+        .next();
+    if let Some(check) = last_check {
+        degraded_providers.extend(check.results.into_iter()
+            .filter(|r| r.status != "online")
+            .map(|r| r.market_name));
+    }
+    for limit in state.rate_limiter.get_all_limits().await {
+        let blocked_until = limit.blocked_until.as_deref()
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok());
+        if limit.is_blocked && blocked_until.is_some_and(|until| until > now) {
+            degraded_providers.push(limit.api_name);
+        }
+    }
+    for feed in state.price_service.ticker_feed_status().await {
+        if !feed.connected && !feed.symbols.is_empty() {
+            degraded_providers.push(format!("{} stream", feed.market));
+        }
+    }
+    degraded_providers.sort();
+    degraded_providers.dedup();
+
+    let last_snapshot_at = match database {
+        HealthState::Down => None,
+        _ => state.db.latest_snapshot_at().await
+            .inspect_err(|e| tracing::warn!("⚠️ Public status could not read snapshots: {}", e))
+            .ok()
+            .flatten(),
+    };
+    let snapshot_stale = last_snapshot_at.is_some_and(|at| now - at > Duration::hours(STALE_SNAPSHOT_HOURS));
+
+    let status = if database == HealthState::Down {
+        HealthState::Down
+    } else if !degraded_providers.is_empty() || snapshot_stale {
+        HealthState::Degraded
+    } else {
+        HealthState::Operational
+    };
+
+    PublicStatus {
+        status,
+        api: HealthState::Operational,
+        database,
+        degraded_providers,
+        last_snapshot_at,
+        last_snapshot_age_seconds: last_snapshot_at.map(|at| (now - at).num_seconds().max(0)),
+        checked_at: now,
+    }
+}
@@ -1,8 +1,12 @@
 //! Symbols handler for stock symbol lookups and autocomplete
 
-use axum::{Json, extract::{Query, State}};
+use axum::{Json, extract::State, http::HeaderMap};
 use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::extract::{Path, Query};
 use crate::AppState;
+use crate::models::{AssetType, Market};
+use crate::services::price_service::PriceEntry;
 use crate::services::symbols::Symbol;
 
 /// Thai stock symbol with name
@@ -590,10 +594,14 @@ pub struct SeedResponse {
     pub message: String,
 }
 
-/// Seed all static symbols to PocketBase
+/// Seed all static symbols to PocketBase (admin only)
 pub async fn seed_symbols(
     State(state): State<AppState>,
-) -> Json<SeedResponse> {
+    headers: HeaderMap,
+) -> Result<Json<SeedResponse>, AppError> {
+    let admin_id = super::users::extract_admin_user_id(&state, &headers)?;
+    tracing::info!("🌱 Symbol seed requested by admin {}", admin_id);
+
     let mut symbols: Vec<Symbol> = Vec::new();
     
     // Add Thai stocks
@@ -607,6 +615,7 @@ pub async fn seed_symbols(
             category: None,
             sector: None,
             icon_url: None,
+            ..Default::default()
         });
     }
     
@@ -621,6 +630,7 @@ pub async fn seed_symbols(
             category: Some(s.contract_type),
             sector: None,
             icon_url: None,
+            ..Default::default()
         });
     }
     
@@ -635,6 +645,7 @@ pub async fn seed_symbols(
             category: Some(s.category),
             sector: None,
             icon_url: None,
+            ..Default::default()
         });
     }
     
@@ -649,12 +660,13 @@ pub async fn seed_symbols(
             category: None,
             sector: Some(s.sector),
             icon_url: None,
+            ..Default::default()
         });
     }
     
     let count = symbols.len();
     
-    match state.symbols_service.seed_symbols(symbols).await {
+    Ok(match state.symbols_service.seed_symbols(symbols).await {
         Ok(seeded) => Json(SeedResponse {
             seeded,
             message: format!("Successfully seeded {} of {} symbols to PocketBase", seeded, count),
@@ -663,5 +675,142 @@ pub async fn seed_symbols(
             seeded: 0,
             message: format!("Error seeding symbols: {}", e),
         }),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DelistSymbolRequest {
+    /// Price to freeze at; defaults to the last known price
+    pub final_price: Option<f64>,
+    pub currency: Option<String>,
+}
+
+/// List delisted symbols with their frozen prices
+pub async fn get_delisted_symbols(State(state): State<AppState>) -> Json<Vec<Symbol>> {
+    Json(state.symbols_service.delisted_symbols().await)
+}
+
+/// Mark a symbol delisted (admin only): its price is frozen for valuation and providers
+/// are no longer asked for it
+pub async fn delist_symbol(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((asset_type, symbol)): Path<(String, String)>,
+    Json(req): Json<DelistSymbolRequest>,
+) -> Result<Json<Symbol>, AppError> {
+    let admin_id = super::users::extract_admin_user_id(&state, &headers)?;
+    let parsed_type: AssetType = asset_type.parse().map_err(AppError::BadRequest)?;
+    if parsed_type == AssetType::Cash {
+        return Err(AppError::BadRequest("Cash balances cannot be delisted".to_string()));
     }
+    let record = state.symbols_service.lookup_symbol(&symbol).await
+        .filter(|s| s.asset_type == parsed_type.to_string())
+        .ok_or_else(|| AppError::NotFound(format!("Symbol {} ({}) not found", symbol.to_uppercase(), parsed_type)))?;
+    let market: Option<Market> = record.market.as_deref().and_then(|m| m.parse().ok());
+
+    let last_known = match state.price_service.cached_price(&symbol, &parsed_type, market.as_ref()).await {
+        Some(lookup) => Some((lookup.entry.price, lookup.entry.currency)),
+        None => stored_price(&state, &symbol, &parsed_type).await,
+    };
+    let price = match req.final_price {
+        Some(price) if price > 0.0 => price,
+        Some(_) => return Err(AppError::BadRequest("final_price must be positive".to_string())),
+        None => last_known.as_ref().map(|(price, _)| *price).ok_or_else(|| AppError::BadRequest(
+            format!("No known price for {}; pass final_price", symbol.to_uppercase())
+        ))?,
+    };
+    let currency = req.currency
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .or(last_known.map(|(_, currency)| currency))
+        .or_else(|| market.as_ref().map(|m| m.default_currency().to_string()))
+        .ok_or_else(|| AppError::BadRequest("currency is required".to_string()))?;
+
+    let saved = state.symbols_service
+        .set_delisted(&symbol, &record.asset_type, Some((price, currency.clone())))
+        .await?;
+    state.price_service.freeze_price(&parsed_type, PriceEntry {
+        symbol: saved.symbol.to_uppercase(),
+        price,
+        currency,
+        updated_at: saved.delisted_at.unwrap_or_else(chrono::Utc::now),
+        source: Some("delisted".to_string()),
+    }).await;
+    tracing::info!("🪦 {} ({}) delisted by admin {}", saved.symbol, parsed_type, admin_id);
+    Ok(Json(saved))
+}
+
+/// Clear a symbol's delisted flag (admin only) so prices are fetched again
+pub async fn relist_symbol(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((asset_type, symbol)): Path<(String, String)>,
+) -> Result<Json<Symbol>, AppError> {
+    let admin_id = super::users::extract_admin_user_id(&state, &headers)?;
+    let parsed_type: AssetType = asset_type.parse().map_err(AppError::BadRequest)?;
+    let saved = state.symbols_service.set_delisted(&symbol, &parsed_type.to_string(), None).await?;
+    state.price_service.unfreeze_price(&symbol, &parsed_type).await;
+    tracing::info!("♻️ {} ({}) relisted by admin {}", saved.symbol, parsed_type, admin_id);
+    Ok(Json(saved))
+}
+
+/// Allowed per-symbol refresh intervals: 10 seconds to a week
+const MIN_REFRESH_INTERVAL_SECONDS: u64 = 10;
+const MAX_REFRESH_INTERVAL_SECONDS: u64 = 7 * 24 * 3600;
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshIntervalRequest {
+    pub seconds: u64,
+}
+
+/// Give a symbol its own background refresh interval (admin only), replacing the
+/// heat-based schedule for both the price refresher and the price_fetch job
+pub async fn set_symbol_refresh_interval(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((asset_type, symbol)): Path<(String, String)>,
+    Json(req): Json<RefreshIntervalRequest>,
+) -> Result<Json<Symbol>, AppError> {
+    let admin_id = super::users::extract_admin_user_id(&state, &headers)?;
+    let parsed_type: AssetType = asset_type.parse().map_err(AppError::BadRequest)?;
+    if !(MIN_REFRESH_INTERVAL_SECONDS..=MAX_REFRESH_INTERVAL_SECONDS).contains(&req.seconds) {
+        return Err(AppError::BadRequest(format!(
+            "seconds must be between {} and {}", MIN_REFRESH_INTERVAL_SECONDS, MAX_REFRESH_INTERVAL_SECONDS
+        )));
+    }
+    let saved = state.symbols_service
+        .set_refresh_interval(&symbol, &parsed_type.to_string(), Some(req.seconds))
+        .await?;
+    state.symbol_heat.set_refresh_override(&saved.symbol, &saved.asset_type, Some(req.seconds)).await;
+    tracing::info!("⏱️ {} ({}) refreshes every {}s, set by admin {}", saved.symbol, parsed_type, req.seconds, admin_id);
+    Ok(Json(saved))
+}
+
+/// Return a symbol to the heat-based refresh schedule (admin only)
+pub async fn clear_symbol_refresh_interval(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((asset_type, symbol)): Path<(String, String)>,
+) -> Result<Json<Symbol>, AppError> {
+    let admin_id = super::users::extract_admin_user_id(&state, &headers)?;
+    let parsed_type: AssetType = asset_type.parse().map_err(AppError::BadRequest)?;
+    let saved = state.symbols_service.set_refresh_interval(&symbol, &parsed_type.to_string(), None).await?;
+    state.symbol_heat.set_refresh_override(&saved.symbol, &saved.asset_type, None).await;
+    tracing::info!("⏱️ {} ({}) back on the heat-based schedule, set by admin {}", saved.symbol, parsed_type, admin_id);
+    Ok(Json(saved))
+}
+
+/// Last price saved in the asset_prices collection, in any market
+async fn stored_price(state: &AppState, symbol: &str, asset_type: &AssetType) -> Option<(f64, String)> {
+    let filter = format!("symbol='{}' && asset_type='{}'", symbol.to_uppercase(), asset_type);
+    let url = format!(
+        "{}/api/collections/asset_prices/records?filter={}&sort=-updated&perPage=1",
+        state.config.pocketbase_url,
+        urlencoding::encode(&filter)
+    );
+    let data: serde_json::Value = state.db.http().get(&url).send().await.ok()?.json().await.ok()?;
+    let first = data.get("items")?.as_array()?.first()?;
+    let price = first.get("price")?.as_f64().filter(|p| *p > 0.0)?;
+    let currency = first.get("currency")?.as_str()?.to_string();
+    Some((price, currency))
 }
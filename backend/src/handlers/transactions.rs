@@ -1,13 +1,21 @@
 use axum::{
-    extract::{Path, State},
-    http::HeaderMap,
+    extract::State,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
     Json,
 };
-use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use crate::error::AppError;
+use crate::extract::{Path, Query};
 use crate::models::{
-    Transaction, CreateTransactionRequest, UpdateTransactionRequest, AssetType, TradeAction
+    custom_value_to_string, Transaction, CreateTransactionRequest, UpdateTransactionRequest, AssetType, TradeAction
 };
+use crate::handlers::accounts::ensure_account_open;
+use crate::handlers::custom_fields::{list_definitions, normalize_custom_fields};
+use crate::services::export_format::export_locale;
+use crate::services::market_rules::check_transaction;
 use crate::AppState;
 
 /// Extract user_id from Authorization header JWT
@@ -26,32 +34,127 @@ fn extract_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppE
 }
 
 /// List all transactions with optional filtering
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
+#[derive(Debug, Default, Deserialize)]
 pub struct ListTransactionsQuery {
-    #[allow(dead_code)]
-    pub asset_type: Option<String>,
-    #[allow(dead_code)]
+    pub asset_type: Option<AssetType>,
     pub symbol: Option<String>,
+    /// Custom field filters as `cf.<key>=<value>` (case-insensitive exact match)
+    #[serde(flatten)]
+    pub extra: HashMap<String, String>,
+}
+
+impl ListTransactionsQuery {
+    fn matches(&self, tx: &Transaction) -> bool {
+        if let Some(asset_type) = &self.asset_type {
+            if tx.asset_type != *asset_type {
+                return false;
+            }
+        }
+        if let Some(symbol) = &self.symbol {
+            if !tx.symbol.eq_ignore_ascii_case(symbol) {
+                return false;
+            }
+        }
+        self.extra.iter()
+            .filter_map(|(k, v)| k.strip_prefix("cf.").map(|key| (key, v)))
+            .all(|(key, expected)| tx.custom_fields.get(key)
+                .is_some_and(|value| custom_value_to_string(value).eq_ignore_ascii_case(expected)))
+    }
 }
 
 /// List all transactions for the logged-in user
 pub async fn list_transactions(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(query): Query<ListTransactionsQuery>,
 ) -> Result<Json<Vec<Transaction>>, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
     tracing::info!("📋 Requesting transactions for user_id: {}", user_id);
     let transactions = state.db.list_transactions(&user_id).await?;
-    Ok(Json(transactions))
+    Ok(Json(transactions.into_iter().filter(|t| query.matches(t)).collect()))
+}
+
+/// CSV of the user's transactions matching `query`, with the number of rows written
+pub(crate) async fn transactions_csv(
+    state: &AppState,
+    user_id: &str,
+    query: &ListTransactionsQuery,
+) -> Result<(String, usize), AppError> {
+    let definitions = list_definitions(state, user_id).await?;
+    let transactions = state.db.list_transactions(user_id).await?;
+    let locale = export_locale(&state.db, user_id).await?;
+
+    let mut columns: Vec<String> = [
+        "id", "timestamp", "asset_type", "symbol", "action", "quantity", "price", "fees",
+        "network_fee", "currency", "market", "account_id", "tags", "notes",
+    ].iter().map(|c| c.to_string()).collect();
+    columns.extend(definitions.iter().map(|d| format!("cf.{}", d.key)));
+    let mut out = locale.csv_line(&columns);
+
+    let mut rows = 0;
+    for tx in transactions.iter().filter(|t| query.matches(t)) {
+        let mut fields = vec![
+            tx.id.clone(),
+            locale.timestamp(tx.timestamp),
+            tx.asset_type.to_string(),
+            tx.symbol.clone(),
+            serde_json::to_value(&tx.action).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default(),
+            locale.number(tx.quantity),
+            locale.number(tx.price),
+            locale.number(tx.fees),
+            locale.number(tx.network_fee),
+            tx.currency.clone().unwrap_or_default(),
+            tx.market.as_ref().map(|m| m.to_string()).unwrap_or_default(),
+            tx.account_id.clone().unwrap_or_default(),
+            tx.tags.join(";"),
+            tx.notes.clone().unwrap_or_default(),
+        ];
+        fields.extend(definitions.iter().map(|d| match tx.custom_fields.get(&d.key) {
+            Some(value @ serde_json::Value::Number(_)) => locale.value(Some(value)),
+            value => value.map(custom_value_to_string).unwrap_or_default(),
+        }));
+        out.push_str(&locale.csv_line(&fields));
+        rows += 1;
+    }
+    Ok((out, rows))
+}
+
+/// GET /api/transactions/export - CSV of the user's transactions with one column per custom field.
+/// Accepts the same filters as the list endpoint; numbers and dates follow the user's export settings.
+pub async fn export_transactions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListTransactionsQuery>,
+) -> Result<Response, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let (out, rows) = transactions_csv(&state, &user_id, &query).await?;
+    tracing::info!("📤 Exported {} transactions for {}", rows, user_id);
+
+    let filename = format!("transactions-{}.csv", chrono::Utc::now().format("%Y%m%d"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        out,
+    ).into_response())
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateTransactionResponse {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    /// Non-blocking market rule notes, e.g. odd-lot quantities
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 /// Create a new transaction for the logged-in user
 pub async fn create_transaction(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(req): Json<CreateTransactionRequest>,
-) -> Result<Json<Transaction>, AppError> {
+    Json(mut req): Json<CreateTransactionRequest>,
+) -> Result<Json<CreateTransactionResponse>, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
     
     // Validate quantity and price
@@ -64,9 +167,25 @@ pub async fn create_transaction(
     if req.fees < 0.0 {
         return Err(AppError::BadRequest("Fees cannot be negative".to_string()));
     }
+    if req.network_fee < 0.0 {
+        return Err(AppError::BadRequest("Network fee cannot be negative".to_string()));
+    }
+
+    ensure_account_open(&state, req.account_id.as_deref()).await?;
+    normalize_custom_fields(&state, &user_id, &mut req.custom_fields, false).await?;
+
+    // Live entries must respect lot and tick rules; imports only get warnings
+    let (blocking, warnings): (Vec<_>, Vec<_>) = check_transaction(&mut req).into_iter().partition(|v| v.blocking);
+    if !blocking.is_empty() {
+        let messages: Vec<String> = blocking.into_iter().map(|v| v.message).collect();
+        return Err(AppError::BadRequest(messages.join("; ")));
+    }
 
     let transaction = state.db.create_transaction(req, &user_id).await?;
-    Ok(Json(transaction))
+    Ok(Json(CreateTransactionResponse {
+        transaction,
+        warnings: warnings.into_iter().map(|v| v.message).collect(),
+    }))
 }
 
 /// Get a single transaction by ID (only if owned by user)
@@ -91,7 +210,7 @@ pub async fn update_transaction(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<String>,
-    Json(req): Json<UpdateTransactionRequest>,
+    Json(mut req): Json<UpdateTransactionRequest>,
 ) -> Result<Json<Transaction>, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
     
@@ -124,6 +243,16 @@ pub async fn update_transaction(
             return Err(AppError::BadRequest("Fees cannot be negative".to_string()));
         }
     }
+    if req.network_fee.is_some_and(|fee| fee < 0.0) {
+        return Err(AppError::BadRequest("Network fee cannot be negative".to_string()));
+    }
+
+    if req.account_id.as_ref().is_some_and(|id| existing.account_id.as_ref() != Some(id)) {
+        ensure_account_open(&state, req.account_id.as_deref()).await?;
+    }
+    if let Some(custom_fields) = req.custom_fields.as_mut() {
+        normalize_custom_fields(&state, &user_id, custom_fields, true).await?;
+    }
 
     let transaction = state.db.update_transaction(&id, req).await?;
     Ok(Json(transaction))
@@ -154,17 +283,10 @@ pub async fn delete_transaction(
 pub async fn get_transactions_by_type(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Path(asset_type): Path<String>,
+    Path(asset_type): Path<AssetType>,
 ) -> Result<Json<Vec<Transaction>>, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
     
-    let asset_type = match asset_type.to_lowercase().as_str() {
-        "stock" => AssetType::Stock,
-        "tfex" => AssetType::Tfex,
-        "crypto" => AssetType::Crypto,
-        _ => return Err(AppError::BadRequest("Invalid asset type".to_string())),
-    };
-    
     // Get all transactions for user, then filter by asset type
     let all = state.db.list_transactions(&user_id).await?;
     let filtered: Vec<Transaction> = all.into_iter()
@@ -173,52 +295,359 @@ pub async fn get_transactions_by_type(
     
     Ok(Json(filtered))
 }
-/// Bulk create transactions
+/// Most rows one bulk request may carry
+const MAX_BULK_TRANSACTIONS: usize = 5000;
+/// Rows validated and saved per chunk before yielding to other requests
+const BULK_CHUNK_SIZE: usize = 250;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemStatus {
+    Created,
+    /// Rejected before saving: unreadable row or failed validation
+    Invalid,
+    /// Valid but could not be saved
+    Failed,
+}
+
+/// Outcome of one row of a bulk create, in request order
+#[derive(Debug, Serialize)]
+pub struct BulkItemResult {
+    /// Zero-based position in the request array
+    pub index: usize,
+    pub status: BulkItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Market rule notes and duplicate warnings; the row is created anyway
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Saved transaction (or earlier row, as "row N") this one looks identical to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BulkCreateSummary {
+    pub total: usize,
+    pub created: usize,
+    pub invalid: usize,
+    pub failed: usize,
+    pub duplicates: usize,
+    pub chunks: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkCreateResponse {
+    /// True when every row was created
+    pub success: bool,
+    pub summary: BulkCreateSummary,
+    pub results: Vec<BulkItemResult>,
+    /// Rows created (same as summary.created)
+    pub count: usize,
+    /// "Row N: message" for each rejected row
+    pub errors: Vec<String>,
+    /// "Row N: message" for each warning
+    pub warnings: Vec<String>,
+}
+
+/// Rows with the same asset, side, time, quantity and price are taken for the same trade
+fn duplicate_key(
+    asset_type: &AssetType,
+    symbol: &str,
+    action: &TradeAction,
+    timestamp: DateTime<Utc>,
+    quantity: f64,
+    price: f64,
+) -> String {
+    format!("{}|{}|{:?}|{}|{}|{}", asset_type, symbol.to_uppercase(), action, timestamp.timestamp_millis(), quantity, price)
+}
+
+/// Checks a bulk row before saving: the same rules as a single create, except that lot and
+/// tick rules only produce warnings since historical rows may predate them
+async fn validate_bulk_item(
+    state: &AppState,
+    user_id: &str,
+    accounts: &mut HashMap<String, Result<(), String>>,
+    req: &mut CreateTransactionRequest,
+) -> Result<Vec<String>, String> {
+    if req.quantity <= 0.0 && req.action != TradeAction::Dividend {
+        return Err("Quantity must be positive".to_string());
+    }
+    if req.price <= 0.0 {
+        return Err("Price must be positive".to_string());
+    }
+    if req.fees < 0.0 {
+        return Err("Fees cannot be negative".to_string());
+    }
+    if req.network_fee < 0.0 {
+        return Err("Network fee cannot be negative".to_string());
+    }
+    if let Some(account_id) = req.account_id.as_deref().filter(|id| !id.is_empty()) {
+        // Imports usually target one account, so each is looked up once per request
+        if !accounts.contains_key(account_id) {
+            let open = ensure_account_open(state, Some(account_id)).await.map_err(|e| e.to_string());
+            accounts.insert(account_id.to_string(), open);
+        }
+        accounts[account_id].clone()?;
+    }
+    normalize_custom_fields(state, user_id, &mut req.custom_fields, false).await.map_err(|e| e.to_string())?;
+    Ok(check_transaction(req).into_iter().map(|v| v.message).collect())
+}
+
+/// POST /api/transactions/bulk - Create up to 5000 transactions in chunks. Each row is read and
+/// validated on its own, so bad rows are reported in `results` without stopping the rest;
+/// rows matching a saved transaction or an earlier row are created with a duplicate warning.
 pub async fn create_transactions_bulk(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(reqs): Json<Vec<CreateTransactionRequest>>,
-) -> Result<Json<serde_json::Value>, AppError> {
+    Json(items): Json<Vec<serde_json::Value>>,
+) -> Result<Json<BulkCreateResponse>, AppError> {
     let user_id = extract_user_id(&state, &headers)?;
-    let mut success_count = 0;
-    let mut errors = Vec::new();
-
-    // Limit batch size to prevent overloading
-    if reqs.len() > 1000 {
-        return Err(AppError::BadRequest("Batch size exceeds limit (1000)".to_string()));
+    if items.len() > MAX_BULK_TRANSACTIONS {
+        return Err(AppError::BadRequest(format!(
+            "Batch size {} exceeds the limit of {}; split the import into several requests",
+            items.len(), MAX_BULK_TRANSACTIONS
+        )));
     }
 
     // Pre-load symbols once to ensure cache is warm
     let _ = state.symbols_service.load_symbols().await;
-    tracing::info!("Starting bulk import of {} transactions", reqs.len());
+    let mut seen: HashMap<String, String> = state.db.list_transactions(&user_id).await?
+        .iter()
+        .map(|tx| (duplicate_key(&tx.asset_type, &tx.symbol, &tx.action, tx.timestamp, tx.quantity, tx.price), tx.id.clone()))
+        .collect();
+    let mut accounts: HashMap<String, Result<(), String>> = HashMap::new();
+    let mut summary = BulkCreateSummary { total: items.len(), ..Default::default() };
+    let mut results = Vec::with_capacity(items.len());
+    tracing::info!("Starting bulk import of {} transactions for {}", items.len(), user_id);
+
+    let mut items = items.into_iter().enumerate().peekable();
+    while items.peek().is_some() {
+        summary.chunks += 1;
+        for (index, item) in items.by_ref().take(BULK_CHUNK_SIZE) {
+            let mut result = BulkItemResult {
+                index,
+                status: BulkItemStatus::Invalid,
+                id: None,
+                error: None,
+                warnings: Vec::new(),
+                duplicate_of: None,
+            };
+            let mut req = match serde_json::from_value::<CreateTransactionRequest>(item) {
+                Ok(req) => req,
+                Err(e) => {
+                    result.error = Some(format!("Invalid transaction: {}", e));
+                    results.push(result);
+                    continue;
+                }
+            };
+            match validate_bulk_item(&state, &user_id, &mut accounts, &mut req).await {
+                Ok(warnings) => result.warnings = warnings,
+                Err(e) => {
+                    result.error = Some(e);
+                    results.push(result);
+                    continue;
+                }
+            }
+
+            let key = duplicate_key(&req.asset_type, &req.symbol, &req.action, req.timestamp, req.quantity, req.price);
+            if let Some(existing) = seen.get(&key) {
+                result.warnings.push(format!("Looks like a duplicate of {}", existing));
+                result.duplicate_of = Some(existing.clone());
+            }
 
-    for (index, mut req) in reqs.into_iter().enumerate() {
-        // Basic validation
-        if req.quantity <= 0.0 && req.action != TradeAction::Dividend {
-            errors.push(format!("Row {}: Quantity must be positive", index + 1));
-            continue;
+            // Auto-populate symbol_name if missing
+            if req.symbol_name.as_ref().is_none_or(|n| n.is_empty()) {
+                if let Some(symbol_data) = state.symbols_service.lookup_symbol(&req.symbol).await {
+                    req.symbol_name = Some(symbol_data.name);
+                }
+            }
+
+            match state.db.create_transaction(req, &user_id).await {
+                Ok(tx) => {
+                    seen.entry(key).or_insert_with(|| format!("row {}", index + 1));
+                    result.status = BulkItemStatus::Created;
+                    result.id = Some(tx.id);
+                }
+                Err(e) => {
+                    result.status = BulkItemStatus::Failed;
+                    result.error = Some(e.to_string());
+                }
+            }
+            results.push(result);
         }
-        if req.price <= 0.0 {
-            errors.push(format!("Row {}: Price must be positive", index + 1));
-            continue;
+        tracing::debug!("Bulk import chunk {} done ({} of {} rows)", summary.chunks, results.len(), summary.total);
+        tokio::task::yield_now().await;
+    }
+
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for result in &results {
+        match result.status {
+            BulkItemStatus::Created => summary.created += 1,
+            BulkItemStatus::Invalid => summary.invalid += 1,
+            BulkItemStatus::Failed => summary.failed += 1,
+        }
+        if result.duplicate_of.is_some() {
+            summary.duplicates += 1;
+        }
+        if let Some(error) = &result.error {
+            errors.push(format!("Row {}: {}", result.index + 1, error));
         }
+        warnings.extend(result.warnings.iter().map(|w| format!("Row {}: {}", result.index + 1, w)));
+    }
+    tracing::info!(
+        "Bulk import for {}: {} created, {} invalid, {} failed, {} possible duplicates",
+        user_id, summary.created, summary.invalid, summary.failed, summary.duplicates
+    );
+
+    Ok(Json(BulkCreateResponse {
+        success: summary.created == summary.total,
+        count: summary.created,
+        summary,
+        results,
+        errors,
+        warnings,
+    }))
+}
 
-        // Auto-populate symbol_name if missing
-        if req.symbol_name.is_none() || req.symbol_name.as_ref().is_some_and(|n| n.is_empty()) {
-            if let Some(symbol_data) = state.symbols_service.lookup_symbol(&req.symbol).await {
-                req.symbol_name = Some(symbol_data.name);
+/// Selects transactions for bulk delete; every given criterion must match
+#[derive(Debug, Default, Deserialize)]
+pub struct BulkDeleteFilter {
+    pub asset_type: Option<AssetType>,
+    pub symbol: Option<String>,
+    pub account_id: Option<String>,
+    /// e.g. "imported" to undo a CSV import
+    pub tag: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl BulkDeleteFilter {
+    fn is_empty(&self) -> bool {
+        self.asset_type.is_none() && self.symbol.is_none() && self.account_id.is_none()
+            && self.tag.is_none() && self.from.is_none() && self.to.is_none()
+    }
+
+    fn matches(&self, tx: &Transaction) -> bool {
+        self.asset_type.as_ref().is_none_or(|a| tx.asset_type == *a)
+            && self.symbol.as_ref().is_none_or(|s| tx.symbol.eq_ignore_ascii_case(s))
+            && self.account_id.as_ref().is_none_or(|a| tx.account_id.as_ref() == Some(a))
+            && self.tag.as_ref().is_none_or(|t| tx.tags.iter().any(|x| x.eq_ignore_ascii_case(t)))
+            && self.from.is_none_or(|from| tx.timestamp >= from)
+            && self.to.is_none_or(|to| tx.timestamp <= to)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkDeleteRequest {
+    pub ids: Option<Vec<String>>,
+    pub filter: Option<BulkDeleteFilter>,
+    /// Without confirm the request only previews what would be deleted
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkDeleteResponse {
+    pub dry_run: bool,
+    pub count: usize,
+    /// Sum of quantity × price (plus fees) per currency
+    pub value_affected: BTreeMap<String, f64>,
+    pub symbols: Vec<String>,
+    /// IDs that do not exist or belong to someone else
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub not_found: Vec<String>,
+    /// First transactions of the selection, for the preview
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sample: Vec<Transaction>,
+    /// Pass to the restore endpoint to undo the delete
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+}
+
+const BULK_DELETE_SAMPLE: usize = 20;
+
+/// POST /api/transactions/bulk-delete - Soft-delete by IDs or filter. Returns a preview unless confirm=true.
+pub async fn bulk_delete_transactions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<BulkDeleteRequest>,
+) -> Result<Json<BulkDeleteResponse>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let transactions = state.db.list_transactions(&user_id).await?;
+
+    let mut not_found = Vec::new();
+    let selected: Vec<Transaction> = match (req.ids, req.filter) {
+        (Some(ids), None) => {
+            if ids.is_empty() {
+                return Err(AppError::BadRequest("ids cannot be empty".to_string()));
             }
+            let mut selected = Vec::new();
+            for id in ids {
+                match transactions.iter().find(|t| t.id == id) {
+                    Some(tx) if !selected.iter().any(|s: &Transaction| s.id == id) => selected.push(tx.clone()),
+                    Some(_) => {}
+                    None => not_found.push(id),
+                }
+            }
+            selected
+        }
+        (None, Some(filter)) => {
+            if filter.is_empty() {
+                return Err(AppError::BadRequest("filter needs at least one criterion".to_string()));
+            }
+            transactions.into_iter().filter(|t| filter.matches(t)).collect()
         }
+        _ => return Err(AppError::BadRequest("Provide either ids or filter".to_string())),
+    };
 
-        match state.db.create_transaction(req, &user_id).await {
-            Ok(_) => success_count += 1,
-            Err(e) => errors.push(format!("Row {}: {}", index + 1, e)),
+    let mut value_affected: BTreeMap<String, f64> = BTreeMap::new();
+    let mut symbols: Vec<String> = Vec::new();
+    for tx in &selected {
+        let currency = tx.currency.clone().unwrap_or_else(|| "THB".to_string());
+        *value_affected.entry(currency).or_insert(0.0) += tx.quantity * tx.price + tx.all_fees();
+        if !symbols.contains(&tx.symbol) {
+            symbols.push(tx.symbol.clone());
         }
     }
+    symbols.sort();
 
+    let batch_id = if req.confirm && !selected.is_empty() {
+        let batch_id = uuid::Uuid::new_v4().to_string();
+        let ids: Vec<String> = selected.iter().map(|t| t.id.clone()).collect();
+        state.db.soft_delete_transactions(&ids, &batch_id).await?;
+        Some(batch_id)
+    } else {
+        None
+    };
+
+    Ok(Json(BulkDeleteResponse {
+        dry_run: !req.confirm,
+        count: selected.len(),
+        value_affected,
+        symbols,
+        not_found,
+        sample: if req.confirm { Vec::new() } else { selected.into_iter().take(BULK_DELETE_SAMPLE).collect() },
+        batch_id,
+    }))
+}
+
+/// POST /api/transactions/bulk-delete/:batch_id/restore - Undo a bulk delete
+pub async fn restore_deleted_transactions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(batch_id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_id = extract_user_id(&state, &headers)?;
+    let restored = state.db.restore_transactions(&user_id, &batch_id).await?;
+    if restored == 0 {
+        return Err(AppError::NotFound(format!("Delete batch {} not found", batch_id)));
+    }
     Ok(Json(serde_json::json!({
         "success": true,
-        "count": success_count,
-        "errors": errors
+        "restored": restored
     })))
 }
@@ -1,11 +1,12 @@
 use axum::{
-    extract::{Path, State},
+    extract::State,
     http::HeaderMap,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use crate::error::AppError;
-use crate::models::UserResponse;
+use crate::extract::Path;
+use crate::models::{User, UserResponse};
 use crate::AppState;
 
 /// Admin user list response
@@ -37,8 +38,13 @@ pub struct CreateUserRequest {
     pub role: Option<String>,
 }
 
-/// Extract user_id from Authorization header JWT and verify admin
-fn extract_admin_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+/// Load the user behind the Authorization header JWT
+fn extract_authenticated_user(state: &AppState, headers: &HeaderMap) -> Result<User, AppError> {
+    extract_authenticated_session(state, headers).map(|(user, _)| user)
+}
+
+/// The user behind the Authorization header JWT, and whether it is a read-only guest token
+fn extract_authenticated_session(state: &AppState, headers: &HeaderMap) -> Result<(User, bool), AppError> {
     let auth_header = headers
         .get("Authorization")
         .and_then(|v| v.to_str().ok())
@@ -50,18 +56,35 @@ fn extract_admin_user_id(state: &AppState, headers: &HeaderMap) -> Result<String
     
     let claims = state.auth_service.verify_jwt(token)?;
     
-    // Check if user is admin
     let user = tokio::task::block_in_place(|| {
         tokio::runtime::Handle::current().block_on(async {
             state.auth_service.get_user(&claims.sub).await
         })
     })?;
+    Ok((user, claims.read_only))
+}
+
+/// Extract user_id from Authorization header JWT and verify admin
+pub(crate) fn extract_admin_user_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let user = extract_authenticated_user(state, headers)?;
     
     if !user.is_admin() {
         return Err(AppError::Forbidden("Admin access required".to_string()));
     }
     
-    Ok(claims.sub)
+    Ok(user.id)
+}
+
+/// Read access to operational data (jobs, API providers, API logs): admins, plus any signed-in
+/// user when OPS_READ_ACCESS_FOR_USERS is enabled
+pub(crate) fn extract_ops_reader_id(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    let (user, guest) = extract_authenticated_session(state, headers)?;
+    
+    if !user.is_admin() && (guest || !state.config.ops_read_access_for_users) {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+    
+    Ok(user.id)
 }
 
 /// GET /api/admin/users - Get all users (admin only)
@@ -110,6 +133,7 @@ pub async fn create_user(
         &req.password,
         req.name.clone(),
         is_admin,
+        false,
     ).await?;
     
     tracing::info!("Admin {} created user {} with role {}", admin_id, user.id, user.role);
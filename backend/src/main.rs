@@ -1,9 +1,18 @@
+mod body_limit;
+mod client_ip;
 mod config;
 mod error;
+mod extract;
+mod guest;
 mod handlers;
 mod models;
+mod plugins;
 mod services;
 mod utils;
+mod versioning;
+
+#[cfg(test)]
+mod authz_tests;
 
 use axum::{
     routing::{get, post, put, delete, patch},
@@ -15,8 +24,9 @@ use tower_http::trace::TraceLayer;
 use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+use body_limit::BodyLimit;
 use config::Config;
-use services::{PocketBaseClient, PriceService, ExchangeRateService, AuthService, JobScheduler, SymbolsService, RateLimiter, NotificationService, AlertService};
+use services::{PocketBaseClient, PriceService, ExchangeRateService, AuthService, JobScheduler, SymbolsService, RateLimiter, NotificationService, AlertService, SymbolHeat, SnapshotCache, PriceRefresher, OrderWatcher, ExportService, BalanceSyncService, CredentialStore, ExchangeSyncService, PublicStatusService, CacheRevalidator, RealtimeBridge, ClientModes};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -24,11 +34,22 @@ pub struct AppState {
     pub price_service: PriceService,
     pub exchange_rate_service: ExchangeRateService,
     pub auth_service: AuthService,
-    pub job_scheduler: JobScheduler,
+    pub job_scheduler: Arc<JobScheduler>,
     pub symbols_service: SymbolsService,
     pub rate_limiter: RateLimiter,
     pub notification_service: NotificationService,
     pub alert_service: AlertService,
+    pub symbol_heat: SymbolHeat,
+    pub snapshot_cache: SnapshotCache,
+    pub price_refresher: PriceRefresher,
+    pub order_watcher: Arc<OrderWatcher>,
+    pub export_service: ExportService,
+    pub balance_sync: Arc<BalanceSyncService>,
+    pub exchange_sync: Arc<ExchangeSyncService>,
+    pub public_status: Arc<PublicStatusService>,
+    pub realtime: Arc<RealtimeBridge>,
+    pub client_modes: Arc<ClientModes>,
+    pub plugins: Arc<plugins::Plugins>,
     pub config: Arc<Config>,
 }
 
@@ -41,19 +62,7 @@ async fn main() {
     }
 
     println!("📂 Current Working Directory: {:?}", std::env::current_dir());
-    println!("🔍 Environment Variables Check:");
-    for (key, value) in std::env::vars() {
-        if key.contains("ADMIN") || key.contains("POCKETBASE") {
-            let masked = if value.is_empty() { 
-                "(empty)".to_string() 
-            } else if value.len() > 3 { 
-                format!("{}***", &value[0..3]) 
-            } else { 
-                "***".to_string() 
-            };
-            println!("   - {}: {}", key, masked);
-        }
-    }
+    services::diagnostics::log_environment();
 
     // Initialize tracing
     tracing_subscriber::registry()
@@ -69,6 +78,7 @@ async fn main() {
     // Load configuration
     let config = Config::from_env();
     let addr = config.server_addr();
+    services::diagnostics::init(&config);
 
     // Initialize services
     let db = PocketBaseClient::new(config.clone());
@@ -82,20 +92,36 @@ async fn main() {
     // Create price service with rate limiter and PocketBase client for logging
     let mut price_service = PriceService::with_rate_limiter(config.clone(), rate_limiter.clone());
     price_service.set_pb_client(db.clone());
+    // Plugin providers must be registered before the service is cloned into the others
+    let plugins = Arc::new(plugins::Plugins::load(&config));
+    for provider in plugins.price_providers() {
+        price_service.register_provider(provider);
+    }
     
-    let exchange_rate_service = ExchangeRateService::new(config.clone());
+    let mut exchange_rate_service = ExchangeRateService::new(config.clone());
+    exchange_rate_service.set_provider_cache(price_service.provider_cache());
     let auth_service = AuthService::new(config.clone(), db.clone()).await;
-    let job_scheduler = JobScheduler::new(config.clone(), db.clone(), price_service.clone());
+    let symbol_heat = SymbolHeat::new(&config);
+    let mut job_scheduler = JobScheduler::new(config.clone(), db.clone(), price_service.clone(), symbol_heat.clone());
     let symbols_service = SymbolsService::new(config.pocketbase_url.clone(), db.clone());
+    // Delisted symbols keep their final price instead of being refreshed
+    price_service.load_delisted(&symbols_service.delisted_symbols().await).await;
+    symbol_heat.load_refresh_overrides(&symbols_service.refresh_overrides().await).await;
     
     // Initialize notification and alert services
-    let notification_service = NotificationService::new(config.clone(), db.clone());
+    let mut notification_service = NotificationService::new(config.clone(), db.clone());
+    notification_service.set_message_transformers(plugins.message_transformers());
+    job_scheduler.set_notification_service(notification_service.clone());
+    job_scheduler.set_report_sections(plugins.report_sections());
+    let snapshot_cache = SnapshotCache::new(&config);
+    job_scheduler.set_snapshot_cache(snapshot_cache.clone());
     let alert_service = AlertService::new(
         config.clone(),
         db.clone(),
         notification_service.clone(),
         price_service.clone(),
     );
+    job_scheduler.set_alert_service(alert_service.clone());
     
     // Initialize alert service (load alerts from database)
     if let Err(e) = alert_service.initialize().await {
@@ -107,189 +133,378 @@ async fn main() {
         tracing::warn!("Failed to initialize job scheduler: {}", e);
     }
     
+    // Provider cache TTLs and proxies come from api_providers (defaults are seeded above)
+    price_service.refresh_provider_settings().await;
+    price_service.start_ticker_feeds();
+    
     // Start the job scheduler loop
     job_scheduler.start();
 
+    // Keep held symbols' prices warm so portfolio requests never wait on providers
+    let price_refresher = PriceRefresher::new(&config, price_service.clone(), symbol_heat.clone());
+    price_refresher.start();
+
+    // Flag open broker orders once incoming prices reach them
+    let order_watcher = OrderWatcher::new(
+        db.clone(),
+        notification_service.clone(),
+        price_refresher.clone(),
+    );
+    order_watcher.start(&price_service);
+
+    // Pick up users, transactions and accounts changed directly in PocketBase
+    CacheRevalidator::new(&config, db.clone(), auth_service.clone()).start();
+    // ...and apply transaction, account and price edits as PocketBase pushes them
+    let realtime = RealtimeBridge::new(&config, db.clone(), price_service.clone());
+    realtime.start();
+
+    // Background exports and cleanup of their expired download files
+    let mut export_service = ExportService::new(&config);
+    if let Some(storage) = services::object_storage::ObjectStorage::from_config(&config) {
+        export_service.set_object_storage(storage);
+    }
+    export_service.start();
+
+    // Users' exchange API keys, for reading their wallets and trade history
+    let credential_store = CredentialStore::new(db.clone(), &config);
+    let balance_sync = BalanceSyncService::new(credential_store.clone(), price_service.clone());
+    let exchange_sync = ExchangeSyncService::new(db.clone(), credential_store, price_service.clone());
+
     let state = AppState {
         db,
         price_service,
         exchange_rate_service,
         auth_service,
-        job_scheduler,
+        job_scheduler: Arc::new(job_scheduler),
         symbols_service,
         rate_limiter,
         notification_service,
         alert_service,
+        symbol_heat,
+        snapshot_cache,
+        price_refresher,
+        order_watcher: Arc::new(order_watcher),
+        export_service,
+        balance_sync: Arc::new(balance_sync),
+        exchange_sync: Arc::new(exchange_sync),
+        public_status: Arc::new(PublicStatusService::new(&config)),
+        realtime: Arc::new(realtime),
+        client_modes: Arc::new(ClientModes::new(&config)),
+        plugins,
         config: Arc::new(config.clone()),
     };
 
+    let api = api_routes(&config);
+
     // Build router
     let app = Router::new()
         // Health check
         .route("/health", get(health_check))
-        .route("/api/status", get(system_status))
+        .merge(versioning::versioned_api(api, &config))
+        
+        // Add middleware
+
+        .layer(TraceLayer::new_for_http())
+        .layer(
+            CorsLayer::new()
+                .allow_origin(
+                    config.cors_allowed_origins
+                        .iter()
+                        .map(|origin| origin.parse::<axum::http::HeaderValue>().unwrap())
+                        .collect::<Vec<_>>()
+                )
+                .allow_methods([axum::http::Method::GET, axum::http::Method::POST, axum::http::Method::PUT, axum::http::Method::DELETE, axum::http::Method::PATCH, axum::http::Method::OPTIONS])
+                .allow_headers([axum::http::header::CONTENT_TYPE, axum::http::header::AUTHORIZATION, axum::http::header::COOKIE, axum::http::HeaderName::from_static("api-version"), axum::http::HeaderName::from_static(services::client_modes::CLIENT_ID_HEADER)])
+                .expose_headers([
+                    axum::http::HeaderName::from_static("api-version"),
+                    axum::http::HeaderName::from_static("deprecation"),
+                    axum::http::HeaderName::from_static("sunset"),
+                    axum::http::header::LINK,
+                    axum::http::header::RETRY_AFTER,
+                    axum::http::HeaderName::from_static("x-ratelimit-limit"),
+                    axum::http::HeaderName::from_static("x-ratelimit-remaining"),
+                    axum::http::HeaderName::from_static("x-ratelimit-reset"),
+                    axum::http::HeaderName::from_static("x-ratelimit-provider"),
+                ])
+                .allow_credentials(true),
+        )
+        .with_state(state);
+
+    tracing::info!("🚀 Portfolio Backend starting on http://{}", addr);
+    tracing::info!("📊 API available at http://{}/api/v1 (unversioned /api paths are deprecated)", addr);
+    tracing::info!("🔐 Auth endpoints available at http://{}/api/auth", addr);
+
+    // Start server
+    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await.unwrap();
+}
+
+/// API routes, mounted under /api/v1 with the unversioned /api paths kept as deprecated aliases
+fn api_routes(config: &Config) -> Router<AppState> {
+    let api = Router::new()
+        .route("/status", get(system_status))
+        .route("/status/public", get(handlers::get_public_status))
         
         // Auth routes
-        .route("/api/auth/providers", get(handlers::get_available_providers))
-        .route("/api/auth/google", get(handlers::google_login))
-        .route("/api/auth/google/callback", get(handlers::google_callback))
-        .route("/api/auth/oidc", get(handlers::oidc_login))
-        .route("/api/auth/oidc/callback", get(handlers::oidc_callback))
-        .route("/api/auth/me", get(handlers::get_current_user))
-        .route("/api/auth/logout", post(handlers::logout))
-        .route("/api/auth/verify", post(handlers::verify_token))
-        .route("/api/auth/linked-providers", get(handlers::get_linked_providers))
-        .route("/api/auth/unlink/:provider", delete(handlers::unlink_provider))
-        .route("/api/auth/local/login", post(handlers::local_login))
-        .route("/api/auth/local/register", post(handlers::local_register))
-        .route("/api/auth/logout-all", post(handlers::logout_all_devices))
-        .route("/api/auth/change-password", post(handlers::change_password))
+        .route("/auth/providers", get(handlers::get_available_providers))
+        .route("/auth/google", get(handlers::google_login))
+        .route("/auth/google/callback", get(handlers::google_callback))
+        .route("/auth/oidc", get(handlers::oidc_login))
+        .route("/auth/oidc/callback", get(handlers::oidc_callback))
+        .route("/auth/me", get(handlers::get_current_user))
+        .route("/auth/logout", post(handlers::logout))
+        .route("/auth/guest", post(handlers::guest_login))
+        .route("/auth/verify", post(handlers::verify_token))
+        .route("/auth/linked-providers", get(handlers::get_linked_providers))
+        .route("/auth/unlink/:provider", delete(handlers::unlink_provider))
+        .route("/auth/local/login", post(handlers::local_login))
+        .route("/auth/local/register", post(handlers::local_register))
+        .route("/auth/local/verify", post(handlers::verify_email))
+        .route("/auth/local/resend-verification", post(handlers::resend_verification))
+        .route("/auth/logout-all", post(handlers::logout_all_devices))
+        .route("/auth/sessions", get(handlers::list_sessions))
+        .route("/auth/change-password", post(handlers::change_password))
+        .route("/settings", get(handlers::get_user_settings))
+        .route("/settings", put(handlers::update_user_settings))
         
         // Transaction routes
-        .route("/api/transactions/bulk", post(handlers::create_transactions_bulk))
-        .route("/api/transactions", get(handlers::list_transactions))
-        .route("/api/transactions", post(handlers::create_transaction))
-        .route("/api/transactions/:id", get(handlers::get_transaction))
-        .route("/api/transactions/:id", put(handlers::update_transaction))
-        .route("/api/transactions/:id", delete(handlers::delete_transaction))
-        .route("/api/transactions/type/:asset_type", get(handlers::get_transactions_by_type))
+        .route("/transactions", get(handlers::list_transactions))
+        .route("/transactions", post(handlers::create_transaction))
+        .route("/transactions/:id", get(handlers::get_transaction))
+        .route("/transactions/:id", put(handlers::update_transaction))
+        .route("/transactions/:id", delete(handlers::delete_transaction))
+        .route("/transactions/type/:asset_type", get(handlers::get_transactions_by_type))
+        .route("/transactions/export", get(handlers::export_transactions))
+        .route("/transactions/bulk-delete", post(handlers::bulk_delete_transactions))
+        .route("/transactions/bulk-delete/:batch_id/restore", post(handlers::restore_deleted_transactions))
+        .route("/custom-fields", get(handlers::list_custom_fields))
+        .route("/custom-fields", post(handlers::create_custom_field))
+        .route("/custom-fields/:id", put(handlers::update_custom_field))
+        .route("/custom-fields/:id", delete(handlers::delete_custom_field))
+        // Dashboard layouts and widget queries, shared by the user's clients
+        .route("/dashboards", get(handlers::list_dashboards))
+        .route("/dashboards", post(handlers::create_dashboard))
+        .route("/dashboards/:id", get(handlers::get_dashboard))
+        .route("/dashboards/:id", put(handlers::update_dashboard))
+        .route("/dashboards/:id", delete(handlers::delete_dashboard))
         
         // Portfolio routes
-        .route("/api/portfolio", get(handlers::get_portfolio))
-        .route("/api/portfolio/summary", get(handlers::get_portfolio_summary))
-        .route("/api/portfolio/type/:asset_type", get(handlers::get_portfolio_by_type))
-        .route("/api/portfolio/market/:market", get(handlers::get_portfolio_by_market))
+        .route("/portfolio", get(handlers::get_portfolio))
+        .route("/portfolio/summary", get(handlers::get_portfolio_summary))
+        .route("/portfolio/refresh", post(handlers::refresh_portfolio_prices))
+        .route("/portfolio/refresh/:job_id", get(handlers::get_refresh_job))
+        .route("/portfolio/type/:asset_type", get(handlers::get_portfolio_by_type))
+        .route("/portfolio/market/:market", get(handlers::get_portfolio_by_market))
+        .route("/portfolio/movers", get(handlers::get_portfolio_movers))
+        .route("/portfolio/heatmap", get(handlers::get_portfolio_heatmap))
+        .route("/portfolio/correlations", get(handlers::get_portfolio_correlations))
+        .route("/portfolio/rebalance/plan", post(handlers::create_rebalance_plan))
+        .route("/portfolio/allocation", get(handlers::get_portfolio_allocation))
+        .route("/portfolio/currency-reconciliation", get(handlers::get_currency_reconciliation))
+        .route("/portfolio/reconcile", get(handlers::get_portfolio_reconciliation))
+        .route("/portfolio/realized/monthly", get(handlers::get_realized_monthly))
+        .route("/portfolio/performance/benchmark", get(handlers::get_benchmark_comparison))
+        .route("/insights", get(handlers::get_insights))
+        
+        // Performance routes
+        .route("/performance", get(handlers::get_performance))
+        .route("/performance/nav", get(handlers::get_nav_series))
+        .route("/analytics/trades", get(handlers::get_trade_analytics))
         
         // Price routes
-        .route("/api/prices/:symbol", get(handlers::get_price))
-        .route("/api/prices/history/:symbol", get(handlers::get_price_history))
-        .route("/api/prices/batch", post(handlers::get_prices_batch))
-        .route("/api/prices/cache/clear", post(handlers::clear_price_cache))
+        .route("/prices/:symbol", get(handlers::get_price))
+        .route("/prices/history/:symbol", get(handlers::get_price_history))
+        .route("/prices/batch", post(handlers::get_prices_batch))
+        .route("/prices/:symbol/history", get(handlers::get_price_candles))
+        .route("/assets/:symbol/chart", get(handlers::get_asset_chart))
+        .route("/ws/prices", get(handlers::stream_prices))
+        .route("/ws/changes", get(handlers::stream_changes))
+        .route("/clients/mode", put(handlers::set_client_mode))
+        .route("/prices/cache/clear", post(handlers::clear_price_cache))
+        .route("/prices/heat", get(handlers::get_symbol_heat))
+        .route("/prices/thai-gold", get(handlers::get_thai_gold_quote))
+        .route("/prices/streams", get(handlers::get_ticker_feeds))
+        .route("/prices/quarantine", get(handlers::get_price_quarantine))
+        .route("/prices/quarantine/release", post(handlers::release_price_quarantine))
         
         // Exchange rate routes
-        .route("/api/exchange-rate", get(handlers::get_exchange_rate))
-        .route("/api/exchange-rate/:base", get(handlers::get_all_exchange_rates))
-        .route("/api/exchange-rate/convert", get(handlers::convert_currency))
-        .route("/api/exchange-rate/cache/clear", post(handlers::clear_exchange_rate_cache))
+        .route("/exchange-rate", get(handlers::get_exchange_rate))
+        .route("/exchange-rate/:base", get(handlers::get_all_exchange_rates))
+        .route("/exchange-rate/convert", get(handlers::convert_currency))
+        .route("/exchange-rate/convert/batch", post(handlers::convert_currency_batch))
+        .route("/exchange-rate/cache/clear", post(handlers::clear_exchange_rate_cache))
         
         // Account routes
-        .route("/api/accounts/reorder", put(handlers::reorder_accounts))
-        .route("/api/accounts", get(handlers::list_accounts))
-        .route("/api/accounts", post(handlers::create_account))
-        .route("/api/accounts/:id", get(handlers::get_account))
-        .route("/api/accounts/:id", put(handlers::update_account))
-        .route("/api/accounts/:id", delete(handlers::delete_account))
+        .route("/accounts/reorder", put(handlers::reorder_accounts))
+        .route("/accounts/summary", get(handlers::get_accounts_summary))
+        .route("/accounts", get(handlers::list_accounts))
+        .route("/accounts", post(handlers::create_account))
+        .route("/accounts/:id", get(handlers::get_account))
+        .route("/accounts/:id", put(handlers::update_account))
+        .route("/accounts/:id", delete(handlers::delete_account))
+        .route("/accounts/:id/archive", post(handlers::archive_account))
+        .route("/accounts/:id/unarchive", post(handlers::unarchive_account))
+        .route("/accounts/:id/summary", get(handlers::get_account_summary))
+        .route("/accounts/:id/target-allocation", put(handlers::set_target_allocation))
+        .route("/accounts/:id/target-allocation", delete(handlers::clear_target_allocation))
+        
+        // Liability routes
+        .route("/liabilities", get(handlers::list_liabilities))
+        .route("/liabilities", post(handlers::create_liability))
+        .route("/liabilities/:id", get(handlers::get_liability))
+        .route("/liabilities/:id", put(handlers::update_liability))
+        .route("/liabilities/:id", delete(handlers::delete_liability))
+        .route("/liabilities/:id/transactions", get(handlers::list_liability_transactions))
+        .route("/liabilities/:id/transactions", post(handlers::create_liability_transaction))
+        .route("/net-worth", get(handlers::get_net_worth))
+
+        // Open broker orders
+        .route("/orders", get(handlers::list_orders))
+        .route("/orders", post(handlers::create_order))
+        .route("/orders/:id", get(handlers::get_order))
+        .route("/orders/:id", put(handlers::update_order))
+        .route("/orders/:id", delete(handlers::delete_order))
+        .route("/orders/:id/fill", post(handlers::fill_order))
+
+        // Dividend income
+        .route("/dividends", get(handlers::list_dividends))
+        .route("/dividends/summary", get(handlers::get_dividend_summary))
+        .route("/dividends/projection", get(handlers::get_dividend_projection))
+        
+        // Equity compensation routes
+        .route("/equity-grants", get(handlers::list_equity_grants))
+        .route("/equity-grants", post(handlers::create_equity_grant))
+        .route("/equity-grants/:id", get(handlers::get_equity_grant))
+        .route("/equity-grants/:id", delete(handlers::delete_equity_grant))
+        .route("/equity-grants/:id/vest", post(handlers::vest_equity_grant))
         
         // Symbol lookup routes
-        .route("/api/symbols/thai-stocks", get(handlers::get_thai_stocks))
-        .route("/api/symbols/tfex", get(handlers::get_tfex_symbols))
-        .route("/api/symbols/crypto", get(handlers::get_crypto_symbols))
-        .route("/api/symbols/foreign-stocks", get(handlers::get_foreign_stocks))
-        .route("/api/symbols/seed", post(handlers::seed_symbols))
+        .route("/symbols/thai-stocks", get(handlers::get_thai_stocks))
+        .route("/symbols/tfex", get(handlers::get_tfex_symbols))
+        .route("/symbols/crypto", get(handlers::get_crypto_symbols))
+        .route("/symbols/foreign-stocks", get(handlers::get_foreign_stocks))
+        .route("/symbols/seed", post(handlers::seed_symbols))
+        .route("/symbols/delisted", get(handlers::get_delisted_symbols))
+        .route("/symbols/:asset_type/:symbol/delist", post(handlers::delist_symbol))
+        .route("/symbols/:asset_type/:symbol/delist", delete(handlers::relist_symbol))
+        .route("/symbols/:asset_type/:symbol/refresh-interval", put(handlers::set_symbol_refresh_interval))
+        .route("/symbols/:asset_type/:symbol/refresh-interval", delete(handlers::clear_symbol_refresh_interval))
         
         // Job scheduler routes
-        .route("/api/jobs", get(handlers::list_jobs))
-        .route("/api/jobs/:id", get(handlers::get_job))
-        .route("/api/jobs/:id", put(handlers::update_job))
-        .route("/api/jobs/:id/run", post(handlers::run_job))
+        .route("/jobs", get(handlers::list_jobs).post(handlers::create_job))
+        .route("/jobs/overview", get(handlers::jobs_overview))
+        .route("/jobs/reload", post(handlers::reload_jobs))
+        .route("/jobs/:id", get(handlers::get_job))
+        .route("/jobs/:id", put(handlers::update_job))
+        .route("/jobs/:id", delete(handlers::delete_job))
+        .route("/jobs/:id/run", post(handlers::run_job))
+        .route("/jobs/:id/toggle", post(handlers::toggle_job))
+        .route("/jobs/:id/runs", get(handlers::list_job_runs))
+        .route("/jobs/:id/runs/:run_id", get(handlers::get_job_run))
+        .route("/jobs/exports", get(handlers::list_exports).post(handlers::create_export))
+        .route("/jobs/exports/:id", get(handlers::get_export))
+        .route("/exports/download/:token", get(handlers::download_export))
         
         // Admin user management routes
-        .route("/api/admin/users", get(handlers::list_users))
-        .route("/api/admin/users", post(handlers::create_user))
-        .route("/api/admin/users/:id", get(handlers::get_user))
-        .route("/api/admin/users/:id", patch(handlers::update_user))
-        .route("/api/admin/users/:id", delete(handlers::delete_user))
-        .route("/api/admin/users/:id/reset-password", post(handlers::reset_user_password))
+        .route("/admin/users", get(handlers::list_users))
+        .route("/admin/users", post(handlers::create_user))
+        .route("/admin/users/:id", get(handlers::get_user))
+        .route("/admin/users/:id", patch(handlers::update_user))
+        .route("/admin/users/:id", delete(handlers::delete_user))
+        .route("/admin/users/:id/reset-password", post(handlers::reset_user_password))
         
         // Portfolio snapshot routes
-        .route("/api/snapshots", get(handlers::get_snapshots))
-        .route("/api/snapshots/now", post(handlers::create_snapshot_now))
+        .route("/snapshots", get(handlers::get_snapshots))
+        .route("/events", get(handlers::list_events))
+        .route("/snapshots/export.csv", get(handlers::export_snapshots_csv))
+        .route("/snapshots/now", post(handlers::create_snapshot_now))
         
         // Rate limit routes
-        .route("/api/rate-limits", get(handlers::get_rate_limits))
+        .route("/rate-limits", get(handlers::get_rate_limits))
+        .route("/admin/rate-limits/:api_name/override", post(handlers::override_rate_limit))
+        .route("/admin/rate-limits/:api_name/override", delete(handlers::clear_rate_limit_override))
+        .route("/admin/maintenance/orphans", get(handlers::get_orphan_report))
+        .route("/admin/maintenance/orphans/cleanup", post(handlers::cleanup_orphans))
+        .route("/admin/invites", get(handlers::list_invites).post(handlers::create_invite))
+        .route("/admin/invites/:id", delete(handlers::delete_invite))
+        .route("/admin/diagnostics", get(handlers::get_diagnostics))
+        .route("/admin/plugins", get(handlers::list_plugins))
         
         // API Provider routes
-        .route("/api/providers", get(handlers::list_providers))
-        .route("/api/providers", post(handlers::create_provider))
-        .route("/api/providers/:id", put(handlers::update_provider))
-        .route("/api/providers/:id", delete(handlers::delete_provider))
-        .route("/api/providers/market/:market_id", get(handlers::get_providers_by_market))
-        .route("/api/providers/market/:market_id/reorder", put(handlers::reorder_providers))
+        .route("/providers", get(handlers::list_providers))
+        .route("/providers", post(handlers::create_provider))
+        .route("/providers/:id", put(handlers::update_provider))
+        .route("/providers/:id", delete(handlers::delete_provider))
+        .route("/providers/:id/test-connectivity", post(handlers::test_provider_connectivity))
+        .route("/providers/market/:market_id", get(handlers::get_providers_by_market))
+        .route("/providers/market/:market_id/reorder", put(handlers::reorder_providers))
         
         // API Logs routes
-        .route("/api/logs", get(handlers::get_api_logs))
-        .route("/api/logs/stats", get(handlers::get_api_stats))
+        .route("/logs", get(handlers::get_api_logs))
+        .route("/logs/stats", get(handlers::get_api_stats))
         
         // Seed data routes
-        .route("/api/seed/upload", post(handlers::upload_seed))
-        .route("/api/seed/export", get(handlers::export_seed))
+        .route("/seed/export", get(handlers::export_seed))
         
         // Alert routes
-        .route("/api/alerts", get(handlers::list_alerts))
-        .route("/api/alerts", post(handlers::create_alert))
-        .route("/api/alerts/history", get(handlers::get_alert_history))
-        .route("/api/alerts/evaluate", post(handlers::evaluate_alerts))
-        .route("/api/alerts/:id", get(handlers::get_alert))
-        .route("/api/alerts/:id", put(handlers::update_alert))
-        .route("/api/alerts/:id", delete(handlers::delete_alert))
+        .route("/alerts", get(handlers::list_alerts))
+        .route("/alerts", post(handlers::create_alert))
+        .route("/alerts/history", get(handlers::get_alert_history))
+        .route("/alerts/evaluate", post(handlers::evaluate_alerts))
+        .route("/alerts/:id", get(handlers::get_alert))
+        .route("/alerts/:id", put(handlers::update_alert))
+        .route("/alerts/:id", delete(handlers::delete_alert))
+        .route("/alerts/:id/snooze", post(handlers::snooze_alert))
+        .route("/alerts/:id/snooze", delete(handlers::unsnooze_alert))
         
         // Notification routes
-        .route("/api/notifications", get(handlers::get_notifications))
-        .route("/api/notifications/read-all", post(handlers::mark_all_notifications_read))
-        .route("/api/notifications/test", post(handlers::send_test_notification))
-        .route("/api/notifications/:id/read", post(handlers::mark_notification_read))
+        .route("/notifications", get(handlers::get_notifications))
+        .route("/notifications/read-all", post(handlers::mark_all_notifications_read))
+        .route("/notifications/test", post(handlers::send_test_notification))
+        .route("/notifications/channels", get(handlers::list_notification_channels))
+        .route("/notifications/channels/:channel", put(handlers::set_notification_channel).delete(handlers::delete_notification_channel))
+        .route("/notifications/channels/:channel/test", post(handlers::test_notification_channel))
+        .route("/notifications/:id/read", post(handlers::mark_notification_read))
         
         // Push subscription routes
-        .route("/api/push/subscribe", post(handlers::subscribe_push))
-        
-        // Add middleware
+        .route("/push/subscribe", post(handlers::subscribe_push))
 
-        .layer(TraceLayer::new_for_http())
-        .layer(
-            CorsLayer::new()
-                .allow_origin(
-                    config.cors_allowed_origins
-                        .iter()
-                        .map(|origin| origin.parse::<axum::http::HeaderValue>().unwrap())
-                        .collect::<Vec<_>>()
-                )
-                .allow_methods([axum::http::Method::GET, axum::http::Method::POST, axum::http::Method::PUT, axum::http::Method::DELETE, axum::http::Method::PATCH, axum::http::Method::OPTIONS])
-                .allow_headers([axum::http::header::CONTENT_TYPE, axum::http::header::AUTHORIZATION, axum::http::header::COOKIE])
-                .allow_credentials(true),
-        )
-        .with_state(state);
+        // Exchange API key routes
+        .route("/integrations/exchange-keys", get(handlers::list_exchange_keys))
+        .route("/integrations/exchange-keys", post(handlers::create_exchange_key))
+        .route("/integrations/exchange-keys/:id", delete(handlers::delete_exchange_key))
+        .route("/integrations/exchange-keys/:id/balances", get(handlers::get_exchange_balances))
+        .route("/integrations/exchange-keys/:id/sync", post(handlers::sync_exchange_trades))
+        .route("/integrations/exchange-keys/:id/staged", get(handlers::list_staged_trades))
+        .route("/integrations/exchange-keys/:id/staged/import", post(handlers::import_staged_trades))
+        .route("/integrations/exchange-keys/:id/staged/dismiss", post(handlers::dismiss_staged_trades));
 
-    tracing::info!("🚀 Portfolio Backend starting on http://{}", addr);
-    tracing::info!("📊 API available at http://{}/api", addr);
-    tracing::info!("🔐 Auth endpoints available at http://{}/api/auth", addr);
-
-    // Start server
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // Large payloads get their own body limits; everything above keeps the default one
+    let bulk_api = Router::new()
+        .route("/transactions/bulk", post(handlers::create_transactions_bulk))
+        .route("/seed/upload", post(handlers::upload_seed));
+    let import_api = Router::new()
+        .route("/import/crypto", post(handlers::import_crypto_history))
+        .route("/transactions/import", post(handlers::import_broker_statement))
+        .route("/import/:id/reconcile", post(handlers::reconcile_import));
+    let routes = body_limit::limit_body(api, BodyLimit::default_group(config))
+        .merge(body_limit::limit_body(bulk_api, BodyLimit::bulk(config)))
+        .merge(body_limit::limit_body(import_api, BodyLimit::import(config)));
+    guest::read_only(routes, config)
 }
 
 async fn health_check() -> &'static str {
     "OK"
 }
 
-/// GET /api/status - Detailed system status including PocketBase connection
+/// GET /api/status - Whether the API and PocketBase are up; details are at /api/admin/diagnostics
 async fn system_status(State(state): State<AppState>) -> Json<serde_json::Value> {
     // Check PocketBase connection
-    let pb_url = format!("{}/api/health", state.config.pocketbase_url);
-    let pb_status = match reqwest::Client::new()
-        .get(&pb_url)
-        .timeout(std::time::Duration::from_secs(2))
-        .send()
-        .await
-    {
-        Ok(resp) => resp.status().is_success(),
-        Err(_) => false,
-    };
-    
+    let pb_status = state.db.is_healthy().await;
+
     Json(serde_json::json!({
         "status": "ok",
         "pocketbase": {
-            "url": state.config.pocketbase_url,
             "connected": pb_status
         },
         "timestamp": chrono::Utc::now().to_rfc3339()
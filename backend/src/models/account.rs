@@ -14,6 +14,61 @@ fn generate_pb_id() -> String {
         .collect()
 }
 
+/// What kind of account this is; savings and fixed deposits accrue interest
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountType {
+    #[default]
+    Investment,
+    Savings,
+    FixedDeposit,
+}
+
+/// How often accrued interest is credited to the balance
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Compounding {
+    #[default]
+    Daily,
+    Monthly,
+    Quarterly,
+    Annually,
+    AtMaturity,
+}
+
+/// Dimension a target allocation model sets weights on
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AllocationDimension {
+    #[default]
+    AssetType,
+    Market,
+    Sector,
+    Currency,
+}
+
+/// Target weight of one group, keyed like the allocation endpoint's groups (e.g. "stock", "USD")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationTarget {
+    pub key: String,
+    pub weight_percent: f64,
+}
+
+/// The mix an account should hold; groups without a target are meant to be sold down
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetAllocation {
+    #[serde(default)]
+    pub dimension: AllocationDimension,
+    pub targets: Vec<AllocationTarget>,
+    /// Drift in percentage points a group may reach before it is flagged for rebalancing
+    #[serde(default = "default_band_percent")]
+    pub band_percent: f64,
+}
+
+fn default_band_percent() -> f64 {
+    5.0
+}
+
 /// Account for grouping transactions
 /// e.g., "Savings Account", "Investment Account"
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,8 +86,38 @@ pub struct Account {
     pub target_value: Option<f64>,
     #[serde(default = "default_currency")]
     pub target_currency: String,
+    /// Currency the account's own summary is reported in
+    #[serde(default = "default_currency", deserialize_with = "deserialize_currency")]
+    pub base_currency: String,
     #[serde(default)]
     pub rank: i32,
+    #[serde(default, deserialize_with = "deserialize_or_default")]
+    pub account_type: AccountType,
+    /// Annual interest rate in percent (savings / fixed deposit only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apr_percent: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_or_default")]
+    pub compounding: Compounding,
+    /// Fixed deposit maturity (YYYY-MM-DD); interest stops accruing after this date
+    #[serde(default, deserialize_with = "deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub maturity_date: Option<String>,
+    /// Interest accrued but not yet credited
+    #[serde(default)]
+    pub pending_interest: f64,
+    /// Last day (YYYY-MM-DD) included in interest accrual
+    #[serde(default, deserialize_with = "deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub last_accrued_date: Option<String>,
+    /// Index the account is measured against, "asset_type:SYMBOL" (e.g. "foreign_stock:^GSPC")
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    pub benchmark: Option<String>,
+    /// Allocation model the account is rebalanced toward (PUT /accounts/:id/target-allocation)
+    #[serde(default)]
+    pub target_allocation: Option<TargetAllocation>,
+    /// Closed account: hidden from the default portfolio and closed to new transactions
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default, deserialize_with = "super::transaction::deserialize_optional_date", skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
     #[serde(default, skip_serializing)]
     pub created_at: DateTime<Utc>,
     #[serde(default, skip_serializing)]
@@ -48,6 +133,36 @@ fn default_currency() -> String {
     "THB".to_string()
 }
 
+/// PocketBase returns "" for fields added after the record was created
+fn deserialize_currency<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.filter(|c| !c.trim().is_empty()).unwrap_or_else(default_currency))
+}
+
+/// PocketBase returns "" for an unset text field
+pub(crate) fn deserialize_optional_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.filter(|v| !v.trim().is_empty()))
+}
+
+/// PocketBase returns "" for an unset text field; that reads as the enum's default
+fn deserialize_or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned + Default,
+{
+    match Option::<String>::deserialize(deserializer)?.filter(|v| !v.trim().is_empty()) {
+        Some(value) => serde_json::from_value(serde_json::Value::String(value)).map_err(serde::de::Error::custom),
+        None => Ok(T::default()),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateAccountRequest {
     pub name: String,
@@ -56,8 +171,31 @@ pub struct CreateAccountRequest {
     pub target_value: Option<f64>,
     #[serde(default = "default_currency")]
     pub target_currency: String,
+    #[serde(default = "default_currency")]
+    pub base_currency: String,
     #[serde(default)]
     pub rank: Option<i32>,
+    #[serde(default)]
+    pub account_type: AccountType,
+    pub apr_percent: Option<f64>,
+    #[serde(default)]
+    pub compounding: Compounding,
+    pub maturity_date: Option<String>,
+    pub benchmark: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListAccountsQuery {
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AccountsSummaryQuery {
+    /// Currency the combined totals are converted into (default THB)
+    pub base_currency: Option<String>,
+    #[serde(default)]
+    pub include_archived: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -67,7 +205,45 @@ pub struct UpdateAccountRequest {
     pub color: Option<String>,
     pub target_value: Option<f64>,
     pub target_currency: Option<String>,
+    pub base_currency: Option<String>,
     pub rank: Option<i32>,
+    pub account_type: Option<AccountType>,
+    pub apr_percent: Option<f64>,
+    pub compounding: Option<Compounding>,
+    pub maturity_date: Option<String>,
+    /// "" removes the benchmark
+    pub benchmark: Option<String>,
+}
+
+impl UpdateAccountRequest {
+    /// Whether the update changes how the account accrues interest (its type or rate)
+    pub fn changes_accrual(&self, account: &Account) -> bool {
+        self.account_type.as_ref().is_some_and(|t| *t != account.account_type)
+            || self.apr_percent.is_some_and(|apr| account.apr_percent != Some(apr))
+    }
+}
+
+/// Body of PUT /accounts/reorder: either the desired order of account ids, or one account
+/// moved to a position. Accounts left out of a full order keep their relative order after it.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ReorderAccountsRequest {
+    Order(Vec<String>),
+    Move { account_id: String, index: usize },
+}
+
+/// Authoritative account order after a reorder
+#[derive(Debug, Serialize)]
+pub struct ReorderAccountsResponse {
+    pub message: String,
+    pub accounts: Vec<AccountRank>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountRank {
+    pub id: String,
+    pub name: String,
+    pub rank: i32,
 }
 
 impl Default for Account {
@@ -81,7 +257,18 @@ impl Default for Account {
             color: None,
             target_value: None,
             target_currency: "THB".to_string(),
+            base_currency: "THB".to_string(),
             rank: 0,
+            account_type: AccountType::Investment,
+            apr_percent: None,
+            compounding: Compounding::Daily,
+            maturity_date: None,
+            pending_interest: 0.0,
+            last_accrued_date: None,
+            benchmark: None,
+            target_allocation: None,
+            archived: false,
+            archived_at: None,
             created_at: now,
             updated_at: now,
             created: None,
@@ -105,7 +292,19 @@ impl Account {
             color: req.color,
             target_value: req.target_value,
             target_currency: req.target_currency,
+            base_currency: req.base_currency,
             rank: req.rank.unwrap_or(0),
+            account_type: req.account_type,
+            apr_percent: req.apr_percent,
+            compounding: req.compounding,
+            maturity_date: req.maturity_date,
+            pending_interest: 0.0,
+            // Interest starts accruing from the day the account is created
+            last_accrued_date: Some(now.format("%Y-%m-%d").to_string()),
+            benchmark: req.benchmark,
+            target_allocation: None,
+            archived: false,
+            archived_at: None,
             created_at: now,
             updated_at: now,
             created: None,
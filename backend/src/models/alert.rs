@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use super::AssetType;
 
 /// Alert Rule - defines when and how to trigger notifications
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,34 +11,111 @@ pub struct AlertRule {
     pub alert_type: AlertType,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub symbol: Option<String>,
+    /// Asset type the symbol is priced as (price alerts)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_type: Option<AssetType>,
+    /// Price a price_change_percent alert measures its move from, taken when it was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_price: Option<f64>,
     pub threshold: f64,
     pub comparison: Comparison,
     pub channels: Vec<NotificationChannel>,
     pub is_active: bool,
     pub cooldown_minutes: i32,
+    /// After firing, the value must move back past the threshold by this percentage
+    /// before the alert can fire again
+    pub hysteresis_percent: f64,
+    /// Set when the alert fired and the value has not yet cleared the hysteresis band
+    pub awaiting_reset: bool,
+    /// No notifications until this time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<DateTime<Utc>>,
+    /// No notifications until unmuted
+    pub muted: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_triggered: Option<DateTime<Utc>>,
     pub created: DateTime<Utc>,
     pub updated: DateTime<Utc>,
 }
 
+impl AlertRule {
+    /// Muted or snoozed at the given time
+    pub fn is_silenced(&self, now: DateTime<Utc>) -> bool {
+        self.muted || self.snoozed_until.is_some_and(|until| until > now)
+    }
+
+    /// Still inside the cooldown window after the last trigger
+    pub fn in_cooldown(&self, now: DateTime<Utc>) -> bool {
+        self.last_triggered
+            .is_some_and(|at| now - at < chrono::Duration::minutes(self.cooldown_minutes as i64))
+    }
+
+    /// Value the alert fires at. A price_change_percent threshold is the size of the move, so
+    /// "below" fires on a fall of that many percent.
+    fn level(&self) -> f64 {
+        match (&self.alert_type, &self.comparison) {
+            (AlertType::PriceChangePercent, Comparison::Below) => -self.threshold.abs(),
+            (AlertType::PriceChangePercent, _) => self.threshold.abs(),
+            _ => self.threshold,
+        }
+    }
+
+    /// Size of the hysteresis band in the threshold's units
+    fn hysteresis_band(&self) -> f64 {
+        self.threshold.abs() * self.hysteresis_percent.max(0.0) / 100.0
+    }
+
+    /// Whether the value satisfies the alert condition
+    pub fn is_met(&self, value: f64) -> bool {
+        let level = self.level();
+        match self.comparison {
+            Comparison::Above => value >= level,
+            Comparison::Below => value <= level,
+            Comparison::Equals => (value - level).abs() < 0.001,
+        }
+    }
+
+    /// Whether the value has moved back out of the condition by more than the hysteresis band,
+    /// re-arming an alert that already fired
+    pub fn has_reset(&self, value: f64) -> bool {
+        let band = self.hysteresis_band();
+        let level = self.level();
+        match self.comparison {
+            Comparison::Above => value < level - band,
+            Comparison::Below => value > level + band,
+            Comparison::Equals => (value - level).abs() > band.max(0.001),
+        }
+    }
+}
+
 /// Types of alerts supported
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum AlertType {
     PriceAbove,
     PriceBelow,
+    /// Price moved by `threshold` percent from the reference price (up with "above", down
+    /// with "below")
+    PriceChangePercent,
     PnlThresholdPercent,
     PnlThresholdAbsolute,
     PortfolioChangePercent,
     DailyPnlReport,
 }
 
+impl AlertType {
+    /// Alert types evaluated against a symbol's price
+    pub fn is_price(&self) -> bool {
+        matches!(self, AlertType::PriceAbove | AlertType::PriceBelow | AlertType::PriceChangePercent)
+    }
+}
+
 impl std::fmt::Display for AlertType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             AlertType::PriceAbove => write!(f, "price_above"),
             AlertType::PriceBelow => write!(f, "price_below"),
+            AlertType::PriceChangePercent => write!(f, "price_change_percent"),
             AlertType::PnlThresholdPercent => write!(f, "pnl_threshold_percent"),
             AlertType::PnlThresholdAbsolute => write!(f, "pnl_threshold_absolute"),
             AlertType::PortfolioChangePercent => write!(f, "portfolio_change_percent"),
@@ -53,6 +131,7 @@ impl std::str::FromStr for AlertType {
         match s {
             "price_above" => Ok(AlertType::PriceAbove),
             "price_below" => Ok(AlertType::PriceBelow),
+            "price_change_percent" => Ok(AlertType::PriceChangePercent),
             "pnl_threshold_percent" => Ok(AlertType::PnlThresholdPercent),
             "pnl_threshold_absolute" => Ok(AlertType::PnlThresholdAbsolute),
             "portfolio_change_percent" => Ok(AlertType::PortfolioChangePercent),
@@ -95,12 +174,24 @@ impl std::str::FromStr for Comparison {
 }
 
 /// Notification delivery channels
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum NotificationChannel {
     Email,
     WebPush,
     InApp,
+    Telegram,
+    /// LINE Notify
+    Line,
+}
+
+impl NotificationChannel {
+    /// Channels delivered outside the app, to a target the user registers
+    pub const EXTERNAL: [NotificationChannel; 3] = [NotificationChannel::Email, NotificationChannel::Telegram, NotificationChannel::Line];
+
+    pub fn is_external(&self) -> bool {
+        Self::EXTERNAL.contains(self)
+    }
 }
 
 impl std::fmt::Display for NotificationChannel {
@@ -109,6 +200,8 @@ impl std::fmt::Display for NotificationChannel {
             NotificationChannel::Email => write!(f, "email"),
             NotificationChannel::WebPush => write!(f, "web_push"),
             NotificationChannel::InApp => write!(f, "in_app"),
+            NotificationChannel::Telegram => write!(f, "telegram"),
+            NotificationChannel::Line => write!(f, "line"),
         }
     }
 }
@@ -121,6 +214,8 @@ impl std::str::FromStr for NotificationChannel {
             "email" => Ok(NotificationChannel::Email),
             "web_push" => Ok(NotificationChannel::WebPush),
             "in_app" => Ok(NotificationChannel::InApp),
+            "telegram" => Ok(NotificationChannel::Telegram),
+            "line" => Ok(NotificationChannel::Line),
             _ => Err(format!("Unknown channel: {}", s)),
         }
     }
@@ -192,17 +287,26 @@ pub struct CreateAlertRequest {
     pub alert_type: AlertType,
     #[serde(default)]
     pub symbol: Option<String>,
+    /// Taken from the user's transactions in the symbol when omitted
+    #[serde(default)]
+    pub asset_type: Option<AssetType>,
     pub threshold: f64,
     pub comparison: Comparison,
     pub channels: Vec<NotificationChannel>,
     #[serde(default = "default_cooldown")]
     pub cooldown_minutes: i32,
+    #[serde(default = "default_hysteresis")]
+    pub hysteresis_percent: f64,
 }
 
 fn default_cooldown() -> i32 {
     60 // 1 hour default cooldown
 }
 
+fn default_hysteresis() -> f64 {
+    1.0 // re-arm once the value is 1% back past the threshold
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UpdateAlertRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -217,6 +321,15 @@ pub struct UpdateAlertRequest {
     pub is_active: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cooldown_minutes: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hysteresis_percent: Option<f64>,
+}
+
+/// Snooze an alert for a number of minutes, or mute it until unsnoozed when omitted
+#[derive(Debug, Deserialize)]
+pub struct SnoozeAlertRequest {
+    #[serde(default)]
+    pub minutes: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -13,6 +13,29 @@ pub struct ApiProvider {
     pub priority: i32,
     pub enabled: bool,
     pub timeout_ms: u64,
+    /// Include this provider when fetching consensus prices for its market
+    #[serde(default)]
+    pub use_for_consensus: bool,
+    /// Cache lifetime for responses from this provider (0 = endpoint class default)
+    #[serde(default)]
+    pub cache_ttl_seconds: u64,
+    /// Outbound proxy for this provider's requests (http, https, socks5 or socks5h URL, or
+    /// "direct"); unset means OUTBOUND_PROXY. Credentials are masked in API responses.
+    #[serde(
+        default,
+        deserialize_with = "super::account::deserialize_optional_text",
+        serialize_with = "serialize_redacted_proxy"
+    )]
+    pub proxy_url: Option<String>,
+}
+
+fn serialize_redacted_proxy<S>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    value.as_deref()
+        .map(crate::services::http_client::redact_proxy_url)
+        .serialize(serializer)
 }
 
 /// Request to create a new API provider
@@ -25,6 +48,9 @@ pub struct CreateApiProviderRequest {
     pub priority: i32,
     pub enabled: Option<bool>,
     pub timeout_ms: Option<u64>,
+    pub use_for_consensus: Option<bool>,
+    pub cache_ttl_seconds: Option<u64>,
+    pub proxy_url: Option<String>,
 }
 
 /// Request to update an API provider
@@ -36,6 +62,10 @@ pub struct UpdateApiProviderRequest {
     pub priority: Option<i32>,
     pub enabled: Option<bool>,
     pub timeout_ms: Option<u64>,
+    pub use_for_consensus: Option<bool>,
+    pub cache_ttl_seconds: Option<u64>,
+    /// Empty string removes the provider's proxy
+    pub proxy_url: Option<String>,
 }
 
 /// Request to reorder providers for a market
@@ -45,6 +75,23 @@ pub struct ReorderProvidersRequest {
     pub provider_ids: Vec<String>,
 }
 
+/// Result of reaching a provider's API URL through its effective proxy
+#[derive(Debug, Serialize)]
+pub struct ProviderConnectivity {
+    pub provider_id: String,
+    pub provider_type: String,
+    pub url: String,
+    /// "provider", "global" (OUTBOUND_PROXY) or "direct"
+    pub proxy_source: String,
+    /// Proxy URL with credentials masked
+    pub proxy: Option<String>,
+    /// Any HTTP response counts; the status shows whether the endpoint itself is happy
+    pub reachable: bool,
+    pub status: Option<u16>,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
 /// API call log entry for monitoring and debugging
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiCallLog {
@@ -101,6 +148,7 @@ pub enum ProviderType {
     // Stock/Finance APIs
     YahooFinance,
     SetMarketData,
+    Finnhub,
     // Gold
     GoldApi,
     GoldTraders,
@@ -121,6 +169,7 @@ impl ProviderType {
             "htx" | "huobi" => ProviderType::Htx,
             "yahoo_finance" | "yahoo" => ProviderType::YahooFinance,
             "set_marketdata" | "set" => ProviderType::SetMarketData,
+            "finnhub" => ProviderType::Finnhub,
             "goldapi" => ProviderType::GoldApi,
             "goldtraders" => ProviderType::GoldTraders,
             _ => ProviderType::Custom,
@@ -138,6 +187,7 @@ impl ProviderType {
             ProviderType::Htx => "htx",
             ProviderType::YahooFinance => "yahoo_finance",
             ProviderType::SetMarketData => "set_marketdata",
+            ProviderType::Finnhub => "finnhub",
             ProviderType::GoldApi => "goldapi",
             ProviderType::GoldTraders => "goldtraders",
             ProviderType::Custom => "custom",
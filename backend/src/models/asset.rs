@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use super::transaction::{AssetType, Market};
 
@@ -26,6 +27,25 @@ pub struct PortfolioAsset {
     pub position_type: String, // "spot", "long", "short"
     #[serde(default)]
     pub realized_dividend: f64, // Total dividends received
+    /// Currency the price source quoted in, when it differs from `currency` and was converted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_currency: Option<String>,
+    /// Set when the symbol is delisted: `current_price` is frozen at its final price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delisted_at: Option<DateTime<Utc>>,
+    /// Where `current_price` came from: a provider such as "coingecko", or "stored", "delisted",
+    /// "avg_cost" (no price found)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_source: Option<String>,
+    /// When that source quoted the price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_updated_at: Option<DateTime<Utc>>,
+    /// Quantity held as % of the symbol's average daily volume (stocks with known volume)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_volume_percent: Option<f64>,
+    /// Whether `currency` came from the transactions (or their market) rather than a fallback
+    #[serde(skip)]
+    pub currency_explicit: bool,
 }
 
 fn default_leverage() -> f64 { 1.0 }
@@ -51,9 +71,21 @@ impl PortfolioAsset {
             leverage: 1.0,
             position_type: "spot".to_string(),
             realized_dividend: 0.0,
+            price_currency: None,
+            delisted_at: None,
+            price_source: None,
+            price_updated_at: None,
+            daily_volume_percent: None,
+            currency_explicit: false,
         }
     }
 
+    /// Record which source produced `current_price` and when it was quoted
+    pub fn attribute_price(&mut self, source: &str, quoted_at: Option<DateTime<Utc>>) {
+        self.price_source = Some(source.to_string());
+        self.price_updated_at = quoted_at;
+    }
+
     /// Update P&L calculations based on current price (includes leverage/multiplier)
     pub fn calculate_pnl(&mut self, current_price: f64) {
         self.current_price = current_price;
@@ -188,6 +220,18 @@ pub struct PortfolioSummary {
     pub realized_pnl_breakdown: std::collections::HashMap<String, f64>,
     pub total_dividend: f64,
     pub assets_count: usize,
+    /// Outstanding loan balances converted to THB at today's rates
+    #[serde(default)]
+    pub total_liabilities: f64,
+    /// Outstanding balances by the liabilities' own currency
+    #[serde(default)]
+    pub liabilities_breakdown: std::collections::HashMap<String, f64>,
+    /// total_current_value - total_liabilities
+    #[serde(default)]
+    pub net_worth: f64,
+    /// Market value of unvested RSU/ESPP shares (not included in total_current_value)
+    #[serde(default)]
+    pub total_unvested_value: f64,
 }
 
 impl PortfolioSummary {
@@ -201,9 +245,20 @@ impl PortfolioSummary {
             realized_pnl_breakdown: std::collections::HashMap::new(),
             total_dividend: 0.0,
             assets_count: 0,
+            total_liabilities: 0.0,
+            liabilities_breakdown: std::collections::HashMap::new(),
+            net_worth: 0.0,
+            total_unvested_value: 0.0,
         }
     }
 
+    /// Add a holding's figures to the totals, at full precision
+    pub fn add_asset(&mut self, asset: &PortfolioAsset) {
+        self.total_invested += asset.total_cost;
+        self.total_current_value += asset.current_value;
+        self.total_unrealized_pnl += asset.unrealized_pnl;
+    }
+
     pub fn calculate_percent(&mut self) {
         if self.total_invested > 0.0 {
             self.total_unrealized_pnl_percent = 
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+pub const BENCHMARK_LEVELS_COLLECTION: &str = "benchmark_levels";
+
+/// Daily close of a benchmark index, stored by the benchmark_update job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkLevel {
+    #[serde(default)]
+    pub id: String,
+    /// Benchmark code, e.g. "SET" (see `services::benchmarks::BENCHMARKS`)
+    pub benchmark: String,
+    /// Trading day as YYYY-MM-DD
+    pub date: String,
+    pub level: f64,
+    #[serde(default)]
+    pub currency: String,
+}
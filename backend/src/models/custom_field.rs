@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const CUSTOM_FIELDS_COLLECTION: &str = "custom_field_definitions";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomFieldType {
+    Text,
+    Number,
+    Boolean,
+    /// YYYY-MM-DD
+    Date,
+    /// One of `options`
+    Select,
+}
+
+/// A user-defined field that can be set on transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomFieldDefinition {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub user_id: String,
+    /// Key used in `custom_fields` on transactions, e.g. "broker_order_id"
+    pub key: String,
+    pub label: String,
+    pub field_type: CustomFieldType,
+    /// Allowed values for select fields
+    #[serde(default, deserialize_with = "deserialize_null_as_empty")]
+    pub options: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCustomFieldRequest {
+    pub key: String,
+    pub label: String,
+    pub field_type: CustomFieldType,
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+/// The key and type are fixed once values exist; only presentation can change
+#[derive(Debug, Deserialize)]
+pub struct UpdateCustomFieldRequest {
+    pub label: Option<String>,
+    pub options: Option<Vec<String>>,
+}
+
+impl CustomFieldDefinition {
+    /// Check a value against the field type, normalizing where the intent is clear
+    /// (numeric strings for number fields, "true"/"false" for booleans)
+    pub fn normalize(&self, value: &Value) -> Result<Value, String> {
+        let invalid = |expected: &str| format!("Custom field '{}' expects {}, got {}", self.key, expected, value);
+        match (&self.field_type, value) {
+            (CustomFieldType::Text, Value::String(_)) => Ok(value.clone()),
+            (CustomFieldType::Text, Value::Number(n)) => Ok(Value::String(n.to_string())),
+            (CustomFieldType::Number, Value::Number(_)) => Ok(value.clone()),
+            (CustomFieldType::Number, Value::String(s)) => s.trim().parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| invalid("a number")),
+            (CustomFieldType::Boolean, Value::Bool(_)) => Ok(value.clone()),
+            (CustomFieldType::Boolean, Value::String(s)) => match s.trim().to_lowercase().as_str() {
+                "true" | "yes" | "1" => Ok(Value::Bool(true)),
+                "false" | "no" | "0" => Ok(Value::Bool(false)),
+                _ => Err(invalid("a boolean")),
+            },
+            (CustomFieldType::Date, Value::String(s)) => chrono::NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+                .map(|d| Value::String(d.format("%Y-%m-%d").to_string()))
+                .map_err(|_| invalid("a YYYY-MM-DD date")),
+            (CustomFieldType::Select, Value::String(s)) if self.options.iter().any(|o| o == s) => Ok(value.clone()),
+            (CustomFieldType::Select, _) => Err(invalid(&format!("one of {:?}", self.options))),
+            (CustomFieldType::Text, _) => Err(invalid("text")),
+            (CustomFieldType::Number, _) => Err(invalid("a number")),
+            (CustomFieldType::Boolean, _) => Err(invalid("a boolean")),
+            (CustomFieldType::Date, _) => Err(invalid("a YYYY-MM-DD date")),
+        }
+    }
+}
+
+/// Plain-text form of a custom field value, used for filtering and CSV export
+pub fn custom_value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn deserialize_null_as_empty<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let opt = Option::<Vec<String>>::deserialize(deserializer)?;
+    Ok(opt.unwrap_or_default())
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+pub const DASHBOARDS_COLLECTION: &str = "dashboards";
+
+/// Where a widget sits on the dashboard grid, in grid cells
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WidgetLayout {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// The API call a widget makes to fill itself, e.g. GET /api/portfolio/movers?days=7
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WidgetQuery {
+    /// Path under /api, starting with "/"
+    pub endpoint: String,
+    /// Query string parameters
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, serde_json::Value>,
+}
+
+/// One card on a dashboard
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DashboardWidget {
+    /// Client-chosen id, unique within the dashboard
+    pub id: String,
+    /// Which component renders the card, e.g. "allocation_pie" or "movers"
+    pub widget_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub layout: WidgetLayout,
+    pub query: WidgetQuery,
+    /// Display settings the client keeps for the widget (colors, chart type...)
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub options: serde_json::Value,
+}
+
+/// A user's saved dashboard layout, shared by all their clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dashboard {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub user_id: String,
+    pub name: String,
+    /// The dashboard clients open first; at most one per user
+    #[serde(default)]
+    pub is_default: bool,
+    #[serde(default, deserialize_with = "deserialize_null_as_empty")]
+    pub widgets: Vec<DashboardWidget>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDashboardRequest {
+    pub name: String,
+    #[serde(default)]
+    pub is_default: bool,
+    #[serde(default)]
+    pub widgets: Vec<DashboardWidget>,
+}
+
+/// Omitted fields are kept; `widgets` replaces the whole list
+#[derive(Debug, Deserialize)]
+pub struct UpdateDashboardRequest {
+    pub name: Option<String>,
+    pub is_default: Option<bool>,
+    pub widgets: Option<Vec<DashboardWidget>>,
+    /// `updated` of the version the client edited; a newer saved version is a conflict
+    pub expected_updated: Option<String>,
+}
+
+fn deserialize_null_as_empty<'de, D>(deserializer: D) -> Result<Vec<DashboardWidget>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let opt = Option::<Vec<DashboardWidget>>::deserialize(deserializer)?;
+    Ok(opt.unwrap_or_default())
+}
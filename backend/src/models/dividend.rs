@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::models::transaction::{AssetType, Market};
+
+/// A dividend or interest payment, read from a `dividend` transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct DividendPayment {
+    pub transaction_id: String,
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    /// Total amount received, in `currency`
+    pub amount: f64,
+    pub currency: String,
+    pub paid_at: DateTime<Utc>,
+    /// From the `ex_date` custom field when recorded, otherwise the payment date
+    pub ex_date: DateTime<Utc>,
+    /// Amount per unit held on the payment date; unknown if nothing was held
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_share: Option<f64>,
+    /// Interest credited to a savings or cash account rather than a company dividend
+    pub interest: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DividendPeriod {
+    Year,
+    #[default]
+    Month,
+}
+
+/// Income received in one year ("2024") or month ("2024-06"), in the base currency
+#[derive(Debug, Clone, Serialize)]
+pub struct DividendPeriodTotal {
+    pub period: String,
+    pub total: f64,
+    pub payments: usize,
+    pub by_symbol: BTreeMap<String, f64>,
+}
+
+/// Expected income from a held position, from its trailing 12 months of payments
+#[derive(Debug, Clone, Serialize)]
+pub struct DividendProjection {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    pub currency: String,
+    pub quantity: f64,
+    /// Sum of per-share payments over the last 12 months
+    pub trailing_per_share: f64,
+    pub payments_per_year: usize,
+    /// In `currency`
+    pub projected_annual: f64,
+    /// In the response's base currency
+    pub projected_annual_base: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_value_base: Option<f64>,
+    /// Projected income over current value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yield_percent: Option<f64>,
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use chrono::{Months, NaiveDate};
+use super::transaction::{AssetType, Market};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GrantType {
+    /// Restricted stock units: shares delivered at vest, cost basis = market price on the vest date
+    Rsu,
+    /// Employee stock purchase plan: shares bought at a discount to the market price
+    Espp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum VestingFrequency {
+    Monthly,
+    #[default]
+    Quarterly,
+    Annually,
+}
+
+impl VestingFrequency {
+    pub fn months(&self) -> u32 {
+        match self {
+            VestingFrequency::Monthly => 1,
+            VestingFrequency::Quarterly => 3,
+            VestingFrequency::Annually => 12,
+        }
+    }
+}
+
+/// A single vest (or ESPP purchase) date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VestingTranche {
+    /// YYYY-MM-DD
+    pub date: String,
+    pub quantity: f64,
+    #[serde(default)]
+    pub vested: bool,
+    /// Market price used when the tranche vested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vest_price: Option<f64>,
+    /// Acquisition transaction created for this tranche
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<String>,
+}
+
+/// Equity compensation grant with its vesting schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquityGrant {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub user_id: String,
+    pub grant_type: GrantType,
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// YYYY-MM-DD
+    pub grant_date: String,
+    pub total_quantity: f64,
+    /// ESPP discount to the market price at purchase, in percent
+    #[serde(default)]
+    pub espp_discount_percent: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub tranches: Vec<VestingTranche>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+}
+
+impl EquityGrant {
+    pub fn unvested_quantity(&self) -> f64 {
+        self.tranches.iter().filter(|t| !t.vested).map(|t| t.quantity).sum()
+    }
+
+    pub fn vested_quantity(&self) -> f64 {
+        self.tranches.iter().filter(|t| t.vested).map(|t| t.quantity).sum()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateEquityGrantRequest {
+    pub grant_type: GrantType,
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+    pub currency: Option<String>,
+    pub grant_date: String,
+    pub total_quantity: f64,
+    #[serde(default)]
+    pub espp_discount_percent: f64,
+    pub account_id: Option<String>,
+    pub notes: Option<String>,
+    /// Months before the first vest (everything accrued so far vests at the cliff)
+    #[serde(default)]
+    pub cliff_months: u32,
+    /// Total vesting period in months
+    #[serde(default = "default_vesting_months")]
+    pub vesting_months: u32,
+    #[serde(default)]
+    pub frequency: VestingFrequency,
+    /// Explicit schedule; overrides cliff/vesting/frequency when given
+    pub tranches: Option<Vec<VestingTranche>>,
+}
+
+fn default_vesting_months() -> u32 {
+    48
+}
+
+/// Build an evenly spread schedule: a tranche every `frequency` months after the cliff,
+/// with the cliff tranche catching up everything accrued before it.
+pub fn build_vesting_schedule(
+    grant_date: NaiveDate,
+    total_quantity: f64,
+    cliff_months: u32,
+    vesting_months: u32,
+    frequency: &VestingFrequency,
+) -> Vec<VestingTranche> {
+    let vesting_months = vesting_months.max(1);
+    let step = frequency.months();
+    let mut vest_months: Vec<u32> = (1..=vesting_months)
+        .filter(|m| *m >= cliff_months && (m.is_multiple_of(step) || *m == cliff_months || *m == vesting_months))
+        .collect();
+    vest_months.dedup();
+
+    let mut tranches = Vec::with_capacity(vest_months.len());
+    let mut allocated = 0.0;
+    for (i, month) in vest_months.iter().enumerate() {
+        let quantity = if i + 1 == vest_months.len() {
+            total_quantity - allocated
+        } else {
+            // Round what has vested so far, not each tranche, so whole-share grants spread
+            // their shares evenly instead of piling the remainder onto the last tranche
+            let vested_so_far = total_quantity * *month as f64 / vesting_months as f64;
+            let vested_so_far = if total_quantity.fract() == 0.0 { vested_so_far.floor() } else { vested_so_far };
+            vested_so_far - allocated
+        };
+        // Small grants leave some dates with no whole share to vest
+        if quantity <= 0.0 {
+            continue;
+        }
+        allocated += quantity;
+
+        let date = grant_date
+            .checked_add_months(Months::new(*month))
+            .unwrap_or(grant_date);
+        tranches.push(VestingTranche {
+            date: date.format("%Y-%m-%d").to_string(),
+            quantity,
+            vested: false,
+            vest_price: None,
+            transaction_id: None,
+        });
+    }
+    tranches
+}
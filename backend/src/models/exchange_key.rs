@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::account::deserialize_optional_text;
+use crate::models::transaction::{deserialize_optional_date, Market};
+
+pub const EXCHANGE_KEYS_COLLECTION: &str = "exchange_keys";
+
+/// Exchanges whose private API can be read with a user's key
+pub const KEYED_EXCHANGES: &[Market] = &[Market::Binance, Market::Bitkub];
+
+/// A user's API key for an exchange. The key and secret are stored sealed in `secret` and are
+/// never serialized back out; `api_key_hint` identifies the key in listings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeKey {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub user_id: String,
+    pub exchange: Market,
+    /// User's name for the key, e.g. "Main account"
+    #[serde(default)]
+    pub label: String,
+    /// Last 4 characters of the API key
+    #[serde(default)]
+    pub api_key_hint: String,
+    #[serde(default, skip_serializing)]
+    pub secret: String,
+    #[serde(default, deserialize_with = "deserialize_optional_date", skip_serializing_if = "Option::is_none")]
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// Error from the last use of the key, cleared by the next successful one
+    #[serde(default, deserialize_with = "deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateExchangeKeyRequest {
+    pub exchange: Market,
+    pub label: Option<String>,
+    pub api_key: String,
+    pub api_secret: String,
+}
+
+/// Decrypted key pair, only held in memory while calling the exchange
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExchangeCredentials {
+    pub api_key: String,
+    pub api_secret: String,
+}
+
+impl std::fmt::Debug for ExchangeCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExchangeCredentials").field("api_key", &"***").field("api_secret", &"***").finish()
+    }
+}
+
+/// One asset in an exchange wallet
+#[derive(Debug, Clone, Serialize)]
+pub struct ExchangeBalance {
+    pub asset: String,
+    /// Available to trade or withdraw
+    pub free: f64,
+    /// Held by open orders
+    pub locked: f64,
+}
+
+impl ExchangeBalance {
+    pub fn total(&self) -> f64 {
+        self.free + self.locked
+    }
+}
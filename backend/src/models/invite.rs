@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const INVITES_COLLECTION: &str = "invites";
+
+/// Admin-generated code that allows one registration while the instance is invitation-only
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invite {
+    #[serde(default)]
+    pub id: String,
+    pub code: String,
+    /// Only this address may redeem the code (empty = anyone holding it)
+    #[serde(default)]
+    pub email: String,
+    /// Admin who generated the code
+    #[serde(default)]
+    pub created_by: String,
+    /// Empty = never expires
+    #[serde(default)]
+    pub expires_at: String,
+    /// User who redeemed the code (empty = still unused)
+    #[serde(default)]
+    pub used_by: String,
+    #[serde(default)]
+    pub used_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+}
+
+impl Invite {
+    pub fn is_used(&self) -> bool {
+        !self.used_by.is_empty()
+    }
+
+    /// PocketBase stores dates as "2024-01-01 00:00:00.000Z"; accept RFC 3339 as well
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        if self.expires_at.is_empty() {
+            return None;
+        }
+        DateTime::parse_from_rfc3339(&self.expires_at)
+            .map(|d| d.with_timezone(&Utc))
+            .or_else(|_| {
+                chrono::NaiveDateTime::parse_from_str(&self.expires_at, "%Y-%m-%d %H:%M:%S%.fZ")
+                    .map(|d| d.and_utc())
+            })
+            .ok()
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at().is_some_and(|at| at <= now)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateInviteRequest {
+    /// Restrict the code to one email address
+    pub email: Option<String>,
+    /// Days until the code expires (omit for no expiry)
+    pub expires_in_days: Option<i64>,
+}
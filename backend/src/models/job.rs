@@ -2,9 +2,10 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Job status enum
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum JobStatus {
+    #[default]
     Idle,
     Running,
     Success,
@@ -12,10 +13,16 @@ pub enum JobStatus {
     Disabled,
 }
 
-impl Default for JobStatus {
-    fn default() -> Self {
-        JobStatus::Idle
-    }
+/// What the scheduler does at startup about runs a job missed while the server was down
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Drop the missed runs and wait for the next slot
+    Skip,
+    /// Run once right away, however many slots were missed
+    RunOnceLate,
+    /// Run once for every missed slot, oldest first; jobs that can't work for a past slot run once
+    Backfill,
 }
 
 /// Job configuration stored in PocketBase
@@ -28,7 +35,7 @@ pub struct JobConfig {
     #[serde(default)]
     pub name_en: String,
     #[serde(default)]
-    pub job_type: String,           // "api_status_check", "price_update", etc.
+    pub job_type: String,           // "api_status_check", "price_update", "interest_accrual", "equity_vesting", "weekly_report", "orphan_cleanup", "benchmark_update", etc.
     #[serde(default = "default_interval", deserialize_with = "deserialize_interval")]
     pub interval_seconds: u64,      // Interval in seconds (default: 86400 = 1 day)
     #[serde(default = "default_true")]
@@ -43,6 +50,15 @@ pub struct JobConfig {
     pub schedule_times: Option<Vec<String>>, // Specific run times e.g. ["07:00", "17:00"]
     #[serde(default)]
     pub last_result: Option<serde_json::Value>,
+    /// Runs that came due while the previous run was still going and were dropped
+    #[serde(default)]
+    pub skipped_runs: u64,
+    #[serde(default)]
+    pub last_skipped_at: Option<String>,
+    /// None follows the job type's default, see `catch_up_policy`. Persisted in the jobs
+    /// collection's `catch_up` text field.
+    #[serde(default, deserialize_with = "deserialize_catch_up")]
+    pub catch_up: Option<CatchUpPolicy>,
     // PocketBase auto-generated fields - ignore unknown fields
     #[serde(default, skip_serializing)]
     pub created: Option<String>,
@@ -99,6 +115,28 @@ where
     }
 }
 
+fn deserialize_catch_up<'de, D>(deserializer: D) -> Result<Option<CatchUpPolicy>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    // PocketBase stores an unset text field as ""
+    let v = serde_json::Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(v).ok())
+}
+
+impl JobConfig {
+    /// The job's catch-up policy. By default daily snapshots are backfilled so history has no
+    /// holes, jobs at fixed times skip what they missed, and interval jobs run once late.
+    pub fn catch_up_policy(&self) -> CatchUpPolicy {
+        match self.catch_up {
+            Some(policy) => policy,
+            None if self.job_type == "portfolio_snapshot" => CatchUpPolicy::Backfill,
+            None if self.schedule_times.is_some() => CatchUpPolicy::Skip,
+            None => CatchUpPolicy::RunOnceLate,
+        }
+    }
+}
+
 fn default_interval() -> u64 { 86400 }
 fn default_true() -> bool { true }
 
@@ -116,6 +154,9 @@ impl Default for JobConfig {
             next_run: None,
             schedule_times: None,
             last_result: None,
+            skipped_runs: 0,
+            last_skipped_at: None,
+            catch_up: None,
             created: None,
             updated: None,
             collection_id: None,
@@ -125,18 +166,61 @@ impl Default for JobConfig {
     }
 }
 
-/// Job run history entry
+pub const JOB_RUNS_COLLECTION: &str = "job_runs";
+
+/// What started a job run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum JobTrigger {
+    #[default]
+    Schedule,
+    /// POST /api/jobs/:id/run
+    Manual,
+    /// Automatic retry after a transient failure
+    Retry,
+    /// Making up for runs missed while the server was down
+    #[serde(rename = "catch_up")]
+    CatchUp,
+}
+
+/// One execution of a job, stored in job_runs
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)]
-pub struct JobRunHistory {
+pub struct JobRun {
     #[serde(default)]
     pub id: String,
     pub job_id: String,
+    #[serde(default)]
+    pub job_type: String,
+    #[serde(default)]
+    pub trigger: JobTrigger,
+    /// 1 for the first try, counting up through retries of the same failure
+    #[serde(default = "default_attempt")]
+    pub attempt: u32,
     pub started_at: DateTime<Utc>,
+    #[serde(default, deserialize_with = "super::transaction::deserialize_optional_date")]
     pub finished_at: Option<DateTime<Utc>>,
-    pub status: String,             // "success", "failed", "running"
-    pub results: serde_json::Value, // Detailed results
-    pub error_message: Option<String>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_job_status")]
+    pub status: JobStatus,
+    #[serde(default)]
+    pub result: Option<serde_json::Value>,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// When the next attempt is due, for failures that will be retried
+    #[serde(default, deserialize_with = "super::transaction::deserialize_optional_date")]
+    pub retry_at: Option<DateTime<Utc>>,
+}
+
+fn default_attempt() -> u32 { 1 }
+
+/// A page of GET /api/jobs/:id/runs, newest first
+#[derive(Debug, Clone, Serialize)]
+pub struct JobRunsPage {
+    pub runs: Vec<JobRun>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total_items: u32,
 }
 
 /// Request to update job config
@@ -145,6 +229,25 @@ pub struct UpdateJobRequest {
     pub interval_seconds: Option<u64>,
     pub enabled: Option<bool>,
     pub schedule_times: Option<serde_json::Value>, // Use Value to distinguish null vs missing vs array
+    #[serde(default)]
+    pub catch_up: Option<CatchUpPolicy>,
+}
+
+/// Request to create a job
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateJobRequest {
+    pub name: String,
+    pub name_en: Option<String>,
+    pub job_type: String,
+    #[serde(default = "default_interval")]
+    pub interval_seconds: u64,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Run at these UTC times ("HH:MM") instead of every interval
+    pub schedule_times: Option<Vec<String>>,
+    /// Defaults by job type, see `JobConfig::catch_up_policy`
+    #[serde(default)]
+    pub catch_up: Option<CatchUpPolicy>,
 }
 
 /// API status check result for a single endpoint
@@ -180,3 +283,87 @@ pub mod intervals {
     #[allow(dead_code)]
     pub const ONE_WEEK: u64 = 604800;
 }
+
+/// Scheduler health summary returned by GET /api/jobs/overview
+#[derive(Debug, Clone, Serialize)]
+pub struct SchedulerOverview {
+    pub heartbeat: SchedulerHeartbeat,
+    pub enabled_jobs: usize,
+    pub overdue_jobs: Vec<OverdueJob>,
+    /// Mean delay between scheduled and actual start over the recent scheduled runs
+    pub average_drift_seconds: Option<f64>,
+    pub drift_by_job: Vec<JobDrift>,
+    /// Most recent failures first
+    pub last_errors: Vec<JobError>,
+    /// Symbols with a stale price waiting for the retry pass
+    pub price_retries: Vec<PriceRetry>,
+    /// Job runs that failed transiently and will be retried
+    pub job_retries: Vec<JobRetry>,
+    /// Ids of the jobs running right now
+    pub running_jobs: Vec<String>,
+    /// How many jobs may run at the same time (JOB_MAX_CONCURRENCY)
+    pub max_concurrent_jobs: usize,
+}
+
+/// Liveness of the scheduler loop
+#[derive(Debug, Clone, Serialize)]
+pub struct SchedulerHeartbeat {
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_tick_at: Option<DateTime<Utc>>,
+    pub seconds_since_last_tick: Option<i64>,
+    pub tick_interval_seconds: u64,
+    pub ticks: u64,
+    /// False when the loop never started or has missed several ticks
+    pub alive: bool,
+}
+
+/// Enabled job whose next_run has passed without the loop picking it up
+#[derive(Debug, Clone, Serialize)]
+pub struct OverdueJob {
+    pub id: String,
+    pub name: String,
+    pub job_type: String,
+    pub next_run: String,
+    pub overdue_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobDrift {
+    pub job_id: String,
+    pub name: String,
+    pub samples: usize,
+    pub average_drift_seconds: f64,
+    pub max_drift_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobError {
+    pub job_id: String,
+    pub name: String,
+    pub at: DateTime<Utc>,
+    pub error: String,
+}
+
+/// Symbol whose price could not be fetched or saved, waiting for another attempt
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceRetry {
+    pub symbol: String,
+    pub asset_type: String,
+    pub market: Option<String>,
+    #[serde(skip)]
+    pub currency: Option<String>,
+    pub attempts: u32,
+    pub last_error: String,
+    pub retry_at: DateTime<Utc>,
+}
+
+/// Job waiting for an automatic retry after a transient failure
+#[derive(Debug, Clone, Serialize)]
+pub struct JobRetry {
+    pub job_id: String,
+    pub name: String,
+    /// Attempt that failed
+    pub attempt: u32,
+    pub last_error: String,
+    pub retry_at: DateTime<Utc>,
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use super::account::Compounding;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LiabilityType {
+    MarginLoan,
+    BankLoan,
+    CreditLine,
+    #[default]
+    Other,
+}
+
+/// Money owed (margin loan, bank loan, ...); subtracted from assets for net worth
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Liability {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub user_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub liability_type: LiabilityType,
+    pub currency: String,
+    /// Amount originally borrowed
+    #[serde(default)]
+    pub principal: f64,
+    /// Outstanding balance including capitalized interest
+    #[serde(default)]
+    pub balance: f64,
+    #[serde(default)]
+    pub apr_percent: f64,
+    #[serde(default)]
+    pub compounding: Compounding,
+    /// Account the loan funds (e.g. the brokerage account for a margin loan)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Interest accrued but not yet capitalized
+    #[serde(default)]
+    pub pending_interest: f64,
+    /// Last day (YYYY-MM-DD) included in interest accrual
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_accrued_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateLiabilityRequest {
+    pub name: String,
+    #[serde(default)]
+    pub liability_type: LiabilityType,
+    pub currency: String,
+    pub principal: f64,
+    #[serde(default)]
+    pub apr_percent: f64,
+    #[serde(default)]
+    pub compounding: Compounding,
+    pub account_id: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateLiabilityRequest {
+    pub name: Option<String>,
+    pub liability_type: Option<LiabilityType>,
+    pub apr_percent: Option<f64>,
+    pub compounding: Option<Compounding>,
+    pub account_id: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LiabilityTransactionKind {
+    /// Borrow more (increases balance)
+    Draw,
+    /// Pay back (decreases balance)
+    Repayment,
+    /// Capitalized interest (increases balance)
+    Interest,
+}
+
+/// Movement on a liability balance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiabilityTransaction {
+    #[serde(default)]
+    pub id: String,
+    pub liability_id: String,
+    #[serde(default)]
+    pub user_id: String,
+    pub kind: LiabilityTransactionKind,
+    pub amount: f64,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// Request for a draw or repayment (interest is only recorded by the accrual job)
+#[derive(Debug, Deserialize)]
+pub struct CreateLiabilityTransactionRequest {
+    pub kind: LiabilityTransactionKind,
+    pub amount: f64,
+    #[serde(default = "Utc::now")]
+    pub timestamp: DateTime<Utc>,
+    pub notes: Option<String>,
+}
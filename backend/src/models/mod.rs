@@ -5,6 +5,22 @@ pub mod account;
 pub mod job;
 pub mod api_provider;
 pub mod alert;
+pub mod liability;
+pub mod equity_grant;
+pub mod custom_field;
+pub mod snapshot;
+pub mod invite;
+pub mod sync_event;
+pub mod user_settings;
+pub mod price_history;
+pub mod order;
+pub mod dividend;
+pub mod session;
+pub mod exchange_key;
+pub mod staged_trade;
+pub mod benchmark;
+pub mod notification_target;
+pub mod dashboard;
 
 pub use transaction::*;
 pub use asset::*;
@@ -13,4 +29,20 @@ pub use account::*;
 pub use job::*;
 pub use api_provider::*;
 pub use alert::*;
+pub use liability::*;
+pub use equity_grant::*;
+pub use custom_field::*;
+pub use snapshot::*;
+pub use invite::*;
+pub use sync_event::*;
+pub use user_settings::*;
+pub use price_history::*;
+pub use order::*;
+pub use dividend::*;
+pub use session::*;
+pub use exchange_key::*;
+pub use staged_trade::*;
+pub use benchmark::*;
+pub use notification_target::*;
+pub use dashboard::*;
 
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::account::deserialize_optional_text;
+use crate::models::alert::NotificationChannel;
+use crate::models::transaction::deserialize_optional_date;
+
+pub const NOTIFICATION_TARGETS_COLLECTION: &str = "notification_targets";
+
+/// Where a user receives one external channel: an email address, a Telegram chat ID or a LINE
+/// Notify token. The LINE token is stored sealed in `secret` and never serialized back out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationTarget {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub user_id: String,
+    pub channel: NotificationChannel,
+    /// Email address or Telegram chat ID; empty for LINE
+    #[serde(default)]
+    pub target: String,
+    /// Last 4 characters of the LINE token
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub token_hint: String,
+    #[serde(default, skip_serializing)]
+    pub secret: String,
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default, deserialize_with = "deserialize_optional_date", skip_serializing_if = "Option::is_none")]
+    pub last_delivered_at: Option<DateTime<Utc>>,
+    /// Error from the last delivery, cleared by the next successful one
+    #[serde(default, deserialize_with = "deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// PUT /api/notifications/channels/:channel
+#[derive(Debug, Deserialize)]
+pub struct UpdateNotificationTargetRequest {
+    /// Email address (email) or chat ID (telegram)
+    #[serde(default)]
+    pub target: Option<String>,
+    /// LINE Notify personal access token; kept when omitted on an update
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::account::deserialize_optional_text;
+use crate::models::transaction::{deserialize_null_as_empty, deserialize_optional_date, AssetType, Market, TradeAction};
+
+pub const ORDERS_COLLECTION: &str = "orders";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    /// Fills at `price` or better
+    #[default]
+    Limit,
+    /// Becomes a market order once the price reaches `price`
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// Working at the broker
+    #[default]
+    Open,
+    /// The market reached the order price, so it has likely filled
+    Triggered,
+    /// Booked as a transaction
+    Filled,
+    Cancelled,
+}
+
+/// A limit or stop order placed at a broker, tracked until it fills
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub user_id: String,
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    pub action: TradeAction,
+    #[serde(default)]
+    pub order_type: OrderType,
+    pub quantity: f64,
+    /// Limit or stop price
+    pub price: f64,
+    #[serde(default, deserialize_with = "deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub status: OrderStatus,
+    /// Market price that triggered the order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub triggered_price: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_optional_date", skip_serializing_if = "Option::is_none")]
+    pub triggered_at: Option<DateTime<Utc>>,
+    /// Transactions the fills were booked as, oldest first
+    #[serde(default, deserialize_with = "deserialize_null_as_empty")]
+    pub transaction_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+}
+
+impl Order {
+    /// Buying (opening long or covering short) rather than selling
+    pub fn is_buy(&self) -> bool {
+        matches!(self.action, TradeAction::Buy | TradeAction::Long | TradeAction::CloseShort)
+    }
+
+    /// Whether a market price reaches the order: limits fill at their price or better,
+    /// stops trigger once the price moves through them
+    pub fn is_triggered_by(&self, market_price: f64) -> bool {
+        match (self.order_type, self.is_buy()) {
+            (OrderType::Limit, true) | (OrderType::Stop, false) => market_price <= self.price,
+            (OrderType::Limit, false) | (OrderType::Stop, true) => market_price >= self.price,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateOrderRequest {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+    pub action: TradeAction,
+    #[serde(default)]
+    pub order_type: OrderType,
+    pub quantity: f64,
+    pub price: f64,
+    pub currency: Option<String>,
+    pub account_id: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateOrderRequest {
+    pub order_type: Option<OrderType>,
+    pub quantity: Option<f64>,
+    pub price: Option<f64>,
+    pub account_id: Option<String>,
+    pub notes: Option<String>,
+    /// `cancelled`, or `open` to re-arm a triggered order that did not fill
+    pub status: Option<OrderStatus>,
+}
+
+/// Book an order as a transaction; omitted values come from the order
+#[derive(Debug, Deserialize)]
+pub struct FillOrderRequest {
+    /// Execution price (defaults to the limit/stop price)
+    pub price: Option<f64>,
+    /// Filled quantity (defaults to the whole order); the rest is re-armed as an open order
+    pub quantity: Option<f64>,
+    #[serde(default)]
+    pub fees: f64,
+    /// Defaults to when the order was triggered, or now
+    pub timestamp: Option<DateTime<Utc>>,
+}
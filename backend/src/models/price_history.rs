@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::account::deserialize_optional_text;
+use crate::models::transaction::deserialize_optional_date;
+
+pub const PRICE_HISTORY_COLLECTION: &str = "asset_price_history";
+
+/// One recorded price of a symbol, written whenever a fresh price is fetched and by the
+/// price_history job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricePoint {
+    pub symbol: String,
+    pub asset_type: String,
+    #[serde(default, deserialize_with = "deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub market: Option<String>,
+    pub price: f64,
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    pub currency: Option<String>,
+    /// Provider that produced the price (see `PriceEntry::source`)
+    #[serde(default, deserialize_with = "deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_date")]
+    pub recorded_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::transaction::deserialize_optional_date;
+
+pub const SESSIONS_COLLECTION: &str = "sessions";
+
+/// A login: one issued token and the device it was issued to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub user_id: String,
+    /// Readable device, e.g. "Chrome on Windows"
+    #[serde(default)]
+    pub device: String,
+    #[serde(default)]
+    pub user_agent: String,
+    #[serde(default)]
+    pub ip: String,
+    /// ISO country code from the proxy's geo header (empty when unknown)
+    #[serde(default)]
+    pub country: String,
+    /// User token_version the token was issued with; older sessions were logged out
+    #[serde(default)]
+    pub token_version: i32,
+    #[serde(default, deserialize_with = "deserialize_optional_date", skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The session of the token making the request
+    #[serde(default)]
+    pub current: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+}
+
+impl Session {
+    /// Still usable: not expired and not invalidated by a logout-all or password change
+    pub fn is_active(&self, token_version: i32, now: DateTime<Utc>) -> bool {
+        self.token_version == token_version && self.expires_at.is_none_or(|at| at > now)
+    }
+}
+
+/// Device details captured when a token is issued
+#[derive(Debug, Clone, Default)]
+pub struct LoginContext {
+    pub user_agent: String,
+    pub ip: Option<String>,
+    pub country: Option<String>,
+}
+
+impl LoginContext {
+    /// Browser and OS named in the user agent, e.g. "Firefox on Linux"
+    pub fn device(&self) -> String {
+        device_label(&self.user_agent)
+    }
+}
+
+/// Summarise a User-Agent as "<browser> on <os>"; order matters since most browsers
+/// also claim to be Safari/Chrome
+pub fn device_label(user_agent: &str) -> String {
+    if user_agent.trim().is_empty() {
+        return "Unknown device".to_string();
+    }
+    let browser = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+        ("curl/", "curl"),
+        ("okhttp", "Android app"),
+        ("Dart/", "Mobile app"),
+    ]
+    .iter()
+    .find(|(marker, _)| user_agent.contains(marker))
+    .map(|(_, name)| *name)
+    .unwrap_or("Unknown browser");
+    let os = [
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Android", "Android"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("CrOS", "ChromeOS"),
+        ("Linux", "Linux"),
+    ]
+    .iter()
+    .find(|(marker, _)| user_agent.contains(marker))
+    .map(|(_, name)| *name);
+
+    match os {
+        Some(os) => format!("{} on {}", browser, os),
+        None => browser.to_string(),
+    }
+}
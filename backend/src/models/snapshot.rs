@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use crate::services::exchange_rate::ExchangeRate;
+
+/// Daily portfolio snapshot written by the portfolio_snapshot job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioSnapshot {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub date: String,
+    #[serde(default)]
+    pub total_invested: f64,
+    #[serde(default)]
+    pub total_current_value: f64,
+    #[serde(default)]
+    pub total_unrealized_pnl: f64,
+    #[serde(default)]
+    pub total_unrealized_pnl_percent: f64,
+    #[serde(default)]
+    pub total_realized_pnl: f64,
+    #[serde(default)]
+    pub assets_count: serde_json::Value, // Flexible type - can be number or null
+    #[serde(default)]
+    pub currency: String,
+    #[serde(default)]
+    pub assets: Option<serde_json::Value>,
+    /// Exchange rates applied when the snapshot was taken
+    #[serde(default, deserialize_with = "deserialize_null_as_empty")]
+    pub conversions: Vec<ExchangeRate>,
+    // Catch any other fields from PocketBase
+    #[serde(flatten)]
+    pub extra: std::collections::HashMap<String, serde_json::Value>,
+}
+
+impl PortfolioSnapshot {
+    /// Calendar day (YYYY-MM-DD) of the snapshot
+    pub fn day(&self) -> &str {
+        self.date.get(..10).unwrap_or(&self.date)
+    }
+}
+
+fn deserialize_null_as_empty<'de, D>(deserializer: D) -> Result<Vec<ExchangeRate>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<Vec<ExchangeRate>>::deserialize(deserializer)?.unwrap_or_default())
+}
@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::account::deserialize_optional_text;
+use crate::models::transaction::{deserialize_optional_date, CreateTransactionRequest, Market};
+
+pub const STAGED_TRADES_COLLECTION: &str = "staged_trades";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StagedTradeStatus {
+    /// Waiting for the user to review it
+    #[default]
+    Pending,
+    Imported,
+    /// Left out by the user; kept so the next sync doesn't stage it again
+    Dismissed,
+}
+
+/// An exchange trade pulled with a user's key, held for review before it becomes transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StagedTrade {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub exchange_key_id: String,
+    pub exchange: Market,
+    /// Exchange pair, e.g. "ETHUSDT"
+    pub pair: String,
+    /// The exchange's trade id; unique per key and pair
+    pub trade_id: u64,
+    #[serde(default, deserialize_with = "deserialize_optional_date", skip_serializing_if = "Option::is_none")]
+    pub traded_at: Option<DateTime<Utc>>,
+    /// Transactions the trade becomes: the base asset leg, the quote asset leg and any fee
+    #[serde(default)]
+    pub transactions: Vec<CreateTransactionRequest>,
+    /// Why the trade can't be imported as is, e.g. no USD price for the quote asset
+    #[serde(default)]
+    pub errors: Vec<String>,
+    #[serde(default)]
+    pub status: StagedTradeStatus,
+    #[serde(default, deserialize_with = "deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub import_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+}
+
+/// Staged trades to import or dismiss; all pending ones when `ids` is empty
+#[derive(Debug, Default, Deserialize)]
+pub struct StagedTradesRequest {
+    #[serde(default)]
+    pub ids: Vec<String>,
+}
@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// PocketBase collection holding the change feed
+pub const SYNC_EVENTS_COLLECTION: &str = "sync_events";
+
+/// Kind of record a sync event describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncEntity {
+    Transaction,
+    Account,
+    /// Daily portfolio snapshot (valuation at that day's prices)
+    Snapshot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncOp {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// One change in a user's data, ordered by `seq`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncEvent {
+    /// Monotonic across all users; pass the last one seen as the next cursor
+    pub seq: u64,
+    #[serde(skip_serializing)]
+    pub user_id: String,
+    pub entity: SyncEntity,
+    pub entity_id: String,
+    pub op: SyncOp,
+    /// The record after the change; null for deletions
+    #[serde(default)]
+    pub data: serde_json::Value,
+    #[serde(default, deserialize_with = "crate::models::transaction::deserialize_optional_date")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+}
+
+/// A page of the events feed
+#[derive(Debug, Serialize)]
+pub struct EventsPage {
+    pub events: Vec<SyncEvent>,
+    /// Cursor for the next request
+    pub next_cursor: u64,
+    /// More events are waiting past `next_cursor`
+    pub has_more: bool,
+    /// The cursor cannot be continued from (missing or not issued by this server): re-fetch
+    /// the full lists, then poll from `next_cursor`
+    pub reset: bool,
+}
@@ -1,9 +1,10 @@
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case", try_from = "String")]
 pub enum AssetType {
     Stock,           // Thai stocks (SET)
     Tfex,            // Thai Futures Exchange
@@ -11,6 +12,7 @@ pub enum AssetType {
     ForeignStock,    // Foreign stocks (US, EU, etc.)
     Gold,            // Gold (XAU)
     Commodity,       // Other commodities
+    Cash,            // Bank / fixed deposits (symbol is the currency code)
 }
 
 impl std::fmt::Display for AssetType {
@@ -22,13 +24,49 @@ impl std::fmt::Display for AssetType {
             AssetType::ForeignStock => write!(f, "foreign_stock"),
             AssetType::Gold => write!(f, "gold"),
             AssetType::Commodity => write!(f, "commodity"),
+            AssetType::Cash => write!(f, "cash"),
         }
     }
 }
 
+impl AssetType {
+    pub const ALL: [AssetType; 7] = [
+        AssetType::Stock,
+        AssetType::Tfex,
+        AssetType::Crypto,
+        AssetType::ForeignStock,
+        AssetType::Gold,
+        AssetType::Commodity,
+        AssetType::Cash,
+    ];
+}
+
+impl std::str::FromStr for AssetType {
+    type Err = String;
+
+    /// Case-insensitive; also accepts "foreignstock"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_lowercase();
+        if normalized == "foreignstock" {
+            return Ok(AssetType::ForeignStock);
+        }
+        Self::ALL.into_iter()
+            .find(|t| t.to_string() == normalized)
+            .ok_or_else(|| format!("Invalid asset type: {}. Must be one of: {}", s, allowed_values(&Self::ALL)))
+    }
+}
+
+impl TryFrom<String> for AssetType {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 /// Market/Exchange categorization
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case", try_from = "String")]
 pub enum Market {
     // Thai markets
     Set,             // Stock Exchange of Thailand
@@ -98,7 +136,42 @@ impl std::fmt::Display for Market {
     }
 }
 
+impl std::str::FromStr for Market {
+    type Err = String;
+
+    /// Case-insensitive, so both the stored form ("set") and the display form ("SET") parse
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_uppercase();
+        Self::ALL.into_iter()
+            .find(|m| m.to_string() == normalized)
+            .ok_or_else(|| format!("Invalid market: {}. Must be one of: {}", s, allowed_values(&Self::ALL)))
+    }
+}
+
+impl TryFrom<String> for Market {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Comma-separated lowercase values, as accepted by the API
+fn allowed_values<T: std::fmt::Display>(values: &[T]) -> String {
+    values.iter().map(|v| v.to_string().to_lowercase()).collect::<Vec<_>>().join(", ")
+}
+
 impl Market {
+    pub const ALL: [Market; 23] = [
+        Market::Set, Market::Mai, Market::Tfex,
+        Market::Nyse, Market::Nasdaq, Market::Amex,
+        Market::Lse, Market::Euronext, Market::Xetra,
+        Market::Hkex, Market::Tse, Market::Sgx, Market::Krx,
+        Market::Binance, Market::Coinbase, Market::Bitkub, Market::Htx, Market::Okx, Market::Kucoin,
+        Market::Comex, Market::Lbma,
+        Market::Local, Market::Other,
+    ];
+
     /// Get the default currency for this market
     pub fn default_currency(&self) -> &str {
         match self {
@@ -149,6 +222,9 @@ pub struct Transaction {
     pub price: f64,
     #[serde(default)]
     pub fees: f64,
+    /// On-chain gas / network fee in the transaction's currency, kept apart from trading `fees`
+    #[serde(default)]
+    pub network_fee: f64,
     pub timestamp: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub market: Option<Market>,
@@ -166,13 +242,22 @@ pub struct Transaction {
     pub initial_margin: Option<f64>,   // Actual money used for futures
     #[serde(skip_serializing_if = "Option::is_none")]
     pub unit: Option<String>,          // Unit of measurement (e.g. "baht", "oz", "gram")
+    /// Values of the user's custom fields, keyed by definition key
+    #[serde(default, deserialize_with = "deserialize_null_as_empty_map")]
+    pub custom_fields: BTreeMap<String, serde_json::Value>,
+    /// Set by bulk delete; soft-deleted transactions are hidden until restored
+    #[serde(default, deserialize_with = "deserialize_optional_date")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Bulk delete batch, used to restore the whole batch at once
+    #[serde(default, deserialize_with = "deserialize_empty_as_none")]
+    pub deletion_batch: Option<String>,
     #[serde(default, skip_serializing)]
     pub created_at: DateTime<Utc>,
     #[serde(default, skip_serializing)]
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTransactionRequest {
     pub asset_type: AssetType,
     pub symbol: String,
@@ -182,6 +267,8 @@ pub struct CreateTransactionRequest {
     pub price: f64,
     #[serde(default)]
     pub fees: f64,
+    #[serde(default)]
+    pub network_fee: f64,
     #[serde(default = "Utc::now")]
     pub timestamp: DateTime<Utc>,
     pub market: Option<Market>,
@@ -193,9 +280,11 @@ pub struct CreateTransactionRequest {
     pub leverage: Option<f64>,
     pub initial_margin: Option<f64>,
     pub unit: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_fields: BTreeMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct UpdateTransactionRequest {
     pub asset_type: Option<AssetType>,
     pub symbol: Option<String>,
@@ -204,6 +293,7 @@ pub struct UpdateTransactionRequest {
     pub quantity: Option<f64>,
     pub price: Option<f64>,
     pub fees: Option<f64>,
+    pub network_fee: Option<f64>,
     pub timestamp: Option<DateTime<Utc>>,
     pub market: Option<Market>,
     pub currency: Option<String>,
@@ -213,8 +303,13 @@ pub struct UpdateTransactionRequest {
     pub leverage: Option<f64>,
     pub initial_margin: Option<f64>,
     pub unit: Option<String>,
+    /// Merged into existing values; a null value removes the field
+    pub custom_fields: Option<BTreeMap<String, serde_json::Value>>,
 }
 
+/// Tag on the transactions the interest accrual job books
+pub const INTEREST_TAG: &str = "interest";
+
 impl Transaction {
     pub fn new(req: CreateTransactionRequest) -> Self {
         Self::new_with_user(req, String::new())
@@ -236,6 +331,7 @@ impl Transaction {
             quantity: req.quantity,
             price: req.price,
             fees: req.fees,
+            network_fee: req.network_fee,
             timestamp: req.timestamp,
             market: req.market,
             currency,
@@ -245,10 +341,23 @@ impl Transaction {
             leverage: req.leverage,
             initial_margin: req.initial_margin,
             unit: req.unit,
+            custom_fields: req.custom_fields,
+            deleted_at: None,
+            deletion_batch: None,
             created_at: now,
             updated_at: now,
         }
     }
+
+    /// Trading and network fees together, as they count toward cost basis and proceeds
+    pub fn all_fees(&self) -> f64 {
+        self.fees + self.network_fee
+    }
+
+    /// Booked by the interest accrual job: a return on the account, not money paid in
+    pub fn is_interest_credit(&self) -> bool {
+        self.tags.iter().any(|t| t == INTEREST_TAG)
+    }
 }
 
 /// Generate a PocketBase compatible ID (15 chars, a-z0-9)
@@ -264,10 +373,40 @@ fn generate_pb_id() -> String {
         .collect()
 }
 
-fn deserialize_null_as_empty<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+pub(crate) fn deserialize_null_as_empty<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
     let opt = Option::<Vec<String>>::deserialize(deserializer)?;
     Ok(opt.unwrap_or_default())
 }
+
+fn deserialize_null_as_empty_map<'de, D>(deserializer: D) -> Result<BTreeMap<String, serde_json::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let opt = Option::<BTreeMap<String, serde_json::Value>>::deserialize(deserializer)?;
+    Ok(opt.unwrap_or_default())
+}
+
+/// PocketBase returns "" for unset date fields
+pub(crate) fn deserialize_optional_date<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let opt = Option::<String>::deserialize(deserializer)?;
+    match opt.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => s.parse::<DateTime<Utc>>()
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+fn deserialize_empty_as_none<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let opt = Option::<String>::deserialize(deserializer)?;
+    Ok(opt.filter(|s| !s.is_empty()))
+}
@@ -86,6 +86,9 @@ pub struct User {
     /// Token version for invalidating old sessions (increment on logout/password change)
     #[serde(default)]
     pub token_version: i32,
+    /// Registered locally but the email address has not been confirmed yet
+    #[serde(default)]
+    pub pending_verification: bool,
 }
 
 /// Generate a PocketBase compatible ID (15 chars, a-z0-9)
@@ -114,6 +117,7 @@ impl User {
             created_at: now,
             updated_at: now,
             token_version: 0,
+            pending_verification: false,
         }
     }
     
@@ -137,6 +141,7 @@ pub struct UserResponse {
     pub avatar_url: Option<String>,
     pub role: String,
     pub has_local_password: bool,
+    pub email_verified: bool,
     pub created_at: DateTime<Utc>,
 }
 
@@ -149,6 +154,7 @@ impl From<&User> for UserResponse {
             avatar_url: user.avatar_url.clone(),
             role: user.role.clone(),
             has_local_password: user.local_password_hash.is_some(),
+            email_verified: !user.pending_verification,
             created_at: user.created_at,
         }
     }
@@ -226,6 +232,12 @@ pub struct Claims {
     /// Token version (must match user's current version)
     #[serde(default)]
     pub token_version: i32,
+    /// Guest session: every write request is rejected
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+    /// Session record the token was issued for (see /auth/sessions)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
 }
 
 /// Google user info from OAuth
@@ -246,6 +258,9 @@ pub struct LocalAuthRequest {
     pub email: String,
     pub password: String,
     pub name: Option<String>,
+    /// Required when registration is invitation-only
+    #[serde(default)]
+    pub invite_code: Option<String>,
 }
 
 /// Auth response with token
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use crate::models::account::deserialize_optional_text;
+
+pub const USER_SETTINGS_COLLECTION: &str = "user_settings";
+
+/// A user's preferences, one record per user. Unset fields fall back to the `locale` preset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserSettings {
+    #[serde(default, skip_serializing)]
+    pub id: String,
+    #[serde(default, skip_serializing)]
+    pub user_id: String,
+    /// Language tag picking the export defaults, e.g. "th-TH" or "de-DE"
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    pub locale: Option<String>,
+    /// "point" or "comma"; with a decimal comma CSV fields are separated by semicolons
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    pub decimal_separator: Option<String>,
+    /// "gregorian" or "buddhist" (year + 543)
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    pub calendar: Option<String>,
+    /// Built from YYYY, YY, MM, M, DD and D with " / - . ," between them, e.g. "DD/MM/YYYY"
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    pub date_pattern: Option<String>,
+    /// UTC offset the digest time is in, e.g. "+07:00"; Bangkok time when unset
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    pub timezone: Option<String>,
+    /// Local "HH:MM" to send the daily digest at; no digest when unset
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    pub digest_time: Option<String>,
+    /// Local date (YYYY-MM-DD) of the last digest sent
+    #[serde(default, skip_serializing, deserialize_with = "deserialize_optional_text")]
+    pub digest_sent_on: Option<String>,
+}
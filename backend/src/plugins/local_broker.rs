@@ -0,0 +1,81 @@
+//! Example plugin (feature `plugin-local-broker`): prices from a broker's own quote API at
+//! LOCAL_BROKER_API_URL, which answers `GET {url}/quotes/{symbol}` with
+//! `{"price": 12.3, "currency": "THB"}`. Add an api_providers record of type "local_broker"
+//! to use it for Thai stocks and TFEX.
+
+use std::sync::Arc;
+use futures_util::future::BoxFuture;
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::price_service::PriceEntry;
+use crate::services::providers::{PriceProvider, ProviderCall, ProviderClient};
+use super::Plugin;
+
+const RATE_LIMIT_KEY: &str = "local_broker";
+
+pub struct LocalBroker {
+    api_url: Option<String>,
+}
+
+impl LocalBroker {
+    pub fn from_env() -> Self {
+        let api_url = std::env::var("LOCAL_BROKER_API_URL").ok()
+            .map(|url| url.trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+        Self { api_url }
+    }
+}
+
+impl Plugin for LocalBroker {
+    fn name(&self) -> &'static str {
+        "local_broker"
+    }
+
+    fn description(&self) -> &'static str {
+        "Thai stock and TFEX quotes from a broker API (LOCAL_BROKER_API_URL)"
+    }
+
+    fn price_providers(&self) -> Vec<Arc<dyn PriceProvider>> {
+        match &self.api_url {
+            Some(url) => vec![Arc::new(LocalBrokerQuotes { api_url: url.clone() })],
+            None => Vec::new(),
+        }
+    }
+}
+
+struct LocalBrokerQuotes {
+    api_url: String,
+}
+
+impl PriceProvider for LocalBrokerQuotes {
+    fn provider_type(&self) -> &'static str {
+        "local_broker"
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::Stock, AssetType::Tfex]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(async move {
+            let url = format!("{}/quotes/{}", self.api_url, urlencoding::encode(&symbol.to_uppercase()));
+            let call = ProviderCall::new("Local broker", RATE_LIMIT_KEY, symbol);
+            let response = client.get_json(call, url, &[]).await?;
+            let Some(price) = response.data.get("price").and_then(|v| v.as_f64()).filter(|p| *p > 0.0) else {
+                return Err(response.unparsable());
+            };
+            let currency = response.data.get("currency").and_then(|v| v.as_str()).unwrap_or("THB");
+            Ok(response.priced(price, currency))
+        })
+    }
+}
@@ -0,0 +1,146 @@
+//! Extensions compiled into the backend.
+//!
+//! A [`Plugin`] bundles extra price providers, sections appended to the daily digest and
+//! transformers applied to outgoing Email/Telegram/LINE messages, so support for e.g. a local
+//! broker's API can live in its own module here instead of a fork. Each plugin module sits
+//! behind a Cargo feature named `plugin-<name>` and is listed in [`compiled_plugins`];
+//! DISABLED_PLUGINS leaves one unloaded without a rebuild. Plugins read their own settings
+//! from the environment.
+//!
+//! Price providers are registered under their `provider_type` like the built-in ones (replacing
+//! a built-in of the same type) and are used once an api_providers record of that type exists.
+
+#[cfg(feature = "plugin-local-broker")]
+pub mod local_broker;
+
+use std::sync::Arc;
+use chrono::NaiveDate;
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::NotificationChannel;
+use crate::services::providers::PriceProvider;
+use crate::services::PocketBaseClient;
+
+/// A bundle of extensions; every part is optional
+pub trait Plugin: Send + Sync {
+    /// Unique name, matched against DISABLED_PLUGINS
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str {
+        ""
+    }
+
+    fn price_providers(&self) -> Vec<Arc<dyn PriceProvider>> {
+        Vec::new()
+    }
+
+    fn report_sections(&self) -> Vec<Arc<dyn ReportSection>> {
+        Vec::new()
+    }
+
+    fn message_transformers(&self) -> Vec<Arc<dyn MessageTransformer>> {
+        Vec::new()
+    }
+}
+
+/// What a report section is rendered for
+pub struct ReportContext<'a> {
+    pub db: &'a PocketBaseClient,
+    pub user_id: &'a str,
+    /// The user's local date the report is for
+    pub date: NaiveDate,
+}
+
+/// Extra block of the daily digest, rendered per user after the built-in lines
+pub trait ReportSection: Send + Sync {
+    /// Heading printed above the section
+    fn title(&self) -> &'static str;
+
+    /// Plain-text body; None leaves the section out for this user
+    fn render<'a>(&'a self, ctx: &'a ReportContext<'a>) -> BoxFuture<'a, Result<Option<String>, AppError>>;
+}
+
+/// A notification about to leave through an external channel
+#[derive(Debug, Clone)]
+pub struct OutgoingMessage {
+    pub channel: NotificationChannel,
+    pub user_id: String,
+    pub title: String,
+    pub body: String,
+}
+
+/// Rewrites outgoing notifications, e.g. to reformat them for a relay or redact amounts
+pub trait MessageTransformer: Send + Sync {
+    /// The message to send instead; None drops it for this channel
+    fn transform(&self, message: OutgoingMessage) -> Option<OutgoingMessage>;
+}
+
+/// Plugins compiled into this build, enabled or not
+fn compiled_plugins() -> Vec<Arc<dyn Plugin>> {
+    vec![
+        #[cfg(feature = "plugin-local-broker")]
+        Arc::new(local_broker::LocalBroker::from_env()),
+    ]
+}
+
+/// A loaded plugin and what it contributes, for GET /api/admin/plugins
+#[derive(Debug, Serialize)]
+pub struct PluginInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub price_providers: Vec<&'static str>,
+    pub report_sections: Vec<&'static str>,
+    pub message_transformers: usize,
+}
+
+/// The loaded plugins
+#[derive(Default)]
+pub struct Plugins {
+    plugins: Vec<Arc<dyn Plugin>>,
+}
+
+impl Plugins {
+    /// Compiled-in plugins minus DISABLED_PLUGINS
+    pub fn load(config: &Config) -> Self {
+        let mut loaded = Self::default();
+        for plugin in compiled_plugins() {
+            if config.disabled_plugins.iter().any(|name| name == plugin.name()) {
+                tracing::info!("🔌 Plugin {} disabled by DISABLED_PLUGINS", plugin.name());
+            } else {
+                loaded.register(plugin);
+            }
+        }
+        loaded
+    }
+
+    /// Add a plugin, replacing any loaded under the same name
+    pub fn register(&mut self, plugin: Arc<dyn Plugin>) {
+        tracing::info!("🔌 Loaded plugin {}", plugin.name());
+        self.plugins.retain(|p| p.name() != plugin.name());
+        self.plugins.push(plugin);
+    }
+
+    pub fn price_providers(&self) -> Vec<Arc<dyn PriceProvider>> {
+        self.plugins.iter().flat_map(|p| p.price_providers()).collect()
+    }
+
+    pub fn report_sections(&self) -> Vec<Arc<dyn ReportSection>> {
+        self.plugins.iter().flat_map(|p| p.report_sections()).collect()
+    }
+
+    pub fn message_transformers(&self) -> Vec<Arc<dyn MessageTransformer>> {
+        self.plugins.iter().flat_map(|p| p.message_transformers()).collect()
+    }
+
+    pub fn info(&self) -> Vec<PluginInfo> {
+        self.plugins.iter().map(|p| PluginInfo {
+            name: p.name(),
+            description: p.description(),
+            price_providers: p.price_providers().iter().map(|provider| provider.provider_type()).collect(),
+            report_sections: p.report_sections().iter().map(|section| section.title()).collect(),
+            message_transformers: p.message_transformers().len(),
+        }).collect()
+    }
+}
@@ -1,16 +1,19 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use chrono::{DateTime, Utc, Duration};
+use chrono::{DateTime, NaiveDateTime, Utc, Duration};
 
 use crate::config::Config;
 use crate::error::AppError;
 use crate::models::{
-    AlertRule, AlertType, Comparison, NotificationChannel,
+    AlertRule, AlertType, AssetType, NotificationChannel,
     CreateAlertRequest, UpdateAlertRequest,
 };
 use crate::services::{PocketBaseClient, NotificationService, PriceService};
 
+/// Longest allowed snooze (30 days)
+const MAX_SNOOZE_MINUTES: i64 = 30 * 24 * 60;
+
 /// Alert service for managing alert rules and evaluation
 #[derive(Clone)]
 pub struct AlertService {
@@ -54,7 +57,7 @@ impl AlertService {
         let token = self.pb_client.get_token().await;
         let url = format!("{}/api/collections/alerts/records?perPage=500", self.config.pocketbase_url);
 
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let response = client
             .get(&url)
             .header("Authorization", token)
@@ -99,16 +102,19 @@ impl AlertService {
             user_id: item.get("user_id")?.as_str()?.to_string(),
             name: item.get("name")?.as_str()?.to_string(),
             alert_type: alert_type_str.parse().ok()?,
-            symbol: item.get("symbol").and_then(|s| s.as_str()).map(|s| s.to_string()),
+            symbol: item.get("symbol").and_then(|s| s.as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string()),
+            asset_type: item.get("asset_type").and_then(|s| s.as_str()).and_then(|s| s.parse().ok()),
+            reference_price: item.get("reference_price").and_then(|n| n.as_f64()).filter(|p| *p > 0.0),
             threshold: item.get("threshold")?.as_f64()?,
             comparison: comparison_str.parse().ok()?,
             channels,
             is_active: item.get("is_active").and_then(|b| b.as_bool()).unwrap_or(true),
             cooldown_minutes: item.get("cooldown_minutes").and_then(|n| n.as_i64()).unwrap_or(60) as i32,
-            last_triggered: item.get("last_triggered")
-                .and_then(|s| s.as_str())
-                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-                .map(|dt| dt.with_timezone(&Utc)),
+            hysteresis_percent: item.get("hysteresis_percent").and_then(|n| n.as_f64()).unwrap_or(0.0),
+            awaiting_reset: item.get("awaiting_reset").and_then(|b| b.as_bool()).unwrap_or(false),
+            snoozed_until: parse_pb_datetime(item, "snoozed_until"),
+            muted: item.get("muted").and_then(|b| b.as_bool()).unwrap_or(false),
+            last_triggered: parse_pb_datetime(item, "last_triggered"),
             created: Utc::now(),
             updated: Utc::now(),
         })
@@ -122,17 +128,46 @@ impl AlertService {
         user_id: &str,
         req: CreateAlertRequest,
     ) -> Result<AlertRule, AppError> {
+        let symbol = req.symbol
+            .map(|s| s.trim().to_uppercase())
+            .filter(|s| !s.is_empty());
+        let mut asset_type = req.asset_type;
+        let mut reference_price = None;
+        if req.alert_type.is_price() {
+            let symbol = symbol.as_deref()
+                .ok_or_else(|| AppError::BadRequest("Symbol required for price alerts".into()))?;
+            if asset_type.is_none() {
+                asset_type = self.held_asset_type(user_id, symbol).await;
+            }
+            let asset_type = asset_type.as_ref().ok_or_else(|| AppError::BadRequest(format!(
+                "asset_type is required for {}, which has no transactions", symbol
+            )))?;
+            if req.alert_type == AlertType::PriceChangePercent {
+                if req.threshold <= 0.0 {
+                    return Err(AppError::BadRequest("threshold must be a positive percentage".into()));
+                }
+                let entry = self.price_service.get_price(symbol, asset_type, None).await?;
+                reference_price = Some(entry.price);
+            }
+        }
+
         let alert = AlertRule {
             id: uuid::Uuid::new_v4().to_string(),
             user_id: user_id.to_string(),
             name: req.name,
             alert_type: req.alert_type,
-            symbol: req.symbol,
+            symbol,
+            asset_type,
+            reference_price,
             threshold: req.threshold,
             comparison: req.comparison,
             channels: req.channels,
             is_active: true,
             cooldown_minutes: req.cooldown_minutes,
+            hysteresis_percent: req.hysteresis_percent.max(0.0),
+            awaiting_reset: false,
+            snoozed_until: None,
+            muted: false,
             last_triggered: None,
             created: Utc::now(),
             updated: Utc::now(),
@@ -144,7 +179,7 @@ impl AlertService {
 
         let channels_str: Vec<String> = alert.channels.iter().map(|c| c.to_string()).collect();
 
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let response = client
             .post(&url)
             .header("Authorization", token)
@@ -153,11 +188,14 @@ impl AlertService {
                 "name": alert.name,
                 "alert_type": alert.alert_type.to_string(),
                 "symbol": alert.symbol,
+                "asset_type": alert.asset_type.as_ref().map(|t| t.to_string()),
+                "reference_price": alert.reference_price,
                 "threshold": alert.threshold,
                 "comparison": alert.comparison.to_string(),
                 "channels": channels_str,
                 "is_active": alert.is_active,
                 "cooldown_minutes": alert.cooldown_minutes,
+                "hysteresis_percent": alert.hysteresis_percent,
             }))
             .send()
             .await
@@ -238,6 +276,9 @@ impl AlertService {
         if let Some(cooldown_minutes) = req.cooldown_minutes {
             alert.cooldown_minutes = cooldown_minutes;
         }
+        if let Some(hysteresis_percent) = req.hysteresis_percent {
+            alert.hysteresis_percent = hysteresis_percent.max(0.0);
+        }
         alert.updated = Utc::now();
 
         // Update in PocketBase
@@ -246,7 +287,7 @@ impl AlertService {
 
         let channels_str: Vec<String> = alert.channels.iter().map(|c| c.to_string()).collect();
 
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let response = client
             .patch(&url)
             .header("Authorization", token)
@@ -257,6 +298,7 @@ impl AlertService {
                 "channels": channels_str,
                 "is_active": alert.is_active,
                 "cooldown_minutes": alert.cooldown_minutes,
+                "hysteresis_percent": alert.hysteresis_percent,
             }))
             .send()
             .await
@@ -286,7 +328,7 @@ impl AlertService {
         let token = self.pb_client.get_token().await;
         let url = format!("{}/api/collections/alerts/records/{}", self.config.pocketbase_url, alert_id);
 
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let response = client
             .delete(&url)
             .header("Authorization", token)
@@ -304,9 +346,94 @@ impl AlertService {
         Ok(())
     }
 
+    // ==================== Snooze / Mute ====================
+
+    /// Snooze an alert for `minutes`, or mute it until unsnoozed when `minutes` is None
+    pub async fn snooze_alert(
+        &self,
+        alert_id: &str,
+        user_id: &str,
+        minutes: Option<i64>,
+    ) -> Result<AlertRule, AppError> {
+        let (snoozed_until, muted) = match minutes {
+            Some(m) if !(1..=MAX_SNOOZE_MINUTES).contains(&m) => {
+                return Err(AppError::BadRequest(format!(
+                    "Snooze minutes must be between 1 and {}", MAX_SNOOZE_MINUTES
+                )));
+            }
+            Some(m) => (Some(Utc::now() + Duration::minutes(m)), false),
+            None => (None, true),
+        };
+        let alert = self.set_silence(alert_id, user_id, snoozed_until, muted).await?;
+        match alert.snoozed_until {
+            Some(until) => tracing::info!("😴 Alert '{}' snoozed until {}", alert.name, until),
+            None => tracing::info!("🔇 Alert '{}' muted", alert.name),
+        }
+        Ok(alert)
+    }
+
+    /// Clear both snooze and mute
+    pub async fn unsnooze_alert(&self, alert_id: &str, user_id: &str) -> Result<AlertRule, AppError> {
+        let alert = self.set_silence(alert_id, user_id, None, false).await?;
+        tracing::info!("🔔 Alert '{}' unsnoozed", alert.name);
+        Ok(alert)
+    }
+
+    async fn set_silence(
+        &self,
+        alert_id: &str,
+        user_id: &str,
+        snoozed_until: Option<DateTime<Utc>>,
+        muted: bool,
+    ) -> Result<AlertRule, AppError> {
+        let mut cache = self.alerts_cache.write().await;
+
+        let alert = cache.get_mut(alert_id)
+            .ok_or_else(|| AppError::NotFound(format!("Alert {} not found", alert_id)))?;
+
+        // Verify ownership
+        if alert.user_id != user_id {
+            return Err(AppError::Unauthorized("Not authorized to update this alert".into()));
+        }
+
+        self.patch_alert_record(alert_id, serde_json::json!({
+            "snoozed_until": snoozed_until.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            "muted": muted,
+        })).await?;
+
+        alert.snoozed_until = snoozed_until;
+        alert.muted = muted;
+        alert.updated = Utc::now();
+        Ok(alert.clone())
+    }
+
+    /// PATCH fields of an alert record in PocketBase
+    async fn patch_alert_record(&self, alert_id: &str, body: serde_json::Value) -> Result<(), AppError> {
+        let token = self.pb_client.get_token().await;
+        let url = format!("{}/api/collections/alerts/records/{}", self.config.pocketbase_url, alert_id);
+
+        let client = self.pb_client.http();
+        let response = client
+            .patch(&url)
+            .header("Authorization", token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to update alert: {}", e)))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::Internal(format!("PocketBase error: {}", error_text)));
+        }
+        Ok(())
+    }
+
     // ==================== Alert Evaluation ====================
 
-    /// Evaluate all active alerts (called by job scheduler)
+    /// Evaluate all active alerts (called by job scheduler).
+    ///
+    /// An alert that fired stays quiet until its value moves back past the threshold by
+    /// `hysteresis_percent`, so a price hovering around the threshold fires once, not every run.
     pub async fn evaluate_all_alerts(&self) -> Result<serde_json::Value, String> {
         let cache = self.alerts_cache.read().await;
         let active_alerts: Vec<AlertRule> = cache
@@ -316,104 +443,119 @@ impl AlertService {
             .collect();
         drop(cache);
 
+        let now = Utc::now();
         let mut triggered_count = 0;
         let mut evaluated_count = 0;
+        let mut silenced_count = 0;
+        let mut rearmed_count = 0;
 
         for alert in active_alerts {
-            // Check cooldown
-            if let Some(last_triggered) = alert.last_triggered {
-                let cooldown = Duration::minutes(alert.cooldown_minutes as i64);
-                if Utc::now() - last_triggered < cooldown {
-                    continue; // Still in cooldown
-                }
+            if alert.is_silenced(now) {
+                silenced_count += 1;
+                continue;
+            }
+            if alert.in_cooldown(now) {
+                continue;
             }
 
             evaluated_count += 1;
 
-            // Evaluate the alert condition
-            match self.evaluate_alert(&alert).await {
-                Ok(Some(current_value)) => {
-                    // Alert triggered!
-                    tracing::info!("Alert '{}' triggered with value {}", alert.name, current_value);
-                    
-                    // Send notifications
-                    if let Err(e) = self.notification_service.send(&alert.user_id, &alert, current_value).await {
-                        tracing::error!("Failed to send notification for alert '{}': {}", alert.name, e);
-                    }
-
-                    // Update last_triggered
-                    self.update_last_triggered(&alert.id).await;
-                    triggered_count += 1;
-                }
-                Ok(None) => {
-                    // Condition not met
-                }
+            let current_value = match self.current_value(&alert).await {
+                Ok(Some(value)) => value,
+                Ok(None) => continue,
                 Err(e) => {
                     tracing::error!("Error evaluating alert '{}': {}", alert.name, e);
+                    continue;
                 }
+            };
+
+            if alert.awaiting_reset {
+                if alert.has_reset(current_value) {
+                    tracing::info!("🔁 Alert '{}' re-armed at {}", alert.name, current_value);
+                    self.set_awaiting_reset(&alert.id, false).await;
+                    rearmed_count += 1;
+                }
+                continue;
+            }
+
+            if !alert.is_met(current_value) {
+                continue;
+            }
+
+            // Alert triggered!
+            tracing::info!("Alert '{}' triggered with value {}", alert.name, current_value);
+
+            // Send notifications
+            match self.notification_service.send(&alert.user_id, &alert, current_value).await {
+                Ok(Some(_)) => triggered_count += 1,
+                Ok(None) => {}
+                Err(e) => tracing::error!("Failed to send notification for alert '{}': {}", alert.name, e),
             }
+
+            // Start cooldown and wait for the value to clear the hysteresis band
+            self.update_last_triggered(&alert.id).await;
         }
 
         Ok(serde_json::json!({
             "evaluated": evaluated_count,
             "triggered": triggered_count,
+            "silenced": silenced_count,
+            "rearmed": rearmed_count,
             "timestamp": Utc::now().to_rfc3339(),
         }))
     }
 
-    /// Evaluate a single alert, returns Some(current_value) if triggered
-    async fn evaluate_alert(&self, alert: &AlertRule) -> Result<Option<f64>, AppError> {
-        let current_value = match &alert.alert_type {
-            AlertType::PriceAbove | AlertType::PriceBelow => {
-                let symbol = alert.symbol.as_ref()
-                    .ok_or_else(|| AppError::BadRequest("Symbol required for price alerts".into()))?;
-                
-                // Get current price from price service
-                // For now, return None - will be implemented with proper price lookup
-                match self.get_current_price(symbol).await {
-                    Some(price) => price,
-                    None => return Ok(None),
-                }
+    /// Current value an alert is compared against, or None if it cannot be determined yet
+    async fn current_value(&self, alert: &AlertRule) -> Result<Option<f64>, AppError> {
+        match &alert.alert_type {
+            AlertType::PriceAbove | AlertType::PriceBelow => self.get_current_price(alert).await,
+            AlertType::PriceChangePercent => {
+                let Some(reference) = alert.reference_price else { return Ok(None) };
+                Ok(self.get_current_price(alert).await?
+                    .map(|price| (price / reference - 1.0) * 100.0))
             }
             AlertType::PnlThresholdPercent | AlertType::PnlThresholdAbsolute => {
                 // TODO: Calculate portfolio P&L
-                return Ok(None);
+                Ok(None)
             }
             AlertType::PortfolioChangePercent => {
                 // TODO: Calculate 24h portfolio change
-                return Ok(None);
+                Ok(None)
             }
             AlertType::DailyPnlReport => {
                 // Daily report is time-based, not value-based
-                return Ok(None);
+                Ok(None)
             }
-        };
+        }
+    }
 
-        // Check if condition is met
-        let triggered = match alert.comparison {
-            Comparison::Above => current_value >= alert.threshold,
-            Comparison::Below => current_value <= alert.threshold,
-            Comparison::Equals => (current_value - alert.threshold).abs() < 0.001,
+    /// Current price of a price alert's symbol. The price_fetch job evaluates alerts right
+    /// after refreshing prices, so this is normally served from the cache.
+    async fn get_current_price(&self, alert: &AlertRule) -> Result<Option<f64>, AppError> {
+        let symbol = alert.symbol.as_deref()
+            .ok_or_else(|| AppError::BadRequest("Symbol required for price alerts".into()))?;
+        let asset_type = match &alert.asset_type {
+            Some(asset_type) => asset_type.clone(),
+            // Alerts created before asset_type was stored
+            None => match self.held_asset_type(&alert.user_id, symbol).await {
+                Some(asset_type) => asset_type,
+                None => return Ok(None),
+            },
         };
-
-        if triggered {
-            Ok(Some(current_value))
-        } else {
-            Ok(None)
-        }
+        let entry = self.price_service.get_price(symbol, &asset_type, None).await?;
+        Ok(Some(entry.price).filter(|price| *price > 0.0))
     }
 
-    /// Get current price for a symbol
-    async fn get_current_price(&self, symbol: &str) -> Option<f64> {
-        // Try to get from price service cache
-        // This is a simplified implementation - would need proper integration
-        tracing::debug!("Getting price for symbol: {}", symbol);
-        
-        // For now, return None - actual implementation would check price_service
-        None
+    /// Asset type of the user's most recent transaction in a symbol
+    async fn held_asset_type(&self, user_id: &str, symbol: &str) -> Option<AssetType> {
+        let transactions = self.pb_client.list_transactions(user_id).await.ok()?;
+        transactions.into_iter()
+            .filter(|tx| tx.symbol.eq_ignore_ascii_case(symbol))
+            .max_by_key(|tx| tx.timestamp)
+            .map(|tx| tx.asset_type)
     }
 
-    /// Update last_triggered timestamp
+    /// Update last_triggered timestamp and hold the alert until its value resets
     async fn update_last_triggered(&self, alert_id: &str) {
         let now = Utc::now();
 
@@ -421,21 +563,30 @@ impl AlertService {
         let mut cache = self.alerts_cache.write().await;
         if let Some(alert) = cache.get_mut(alert_id) {
             alert.last_triggered = Some(now);
+            alert.awaiting_reset = true;
         }
+        drop(cache);
 
         // Update in PocketBase
-        let token = self.pb_client.get_token().await;
-        let url = format!("{}/api/collections/alerts/records/{}", self.config.pocketbase_url, alert_id);
+        let _ = self.patch_alert_record(alert_id, serde_json::json!({
+            "last_triggered": now.to_rfc3339(),
+            "awaiting_reset": true,
+        })).await;
+    }
 
-        let client = reqwest::Client::new();
-        let _ = client
-            .patch(&url)
-            .header("Authorization", token)
-            .json(&serde_json::json!({
-                "last_triggered": now.to_rfc3339(),
-            }))
-            .send()
-            .await;
+    /// Re-arm (or hold) an alert after its value moved back out of the hysteresis band
+    async fn set_awaiting_reset(&self, alert_id: &str, awaiting_reset: bool) {
+        let mut cache = self.alerts_cache.write().await;
+        if let Some(alert) = cache.get_mut(alert_id) {
+            alert.awaiting_reset = awaiting_reset;
+        }
+        drop(cache);
+
+        if let Err(e) = self.patch_alert_record(alert_id, serde_json::json!({
+            "awaiting_reset": awaiting_reset,
+        })).await {
+            tracing::warn!("⚠️ Failed to persist awaiting_reset for alert {}: {}", alert_id, e);
+        }
     }
 
     // ==================== Alert History ====================
@@ -444,15 +595,20 @@ impl AlertService {
     pub async fn get_alert_history(
         &self,
         user_id: &str,
+        alert_id: Option<&str>,
         limit: u32,
     ) -> Result<Vec<serde_json::Value>, AppError> {
         let token = self.pb_client.get_token().await;
+        let mut filter = format!("user_id='{}'", user_id);
+        if let Some(alert_id) = alert_id {
+            filter.push_str(&format!(" && alert_id='{}'", alert_id));
+        }
         let url = format!(
-            "{}/api/collections/alert_history/records?filter=(user_id='{}')&sort=-triggered_at&perPage={}",
-            self.config.pocketbase_url, user_id, limit
+            "{}/api/collections/alert_history/records?filter=({})&sort=-triggered_at&perPage={}",
+            self.config.pocketbase_url, urlencoding::encode(&filter), limit
         );
 
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let response = client
             .get(&url)
             .header("Authorization", token)
@@ -473,3 +629,15 @@ impl AlertService {
         Ok(data.items)
     }
 }
+
+/// Parse a PocketBase date field, which may be RFC 3339 or "YYYY-MM-DD HH:MM:SS.sssZ"
+fn parse_pb_datetime(item: &serde_json::Value, key: &str) -> Option<DateTime<Utc>> {
+    let value = item.get(key)?.as_str()?;
+    if value.is_empty() {
+        return None;
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.fZ").map(|dt| dt.and_utc()))
+        .ok()
+}
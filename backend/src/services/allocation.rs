@@ -0,0 +1,126 @@
+//! Holdings split by asset type, market, sector and currency, and how far one of those splits
+//! is from an account's target allocation.
+
+use serde::Serialize;
+use crate::models::{AllocationDimension, TargetAllocation};
+use crate::services::rebalance::TradeSide;
+
+/// Drift below this many base-currency units isn't worth a trade
+const MIN_SUGGESTED_AMOUNT: f64 = 0.01;
+
+/// One holding's value in the base currency and the group it falls in per dimension
+#[derive(Debug, Clone)]
+pub struct AllocationHolding {
+    pub value: f64,
+    pub asset_type: String,
+    pub market: String,
+    /// (key, display name)
+    pub sector: (String, String),
+    pub currency: String,
+}
+
+impl AllocationHolding {
+    fn group(&self, dimension: AllocationDimension) -> (&str, &str) {
+        match dimension {
+            AllocationDimension::AssetType => (&self.asset_type, &self.asset_type),
+            AllocationDimension::Market => (&self.market, &self.market),
+            AllocationDimension::Sector => (&self.sector.0, &self.sector.1),
+            AllocationDimension::Currency => (&self.currency, &self.currency),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AllocationSlice {
+    pub key: String,
+    pub name: String,
+    pub assets_count: usize,
+    pub value: f64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AllocationDrift {
+    pub key: String,
+    pub name: String,
+    pub current_value: f64,
+    pub current_percent: f64,
+    pub target_percent: f64,
+    /// Current minus target, in percentage points
+    pub drift_percent: f64,
+    pub out_of_band: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<TradeSide>,
+    /// Base-currency amount to buy or sell to land on the target
+    pub suggested_amount: f64,
+}
+
+/// Groups of one dimension, largest first
+pub fn allocation(holdings: &[AllocationHolding], dimension: AllocationDimension) -> Vec<AllocationSlice> {
+    let total: f64 = holdings.iter().map(|h| h.value).sum();
+    let mut slices: Vec<AllocationSlice> = Vec::new();
+    for holding in holdings {
+        let (key, name) = holding.group(dimension);
+        match slices.iter_mut().find(|s| s.key == key) {
+            Some(slice) => {
+                slice.assets_count += 1;
+                slice.value += holding.value;
+            }
+            None => slices.push(AllocationSlice {
+                key: key.to_string(),
+                name: name.to_string(),
+                assets_count: 1,
+                value: holding.value,
+                percent: 0.0,
+            }),
+        }
+    }
+    for slice in &mut slices {
+        slice.percent = if total > 0.0 { slice.value / total * 100.0 } else { 0.0 };
+    }
+    slices.sort_by(|a, b| b.value.total_cmp(&a.value));
+    slices
+}
+
+/// Compare slices of the model's dimension with its targets. Targeted groups not held show up
+/// with no value; held groups without a target have a target of zero.
+pub fn drift(slices: &[AllocationSlice], model: &TargetAllocation) -> Vec<AllocationDrift> {
+    let total: f64 = slices.iter().map(|s| s.value).sum();
+    let target_for = |key: &str| model.targets.iter()
+        .find(|t| t.key.eq_ignore_ascii_case(key))
+        .map(|t| t.weight_percent)
+        .unwrap_or(0.0);
+
+    let held = slices.iter().map(|s| (s.key.clone(), s.name.clone(), s.value));
+    let missing = model.targets.iter()
+        .filter(|t| !slices.iter().any(|s| s.key.eq_ignore_ascii_case(&t.key)))
+        .map(|t| (t.key.clone(), t.key.clone(), 0.0));
+    let mut rows: Vec<AllocationDrift> = held.chain(missing)
+        .map(|(key, name, value)| {
+            let current_percent = if total > 0.0 { value / total * 100.0 } else { 0.0 };
+            let target_percent = target_for(&key);
+            let drift_percent = current_percent - target_percent;
+            let suggested = total * target_percent / 100.0 - value;
+            let side = if suggested > MIN_SUGGESTED_AMOUNT {
+                Some(TradeSide::Buy)
+            } else if suggested < -MIN_SUGGESTED_AMOUNT {
+                Some(TradeSide::Sell)
+            } else {
+                None
+            };
+            AllocationDrift {
+                key,
+                name,
+                current_value: value,
+                current_percent,
+                target_percent,
+                drift_percent,
+                out_of_band: drift_percent.abs() > model.band_percent,
+                side,
+                suggested_amount: if side.is_some() { suggested.abs() } else { 0.0 },
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| b.drift_percent.abs().total_cmp(&a.drift_percent.abs()));
+    rows
+}
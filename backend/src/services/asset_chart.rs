@@ -0,0 +1,197 @@
+//! Price chart of one asset: recorded history, provider closes from before recording started,
+//! the live cached price and the user's trades, merged for charting libraries.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::{AssetType, Market, TradeAction, Transaction};
+use crate::services::price_history::{build_candles, CandleInterval};
+use crate::services::price_service::{HistoryEntry, PriceEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ChartRange {
+    #[serde(rename = "1d")]
+    Day,
+    #[serde(rename = "5d")]
+    FiveDays,
+    #[serde(rename = "1m")]
+    Month,
+    #[serde(rename = "3m")]
+    ThreeMonths,
+    #[serde(rename = "6m")]
+    SixMonths,
+    #[serde(rename = "ytd")]
+    YearToDate,
+    #[default]
+    #[serde(rename = "1y")]
+    Year,
+    #[serde(rename = "5y")]
+    FiveYears,
+}
+
+impl ChartRange {
+    pub fn start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            ChartRange::Day => now - Duration::days(1),
+            ChartRange::FiveDays => now - Duration::days(5),
+            ChartRange::Month => now - Duration::days(30),
+            ChartRange::ThreeMonths => now - Duration::days(91),
+            ChartRange::SixMonths => now - Duration::days(182),
+            ChartRange::YearToDate => NaiveDate::from_ymd_opt(now.year(), 1, 1)
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|d| d.and_utc())
+                .unwrap_or(now),
+            ChartRange::Year => now - Duration::days(365),
+            ChartRange::FiveYears => now - Duration::days(5 * 365),
+        }
+    }
+
+    /// Interval used when the request gives none
+    pub fn default_interval(&self) -> CandleInterval {
+        match self {
+            ChartRange::Day | ChartRange::FiveDays => CandleInterval::Hour,
+            ChartRange::Month => CandleInterval::FourHours,
+            ChartRange::ThreeMonths | ChartRange::SixMonths | ChartRange::YearToDate | ChartRange::Year => CandleInterval::Day,
+            ChartRange::FiveYears => CandleInterval::Week,
+        }
+    }
+}
+
+/// OHLC bar keyed by Unix seconds, the format most JS charting libraries take directly
+#[derive(Debug, Clone, Serialize)]
+pub struct ChartCandle {
+    pub time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerSide {
+    Buy,
+    Sell,
+    Dividend,
+}
+
+/// One of the user's transactions, to be drawn on the chart
+#[derive(Debug, Clone, Serialize)]
+pub struct ChartMarker {
+    pub time: i64,
+    pub transaction_id: String,
+    pub action: TradeAction,
+    pub side: MarkerSide,
+    /// Trade price, or the amount received for dividends
+    pub price: f64,
+    pub quantity: f64,
+    /// Short label, e.g. "Buy 100 @ 35.5"
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetChart {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    pub range: ChartRange,
+    pub interval: CandleInterval,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub candles: Vec<ChartCandle>,
+    pub markers: Vec<ChartMarker>,
+    /// Latest cached price; extends the last candle when newer than the recorded points
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_price: Option<PriceEntry>,
+    /// Points taken from asset_price_history
+    pub recorded_points: usize,
+    /// Provider daily closes used for the stretch before recording started
+    pub provider_points: usize,
+}
+
+/// Provider daily closes from `from` up to the first recorded point
+pub fn backfill_points(
+    history: &[HistoryEntry],
+    first_recorded: Option<DateTime<Utc>>,
+    from: DateTime<Utc>,
+) -> Vec<(DateTime<Utc>, f64)> {
+    let mut points: Vec<(DateTime<Utc>, f64)> = history.iter()
+        .filter(|h| h.price > 0.0)
+        .filter_map(|h| {
+            let day = NaiveDate::parse_from_str(&h.date, "%Y-%m-%d").ok()?;
+            Some((day.and_hms_opt(0, 0, 0)?.and_utc(), h.price))
+        })
+        .filter(|(at, _)| *at >= from && first_recorded.is_none_or(|first| *at < first))
+        .collect();
+    points.sort_by_key(|(at, _)| *at);
+    points
+}
+
+/// Backfill, recorded points and the live price as one time-ordered series. The live price is
+/// only appended when it is newer than the last point and quoted in the chart's currency.
+pub fn merge_points(
+    backfill: Vec<(DateTime<Utc>, f64)>,
+    recorded: Vec<(DateTime<Utc>, f64)>,
+    currency: Option<&str>,
+    live: Option<&PriceEntry>,
+) -> Vec<(DateTime<Utc>, f64)> {
+    let mut points = backfill;
+    points.extend(recorded);
+    if let Some(live) = live {
+        let newer = points.last().is_none_or(|(at, _)| live.updated_at > *at);
+        let same_currency = currency.is_none_or(|c| c.eq_ignore_ascii_case(&live.currency));
+        if newer && same_currency && live.price > 0.0 {
+            points.push((live.updated_at, live.price));
+        }
+    }
+    points
+}
+
+pub fn chart_candles(points: &[(DateTime<Utc>, f64)], interval: CandleInterval) -> Vec<ChartCandle> {
+    build_candles(points, interval).into_iter()
+        .map(|c| ChartCandle { time: c.time.timestamp(), open: c.open, high: c.high, low: c.low, close: c.close })
+        .collect()
+}
+
+/// The user's trades and dividends in this symbol between `from` and `to`, oldest first
+pub fn trade_markers(
+    transactions: &[Transaction],
+    symbol: &str,
+    asset_type: &AssetType,
+    market: Option<&Market>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<ChartMarker> {
+    let mut markers: Vec<ChartMarker> = transactions.iter()
+        .filter(|t| t.symbol.eq_ignore_ascii_case(symbol) && t.asset_type == *asset_type)
+        // Transactions without a market match any
+        .filter(|t| market.is_none_or(|m| t.market.as_ref().is_none_or(|tm| tm == m)))
+        .filter(|t| t.timestamp >= from && t.timestamp <= to)
+        .filter_map(|t| {
+            let side = match t.action {
+                TradeAction::Buy | TradeAction::Long | TradeAction::CloseShort | TradeAction::LiquidateShort => MarkerSide::Buy,
+                TradeAction::Sell | TradeAction::Short | TradeAction::CloseLong | TradeAction::LiquidateLong => MarkerSide::Sell,
+                TradeAction::Dividend => MarkerSide::Dividend,
+                TradeAction::Deposit | TradeAction::Withdraw => return None,
+            };
+            let text = match side {
+                MarkerSide::Buy => format!("Buy {} @ {}", t.quantity, t.price),
+                MarkerSide::Sell => format!("Sell {} @ {}", t.quantity, t.price),
+                MarkerSide::Dividend => format!("Dividend {}", t.price),
+            };
+            Some(ChartMarker {
+                time: t.timestamp.timestamp(),
+                transaction_id: t.id.clone(),
+                action: t.action.clone(),
+                side,
+                price: t.price,
+                quantity: t.quantity,
+                text,
+            })
+        })
+        .collect();
+    markers.sort_by_key(|m| m.time);
+    markers
+}
@@ -5,18 +5,20 @@ use oauth2::{
     PkceCodeVerifier, RedirectUrl, Scope, TokenUrl,
     basic::BasicClient, reqwest::async_http_client,
 };
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 
 use crate::config::Config;
 use crate::error::AppError;
 use crate::models::{
     User, OAuthAccount, OAuthProvider, Claims, GoogleUserInfo, AuthResponse,
-    LinkedProvider, UserResponse,
+    LinkedProvider, UserResponse, Invite, INVITES_COLLECTION,
+    LoginContext, Session, SESSIONS_COLLECTION,
 };
 
 use crate::services::PocketBaseClient;
+use crate::services::pocketbase::{updated_since_filter, CacheChanges, PENDING_WRITE_GRACE_SECONDS};
 
 /// Auth service for handling OAuth/OIDC authentication
 #[derive(Clone)]
@@ -32,6 +34,61 @@ pub struct AuthService {
     oauth_accounts: Arc<RwLock<HashMap<String, OAuthAccount>>>,
     // PKCE verifiers storage (csrf_token -> verifier)
     pkce_verifiers: Arc<RwLock<HashMap<String, String>>>,
+    // Invite codes with a registration in progress
+    redeeming: Arc<Mutex<HashSet<String>>>,
+}
+
+/// A valid invite held for one registration; dropping it frees the code
+pub struct InviteClaim {
+    pub invite: Invite,
+    redeeming: Arc<Mutex<HashSet<String>>>,
+    code: String,
+}
+
+impl Drop for InviteClaim {
+    fn drop(&mut self) {
+        self.redeeming.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.code);
+    }
+}
+
+/// User record as stored in PocketBase
+#[derive(serde::Deserialize)]
+struct PBUser {
+    id: String,
+    email: String,
+    name: Option<String>,
+    avatar_url: Option<String>,
+    #[serde(default)]
+    local_password_hash: Option<String>,
+    #[serde(default = "default_user_role")]
+    role: String,
+    #[serde(default)]
+    token_version: i32,
+    #[serde(default)]
+    pending_verification: bool,
+}
+
+fn default_user_role() -> String {
+    "user".to_string()
+}
+
+impl PBUser {
+    /// Cached user for this record, keeping the timestamps of the entry it replaces
+    fn into_user(self, cached: Option<&User>) -> User {
+        let now = Utc::now();
+        User {
+            id: self.id,
+            email: self.email,
+            name: self.name,
+            avatar_url: self.avatar_url,
+            role: self.role,
+            local_password_hash: self.local_password_hash,
+            created_at: cached.map_or(now, |u| u.created_at),
+            updated_at: cached.map_or(now, |u| u.updated_at),
+            token_version: self.token_version,
+            pending_verification: self.pending_verification,
+        }
+    }
 }
 
 /// OIDC Discovery document
@@ -69,13 +126,14 @@ impl AuthService {
         let pocketbase_url = config.pocketbase_url.clone();
         let service = Self {
             config: config.clone(),
-            http_client: reqwest::Client::new(),
+            http_client: crate::services::http_client::clients(&config).external.clone(),
             pocketbase_url,
             pb_client,
             users: Arc::new(RwLock::new(HashMap::new())),
             loaded_users: Arc::new(RwLock::new(false)),
             oauth_accounts: Arc::new(RwLock::new(HashMap::new())),
             pkce_verifiers: Arc::new(RwLock::new(HashMap::new())),
+            redeeming: Arc::new(Mutex::new(HashSet::new())),
         };
         
         // Load users from PocketBase
@@ -90,7 +148,7 @@ impl AuthService {
         );
 
         if let (Some(email), Some(password)) = (&config.admin_email, &config.admin_password) {
-            match service.register_local_user(email, password, Some("Admin".to_string()), true).await {
+            match service.register_local_user(email, password, Some("Admin".to_string()), true, false).await {
                 Ok(user) => {
                     tracing::info!("Created initial admin user: {} ({}) with role: {}", user.email, user.id, user.role);
                 }
@@ -122,24 +180,6 @@ impl AuthService {
             items: Vec<T>,
         }
         
-        #[derive(serde::Deserialize)]
-        struct PBUser {
-            id: String,
-            email: String,
-            name: Option<String>,
-            avatar_url: Option<String>,
-            #[serde(default)]
-            local_password_hash: Option<String>,
-            #[serde(default = "default_user_role")]
-            role: String,
-            #[serde(default)]
-            token_version: i32,
-        }
-        
-        fn default_user_role() -> String {
-            "user".to_string()
-        }
-
         let request = self.http_client.get(&url);
         let request = if !token.is_empty() {
             request.header("Authorization", token)
@@ -157,21 +197,11 @@ impl AuthService {
                             let has_hash = pb_user.local_password_hash.is_some();
                             if has_hash { users_with_hash += 1; }
                             
-                            let user = User {
-                                id: pb_user.id.clone(),
-                                email: pb_user.email.clone(),
-                                name: pb_user.name,
-                                avatar_url: pb_user.avatar_url,
-                                role: pb_user.role,
-                                local_password_hash: pb_user.local_password_hash,
-                                created_at: chrono::Utc::now(),
-                                updated_at: chrono::Utc::now(),
-                                token_version: pb_user.token_version,
-                            };
+                            let user = pb_user.into_user(None);
                             cache.insert(user.id.clone(), user);
                         }
                         tracing::info!("📦 Loaded {} users from PocketBase ({} have local_password_hash)", cache.len(), users_with_hash);
-                        if cache.len() > 0 && users_with_hash == 0 {
+                        if !cache.is_empty() && users_with_hash == 0 {
                             tracing::warn!("⚠️ No users have local_password_hash! Check if 'local_password_hash' field exists in PocketBase 'users' collection.");
                         }
                     }
@@ -188,8 +218,38 @@ impl AuthService {
         Ok(())
     }
 
-    /// Sync user to PocketBase (async, don't block)
-    fn sync_user_to_pb(&self, user: &User, password: Option<String>) {
+    /// Merge users changed in PocketBase since `since` into the cache and drop users deleted
+    /// there, so role changes, revoked sessions (token_version) and removals made outside this
+    /// API take effect. Users written here in the last minute are left alone while their own
+    /// sync may still be in flight.
+    pub async fn revalidate_users(&self, since: chrono::DateTime<Utc>) -> Result<CacheChanges, AppError> {
+        if !*self.loaded_users.read().await {
+            return Ok(CacheChanges::default());
+        }
+        let grace_start = Utc::now() - Duration::seconds(PENDING_WRITE_GRACE_SECONDS);
+        let changed: Vec<PBUser> = self.pb_client.list_all_records("users", Some(updated_since_filter(since))).await?;
+        let ids = self.pb_client.record_ids("users").await?;
+
+        let mut cache = self.users.write().await;
+        let mut changes = CacheChanges::default();
+        for pb_user in changed {
+            let cached = cache.get(&pb_user.id);
+            if cached.is_some_and(|u| u.updated_at > grace_start) {
+                continue;
+            }
+            let user = pb_user.into_user(cached);
+            cache.insert(user.id.clone(), user);
+            changes.updated += 1;
+        }
+        let before = cache.len();
+        cache.retain(|id, user| ids.contains(id) || user.updated_at > grace_start);
+        changes.removed = before - cache.len();
+        Ok(changes)
+    }
+
+    /// Sync user to PocketBase (async, don't block). The handle can be awaited when a
+    /// follow-up request needs the record to exist.
+    fn sync_user_to_pb(&self, user: &User, password: Option<String>) -> tokio::task::JoinHandle<()> {
         let url = format!("{}/api/collections/users/records", self.pocketbase_url);
         let user_clone = user.clone();
         let client = self.http_client.clone();
@@ -205,6 +265,7 @@ impl AuthService {
             role: String,
             local_password_hash: Option<String>,
             token_version: i32,
+            pending_verification: bool,
             #[serde(skip_serializing_if = "Option::is_none")]
             password: Option<String>,
             #[serde(skip_serializing_if = "Option::is_none")]
@@ -220,6 +281,7 @@ impl AuthService {
             role: user_clone.role.clone(),
             local_password_hash: user_clone.local_password_hash.clone(),
             token_version: user_clone.token_version,
+            pending_verification: user_clone.pending_verification,
             password: password.clone(),
             password_confirm: password.clone(),
         };
@@ -234,15 +296,11 @@ impl AuthService {
             let req = client.patch(&update_url);
             let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
             
-            match req.json(&payload).send().await {
-                Ok(resp) => {
-                    let status = resp.status();
-                    if status.is_success() {
-                        tracing::info!("✅ User updated in PocketBase: {} (role: {})", user_clone.id, user_clone.role);
-                        return;
-                    }
+            if let Ok(resp) = req.json(&payload).send().await {
+                if resp.status().is_success() {
+                    tracing::info!("✅ User updated in PocketBase: {} (role: {})", user_clone.id, user_clone.role);
+                    return;
                 }
-                Err(_) => {}
             }
             
             // If update fails, try to create new record
@@ -261,7 +319,7 @@ impl AuthService {
                 }
                 Err(e) => tracing::error!("❌ Could not sync user to PocketBase: {}", e),
             }
-        });
+        })
     }
 
     // ==================== Google OAuth ====================
@@ -470,6 +528,11 @@ impl AuthService {
 
     /// Create JWT token for user
     pub fn create_jwt(&self, user: &User) -> Result<String, AppError> {
+        self.create_session_jwt(user, None)
+    }
+
+    /// Create JWT token for user, tied to a recorded session
+    fn create_session_jwt(&self, user: &User, sid: Option<String>) -> Result<String, AppError> {
         let now = Utc::now();
         let exp = now + Duration::hours(self.config.jwt_expiry_hours as i64);
 
@@ -479,6 +542,8 @@ impl AuthService {
             exp: exp.timestamp() as usize,
             iat: now.timestamp() as usize,
             token_version: user.token_version,
+            read_only: false,
+            sid,
         };
 
         let token = encode(
@@ -490,6 +555,37 @@ impl AuthService {
         Ok(token)
     }
 
+    /// Read-only token for the guest (demo) account
+    pub async fn create_guest_session(&self) -> Result<AuthResponse, AppError> {
+        let email = self.config.guest_user_email.as_deref()
+            .filter(|_| self.config.guest_login_enabled)
+            .ok_or_else(|| AppError::BadRequest("Guest access is disabled".to_string()))?;
+        let user = self.find_user_by_email(email).await
+            .ok_or_else(|| AppError::Internal(format!("Guest account {} not found", email)))?;
+        if user.is_admin() {
+            // A guest must never see admin pages, even read-only
+            return Err(AppError::Internal("The guest account must not be an admin".to_string()));
+        }
+
+        let now = Utc::now();
+        let claims = Claims {
+            sub: user.id.clone(),
+            email: user.email.clone(),
+            exp: (now + Duration::hours(self.config.guest_session_hours.max(1) as i64)).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            token_version: user.token_version,
+            read_only: true,
+            sid: None,
+        };
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.config.jwt_secret.as_bytes()),
+        ).map_err(|e| AppError::Internal(format!("Failed to create JWT: {}", e)))?;
+
+        Ok(AuthResponse { token, user: UserResponse::from(&user) })
+    }
+
     /// Verify JWT token
     pub fn verify_jwt(&self, token: &str) -> Result<Claims, AppError> {
         let token_data = decode::<Claims>(
@@ -501,6 +597,54 @@ impl AuthService {
         Ok(token_data.claims)
     }
 
+    // ==================== Sessions ====================
+
+    /// Issue a token for `user` and record the device it was issued to. Also returns what was
+    /// new about this login compared to the user's earlier sessions. If the session cannot be
+    /// recorded the login still succeeds, just without device details.
+    pub async fn start_session(&self, user: &User, context: &LoginContext) -> Result<(String, LoginNovelty), AppError> {
+        let previous = self.pb_client
+            .list_records::<Session>(SESSIONS_COLLECTION, Some(format!("user_id = '{}'", user.id)), "-created")
+            .await
+            .unwrap_or_default();
+        let novelty = LoginNovelty::compare(&previous, context);
+
+        let expires_at = Utc::now() + Duration::hours(self.config.jwt_expiry_hours as i64);
+        let body = serde_json::json!({
+            "user_id": user.id,
+            "device": context.device(),
+            "user_agent": context.user_agent.chars().take(500).collect::<String>(),
+            "ip": context.ip.clone().unwrap_or_default(),
+            "country": context.country.clone().unwrap_or_default(),
+            "token_version": user.token_version,
+            "expires_at": expires_at.to_rfc3339(),
+        });
+        let sid = match self.pb_client.create_record::<Session>(SESSIONS_COLLECTION, &body).await {
+            Ok(session) => Some(session.id),
+            Err(e) => {
+                tracing::warn!("⚠️ Could not record session for {}: {}", user.email, e);
+                None
+            }
+        };
+
+        Ok((self.create_session_jwt(user, sid)?, novelty))
+    }
+
+    /// The user's sessions that can still be used, newest first; `current_sid` is flagged
+    pub async fn list_sessions(&self, user: &User, current_sid: Option<&str>) -> Result<Vec<Session>, AppError> {
+        let now = Utc::now();
+        let sessions = self.pb_client
+            .list_records::<Session>(SESSIONS_COLLECTION, Some(format!("user_id = '{}'", user.id)), "-created")
+            .await?;
+        Ok(sessions.into_iter()
+            .filter(|s| s.is_active(user.token_version, now))
+            .map(|mut s| {
+                s.current = current_sid == Some(s.id.as_str());
+                s
+            })
+            .collect())
+    }
+
     // ==================== User Management ====================
 
     /// Find or create user from OAuth info
@@ -517,6 +661,12 @@ impl AuthService {
             return Ok(user);
         }
 
+        // Existing accounts can always sign in; new ones need an invite in invitation-only mode
+        if self.is_invite_only() {
+            tracing::warn!("🚫 Refused OAuth sign-up for {}: registration is by invitation only", email);
+            return Err(AppError::Forbidden("Registration is by invitation only".to_string()));
+        }
+
         // Create new user
         let mut user = User::new(email.to_string(), name);
         user.avatar_url = avatar_url;
@@ -546,13 +696,15 @@ impl AuthService {
         users.values().find(|u| u.email == email).cloned()
     }
 
-    /// Register local user with password
+    /// Register local user with password. With `pending_verification` the account cannot log in
+    /// until the verification email (sent once the user exists in PocketBase) is confirmed.
     pub async fn register_local_user(
         &self,
         email: &str,
         password: &str,
         name: Option<String>,
         is_admin: bool,
+        pending_verification: bool,
     ) -> Result<User, AppError> {
         let mut users = self.users.write().await;
         
@@ -572,11 +724,22 @@ impl AuthService {
             User::new(email.to_string(), name)
         };
         user.local_password_hash = Some(password_hash);
+        user.pending_verification = pending_verification;
         users.insert(user.id.clone(), user.clone());
         
         // Sync to PocketBase
         drop(users); // Release lock before sync
-        self.sync_user_to_pb(&user, Some(password.to_string()));
+        let synced = self.sync_user_to_pb(&user, Some(password.to_string()));
+        if pending_verification {
+            let service = self.clone();
+            let email = user.email.clone();
+            tokio::spawn(async move {
+                let _ = synced.await;
+                if let Err(e) = service.request_email_verification(&email).await {
+                    tracing::warn!("⚠️ Could not send verification email to {}: {}", email, e);
+                }
+            });
+        }
         
         tracing::info!("Registered new local user: {} ({}) role: {}", user.id, user.email, user.role);
         Ok(user)
@@ -619,7 +782,7 @@ impl AuthService {
                     // Ideally we should update the local hash here if we could get it, 
                     // but PB doesn't return the raw hash usually.
                     // We just return the authenticated user.
-                    return Ok(local_user);
+                    Ok(local_user)
                 } else {
                     // User existed in PB but not locally? This shouldn't happen if sync works,
                     // but we can trust PB's return.
@@ -634,18 +797,19 @@ impl AuthService {
                         created_at: chrono::Utc::now(),
                         updated_at: chrono::Utc::now(),
                         token_version: pb_user.token_version,
+                        pending_verification: pb_user.pending_verification,
                     };
                     
                     let mut users = self.users.write().await;
                     users.insert(user.id.clone(), user.clone());
                     
-                    return Ok(user);
+                    Ok(user)
                 }
             }
             Err(e) => {
                 // Both strategies failed
                 tracing::warn!("Login failed for {}: {}", email, e);
-                return Err(AppError::Unauthorized("Invalid email or password".to_string())); 
+                Err(AppError::Unauthorized("Invalid email or password".to_string()))
             }
         }
     }
@@ -746,6 +910,8 @@ impl AuthService {
             role: String,
             #[serde(default)]
             token_version: i32,
+            #[serde(default)]
+            pending_verification: bool,
             // PB might use 'avatar' field name in record, mapped to avatar_url in our model
         }
         
@@ -778,6 +944,7 @@ impl AuthService {
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
                 token_version: data.record.token_version,
+                pending_verification: data.record.pending_verification,
             })
         } else {
             let status = resp.status();
@@ -792,6 +959,139 @@ impl AuthService {
         self.config.local_auth_enabled.unwrap_or(true)
     }
 
+    // ==================== Email Verification ====================
+
+    pub fn requires_email_verification(&self) -> bool {
+        self.config.require_email_verification
+    }
+
+    /// Ask PocketBase to email a verification link (uses its SMTP settings and template).
+    /// Does nothing for accounts that are already verified.
+    pub async fn request_email_verification(&self, email: &str) -> Result<(), AppError> {
+        match self.find_user_by_email(email).await {
+            Some(user) if user.pending_verification => {}
+            _ => return Ok(()),
+        }
+
+        let url = format!("{}/api/collections/users/request-verification", self.pocketbase_url);
+        let resp = self.http_client.post(&url)
+            .json(&serde_json::json!({ "email": email }))
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to contact PocketBase: {}", e)))?;
+
+        if resp.status().is_success() {
+            tracing::info!("📧 Verification email requested for {}", email);
+            Ok(())
+        } else {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            Err(AppError::ExternalApiError(format!("PocketBase verification request failed: {} - {}", status, body)))
+        }
+    }
+
+    /// Confirm a verification token from the emailed link and activate the account
+    pub async fn confirm_email_verification(&self, token: &str) -> Result<User, AppError> {
+        let url = format!("{}/api/collections/users/confirm-verification", self.pocketbase_url);
+        let resp = self.http_client.post(&url)
+            .json(&serde_json::json!({ "token": token }))
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to contact PocketBase: {}", e)))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            tracing::warn!("⚠️ Email verification rejected by PocketBase: {} - {}", status, body);
+            return Err(AppError::BadRequest("Invalid or expired verification token".to_string()));
+        }
+
+        // PocketBase has checked the signature; we only need the record id from the claims
+        #[derive(serde::Deserialize)]
+        struct VerificationClaims {
+            id: String,
+        }
+        let mut validation = Validation::default();
+        validation.insecure_disable_signature_validation();
+        validation.validate_exp = false;
+        validation.required_spec_claims.clear();
+        let claims = decode::<VerificationClaims>(token, &DecodingKey::from_secret(&[]), &validation)
+            .map_err(|e| AppError::BadRequest(format!("Invalid verification token: {}", e)))?
+            .claims;
+
+        let mut users = self.users.write().await;
+        let user = users.get_mut(&claims.id)
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        user.pending_verification = false;
+        user.updated_at = chrono::Utc::now();
+        let user_clone = user.clone();
+
+        drop(users);
+        self.sync_user_to_pb(&user_clone, None);
+
+        tracing::info!("✅ Email verified for user {} ({})", user_clone.id, user_clone.email);
+        Ok(user_clone)
+    }
+
+    // ==================== Invitations ====================
+
+    pub fn is_invite_only(&self) -> bool {
+        self.config.registration_invite_only
+    }
+
+    pub fn is_guest_login_enabled(&self) -> bool {
+        self.config.guest_login_enabled && self.config.guest_user_email.is_some()
+    }
+
+    /// Hold an invite code for one registration, so a single-use code can't be redeemed twice.
+    /// The code is read (and checked unused) only once it is held.
+    pub async fn claim_invite(&self, code: &str, email: &str) -> Result<InviteClaim, AppError> {
+        let claimed = self.redeeming.lock().unwrap_or_else(|e| e.into_inner()).insert(code.to_string());
+        if !claimed {
+            return Err(AppError::Conflict("This invite code is already being used".to_string()));
+        }
+        match self.validate_invite(code, email).await {
+            Ok(invite) => Ok(InviteClaim { invite, redeeming: self.redeeming.clone(), code: code.to_string() }),
+            Err(e) => {
+                self.redeeming.lock().unwrap_or_else(|e| e.into_inner()).remove(code);
+                Err(e)
+            }
+        }
+    }
+
+    /// Look up an invite code and check it can still be used by `email`
+    async fn validate_invite(&self, code: &str, email: &str) -> Result<Invite, AppError> {
+        let invalid = || AppError::Forbidden("Invalid or expired invite code".to_string());
+        let filter = format!("code = '{}'", code.replace('\'', ""));
+        let invite = self.pb_client
+            .list_records::<Invite>(INVITES_COLLECTION, Some(filter), "-created")
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(invalid)?;
+
+        if invite.is_used() || invite.is_expired(Utc::now()) {
+            return Err(invalid());
+        }
+        if !invite.email.is_empty() && !invite.email.eq_ignore_ascii_case(email) {
+            return Err(invalid());
+        }
+        Ok(invite)
+    }
+
+    /// Mark an invite as used by the newly registered user
+    pub async fn redeem_invite(&self, invite: &Invite, user_id: &str) -> Result<(), AppError> {
+        let body = serde_json::json!({
+            "used_by": user_id,
+            "used_at": Utc::now().to_rfc3339(),
+        });
+        self.pb_client
+            .update_record::<serde_json::Value>(INVITES_COLLECTION, &invite.id, &body)
+            .await?;
+        tracing::info!("🎟️ Invite {} redeemed by {}", invite.code, user_id);
+        Ok(())
+    }
+
     /// Update user
     #[allow(dead_code)]
     pub async fn update_user(&self, user: User) -> Result<User, AppError> {
@@ -1004,3 +1304,31 @@ impl AuthService {
         });
     }
 }
+
+/// What a login has that none of the user's earlier sessions had
+#[derive(Debug, Clone, Default)]
+pub struct LoginNovelty {
+    pub new_device: Option<String>,
+    pub new_country: Option<String>,
+}
+
+impl LoginNovelty {
+    /// Nothing is new on a first login. A country only counts once earlier sessions
+    /// have recorded one, so turning on the geo header does not flag everyone.
+    fn compare(previous: &[Session], context: &LoginContext) -> Self {
+        if previous.is_empty() {
+            return Self::default();
+        }
+        let device = context.device();
+        let new_device = (!previous.iter().any(|s| s.device == device)).then_some(device);
+        let new_country = context.country.clone().filter(|country| {
+            previous.iter().any(|s| !s.country.is_empty())
+                && !previous.iter().any(|s| s.country.eq_ignore_ascii_case(country))
+        });
+        Self { new_device, new_country }
+    }
+
+    pub fn is_suspicious(&self) -> bool {
+        self.new_device.is_some() || self.new_country.is_some()
+    }
+}
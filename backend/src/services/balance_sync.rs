@@ -0,0 +1,121 @@
+//! Reads exchange wallets with the user's stored API keys and values them at cached prices.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::error::AppError;
+use crate::models::{AssetType, CreateExchangeKeyRequest, ExchangeBalance, ExchangeCredentials, ExchangeKey, Market, KEYED_EXCHANGES};
+use crate::services::credentials::CredentialStore;
+use crate::services::price_service::PriceService;
+
+/// One wallet asset with its value, when a price for it is cached
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceValue {
+    #[serde(flatten)]
+    pub balance: ExchangeBalance,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountBalances {
+    pub key_id: String,
+    pub exchange: Market,
+    pub label: String,
+    pub fetched_at: DateTime<Utc>,
+    pub balances: Vec<BalanceValue>,
+}
+
+pub struct BalanceSyncService {
+    store: CredentialStore,
+    price_service: PriceService,
+}
+
+impl BalanceSyncService {
+    pub fn new(store: CredentialStore, price_service: PriceService) -> Self {
+        Self { store, price_service }
+    }
+
+    pub fn store(&self) -> &CredentialStore {
+        &self.store
+    }
+
+    /// Check a key against the exchange before storing it, so typos and keys without read
+    /// permission are rejected up front
+    pub async fn register(&self, user_id: &str, req: CreateExchangeKeyRequest) -> Result<ExchangeKey, AppError> {
+        if !KEYED_EXCHANGES.contains(&req.exchange) {
+            let supported: Vec<String> = KEYED_EXCHANGES.iter().map(|m| m.to_string()).collect();
+            return Err(AppError::BadRequest(format!(
+                "API keys are supported for {} only", supported.join(", ")
+            )));
+        }
+        let credentials = ExchangeCredentials {
+            api_key: req.api_key.trim().to_string(),
+            api_secret: req.api_secret.trim().to_string(),
+        };
+        if credentials.api_key.is_empty() || credentials.api_secret.is_empty() {
+            return Err(AppError::BadRequest("api_key and api_secret are required".to_string()));
+        }
+        let label = req.label.as_deref().map(str::trim).filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| req.exchange.to_string());
+
+        self.price_service.account_balances(&req.exchange, &credentials).await
+            .map_err(|e| AppError::Unprocessable(format!("{} rejected the key: {}", req.exchange, e)))?;
+        self.store.add(user_id, &req.exchange, &label, &credentials).await
+    }
+
+    /// Current wallet of one of the user's keys
+    pub async fn balances(&self, user_id: &str, key_id: &str) -> Result<AccountBalances, AppError> {
+        let key = self.store.get(user_id, key_id).await?;
+        let credentials = self.store.credentials(&key)?;
+        let balances = match self.price_service.account_balances(&key.exchange, &credentials).await {
+            Ok(balances) => {
+                self.store.record_use(&key, None).await;
+                balances
+            }
+            Err(e) => {
+                self.store.record_use(&key, Some(&e.to_string())).await;
+                return Err(e);
+            }
+        };
+
+        let quote_currency = quote_currency(&key.exchange);
+        let mut values = Vec::with_capacity(balances.len());
+        for balance in balances {
+            let (price, currency) = if balance.asset == quote_currency {
+                (Some(1.0), Some(quote_currency.to_string()))
+            } else {
+                match self.price_service.cached_price(&balance.asset, &AssetType::Crypto, Some(&key.exchange)).await {
+                    Some(lookup) => (Some(lookup.entry.price), Some(lookup.entry.currency)),
+                    None => (None, None),
+                }
+            };
+            values.push(BalanceValue {
+                value: price.map(|p| p * balance.total()),
+                balance,
+                price,
+                currency,
+            });
+        }
+
+        Ok(AccountBalances {
+            key_id: key.id,
+            exchange: key.exchange,
+            label: key.label,
+            fetched_at: Utc::now(),
+            balances: values,
+        })
+    }
+}
+
+/// Currency the exchange's spot prices are quoted in
+fn quote_currency(exchange: &Market) -> &'static str {
+    match exchange {
+        Market::Bitkub => "THB",
+        _ => "USDT",
+    }
+}
@@ -0,0 +1,121 @@
+//! Benchmark indices the portfolio can be compared against. The benchmark_update job stores
+//! their daily closes in benchmark_levels, so comparisons don't depend on how much history a
+//! provider returns on the day they are asked for.
+
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+use crate::error::AppError;
+use crate::models::{AssetType, BenchmarkLevel, BENCHMARK_LEVELS_COLLECTION};
+use crate::services::{PocketBaseClient, PriceService};
+
+/// History fetched for a benchmark with no stored levels yet (Yahoo serves up to 2 years)
+const BACKFILL_DAYS: u32 = 730;
+/// Extra days fetched before the last stored level, to fill in closes published late
+const OVERLAP_DAYS: i64 = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Benchmark {
+    pub code: &'static str,
+    pub name: &'static str,
+    /// Symbol the provider knows the index by
+    pub symbol: &'static str,
+    #[serde(skip)]
+    pub asset_type: AssetType,
+    pub currency: &'static str,
+}
+
+pub const BENCHMARKS: &[Benchmark] = &[
+    Benchmark { code: "SET", name: "SET Index", symbol: "^SET.BK", asset_type: AssetType::ForeignStock, currency: "THB" },
+    Benchmark { code: "GSPC", name: "S&P 500", symbol: "^GSPC", asset_type: AssetType::ForeignStock, currency: "USD" },
+    Benchmark { code: "BTC", name: "Bitcoin", symbol: "BTC", asset_type: AssetType::Crypto, currency: "USD" },
+];
+
+/// Benchmark by code, ignoring case and a leading "^" ("^GSPC" finds GSPC)
+pub fn find_benchmark(code: &str) -> Option<&'static Benchmark> {
+    let code = code.trim().trim_start_matches('^');
+    BENCHMARKS.iter().find(|b| b.code.eq_ignore_ascii_case(code))
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BenchmarkRefresh {
+    pub benchmark: String,
+    pub stored: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Stored closes of a benchmark from `from` to `to` (inclusive), oldest first
+pub async fn load_levels(
+    db: &PocketBaseClient,
+    benchmark: &Benchmark,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<Vec<(NaiveDate, f64)>, AppError> {
+    let mut filter = format!("benchmark='{}'", benchmark.code);
+    if let Some(from) = from {
+        filter.push_str(&format!(" && date>='{}'", from.format("%Y-%m-%d")));
+    }
+    if let Some(to) = to {
+        filter.push_str(&format!(" && date<='{}'", to.format("%Y-%m-%d")));
+    }
+    let levels: Vec<BenchmarkLevel> = db.list_all_records(BENCHMARK_LEVELS_COLLECTION, Some(filter)).await?;
+    let mut levels: Vec<(NaiveDate, f64)> = levels.into_iter()
+        .filter(|l| l.level > 0.0)
+        .filter_map(|l| Some((NaiveDate::parse_from_str(l.date.get(..10)?, "%Y-%m-%d").ok()?, l.level)))
+        .collect();
+    levels.sort_by_key(|(date, _)| *date);
+    levels.dedup_by_key(|(date, _)| *date);
+    Ok(levels)
+}
+
+/// Fetch and store closes newer than the last stored one. Today's close is left for the next
+/// run since the session may still be open.
+pub async fn refresh_levels(db: &PocketBaseClient, price_service: &PriceService, benchmark: &Benchmark) -> Result<BenchmarkRefresh, AppError> {
+    let today = Utc::now().date_naive();
+    let latest: Vec<BenchmarkLevel> = db
+        .list_records(BENCHMARK_LEVELS_COLLECTION, Some(format!("benchmark='{}'", benchmark.code)), "-date")
+        .await?;
+    let last_stored = latest.first()
+        .and_then(|l| NaiveDate::parse_from_str(l.date.get(..10)?, "%Y-%m-%d").ok());
+    let days = match last_stored {
+        Some(last) => ((today - last).num_days() + OVERLAP_DAYS).clamp(30, BACKFILL_DAYS as i64) as u32,
+        None => BACKFILL_DAYS,
+    };
+
+    let history = price_service.get_price_history(benchmark.symbol, &benchmark.asset_type, None, days).await?;
+    let mut report = BenchmarkRefresh { benchmark: benchmark.code.to_string(), ..Default::default() };
+    let stored: std::collections::HashSet<&str> = latest.iter().map(|l| l.date.get(..10).unwrap_or(&l.date)).collect();
+    for entry in history.iter().filter(|h| h.price > 0.0) {
+        let Ok(date) = NaiveDate::parse_from_str(entry.date.get(..10).unwrap_or(&entry.date), "%Y-%m-%d") else { continue };
+        let date_key = date.format("%Y-%m-%d").to_string();
+        if date >= today || last_stored.is_some_and(|last| date < last - chrono::Duration::days(OVERLAP_DAYS)) || stored.contains(date_key.as_str()) {
+            continue;
+        }
+        let body = serde_json::json!({
+            "benchmark": benchmark.code,
+            "date": date_key,
+            "level": entry.price,
+            "currency": benchmark.currency,
+        });
+        db.create_record::<serde_json::Value>(BENCHMARK_LEVELS_COLLECTION, &body).await?;
+        report.stored += 1;
+        report.latest_date = Some(date_key);
+    }
+    Ok(report)
+}
+
+/// Latest level on or before each date (markets are shut on weekends and holidays), as a
+/// return in percent from the first date that has one
+pub fn aligned_returns(levels: &[(NaiveDate, f64)], dates: &[NaiveDate]) -> Vec<Option<f64>> {
+    let mut base: Option<f64> = None;
+    dates.iter()
+        .map(|date| {
+            let index = levels.partition_point(|(d, _)| d <= date);
+            let level = index.checked_sub(1).map(|i| levels[i].1)?;
+            let base = *base.get_or_insert(level);
+            Some((level / base - 1.0) * 100.0)
+        })
+        .collect()
+}
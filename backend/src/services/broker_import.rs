@@ -0,0 +1,439 @@
+//! Map broker statement CSVs (Streaming/SET, Binance, Interactive Brokers) to transactions.
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::models::{AssetType, CreateTransactionRequest, Market, TradeAction};
+use crate::services::crypto_import::{is_usd_stablecoin, parse_csv, parse_timestamp};
+use crate::services::market_rules::check_transaction;
+
+/// Years between the Thai Buddhist and Gregorian calendars (Streaming may date rows in B.E.)
+const BUDDHIST_ERA_OFFSET: i32 = 543;
+
+// Statements may start with account details before the header row
+const MAX_PREAMBLE_ROWS: usize = 30;
+
+/// Quote assets tried, longest first, when splitting an old-style Binance market like "BTCUSDT"
+const BINANCE_QUOTES: &[&str] = &[
+    "FDUSD", "USDT", "USDC", "BUSD", "TUSD", "BTC", "ETH", "BNB", "THB", "EUR", "TRY", "BRL", "DAI",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrokerFormat {
+    /// Settrade Streaming trade history (Thai SET stocks)
+    Streaming,
+    /// Binance spot trade history
+    Binance,
+    /// IBKR activity statement (Trades section) or Flex query trades
+    InteractiveBrokers,
+}
+
+impl BrokerFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BrokerFormat::Streaming => "streaming",
+            BrokerFormat::Binance => "binance",
+            BrokerFormat::InteractiveBrokers => "interactive_brokers",
+        }
+    }
+}
+
+/// One statement row as it would be imported
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementRow {
+    /// 1-based record number in the file, counting the header
+    pub row: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<CreateTransactionRequest>,
+    /// Why the row cannot be imported as it is
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+    /// Imported anyway, but worth a look (odd lots, unconverted fees...)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl StatementRow {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty() && self.transaction.is_some()
+    }
+}
+
+/// Column lookup by normalized header name, trying each alias in turn
+struct Columns {
+    headers: Vec<String>,
+}
+
+impl Columns {
+    fn new(row: &[String]) -> Self {
+        Self { headers: row.iter().map(|h| normalize_header(h)).collect() }
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.headers.iter().any(|h| h == name)
+    }
+
+    fn get<'a>(&self, row: &'a [String], aliases: &[&str]) -> Option<&'a str> {
+        aliases.iter()
+            .filter_map(|alias| self.headers.iter().position(|h| h == alias))
+            .filter_map(|i| row.get(i))
+            .map(|s| s.trim())
+            .find(|s| !s.is_empty())
+    }
+
+    /// Sum of every listed column that has a value (fee breakdowns)
+    fn sum(&self, row: &[String], names: &[&str]) -> Result<f64, String> {
+        let mut total = 0.0;
+        for name in names {
+            if let Some(value) = self.get(row, &[name]) {
+                total += parse_signed(value).ok_or_else(|| format!("invalid {} '{}'", name, value))?.abs();
+            }
+        }
+        Ok(total)
+    }
+}
+
+fn normalize_header(h: &str) -> String {
+    h.trim().trim_start_matches('\u{feff}').trim_matches('"').to_lowercase()
+}
+
+fn detect_header(row: &[String]) -> Option<BrokerFormat> {
+    let columns = Columns::new(row);
+    let first_two: Vec<&str> = columns.headers.iter().take(2).map(String::as_str).collect();
+    if first_two == ["trades", "header"]
+        || (columns.has("symbol") && (columns.has("tradeprice") || columns.has("t. price")))
+    {
+        return Some(BrokerFormat::InteractiveBrokers);
+    }
+    if columns.has("date(utc)") && (columns.has("pair") || columns.has("market")) {
+        return Some(BrokerFormat::Binance);
+    }
+    if (columns.has("symbol") || columns.has("stock"))
+        && (columns.has("b/s") || columns.has("side"))
+        && (columns.has("volume") || columns.has("qty"))
+    {
+        return Some(BrokerFormat::Streaming);
+    }
+    None
+}
+
+/// Signed number; thousands separators and spaces are ignored and "(1.5)" is negative
+fn parse_signed(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let (negative, s) = match s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        Some(inner) => (true, inner),
+        None => (false, s),
+    };
+    let cleaned: String = s.chars().filter(|c| *c != ',' && *c != ' ').collect();
+    if cleaned.is_empty() || cleaned == "-" || cleaned == "--" {
+        return None;
+    }
+    cleaned.parse::<f64>().ok().map(|v| if negative { -v } else { v })
+}
+
+/// Binance writes amounts with their asset appended, e.g. "0.00100000BTC"
+fn split_amount(s: &str) -> Option<(f64, String)> {
+    let s = s.trim();
+    let split = s.find(|c: char| c.is_ascii_alphabetic())?;
+    let quantity = parse_signed(&s[..split])?;
+    Some((quantity, s[split..].trim().to_uppercase()))
+}
+
+/// Day-first dates as Thai brokers write them, with Buddhist-era years converted
+fn parse_thai_date(date: &str, time: Option<&str>) -> Option<DateTime<Utc>> {
+    let date = date.trim();
+    let day = ["%d/%m/%Y", "%d-%m-%Y", "%Y-%m-%d"].iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())?;
+    let day = if day.year() > 2400 { day.with_year(day.year() - BUDDHIST_ERA_OFFSET)? } else { day };
+    let time = time
+        .and_then(|t| ["%H:%M:%S", "%H:%M"].iter().find_map(|f| chrono::NaiveTime::parse_from_str(t.trim(), f).ok()))
+        .unwrap_or_default();
+    // Statements are in Bangkok time
+    Some((NaiveDateTime::new(day, time) - chrono::Duration::hours(7)).and_utc())
+}
+
+fn parse_side(value: &str) -> Option<TradeAction> {
+    match value.trim().to_lowercase().as_str() {
+        "b" | "buy" | "bot" | "ซื้อ" => Some(TradeAction::Buy),
+        "s" | "sell" | "sld" | "ขาย" => Some(TradeAction::Sell),
+        _ => None,
+    }
+}
+
+fn base_request(format: BrokerFormat, row: usize, account_id: &Option<String>) -> CreateTransactionRequest {
+    CreateTransactionRequest {
+        asset_type: AssetType::Stock,
+        symbol: String::new(),
+        symbol_name: None,
+        action: TradeAction::Buy,
+        quantity: 0.0,
+        price: 0.0,
+        fees: 0.0,
+        network_fee: 0.0,
+        timestamp: Utc::now(),
+        market: None,
+        currency: None,
+        notes: Some(format!("{} import row {}", format.as_str(), row)),
+        account_id: account_id.clone(),
+        tags: vec!["imported".to_string(), format.as_str().to_string()],
+        leverage: None,
+        initial_margin: None,
+        unit: None,
+        custom_fields: Default::default(),
+    }
+}
+
+/// Collects the problems of one row so they are all reported together
+#[derive(Default)]
+struct RowCheck {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl RowCheck {
+    fn require<T>(&mut self, value: Option<T>, message: impl FnOnce() -> String) -> Option<T> {
+        if value.is_none() {
+            self.errors.push(message());
+        }
+        value
+    }
+
+    fn number(&mut self, columns: &Columns, row: &[String], aliases: &[&str]) -> Option<f64> {
+        match columns.get(row, aliases) {
+            None => {
+                self.errors.push(format!("missing {}", aliases[0]));
+                None
+            }
+            Some(raw) => {
+                let value = parse_signed(raw);
+                self.require(value, || format!("invalid {} '{}'", aliases[0], raw))
+            }
+        }
+    }
+}
+
+fn map_streaming(columns: &Columns, record: &[String], check: &mut RowCheck, tx: &mut CreateTransactionRequest) {
+    tx.asset_type = AssetType::Stock;
+    tx.market = Some(Market::Set);
+    tx.currency = Some("THB".to_string());
+
+    let symbol = columns.get(record, &["symbol", "stock"]);
+    if let Some(symbol) = check.require(symbol, || "missing symbol".to_string()) {
+        tx.symbol = symbol.to_uppercase();
+    }
+    let side = columns.get(record, &["b/s", "side"]);
+    if let Some(side) = check.require(side, || "missing side".to_string()) {
+        if let Some(action) = check.require(parse_side(side), || format!("unknown side '{}'", side)) {
+            tx.action = action;
+        }
+    }
+    let date = columns.get(record, &["date", "trade date"]);
+    if let Some(date) = check.require(date, || "missing date".to_string()) {
+        let timestamp = parse_thai_date(date, columns.get(record, &["time"]));
+        if let Some(timestamp) = check.require(timestamp, || format!("invalid date '{}'", date)) {
+            tx.timestamp = timestamp;
+        }
+    }
+    tx.quantity = check.number(columns, record, &["volume", "qty"]).unwrap_or_default();
+    tx.price = check.number(columns, record, &["price", "avg price"]).unwrap_or_default();
+    match columns.sum(record, &["comm.", "commission", "vat", "trading fee", "clearing fee", "fee"]) {
+        Ok(fees) => tx.fees = fees,
+        Err(e) => check.errors.push(e),
+    }
+}
+
+fn binance_base_quote(market: &str) -> Option<(String, String)> {
+    let market = market.trim().to_uppercase().replace(['/', '-', '_'], "");
+    BINANCE_QUOTES.iter()
+        .filter(|quote| market.len() > quote.len() && market.ends_with(*quote))
+        .max_by_key(|quote| quote.len())
+        .map(|quote| (market[..market.len() - quote.len()].to_string(), quote.to_string()))
+}
+
+fn map_binance(columns: &Columns, record: &[String], check: &mut RowCheck, tx: &mut CreateTransactionRequest) {
+    tx.asset_type = AssetType::Crypto;
+    tx.market = Some(Market::Binance);
+
+    let date = columns.get(record, &["date(utc)"]);
+    if let Some(date) = check.require(date, || "missing date".to_string()) {
+        if let Some(timestamp) = check.require(parse_timestamp(date), || format!("invalid date '{}'", date)) {
+            tx.timestamp = timestamp;
+        }
+    }
+    let side = columns.get(record, &["side", "type"]);
+    if let Some(side) = check.require(side, || "missing side".to_string()) {
+        if let Some(action) = check.require(parse_side(side), || format!("unknown side '{}'", side)) {
+            tx.action = action;
+        }
+    }
+    tx.price = check.number(columns, record, &["price"]).unwrap_or_default();
+
+    // Current export: "Executed" and "Amount" carry their asset; older ones name the market
+    let (base, quote, quantity) = match columns.get(record, &["executed"]) {
+        Some(executed) => {
+            let quantity = split_amount(executed);
+            let quote = columns.get(record, &["amount"]).and_then(split_amount).map(|(_, asset)| asset);
+            match (quantity, quote) {
+                (Some((quantity, base)), Some(quote)) => (base, quote, quantity),
+                _ => {
+                    check.errors.push(format!("invalid executed amount '{}'", executed));
+                    return;
+                }
+            }
+        }
+        None => {
+            let market = columns.get(record, &["pair", "market"]).unwrap_or_default();
+            let Some((base, quote)) = binance_base_quote(market) else {
+                check.errors.push(format!("unknown trading pair '{}'", market));
+                return;
+            };
+            let quantity = check.number(columns, record, &["amount"]).unwrap_or_default();
+            (base, quote, quantity)
+        }
+    };
+    tx.symbol = base.clone();
+    tx.quantity = quantity;
+    tx.currency = Some(if is_usd_stablecoin(&quote) { "USD".to_string() } else { quote.clone() });
+
+    let fee = match (columns.get(record, &["fee"]), columns.get(record, &["fee coin"])) {
+        (Some(fee), Some(coin)) => parse_signed(fee).map(|f| (f.abs(), coin.to_uppercase())),
+        (Some(fee), None) => split_amount(fee).map(|(f, coin)| (f.abs(), coin)),
+        (None, _) => Some((0.0, quote.clone())),
+    };
+    match fee {
+        Some((fee, coin)) if coin == quote => tx.fees = fee,
+        Some((fee, coin)) if coin == base => tx.fees = fee * tx.price,
+        Some((fee, coin)) if fee > 0.0 => {
+            check.warnings.push(format!("fee of {} {} is not in {} or {} and was left out", fee, coin, base, quote));
+            tx.notes = Some(format!("{}; fee {} {}", tx.notes.clone().unwrap_or_default(), fee, coin));
+        }
+        Some(_) => {}
+        None => check.errors.push("invalid fee".to_string()),
+    }
+}
+
+fn map_interactive_brokers(columns: &Columns, record: &[String], check: &mut RowCheck, tx: &mut CreateTransactionRequest) {
+    tx.asset_type = AssetType::ForeignStock;
+
+    let category = columns.get(record, &["asset category", "assetclass"]).unwrap_or("Stocks");
+    if !matches!(category.to_lowercase().as_str(), "stocks" | "stk") {
+        check.errors.push(format!("asset category '{}' is not supported, only stocks", category));
+    }
+    let symbol = columns.get(record, &["symbol"]);
+    if let Some(symbol) = check.require(symbol, || "missing symbol".to_string()) {
+        tx.symbol = symbol.to_uppercase();
+    }
+    let currency = columns.get(record, &["currency", "currencyprimary"]);
+    if let Some(currency) = check.require(currency, || "missing currency".to_string()) {
+        tx.currency = Some(currency.to_uppercase());
+    }
+
+    let date = columns.get(record, &["date/time", "datetime", "tradedate"]);
+    if let Some(date) = check.require(date, || "missing date".to_string()) {
+        // "2024-01-05, 10:30:00" in activity statements, "20240105" or "20240105;103000" in Flex
+        let cleaned = date.replace(", ", " ");
+        let timestamp = parse_timestamp(&cleaned)
+            .or_else(|| NaiveDateTime::parse_from_str(&cleaned, "%Y%m%d;%H%M%S").ok().map(|dt| dt.and_utc()))
+            .or_else(|| NaiveDate::parse_from_str(&cleaned, "%Y%m%d").ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc()));
+        if let Some(timestamp) = check.require(timestamp, || format!("invalid date '{}'", date)) {
+            tx.timestamp = timestamp;
+        }
+    }
+
+    let quantity = check.number(columns, record, &["quantity"]).unwrap_or_default();
+    let side = columns.get(record, &["buy/sell"]).and_then(parse_side);
+    tx.action = side.unwrap_or(if quantity < 0.0 { TradeAction::Sell } else { TradeAction::Buy });
+    tx.quantity = quantity.abs();
+    tx.price = check.number(columns, record, &["t. price", "tradeprice"]).unwrap_or_default();
+    match columns.sum(record, &["comm/fee", "ibcommission"]) {
+        Ok(fees) => tx.fees = fees,
+        Err(e) => check.errors.push(e),
+    }
+}
+
+/// Whether an activity-statement record is a trade line (not a subtotal or another section)
+fn is_ibkr_trade_line(columns: &Columns, record: &[String]) -> bool {
+    if columns.headers.first().map(String::as_str) != Some("trades") {
+        return true;
+    }
+    let cell = |i: usize| record.get(i).map(|s| s.trim().to_lowercase()).unwrap_or_default();
+    cell(0) == "trades"
+        && cell(1) == "data"
+        && columns.get(record, &["datadiscriminator"]).is_none_or(|d| d.eq_ignore_ascii_case("order"))
+}
+
+/// Parse a statement into rows, each with the transaction it maps to or the reasons it cannot
+pub fn parse_statement(
+    csv: &str,
+    format: Option<BrokerFormat>,
+    account_id: &Option<String>,
+    max_rows: usize,
+) -> Result<(BrokerFormat, Vec<StatementRow>), AppError> {
+    let records = parse_csv(csv);
+    let (header_index, detected) = records.iter()
+        .take(MAX_PREAMBLE_ROWS)
+        .enumerate()
+        .find_map(|(i, record)| detect_header(record).map(|f| (i, f)))
+        .ok_or_else(|| AppError::BadRequest(
+            "Unrecognized statement: expected a Streaming, Binance or Interactive Brokers trade CSV".to_string(),
+        ))?;
+    if let Some(requested) = format.filter(|f| *f != detected) {
+        return Err(AppError::BadRequest(format!(
+            "CSV looks like a {} statement, not {}",
+            detected.as_str(),
+            requested.as_str()
+        )));
+    }
+    let columns = Columns::new(&records[header_index]);
+
+    let mut rows = Vec::new();
+    for (i, record) in records.iter().enumerate().skip(header_index + 1) {
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        if detected == BrokerFormat::InteractiveBrokers && !is_ibkr_trade_line(&columns, record) {
+            continue;
+        }
+        if rows.len() >= max_rows {
+            return Err(AppError::BadRequest(format!(
+                "Statement exceeds limit ({} rows), split it into smaller files",
+                max_rows
+            )));
+        }
+
+        let row = i + 1;
+        let mut check = RowCheck::default();
+        let mut tx = base_request(detected, row, account_id);
+        match detected {
+            BrokerFormat::Streaming => map_streaming(&columns, record, &mut check, &mut tx),
+            BrokerFormat::Binance => map_binance(&columns, record, &mut check, &mut tx),
+            BrokerFormat::InteractiveBrokers => map_interactive_brokers(&columns, record, &mut check, &mut tx),
+        }
+        if check.errors.is_empty() {
+            if tx.quantity <= 0.0 {
+                check.errors.push("quantity must be greater than 0".to_string());
+            }
+            if tx.price < 0.0 {
+                check.errors.push("price cannot be negative".to_string());
+            }
+            for violation in check_transaction(&mut tx) {
+                if violation.blocking {
+                    check.errors.push(violation.message);
+                } else {
+                    check.warnings.push(violation.message);
+                }
+            }
+        }
+
+        rows.push(StatementRow {
+            row,
+            transaction: check.errors.is_empty().then_some(tx),
+            errors: check.errors,
+            warnings: check.warnings,
+        });
+    }
+    Ok((detected, rows))
+}
@@ -0,0 +1,67 @@
+//! Keeps the in-memory user, transaction and account caches in step with PocketBase. Every
+//! CACHE_REVALIDATE_SECONDS the records changed since the last pass are merged in (filtered on
+//! PocketBase's `updated` field) and records deleted there are dropped.
+
+use chrono::{DateTime, Duration, Utc};
+use crate::config::Config;
+use crate::services::{AuthService, PocketBaseClient};
+
+/// Each pass looks this far behind the previous one, for clock skew between the two servers
+const OVERLAP_SECONDS: i64 = 5;
+
+pub struct CacheRevalidator {
+    db: PocketBaseClient,
+    auth_service: AuthService,
+    interval_seconds: u64,
+}
+
+impl CacheRevalidator {
+    pub fn new(config: &Config, db: PocketBaseClient, auth_service: AuthService) -> Self {
+        Self { db, auth_service, interval_seconds: config.cache_revalidate_seconds }
+    }
+
+    pub fn start(self) {
+        if self.interval_seconds == 0 {
+            tracing::info!("♻️ Cache revalidation disabled (CACHE_REVALIDATE_SECONDS=0)");
+            return;
+        }
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(self.interval_seconds));
+            // The first tick fires at once; the caches were just loaded
+            interval.tick().await;
+            let mut since = Utc::now();
+            tracing::info!("♻️ Cache revalidation every {}s", self.interval_seconds);
+            loop {
+                interval.tick().await;
+                let started = Utc::now();
+                if self.revalidate(since - Duration::seconds(OVERLAP_SECONDS)).await {
+                    since = started;
+                }
+            }
+        });
+    }
+
+    /// One pass over all caches; false if any of them couldn't be checked, so the next pass
+    /// covers the same window again
+    async fn revalidate(&self, since: DateTime<Utc>) -> bool {
+        let results = [
+            ("users", self.auth_service.revalidate_users(since).await),
+            ("transactions", self.db.revalidate_transactions(since).await),
+            ("accounts", self.db.revalidate_accounts(since).await),
+        ];
+        let mut complete = true;
+        for (cache, result) in results {
+            match result {
+                Ok(changes) if changes.updated > 0 || changes.removed > 0 => tracing::info!(
+                    "♻️ Revalidated {} cache: {} updated, {} removed", cache, changes.updated, changes.removed
+                ),
+                Ok(_) => {}
+                Err(e) => {
+                    complete = false;
+                    tracing::warn!("⚠️ Could not revalidate {} cache: {}", cache, e);
+                }
+            }
+        }
+        complete
+    }
+}
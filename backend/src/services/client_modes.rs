@@ -0,0 +1,142 @@
+//! Power modes declared by clients (PUT /api/clients/mode). A tab in the background or a phone
+//! saving battery gets its WebSocket pushes batched, is told to poll less often and no longer
+//! heats the symbols it loads, so idle screens don't keep providers busy. Clients identify
+//! themselves with a random id, sent as the X-Client-Id header or the `client_id` parameter of
+//! the WebSocket URLs.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use crate::config::Config;
+use crate::error::AppError;
+
+pub const CLIENT_ID_HEADER: &str = "x-client-id";
+/// A declaration lapses back to foreground unless the client repeats it within this time
+pub const MODE_TTL_SECONDS: i64 = 900;
+/// Declared clients kept at most, so random ids can't grow the map without bound
+const MAX_CLIENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClientMode {
+    /// Visible and in use: live pushes
+    #[default]
+    Foreground,
+    /// Open but hidden (background tab, minimised app)
+    Background,
+    /// A phone with battery saver on, or a backgrounded mobile app
+    MobileLowPower,
+}
+
+impl ClientMode {
+    /// How long pushes are held and batched; None sends each one as it happens
+    pub fn push_interval(self) -> Option<Duration> {
+        match self {
+            ClientMode::Foreground => None,
+            ClientMode::Background => Some(Duration::from_secs(60)),
+            ClientMode::MobileLowPower => Some(Duration::from_secs(300)),
+        }
+    }
+
+    /// Whether requests from the client count as someone looking at a symbol
+    pub fn is_active(self) -> bool {
+        self == ClientMode::Foreground
+    }
+}
+
+/// Suggested intervals for a client that polls instead of (or besides) streaming
+#[derive(Debug, Clone, Serialize)]
+pub struct PollingHints {
+    pub portfolio_seconds: u64,
+    pub prices_seconds: u64,
+    /// How often the WebSocket streams deliver batched updates; 0 when they push live
+    pub push_batch_seconds: u64,
+}
+
+struct DeclaredMode {
+    user_id: String,
+    mode: ClientMode,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct ClientModes {
+    clients: Arc<RwLock<HashMap<String, DeclaredMode>>>,
+    price_refresh_seconds: u64,
+}
+
+impl ClientModes {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            clients: Arc::new(RwLock::new(HashMap::new())),
+            price_refresh_seconds: config.price_refresh_interval_seconds.max(10),
+        }
+    }
+
+    /// Ids are client-generated: 8-64 letters, digits, '-' or '_'
+    pub fn valid_client_id(client_id: &str) -> bool {
+        (8..=64).contains(&client_id.len())
+            && client_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+
+    /// Record a client's mode; returns when the declaration lapses. A client id still held by
+    /// another user is refused, so one account can't quiet another's streams.
+    pub async fn declare(&self, user_id: &str, client_id: &str, mode: ClientMode) -> Result<DateTime<Utc>, AppError> {
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::seconds(MODE_TTL_SECONDS);
+        let mut clients = self.clients.write().await;
+        clients.retain(|_, declared| declared.expires_at > now);
+        match clients.get(client_id) {
+            Some(declared) if declared.user_id != user_id => {
+                return Err(AppError::Conflict(format!("Client id {} belongs to another session", client_id)));
+            }
+            None if clients.len() >= MAX_CLIENTS => {
+                tracing::warn!("⚠️ {} clients have declared a mode, ignoring {}", clients.len(), client_id);
+                return Ok(expires_at);
+            }
+            _ => {}
+        }
+        clients.insert(client_id.to_string(), DeclaredMode {
+            user_id: user_id.to_string(),
+            mode,
+            expires_at,
+        });
+        Ok(expires_at)
+    }
+
+    /// Current mode of a client; undeclared and lapsed clients are foreground
+    pub async fn mode(&self, client_id: Option<&str>) -> ClientMode {
+        let Some(client_id) = client_id else { return ClientMode::Foreground };
+        self.clients.read().await.get(client_id)
+            .filter(|declared| declared.expires_at > Utc::now())
+            .map(|declared| declared.mode)
+            .unwrap_or_default()
+    }
+
+    /// Mode of the client named by the X-Client-Id header
+    pub async fn mode_for(&self, headers: &HeaderMap) -> ClientMode {
+        self.mode(headers.get(CLIENT_ID_HEADER).and_then(|h| h.to_str().ok())).await
+    }
+
+    pub fn polling_hints(&self, mode: ClientMode) -> PollingHints {
+        // Polling prices faster than the background refresher only re-reads the cache
+        let prices_seconds = match mode {
+            ClientMode::Foreground => self.price_refresh_seconds,
+            ClientMode::Background => self.price_refresh_seconds.max(300),
+            ClientMode::MobileLowPower => self.price_refresh_seconds.max(900),
+        };
+        PollingHints {
+            portfolio_seconds: match mode {
+                ClientMode::Foreground => 60,
+                ClientMode::Background => 300,
+                ClientMode::MobileLowPower => 900,
+            },
+            prices_seconds,
+            push_batch_seconds: mode.push_interval().map(|d| d.as_secs()).unwrap_or(0),
+        }
+    }
+}
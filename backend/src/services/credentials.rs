@@ -0,0 +1,175 @@
+//! Users' exchange API keys, sealed with AES-256-GCM (CREDENTIALS_ENCRYPTION_KEY) before they
+//! are written to PocketBase. The sealed value is bound to its owner and exchange, so copying
+//! it onto another record makes it undecryptable.
+
+use std::sync::Arc;
+use chrono::Utc;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::{ExchangeCredentials, ExchangeKey, Market, EXCHANGE_KEYS_COLLECTION};
+use crate::services::pocketbase::PocketBaseClient;
+
+/// Prefix of sealed values, so the format can change without guessing
+const SEALED_VERSION: &str = "v1:";
+
+#[derive(Clone)]
+pub struct CredentialCipher {
+    key: Arc<LessSafeKey>,
+    rng: SystemRandom,
+}
+
+impl CredentialCipher {
+    /// Cipher for CREDENTIALS_ENCRYPTION_KEY, or None when it is unset or not 32 bytes of hex
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let hex_key = config.credentials_encryption_key.as_deref()?;
+        let key = hex::decode(hex_key.trim()).ok()
+            .and_then(|bytes| UnboundKey::new(&AES_256_GCM, &bytes).ok());
+        let Some(key) = key else {
+            tracing::warn!("⚠️ Ignoring CREDENTIALS_ENCRYPTION_KEY: expected 64 hex characters");
+            return None;
+        };
+        Some(Self { key: Arc::new(LessSafeKey::new(key)), rng: SystemRandom::new() })
+    }
+
+    /// "v1:" + hex(nonce || ciphertext || tag)
+    pub fn seal(&self, plaintext: &[u8], context: &str) -> Result<String, AppError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng.fill(&mut nonce)
+            .map_err(|_| AppError::InternalError("Could not generate a nonce".to_string()))?;
+        let mut sealed = plaintext.to_vec();
+        self.key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(context.as_bytes()), &mut sealed)
+            .map_err(|_| AppError::InternalError("Could not encrypt credentials".to_string()))?;
+        Ok(format!("{}{}{}", SEALED_VERSION, hex::encode(nonce), hex::encode(sealed)))
+    }
+
+    pub fn open(&self, sealed: &str, context: &str) -> Result<Vec<u8>, AppError> {
+        let unreadable = || AppError::InternalError("Stored credentials can't be decrypted; was the encryption key changed?".to_string());
+        let bytes = sealed.strip_prefix(SEALED_VERSION)
+            .and_then(|hex_value| hex::decode(hex_value).ok())
+            .filter(|bytes| bytes.len() > NONCE_LEN)
+            .ok_or_else(unreadable)?;
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| unreadable())?;
+        let mut buffer = ciphertext.to_vec();
+        let plaintext = self.key.open_in_place(nonce, Aad::from(context.as_bytes()), &mut buffer)
+            .map_err(|_| unreadable())?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+/// Associated data a key is sealed with
+fn seal_context(user_id: &str, exchange: &Market) -> String {
+    format!("{}:{}", user_id, exchange)
+}
+
+/// The exchange_keys collection, decrypting keys only when they are used
+#[derive(Clone)]
+pub struct CredentialStore {
+    db: PocketBaseClient,
+    cipher: Option<CredentialCipher>,
+}
+
+impl CredentialStore {
+    pub fn new(db: PocketBaseClient, config: &Config) -> Self {
+        Self { db, cipher: CredentialCipher::from_config(config) }
+    }
+
+    fn cipher(&self) -> Result<&CredentialCipher, AppError> {
+        self.cipher.as_ref()
+            .ok_or_else(|| AppError::Config("Exchange keys need CREDENTIALS_ENCRYPTION_KEY to be set".to_string()))
+    }
+
+    pub async fn list(&self, user_id: &str) -> Result<Vec<ExchangeKey>, AppError> {
+        self.db.list_records(EXCHANGE_KEYS_COLLECTION, Some(format!("user_id='{}'", user_id)), "-created").await
+    }
+
+    /// A key of the user's; someone else's is reported as not found
+    pub async fn get(&self, user_id: &str, id: &str) -> Result<ExchangeKey, AppError> {
+        let key: ExchangeKey = self.db.get_record(EXCHANGE_KEYS_COLLECTION, id).await?;
+        if key.user_id != user_id {
+            return Err(AppError::NotFound(format!("Exchange key {} not found", id)));
+        }
+        Ok(key)
+    }
+
+    pub async fn add(&self, user_id: &str, exchange: &Market, label: &str, credentials: &ExchangeCredentials) -> Result<ExchangeKey, AppError> {
+        let plaintext = serde_json::to_vec(credentials)
+            .map_err(|e| AppError::InternalError(format!("Could not encode credentials: {}", e)))?;
+        let secret = self.cipher()?.seal(&plaintext, &seal_context(user_id, exchange))?;
+        let api_key = credentials.api_key.trim();
+        let hint: String = api_key.chars().skip(api_key.chars().count().saturating_sub(4)).collect();
+        let body = serde_json::json!({
+            "user_id": user_id,
+            "exchange": exchange,
+            "label": label,
+            "api_key_hint": hint,
+            "secret": secret,
+            "last_synced_at": Utc::now(),
+        });
+        self.db.create_record(EXCHANGE_KEYS_COLLECTION, &body).await
+    }
+
+    pub async fn remove(&self, user_id: &str, id: &str) -> Result<(), AppError> {
+        self.get(user_id, id).await?;
+        self.db.delete_record(EXCHANGE_KEYS_COLLECTION, id).await
+    }
+
+    pub fn credentials(&self, key: &ExchangeKey) -> Result<ExchangeCredentials, AppError> {
+        let plaintext = self.cipher()?.open(&key.secret, &seal_context(&key.user_id, &key.exchange))?;
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| AppError::InternalError(format!("Stored credentials are malformed: {}", e)))
+    }
+
+    /// Remember the outcome of the last call made with a key (best effort)
+    pub async fn record_use(&self, key: &ExchangeKey, error: Option<&str>) {
+        let body = match error {
+            None => serde_json::json!({ "last_synced_at": Utc::now(), "last_error": "" }),
+            Some(error) => serde_json::json!({ "last_error": error }),
+        };
+        if let Err(e) = self.db.update_record::<serde_json::Value>(EXCHANGE_KEYS_COLLECTION, &key.id, &body).await {
+            tracing::warn!("⚠️ Failed to update exchange key {}: {}", key.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher() -> CredentialCipher {
+        let key = UnboundKey::new(&AES_256_GCM, &[7u8; 32]).expect("32-byte key");
+        CredentialCipher { key: Arc::new(LessSafeKey::new(key)), rng: SystemRandom::new() }
+    }
+
+    #[test]
+    fn sealed_credentials_open_only_for_their_owner_and_exchange() {
+        let cipher = cipher();
+        let context = seal_context("user1", &Market::Binance);
+        let sealed = cipher.seal(b"{\"api_key\":\"k\",\"api_secret\":\"s\"}", &context).unwrap();
+
+        assert!(sealed.starts_with(SEALED_VERSION));
+        assert_eq!(cipher.open(&sealed, &context).unwrap(), b"{\"api_key\":\"k\",\"api_secret\":\"s\"}");
+        assert!(cipher.open(&sealed, &seal_context("user2", &Market::Binance)).is_err());
+        assert!(cipher.open(&sealed, &seal_context("user1", &Market::Bitkub)).is_err());
+    }
+
+    #[test]
+    fn tampered_or_malformed_values_do_not_open() {
+        let cipher = cipher();
+        let context = seal_context("user1", &Market::Binance);
+        let sealed = cipher.seal(b"secret", &context).unwrap();
+
+        // Flip one bit of the ciphertext (past the prefix and nonce)
+        let mut bytes = hex::decode(&sealed[SEALED_VERSION.len()..]).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        let tampered = format!("{}{}", SEALED_VERSION, hex::encode(bytes));
+        assert!(cipher.open(&tampered, &context).is_err());
+
+        assert!(cipher.open(&sealed[SEALED_VERSION.len()..], &context).is_err());
+        assert!(cipher.open("v1:zz", &context).is_err());
+        assert!(cipher.open("v1:00", &context).is_err());
+    }
+}
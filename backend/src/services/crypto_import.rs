@@ -0,0 +1,596 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::models::{AssetType, CreateTransactionRequest, TradeAction};
+
+const FIAT_CURRENCIES: &[&str] = &[
+    "USD", "EUR", "GBP", "THB", "JPY", "AUD", "CAD", "CHF", "SGD", "HKD", "CNY", "KRW", "INR", "NZD",
+];
+
+/// Stablecoins pegged to USD: held as crypto, but their amount is a usable USD valuation
+const USD_STABLECOINS: &[&str] = &["USDT", "USDC", "BUSD", "DAI", "TUSD", "FDUSD", "USDP"];
+
+pub fn is_fiat(currency: &str) -> bool {
+    FIAT_CURRENCIES.contains(&currency.to_uppercase().as_str())
+}
+
+pub(crate) fn is_usd_stablecoin(currency: &str) -> bool {
+    USD_STABLECOINS.contains(&currency.to_uppercase().as_str())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Koinly,
+    CoinTracking,
+}
+
+impl ImportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportFormat::Koinly => "koinly",
+            ImportFormat::CoinTracking => "cointracking",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Amount {
+    pub quantity: f64,
+    pub currency: String,
+}
+
+/// What a row means for holdings, independent of the source tool's vocabulary
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportKind {
+    /// Buy, sell or crypto-to-crypto exchange
+    Trade,
+    /// Staking, mining, airdrops, interest... (tag describes which)
+    Income(String),
+    /// Funds arriving from outside the imported wallets
+    TransferIn,
+    /// Funds leaving to outside the imported wallets
+    TransferOut,
+    /// Move between the user's own wallets: only the fee changes holdings
+    InternalTransfer,
+    /// Standalone fee
+    Fee,
+    /// Spending crypto on goods/services (a disposal)
+    Spend,
+    /// Lost, stolen or gifted away (removed without proceeds)
+    Removal(String),
+}
+
+/// One source row normalized across Koinly and CoinTracking
+#[derive(Debug, Clone)]
+pub struct ImportRow {
+    pub row: usize,
+    pub timestamp: DateTime<Utc>,
+    pub kind: ImportKind,
+    pub sent: Option<Amount>,
+    pub received: Option<Amount>,
+    pub fee: Option<Amount>,
+    /// Fiat value of the row as reported by the source tool
+    pub value: Option<Amount>,
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedRow {
+    pub row: usize,
+    pub reason: String,
+}
+
+/// Incremental RFC 4180 reader (quoted fields, escaped quotes, newlines inside quotes)
+/// so uploads can be parsed chunk by chunk as they stream in
+#[derive(Default)]
+pub struct CsvReader {
+    row: Vec<String>,
+    field: String,
+    in_quotes: bool,
+    // Saw a quote inside a quoted field: either an escaped quote or the closing one
+    quote_pending: bool,
+    started: bool,
+}
+
+impl CsvReader {
+    pub fn push(&mut self, chunk: &str, out: &mut Vec<Vec<String>>) {
+        let chunk = if self.started { chunk } else { chunk.trim_start_matches('\u{feff}') };
+        self.started = true;
+
+        for c in chunk.chars() {
+            if self.quote_pending {
+                self.quote_pending = false;
+                if c == '"' {
+                    self.field.push('"');
+                    continue;
+                }
+                self.in_quotes = false;
+            }
+            if self.in_quotes {
+                match c {
+                    '"' => self.quote_pending = true,
+                    _ => self.field.push(c),
+                }
+                continue;
+            }
+            match c {
+                '"' => self.in_quotes = true,
+                ',' => self.row.push(std::mem::take(&mut self.field)),
+                '\r' => {}
+                '\n' => self.end_row(out),
+                _ => self.field.push(c),
+            }
+        }
+    }
+
+    fn end_row(&mut self, out: &mut Vec<Vec<String>>) {
+        self.row.push(std::mem::take(&mut self.field));
+        let row = std::mem::take(&mut self.row);
+        if row.iter().any(|f| !f.trim().is_empty()) {
+            out.push(row);
+        }
+    }
+
+    pub fn finish(mut self, out: &mut Vec<Vec<String>>) {
+        if !self.field.is_empty() || !self.row.is_empty() {
+            self.end_row(out);
+        }
+    }
+}
+
+pub fn parse_csv(input: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut reader = CsvReader::default();
+    reader.push(input, &mut rows);
+    reader.finish(&mut rows);
+    rows
+}
+
+fn normalize_header(h: &str) -> String {
+    h.trim().trim_matches('"').to_lowercase()
+}
+
+/// Format whose header row this is, if any
+fn detect_header(row: &[String]) -> Option<ImportFormat> {
+    let headers: Vec<String> = row.iter().map(|h| normalize_header(h)).collect();
+    let has = |name: &str| headers.iter().any(|h| h == name);
+    if has("sent amount") && has("received amount") {
+        return Some(ImportFormat::Koinly);
+    }
+    if has("type") && headers.iter().any(|h| h.starts_with("buy")) && headers.iter().any(|h| h.starts_with("sell")) {
+        return Some(ImportFormat::CoinTracking);
+    }
+    None
+}
+
+fn parse_number(s: &str) -> Option<f64> {
+    let cleaned: String = s.trim().chars().filter(|c| *c != ',' && *c != ' ').collect();
+    if cleaned.is_empty() || cleaned == "-" {
+        return None;
+    }
+    cleaned.parse::<f64>().ok().map(f64::abs)
+}
+
+pub(crate) fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim().trim_end_matches(" UTC").trim_end_matches('Z').trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    const FORMATS: &[&str] = &[
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%d.%m.%Y %H:%M:%S",
+        "%d.%m.%Y %H:%M",
+        "%m/%d/%Y %H:%M:%S",
+        "%m/%d/%Y %H:%M",
+    ];
+    for format in FORMATS {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, format) {
+            return Some(dt.and_utc());
+        }
+    }
+    for format in ["%Y-%m-%d", "%d.%m.%Y"] {
+        if let Ok(d) = NaiveDate::parse_from_str(s, format) {
+            return d.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc());
+        }
+    }
+    None
+}
+
+fn amount(quantity: Option<f64>, currency: Option<&str>) -> Option<Amount> {
+    let quantity = quantity.filter(|q| *q > 0.0)?;
+    let currency = currency.map(|c| c.trim().to_uppercase()).filter(|c| !c.is_empty())?;
+    Some(Amount { quantity, currency })
+}
+
+/// Column lookup by (normalized) header name
+struct Columns {
+    headers: Vec<String>,
+}
+
+impl Columns {
+    fn new(row: &[String]) -> Self {
+        Self { headers: row.iter().map(|h| normalize_header(h)).collect() }
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.headers.iter().position(|h| h == name)
+    }
+
+    fn index_starting(&self, prefix: &str) -> Option<usize> {
+        self.headers.iter().position(|h| h.starts_with(prefix))
+    }
+
+    fn get<'a>(&self, row: &'a [String], name: &str) -> Option<&'a str> {
+        self.index(name).and_then(|i| row.get(i)).map(|s| s.trim()).filter(|s| !s.is_empty())
+    }
+}
+
+// Exports may start with a few title lines before the header
+const MAX_PREAMBLE_ROWS: usize = 20;
+
+/// Turns CSV records into normalized rows, detecting the format from the header row
+pub struct ExportParser {
+    requested: Option<ImportFormat>,
+    header: Option<(ImportFormat, Columns)>,
+    records_seen: usize,
+    rows: Vec<ImportRow>,
+    skipped: Vec<SkippedRow>,
+}
+
+impl ExportParser {
+    pub fn new(format: Option<ImportFormat>) -> Self {
+        Self { requested: format, header: None, records_seen: 0, rows: Vec::new(), skipped: Vec::new() }
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn push_record(&mut self, record: &[String]) -> Result<(), AppError> {
+        self.records_seen += 1;
+        // 1-based record number in the file, counting the header
+        let line = self.records_seen;
+
+        let Some((format, columns)) = &self.header else {
+            if let Some(detected) = detect_header(record) {
+                if let Some(requested) = self.requested.filter(|f| *f != detected) {
+                    return Err(AppError::BadRequest(format!(
+                        "CSV looks like a {} export, not {}",
+                        detected.as_str(),
+                        requested.as_str()
+                    )));
+                }
+                self.header = Some((detected, Columns::new(record)));
+            } else if self.records_seen >= MAX_PREAMBLE_ROWS {
+                return Err(unrecognized_export());
+            }
+            return Ok(());
+        };
+
+        let result = match format {
+            ImportFormat::Koinly => parse_koinly_row(columns, record, line),
+            ImportFormat::CoinTracking => parse_cointracking_row(columns, record, line),
+        };
+        match result {
+            Ok(Some(r)) => self.rows.push(r),
+            Ok(None) => {}
+            Err(reason) => self.skipped.push(SkippedRow { row: line, reason }),
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(ImportFormat, Vec<ImportRow>, Vec<SkippedRow>), AppError> {
+        let (format, _) = self.header.ok_or_else(unrecognized_export)?;
+        Ok((format, self.rows, self.skipped))
+    }
+}
+
+fn unrecognized_export() -> AppError {
+    AppError::BadRequest("Unrecognized export: expected a Koinly or CoinTracking CSV".to_string())
+}
+
+/// Parse a Koinly or CoinTracking CSV export into normalized rows
+pub fn parse_export(
+    csv: &str,
+    format: Option<ImportFormat>,
+) -> Result<(ImportFormat, Vec<ImportRow>, Vec<SkippedRow>), AppError> {
+    let mut parser = ExportParser::new(format);
+    for record in parse_csv(csv) {
+        parser.push_record(&record)?;
+    }
+    parser.finish()
+}
+
+fn koinly_label_kind(label: &str) -> Option<ImportKind> {
+    match label {
+        "staking" | "stake" => Some(ImportKind::Income("staking".to_string())),
+        "reward" | "mining" | "airdrop" | "fork" | "lending_interest" | "income" | "other_income" | "cashback" => {
+            Some(ImportKind::Income(label.to_string()))
+        }
+        "gift" | "lost" | "donation" => Some(ImportKind::Removal(label.to_string())),
+        "cost" | "margin_fee" | "fee" => Some(ImportKind::Fee),
+        _ => None,
+    }
+}
+
+fn parse_koinly_row(columns: &Columns, row: &[String], line: usize) -> Result<Option<ImportRow>, String> {
+    let date = columns.get(row, "date").ok_or("missing date")?;
+    let timestamp = parse_timestamp(date).ok_or_else(|| format!("unparseable date '{}'", date))?;
+
+    let sent = amount(
+        columns.get(row, "sent amount").and_then(parse_number),
+        columns.get(row, "sent currency"),
+    );
+    let received = amount(
+        columns.get(row, "received amount").and_then(parse_number),
+        columns.get(row, "received currency"),
+    );
+    let fee = amount(
+        columns.get(row, "fee amount").and_then(parse_number),
+        columns.get(row, "fee currency"),
+    );
+
+    // Full export: "Net Value (USD)"; universal format: "Net Worth Amount" + "Net Worth Currency"
+    let value = match columns.headers.iter().position(|h| h.starts_with("net value")) {
+        Some(i) => {
+            let currency = columns.headers[i]
+                .split_once('(')
+                .map(|(_, c)| c.trim_end_matches(')').to_string());
+            amount(row.get(i).and_then(|s| parse_number(s)), currency.as_deref())
+        }
+        None => amount(
+            columns.get(row, "net worth amount").and_then(parse_number),
+            columns.get(row, "net worth currency"),
+        ),
+    };
+
+    let label = columns.get(row, "label").unwrap_or_default().to_lowercase().replace(' ', "_");
+    let tx_type = columns.get(row, "type").unwrap_or_default().to_lowercase();
+    let kind = match (tx_type.as_str(), &sent, &received) {
+        ("transfer", _, _) => ImportKind::InternalTransfer,
+        _ if koinly_label_kind(&label).is_some() => koinly_label_kind(&label).unwrap_or(ImportKind::Trade),
+        ("buy" | "sell" | "exchange", _, _) => ImportKind::Trade,
+        ("crypto_deposit" | "fiat_deposit", _, _) => ImportKind::TransferIn,
+        ("crypto_withdrawal" | "fiat_withdrawal", _, _) => ImportKind::TransferOut,
+        // Universal format has no type column: infer from which sides are filled
+        (_, Some(_), Some(_)) => ImportKind::Trade,
+        (_, None, Some(_)) => ImportKind::TransferIn,
+        (_, Some(_), None) => ImportKind::TransferOut,
+        (_, None, None) if fee.is_some() => ImportKind::Fee,
+        _ => return Err("row has no sent, received or fee amount".to_string()),
+    };
+
+    let reference = columns.get(row, "txhash")
+        .or_else(|| columns.get(row, "description"))
+        .map(|s| s.to_string());
+
+    Ok(Some(ImportRow { row: line, timestamp, kind, sent, received, fee, value, reference }))
+}
+
+/// "reward / bonus" -> "reward_bonus"
+fn slug(s: &str) -> String {
+    s.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn parse_cointracking_row(columns: &Columns, row: &[String], line: usize) -> Result<Option<ImportRow>, String> {
+    // Currency columns are all named "Cur." so they are read by position next to their amount
+    let pair = |prefix: &str| -> Option<Amount> {
+        let i = columns.index_starting(prefix)?;
+        amount(
+            row.get(i).and_then(|s| parse_number(s)),
+            row.get(i + 1).map(|s| s.as_str()),
+        )
+    };
+    let received = pair("buy");
+    let sent = pair("sell");
+    let fee = pair("fee");
+
+    let date = columns.get(row, "date").ok_or("missing date")?;
+    let timestamp = parse_timestamp(date).ok_or_else(|| format!("unparseable date '{}'", date))?;
+
+    let tx_type = columns.get(row, "type").unwrap_or_default().to_lowercase();
+    let kind = match tx_type.as_str() {
+        "trade" | "margin trade" | "derivatives / futures" => ImportKind::Trade,
+        "staking" | "staking income" => ImportKind::Income("staking".to_string()),
+        "income" | "income (non taxable)" | "mining" | "reward / bonus" | "interest income"
+        | "lending income" | "airdrop" | "airdrop (non taxable)" | "gift / tip" | "masternode" => {
+            ImportKind::Income(slug(&tx_type))
+        }
+        "deposit" => ImportKind::TransferIn,
+        "withdrawal" => ImportKind::TransferOut,
+        "other fee" | "margin fee" | "borrowing fee" => ImportKind::Fee,
+        "spend" => ImportKind::Spend,
+        "lost" | "stolen" | "donation" | "gift" => ImportKind::Removal(tx_type.clone()),
+        "" => return Err("missing type".to_string()),
+        other => return Err(format!("unsupported CoinTracking type '{}'", other)),
+    };
+
+    let exchange = columns.get(row, "exchange");
+    let reference = columns.get(row, "tx-id")
+        .or_else(|| columns.get(row, "trade-id"))
+        .or_else(|| columns.get(row, "comment"))
+        .or(exchange)
+        .map(|s| s.to_string());
+
+    Ok(Some(ImportRow { row: line, timestamp, kind, sent, received, fee, value: None, reference }))
+}
+
+impl ImportRow {
+    /// Fiat valuation available without a price lookup
+    pub fn known_value(&self) -> Option<Amount> {
+        if let Some(value) = &self.value {
+            return Some(value.clone());
+        }
+        let sides = [&self.sent, &self.received];
+        if let Some(fiat) = sides.iter().filter_map(|s| s.as_ref()).find(|a| is_fiat(&a.currency)) {
+            return Some(fiat.clone());
+        }
+        sides.iter()
+            .filter_map(|s| s.as_ref())
+            .find(|a| is_usd_stablecoin(&a.currency))
+            .map(|a| Amount { quantity: a.quantity, currency: "USD".to_string() })
+    }
+
+    /// Crypto whose market price can value the row when the export carries no fiat value
+    pub fn valuation_symbol(&self) -> Option<&Amount> {
+        [&self.received, &self.sent, &self.fee]
+            .into_iter()
+            .filter_map(|s| s.as_ref())
+            .find(|a| !is_fiat(&a.currency))
+    }
+
+    /// Whether the row's fee is an on-chain network (gas) fee rather than a trading fee: fees
+    /// on deposits, withdrawals and moves between the user's wallets pay for the transfer
+    pub fn has_network_fee(&self) -> bool {
+        matches!(self.kind, ImportKind::TransferIn | ImportKind::TransferOut | ImportKind::InternalTransfer)
+    }
+
+    /// Whether the row creates priced legs (transfers and fees can go without a value)
+    pub fn needs_value(&self) -> bool {
+        !matches!(self.kind, ImportKind::InternalTransfer | ImportKind::Fee | ImportKind::Removal(_))
+    }
+}
+
+fn leg(
+    row: &ImportRow,
+    format: ImportFormat,
+    action: TradeAction,
+    asset: &Amount,
+    (price, currency): (f64, &str),
+    tags: &[&str],
+    account_id: &Option<String>,
+) -> CreateTransactionRequest {
+    let asset_type = if is_fiat(&asset.currency) { AssetType::Cash } else { AssetType::Crypto };
+    let mut all_tags = vec!["imported".to_string(), format.as_str().to_string()];
+    all_tags.extend(tags.iter().map(|t| t.to_string()));
+    CreateTransactionRequest {
+        asset_type,
+        symbol: asset.currency.clone(),
+        symbol_name: None,
+        action,
+        quantity: asset.quantity,
+        price,
+        fees: 0.0,
+        network_fee: 0.0,
+        timestamp: row.timestamp,
+        market: None,
+        currency: Some(currency.to_string()),
+        notes: Some(match &row.reference {
+            Some(reference) => format!("{} import row {}: {}", format.as_str(), row.row, reference),
+            None => format!("{} import row {}", format.as_str(), row.row),
+        }),
+        account_id: account_id.clone(),
+        tags: all_tags,
+        leverage: None,
+        initial_margin: None,
+        unit: None,
+        custom_fields: Default::default(),
+    }
+}
+
+/// Map a normalized row onto transactions. `value` is the row's fiat value (from the export
+/// or a price lookup); fees in the value currency go on the main leg, other fees become
+/// separate withdrawals tagged "fee". Fees on transfers are recorded as `network_fee` (and
+/// their withdrawals also tagged "network") instead of trading fees.
+pub fn to_transactions(
+    row: &ImportRow,
+    format: ImportFormat,
+    value: Option<&Amount>,
+    account_id: &Option<String>,
+) -> Result<Vec<CreateTransactionRequest>, String> {
+    let mut txs = Vec::new();
+    let unit_price = |asset: &Amount| -> Result<(f64, String), String> {
+        if is_fiat(&asset.currency) {
+            return Ok((1.0, asset.currency.clone()));
+        }
+        let value = value.ok_or_else(|| format!("no fiat value for {} {}", asset.quantity, asset.currency))?;
+        Ok((value.quantity / asset.quantity, value.currency.clone()))
+    };
+
+    match &row.kind {
+        ImportKind::Trade => {
+            if let (Some(sent), Some(received)) = (&row.sent, &row.received) {
+                if !is_fiat(&sent.currency) {
+                    let (price, currency) = unit_price(sent)?;
+                    txs.push(leg(row, format, TradeAction::Sell, sent, (price, &currency), &[], account_id));
+                }
+                if !is_fiat(&received.currency) {
+                    let (price, currency) = unit_price(received)?;
+                    txs.push(leg(row, format, TradeAction::Buy, received, (price, &currency), &[], account_id));
+                }
+                if txs.is_empty() {
+                    return Err("fiat-to-fiat conversions are not imported".to_string());
+                }
+            } else {
+                return Err("trade without both sides".to_string());
+            }
+        }
+        ImportKind::Income(tag) => {
+            let received = row.received.as_ref().ok_or("income row without received amount")?;
+            let (price, currency) = unit_price(received)?;
+            txs.push(leg(row, format, TradeAction::Buy, received, (price, &currency), &["income", tag], account_id));
+        }
+        ImportKind::TransferIn => {
+            let received = row.received.as_ref().ok_or("deposit without received amount")?;
+            let (price, currency) = unit_price(received)?;
+            txs.push(leg(row, format, TradeAction::Deposit, received, (price, &currency), &["transfer"], account_id));
+        }
+        ImportKind::TransferOut => {
+            let sent = row.sent.as_ref().ok_or("withdrawal without sent amount")?;
+            let (price, currency) = unit_price(sent).unwrap_or((0.0, sent.currency.clone()));
+            txs.push(leg(row, format, TradeAction::Withdraw, sent, (price, &currency), &["transfer"], account_id));
+        }
+        ImportKind::Spend => {
+            let sent = row.sent.as_ref().ok_or("spend without sent amount")?;
+            let (price, currency) = unit_price(sent)?;
+            txs.push(leg(row, format, TradeAction::Sell, sent, (price, &currency), &["spend"], account_id));
+        }
+        ImportKind::Removal(tag) => {
+            let sent = row.sent.as_ref().ok_or("removal without sent amount")?;
+            let (price, currency) = unit_price(sent).unwrap_or((0.0, sent.currency.clone()));
+            txs.push(leg(row, format, TradeAction::Withdraw, sent, (price, &currency), &[tag], account_id));
+        }
+        ImportKind::Fee => {
+            // Koinly records standalone fees as the sent amount; CoinTracking uses the fee columns
+            if let Some(fee) = row.fee.as_ref().or(row.sent.as_ref()) {
+                let (price, currency) = unit_price(fee).unwrap_or((0.0, fee.currency.clone()));
+                txs.push(leg(row, format, TradeAction::Withdraw, fee, (price, &currency), &["fee"], account_id));
+            }
+            return Ok(txs);
+        }
+        ImportKind::InternalTransfer => {}
+    }
+
+    if let Some(fee) = &row.fee {
+        let network = row.has_network_fee();
+        // Fees paid in the trade's currency are recorded on the main leg
+        if let Some(main) = txs.iter_mut().rev().find(|t| t.currency.as_deref() == Some(fee.currency.as_str())) {
+            if network {
+                main.network_fee += fee.quantity;
+            } else {
+                main.fees += fee.quantity;
+            }
+        } else {
+            let (price, currency) = match (value, [&row.sent, &row.received].iter().filter_map(|s| s.as_ref()).find(|a| a.currency == fee.currency)) {
+                // Fee taken in one of the traded assets: price it like that side
+                (Some(value), Some(side)) if !is_fiat(&fee.currency) => (value.quantity / side.quantity, value.currency.clone()),
+                _ if is_fiat(&fee.currency) => (1.0, fee.currency.clone()),
+                _ => (0.0, fee.currency.clone()),
+            };
+            let tags: &[&str] = if network { &["fee", "network"] } else { &["fee"] };
+            let mut fee_leg = leg(row, format, TradeAction::Withdraw, fee, (price, &currency), tags, account_id);
+            if network {
+                fee_leg.network_fee = fee.quantity * price;
+            }
+            txs.push(fee_leg);
+        }
+    }
+    Ok(txs)
+}
@@ -0,0 +1,176 @@
+//! Keeps internal addresses and secrets out of API responses and logs. `init` registers the
+//! PocketBase URL and configured secrets once at startup; `sanitize` swaps them for placeholders
+//! in any text (error messages include the URL reqwest failed on). Admins see a redacted view of
+//! the configuration at GET /api/admin/diagnostics.
+
+use std::borrow::Cow;
+use std::sync::OnceLock;
+use serde::Serialize;
+use crate::config::Config;
+
+/// Values shorter than this aren't registered, so short strings don't get masked everywhere
+const MIN_REDACTED_LENGTH: usize = 8;
+
+/// Query parameters and environment variable names whose values are never shown
+const SECRET_MARKERS: &[&str] = &["SECRET", "PASSWORD", "TOKEN", "KEY", "SIGNATURE"];
+
+/// (value, placeholder) pairs replaced by `sanitize`, longest value first
+static REDACTIONS: OnceLock<Vec<(String, &'static str)>> = OnceLock::new();
+
+/// Register the values `sanitize` hides. Later calls are ignored.
+pub fn init(config: &Config) {
+    let urls = [
+        (Some(&config.pocketbase_url), "[pocketbase]"),
+        (Some(&config.yahoo_finance_service_url), "[yahoo-finance-service]"),
+        (config.s3_endpoint.as_ref(), "[object-storage]"),
+    ];
+    let mut redactions: Vec<(String, &'static str)> = urls.into_iter()
+        .filter_map(|(url, placeholder)| Some((url?.trim_end_matches('/').to_string(), placeholder)))
+        .collect();
+    redactions.extend(secret_values(config).into_iter().map(|secret| (secret.to_string(), "****")));
+    redactions.retain(|(value, _)| value.len() >= MIN_REDACTED_LENGTH);
+    redactions.sort_by_key(|(value, _)| std::cmp::Reverse(value.len()));
+    let _ = REDACTIONS.set(redactions);
+}
+
+/// Text with registered URLs and secrets replaced by placeholders
+pub fn sanitize(text: &str) -> Cow<'_, str> {
+    let Some(redactions) = REDACTIONS.get() else { return Cow::Borrowed(text) };
+    let mut text = Cow::Borrowed(text);
+    for (value, placeholder) in redactions {
+        if text.contains(value.as_str()) {
+            text = Cow::Owned(text.replace(value.as_str(), placeholder));
+        }
+    }
+    text
+}
+
+/// Whether an environment variable or query parameter name holds a secret
+pub fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+/// URL with its password and secret query parameters masked
+pub fn redact_url(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else { return sanitize(url).into_owned() };
+    if parsed.password().is_some() {
+        let _ = parsed.set_password(Some("****"));
+    }
+    if parsed.query_pairs().any(|(name, _)| is_secret_name(&name)) {
+        let pairs: Vec<(String, String)> = parsed.query_pairs()
+            .map(|(name, value)| {
+                let value = if is_secret_name(&name) { "****".to_string() } else { value.into_owned() };
+                (name.into_owned(), value)
+            })
+            .collect();
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    parsed.to_string()
+}
+
+/// Print which admin and PocketBase variables are set, without any of their values. Runs before
+/// tracing is initialised.
+pub fn log_environment() {
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(key, _)| key.contains("ADMIN") || key.contains("POCKETBASE"))
+        .collect();
+    vars.sort();
+    println!("🔍 Environment Variables Check:");
+    for (key, value) in vars {
+        println!("   - {}: {}", key, if value.trim().is_empty() { "(empty)" } else { "set" });
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigEntry {
+    pub name: &'static str,
+    pub set: bool,
+    /// Left out for secrets and email addresses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl ConfigEntry {
+    fn value(name: &'static str, value: impl ToString) -> Self {
+        Self { name, set: true, value: Some(value.to_string()) }
+    }
+
+    fn url(name: &'static str, url: Option<&str>) -> Self {
+        Self { name, set: url.is_some(), value: url.map(redact_url) }
+    }
+
+    fn hidden(name: &'static str, value: Option<&str>) -> Self {
+        Self { name, set: value.is_some_and(|v| !v.trim().is_empty()), value: None }
+    }
+}
+
+/// Configuration as shown to admins: URLs with credentials masked, secrets and emails only as
+/// set or not
+pub fn config_summary(config: &Config) -> Vec<ConfigEntry> {
+    vec![
+        ConfigEntry::value("SERVER_ADDR", config.server_addr()),
+        ConfigEntry::url("POCKETBASE_URL", Some(&config.pocketbase_url)),
+        ConfigEntry::hidden("POCKETBASE_ADMIN_EMAIL", config.pb_admin_email.as_deref()),
+        ConfigEntry::hidden("ADMIN_EMAIL", config.admin_email.as_deref()),
+        ConfigEntry::hidden("POCKETBASE_ADMIN_PASSWORD", config.pb_admin_password.as_deref()),
+        ConfigEntry::url("YAHOO_FINANCE_SERVICE_URL", Some(&config.yahoo_finance_service_url)),
+        ConfigEntry::url("COINGECKO_API_URL", Some(&config.coingecko_api_url)),
+        ConfigEntry::url("SETTRADE_API_URL", Some(&config.settrade_api_url)),
+        ConfigEntry::url("FINNHUB_API_URL", Some(&config.finnhub_api_url)),
+        ConfigEntry::hidden("FINNHUB_API_KEY", config.finnhub_api_key.as_deref()),
+        ConfigEntry::url("GOLDAPI_API_URL", Some(&config.goldapi_api_url)),
+        ConfigEntry::hidden("GOLDAPI_API_KEY", config.goldapi_api_key.as_deref()),
+        ConfigEntry::url("OUTBOUND_PROXY", config.outbound_proxy.as_deref()),
+        ConfigEntry::value("STRICT_PRICE_DATA", config.strict_price_data),
+        ConfigEntry::value("CRYPTO_PRICE_STREAMS", config.crypto_price_streams),
+        ConfigEntry::value("OAUTH_ENABLED", config.oauth_enabled),
+        ConfigEntry::hidden("GOOGLE_CLIENT_SECRET", config.google_client_secret.as_deref()),
+        ConfigEntry::url("OIDC_ISSUER_URL", config.oidc_issuer_url.as_deref()),
+        ConfigEntry::hidden("OIDC_CLIENT_SECRET", config.oidc_client_secret.as_deref()),
+        ConfigEntry::hidden("JWT_SECRET", Some(&config.jwt_secret)),
+        ConfigEntry::value("JWT_EXPIRY_HOURS", config.jwt_expiry_hours),
+        ConfigEntry::url("FRONTEND_URL", Some(&config.frontend_url)),
+        ConfigEntry::value("REQUIRE_EMAIL_VERIFICATION", config.require_email_verification),
+        ConfigEntry::value("REGISTRATION_INVITE_ONLY", config.registration_invite_only),
+        ConfigEntry::value("GUEST_LOGIN_ENABLED", config.guest_login_enabled),
+        ConfigEntry::value("OPS_READ_ACCESS_FOR_USERS", config.ops_read_access_for_users),
+        ConfigEntry::value("TRUSTED_PROXIES", config.trusted_proxies.len()),
+        ConfigEntry::url("S3_ENDPOINT", config.s3_endpoint.as_deref()),
+        ConfigEntry::hidden("S3_ACCESS_KEY_ID", config.s3_access_key_id.as_deref()),
+        ConfigEntry::hidden("S3_SECRET_ACCESS_KEY", config.s3_secret_access_key.as_deref()),
+        ConfigEntry::hidden("CREDENTIALS_ENCRYPTION_KEY", config.credentials_encryption_key.as_deref()),
+        ConfigEntry::hidden("SMTP_HOST", config.smtp_host.as_deref()),
+        ConfigEntry::value("SMTP_SECURITY", &config.smtp_security),
+        ConfigEntry::hidden("SMTP_PASSWORD", config.smtp_password.as_deref()),
+        ConfigEntry::hidden("TELEGRAM_BOT_TOKEN", config.telegram_bot_token.as_deref()),
+        ConfigEntry::value("NOTIFICATION_RETRY_ATTEMPTS", config.notification_retry_attempts),
+        ConfigEntry::value("JOB_MAX_CONCURRENCY", config.job_max_concurrency),
+        ConfigEntry::value("PRICE_FETCH_CONCURRENCY", config.price_fetch_concurrency),
+        ConfigEntry::value("SNAPSHOT_CONCURRENCY", config.snapshot_concurrency),
+        ConfigEntry::value("DISABLED_PLUGINS", config.disabled_plugins.join(",")),
+        ConfigEntry::value("CACHE_REVALIDATE_SECONDS", config.cache_revalidate_seconds),
+        ConfigEntry::value("POCKETBASE_REALTIME", config.pocketbase_realtime),
+        ConfigEntry::value("PUBLIC_STATUS_RATE_LIMIT", config.public_status_rate_limit),
+    ]
+}
+
+fn secret_values(config: &Config) -> Vec<&str> {
+    [
+        config.finnhub_api_key.as_deref(),
+        config.goldapi_api_key.as_deref(),
+        config.google_client_secret.as_deref(),
+        config.oidc_client_secret.as_deref(),
+        Some(config.jwt_secret.as_str()),
+        config.admin_password.as_deref(),
+        config.pb_admin_password.as_deref(),
+        config.s3_access_key_id.as_deref(),
+        config.s3_secret_access_key.as_deref(),
+        config.credentials_encryption_key.as_deref(),
+        config.smtp_password.as_deref(),
+        config.telegram_bot_token.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
@@ -0,0 +1,176 @@
+//! Daily portfolio digest ("daily_digest" job): total value and day change from the user's
+//! snapshots, the day's top movers and TFEX contracts nearing expiry. Each user picks a local
+//! time (`digest_time`) and UTC offset (`timezone`) in their settings; the job sends the digest
+//! on its first run after that time each day.
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, Utc, Weekday};
+use serde::Deserialize;
+use crate::error::AppError;
+use crate::models::UserSettings;
+use crate::services::movers::MoversReport;
+use crate::services::PocketBaseClient;
+
+/// Offset used when the user hasn't set a timezone (Thailand, no daylight saving)
+const DEFAULT_UTC_OFFSET_SECONDS: i32 = 7 * 3600;
+/// TFEX contracts expiring within this many days are listed
+pub const EXPIRY_WINDOW_DAYS: i64 = 14;
+
+/// Parse a UTC offset such as "+07:00", "+0700", "UTC+7" or "-05:30"
+pub fn parse_utc_offset(value: &str) -> Option<FixedOffset> {
+    let value = value.trim();
+    let value = value.strip_prefix("UTC").or_else(|| value.strip_prefix("GMT")).unwrap_or(value);
+    if value.is_empty() || value == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+    let (sign, rest) = match value.split_at(1) {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return None,
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+pub fn parse_digest_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+/// The user's local date if their digest time has passed today and today's digest wasn't sent
+pub fn digest_due(settings: &UserSettings, now: DateTime<Utc>) -> Option<NaiveDate> {
+    let time = parse_digest_time(settings.digest_time.as_deref()?)?;
+    let offset = settings.timezone.as_deref()
+        .and_then(parse_utc_offset)
+        .or_else(|| FixedOffset::east_opt(DEFAULT_UTC_OFFSET_SECONDS))?;
+    let local = now.with_timezone(&offset);
+    if local.time() < time {
+        return None;
+    }
+    let today = local.date_naive();
+    let sent_on = settings.digest_sent_on.as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+    sent_on.is_none_or(|sent| sent < today).then_some(today)
+}
+
+/// Last trading day of a TFEX contract from its series code (e.g. S50Z24, GFM25): the business
+/// day before the last business day of the contract month. Exchange holidays aren't known, so
+/// only weekends are skipped.
+pub fn tfex_expiry(symbol: &str) -> Option<NaiveDate> {
+    let symbol = symbol.trim().to_uppercase();
+    if symbol.len() < 4 || !symbol.is_ascii() {
+        return None;
+    }
+    let (head, year) = symbol.split_at(symbol.len() - 2);
+    let year: i32 = 2000 + year.parse::<i32>().ok()?;
+    let month = match head.chars().last()? {
+        'F' => 1, 'G' => 2, 'H' => 3, 'J' => 4, 'K' => 5, 'M' => 6,
+        'N' => 7, 'Q' => 8, 'U' => 9, 'V' => 10, 'X' => 11, 'Z' => 12,
+        _ => return None,
+    };
+    let first_of_next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)?
+    };
+    let previous_business_day = |mut day: NaiveDate| {
+        day = day.pred_opt()?;
+        while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+            day = day.pred_opt()?;
+        }
+        Some(day)
+    };
+    previous_business_day(previous_business_day(first_of_next)?)
+}
+
+/// Total value and day change from the user's two latest snapshots
+#[derive(Debug, Clone)]
+pub struct ValueChange {
+    pub currency: String,
+    pub total_value: f64,
+    /// None with only one snapshot
+    pub day_change: Option<f64>,
+}
+
+/// Only the totals of a portfolio_snapshots record
+#[derive(Debug, Deserialize)]
+struct SnapshotTotal {
+    #[serde(default)]
+    total_current_value: f64,
+    #[serde(default)]
+    currency: String,
+}
+
+pub async fn value_change(db: &PocketBaseClient, user_id: &str) -> Result<Option<ValueChange>, AppError> {
+    let from = (Utc::now() - Duration::days(7)).format("%Y-%m-%d");
+    let snapshots: Vec<SnapshotTotal> = db.list_records(
+        "portfolio_snapshots",
+        Some(format!("user_id='{}' && date >= '{}'", user_id, from)),
+        "-date",
+    ).await?;
+    let Some(latest) = snapshots.first() else { return Ok(None) };
+    Ok(Some(ValueChange {
+        currency: if latest.currency.is_empty() { "THB".to_string() } else { latest.currency.clone() },
+        total_value: latest.total_current_value,
+        day_change: snapshots.get(1).map(|previous| latest.total_current_value - previous.total_current_value),
+    }))
+}
+
+/// A held TFEX contract and when it expires
+#[derive(Debug, Clone)]
+pub struct UpcomingExpiry {
+    pub symbol: String,
+    pub expires_on: NaiveDate,
+    pub days_left: i64,
+}
+
+fn amount(value: f64) -> String {
+    let fixed = format!("{:.2}", value.abs());
+    let (whole, fraction) = fixed.split_once('.').unwrap_or((&fixed, "00"));
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("{}{}.{}", if value < 0.0 { "-" } else { "" }, grouped, fraction)
+}
+
+/// Plain-text digest body; None when there is nothing to report
+pub fn render_digest(value: Option<&ValueChange>, movers: Option<&MoversReport>, expiries: &[UpcomingExpiry]) -> Option<String> {
+    let mut lines = Vec::new();
+    if let Some(value) = value {
+        lines.push(format!("Total value: {} {}", amount(value.total_value), value.currency));
+        if let Some(change) = value.day_change {
+            let previous = value.total_value - change;
+            let percent = if previous.abs() > 0.0 { format!(" ({:+.2}%)", change / previous * 100.0) } else { String::new() };
+            lines.push(format!("Day change: {}{} {}{}", if change >= 0.0 { "+" } else { "" }, amount(change), value.currency, percent));
+        }
+    }
+    if let Some(window) = movers.and_then(|report| report.windows.iter().find(|w| w.days == 1)) {
+        let fmt = |movers: &[crate::services::movers::Mover]| if movers.is_empty() {
+            "-".to_string()
+        } else {
+            movers.iter().map(|m| format!("{} {:+.2}%", m.symbol, m.change_percent)).collect::<Vec<_>>().join(", ")
+        };
+        if !window.gainers.is_empty() || !window.losers.is_empty() {
+            lines.push(format!("Top movers: ▲ {} | ▼ {}", fmt(&window.gainers), fmt(&window.losers)));
+        }
+    }
+    for expiry in expiries {
+        let when = match expiry.days_left {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            days => format!("in {} days", days),
+        };
+        lines.push(format!("TFEX {} expires {} ({})", expiry.symbol, expiry.expires_on.format("%Y-%m-%d"), when));
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
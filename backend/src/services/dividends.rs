@@ -0,0 +1,357 @@
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use crate::models::{
+    AssetType, DividendPayment, DividendPeriod, DividendPeriodTotal, Market, TradeAction, Transaction,
+};
+use crate::utils::units::{normalize_price, normalize_quantity};
+
+/// Window of past payments used to project annual income
+const TRAILING_DAYS: i64 = 365;
+
+/// A position still held, with the payments it received in the trailing window
+#[derive(Debug, Clone)]
+pub struct IncomeHolding {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+    pub currency: String,
+    pub quantity: f64,
+    pub trailing_per_share: f64,
+    pub payments_per_year: usize,
+}
+
+/// Custom field holding a dividend's ex-dividend date (YYYY-MM-DD or RFC 3339)
+pub const EX_DATE_FIELD: &str = "ex_date";
+
+/// Key of one asset across accounts
+pub fn asset_key(symbol: &str, asset_type: &AssetType, market: Option<&Market>) -> String {
+    let market = market.map(|m| m.to_string()).unwrap_or_default();
+    format!("{}:{}:{}", asset_type, market, symbol.to_uppercase())
+}
+
+fn position_key(tx: &Transaction) -> String {
+    asset_key(&tx.symbol, &tx.asset_type, tx.market.as_ref())
+}
+
+/// Interest credited to a savings or cash account rather than a company dividend
+fn is_interest(tx: &Transaction) -> bool {
+    tx.asset_type == AssetType::Cash || tx.is_interest_credit()
+}
+
+fn ex_date(tx: &Transaction) -> DateTime<Utc> {
+    let Some(value) = tx.custom_fields.get(EX_DATE_FIELD).and_then(|v| v.as_str()).map(str::trim) else {
+        return tx.timestamp;
+    };
+    value.parse::<DateTime<Utc>>().ok()
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+            Some(date.and_hms_opt(23, 59, 59)?.and_utc())
+        })
+        .unwrap_or(tx.timestamp)
+}
+
+fn currency_of(tx: &Transaction) -> String {
+    tx.currency.clone()
+        .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
+        .unwrap_or_else(|| "THB".to_string())
+}
+
+/// Replay long holdings and return them with every dividend payment, oldest first.
+/// Dividend transactions keep the amount received in `price`.
+fn replay(transactions: &[Transaction]) -> (Vec<DividendPayment>, HashMap<String, (Transaction, f64)>) {
+    let mut sorted: Vec<&Transaction> = transactions.iter().collect();
+    sorted.sort_by_key(|t| t.timestamp);
+
+    let mut held: HashMap<String, (Transaction, f64)> = HashMap::new();
+    let mut payments = Vec::new();
+    for tx in sorted {
+        let key = position_key(tx);
+        let (quantity, _) = normalize_quantity(tx.quantity, tx.unit.as_deref(), &tx.asset_type, &tx.symbol);
+        match tx.action {
+            TradeAction::Buy | TradeAction::Long => {
+                held.entry(key).or_insert_with(|| (tx.clone(), 0.0)).1 += quantity;
+            }
+            TradeAction::Sell | TradeAction::CloseLong | TradeAction::LiquidateLong => {
+                if let Some((_, position)) = held.get_mut(&key) {
+                    *position = (*position - quantity).max(0.0);
+                }
+            }
+            TradeAction::Dividend => {
+                let quantity_held = held.get(&key).map(|(_, q)| *q).unwrap_or(0.0);
+                payments.push(DividendPayment {
+                    transaction_id: tx.id.clone(),
+                    symbol: tx.symbol.clone(),
+                    asset_type: tx.asset_type.clone(),
+                    market: tx.market.clone(),
+                    account_id: tx.account_id.clone(),
+                    amount: tx.price,
+                    currency: currency_of(tx),
+                    paid_at: tx.timestamp,
+                    ex_date: ex_date(tx),
+                    per_share: (quantity_held > 0.0).then(|| tx.price / quantity_held),
+                    interest: is_interest(tx),
+                    notes: tx.notes.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+    (payments, held)
+}
+
+/// Every dividend and interest payment, oldest first
+pub fn dividend_payments(transactions: &[Transaction]) -> Vec<DividendPayment> {
+    replay(transactions).0
+}
+
+/// Group payments by year or month. `amounts` holds each payment converted to the base currency.
+pub fn period_totals(payments: &[DividendPayment], amounts: &[f64], period: DividendPeriod) -> Vec<DividendPeriodTotal> {
+    let mut totals: BTreeMap<String, DividendPeriodTotal> = BTreeMap::new();
+    for (payment, amount) in payments.iter().zip(amounts) {
+        let label = match period {
+            DividendPeriod::Year => payment.paid_at.format("%Y").to_string(),
+            DividendPeriod::Month => payment.paid_at.format("%Y-%m").to_string(),
+        };
+        let total = totals.entry(label.clone()).or_insert_with(|| DividendPeriodTotal {
+            period: label,
+            total: 0.0,
+            payments: 0,
+            by_symbol: BTreeMap::new(),
+        });
+        total.total += amount;
+        total.payments += 1;
+        *total.by_symbol.entry(payment.symbol.clone()).or_insert(0.0) += amount;
+    }
+    totals.into_values().collect()
+}
+
+/// Held positions that paid dividends in the last 12 months. Projected income is the
+/// trailing per-share payout times today's quantity, so buying more raises the projection.
+pub fn income_holdings(transactions: &[Transaction], now: DateTime<Utc>) -> Vec<IncomeHolding> {
+    let (payments, held) = replay(transactions);
+    let since = now - Duration::days(TRAILING_DAYS);
+
+    let mut trailing: HashMap<String, (f64, usize)> = HashMap::new();
+    for payment in payments.iter().filter(|p| p.paid_at >= since && !p.interest) {
+        let Some(per_share) = payment.per_share else { continue };
+        let key = asset_key(&payment.symbol, &payment.asset_type, payment.market.as_ref());
+        let entry = trailing.entry(key).or_insert((0.0, 0));
+        entry.0 += per_share;
+        entry.1 += 1;
+    }
+
+    let mut holdings: Vec<IncomeHolding> = held.into_iter()
+        .filter(|(_, (_, quantity))| *quantity > 0.00000001)
+        .filter_map(|(key, (tx, quantity))| {
+            let (per_share, count) = trailing.get(&key)?;
+            Some(IncomeHolding {
+                symbol: tx.symbol.to_uppercase(),
+                asset_type: tx.asset_type.clone(),
+                market: tx.market.clone(),
+                currency: currency_of(&tx),
+                quantity,
+                trailing_per_share: *per_share,
+                payments_per_year: *count,
+            })
+        })
+        .collect();
+    holdings.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    holdings
+}
+
+// ==================== Dividend Reinvestment (DRIP) ====================
+
+/// One dividend bought back into the asset in the reinvested scenario
+#[derive(Debug, Clone, Serialize)]
+pub struct Reinvestment {
+    pub transaction_id: String,
+    pub ex_date: DateTime<Utc>,
+    /// Paid on the actual holding plus the shares earlier dividends bought
+    pub dividend: f64,
+    /// None when no price was known, in which case the dividend stays as cash
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    pub quantity: f64,
+    /// No price was recorded on the ex-date; the last trade price was used
+    pub price_estimated: bool,
+}
+
+/// Where one scenario ends up, in the asset's currency
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScenarioReturn {
+    pub quantity: f64,
+    pub market_value: f64,
+    /// Dividends kept as cash (in the reinvested scenario, only those that had no price)
+    pub cash: f64,
+    pub sale_proceeds: f64,
+    /// Market value plus cash plus sale proceeds, minus everything invested
+    pub total_return: f64,
+    pub total_return_percent: f64,
+}
+
+/// Both scenarios valued on an ex-date, or now for the last point
+#[derive(Debug, Clone, Serialize)]
+pub struct DripPoint {
+    pub date: DateTime<Utc>,
+    pub price: f64,
+    pub cash_return_percent: f64,
+    pub reinvested_return_percent: f64,
+}
+
+/// One dividend-paying asset with its dividends taken as cash and reinvested
+#[derive(Debug, Clone, Serialize)]
+pub struct DripModel {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    pub currency: String,
+    /// Buys including fees
+    pub invested: f64,
+    pub cash: ScenarioReturn,
+    pub reinvested: ScenarioReturn,
+    pub reinvestments: Vec<Reinvestment>,
+    pub series: Vec<DripPoint>,
+}
+
+/// Replay state of one asset in both scenarios
+struct DripState {
+    model: DripModel,
+    held: f64,
+    /// Shares only the reinvested scenario owns
+    extra: f64,
+    dividends_cash: f64,
+    /// Dividends the reinvested scenario couldn't buy with
+    uninvested: f64,
+    last_trade_price: Option<f64>,
+}
+
+impl DripState {
+    fn point(&mut self, date: DateTime<Utc>, price: f64) {
+        let percent = |value: f64| if self.model.invested > 0.0 { (value - self.model.invested) / self.model.invested * 100.0 } else { 0.0 };
+        let cash_value = self.held * price + self.dividends_cash + self.model.cash.sale_proceeds;
+        let reinvested_value = (self.held + self.extra) * price + self.uninvested + self.model.reinvested.sale_proceeds;
+        self.model.series.push(DripPoint {
+            date,
+            price,
+            cash_return_percent: percent(cash_value),
+            reinvested_return_percent: percent(reinvested_value),
+        });
+    }
+}
+
+/// Model every asset that paid dividends twice: dividends taken as cash, and each dividend
+/// reinvested at the asset's price on its ex-date (`ex_date_prices`, by dividend transaction
+/// ID). Reinvested shares earn later dividends and are sold down with the position. Assets are
+/// valued at `current_prices` (by `asset_key`), falling back to the last trade price.
+pub fn model_reinvestment(
+    transactions: &[Transaction],
+    ex_date_prices: &HashMap<String, f64>,
+    current_prices: &HashMap<String, f64>,
+    now: DateTime<Utc>,
+) -> Vec<DripModel> {
+    let mut sorted: Vec<&Transaction> = transactions.iter().collect();
+    sorted.sort_by_key(|t| t.timestamp);
+
+    let mut states: HashMap<String, DripState> = HashMap::new();
+    for tx in sorted {
+        let is_dividend = tx.action == TradeAction::Dividend && !is_interest(tx);
+        let is_trade = matches!(
+            tx.action,
+            TradeAction::Buy | TradeAction::Long | TradeAction::Sell | TradeAction::CloseLong | TradeAction::LiquidateLong
+        );
+        if !is_trade && !is_dividend {
+            continue;
+        }
+        let state = states.entry(position_key(tx)).or_insert_with(|| DripState {
+            model: DripModel {
+                symbol: tx.symbol.to_uppercase(),
+                asset_type: tx.asset_type.clone(),
+                market: tx.market.clone(),
+                currency: currency_of(tx),
+                invested: 0.0,
+                cash: ScenarioReturn::default(),
+                reinvested: ScenarioReturn::default(),
+                reinvestments: Vec::new(),
+                series: Vec::new(),
+            },
+            held: 0.0,
+            extra: 0.0,
+            dividends_cash: 0.0,
+            uninvested: 0.0,
+            last_trade_price: None,
+        });
+        let (quantity, _) = normalize_quantity(tx.quantity, tx.unit.as_deref(), &tx.asset_type, &tx.symbol);
+        let price = normalize_price(tx.price, tx.unit.as_deref(), &tx.asset_type, &tx.symbol);
+
+        match tx.action {
+            TradeAction::Buy | TradeAction::Long => {
+                state.held += quantity;
+                state.model.invested += quantity * price + tx.all_fees();
+                state.last_trade_price = Some(price);
+            }
+            TradeAction::Dividend => {
+                // Dividend transactions keep the amount received in `price`
+                let amount = tx.price;
+                let dividend = if state.held > 0.0 { amount * (state.held + state.extra) / state.held } else { amount };
+                let ex_date_price = ex_date_prices.get(&tx.id).copied().filter(|p| *p > 0.0);
+                let reinvest_price = ex_date_price.or(state.last_trade_price);
+                let bought = match reinvest_price {
+                    Some(price) => dividend / price,
+                    None => {
+                        state.uninvested += dividend;
+                        0.0
+                    }
+                };
+                state.dividends_cash += amount;
+                state.extra += bought;
+                let ex_date = ex_date(tx);
+                state.model.reinvestments.push(Reinvestment {
+                    transaction_id: tx.id.clone(),
+                    ex_date,
+                    dividend,
+                    price: reinvest_price,
+                    quantity: bought,
+                    price_estimated: ex_date_price.is_none() && reinvest_price.is_some(),
+                });
+                if let Some(price) = reinvest_price {
+                    state.point(ex_date, price);
+                }
+            }
+            _ if state.held > 0.0 => {
+                // Sells take the same share of the reinvested shares with them
+                let ratio = (quantity / state.held).min(1.0);
+                let proceeds = quantity.min(state.held) * price - tx.all_fees();
+                let extra_sold = state.extra * ratio;
+                state.model.cash.sale_proceeds += proceeds;
+                state.model.reinvested.sale_proceeds += proceeds + extra_sold * price;
+                state.extra -= extra_sold;
+                state.held -= quantity.min(state.held);
+                state.last_trade_price = Some(price);
+            }
+            _ => {}
+        }
+    }
+
+    let mut models: Vec<DripModel> = states.into_iter()
+        .filter(|(_, state)| !state.model.reinvestments.is_empty())
+        .map(|(key, mut state)| {
+            let price = current_prices.get(&key).copied().or(state.last_trade_price).unwrap_or(0.0);
+            state.point(now, price);
+            let invested = state.model.invested;
+            let finish = |scenario: &mut ScenarioReturn, quantity: f64, cash: f64| {
+                scenario.quantity = quantity;
+                scenario.market_value = quantity * price;
+                scenario.cash = cash;
+                scenario.total_return = scenario.market_value + cash + scenario.sale_proceeds - invested;
+                scenario.total_return_percent = if invested > 0.0 { scenario.total_return / invested * 100.0 } else { 0.0 };
+            };
+            finish(&mut state.model.cash, state.held, state.dividends_cash);
+            finish(&mut state.model.reinvested, state.held + state.extra, state.uninvested);
+            state.model
+        })
+        .collect();
+    models.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    models
+}
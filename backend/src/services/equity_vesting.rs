@@ -0,0 +1,200 @@
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+use crate::error::AppError;
+use crate::models::{CreateTransactionRequest, EquityGrant, GrantType, TradeAction};
+use crate::services::{PocketBaseClient, PriceService};
+
+pub const EQUITY_GRANTS_COLLECTION: &str = "equity_grants";
+
+/// Unvested part of a grant, valued at the current market price
+#[derive(Debug, Clone, Serialize)]
+pub struct UnvestedGrant {
+    pub grant_id: String,
+    pub grant_type: GrantType,
+    pub symbol: String,
+    pub unvested_quantity: f64,
+    pub vested_quantity: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_vest_date: Option<String>,
+    pub current_price: f64,
+    pub unvested_value: f64,
+    pub currency: String,
+}
+
+/// Market price on (or just before) a past vest date, falling back to the current price
+async fn price_on(
+    price_service: &PriceService,
+    grant: &EquityGrant,
+    date: NaiveDate,
+    today: NaiveDate,
+) -> Result<(f64, String), AppError> {
+    let current = price_service.get_price(&grant.symbol, &grant.asset_type, grant.market.as_ref()).await?;
+    let days_ago = (today - date).num_days();
+    if days_ago <= 1 {
+        return Ok((current.price, current.currency));
+    }
+
+    let history = price_service
+        .get_price_history(&grant.symbol, &grant.asset_type, grant.market.as_ref(), (days_ago + 7) as u32)
+        .await
+        .unwrap_or_default();
+    let target = date.format("%Y-%m-%d").to_string();
+    let historical = history
+        .iter()
+        .filter(|h| h.date.get(..10).unwrap_or(&h.date) <= target.as_str())
+        .max_by(|a, b| a.date.cmp(&b.date))
+        .map(|h| h.price);
+
+    Ok((historical.unwrap_or(current.price), current.currency))
+}
+
+/// Serializes vesting, so the manual vest endpoint and the job never vest the same tranche twice
+static VESTING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Tranches vested by one call, and why others that were due could not be
+#[derive(Debug, Default)]
+pub struct VestOutcome {
+    pub vested: usize,
+    pub errors: Vec<String>,
+}
+
+/// Create acquisition transactions for every tranche whose vest date has passed. Each tranche
+/// is saved as vested right after its transaction is created, so a failure part way through
+/// never leaves a transaction behind for a tranche that will be vested again. `grant` is
+/// reloaded first and left as saved.
+pub async fn vest_due_tranches(
+    db: &PocketBaseClient,
+    price_service: &PriceService,
+    grant: &mut EquityGrant,
+) -> Result<VestOutcome, AppError> {
+    let _vesting = VESTING.lock().await;
+    *grant = db.get_record(EQUITY_GRANTS_COLLECTION, &grant.id).await?;
+    let today = Utc::now().date_naive();
+    let mut outcome = VestOutcome::default();
+
+    for i in 0..grant.tranches.len() {
+        if grant.tranches[i].vested {
+            continue;
+        }
+        let Ok(date) = NaiveDate::parse_from_str(&grant.tranches[i].date, "%Y-%m-%d") else {
+            tracing::warn!("⚠️ Invalid vest date {} on grant {}", grant.tranches[i].date, grant.id);
+            continue;
+        };
+        if date > today {
+            continue;
+        }
+
+        let (market_price, price_currency) = match price_on(price_service, grant, date, today).await {
+            Ok(price) => price,
+            Err(e) => {
+                outcome.errors.push(format!("{} tranche: no price ({})", grant.tranches[i].date, e));
+                continue;
+            }
+        };
+        let (price, tag) = match grant.grant_type {
+            GrantType::Rsu => (market_price, "rsu"),
+            GrantType::Espp => (market_price * (1.0 - grant.espp_discount_percent / 100.0), "espp"),
+        };
+
+        let req = CreateTransactionRequest {
+            asset_type: grant.asset_type.clone(),
+            symbol: grant.symbol.clone(),
+            symbol_name: None,
+            action: TradeAction::Buy,
+            quantity: grant.tranches[i].quantity,
+            price,
+            fees: 0.0,
+            network_fee: 0.0,
+            timestamp: date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+            market: grant.market.clone(),
+            currency: Some(grant.currency.clone().unwrap_or(price_currency)),
+            notes: Some(format!("Vest of {} grant {}", tag.to_uppercase(), grant.id)),
+            account_id: grant.account_id.clone(),
+            tags: vec![tag.to_string(), "vest".to_string()],
+            leverage: None,
+            initial_margin: None,
+            unit: None,
+            custom_fields: Default::default(),
+        };
+        let tx = match db.create_transaction(req, &grant.user_id).await {
+            Ok(tx) => tx,
+            Err(e) => {
+                outcome.errors.push(format!("{} tranche: transaction not created ({})", grant.tranches[i].date, e));
+                continue;
+            }
+        };
+
+        let mut tranches = grant.tranches.clone();
+        tranches[i].vested = true;
+        tranches[i].vest_price = Some(market_price);
+        tranches[i].transaction_id = Some(tx.id.clone());
+        let saved: Result<EquityGrant, AppError> = db
+            .update_record(EQUITY_GRANTS_COLLECTION, &grant.id, &serde_json::json!({ "tranches": tranches }))
+            .await;
+        match saved {
+            Ok(_) => {
+                grant.tranches = tranches;
+                outcome.vested += 1;
+            }
+            Err(e) => {
+                // Unrecorded, the tranche would be vested again with a second transaction
+                if let Err(undo) = db.delete_transaction(&tx.id).await {
+                    tracing::error!("❌ Transaction {} kept for unrecorded tranche of grant {}: {}", tx.id, grant.id, undo);
+                }
+                outcome.errors.push(format!("{} tranche: schedule not saved ({})", grant.tranches[i].date, e));
+            }
+        }
+    }
+
+    if outcome.vested > 0 {
+        tracing::info!("📜 Vested {} tranche(s) of grant {} ({})", outcome.vested, grant.id, grant.symbol);
+    }
+    Ok(outcome)
+}
+
+/// Value the unvested shares of a user's grants at current prices
+pub async fn unvested_holdings(
+    db: &PocketBaseClient,
+    price_service: &PriceService,
+    user_id: &str,
+) -> Result<Vec<UnvestedGrant>, AppError> {
+    let grants: Vec<EquityGrant> = db
+        .list_records(EQUITY_GRANTS_COLLECTION, Some(format!("user_id='{}'", user_id)), "grant_date")
+        .await?;
+
+    let mut unvested = Vec::new();
+    for grant in grants {
+        let quantity = grant.unvested_quantity();
+        if quantity <= 0.0 {
+            continue;
+        }
+        let (current_price, currency) = match price_service
+            .get_price(&grant.symbol, &grant.asset_type, grant.market.as_ref())
+            .await
+        {
+            Ok(entry) => (entry.price, entry.currency),
+            Err(e) => {
+                tracing::warn!("⚠️ No price for unvested grant {} ({}): {}", grant.id, grant.symbol, e);
+                (0.0, grant.currency.clone().unwrap_or_default())
+            }
+        };
+        let vested_quantity = grant.vested_quantity();
+        let next_vest_date = grant.tranches.iter()
+            .filter(|t| !t.vested)
+            .map(|t| t.date.clone())
+            .min();
+
+        unvested.push(UnvestedGrant {
+            grant_id: grant.id,
+            grant_type: grant.grant_type,
+            symbol: grant.symbol,
+            unvested_quantity: quantity,
+            vested_quantity,
+            next_vest_date,
+            current_price,
+            unvested_value: quantity * current_price,
+            currency,
+        });
+    }
+    Ok(unvested)
+}
@@ -1,10 +1,82 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use crate::config::Config;
 use crate::error::AppError;
+use crate::services::provider_cache::{EndpointClass, ProviderCache};
+
+// Provider name the live FX rates are cached under (api_providers market "fx")
+const FX_CACHE_PROVIDER: &str = "fx";
+// Every live rate is derived from both currencies' value in this one
+const PIVOT_CURRENCY: &str = "USD";
+// Cache keys of the shared USD legs, next to the per-pair "FROM:TO" entries
+const USD_TABLE_KEY: &str = "table:USD";
+const BTC_LEG_KEY: &str = "leg:BTC";
+// Used when CoinGecko can't be reached (approximate, early 2025)
+const MOCK_BTC_USD: f64 = 100000.0;
+
+/// One FX_ALLOWED_PAIRS entry, e.g. EUR/THB or */THB
+#[derive(Debug, Clone, PartialEq)]
+pub struct FxPair {
+    pub from: String,
+    pub to: String,
+}
+
+impl FxPair {
+    fn matches(&self, from: &str, to: &str) -> bool {
+        let side = |rule: &str, code: &str| rule == "*" || rule.eq_ignore_ascii_case(code);
+        side(&self.from, from) && side(&self.to, to)
+    }
+}
+
+/// Parse FX_ALLOWED_PAIRS ("USD/THB,EUR/THB,*/USD"); malformed entries are ignored
+pub fn parse_fx_pairs(spec: &str) -> Vec<FxPair> {
+    spec.split(',')
+        .filter_map(|item| {
+            let (from, to) = item.split_once('/')?;
+            let (from, to) = (from.trim().to_uppercase(), to.trim().to_uppercase());
+            (!from.is_empty() && !to.is_empty()).then_some(FxPair { from, to })
+        })
+        .collect()
+}
+
+/// Value of one unit of a currency in USD
+struct UsdLeg {
+    currency: String,
+    value: f64,
+    source: String,
+    /// Not quoted anywhere, so taken as 1 USD
+    assumed: bool,
+}
+
+impl UsdLeg {
+    /// USD itself or a stablecoin pegged to it, so the leg adds nothing to the rate
+    fn is_pivot(&self) -> bool {
+        matches!(self.source.as_str(), "identity" | "usd_peg")
+    }
+}
+
+/// Audit source of a rate from two legs, e.g. "open.er-api.com via USD" for EUR/THB
+fn cross_source(from: &UsdLeg, to: &UsdLeg) -> String {
+    let mut sources: Vec<&str> = Vec::new();
+    for leg in [from, to] {
+        if !leg.is_pivot() && !sources.contains(&leg.source.as_str()) {
+            sources.push(&leg.source);
+        }
+    }
+    let mut source = if sources.is_empty() { from.source.clone() } else { sources.join(" + ") };
+    if !from.is_pivot() && !to.is_pivot() {
+        source.push_str(&format!(" via {}", PIVOT_CURRENCY));
+    }
+    for leg in [from, to] {
+        if leg.assumed {
+            source.push_str(&format!(" ({} assumed 1 USD)", leg.currency));
+        }
+    }
+    source
+}
 
 /// Exchange rate entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,7 +84,56 @@ pub struct ExchangeRate {
     pub from_currency: String,
     pub to_currency: String,
     pub rate: f64,
+    /// When the rate was fetched (start of day for historical rates)
     pub updated_at: DateTime<Utc>,
+    /// Where the rate came from, e.g. "open.er-api.com" or "fallback_mock"
+    #[serde(default)]
+    pub source: String,
+}
+
+/// Rates applied while building a response, so converted figures can be reproduced later
+#[derive(Debug, Default)]
+pub struct ConversionTrail {
+    entries: Vec<ExchangeRate>,
+}
+
+impl ConversionTrail {
+    pub fn record(&mut self, quote: &ExchangeRate) {
+        if quote.from_currency == quote.to_currency {
+            return;
+        }
+        let seen = self.entries.iter().any(|e| {
+            e.from_currency == quote.from_currency
+                && e.to_currency == quote.to_currency
+                && e.updated_at == quote.updated_at
+        });
+        if !seen {
+            self.entries.push(quote.clone());
+        }
+    }
+
+    pub fn extend(&mut self, quotes: &[ExchangeRate]) {
+        for quote in quotes {
+            self.record(quote);
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<ExchangeRate> {
+        self.entries
+    }
+}
+
+/// Continue a trail from figures that were themselves converted, e.g. the portfolio valuation
+impl From<Vec<ExchangeRate>> for ConversionTrail {
+    fn from(entries: Vec<ExchangeRate>) -> Self {
+        Self { entries }
+    }
+}
+
+/// Currency code used for historical lookups; stablecoins are priced as their USD peg
+fn historical_code(code: &str) -> String {
+    let code = code.to_uppercase();
+    if code == "USDT" || code == "USDC" { "USD".to_string() } else { code }
 }
 
 /// Exchange rate response for API
@@ -28,185 +149,190 @@ pub struct ExchangeRatesResponse {
 pub struct ExchangeRateService {
     client: reqwest::Client,
     config: Config,
-    cache: Arc<RwLock<HashMap<String, ExchangeRate>>>,
+    cache: ProviderCache,
+    // Historical daily rates never change, so they are cached without expiry
+    history_cache: Arc<RwLock<HashMap<String, f64>>>,
 }
 
 impl ExchangeRateService {
     pub fn new(config: Config) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: crate::services::http_client::clients(&config).providers.clone(),
             config,
-            cache: Arc::new(RwLock::new(HashMap::new())),
+            cache: ProviderCache::new(),
+            history_cache: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Share the response cache with the price service so provider TTL overrides apply here too
+    pub fn set_provider_cache(&mut self, cache: ProviderCache) {
+        self.cache = cache;
+    }
+
     /// Get exchange rate between two currencies
     pub async fn get_rate(&self, from: &str, to: &str) -> Result<f64, AppError> {
+        Ok(self.get_quote(from, to).await?.rate)
+    }
+
+    /// Get exchange rate and record it in `trail`
+    pub async fn get_rate_recorded(&self, from: &str, to: &str, trail: &mut ConversionTrail) -> Result<f64, AppError> {
+        let quote = self.get_quote(from, to).await?;
+        trail.record(&quote);
+        Ok(quote.rate)
+    }
+
+    /// Get exchange rate with the time it was fetched and its source
+    pub async fn get_quote(&self, from: &str, to: &str) -> Result<ExchangeRate, AppError> {
         // Same currency
         if from.to_uppercase() == to.to_uppercase() {
-            return Ok(1.0);
+            return Ok(ExchangeRate {
+                from_currency: from.to_uppercase(),
+                to_currency: to.to_uppercase(),
+                rate: 1.0,
+                updated_at: Utc::now(),
+                source: "identity".to_string(),
+            });
+        }
+
+        if !self.pair_allowed(from, to) {
+            return Err(AppError::BadRequest(format!(
+                "Exchange rate {}/{} is not enabled (FX_ALLOWED_PAIRS)",
+                from.to_uppercase(), to.to_uppercase()
+            )));
         }
 
         let cache_key = format!("{}:{}", from.to_uppercase(), to.to_uppercase());
         
         // Check cache
-        {
-            let cache = self.cache.read().await;
-            if let Some(entry) = cache.get(&cache_key) {
-                let age = Utc::now().signed_duration_since(entry.updated_at);
-                // Cache for 5 minutes for exchange rates
-                if age.num_seconds() < 300 {
-                    tracing::debug!("Exchange rate cache hit for {}", cache_key);
-                    return Ok(entry.rate);
-                }
-            }
+        if let Some(entry) = self.cache.get::<ExchangeRate>(FX_CACHE_PROVIDER, EndpointClass::Fx, &cache_key).await {
+            tracing::debug!("Exchange rate cache hit for {}", cache_key);
+            return Ok(entry);
         }
 
         // Fetch fresh rate
-        let rate = self.fetch_exchange_rate(from, to).await?;
+        let (rate, source) = self.fetch_exchange_rate(from, to).await?;
+        let quote = ExchangeRate {
+            from_currency: from.to_uppercase(),
+            to_currency: to.to_uppercase(),
+            rate,
+            updated_at: Utc::now(),
+            source,
+        };
 
         // Update cache
-        {
-            let mut cache = self.cache.write().await;
-            cache.insert(cache_key, ExchangeRate {
-                from_currency: from.to_uppercase(),
-                to_currency: to.to_uppercase(),
-                rate,
-                updated_at: Utc::now(),
-            });
-        }
+        self.cache.put(FX_CACHE_PROVIDER, EndpointClass::Fx, &cache_key, &quote).await;
 
-        Ok(rate)
+        Ok(quote)
     }
 
-    /// Fetch exchange rate (using CoinGecko for BTC, mock for others)
-    async fn fetch_exchange_rate(&self, from: &str, to: &str) -> Result<f64, AppError> {
-        let from_upper = from.to_uppercase();
-        let to_upper = to.to_uppercase();
+    /// Derive a rate from each currency's USD value; pairs without USD are cross rates through it
+    async fn fetch_exchange_rate(&self, from: &str, to: &str) -> Result<(f64, String), AppError> {
+        let from_leg = self.usd_leg(&from.to_uppercase()).await?;
+        let to_leg = self.usd_leg(&to.to_uppercase()).await?;
+        if to_leg.value <= 0.0 {
+            return Err(AppError::ExternalApiError(format!("Invalid USD value for {}", to_leg.currency)));
+        }
+
+        // 1 XAU = 2650 USD and 1 THB = 0.028 USD, so 1 XAU = 2650 / 0.028 = 94642.86 THB
+        let rate = from_leg.value / to_leg.value;
+        let source = cross_source(&from_leg, &to_leg);
+        tracing::info!("Exchange rate {}/{}: {} ({})", from_leg.currency, to_leg.currency, rate, source);
+        Ok((rate, source))
+    }
 
-        // If BTC or XAU is involved with BTC, use special handling
-        if from_upper == "BTC" || to_upper == "BTC" || 
-           (from_upper == "XAU" && to_upper == "BTC") || 
-           (from_upper == "BTC" && to_upper == "XAU") {
-            return self.fetch_btc_rate(&from_upper, &to_upper).await;
+    /// What one unit of `currency` is worth in USD, and where that came from
+    async fn usd_leg(&self, currency: &str) -> Result<UsdLeg, AppError> {
+        let leg = |value: f64, source: &str, assumed: bool| UsdLeg {
+            currency: currency.to_string(),
+            value,
+            source: source.to_string(),
+            assumed,
+        };
+        match currency {
+            PIVOT_CURRENCY => return Ok(leg(1.0, "identity", false)),
+            "USDT" | "USDC" => return Ok(leg(1.0, "usd_peg", false)),
+            "BTC" => {
+                let (value, source) = self.btc_usd().await?;
+                return Ok(leg(value, &source, false));
+            }
+            _ => {}
         }
 
-        // Mock rates for traditional currencies and gold
-        // These would come from a forex API in production
-        // Now using Free Forex API
-        let forex_rates_result = self.fetch_forex_rates_api().await;
-        
-        // Use fetched rates or fallback to hardcoded mocks for critical currencies
-        let mock_rates = match forex_rates_result {
-            Ok(rates) => rates,
+        let (table, source) = self.usd_table().await?;
+        Ok(match table.get(currency) {
+            Some(value) => leg(*value, &source, false),
+            // Unknown currencies are treated as USD; make that visible in the audit trail
+            None => leg(1.0, &source, true),
+        })
+    }
+
+    /// USD value of every currency open.er-api.com quotes, cached as one entry so each new pair
+    /// doesn't refetch it. Falls back to hardcoded values (not cached) when the API is down,
+    /// unless STRICT_PRICE_DATA is set.
+    async fn usd_table(&self) -> Result<(HashMap<String, f64>, String), AppError> {
+        if let Some(table) = self.cache.get::<HashMap<String, f64>>(FX_CACHE_PROVIDER, EndpointClass::Fx, USD_TABLE_KEY).await {
+            return Ok((table, "open.er-api.com".to_string()));
+        }
+        match self.fetch_forex_rates_api().await {
+            Ok(table) => {
+                self.cache.put(FX_CACHE_PROVIDER, EndpointClass::Fx, USD_TABLE_KEY, &table).await;
+                Ok((table, "open.er-api.com".to_string()))
+            }
+            Err(e) if self.config.strict_price_data => Err(AppError::ExternalApiError(format!(
+                "Forex rates unavailable and mock rates are disabled (STRICT_PRICE_DATA): {}", e
+            ))),
             Err(e) => {
                 tracing::error!("Failed to fetch forex rates, using fallback mocks: {}", e);
-                // Fallback mock map
-                 [
+                let table = [
                     ("USD", 1.0), ("USDT", 1.0), ("THB", 0.028),
                     ("EUR", 1.08), ("GBP", 1.27), ("JPY", 0.0067),
                     ("HKD", 0.128), ("SGD", 0.74), ("XAU", 2650.0),
                 ].into_iter()
                 .map(|(k, v)| (k.to_string(), v))
-                .collect()
+                .collect();
+                Ok((table, "fallback_mock".to_string()))
             }
-        };
-        
-        // mock_rates contains: how much USD per 1 unit of currency
-        // e.g., THB: 0.028 means 1 THB = 0.028 USD
-        // XAU: 2650.0 means 1 oz Gold = 2650 USD
-        let from_to_usd = mock_rates.get(&from_upper).copied().unwrap_or(1.0);
-        let to_to_usd = mock_rates.get(&to_upper).copied().unwrap_or(1.0);
-        
-        // Calculate cross rate: how many "to" per 1 "from"
-        // If 1 XAU = 2650 USD, and 1 THB = 0.028 USD
-        // Then XAU->THB rate = 2650 / 0.028 = 94642.86 (1 oz gold = 94642 THB)
-        let rate = from_to_usd / to_to_usd;
-        
-        tracing::info!("Exchange rate {}/{}: {}", from_upper, to_upper, rate);
-        
-        Ok(rate)
+        }
     }
 
-    /// Fetch BTC rate from CoinGecko
-    async fn fetch_btc_rate(&self, from: &str, to: &str) -> Result<f64, AppError> {
-        // Get BTC price in both currencies
-        let url = format!(
-            "{}/simple/price?ids=bitcoin&vs_currencies=usd,thb,eur,gbp",
-            self.config.coingecko_api_url
-        );
-
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            // Fallback to mock rates
-            return Ok(self.get_mock_btc_rate(from, to));
+    /// USD price of one BTC from CoinGecko, or a rough mock when it is unavailable
+    /// (an error under STRICT_PRICE_DATA)
+    async fn btc_usd(&self) -> Result<(f64, String), AppError> {
+        if let Some(value) = self.cache.get::<f64>(FX_CACHE_PROVIDER, EndpointClass::Fx, BTC_LEG_KEY).await {
+            return Ok((value, "coingecko".to_string()));
         }
 
-        let data: serde_json::Value = response.json().await?;
-        
-        let btc_usd = data
-            .get("bitcoin")
-            .and_then(|v| v.get("usd"))
-            .and_then(|v| v.as_f64())
-            .unwrap_or(100000.0);
-            
-        let btc_thb = data
-            .get("bitcoin")
-            .and_then(|v| v.get("thb"))
-            .and_then(|v| v.as_f64())
-            .unwrap_or(3500000.0);
-
-        let btc_eur = data
-            .get("bitcoin")
-            .and_then(|v| v.get("eur"))
-            .and_then(|v| v.as_f64())
-            .unwrap_or(btc_usd / 1.08); // fallback using USD rate
-            
-        let btc_gbp = data
-            .get("bitcoin")
-            .and_then(|v| v.get("gbp"))
-            .and_then(|v| v.as_f64())
-            .unwrap_or(btc_usd / 1.27); // fallback using USD rate
-
-        // Calculate rate based on direction
-        match (from, to) {
-            ("BTC", "USD") => Ok(btc_usd),
-            ("BTC", "THB") => Ok(btc_thb),
-            ("BTC", "EUR") => Ok(btc_eur),
-            ("BTC", "GBP") => Ok(btc_gbp),
-            ("BTC", "XAU") => Ok(btc_usd / 2650.0), // 1 BTC = X oz gold (gold ~$2650/oz)
-            ("USD", "BTC") => Ok(1.0 / btc_usd),
-            ("THB", "BTC") => Ok(1.0 / btc_thb),
-            ("EUR", "BTC") => Ok(1.0 / btc_eur),
-            ("GBP", "BTC") => Ok(1.0 / btc_gbp),
-            ("XAU", "BTC") => Ok(2650.0 / btc_usd), // 1 oz gold = X BTC
-            ("USD", "THB") => Ok(btc_thb / btc_usd),
-            ("THB", "USD") => Ok(btc_usd / btc_thb),
-            _ => {
-                // For any other pair, try to calculate via USD
-                let from_btc_usd = match from {
-                    "THB" => btc_thb / btc_usd,
-                    "EUR" => btc_eur / btc_usd,
-                    "GBP" => btc_gbp / btc_usd,
-                    _ => 1.0,
-                };
-                let to_btc_usd = match to {
-                    "THB" => btc_thb / btc_usd,
-                    "EUR" => btc_eur / btc_usd,
-                    "GBP" => btc_gbp / btc_usd,
-                    _ => 1.0,
-                };
-                Ok(from_btc_usd / to_btc_usd)
+        let url = format!("{}/simple/price?ids=bitcoin&vs_currencies=usd", self.config.coingecko_api_url);
+        let price = match self.client.get(&url).header("Accept", "application/json").send().await {
+            Ok(response) if response.status().is_success() => response
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|data| data.get("bitcoin")?.get("usd")?.as_f64())
+                .filter(|v| *v > 0.0),
+            Ok(response) => {
+                tracing::warn!("⚠️ CoinGecko BTC price returned {}, using mock", response.status());
+                None
             }
+            Err(e) => {
+                tracing::warn!("⚠️ CoinGecko BTC price failed, using mock: {}", e);
+                None
+            }
+        };
+        match price {
+            Some(value) => {
+                self.cache.put(FX_CACHE_PROVIDER, EndpointClass::Fx, BTC_LEG_KEY, &value).await;
+                Ok((value, "coingecko".to_string()))
+            }
+            None if self.config.strict_price_data => Err(AppError::ExternalApiError(
+                "BTC/USD unavailable and mock rates are disabled (STRICT_PRICE_DATA)".to_string()
+            )),
+            None => Ok((MOCK_BTC_USD, "mock".to_string())),
         }
     }
 
-    /// Fetch forex rates from free API (https://open.er-api.com)
+    /// Fetch forex rates from free API (https://open.er-api.com) as the USD value of one unit
+    /// of each currency. The API quotes units per USD, so every rate is inverted.
     async fn fetch_forex_rates_api(&self) -> Result<HashMap<String, f64>, AppError> {
         let url = "https://open.er-api.com/v6/latest/USD";
         tracing::debug!("Fetching forex rates from {}", url);
@@ -230,47 +356,11 @@ impl ExchangeRateService {
             .and_then(|v| v.as_object())
             .ok_or_else(|| AppError::ExternalApiError("Invalid forex API response format".to_string()))?;
 
-        let mut rates = HashMap::new();
-        // Add USD itself
-        rates.insert("USD".to_string(), 1.0);
-        // Add USDT as peg to USD (or close enough)
-        rates.insert("USDT".to_string(), 1.0);
-        // Add Gold mock until we have a better source, or maybe the API has XAU?
-        // Open Exchange Rates usually has XAU, but let's check or keep fallback.
-        // For safety, let's keep hardcoded generic commodities if API misses them.
-        
-        for (currency, rate_val) in rates_map {
-            if let Some(rate) = rate_val.as_f64() {
-                rates.insert(currency.to_string(), rate);
-            }
-        }
-        
-        // Ensure standard mocks exist if API didn't return them (e.g. specialized commodities not in std forex)
-        rates.entry("XAU".to_string()).or_insert(2650.0); // Price of 1 oz Gold in USD (Inverse of rate usually? Wait.)
-        // API returns "How many Currency units per 1 USD".
-        // XAU rate 0.00038 means 1 USD = 0.00038 oz Gold. 
-        // So Gold Price in USD = 1 / 0.00038 = ~2631.
-        // Let's handle special inversion for XAU/XAG if the API provides them, otherwise use mock price.
-        
-        // Actually, let's stick to the "Rate = USD value" or "Rate = Units per USD"?
-        // fetch_exchange_rate logic: 
-        // from_to_usd = mock_rates.get(from).unwrap_or(1.0) -> This implies the map stores "How many USD is 1 unit worth?"
-        // WAIT. 
-        // Previous logic:
-        // THB: 0.028 => 1 THB = 0.028 USD.
-        // OpenER API returns: THB: 35.7 => 1 USD = 35.7 THB.
-        // So OpenER returns "Units per USD".
-        // Previous mock was "USD per Unit".
-        // I need to INVERT the rates from OpenER to match the internal logic, OR update the internal logic.
-        // Updating internal logic to use standard "Units per USD" is better but risky for regressions.
-        // Easiest: Invert the rates here.
-        // Map will store: "Value of 1 Unit in USD".
-        
-        // Re-building map with inversion
         let mut value_in_usd_map = HashMap::new();
         value_in_usd_map.insert("USD".to_string(), 1.0);
         value_in_usd_map.insert("USDT".to_string(), 1.0);
 
+        // XAU is quoted as ounces per USD, so 1 / rate is the USD price of an ounce
         for (currency, rate_per_usd) in rates_map {
             if let Some(rate) = rate_per_usd.as_f64() {
                 if rate > 0.0 {
@@ -278,59 +368,89 @@ impl ExchangeRateService {
                 }
             }
         }
-        
-        // Gold/Silver overrides if missing or weird
-        // XAU in API might be "Ounces per USD" -> very small number.
-        // 1/Rate gives "USD per Ounce". Perfect.
-        
+        // Keep gold priced when the API doesn't list it
+        value_in_usd_map.entry("XAU".to_string()).or_insert(2650.0);
+
         Ok(value_in_usd_map)
     }
 
-    /// Implement a simple memory cache for the forex rates to avoid fetching every call
-    /// For this single-file edit, I'll just call the API directly inside `fetch_exchange_rate` 
-    /// but limit it by checking if I already have a recent entry in the main `cache`?
-    /// No, `cache` stores specific pairs.
-    /// I should probably just fetch it. The user said "Free API", usually has limits but 
-    /// OpenER is generous. Let's add a small valid-time if possible, but 
-    /// effectively, `fetch_exchange_rate` is called only on cache miss of `get_rate`.
-    /// `get_rate` caches for 5 mins. So we verify only once per 5 mins per pair.
-    /// That is effectively 1 API call per 5 mins per active pair. Acceptable.
-
-    /// Mock forex rates (Removed, replaced by fetch_forex_rates_api logic inside fetch logic)
-
-    /// Mock BTC rate
-    fn get_mock_btc_rate(&self, from: &str, to: &str) -> f64 {
-        // Approximate rates (as of late 2024/early 2025)
-        let btc_usd = 100000.0;
-        let btc_thb = 3500000.0;
-        let btc_eur = 92600.0;    // ~92,600 EUR
-        let btc_gbp = 78700.0;    // ~78,700 GBP
-        let xau_usd = 2650.0;     // 1 oz gold = $2650
-
-        match (from, to) {
-            ("BTC", "USD") => btc_usd,
-            ("BTC", "THB") => btc_thb,
-            ("BTC", "EUR") => btc_eur,
-            ("BTC", "GBP") => btc_gbp,
-            ("BTC", "XAU") => btc_usd / xau_usd, // 1 BTC = X oz gold
-            ("USD", "BTC") => 1.0 / btc_usd,
-            ("THB", "BTC") => 1.0 / btc_thb,
-            ("EUR", "BTC") => 1.0 / btc_eur,
-            ("GBP", "BTC") => 1.0 / btc_gbp,
-            ("XAU", "BTC") => xau_usd / btc_usd, // 1 oz gold = X BTC
-            _ => 1.0,
+    /// Whether FX_ALLOWED_PAIRS lets `from` be converted to `to` (either direction)
+    pub fn pair_allowed(&self, from: &str, to: &str) -> bool {
+        let pairs = &self.config.fx_allowed_pairs;
+        pairs.is_empty() || pairs.iter().any(|pair| pair.matches(from, to) || pair.matches(to, from))
+    }
+
+    /// Get the exchange rate that applied on a given date (ECB reference rates via frankfurter.app)
+    pub async fn get_historical_rate(&self, from: &str, to: &str, date: NaiveDate) -> Result<f64, AppError> {
+        let from_upper = historical_code(from);
+        let to_upper = historical_code(to);
+        if from_upper == to_upper {
+            return Ok(1.0);
         }
+        if !self.pair_allowed(from, to) {
+            return Err(AppError::BadRequest(format!(
+                "Exchange rate {}/{} is not enabled (FX_ALLOWED_PAIRS)",
+                from.to_uppercase(), to.to_uppercase()
+            )));
+        }
+
+        let cache_key = format!("{}:{}:{}", from_upper, to_upper, date);
+        if let Some(rate) = self.history_cache.read().await.get(&cache_key) {
+            return Ok(*rate);
+        }
+
+        let url = format!(
+            "https://api.frankfurter.app/{}?from={}&to={}",
+            date.format("%Y-%m-%d"), from_upper, to_upper
+        );
+        tracing::debug!("Fetching historical exchange rate from {}", url);
+
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalApiError(format!("Failed to fetch historical rate: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::ExternalApiError(format!("Historical forex API error: {}", response.status())));
+        }
+
+        // Format: { "date": "2024-01-02", "rates": { "THB": 34.2 } }
+        let data: serde_json::Value = response.json().await
+            .map_err(|e| AppError::ExternalApiError(format!("Failed to parse historical rate: {}", e)))?;
+
+        let rate = data
+            .get("rates")
+            .and_then(|r| r.get(&to_upper))
+            .and_then(|v| v.as_f64())
+            .ok_or_else(|| AppError::ExternalApiError(format!("No historical rate for {}/{} on {}", from_upper, to_upper, date)))?;
+
+        self.history_cache.write().await.insert(cache_key, rate);
+        Ok(rate)
+    }
+
+    /// Historical rate as a quote dated at the start of `date`, for conversion trails
+    pub async fn get_historical_quote(&self, from: &str, to: &str, date: NaiveDate) -> Result<ExchangeRate, AppError> {
+        let rate = self.get_historical_rate(from, to, date).await?;
+        let source = if historical_code(from) == historical_code(to) { "usd_peg" } else { "frankfurter.app (ECB)" };
+        Ok(ExchangeRate {
+            from_currency: from.to_uppercase(),
+            to_currency: to.to_uppercase(),
+            rate,
+            updated_at: date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+            source: source.to_string(),
+        })
     }
 
-    /// Get all exchange rates for a base currency
+    /// Get the rates from a base currency to each FX_CURRENCIES entry it may be converted to
     pub async fn get_all_rates(&self, base: &str) -> Result<ExchangeRatesResponse, AppError> {
-        let currencies = vec!["USD", "THB", "BTC", "EUR", "GBP", "XAU", "USDT"];
+        let base_upper = base.to_uppercase();
         let mut rates = HashMap::new();
 
-        for currency in currencies {
-            if currency != base.to_uppercase() {
-                let rate = self.get_rate(base, currency).await?;
-                rates.insert(currency.to_string(), rate);
+        for currency in &self.config.fx_currencies {
+            if *currency != base_upper && self.pair_allowed(&base_upper, currency) {
+                let rate = self.get_rate(&base_upper, currency).await?;
+                rates.insert(currency.clone(), rate);
             }
         }
 
@@ -349,8 +469,7 @@ impl ExchangeRateService {
 
     /// Clear cache
     pub async fn clear_cache(&self) {
-        let mut cache = self.cache.write().await;
-        cache.clear();
+        self.cache.clear_classes(&[EndpointClass::Fx]).await;
         tracing::info!("Exchange rate cache cleared");
     }
 }
@@ -0,0 +1,397 @@
+//! Pulls a user's Binance trade history with their stored key into a review area
+//! (staged_trades). Nothing becomes a transaction until the user imports it; the import is
+//! then reconciled against the live wallet so missing deposits or withdrawals show up.
+
+use std::collections::{BTreeSet, HashMap};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::models::{
+    AssetType, CreateTransactionRequest, ExchangeBalance, ExchangeKey, Market, StagedTrade, StagedTradeStatus,
+    TradeAction, Transaction, STAGED_TRADES_COLLECTION,
+};
+use crate::services::crypto_import::is_usd_stablecoin;
+use crate::services::credentials::CredentialStore;
+use crate::services::import_reconcile::{import_tag, new_import_id, reconcile, ReconcileReport, ReconcileRequest, ReportedHolding};
+use crate::services::pocketbase::PocketBaseClient;
+use crate::services::price_service::{HistoryEntry, PriceService};
+use crate::services::providers::binance::{AccountTrade, ACCOUNT_TRADES_PAGE};
+
+/// Quote assets tried for every asset the user holds or has traded
+const QUOTE_ASSETS: &[&str] = &["USDT", "USDC", "FDUSD", "BTC", "ETH", "BNB"];
+
+/// Pages fetched per pair in one sync; the next sync carries on from the last staged trade
+const MAX_PAGES_PER_PAIR: usize = 20;
+
+/// Tags on every synced transaction
+const SYNC_TAGS: &[&str] = &["imported", "binance_sync"];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SyncRequest {
+    /// Pairs to sync, e.g. ["ETHBTC"]; by default every listed pair of a held or traded asset
+    #[serde(default)]
+    pub pairs: Vec<String>,
+    /// Account the transactions are booked to
+    pub account_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncReport {
+    pub key_id: String,
+    pub exchange: Market,
+    pub pairs_checked: usize,
+    pub trades_fetched: usize,
+    /// Newly staged by this sync
+    pub staged: usize,
+    /// Staged trades waiting for review, including earlier ones
+    pub pending: usize,
+    /// Set when the sync stopped early (rate limit, exchange error); the next sync resumes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incomplete: Option<String>,
+    pub balances: Vec<ExchangeBalance>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StagedImportReport {
+    /// Tags the created transactions, see `/import/:id/reconcile`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import_id: Option<String>,
+    pub trades_imported: usize,
+    pub transactions_created: usize,
+    /// Trades left pending, with the reason
+    pub skipped: Vec<String>,
+    /// The account's holdings after the import against the exchange wallet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reconcile: Option<ReconcileReport>,
+}
+
+pub struct ExchangeSyncService {
+    db: PocketBaseClient,
+    store: CredentialStore,
+    price_service: PriceService,
+}
+
+impl ExchangeSyncService {
+    pub fn new(db: PocketBaseClient, store: CredentialStore, price_service: PriceService) -> Self {
+        Self { db, store, price_service }
+    }
+
+    pub async fn staged(&self, user_id: &str, key_id: &str, status: Option<StagedTradeStatus>) -> Result<Vec<StagedTrade>, AppError> {
+        self.store.get(user_id, key_id).await?;
+        let mut filter = format!("user_id='{}' && exchange_key_id='{}'", user_id, key_id);
+        if let Some(status) = status {
+            filter.push_str(&format!(" && status='{}'", serde_json::json!(status).as_str().unwrap_or_default()));
+        }
+        let mut trades: Vec<StagedTrade> = self.db.list_all_records(STAGED_TRADES_COLLECTION, Some(filter)).await?;
+        trades.sort_by(|a, b| a.traded_at.cmp(&b.traded_at).then(a.trade_id.cmp(&b.trade_id)));
+        Ok(trades)
+    }
+
+    /// Stage every trade since the last sync for review
+    pub async fn sync(&self, user_id: &str, key_id: &str, req: SyncRequest) -> Result<SyncReport, AppError> {
+        let key = self.store.get(user_id, key_id).await?;
+        if key.exchange != Market::Binance {
+            return Err(AppError::BadRequest(format!("Trade sync is not available for {} keys", key.exchange)));
+        }
+        let credentials = self.store.credentials(&key)?;
+        let balances = match self.price_service.account_balances(&key.exchange, &credentials).await {
+            Ok(balances) => balances,
+            Err(e) => {
+                self.store.record_use(&key, Some(&e.to_string())).await;
+                return Err(e);
+            }
+        };
+
+        let staged = self.staged(user_id, key_id, None).await?;
+        // Next trade id to ask for, per pair
+        let mut cursors: HashMap<String, u64> = HashMap::new();
+        for trade in &staged {
+            let next = cursors.entry(trade.pair.clone()).or_insert(0);
+            *next = (*next).max(trade.trade_id + 1);
+        }
+
+        let pairs = if req.pairs.is_empty() {
+            let transactions = self.db.list_transactions(user_id).await?;
+            let listed = self.price_service.binance_spot_pairs().await?;
+            candidate_pairs(&balances, &transactions, cursors.keys(), |pair| listed.contains(pair))
+        } else {
+            req.pairs.iter()
+                .filter_map(|pair| split_pair(&pair.trim().to_uppercase()))
+                .collect()
+        };
+
+        let mut report = SyncReport {
+            key_id: key.id.clone(),
+            exchange: key.exchange.clone(),
+            pairs_checked: 0,
+            trades_fetched: 0,
+            staged: 0,
+            pending: staged.iter().filter(|t| t.status == StagedTradeStatus::Pending).count(),
+            incomplete: None,
+            balances,
+        };
+        let mut quote_history: HashMap<String, Option<Vec<HistoryEntry>>> = HashMap::new();
+        'pairs: for (base, quote) in &pairs {
+            let pair = format!("{}{}", base, quote);
+            let mut from_id = cursors.get(&pair).copied().unwrap_or(0);
+            report.pairs_checked += 1;
+            for _ in 0..MAX_PAGES_PER_PAIR {
+                let trades = match self.price_service.binance_account_trades(&credentials, &pair, from_id).await {
+                    Ok(trades) => trades,
+                    Err(e) => {
+                        report.incomplete = Some(format!("{}: {}", pair, e));
+                        break 'pairs;
+                    }
+                };
+                report.trades_fetched += trades.len();
+                for trade in &trades {
+                    let quote_usd = if is_usd_stablecoin(quote) {
+                        None
+                    } else {
+                        self.usd_close(&mut quote_history, quote, trade.time).await
+                    };
+                    let (transactions, errors) = match trade_transactions(trade, base, quote, quote_usd, &req.account_id) {
+                        Ok(transactions) => (transactions, Vec::new()),
+                        Err(error) => (Vec::new(), vec![error]),
+                    };
+                    let body = serde_json::json!({
+                        "user_id": user_id,
+                        "exchange_key_id": key.id,
+                        "exchange": key.exchange,
+                        "pair": pair,
+                        "trade_id": trade.id,
+                        "traded_at": trade.time,
+                        "transactions": transactions,
+                        "errors": errors,
+                        "status": StagedTradeStatus::Pending,
+                    });
+                    self.db.create_record::<serde_json::Value>(STAGED_TRADES_COLLECTION, &body).await?;
+                    report.staged += 1;
+                    report.pending += 1;
+                }
+                match trades.last() {
+                    Some(last) if trades.len() >= ACCOUNT_TRADES_PAGE => from_id = last.id + 1,
+                    _ => break,
+                }
+            }
+        }
+
+        self.store.record_use(&key, report.incomplete.as_deref()).await;
+        tracing::info!(
+            "🔄 Binance sync for {}: {} pairs, {} trades staged{}",
+            user_id, report.pairs_checked, report.staged,
+            report.incomplete.as_deref().map(|e| format!(" (stopped: {})", e)).unwrap_or_default()
+        );
+        Ok(report)
+    }
+
+    /// Turn pending staged trades into transactions under one import id. Trades with errors
+    /// stay pending.
+    pub async fn import(&self, user_id: &str, key_id: &str, ids: &[String]) -> Result<StagedImportReport, AppError> {
+        let key = self.store.get(user_id, key_id).await?;
+        let pending: Vec<StagedTrade> = self.staged(user_id, key_id, Some(StagedTradeStatus::Pending)).await?
+            .into_iter()
+            .filter(|t| ids.is_empty() || ids.contains(&t.id))
+            .collect();
+
+        let mut report = StagedImportReport {
+            import_id: None,
+            trades_imported: 0,
+            transactions_created: 0,
+            skipped: Vec::new(),
+            reconcile: None,
+        };
+        let import_id = new_import_id();
+        let tag = import_tag(&import_id);
+        let mut account_id = None;
+        for trade in &pending {
+            if !trade.errors.is_empty() {
+                report.skipped.push(format!("{} trade {}: {}", trade.pair, trade.trade_id, trade.errors.join("; ")));
+                continue;
+            }
+            let mut created = 0;
+            for tx in &trade.transactions {
+                let mut tx = tx.clone();
+                tx.tags.push(tag.clone());
+                account_id = account_id.or(tx.account_id.clone());
+                match self.db.create_transaction(tx, user_id).await {
+                    Ok(_) => created += 1,
+                    Err(e) => report.skipped.push(format!("{} trade {}: {}", trade.pair, trade.trade_id, e)),
+                }
+            }
+            report.transactions_created += created;
+            // A partly created trade is marked imported too, so it can't be created twice
+            if created > 0 {
+                report.trades_imported += 1;
+                let body = serde_json::json!({ "status": StagedTradeStatus::Imported, "import_id": import_id });
+                self.db.update_record::<serde_json::Value>(STAGED_TRADES_COLLECTION, &trade.id, &body).await?;
+            }
+        }
+        if report.trades_imported == 0 {
+            return Ok(report);
+        }
+        tracing::info!("✅ Imported {} Binance trades as {}", report.trades_imported, import_id);
+
+        report.reconcile = self.reconcile_with_wallet(user_id, &key, &import_id, account_id).await;
+        report.import_id = Some(import_id);
+        Ok(report)
+    }
+
+    pub async fn dismiss(&self, user_id: &str, key_id: &str, ids: &[String]) -> Result<usize, AppError> {
+        let pending = self.staged(user_id, key_id, Some(StagedTradeStatus::Pending)).await?;
+        let mut dismissed = 0;
+        for trade in pending.iter().filter(|t| ids.is_empty() || ids.contains(&t.id)) {
+            let body = serde_json::json!({ "status": StagedTradeStatus::Dismissed });
+            self.db.update_record::<serde_json::Value>(STAGED_TRADES_COLLECTION, &trade.id, &body).await?;
+            dismissed += 1;
+        }
+        Ok(dismissed)
+    }
+
+    /// Holdings computed from the transactions against the wallet; None when the wallet can't be read
+    async fn reconcile_with_wallet(&self, user_id: &str, key: &ExchangeKey, import_id: &str, account_id: Option<String>) -> Option<ReconcileReport> {
+        let credentials = self.store.credentials(key).ok()?;
+        let balances = match self.price_service.account_balances(&key.exchange, &credentials).await {
+            Ok(balances) => balances,
+            Err(e) => {
+                tracing::warn!("Could not read the wallet to reconcile import {}: {}", import_id, e);
+                return None;
+            }
+        };
+        let transactions = self.db.list_transactions(user_id).await.ok()?;
+        let req = ReconcileRequest {
+            as_of: None,
+            holdings: balances.iter()
+                .map(|b| ReportedHolding { symbol: b.asset.clone(), asset_type: AssetType::Crypto, quantity: b.total() })
+                .collect(),
+            balances: HashMap::new(),
+            tolerance: None,
+        };
+        Some(reconcile(import_id, account_id, Utc::now(), &transactions, &req))
+    }
+
+    /// USD close of a crypto on the day of `at`, from a history fetched once per asset
+    async fn usd_close(
+        &self,
+        histories: &mut HashMap<String, Option<Vec<HistoryEntry>>>,
+        asset: &str,
+        at: DateTime<Utc>,
+    ) -> Option<f64> {
+        if !histories.contains_key(asset) {
+            // Enough days to cover the oldest trades; the providers cap long ranges themselves
+            let days = (Utc::now() - at).num_days().max(1) as u32 + 365;
+            let history = self.price_service.get_price_history(asset, &AssetType::Crypto, None, days).await
+                .map_err(|e| tracing::warn!("No USD history for {}: {}", asset, e))
+                .ok();
+            histories.insert(asset.to_string(), history);
+        }
+        let date = at.format("%Y-%m-%d").to_string();
+        histories.get(asset)?.as_ref()?
+            .iter()
+            .find(|h| h.date == date)
+            .map(|h| h.price)
+            .filter(|p| *p > 0.0)
+    }
+}
+
+/// (base, quote) of a pair ending in one of QUOTE_ASSETS
+fn split_pair(pair: &str) -> Option<(String, String)> {
+    QUOTE_ASSETS.iter()
+        .filter_map(|quote| pair.strip_suffix(quote).filter(|base| !base.is_empty()).map(|base| (base.to_string(), quote.to_string())))
+        .next()
+}
+
+/// Listed pairs of every asset in the wallet or traded on Binance before, plus pairs synced
+/// earlier (the asset may have been sold since)
+fn candidate_pairs<'a>(
+    balances: &[ExchangeBalance],
+    transactions: &[Transaction],
+    synced: impl Iterator<Item = &'a String>,
+    listed: impl Fn(&str) -> bool,
+) -> Vec<(String, String)> {
+    let mut assets: BTreeSet<String> = balances.iter().map(|b| b.asset.clone()).collect();
+    assets.extend(transactions.iter()
+        .filter(|t| t.asset_type == AssetType::Crypto && t.market == Some(Market::Binance))
+        .map(|t| t.symbol.to_uppercase()));
+
+    let mut pairs: BTreeSet<(String, String)> = synced.filter_map(|pair| split_pair(pair)).collect();
+    for base in &assets {
+        for quote in QUOTE_ASSETS {
+            if base != quote && listed(&format!("{}{}", base, quote)) {
+                pairs.insert((base.clone(), quote.to_string()));
+            }
+        }
+    }
+    pairs.into_iter().collect()
+}
+
+/// A fill as transactions: the base asset bought or sold and the quote asset paid or received.
+/// Pairs quoted in a USD stablecoin are booked in that coin; others in USD at the quote asset's
+/// daily close. Commission in either traded asset adjusts that leg's quantity and is added to
+/// the fees; commission in a third asset (BNB) is a separate withdrawal tagged "fee".
+pub fn trade_transactions(
+    trade: &AccountTrade,
+    base: &str,
+    quote: &str,
+    quote_usd: Option<f64>,
+    account_id: &Option<String>,
+) -> Result<Vec<CreateTransactionRequest>, String> {
+    let (currency, quote_price) = if is_usd_stablecoin(quote) {
+        (quote.to_string(), 1.0)
+    } else {
+        let usd = quote_usd.ok_or_else(|| format!(
+            "no USD price for {} on {}", quote, trade.time.format("%Y-%m-%d")
+        ))?;
+        ("USD".to_string(), usd)
+    };
+    let base_price = trade.price * quote_price;
+    let notes = format!("Binance trade {} ({}{})", trade.id, base, quote);
+    let leg = |action: TradeAction, symbol: &str, quantity: f64, price: f64, tags: &[&str]| CreateTransactionRequest {
+        asset_type: AssetType::Crypto,
+        symbol: symbol.to_string(),
+        symbol_name: None,
+        action,
+        quantity,
+        price,
+        fees: 0.0,
+        network_fee: 0.0,
+        timestamp: trade.time,
+        market: Some(Market::Binance),
+        currency: Some(currency.clone()),
+        notes: Some(notes.clone()),
+        account_id: account_id.clone(),
+        tags: SYNC_TAGS.iter().chain(tags).map(|t| t.to_string()).collect(),
+        leverage: None,
+        initial_margin: None,
+        unit: None,
+        custom_fields: Default::default(),
+    };
+
+    // Quantity the account gained (+) or lost (-) per leg
+    let side = if trade.is_buyer { 1.0 } else { -1.0 };
+    let mut base_quantity = trade.quantity * side;
+    let mut quote_quantity = -trade.quote_quantity * side;
+    let mut fees = 0.0;
+    let mut fee_leg = None;
+    if trade.commission > 0.0 {
+        if trade.commission_asset == base {
+            base_quantity -= trade.commission;
+            fees = trade.commission * base_price;
+        } else if trade.commission_asset == quote {
+            quote_quantity -= trade.commission;
+            fees = trade.commission * quote_price;
+        } else {
+            fee_leg = Some(leg(TradeAction::Withdraw, &trade.commission_asset, trade.commission, 0.0, &["fee"]));
+        }
+    }
+
+    let action = |quantity: f64| if quantity >= 0.0 { TradeAction::Buy } else { TradeAction::Sell };
+    let mut base_leg = leg(action(base_quantity), base, base_quantity.abs(), base_price, &[]);
+    base_leg.fees = fees;
+    let mut transactions = vec![base_leg];
+    if quote_quantity != 0.0 {
+        transactions.push(leg(action(quote_quantity), quote, quote_quantity.abs(), quote_price, &[]));
+    }
+    transactions.extend(fee_leg);
+    Ok(transactions)
+}
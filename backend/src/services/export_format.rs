@@ -0,0 +1,251 @@
+//! Locale-aware rendering of numbers and dates in CSV exports.
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::Serialize;
+use crate::error::AppError;
+use crate::models::{UserSettings, USER_SETTINGS_COLLECTION};
+use crate::services::PocketBaseClient;
+
+/// Years between the Gregorian and Thai Buddhist calendars
+const BUDDHIST_ERA_OFFSET: i32 = 543;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecimalSeparator {
+    #[default]
+    Point,
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Calendar {
+    #[default]
+    Gregorian,
+    Buddhist,
+}
+
+/// Defaults picked by a locale tag; explicit settings override them
+struct LocalePreset {
+    tag: &'static str,
+    decimal_separator: DecimalSeparator,
+    calendar: Calendar,
+    date_pattern: &'static str,
+}
+
+const LOCALE_PRESETS: &[LocalePreset] = &[
+    LocalePreset { tag: "th", decimal_separator: DecimalSeparator::Point, calendar: Calendar::Buddhist, date_pattern: "DD/MM/YYYY" },
+    LocalePreset { tag: "en-us", decimal_separator: DecimalSeparator::Point, calendar: Calendar::Gregorian, date_pattern: "MM/DD/YYYY" },
+    LocalePreset { tag: "en", decimal_separator: DecimalSeparator::Point, calendar: Calendar::Gregorian, date_pattern: "DD/MM/YYYY" },
+    LocalePreset { tag: "de", decimal_separator: DecimalSeparator::Comma, calendar: Calendar::Gregorian, date_pattern: "DD.MM.YYYY" },
+    LocalePreset { tag: "fr", decimal_separator: DecimalSeparator::Comma, calendar: Calendar::Gregorian, date_pattern: "DD/MM/YYYY" },
+    LocalePreset { tag: "nl", decimal_separator: DecimalSeparator::Comma, calendar: Calendar::Gregorian, date_pattern: "DD-MM-YYYY" },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateToken {
+    Year4,
+    Year2,
+    Month2,
+    Month,
+    Day2,
+    Day,
+    Literal(char),
+}
+
+/// How numbers and dates are written in a user's exports. The default keeps the
+/// machine-friendly output: decimal point, ISO dates and RFC 3339 timestamps.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportLocale {
+    pub decimal_separator: DecimalSeparator,
+    pub calendar: Calendar,
+    /// None writes ISO dates (YYYY-MM-DD)
+    pub date_pattern: Option<String>,
+    #[serde(skip)]
+    tokens: Vec<DateToken>,
+}
+
+impl ExportLocale {
+    /// Combine the settings with their locale preset, rejecting unknown values
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, String> {
+        let preset = settings.locale.as_deref().map(|tag| {
+            let tag = tag.trim().to_lowercase().replace('_', "-");
+            LOCALE_PRESETS.iter()
+                .find(|p| tag == p.tag || tag.starts_with(&format!("{}-", p.tag)))
+                .ok_or_else(|| format!(
+                    "Unknown locale '{}', expected one of: {}",
+                    tag,
+                    LOCALE_PRESETS.iter().map(|p| p.tag).collect::<Vec<_>>().join(", ")
+                ))
+        }).transpose()?;
+
+        let decimal_separator = match settings.decimal_separator.as_deref().map(str::trim) {
+            Some("point") | Some(".") => DecimalSeparator::Point,
+            Some("comma") | Some(",") => DecimalSeparator::Comma,
+            Some(other) => return Err(format!("Unknown decimal_separator '{}', expected point or comma", other)),
+            None => preset.map(|p| p.decimal_separator).unwrap_or_default(),
+        };
+        let calendar = match settings.calendar.as_deref().map(str::trim) {
+            Some("gregorian") => Calendar::Gregorian,
+            Some("buddhist") => Calendar::Buddhist,
+            Some(other) => return Err(format!("Unknown calendar '{}', expected gregorian or buddhist", other)),
+            None => preset.map(|p| p.calendar).unwrap_or_default(),
+        };
+        let date_pattern = settings.date_pattern.clone()
+            .or_else(|| preset.map(|p| p.date_pattern.to_string()));
+        let tokens = match &date_pattern {
+            Some(pattern) => parse_date_pattern(pattern)?,
+            None => Vec::new(),
+        };
+        Ok(Self { decimal_separator, calendar, date_pattern, tokens })
+    }
+
+    fn is_default(&self) -> bool {
+        self.decimal_separator == DecimalSeparator::Point
+            && self.calendar == Calendar::Gregorian
+            && self.date_pattern.is_none()
+    }
+
+    /// Semicolons when commas are taken by decimals, as spreadsheet apps expect
+    pub fn field_separator(&self) -> char {
+        match self.decimal_separator {
+            DecimalSeparator::Point => ',',
+            DecimalSeparator::Comma => ';',
+        }
+    }
+
+    pub fn number(&self, value: f64) -> String {
+        self.localize_decimal(value.to_string())
+    }
+
+    /// Number rounded to `decimals` places
+    pub fn fixed(&self, value: f64, decimals: usize) -> String {
+        self.localize_decimal(format!("{:.*}", decimals, value))
+    }
+
+    fn localize_decimal(&self, formatted: String) -> String {
+        match self.decimal_separator {
+            DecimalSeparator::Point => formatted,
+            DecimalSeparator::Comma => formatted.replace('.', ","),
+        }
+    }
+
+    /// A JSON scalar from a stored record; numbers are localized, strings kept as they are
+    pub fn value(&self, value: Option<&serde_json::Value>) -> String {
+        match value {
+            Some(serde_json::Value::Number(n)) => n.as_f64().map(|v| self.number(v)).unwrap_or_else(|| n.to_string()),
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(serde_json::Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        }
+    }
+
+    pub fn date(&self, date: NaiveDate) -> String {
+        let year = match self.calendar {
+            Calendar::Gregorian => date.year(),
+            Calendar::Buddhist => date.year() + BUDDHIST_ERA_OFFSET,
+        };
+        if self.tokens.is_empty() {
+            return format!("{:04}-{:02}-{:02}", year, date.month(), date.day());
+        }
+        self.tokens.iter()
+            .map(|token| match token {
+                DateToken::Year4 => format!("{:04}", year),
+                DateToken::Year2 => format!("{:02}", year.rem_euclid(100)),
+                DateToken::Month2 => format!("{:02}", date.month()),
+                DateToken::Month => date.month().to_string(),
+                DateToken::Day2 => format!("{:02}", date.day()),
+                DateToken::Day => date.day().to_string(),
+                DateToken::Literal(c) => c.to_string(),
+            })
+            .collect()
+    }
+
+    /// A "YYYY-MM-DD" (or longer) date string; anything unparseable is kept as it is
+    pub fn date_str(&self, value: &str) -> String {
+        match value.get(..10).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()) {
+            Some(date) => self.date(date),
+            None => value.to_string(),
+        }
+    }
+
+    /// RFC 3339 by default, otherwise the localized date and UTC time of day
+    pub fn timestamp(&self, at: DateTime<Utc>) -> String {
+        if self.is_default() {
+            return at.to_rfc3339();
+        }
+        format!("{} {}", self.date(at.date_naive()), at.format("%H:%M:%S"))
+    }
+
+    /// A header row written with this locale's field separator
+    pub fn header(&self, columns: &str) -> String {
+        columns.replace(',', &self.field_separator().to_string())
+    }
+
+    /// One CSV line, quoting fields that contain the separator, quotes or line breaks
+    pub fn csv_line(&self, fields: &[String]) -> String {
+        let separator = self.field_separator();
+        let mut line = fields.iter()
+            .map(|value| if value.contains([separator, '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.clone()
+            })
+            .collect::<Vec<_>>()
+            .join(&separator.to_string());
+        line.push('\n');
+        line
+    }
+}
+
+fn parse_date_pattern(pattern: &str) -> Result<Vec<DateToken>, String> {
+    let chars: Vec<char> = pattern.trim().chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let run = chars[i..].iter().take_while(|&&x| x == c).count();
+        let token = match (c, run) {
+            ('Y', 4) => DateToken::Year4,
+            ('Y', 2) => DateToken::Year2,
+            ('M', 2) => DateToken::Month2,
+            ('M', 1) => DateToken::Month,
+            ('D', 2) => DateToken::Day2,
+            ('D', 1) => DateToken::Day,
+            (' ' | '/' | '-' | '.' | ',', _) => DateToken::Literal(c),
+            _ => return Err(format!(
+                "Invalid date_pattern '{}': use YYYY, YY, MM, M, DD and D separated by space, /, -, . or ,",
+                pattern
+            )),
+        };
+        let consumed = if matches!(token, DateToken::Literal(_)) { 1 } else { run };
+        tokens.push(token);
+        i += consumed;
+    }
+
+    let has = |wanted: &[DateToken]| tokens.iter().any(|t| wanted.contains(t));
+    if !has(&[DateToken::Year4, DateToken::Year2])
+        || !has(&[DateToken::Month2, DateToken::Month])
+        || !has(&[DateToken::Day2, DateToken::Day])
+    {
+        return Err(format!("Invalid date_pattern '{}': it needs a year, a month and a day", pattern));
+    }
+    Ok(tokens)
+}
+
+/// The user's stored settings (empty when they never saved any)
+pub async fn load_user_settings(db: &PocketBaseClient, user_id: &str) -> Result<UserSettings, AppError> {
+    let settings: Vec<UserSettings> = db
+        .list_records(USER_SETTINGS_COLLECTION, Some(format!("user_id='{}'", user_id)), "-updated")
+        .await?;
+    Ok(settings.into_iter().next().unwrap_or_default())
+}
+
+/// How the user's exports are formatted. Settings that no longer validate fall back to the default.
+pub async fn export_locale(db: &PocketBaseClient, user_id: &str) -> Result<ExportLocale, AppError> {
+    let settings = load_user_settings(db, user_id).await?;
+    Ok(ExportLocale::from_settings(&settings).unwrap_or_else(|e| {
+        tracing::warn!("⚠️ Ignoring export settings of {}: {}", user_id, e);
+        ExportLocale::default()
+    }))
+}
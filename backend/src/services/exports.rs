@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::services::object_storage::ObjectStorage;
+use crate::versioning::CURRENT_API_VERSION;
+
+const TOKEN_CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+const TOKEN_LENGTH: usize = 40;
+/// Lifetime of the presigned S3 URL a download token redirects to
+const REDIRECT_URL_SECONDS: u64 = 300;
+
+fn download_token() -> String {
+    let mut rng = rand::rng();
+    (0..TOKEN_LENGTH)
+        .map(|_| TOKEN_CHARSET[rng.random_range(0..TOKEN_CHARSET.len())] as char)
+        .collect()
+}
+
+/// What an export job produces
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    /// Every transaction as CSV, one column per custom field
+    TransactionsCsv,
+    /// Accounts, transactions and portfolio snapshots as one JSON document
+    FullHistoryJson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Handle for a background export; poll it until `download_url` is set
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportJob {
+    pub id: String,
+    #[serde(skip)]
+    pub user_id: String,
+    pub kind: ExportKind,
+    pub status: ExportStatus,
+    pub progress_percent: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    /// Works without a login until `expires_at`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    token: Option<String>,
+    #[serde(skip)]
+    content_type: String,
+    #[serde(skip)]
+    location: Option<ExportLocation>,
+}
+
+/// Where a finished export file lives
+#[derive(Debug, Clone)]
+enum ExportLocation {
+    File(PathBuf),
+    /// Key in the S3 bucket
+    Object(String),
+}
+
+/// How a download token is served
+pub enum ExportDownload {
+    File { file_name: String, content_type: String, bytes: Vec<u8> },
+    /// Short-lived presigned URL of the object in S3
+    Redirect(String),
+}
+
+/// Finished export content
+pub struct ExportFile {
+    pub file_name: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+    pub rows: usize,
+}
+
+/// Lets the export work report how far it got
+#[derive(Clone)]
+pub struct ExportProgress {
+    jobs: Arc<RwLock<HashMap<String, ExportJob>>>,
+    job_id: String,
+}
+
+impl ExportProgress {
+    pub async fn set(&self, percent: u8) {
+        if let Some(job) = self.jobs.write().await.get_mut(&self.job_id) {
+            job.progress_percent = percent.min(99);
+        }
+    }
+}
+
+/// Runs large exports in the background and serves the results from expiring download links
+#[derive(Clone)]
+pub struct ExportService {
+    dir: PathBuf,
+    link_ttl: Duration,
+    jobs: Arc<RwLock<HashMap<String, ExportJob>>>,
+    storage: Option<ObjectStorage>,
+}
+
+impl ExportService {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            dir: PathBuf::from(&config.export_dir),
+            link_ttl: Duration::hours(config.export_link_ttl_hours.max(1) as i64),
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            storage: None,
+        }
+    }
+
+    /// Keep finished exports in S3 instead of EXPORT_DIR
+    pub fn set_object_storage(&mut self, storage: ObjectStorage) {
+        self.storage = Some(storage);
+    }
+
+    /// Start the loop that deletes expired export files
+    pub fn start(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(600));
+            loop {
+                interval.tick().await;
+                service.prune().await;
+            }
+        });
+    }
+
+    /// Register a job and run `work` in the background, writing its output to S3 or EXPORT_DIR
+    pub async fn start_job<F, Fut>(&self, user_id: &str, kind: ExportKind, work: F) -> ExportJob
+    where
+        F: FnOnce(ExportProgress) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<ExportFile, AppError>> + Send + 'static,
+    {
+        let job = ExportJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            kind,
+            status: ExportStatus::Running,
+            progress_percent: 0,
+            rows: None,
+            file_name: None,
+            size_bytes: None,
+            download_url: None,
+            error: None,
+            created_at: Utc::now(),
+            finished_at: None,
+            expires_at: None,
+            token: None,
+            content_type: String::new(),
+            location: None,
+        };
+        self.jobs.write().await.insert(job.id.clone(), job.clone());
+        tracing::info!("📦 Export job {} ({:?}) started for {}", job.id, kind, user_id);
+
+        let service = self.clone();
+        let job_id = job.id.clone();
+        let progress = ExportProgress { jobs: self.jobs.clone(), job_id: job_id.clone() };
+        tokio::spawn(async move {
+            let result = match work(progress).await {
+                Ok(file) => service.store(&job_id, file).await,
+                Err(e) => Err(e),
+            };
+            let mut jobs = service.jobs.write().await;
+            let Some(job) = jobs.get_mut(&job_id) else { return };
+            job.finished_at = Some(Utc::now());
+            match result {
+                Ok(()) => {
+                    job.status = ExportStatus::Completed;
+                    job.progress_percent = 100;
+                    tracing::info!("✅ Export job {} done ({} bytes)", job.id, job.size_bytes.unwrap_or(0));
+                }
+                Err(e) => {
+                    job.status = ExportStatus::Failed;
+                    job.error = Some(e.to_string());
+                    tracing::error!("❌ Export job {} failed: {}", job.id, e);
+                }
+            }
+        });
+
+        job
+    }
+
+    async fn store(&self, job_id: &str, file: ExportFile) -> Result<(), AppError> {
+        let size_bytes = file.bytes.len() as u64;
+        let location = match &self.storage {
+            Some(storage) => {
+                let key = format!("exports/{}/{}", job_id, file.file_name);
+                storage.put_object(&key, file.bytes, &file.content_type).await?;
+                ExportLocation::Object(key)
+            }
+            None => {
+                tokio::fs::create_dir_all(&self.dir).await
+                    .map_err(|e| AppError::Internal(format!("Could not create export directory: {}", e)))?;
+                let path = self.dir.join(job_id);
+                tokio::fs::write(&path, &file.bytes).await
+                    .map_err(|e| AppError::Internal(format!("Could not write export file: {}", e)))?;
+                ExportLocation::File(path)
+            }
+        };
+
+        let token = download_token();
+        let mut jobs = self.jobs.write().await;
+        if let Some(job) = jobs.get_mut(job_id) {
+            job.rows = Some(file.rows);
+            job.file_name = Some(file.file_name);
+            job.size_bytes = Some(size_bytes);
+            job.download_url = Some(format!("/api/v{}/exports/download/{}", CURRENT_API_VERSION, token));
+            job.expires_at = Some(Utc::now() + self.link_ttl);
+            job.token = Some(token);
+            job.content_type = file.content_type;
+            job.location = Some(location);
+        }
+        Ok(())
+    }
+
+    /// An export job, only visible to the user who started it
+    pub async fn get_job(&self, user_id: &str, job_id: &str) -> Option<ExportJob> {
+        self.jobs.read().await
+            .get(job_id)
+            .filter(|job| job.user_id == user_id)
+            .cloned()
+    }
+
+    /// The user's export jobs, newest first
+    pub async fn list_jobs(&self, user_id: &str) -> Vec<ExportJob> {
+        let mut jobs: Vec<ExportJob> = self.jobs.read().await
+            .values()
+            .filter(|job| job.user_id == user_id)
+            .cloned()
+            .collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+        jobs
+    }
+
+    /// The file behind a download token that has not expired
+    pub async fn download(&self, token: &str) -> Result<ExportDownload, AppError> {
+        let not_found = || AppError::NotFound("Export not found or link expired".to_string());
+        let job = self.jobs.read().await
+            .values()
+            .find(|job| job.token.as_deref() == Some(token))
+            .cloned()
+            .ok_or_else(not_found)?;
+        if job.expires_at.is_none_or(|at| at <= Utc::now()) {
+            return Err(not_found());
+        }
+        let file_name = job.file_name.unwrap_or_else(|| job.id.clone());
+        match job.location.ok_or_else(not_found)? {
+            ExportLocation::File(path) => {
+                let bytes = tokio::fs::read(&path).await.map_err(|_| not_found())?;
+                Ok(ExportDownload::File { file_name, content_type: job.content_type, bytes })
+            }
+            ExportLocation::Object(key) => {
+                let storage = self.storage.as_ref().ok_or_else(not_found)?;
+                // The token governs access; the presigned URL only needs to outlive the redirect
+                let url = storage.presigned_get_url(&key, REDIRECT_URL_SECONDS, Some(&file_name));
+                Ok(ExportDownload::Redirect(url))
+            }
+        }
+    }
+
+    async fn prune(&self) {
+        let now = Utc::now();
+        let expired: Vec<ExportJob> = {
+            let mut jobs = self.jobs.write().await;
+            let expired = jobs.values()
+                .filter(|job| job.expires_at.is_some_and(|at| at <= now)
+                    || (job.status == ExportStatus::Failed && job.finished_at.is_some_and(|at| at + self.link_ttl <= now)))
+                .cloned()
+                .collect::<Vec<_>>();
+            for job in &expired {
+                jobs.remove(&job.id);
+            }
+            expired
+        };
+        for job in expired {
+            let result = match (job.location, &self.storage) {
+                (Some(ExportLocation::File(path)), _) => tokio::fs::remove_file(&path).await
+                    .map_err(|e| AppError::Internal(format!("{}: {}", path.display(), e))),
+                (Some(ExportLocation::Object(key)), Some(storage)) => storage.delete_object(&key).await,
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                tracing::warn!("⚠️ Could not delete expired export {}: {}", job.id, e);
+            }
+        }
+    }
+}
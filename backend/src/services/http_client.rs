@@ -0,0 +1,127 @@
+//! Outbound HTTP clients shared by every service.
+//!
+//! `reqwest::Client` keeps its connection pool internally, so building one per request (or per
+//! service) throws away warm TLS connections. Services clone one of these instead; pool sizes,
+//! timeouts, HTTP/2 keepalive and the outbound proxy all come from [`Config`].
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use crate::config::Config;
+
+/// One pooled client per kind of upstream
+pub struct HttpClients {
+    /// Price, FX and symbol providers: request timeout and outbound proxy applied
+    pub providers: reqwest::Client,
+    /// PocketBase and object storage: no overall timeout (bulk list, seed and upload requests
+    /// can be slow), never proxied
+    pub internal: reqwest::Client,
+    /// OAuth and OIDC endpoints: request timeout and outbound proxy applied
+    pub external: reqwest::Client,
+}
+
+static CLIENTS: OnceLock<HttpClients> = OnceLock::new();
+
+/// Provider clients for per-provider proxies, one per proxy URL so they keep their own pool
+static PROXIED: OnceLock<Mutex<HashMap<String, reqwest::Client>>> = OnceLock::new();
+
+/// `api_providers.proxy_url` value that sends a provider's requests around OUTBOUND_PROXY
+pub const DIRECT: &str = "direct";
+
+/// The process-wide clients, built from `config` on first use
+pub fn clients(config: &Config) -> &'static HttpClients {
+    CLIENTS.get_or_init(|| {
+        tracing::info!(
+            "🌐 HTTP clients: {} idle conns/host, {}s connect / {}s request timeout, proxy {}",
+            config.http_pool_max_idle_per_host,
+            config.http_connect_timeout_seconds,
+            config.http_timeout_seconds,
+            if config.outbound_proxy.is_some() { "on" } else { "off" },
+        );
+        HttpClients {
+            providers: build(config, true),
+            internal: build(config, false),
+            external: build(config, true),
+        }
+    })
+}
+
+/// Provider client routed through `proxy_url` (or no proxy at all for "direct") instead of
+/// OUTBOUND_PROXY, built on first use and shared afterwards
+pub fn proxied(config: &Config, proxy_url: &str) -> Result<reqwest::Client, String> {
+    let cache = PROXIED.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(client) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(proxy_url) {
+        return Ok(client.clone());
+    }
+
+    let builder = base_builder(config).timeout(Duration::from_secs(config.http_timeout_seconds));
+    let builder = if proxy_url.eq_ignore_ascii_case(DIRECT) {
+        builder.no_proxy()
+    } else {
+        builder.proxy(parse_proxy(proxy_url)?)
+    };
+    let client = builder.build().map_err(|e| e.to_string())?;
+    tracing::info!("🌐 Built provider client for proxy {}", redact_proxy_url(proxy_url));
+    cache.lock().unwrap_or_else(|e| e.into_inner()).insert(proxy_url.to_string(), client.clone());
+    Ok(client)
+}
+
+/// Check a proxy URL: http, https, socks5 or socks5h (DNS resolved by the proxy)
+pub fn parse_proxy(url: &str) -> Result<reqwest::Proxy, String> {
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+    if !matches!(scheme.as_deref(), Some("http" | "https" | "socks5" | "socks5h")) {
+        return Err(format!(
+            "Unsupported proxy '{}': use an http://, https://, socks5:// or socks5h:// URL",
+            redact_proxy_url(url)
+        ));
+    }
+    reqwest::Proxy::all(url).map_err(|e| format!("Invalid proxy '{}': {}", redact_proxy_url(url), e))
+}
+
+/// Proxy URL with its password masked, for logs and API responses
+pub fn redact_proxy_url(url: &str) -> String {
+    crate::services::diagnostics::redact_url(url)
+}
+
+fn base_builder(config: &Config) -> reqwest::ClientBuilder {
+    let mut builder = reqwest::Client::builder()
+        .pool_max_idle_per_host(config.http_pool_max_idle_per_host)
+        .pool_idle_timeout(Duration::from_secs(config.http_pool_idle_timeout_seconds))
+        .connect_timeout(Duration::from_secs(config.http_connect_timeout_seconds))
+        .tcp_keepalive(Duration::from_secs(60));
+    if config.http2_keepalive_seconds > 0 {
+        builder = builder
+            .http2_keep_alive_interval(Duration::from_secs(config.http2_keepalive_seconds))
+            .http2_keep_alive_timeout(Duration::from_secs(10))
+            .http2_keep_alive_while_idle(true);
+    }
+    builder
+}
+
+fn build(config: &Config, outbound: bool) -> reqwest::Client {
+    let mut builder = base_builder(config);
+    if outbound {
+        builder = builder.timeout(Duration::from_secs(config.http_timeout_seconds));
+        if let Some(url) = config.outbound_proxy.as_deref() {
+            match parse_proxy(url) {
+                Ok(proxy) => builder = builder.proxy(proxy),
+                Err(e) => tracing::warn!("⚠️ Ignoring OUTBOUND_PROXY: {}", e),
+            }
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        tracing::warn!("⚠️ Could not build tuned HTTP client ({}), using defaults", e);
+        reqwest::Client::new()
+    })
+}
+
+/// Parse PROVIDER_TIMEOUTS ("yahoo_finance=15,binance=5") into seconds per rate-limiter bucket
+pub fn parse_provider_timeouts(spec: &str) -> HashMap<String, u64> {
+    spec.split(',')
+        .filter_map(|item| {
+            let (name, seconds) = item.split_once('=')?;
+            let seconds = seconds.trim().parse::<u64>().ok().filter(|s| *s > 0)?;
+            Some((name.trim().to_lowercase(), seconds))
+        })
+        .collect()
+}
@@ -0,0 +1,236 @@
+//! Reconcile an import against the end-of-period totals on the broker's statement.
+
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::{AssetType, TradeAction, Transaction};
+use crate::services::lot_engine::{self, DustPolicy};
+
+/// Tag prefix that ties transactions to the import that created them
+pub const IMPORT_TAG_PREFIX: &str = "import:";
+
+/// Quantity difference still counted as a match when the request gives none
+const DEFAULT_TOLERANCE: f64 = 0.00000001;
+
+pub fn import_tag(import_id: &str) -> String {
+    format!("{}{}", IMPORT_TAG_PREFIX, import_id)
+}
+
+/// New import id, also the suffix of the tag on every transaction it creates
+pub fn new_import_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..12].to_string()
+}
+
+/// A position as the broker reports it
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportedHolding {
+    pub symbol: String,
+    /// Defaults to crypto, like the import
+    #[serde(default = "default_asset_type")]
+    pub asset_type: AssetType,
+    pub quantity: f64,
+}
+
+fn default_asset_type() -> AssetType {
+    AssetType::Crypto
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReconcileRequest {
+    /// End of the statement period; defaults to the last imported transaction
+    pub as_of: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub holdings: Vec<ReportedHolding>,
+    /// Reported cash balances by currency
+    #[serde(default)]
+    pub balances: HashMap<String, f64>,
+    /// Largest quantity difference still counted as a match
+    pub tolerance: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconcileStatus {
+    Matched,
+    Mismatched,
+    /// On the statement but not held according to the transactions
+    MissingFromPortfolio,
+    /// Held according to the transactions but not on the statement
+    NotOnStatement,
+}
+
+/// Imported transaction that may explain a difference
+#[derive(Debug, Clone, Serialize)]
+pub struct SuspectTransaction {
+    pub id: String,
+    pub action: TradeAction,
+    pub quantity: f64,
+    pub timestamp: DateTime<Utc>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconcileLine {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub reported_quantity: f64,
+    pub computed_quantity: f64,
+    /// Computed minus reported
+    pub difference: f64,
+    pub status: ReconcileStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suspects: Vec<SuspectTransaction>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReconcileReport {
+    pub import_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    pub as_of: DateTime<Utc>,
+    pub import_transactions: usize,
+    /// Every reported line matched and nothing held is missing from the statement
+    pub reconciled: bool,
+    pub matched: usize,
+    pub differences: usize,
+    /// Differences first, then matches
+    pub lines: Vec<ReconcileLine>,
+}
+
+/// Diff the reported totals against holdings replayed from every transaction in the import's
+/// account up to `as_of`. Computed positions are only compared for the sections the statement
+/// covers: non-cash holdings when `holdings` is given, cash when `balances` is.
+pub fn reconcile(
+    import_id: &str,
+    account_id: Option<String>,
+    as_of: DateTime<Utc>,
+    transactions: &[Transaction],
+    req: &ReconcileRequest,
+) -> ReconcileReport {
+    let tolerance = req.tolerance.filter(|t| *t >= 0.0).unwrap_or(DEFAULT_TOLERANCE);
+    let tag = import_tag(import_id);
+    let in_account = |tx: &Transaction| match account_id.as_deref() {
+        Some(id) => tx.account_id.as_deref() == Some(id),
+        None => tx.account_id.as_deref().is_none_or(str::is_empty),
+    };
+    let imported: Vec<&Transaction> = transactions.iter().filter(|tx| tx.tags.contains(&tag)).collect();
+    let in_period: Vec<Transaction> = transactions.iter()
+        .filter(|tx| in_account(tx) && tx.timestamp <= as_of)
+        .cloned()
+        .collect();
+
+    let mut computed: BTreeMap<(String, String), (AssetType, f64)> = BTreeMap::new();
+    for asset in lot_engine::replay(&in_period, &DustPolicy::default()).holdings.into_values() {
+        let entry = computed
+            .entry((asset.asset_type.to_string(), asset.symbol.to_uppercase()))
+            .or_insert((asset.asset_type.clone(), 0.0));
+        entry.1 += asset.quantity;
+    }
+
+    let mut reported: BTreeMap<(String, String), (AssetType, f64)> = BTreeMap::new();
+    for holding in &req.holdings {
+        let entry = reported
+            .entry((holding.asset_type.to_string(), holding.symbol.trim().to_uppercase()))
+            .or_insert((holding.asset_type.clone(), 0.0));
+        entry.1 += holding.quantity;
+    }
+    for (currency, balance) in &req.balances {
+        let entry = reported
+            .entry((AssetType::Cash.to_string(), currency.trim().to_uppercase()))
+            .or_insert((AssetType::Cash, 0.0));
+        entry.1 += balance;
+    }
+
+    let covers = |asset_type: &AssetType| match asset_type {
+        AssetType::Cash => !req.balances.is_empty(),
+        _ => !req.holdings.is_empty(),
+    };
+    let mut keys: Vec<(String, String)> = reported.keys().cloned().collect();
+    for (key, (asset_type, quantity)) in &computed {
+        if quantity.abs() > tolerance && covers(asset_type) && !reported.contains_key(key) {
+            keys.push(key.clone());
+        }
+    }
+
+    let mut lines: Vec<ReconcileLine> = keys.into_iter()
+        .map(|key| {
+            let reported_entry = reported.get(&key);
+            let computed_entry = computed.get(&key);
+            let asset_type = reported_entry.or(computed_entry).map(|(t, _)| t.clone()).unwrap_or(AssetType::Crypto);
+            let reported_quantity = reported_entry.map(|(_, q)| *q).unwrap_or(0.0);
+            let computed_quantity = computed_entry.map(|(_, q)| *q).unwrap_or(0.0);
+            let difference = computed_quantity - reported_quantity;
+            let status = if difference.abs() <= tolerance {
+                ReconcileStatus::Matched
+            } else if reported_entry.is_none() {
+                ReconcileStatus::NotOnStatement
+            } else if computed_quantity.abs() <= tolerance {
+                ReconcileStatus::MissingFromPortfolio
+            } else {
+                ReconcileStatus::Mismatched
+            };
+            let suspects = if status == ReconcileStatus::Matched {
+                Vec::new()
+            } else {
+                suspects(&imported, &key.1, &asset_type, difference, as_of, tolerance)
+            };
+            ReconcileLine { symbol: key.1, asset_type, reported_quantity, computed_quantity, difference, status, suspects }
+        })
+        .collect();
+    lines.sort_by_key(|line| (line.status == ReconcileStatus::Matched, line.symbol.clone()));
+
+    let matched = lines.iter().filter(|l| l.status == ReconcileStatus::Matched).count();
+    ReconcileReport {
+        import_id: import_id.to_string(),
+        account_id,
+        as_of,
+        import_transactions: imported.len(),
+        reconciled: matched == lines.len(),
+        matched,
+        differences: lines.len() - matched,
+        lines,
+    }
+}
+
+/// Imported transactions of the symbol that look duplicated, fall after the statement date,
+/// or move exactly the missing quantity
+fn suspects(
+    imported: &[&Transaction],
+    symbol: &str,
+    asset_type: &AssetType,
+    difference: f64,
+    as_of: DateTime<Utc>,
+    tolerance: f64,
+) -> Vec<SuspectTransaction> {
+    let same_quantity = |a: f64, b: f64| (a - b).abs() <= tolerance.max(b.abs() * 1e-9);
+    let of_symbol: Vec<&&Transaction> = imported.iter()
+        .filter(|tx| &tx.asset_type == asset_type && tx.symbol.eq_ignore_ascii_case(symbol))
+        .collect();
+
+    let mut suspects = Vec::new();
+    for tx in &of_symbol {
+        let reason = if tx.timestamp > as_of {
+            Some("dated after the statement period".to_string())
+        } else if of_symbol.iter().any(|other| other.id != tx.id
+            && other.action == tx.action
+            && other.timestamp == tx.timestamp
+            && same_quantity(other.quantity, tx.quantity))
+        {
+            Some("possible duplicate of another imported transaction".to_string())
+        } else if same_quantity(tx.quantity, difference.abs()) {
+            Some("quantity equals the difference".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            suspects.push(SuspectTransaction {
+                id: tx.id.clone(),
+                action: tx.action.clone(),
+                quantity: tx.quantity,
+                timestamp: tx.timestamp,
+                reason,
+            });
+        }
+    }
+    suspects
+}
@@ -0,0 +1,225 @@
+use serde::Serialize;
+use crate::models::AssetType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// One observation produced by a rule
+#[derive(Debug, Clone, Serialize)]
+pub struct Insight {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub title: String,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub symbols: Vec<String>,
+    /// Measured value the rule compared against `threshold`
+    pub metric: f64,
+    pub threshold: f64,
+}
+
+/// Rule thresholds, overridable per request
+#[derive(Debug, Clone, Serialize)]
+pub struct InsightThresholds {
+    /// Single position above this % of portfolio value
+    pub concentration_percent: f64,
+    /// Fees above this % of invested capital
+    pub fee_drag_percent: f64,
+    /// Cash above this % of portfolio value
+    pub idle_cash_percent: f64,
+    /// Position down more than this % from cost
+    pub drawdown_percent: f64,
+    /// Price not refreshed for this many hours
+    pub stale_price_hours: f64,
+    /// Position above this % of the symbol's average daily volume
+    pub daily_volume_percent: f64,
+}
+
+impl Default for InsightThresholds {
+    fn default() -> Self {
+        Self {
+            concentration_percent: 25.0,
+            fee_drag_percent: 1.0,
+            idle_cash_percent: 10.0,
+            drawdown_percent: 20.0,
+            stale_price_hours: 24.0,
+            daily_volume_percent: 10.0,
+        }
+    }
+}
+
+/// Position figures the rules look at, already converted to one currency
+#[derive(Debug, Clone)]
+pub struct InsightHolding {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub value: f64,
+    /// Cost including fees
+    pub cost: f64,
+    pub fees: f64,
+    pub pnl_percent: f64,
+    /// None when no live price could be fetched
+    pub price_age_hours: Option<f64>,
+    /// Quantity as % of average daily volume; None when volume is unknown or not applicable
+    pub daily_volume_percent: Option<f64>,
+}
+
+/// Run every rule over the holdings. Output is deterministic: most severe first,
+/// then by rule and symbol.
+pub fn generate_insights(holdings: &[InsightHolding], thresholds: &InsightThresholds) -> Vec<Insight> {
+    let total_value: f64 = holdings.iter().map(|h| h.value).sum();
+    let mut insights = Vec::new();
+    if total_value <= 0.0 {
+        return insights;
+    }
+
+    concentration(holdings, total_value, thresholds, &mut insights);
+    fee_drag(holdings, thresholds, &mut insights);
+    idle_cash(holdings, total_value, thresholds, &mut insights);
+    drawdowns(holdings, thresholds, &mut insights);
+    stale_prices(holdings, thresholds, &mut insights);
+    liquidity(holdings, thresholds, &mut insights);
+
+    insights.sort_by(|a, b| {
+        b.severity.cmp(&a.severity)
+            .then(a.rule.cmp(b.rule))
+            .then(a.symbols.cmp(&b.symbols))
+    });
+    insights
+}
+
+fn concentration(holdings: &[InsightHolding], total_value: f64, t: &InsightThresholds, out: &mut Vec<Insight>) {
+    for h in holdings.iter().filter(|h| h.asset_type != AssetType::Cash) {
+        let weight = h.value / total_value * 100.0;
+        if weight <= t.concentration_percent {
+            continue;
+        }
+        let severity = if weight > (t.concentration_percent * 2.0).min(50.0) { Severity::Critical } else { Severity::Warning };
+        out.push(Insight {
+            rule: "concentration_risk",
+            severity,
+            title: format!("{} is {:.1}% of the portfolio", h.symbol, weight),
+            detail: format!(
+                "A single position above {:.0}% means one bad outcome moves the whole portfolio. Consider trimming or adding other holdings.",
+                t.concentration_percent
+            ),
+            symbols: vec![h.symbol.clone()],
+            metric: weight,
+            threshold: t.concentration_percent,
+        });
+    }
+}
+
+fn fee_drag(holdings: &[InsightHolding], t: &InsightThresholds, out: &mut Vec<Insight>) {
+    let invested: f64 = holdings.iter().filter(|h| h.asset_type != AssetType::Cash).map(|h| h.cost).sum();
+    let fees: f64 = holdings.iter().map(|h| h.fees).sum();
+    if invested <= 0.0 {
+        return;
+    }
+    let drag = fees / invested * 100.0;
+    if drag <= t.fee_drag_percent {
+        return;
+    }
+
+    // Positions where fees ate the most, relative to their own cost
+    let mut worst: Vec<&InsightHolding> = holdings.iter()
+        .filter(|h| h.cost > 0.0 && h.fees / h.cost * 100.0 > t.fee_drag_percent)
+        .collect();
+    worst.sort_by(|a, b| (b.fees / b.cost).partial_cmp(&(a.fees / a.cost)).unwrap_or(std::cmp::Ordering::Equal));
+
+    out.push(Insight {
+        rule: "fee_drag",
+        severity: if drag > t.fee_drag_percent * 3.0 { Severity::Critical } else { Severity::Warning },
+        title: format!("Fees are {:.2}% of invested capital", drag),
+        detail: format!(
+            "Trading costs above {:.2}% compound against returns. Fewer, larger trades or a cheaper broker reduce the drag.",
+            t.fee_drag_percent
+        ),
+        symbols: worst.iter().take(5).map(|h| h.symbol.clone()).collect(),
+        metric: drag,
+        threshold: t.fee_drag_percent,
+    });
+}
+
+fn idle_cash(holdings: &[InsightHolding], total_value: f64, t: &InsightThresholds, out: &mut Vec<Insight>) {
+    let cash: Vec<&InsightHolding> = holdings.iter().filter(|h| h.asset_type == AssetType::Cash).collect();
+    let cash_value: f64 = cash.iter().map(|h| h.value).sum();
+    let weight = cash_value / total_value * 100.0;
+    if weight <= t.idle_cash_percent {
+        return;
+    }
+    out.push(Insight {
+        rule: "idle_cash",
+        severity: if weight > t.idle_cash_percent * 2.0 { Severity::Warning } else { Severity::Info },
+        title: format!("{:.1}% of the portfolio is cash", weight),
+        detail: "Cash beyond an emergency buffer loses to inflation. Check it is intentional or put it to work.".to_string(),
+        symbols: cash.iter().map(|h| h.symbol.clone()).collect(),
+        metric: weight,
+        threshold: t.idle_cash_percent,
+    });
+}
+
+fn drawdowns(holdings: &[InsightHolding], t: &InsightThresholds, out: &mut Vec<Insight>) {
+    for h in holdings.iter().filter(|h| h.asset_type != AssetType::Cash && h.pnl_percent < -t.drawdown_percent) {
+        out.push(Insight {
+            rule: "position_drawdown",
+            severity: if h.pnl_percent < -t.drawdown_percent * 2.0 { Severity::Critical } else { Severity::Warning },
+            title: format!("{} is down {:.1}% from cost", h.symbol, -h.pnl_percent),
+            detail: "Revisit the thesis for this position: hold with conviction, or realize the loss (it may offset gains).".to_string(),
+            symbols: vec![h.symbol.clone()],
+            metric: h.pnl_percent,
+            threshold: -t.drawdown_percent,
+        });
+    }
+}
+
+fn stale_prices(holdings: &[InsightHolding], t: &InsightThresholds, out: &mut Vec<Insight>) {
+    let mut stale: Vec<&InsightHolding> = holdings.iter()
+        .filter(|h| h.asset_type != AssetType::Cash)
+        .filter(|h| h.price_age_hours.is_none_or(|age| age > t.stale_price_hours))
+        .collect();
+    if stale.is_empty() {
+        return;
+    }
+    stale.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    let oldest = stale.iter().filter_map(|h| h.price_age_hours).fold(0.0, f64::max);
+    let missing = stale.iter().filter(|h| h.price_age_hours.is_none()).count();
+
+    out.push(Insight {
+        rule: "stale_prices",
+        severity: Severity::Warning,
+        title: format!("{} position(s) have stale or missing prices", stale.len()),
+        detail: if missing > 0 {
+            format!("{} have no live price and are valued at cost; values and P&L may be wrong.", missing)
+        } else {
+            format!("Oldest price is {:.0}h old; values and P&L may be out of date.", oldest)
+        },
+        symbols: stale.iter().map(|h| h.symbol.clone()).collect(),
+        metric: oldest,
+        threshold: t.stale_price_hours,
+    });
+}
+
+fn liquidity(holdings: &[InsightHolding], t: &InsightThresholds, out: &mut Vec<Insight>) {
+    if t.daily_volume_percent <= 0.0 {
+        return;
+    }
+    for h in holdings {
+        let Some(percent) = h.daily_volume_percent.filter(|p| *p > t.daily_volume_percent) else { continue };
+        out.push(Insight {
+            rule: "liquidity_risk",
+            // More than a whole day's trading cannot be sold in one session without moving the price
+            severity: if percent > 100.0 { Severity::Critical } else { Severity::Warning },
+            title: format!("{} position is {:.0}% of its average daily volume", h.symbol, percent),
+            detail: "Exiting could take several sessions or push the price down. Sell in smaller lots over time, and size new buys with liquidity in mind.".to_string(),
+            symbols: vec![h.symbol.clone()],
+            metric: percent,
+            threshold: t.daily_volume_percent,
+        });
+    }
+}
@@ -1,38 +1,243 @@
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{RwLock, Semaphore};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
 use reqwest::Client;
+use futures_util::{stream, StreamExt};
 
 use crate::config::Config;
-use crate::models::{JobConfig, JobStatus, ApiStatusResult, ApiStatusCheckResult, AssetType, Market};
-use crate::services::{PocketBaseClient, PriceService};
+use crate::error::AppError;
+use crate::models::{
+    CatchUpPolicy, JobConfig, JobStatus, ApiStatusResult, SchedulerOverview, SchedulerHeartbeat, OverdueJob, JobDrift, JobError, PriceRetry, ApiStatusCheckResult, AssetType, Market,
+    JobRetry, JobRun, JobRunsPage, JobTrigger, JOB_RUNS_COLLECTION,
+    Account, AccountType, Compounding, CreateTransactionRequest, TradeAction, INTEREST_TAG,
+    Liability, LiabilityTransaction, LiabilityTransactionKind, EquityGrant, SyncEntity, SyncOp,
+    UserSettings, USER_SETTINGS_COLLECTION,
+};
+use crate::services::{AlertService, NotificationService, PocketBaseClient, PriceService, SnapshotCache, SymbolHeat};
+use crate::services::movers::{compute_movers, format_movers_summary, latest_snapshot_holdings, MoverHolding};
+use crate::services::equity_vesting::{vest_due_tranches, EQUITY_GRANTS_COLLECTION};
+use crate::services::orphans::clean_orphans;
+use crate::services::digest::{digest_due, render_digest, tfex_expiry, value_change, UpcomingExpiry, EXPIRY_WINDOW_DAYS};
+use crate::services::benchmarks::{refresh_levels, BENCHMARKS};
+use crate::services::price_service::{quote_currency, stored_price_source, BatchRequest, PriceEntry};
+use crate::services::valuation::round_money;
+use crate::services::price_history::price_as_of;
+use crate::plugins::{ReportContext, ReportSection};
+
+/// How often the scheduler loop wakes up to look for due jobs
+const TICK_SECONDS: u64 = 60;
+/// Drift samples kept per job
+const MAX_DRIFT_SAMPLES: usize = 50;
+/// Failures kept for the overview
+const MAX_RECENT_ERRORS: usize = 20;
+/// Attempts per symbol before it is dropped from the price retry queue
+const MAX_PRICE_RETRIES: u32 = 5;
+/// Back-off before the first price retry; doubles with every failed attempt
+const RETRY_BASE_SECONDS: u64 = 30;
+const RETRY_MAX_SECONDS: u64 = 3600;
+/// Retries of a job run that failed with a transient error, before giving up until the next slot
+const MAX_JOB_RETRIES: u32 = 3;
+/// Wait before the first job retry; doubles with every failed attempt
+const JOB_RETRY_BASE_SECONDS: u64 = 60;
+const JOB_RETRY_MAX_SECONDS: u64 = 1800;
+/// job_runs older than this are pruned
+const JOB_RUN_RETENTION_DAYS: i64 = 30;
+/// How often the loop prunes old job_runs
+const JOB_RUN_PRUNE_SECONDS: i64 = 3600;
+/// Missed slots looked at when catching up after downtime, most recent first
+const MAX_CATCH_UP_SLOTS: i64 = 366;
+/// Days of snapshots a backfill rebuilds at most
+const MAX_BACKFILL_DAYS: usize = 31;
+
+/// Job types the scheduler knows how to run
+pub const JOB_TYPES: [&str; 11] = [
+    "api_status_check", "price_fetch", "price_update", "portfolio_snapshot", "price_history_log",
+    "interest_accrual", "equity_vesting", "weekly_report", "orphan_cleanup", "benchmark_update",
+    "daily_digest",
+];
+
+/// Next run of an enabled job: the next HH:MM slot (UTC) for schedule_times jobs, otherwise one interval from now
+fn set_next_run(job: &mut JobConfig) {
+    if !job.enabled {
+        job.next_run = None;
+        return;
+    }
+    let now = Utc::now();
+    job.next_run = match &job.schedule_times {
+        Some(times) => {
+            let today = now.date_naive();
+            let mut candidates: Vec<DateTime<Utc>> = times.iter()
+                .filter_map(|t| chrono::NaiveTime::parse_from_str(t, "%H:%M").ok())
+                .flat_map(|t| [today.and_time(t).and_utc(), today.and_time(t).and_utc() + chrono::Duration::days(1)])
+                .collect();
+            candidates.sort();
+            candidates.into_iter().find(|t| *t > now).map(|t| t.to_rfc3339())
+        }
+        None => Some((now + chrono::Duration::seconds(job.interval_seconds as i64)).to_rfc3339()),
+    };
+}
+
+/// Slots a job should have run in while the scheduler was down, oldest first. Interval jobs
+/// count from their next_run; jobs at fixed times from their last run, so a job that never ran
+/// has nothing to make up. The current minute is left to the loop.
+fn missed_slots(job: &JobConfig, now: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    // PocketBase hands stored dates back as "2026-01-16 16:42:15.797Z"
+    let parse = |t: &Option<String>| t.as_deref()
+        .and_then(|t| DateTime::parse_from_rfc3339(&t.replacen(' ', "T", 1)).ok())
+        .map(|t| t.with_timezone(&Utc));
+    match &job.schedule_times {
+        Some(times) => {
+            let Some(last_run) = parse(&job.last_run) else { return Vec::new() };
+            let minute_start = now - chrono::Duration::seconds(now.second() as i64);
+            let times: Vec<chrono::NaiveTime> = times.iter()
+                .filter_map(|t| chrono::NaiveTime::parse_from_str(t, "%H:%M").ok())
+                .collect();
+            let first_day = last_run.date_naive().max(now.date_naive() - chrono::Duration::days(MAX_CATCH_UP_SLOTS));
+            let mut slots: Vec<DateTime<Utc>> = first_day.iter_days()
+                .take_while(|day| *day <= now.date_naive())
+                .flat_map(|day| times.iter().map(move |t| day.and_time(*t).and_utc()))
+                .filter(|slot| *slot > last_run && *slot < minute_start)
+                .collect();
+            slots.sort();
+            slots
+        }
+        None => {
+            let Some(next_run) = parse(&job.next_run) else { return Vec::new() };
+            if next_run >= now {
+                return Vec::new();
+            }
+            let step = job.interval_seconds.max(60) as i64;
+            let last = ((now - next_run).num_seconds() - 1) / step;
+            (last.saturating_sub(MAX_CATCH_UP_SLOTS - 1).max(0)..=last)
+                .map(|k| next_run + chrono::Duration::seconds(k * step))
+                .collect()
+        }
+    }
+}
+
+/// What the loop has been doing, for GET /api/jobs/overview
+#[derive(Default)]
+struct SchedulerStats {
+    started_at: Option<DateTime<Utc>>,
+    last_tick_at: Option<DateTime<Utc>>,
+    ticks: u64,
+    /// Seconds between scheduled and actual start, per job id
+    drift: HashMap<String, VecDeque<i64>>,
+    errors: VecDeque<JobError>,
+    last_run_prune: Option<DateTime<Utc>>,
+}
+
+/// A failed run waiting for its automatic retry
+#[derive(Debug, Clone)]
+struct PendingRetry {
+    /// Attempt that failed
+    attempt: u32,
+    retry_at: DateTime<Utc>,
+    error: String,
+    /// Whether the job had already failed before this chain of attempts started
+    previously_failed: bool,
+}
+
+/// Marks a job as running while held; dropping it (run finished or task panicked) frees the job
+struct RunningJob {
+    running: Arc<Mutex<HashSet<String>>>,
+    id: String,
+}
+
+impl Drop for RunningJob {
+    fn drop(&mut self) {
+        self.running.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+    }
+}
+
+/// Failures worth retrying soon: network trouble, timeouts, rate limits and 5xx answers.
+/// Anything else (bad data, missing config) would fail the same way again.
+pub fn is_transient_job_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    [
+        "timed out", "timeout", "connection", "connect", "error sending request", "dns",
+        "429", "too many requests", "rate limit", "502", "503", "504", "bad gateway",
+        "service unavailable", "temporarily",
+    ].iter().any(|marker| error.contains(marker))
+}
 
 /// Job scheduler service for background tasks
 #[derive(Clone)]
 pub struct JobScheduler {
-    #[allow(dead_code)]
     config: Config,
     http_client: Client,
     pb_client: PocketBaseClient,
     jobs: Arc<RwLock<HashMap<String, JobConfig>>>,
     pocketbase_url: String,
     price_service: PriceService,
+    symbol_heat: SymbolHeat,
+    notification_service: Option<NotificationService>,
+    snapshot_cache: Option<SnapshotCache>,
+    alert_service: Option<AlertService>,
+    stats: Arc<RwLock<SchedulerStats>>,
+    /// Symbols whose last price refresh failed, by "SYMBOL-asset_type"
+    retry_queue: Arc<RwLock<HashMap<String, PriceRetry>>>,
+    /// Job runs that failed transiently, by job id
+    job_retries: Arc<RwLock<HashMap<String, PendingRetry>>>,
+    /// Ids of jobs with a run in progress
+    running: Arc<Mutex<HashSet<String>>>,
+    /// One permit per job allowed to run at the same time
+    run_slots: Arc<Semaphore>,
+    /// Days a job's next catch-up or retry run should backfill, by job id
+    backfill_dates: Arc<RwLock<HashMap<String, Vec<NaiveDate>>>>,
+    /// Plugin sections appended to the daily digest
+    report_sections: Vec<Arc<dyn ReportSection>>,
+    /// Held while account interest is accrued, so an account's settings can't change mid-run
+    interest_accrual: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl JobScheduler {
-    pub fn new(config: Config, pb_client: PocketBaseClient, price_service: PriceService) -> Self {
+    pub fn new(config: Config, pb_client: PocketBaseClient, price_service: PriceService, symbol_heat: SymbolHeat) -> Self {
         let pocketbase_url = config.pocketbase_url.clone();
+        let run_slots = Arc::new(Semaphore::new(config.job_max_concurrency.max(1)));
         Self {
             config,
-            http_client: Client::new(),
+            http_client: pb_client.http(),
             pb_client,
             jobs: Arc::new(RwLock::new(HashMap::new())),
             pocketbase_url,
             price_service,
+            symbol_heat,
+            notification_service: None,
+            snapshot_cache: None,
+            alert_service: None,
+            stats: Arc::new(RwLock::new(SchedulerStats::default())),
+            retry_queue: Arc::new(RwLock::new(HashMap::new())),
+            job_retries: Arc::new(RwLock::new(HashMap::new())),
+            running: Arc::new(Mutex::new(HashSet::new())),
+            run_slots,
+            backfill_dates: Arc::new(RwLock::new(HashMap::new())),
+            report_sections: Vec::new(),
+            interest_accrual: Arc::new(tokio::sync::Mutex::new(())),
         }
     }
 
+    /// Needed by jobs that notify users (weekly report)
+    pub fn set_notification_service(&mut self, notification_service: NotificationService) {
+        self.notification_service = Some(notification_service);
+    }
+
+    /// Sections plugins add to the daily digest
+    pub fn set_report_sections(&mut self, report_sections: Vec<Arc<dyn ReportSection>>) {
+        self.report_sections = report_sections;
+    }
+
+    /// Lets the snapshot job drop a user's cached series after writing a new snapshot
+    pub fn set_snapshot_cache(&mut self, snapshot_cache: SnapshotCache) {
+        self.snapshot_cache = Some(snapshot_cache);
+    }
+
+    /// Lets the price_fetch job check price alerts once prices are refreshed
+    pub fn set_alert_service(&mut self, alert_service: AlertService) {
+        self.alert_service = Some(alert_service);
+    }
+
     /// Initialize job scheduler - load jobs from PocketBase and create defaults if needed
     pub async fn initialize(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         tracing::info!("📋 Initializing job scheduler...");
@@ -70,6 +275,7 @@ impl JobScheduler {
         let defaults = vec![
             ("thai_stock", "SET Market Data", "set_marketdata", "https://www.set.or.th", 1),
             ("us_stock", "Yahoo Finance", "yahoo_finance", "https://query1.finance.yahoo.com", 1),
+            ("us_stock", "Finnhub", "finnhub", "https://finnhub.io", 2),
             ("crypto", "Binance", "binance", "https://api.binance.com", 1),
             ("crypto", "CoinGecko", "coingecko", "https://api.coingecko.com", 2),
             ("crypto", "Bitkub", "bitkub", "https://api.bitkub.com", 3),
@@ -139,8 +345,10 @@ impl JobScheduler {
 
     /// Ensure default job exists (in-memory or database)
     async fn ensure_default_job(&self) {
-        let mut default_job = JobConfig::default();
-        default_job.id = "api_check_01".to_string(); // PocketBase max 15 chars
+        let mut default_job = JobConfig {
+            id: "api_check_01".to_string(), // PocketBase max 15 chars
+            ..Default::default()
+        };
         let next_run = Utc::now() + chrono::Duration::seconds(default_job.interval_seconds as i64);
         default_job.next_run = Some(next_run.to_rfc3339());
         
@@ -198,6 +406,8 @@ impl JobScheduler {
             "status": job.status,
             "last_run": job.last_run,
             "next_run": job.next_run,
+            "schedule_times": job.schedule_times,
+            "catch_up": job.catch_up,
             "last_result": job.last_result
         });
         
@@ -232,6 +442,68 @@ impl JobScheduler {
         Ok(updated)
     }
 
+    /// Delete a job in PocketBase; jobs that only ever lived in memory are not there
+    async fn delete_job_in_db(&self, id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let token = self.pb_client.get_token().await;
+        let url = format!("{}/api/collections/jobs/records/{}", self.pocketbase_url, id);
+
+        let req = self.http_client.delete(&url);
+        let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
+        let response = req.send().await?;
+
+        if !response.status().is_success() && response.status() != reqwest::StatusCode::NOT_FOUND {
+            return Err(format!("Failed to delete job: {}", response.status()).into());
+        }
+        Ok(())
+    }
+
+    /// Add a job; the loop picks it up on its next tick
+    pub async fn create_job(&self, mut job: JobConfig) -> Result<JobConfig, String> {
+        job.status = if job.enabled { JobStatus::Idle } else { JobStatus::Disabled };
+        set_next_run(&mut job);
+        let created = self.create_job_in_db(&job).await.map_err(|e| e.to_string())?;
+        tracing::info!("➕ Created job {} ({})", created.name_en, created.id);
+        self.jobs.write().await.insert(created.id.clone(), created.clone());
+        Ok(created)
+    }
+
+    /// Remove a job from the database and the running scheduler
+    pub async fn delete_job(&self, id: &str) -> Result<JobConfig, String> {
+        if !self.jobs.read().await.contains_key(id) {
+            return Err("Job not found".to_string());
+        }
+        self.delete_job_in_db(id).await.map_err(|e| e.to_string())?;
+        self.stats.write().await.drift.remove(id);
+        tracing::info!("🗑️ Deleted job {}", id);
+        self.jobs.write().await.remove(id).ok_or_else(|| "Job not found".to_string())
+    }
+
+    /// Flip a job between enabled and disabled
+    pub async fn toggle_job(&self, id: &str) -> Result<JobConfig, String> {
+        let enabled = self.get_job(id).await.ok_or_else(|| "Job not found".to_string())?.enabled;
+        self.update_job(id, None, Some(!enabled), None, None).await
+    }
+
+    /// Replace the in-memory jobs with what is in PocketBase, for edits made directly in the database.
+    /// A job that is running keeps its running status until it finishes.
+    pub async fn reload_jobs(&self) -> Result<Vec<JobConfig>, String> {
+        let loaded = self.load_jobs_from_db().await.map_err(|e| e.to_string())?;
+        let mut jobs = self.jobs.write().await;
+        let running: Vec<String> = jobs.values()
+            .filter(|job| job.status == JobStatus::Running)
+            .map(|job| job.id.clone())
+            .collect();
+        jobs.clear();
+        for mut job in loaded {
+            if running.contains(&job.id) {
+                job.status = JobStatus::Running;
+            }
+            jobs.insert(job.id.clone(), job);
+        }
+        tracing::info!("🔄 Reloaded {} jobs from database", jobs.len());
+        Ok(jobs.values().cloned().collect())
+    }
+
     /// Get all jobs
     pub async fn get_jobs(&self) -> Vec<JobConfig> {
         let jobs = self.jobs.read().await;
@@ -245,8 +517,14 @@ impl JobScheduler {
     }
 
     /// Update job configuration
-    /// Update job configuration
-    pub async fn update_job(&self, id: &str, interval_seconds: Option<u64>, enabled: Option<bool>, schedule_times: Option<Option<Vec<String>>>) -> Result<JobConfig, String> {
+    pub async fn update_job(
+        &self,
+        id: &str,
+        interval_seconds: Option<u64>,
+        enabled: Option<bool>,
+        schedule_times: Option<Option<Vec<String>>>,
+        catch_up: Option<CatchUpPolicy>,
+    ) -> Result<JobConfig, String> {
         let mut jobs = self.jobs.write().await;
         
         if let Some(job) = jobs.get_mut(id) {
@@ -264,56 +542,28 @@ impl JobScheduler {
             if let Some(times_opt) = schedule_times {
                 job.schedule_times = times_opt;
             }
-            
-            // Calculate next run time
-            if job.enabled {
-                // If using schedule_times, we need to find the next scheduled time from NOW
-                if let Some(times) = &job.schedule_times {
-                     if times.is_empty() {
-                         // No times set? Fallback to interval or just wait
-                         job.next_run = None;
-                     } else {
-                         // Find next time in list
-                         let now = Utc::now();
-                         let current_date = now.format("%Y-%m-%d").to_string();
-                         
-                         // Create candidates for today and tomorrow
-                         let mut candidates = Vec::new();
-                         for time_str in times {
-                             // Try today
-                             if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(&format!("{} {}:00", current_date, time_str), "%Y-%m-%d %H:%M:%S") {
-                                 let dt_utc = DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc); // Assuming times are UTC? Or local? Frontend sends HH:MM.
-                                 // Let's assume Local for user convenience but server is UTC.
-                                 // For simplicity MVP, assume input is UTC or server local match.
-                                 // Ideally we should handle timezone.
-                                 // Given the current implementation checks `current_time = now.format("%H:%M")`, it implies everything is UTC or consistent timezone.
-                                 // Let's stick to the simpler logic: just set next_run to "Informational".
-                                 
-                                 // Actually the loop logic handles execution. `next_run` is mostly for display in UI.
-                                 // We'll leave next_run update simplified or properly calc it.
-                                 // Let's stick to interval logic fallback for next_run display or try to calc it.
-                                 candidates.push(dt_utc);
-                                 candidates.push(dt_utc + chrono::Duration::days(1));
-                             }
-                         }
-                         candidates.sort();
-                         job.next_run = candidates.into_iter().find(|t| *t > now).map(|t| t.to_rfc3339());
-                     }
-                } else {
-                    let next_run = Utc::now() + chrono::Duration::seconds(job.interval_seconds as i64);
-                    job.next_run = Some(next_run.to_rfc3339());
-                }
-            } else {
-                job.next_run = None;
+            if catch_up.is_some() {
+                job.catch_up = catch_up;
             }
             
+            set_next_run(job);
+            
             // Update in database
             let job_clone = job.clone();
             drop(jobs); // Release lock before async call
             
             match self.update_job_in_db(&job_clone).await {
-                Ok(updated) => {
+                Ok(mut updated) => {
                     let mut jobs = self.jobs.write().await;
+                    // Skip counters only live in memory
+                    if let Some(current) = jobs.get(id) {
+                        updated.skipped_runs = current.skipped_runs;
+                        updated.last_skipped_at = current.last_skipped_at.clone();
+                    }
+                    // Collections without the catch_up field still keep the choice until restart
+                    if updated.catch_up.is_none() {
+                        updated.catch_up = job_clone.catch_up;
+                    }
                     jobs.insert(id.to_string(), updated.clone());
                     Ok(updated)
                 }
@@ -324,32 +574,136 @@ impl JobScheduler {
         }
     }
 
-    /// Run a job immediately
-    pub async fn run_job_now(&self, id: &str) -> Result<serde_json::Value, String> {
+    /// Run a job immediately; refused while the job is already running
+    pub async fn run_job_now(&self, id: &str) -> Result<serde_json::Value, AppError> {
+        if self.get_job(id).await.is_none() {
+            return Err(AppError::NotFound(format!("Job {} not found", id)));
+        }
+        let Some(_running) = self.claim(id) else {
+            return Err(AppError::Conflict(format!("Job {} is already running", id)));
+        };
+        self.run_job(id, JobTrigger::Manual).await.map_err(AppError::Internal)
+    }
+
+    /// Lock a job for one run; None while another run of it is in progress
+    fn claim(&self, id: &str) -> Option<RunningJob> {
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        running.insert(id.to_string()).then(|| RunningJob {
+            running: self.running.clone(),
+            id: id.to_string(),
+        })
+    }
+
+    fn running_job_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.running.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Start a run in the background, or count it as skipped while the previous run is still going
+    async fn dispatch(&self, job: &JobConfig, trigger: JobTrigger) {
+        // This occurrence is taken care of either way, so a long run only counts as skipping
+        // the occurrences that come due while it is still going
+        if trigger == JobTrigger::Schedule {
+            if let Some(job) = self.jobs.write().await.get_mut(&job.id) {
+                set_next_run(job);
+            }
+        }
+        let Some(running) = self.claim(&job.id) else {
+            if trigger == JobTrigger::Schedule {
+                self.record_skipped_run(&job.id).await;
+            }
+            return;
+        };
+        if trigger == JobTrigger::Schedule {
+            self.record_drift(job).await;
+        }
+        let scheduler = self.clone();
+        let (id, name) = (job.id.clone(), job.name.clone());
+        tokio::spawn(async move {
+            let _running = running;
+            match (scheduler.run_job(&id, trigger).await, trigger) {
+                (Ok(_), JobTrigger::Retry) => tracing::info!("✅ Job {} recovered on retry", name),
+                (Ok(_), _) => tracing::info!("✅ Job {} completed successfully", name),
+                (Err(e), JobTrigger::Retry) => tracing::error!("❌ Retry of job {} failed: {}", name, e),
+                (Err(e), _) => tracing::error!("❌ Job {} failed: {}", name, e),
+            }
+        });
+    }
+
+    async fn record_skipped_run(&self, id: &str) {
+        let mut jobs = self.jobs.write().await;
+        if let Some(job) = jobs.get_mut(id) {
+            job.skipped_runs += 1;
+            job.last_skipped_at = Some(Utc::now().to_rfc3339());
+            tracing::warn!(
+                "⏭️ Skipping run of job {}: the previous run is still going ({} skipped)",
+                job.name_en, job.skipped_runs
+            );
+        }
+    }
+
+    /// Execute a job the caller has claimed, record the run in job_runs and schedule a retry
+    /// for transient failures. Waits for a free run slot first.
+    async fn run_job(&self, id: &str, trigger: JobTrigger) -> Result<serde_json::Value, String> {
+        let _slot = self.run_slots.acquire().await.map_err(|e| e.to_string())?;
         let job = {
             let jobs = self.jobs.read().await;
             jobs.get(id).cloned()
         };
 
-        if let Some(mut job) = job {
-            // Update status to running
-            job.status = JobStatus::Running;
-            {
-                let mut jobs = self.jobs.write().await;
-                jobs.insert(id.to_string(), job.clone());
+        if let Some(job) = job {
+            // Any run replaces a pending retry; a retry continues its chain of attempts
+            let pending = self.job_retries.write().await.remove(id);
+            let (attempt, previously_failed) = match (&pending, trigger) {
+                (Some(retry), JobTrigger::Retry) => (retry.attempt + 1, retry.previously_failed),
+                // Admins hear about a job once when it starts failing, not on every failed run
+                _ => (1, job.status == JobStatus::Failed),
+            };
+            // Update status to running; the map entry is edited in place so skip counts aren't lost
+            if let Some(job) = self.jobs.write().await.get_mut(id) {
+                job.status = JobStatus::Running;
             }
+            let started_at = Utc::now();
+            let run_id = self.start_run_record(&job, trigger, attempt, started_at).await;
+
+            // Catch-up runs, and retries of them, fill in the days they were given
+            let backfill = match trigger {
+                JobTrigger::CatchUp | JobTrigger::Retry => self.backfill_dates.write().await.remove(id),
+                _ => None,
+            };
 
             // Execute the job based on type
             let result = match job.job_type.as_str() {
                 "api_status_check" => self.run_api_status_check().await,
-                "price_fetch" | "price_update" => self.run_price_update_job().await,
-                "portfolio_snapshot" => self.run_portfolio_snapshot_job().await,
+                "price_fetch" | "price_update" => self.run_price_fetch_job().await,
+                "portfolio_snapshot" => match backfill {
+                    Some(dates) => self.run_snapshot_backfill(id, dates).await,
+                    None => self.run_portfolio_snapshot_job().await,
+                },
                 "price_history_log" => self.run_price_history_job().await,
+                "interest_accrual" => self.run_interest_accrual_job().await,
+                "equity_vesting" => self.run_equity_vesting_job().await,
+                "weekly_report" => self.run_weekly_report_job().await,
+                "daily_digest" => self.run_daily_digest_job().await,
+                "orphan_cleanup" => self.run_orphan_cleanup_job().await,
+                "benchmark_update" => self.run_benchmark_update_job().await,
                 _ => Err(format!("Unknown job type: {}", job.job_type)),
             };
 
             // Update status based on result
             let now = Utc::now();
+            let retry_at = match &result {
+                Err(e) if attempt <= MAX_JOB_RETRIES && is_transient_job_error(e) => {
+                    let delay = (JOB_RETRY_BASE_SECONDS << (attempt - 1)).min(JOB_RETRY_MAX_SECONDS);
+                    Some(now + chrono::Duration::seconds(delay as i64))
+                }
+                _ => None,
+            };
+            if let Some(run_id) = run_id {
+                self.finish_run_record(run_id, started_at, now, &result, retry_at);
+            }
+
             let mut jobs = self.jobs.write().await;
             if let Some(job) = jobs.get_mut(id) {
                 job.last_run = Some(now.to_rfc3339());
@@ -364,6 +718,29 @@ impl JobScheduler {
                     Err(e) => {
                         job.status = JobStatus::Failed;
                         job.last_result = Some(serde_json::json!({ "error": e }));
+                        let mut stats = self.stats.write().await;
+                        if stats.errors.len() >= MAX_RECENT_ERRORS {
+                            stats.errors.pop_back();
+                        }
+                        stats.errors.push_front(JobError {
+                            job_id: job.id.clone(),
+                            name: job.name_en.clone(),
+                            at: now,
+                            error: e.clone(),
+                        });
+                        match retry_at {
+                            Some(retry_at) => {
+                                tracing::warn!("🔁 Job {} failed (attempt {}), retrying at {}: {}", job.name_en, attempt, retry_at, e);
+                                self.job_retries.write().await.insert(id.to_string(), PendingRetry {
+                                    attempt,
+                                    retry_at,
+                                    error: e.clone(),
+                                    previously_failed,
+                                });
+                            }
+                            None if !previously_failed => self.notify_job_failure(&job.name_en, e),
+                            None => {}
+                        }
                     }
                 }
                 
@@ -381,6 +758,131 @@ impl JobScheduler {
         }
     }
 
+    /// Store a run as running; None when job_runs can't be written (the job still runs)
+    async fn start_run_record(&self, job: &JobConfig, trigger: JobTrigger, attempt: u32, started_at: DateTime<Utc>) -> Option<String> {
+        let body = serde_json::json!({
+            "job_id": job.id,
+            "job_type": job.job_type,
+            "trigger": trigger,
+            "attempt": attempt,
+            "started_at": started_at.to_rfc3339(),
+            "status": JobStatus::Running,
+        });
+        match self.pb_client.create_record::<JobRun>(JOB_RUNS_COLLECTION, &body).await {
+            Ok(run) => Some(run.id),
+            Err(e) => {
+                tracing::warn!("⚠️ Could not record run of job {}: {}", job.id, e);
+                None
+            }
+        }
+    }
+
+    fn finish_run_record(
+        &self,
+        run_id: String,
+        started_at: DateTime<Utc>,
+        finished_at: DateTime<Utc>,
+        result: &Result<serde_json::Value, String>,
+        retry_at: Option<DateTime<Utc>>,
+    ) {
+        let mut body = serde_json::json!({
+            "finished_at": finished_at.to_rfc3339(),
+            "duration_ms": (finished_at - started_at).num_milliseconds().max(0),
+            "retry_at": retry_at.map(|at| at.to_rfc3339()).unwrap_or_default(),
+        });
+        match result {
+            Ok(value) => {
+                body["status"] = serde_json::json!(JobStatus::Success);
+                body["result"] = value.clone();
+            }
+            Err(e) => {
+                body["status"] = serde_json::json!(JobStatus::Failed);
+                body["error"] = serde_json::json!(e);
+            }
+        }
+        let pb_client = self.pb_client.clone();
+        tokio::spawn(async move {
+            if let Err(e) = pb_client.update_record::<serde_json::Value>(JOB_RUNS_COLLECTION, &run_id, &body).await {
+                tracing::warn!("⚠️ Could not finish job run record {}: {}", run_id, e);
+            }
+        });
+    }
+
+    /// A page of a job's runs, newest first
+    pub async fn list_runs(&self, job_id: &str, status: Option<JobStatus>, page: u32, per_page: u32) -> Result<JobRunsPage, AppError> {
+        let mut filter = format!("job_id='{}'", job_id);
+        if let Some(status) = status {
+            let status = serde_json::to_value(status).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+            filter.push_str(&format!(" && status='{}'", status));
+        }
+        let (runs, total_items) = self.pb_client
+            .list_records_page(JOB_RUNS_COLLECTION, Some(filter), "-started_at", page, per_page)
+            .await?;
+        Ok(JobRunsPage { runs, page, per_page, total_items })
+    }
+
+    /// One run of a job
+    pub async fn get_run(&self, job_id: &str, run_id: &str) -> Result<JobRun, AppError> {
+        let run: JobRun = self.pb_client.get_record(JOB_RUNS_COLLECTION, run_id).await?;
+        if run.job_id != job_id {
+            return Err(AppError::NotFound(format!("Run {} not found for job {}", run_id, job_id)));
+        }
+        Ok(run)
+    }
+
+    /// Run the retries that are due, unless the job was disabled or deleted meanwhile
+    async fn run_due_job_retries(&self) {
+        let now = Utc::now();
+        let due: Vec<String> = self.job_retries.read().await.iter()
+            .filter(|(_, retry)| retry.retry_at <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in due {
+            match self.get_job(&id).await {
+                Some(job) if job.enabled => self.dispatch(&job, JobTrigger::Retry).await,
+                _ => {
+                    self.job_retries.write().await.remove(&id);
+                }
+            }
+        }
+    }
+
+    /// Delete job_runs past the retention window, at most once per JOB_RUN_PRUNE_SECONDS
+    async fn prune_job_runs(&self) {
+        let now = Utc::now();
+        {
+            let mut stats = self.stats.write().await;
+            if stats.last_run_prune.is_some_and(|at| (now - at).num_seconds() < JOB_RUN_PRUNE_SECONDS) {
+                return;
+            }
+            stats.last_run_prune = Some(now);
+        }
+        #[derive(serde::Deserialize)]
+        struct RunId {
+            id: String,
+        }
+        let cutoff = (now - chrono::Duration::days(JOB_RUN_RETENTION_DAYS)).format("%Y-%m-%d %H:%M:%S");
+        let old: Vec<RunId> = match self.pb_client
+            .list_records(JOB_RUNS_COLLECTION, Some(format!("started_at < '{}'", cutoff)), "started_at")
+            .await
+        {
+            Ok(old) => old,
+            Err(e) => {
+                tracing::warn!("⚠️ Could not list old job runs: {}", e);
+                return;
+            }
+        };
+        let mut deleted = 0;
+        for run in &old {
+            if self.pb_client.delete_record(JOB_RUNS_COLLECTION, &run.id).await.is_ok() {
+                deleted += 1;
+            }
+        }
+        if deleted > 0 {
+            tracing::info!("🧹 Pruned {} job runs older than {} days", deleted, JOB_RUN_RETENTION_DAYS);
+        }
+    }
+
     /// Run API status check job
     async fn run_api_status_check(&self) -> Result<serde_json::Value, String> {
         tracing::info!("🔍 Running API status check job...");
@@ -464,6 +966,18 @@ impl JobScheduler {
     }
 
     /// Run price update job - fetch latest prices for all assets in portfolio
+    /// Refresh prices, then evaluate price alerts against them
+    async fn run_price_fetch_job(&self) -> Result<serde_json::Value, String> {
+        let mut result = self.run_price_update_job().await?;
+        if let Some(alert_service) = &self.alert_service {
+            match alert_service.evaluate_all_alerts().await {
+                Ok(alerts) => result["alerts"] = alerts,
+                Err(e) => tracing::warn!("⚠️ Alert evaluation after price fetch failed: {}", e),
+            }
+        }
+        Ok(result)
+    }
+
     async fn run_price_update_job(&self) -> Result<serde_json::Value, String> {
         tracing::info!("🔄 Running price update job...");
         
@@ -471,7 +985,12 @@ impl JobScheduler {
         // We need to query all transactions to find out what assets users hold
         // In a real app with many users, this should be optimized
         let token = self.pb_client.get_token().await;
-        let url = format!("{}/api/collections/transactions/records?perPage=500&sort=-timestamp", self.pocketbase_url);
+        // Soft-deleted transactions no longer count as holdings
+        let url = format!(
+            "{}/api/collections/transactions/records?filter={}&perPage=500&sort=-timestamp",
+            self.pocketbase_url,
+            urlencoding::encode("deleted_at=''")
+        );
         
         let req = self.http_client.get(&url);
         let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
@@ -551,131 +1070,82 @@ impl JobScheduler {
             let symbol_upper = tx.symbol.to_uppercase();
             let asset_type_lower = tx.asset_type.to_lowercase();
             let key = format!("{}-{}", symbol_upper, asset_type_lower);
-            if !unique_symbols.contains_key(&key) {
-                unique_symbols.insert(key, (asset_type_lower, tx.market, tx.currency));
-            }
+            unique_symbols.entry(key).or_insert((asset_type_lower, tx.market, tx.currency));
         }
         
         tracing::info!("📊 Found {} unique symbols to fetch prices for", unique_symbols.len());
         
+        // Hottest symbols first; cold ones are only refreshed once their interval has passed
+        let mut ordered: Vec<(f64, &String)> = Vec::with_capacity(unique_symbols.len());
+        for key in unique_symbols.keys() {
+            ordered.push((self.symbol_heat.score(key).await, key));
+        }
+        ordered.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        
         let mut fetched = 0;
         let mut errors = 0;
+        let mut skipped = 0;
+        let mut failed_symbols: Vec<PriceRetry> = Vec::new();
         let now = Utc::now().to_rfc3339();
         
-        // Step 2: Fetch price for each symbol and save to PocketBase
-        for (key, (asset_type_str, market_str, currency)) in unique_symbols.iter() {
+        // Step 2: Price the due symbols, batched per provider, then save each to PocketBase
+        let mut due: Vec<(&String, BatchRequest)> = Vec::new();
+        for (_, key) in ordered {
+            let (asset_type_str, market_str, _) = &unique_symbols[key];
+            if !self.symbol_heat.should_refresh(key).await {
+                skipped += 1;
+                continue;
+            }
             let symbol = key.split('-').next().unwrap_or("");
             
-            // Parse enums
-            let asset_type = match self.parse_asset_type(asset_type_str) {
-                Ok(a) => a,
-                Err(_) => {
-                    tracing::warn!("⚠️ Unknown asset type: {}", asset_type_str);
-                    continue;
-                }
+            let Ok(asset_type) = asset_type_str.parse::<AssetType>() else {
+                tracing::warn!("⚠️ Unknown asset type: {}", asset_type_str);
+                continue;
             };
-            
-            let market = market_str.as_ref()
-                .map(|m| self.parse_market(m))
-                .transpose()
-                .unwrap_or(None);
+            due.push((key, BatchRequest {
+                symbol: symbol.to_string(),
+                asset_type,
+                market: market_str.as_deref().and_then(|m| m.parse::<Market>().ok()),
+                // A symbol's own interval can be shorter than the cache TTL, so it bypasses the cache
+                force: self.symbol_heat.refresh_override(key).await.is_some(),
+            }));
+        }
+        let requests: Vec<BatchRequest> = due.iter().map(|(_, request)| request.clone()).collect();
+        let batch = self.price_service.get_prices_batch(&requests).await;
 
-            // Fetch price using PriceService directly
-            match self.price_service.get_price(symbol, &asset_type, market.as_ref()).await {
-                Ok(price_entry) => {
-                    // Save or update price in PocketBase
-                    let curr = currency.clone().unwrap_or_else(|| price_entry.currency.clone());
-                    let market_val = market_str.as_ref().map(|m| m.to_lowercase());
-                    
-                    // Check if price record exists
-                    let filter = if let Some(m) = &market_val {
-                        format!("symbol='{}' && asset_type='{}' && market='{}'", symbol, asset_type_str, m)
-                    } else {
-                        format!("symbol='{}' && asset_type='{}'", symbol, asset_type_str)
-                    };
-                    
-                    let check_url = format!(
-                        "{}/api/collections/asset_prices/records?filter={}",
-                        self.pocketbase_url,
-                        urlencoding::encode(&filter)
-                    );
-                    
-                    let req = self.http_client.get(&check_url);
-                    let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
-                    let existing = req.send().await;
-                    
-                    let payload = serde_json::json!({
-                        "symbol": symbol,
-                        "asset_type": asset_type_str,
-                        "price": price_entry.price,
-                        "currency": curr,
-                        "market": market_val,
-                        "last_updated": now.clone()
-                    });
-                    
-                    let save_result = if let Ok(resp) = existing {
-                        if let Ok(data) = resp.json::<serde_json::Value>().await {
-                            if let Some(items) = data.get("items").and_then(|i| i.as_array()) {
-                                if let Some(first) = items.first() {
-                                    if let Some(id) = first.get("id").and_then(|i| i.as_str()) {
-                                        // Update existing
-                                        let update_url = format!(
-                                            "{}/api/collections/asset_prices/records/{}",
-                                            self.pocketbase_url, id
-                                        );
-                                        let req = self.http_client.patch(&update_url);
-                                        let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
-                                        req.json(&payload).send().await
-                                    } else {
-                                        // Create new
-                                        let create_url = format!("{}/api/collections/asset_prices/records", self.pocketbase_url);
-                                        let req = self.http_client.post(&create_url);
-                                        let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
-                                        req.json(&payload).send().await
-                                    }
-                                } else {
-                                    // Create new
-                                    let create_url = format!("{}/api/collections/asset_prices/records", self.pocketbase_url);
-                                    let req = self.http_client.post(&create_url);
-                                    let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
-                                    req.json(&payload).send().await
-                                }
-                            } else {
-                                // Create new
-                                let create_url = format!("{}/api/collections/asset_prices/records", self.pocketbase_url);
-                                let req = self.http_client.post(&create_url);
-                                let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
-                                req.json(&payload).send().await
-                            }
-                        } else {
-                            // Create new
-                            let create_url = format!("{}/api/collections/asset_prices/records", self.pocketbase_url);
-                            let req = self.http_client.post(&create_url);
-                            let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
-                            req.json(&payload).send().await
-                        }
-                    } else {
-                        // Create new
-                        let create_url = format!("{}/api/collections/asset_prices/records", self.pocketbase_url);
-                        let req = self.http_client.post(&create_url);
-                        let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
-                        req.json(&payload).send().await
-                    };
-                    
-                    if let Ok(resp) = save_result {
-                        if resp.status().is_success() {
-                            fetched += 1;
-                        } else {
-                            errors += 1;
-                            tracing::warn!("⚠️ Failed to save price for {}", symbol);
-                        }
-                    } else {
-                        errors += 1;
+        let (due, unique_symbols, now) = (&due, &unique_symbols, &now);
+        let outcomes: Vec<Option<PriceRetry>> = stream::iter(batch.results.into_iter().enumerate())
+            .map(|(i, priced)| async move {
+                let (key, request) = &due[i];
+                let (asset_type_str, market_str, currency) = &unique_symbols[*key];
+                let symbol = request.symbol.as_str();
+                let saved = match priced {
+                    Ok(entry) => {
+                        self.symbol_heat.mark_refreshed(symbol, asset_type_str).await;
+                        self.save_asset_price(symbol, asset_type_str, market_str.as_deref(), currency.as_deref(), &entry, now).await
+                    }
+                    Err(e) => Err(e),
+                };
+                match saved {
+                    Ok(()) => {
+                        self.retry_queue.write().await.remove(*key);
+                        None
+                    }
+                    Err(e) => {
+                        tracing::error!("❌ Failed to refresh price for {} ({}): {}", symbol, asset_type_str, e);
+                        Some(self.queue_price_retry(key, symbol, asset_type_str, market_str.as_deref(), currency.as_deref(), &e).await)
                     }
                 }
-                Err(e) => {
+            })
+            .buffer_unordered(self.config.price_fetch_concurrency.max(1))
+            .collect()
+            .await;
+        for outcome in outcomes {
+            match outcome {
+                None => fetched += 1,
+                Some(retry) => {
                     errors += 1;
-                    tracing::error!("❌ Failed to fetch price for {} ({}): {}", symbol, asset_type_str, e);
+                    failed_symbols.push(retry);
                 }
             }
         }
@@ -684,51 +1154,162 @@ impl JobScheduler {
             "total_symbols": unique_symbols.len(),
             "fetched": fetched,
             "errors": errors,
+            "skipped_not_due": skipped,
+            "batch_requests": batch.batch_requests,
+            "batched_symbols": batch.batched_symbols,
+            "failed_symbols": failed_symbols,
+            "retry_queue_size": self.retry_queue.read().await.len(),
             "last_updated": now
         });
         
-        tracing::info!("✅ Price fetch complete: {}/{} prices updated", fetched, unique_symbols.len());
+        tracing::info!("✅ Price fetch complete: {}/{} prices updated ({} not due)", fetched, unique_symbols.len(), skipped);
         
         Ok(result)
     }
 
-    fn parse_asset_type(&self, s: &str) -> Result<AssetType, String> {
-        match s.to_lowercase().as_str() {
-            "stock" => Ok(AssetType::Stock),
-            "tfex" => Ok(AssetType::Tfex),
-            "crypto" => Ok(AssetType::Crypto),
-            "foreign_stock" | "foreignstock" => Ok(AssetType::ForeignStock),
-            "gold" => Ok(AssetType::Gold),
-            "commodity" => Ok(AssetType::Commodity),
-            _ => Err(format!("Invalid asset type: {}", s)),
-        }
-    }
-
-    fn parse_market(&self, s: &str) -> Result<Market, String> {
-        match s.to_lowercase().as_str() {
-            "set" => Ok(Market::Set),
-            "mai" => Ok(Market::Mai),
-            "tfex" => Ok(Market::Tfex),
-            "nyse" => Ok(Market::Nyse),
-            "nasdaq" => Ok(Market::Nasdaq),
-            "amex" => Ok(Market::Amex),
-            "lse" => Ok(Market::Lse),
-            "euronext" => Ok(Market::Euronext),
-            "xetra" => Ok(Market::Xetra),
-            "hkex" => Ok(Market::Hkex),
-            "tse" => Ok(Market::Tse),
-            "sgx" => Ok(Market::Sgx),
-            "krx" => Ok(Market::Krx),
-            "binance" => Ok(Market::Binance),
-            "coinbase" => Ok(Market::Coinbase),
-            "bitkub" => Ok(Market::Bitkub),
-            "okx" => Ok(Market::Okx),
-            "htx" => Ok(Market::Htx),
-            "kucoin" => Ok(Market::Kucoin),
-            "comex" => Ok(Market::Comex),
-            "lbma" => Ok(Market::Lbma),
-            _ => Err(format!("Invalid market: {}", s)),
+    /// Fetch one symbol's price and upsert it into asset_prices
+    async fn fetch_and_save_price(
+        &self,
+        symbol: &str,
+        asset_type_str: &str,
+        market_str: Option<&str>,
+        currency: Option<&str>,
+        now: &str,
+    ) -> Result<(), AppError> {
+        let asset_type = asset_type_str.parse::<AssetType>().map_err(AppError::BadRequest)?;
+        let market = market_str.and_then(|m| m.parse::<Market>().ok());
+        // A symbol's own interval can be shorter than the cache TTL, so it bypasses the cache
+        let price_entry = if self.symbol_heat.refresh_override(&SymbolHeat::key(symbol, asset_type_str)).await.is_some() {
+            self.price_service.refresh_price(symbol, &asset_type, market.as_ref()).await?
+        } else {
+            self.price_service.get_price(symbol, &asset_type, market.as_ref()).await?
+        };
+        self.symbol_heat.mark_refreshed(symbol, asset_type_str).await;
+        self.save_asset_price(symbol, asset_type_str, market_str, currency, &price_entry, now).await
+    }
+
+    /// Upsert a fetched price into asset_prices
+    async fn save_asset_price(
+        &self,
+        symbol: &str,
+        asset_type_str: &str,
+        market_str: Option<&str>,
+        currency: Option<&str>,
+        price_entry: &PriceEntry,
+        now: &str,
+    ) -> Result<(), AppError> {
+        let token = self.pb_client.get_token().await;
+        let curr = currency.map(str::to_string).unwrap_or_else(|| price_entry.currency.clone());
+        let market_val = market_str.map(|m| m.to_lowercase());
+
+        // Check if price record exists
+        let filter = if let Some(m) = &market_val {
+            format!("symbol='{}' && asset_type='{}' && market='{}'", symbol, asset_type_str, m)
+        } else {
+            format!("symbol='{}' && asset_type='{}'", symbol, asset_type_str)
+        };
+        let check_url = format!(
+            "{}/api/collections/asset_prices/records?filter={}",
+            self.pocketbase_url,
+            urlencoding::encode(&filter)
+        );
+        let req = self.http_client.get(&check_url);
+        let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
+        let existing_id = match req.send().await {
+            Ok(resp) => resp.json::<serde_json::Value>().await.ok()
+                .and_then(|data| data.get("items")?.as_array()?.first()?.get("id")?.as_str().map(str::to_string)),
+            Err(_) => None,
+        };
+
+        let payload = serde_json::json!({
+            "symbol": symbol,
+            "asset_type": asset_type_str,
+            "price": price_entry.price,
+            "currency": curr,
+            "market": market_val,
+            "source": price_entry.source.as_deref().unwrap_or_default(),
+            "last_updated": now
+        });
+        // Update the existing record, otherwise create one
+        let req = match existing_id {
+            Some(id) => self.http_client.patch(format!("{}/api/collections/asset_prices/records/{}", self.pocketbase_url, id)),
+            None => self.http_client.post(format!("{}/api/collections/asset_prices/records", self.pocketbase_url)),
+        };
+        let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
+        let resp = req.json(&payload).send().await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save price for {}: {}", symbol, e)))?;
+        if !resp.status().is_success() {
+            return Err(AppError::DatabaseError(format!("Failed to save price for {}: {}", symbol, resp.status())));
+        }
+        Ok(())
+    }
+
+    /// Queue a failed symbol for the retry pass: after the rate limit resets, or with
+    /// exponential back-off for other errors. Gives up after MAX_PRICE_RETRIES attempts.
+    async fn queue_price_retry(
+        &self,
+        key: &str,
+        symbol: &str,
+        asset_type: &str,
+        market: Option<&str>,
+        currency: Option<&str>,
+        error: &AppError,
+    ) -> PriceRetry {
+        let mut queue = self.retry_queue.write().await;
+        let attempts = queue.get(key).map(|r| r.attempts).unwrap_or(0) + 1;
+        let retry_at = match error {
+            AppError::RateLimited(info) => info.resets_at,
+            _ => {
+                let backoff = (RETRY_BASE_SECONDS << attempts.min(10)).min(RETRY_MAX_SECONDS);
+                Utc::now() + chrono::Duration::seconds(backoff as i64)
+            }
+        };
+        let entry = PriceRetry {
+            symbol: symbol.to_string(),
+            asset_type: asset_type.to_string(),
+            market: market.map(str::to_string),
+            currency: currency.map(str::to_string),
+            attempts,
+            last_error: error.to_string(),
+            retry_at,
+        };
+        if attempts >= MAX_PRICE_RETRIES {
+            queue.remove(key);
+            tracing::warn!("⚠️ Giving up on price for {} ({}) after {} attempts", symbol, asset_type, attempts);
+        } else {
+            queue.insert(key.to_string(), entry.clone());
         }
+        entry
+    }
+
+    /// Retry queued price refreshes whose rate-limit window or back-off has passed
+    async fn run_price_retry_pass(&self) {
+        let now = Utc::now();
+        let due: Vec<(String, PriceRetry)> = self.retry_queue.read().await
+            .iter()
+            .filter(|(_, retry)| retry.retry_at <= now)
+            .map(|(key, retry)| (key.clone(), retry.clone()))
+            .collect();
+        if due.is_empty() {
+            return;
+        }
+
+        let stamp = now.to_rfc3339();
+        let mut recovered = 0;
+        for (key, retry) in &due {
+            match self.fetch_and_save_price(
+                &retry.symbol, &retry.asset_type, retry.market.as_deref(), retry.currency.as_deref(), &stamp,
+            ).await {
+                Ok(()) => {
+                    recovered += 1;
+                    self.retry_queue.write().await.remove(key);
+                }
+                Err(e) => {
+                    self.queue_price_retry(key, &retry.symbol, &retry.asset_type, retry.market.as_deref(), retry.currency.as_deref(), &e).await;
+                }
+            }
+        }
+        tracing::info!("🔁 Price retry pass: {}/{} symbols recovered", recovered, due.len());
     }
 
     /// Start the job scheduler loop (spawns a background task)
@@ -736,10 +1317,17 @@ impl JobScheduler {
         let scheduler = self.clone();
         tokio::spawn(async move {
             tracing::info!("⏰ Job scheduler loop started");
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60)); // Check every minute
+            scheduler.stats.write().await.started_at = Some(Utc::now());
+            scheduler.catch_up_missed_runs().await;
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(TICK_SECONDS)); // Check every minute
             
             loop {
                 interval.tick().await;
+                {
+                    let mut stats = scheduler.stats.write().await;
+                    stats.last_tick_at = Some(Utc::now());
+                    stats.ticks += 1;
+                }
                 
                 // Get all enabled jobs
                 let jobs = scheduler.get_jobs().await;
@@ -784,21 +1372,166 @@ impl JobScheduler {
 
                     if should_run {
                         tracing::info!("🚀 Triggering scheduled job: {} ({})", job.name, job.id);
-                        
-                        // We use run_job_now which updates status/DB
-                        // But for schedule_times, we need to handle next_run differently
-                        // If using schedule_times, next_run is just informational for the NEXT slot
-                        
-                        match scheduler.run_job_now(&job.id).await {
-                           Ok(_) => tracing::info!("✅ Job {} completed successfully", job.name),
-                           Err(e) => tracing::error!("❌ Job {} failed: {}", job.name, e),
-                        }
+                        // Runs in the background so a slow job doesn't hold up the tick;
+                        // a job still running from its last slot skips this one
+                        scheduler.dispatch(&job, JobTrigger::Schedule).await;
                     }
                 }
+
+                scheduler.run_due_job_retries().await;
+                // The price job already calls the same rate-limited APIs
+                if !scheduler.price_job_running().await {
+                    scheduler.run_price_retry_pass().await;
+                }
+                scheduler.prune_job_runs().await;
             }
         });
     }
 
+    /// Apply each job's catch-up policy to the slots it missed while the server was down
+    async fn catch_up_missed_runs(&self) {
+        let now = Utc::now();
+        for job in self.get_jobs().await.into_iter().filter(|job| job.enabled) {
+            let slots = missed_slots(&job, now);
+            if slots.is_empty() {
+                continue;
+            }
+            let policy = job.catch_up_policy();
+            tracing::info!("⏪ Job {} missed {} run(s) since {}, catching up with {:?}", job.name_en, slots.len(), slots[0], policy);
+            // The catch-up stands in for the overdue slot, so the loop must not start it again
+            if let Some(job) = self.jobs.write().await.get_mut(&job.id) {
+                set_next_run(job);
+            }
+            match policy {
+                CatchUpPolicy::Skip => continue,
+                CatchUpPolicy::Backfill if job.job_type == "portfolio_snapshot" => {
+                    let mut dates: Vec<NaiveDate> = slots.iter().map(|slot| slot.date_naive()).collect();
+                    dates.dedup();
+                    if dates.len() > MAX_BACKFILL_DAYS {
+                        tracing::warn!("⚠️ Backfilling only the last {} of {} missed days for {}", MAX_BACKFILL_DAYS, dates.len(), job.name_en);
+                        dates.drain(..dates.len() - MAX_BACKFILL_DAYS);
+                    }
+                    self.backfill_dates.write().await.insert(job.id.clone(), dates);
+                }
+                CatchUpPolicy::Backfill => {
+                    tracing::info!("Job type {} can't run for a past slot, running {} once instead", job.job_type, job.name_en);
+                }
+                CatchUpPolicy::RunOnceLate => {}
+            }
+            self.dispatch(&job, JobTrigger::CatchUp).await;
+        }
+    }
+
+    async fn price_job_running(&self) -> bool {
+        let running = self.running_job_ids();
+        self.jobs.read().await.values()
+            .any(|job| matches!(job.job_type.as_str(), "price_fetch" | "price_update") && running.contains(&job.id))
+    }
+
+    /// Remember how late a scheduled run starts compared to its slot
+    async fn record_drift(&self, job: &JobConfig) {
+        let now = Utc::now();
+        let scheduled = if job.schedule_times.is_some() {
+            // The slot is the start of the current HH:MM minute
+            now.date_naive()
+                .and_hms_opt(now.time().hour(), now.time().minute(), 0)
+                .map(|dt| dt.and_utc())
+        } else {
+            job.next_run.as_deref()
+                .and_then(|next| DateTime::parse_from_rfc3339(next).ok())
+                .map(|dt| dt.with_timezone(&Utc))
+        };
+        let Some(scheduled) = scheduled else { return };
+
+        let mut stats = self.stats.write().await;
+        let samples = stats.drift.entry(job.id.clone()).or_default();
+        if samples.len() >= MAX_DRIFT_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back((now - scheduled).num_seconds().max(0));
+    }
+
+    /// Heartbeat, overdue jobs, drift and recent failures
+    pub async fn overview(&self) -> SchedulerOverview {
+        let jobs = self.get_jobs().await;
+        let stats = self.stats.read().await;
+        let now = Utc::now();
+
+        let seconds_since_last_tick = stats.last_tick_at.map(|at| (now - at).num_seconds());
+        let heartbeat = SchedulerHeartbeat {
+            started_at: stats.started_at,
+            last_tick_at: stats.last_tick_at,
+            seconds_since_last_tick,
+            tick_interval_seconds: TICK_SECONDS,
+            ticks: stats.ticks,
+            // Allow a few missed ticks (busy runtime, suspended host) before calling it dead
+            alive: seconds_since_last_tick.is_some_and(|s| s < (TICK_SECONDS * 3) as i64),
+        };
+
+        // One full tick of slack: a job due just after a tick waits for the next one
+        let grace = chrono::Duration::seconds((TICK_SECONDS * 2) as i64);
+        let mut overdue_jobs: Vec<OverdueJob> = jobs.iter()
+            .filter(|job| job.enabled && job.schedule_times.is_none() && job.status != JobStatus::Running)
+            .filter_map(|job| {
+                let next_run = job.next_run.as_deref()?;
+                let due = DateTime::parse_from_rfc3339(next_run).ok()?.with_timezone(&Utc);
+                (now > due + grace).then(|| OverdueJob {
+                    id: job.id.clone(),
+                    name: job.name_en.clone(),
+                    job_type: job.job_type.clone(),
+                    next_run: next_run.to_string(),
+                    overdue_seconds: (now - due).num_seconds(),
+                })
+            })
+            .collect();
+        overdue_jobs.sort_by_key(|job| std::cmp::Reverse(job.overdue_seconds));
+
+        let mut drift_by_job: Vec<JobDrift> = stats.drift.iter()
+            .filter(|(_, samples)| !samples.is_empty())
+            .map(|(job_id, samples)| JobDrift {
+                job_id: job_id.clone(),
+                name: jobs.iter().find(|j| &j.id == job_id).map(|j| j.name_en.clone()).unwrap_or_default(),
+                samples: samples.len(),
+                average_drift_seconds: samples.iter().sum::<i64>() as f64 / samples.len() as f64,
+                max_drift_seconds: samples.iter().copied().max().unwrap_or(0),
+            })
+            .collect();
+        drift_by_job.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let all_samples: Vec<i64> = stats.drift.values().flatten().copied().collect();
+        let average_drift_seconds = (!all_samples.is_empty())
+            .then(|| all_samples.iter().sum::<i64>() as f64 / all_samples.len() as f64);
+
+        SchedulerOverview {
+            heartbeat,
+            enabled_jobs: jobs.iter().filter(|job| job.enabled).count(),
+            overdue_jobs,
+            average_drift_seconds,
+            drift_by_job,
+            last_errors: stats.errors.iter().cloned().collect(),
+            price_retries: {
+                let mut retries: Vec<PriceRetry> = self.retry_queue.read().await.values().cloned().collect();
+                retries.sort_by_key(|r| r.retry_at);
+                retries
+            },
+            job_retries: {
+                let mut retries: Vec<JobRetry> = self.job_retries.read().await.iter()
+                    .map(|(job_id, retry)| JobRetry {
+                        job_id: job_id.clone(),
+                        name: jobs.iter().find(|j| &j.id == job_id).map(|j| j.name_en.clone()).unwrap_or_default(),
+                        attempt: retry.attempt,
+                        last_error: retry.error.clone(),
+                        retry_at: retry.retry_at,
+                    })
+                    .collect();
+                retries.sort_by_key(|r| r.retry_at);
+                retries
+            },
+            running_jobs: self.running_job_ids(),
+            max_concurrent_jobs: self.config.job_max_concurrency.max(1),
+        }
+    }
+
     /// Run price history job - fetch prices and save to history collection
     async fn run_price_history_job(&self) -> Result<serde_json::Value, String> {
         tracing::info!("📈 Running price history job...");
@@ -848,12 +1581,12 @@ impl JobScheduler {
             if symbol.is_empty() { continue; }
             
             // Re-fetch FRESH price to ensure accuracy at this timestamp
-             let asset_type = match self.parse_asset_type(asset_type_str) {
+             let asset_type = match asset_type_str.parse::<AssetType>() {
                 Ok(a) => a,
                 Err(_) => continue,
             };
             
-            let market = market_str.map(|m| self.parse_market(m)).transpose().ok().flatten();
+            let market = market_str.map(|m| m.parse::<Market>()).transpose().ok().flatten();
 
             match self.price_service.get_price(symbol, &asset_type, market.as_ref()).await {
                 Ok(price_entry) => {
@@ -863,6 +1596,7 @@ impl JobScheduler {
                         "market": market_str,
                         "price": price_entry.price,
                         "currency": price_entry.currency,
+                        "source": price_entry.source,
                         "recorded_at": now.to_rfc3339()
                     });
                     
@@ -893,12 +1627,613 @@ impl JobScheduler {
         }))
     }
 
+    /// Run interest accrual job - accrue daily interest on savings / fixed deposit accounts
+    /// and credit it as income (dividend) plus a matching cash deposit on each compounding date.
+    /// Interest on liabilities is accrued in the same pass.
+    async fn run_interest_accrual_job(&self) -> Result<serde_json::Value, String> {
+        tracing::info!("🏦 Running interest accrual job...");
+        let _accruing = self.interest_accrual.lock().await;
+
+        let accounts = self.pb_client.list_all_accounts().await.map_err(|e| e.to_string())?;
+        let today = Utc::now().date_naive();
+
+        let mut processed = 0;
+        let mut credited_count = 0;
+        let mut credited_total = 0.0;
+        let mut errors = 0;
+
+        for account in accounts {
+            if account.account_type == AccountType::Investment || account.archived {
+                continue;
+            }
+            let apr = account.apr_percent.unwrap_or(0.0);
+            if apr <= 0.0 {
+                continue;
+            }
+
+            match self.accrue_account_interest(&account, apr, today).await {
+                Ok(credited) => {
+                    processed += 1;
+                    if credited > 0.0 {
+                        credited_count += 1;
+                        credited_total += credited;
+                    }
+                }
+                Err(e) => {
+                    errors += 1;
+                    tracing::warn!("⚠️ Failed to accrue interest for account {}: {}", account.id, e);
+                }
+            }
+        }
+
+        // Loans accrue the same way, but interest is capitalized onto the balance owed
+        let liabilities = self.pb_client.list_all_liabilities().await.map_err(|e| e.to_string())?;
+        let mut liabilities_processed = 0;
+        let mut interest_charged = 0.0;
+        for liability in liabilities.iter().filter(|l| l.apr_percent > 0.0 && l.balance > 0.0) {
+            match self.accrue_liability_interest(liability, today).await {
+                Ok(charged) => {
+                    liabilities_processed += 1;
+                    interest_charged += charged;
+                }
+                Err(e) => {
+                    errors += 1;
+                    tracing::warn!("⚠️ Failed to accrue interest for liability {}: {}", liability.id, e);
+                }
+            }
+        }
+
+        tracing::info!(
+            "✅ Interest accrual complete: {} accounts, {} credited, {} liabilities",
+            processed, credited_count, liabilities_processed
+        );
+
+        Ok(serde_json::json!({
+            "accounts_processed": processed,
+            "accounts_credited": credited_count,
+            "interest_credited": credited_total,
+            "liabilities_processed": liabilities_processed,
+            "interest_charged": interest_charged,
+            "errors": errors,
+            "date": today.to_string()
+        }))
+    }
+
+    /// Send a job failure to every admin in the background
+    fn notify_job_failure(&self, job_name: &str, error: &str) {
+        let Some(notification_service) = self.notification_service.clone() else { return };
+        let pb_client = self.pb_client.clone();
+        let job_name = job_name.to_string();
+        let error = error.to_string();
+        tokio::spawn(async move {
+            #[derive(serde::Deserialize)]
+            struct Admin {
+                id: String,
+            }
+            let admins: Vec<Admin> = match pb_client.list_records("users", Some("role='admin'".to_string()), "created").await {
+                Ok(admins) => admins,
+                Err(e) => {
+                    tracing::warn!("⚠️ Could not load admins to notify about {}: {}", job_name, e);
+                    return;
+                }
+            };
+            for admin in admins {
+                if let Err(e) = notification_service.send_job_failure(&admin.id, &job_name, &error).await {
+                    tracing::error!("Failed to send job failure notice: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Weekly report - send each user their top movers over 1d/7d/30d
+    async fn run_weekly_report_job(&self) -> Result<serde_json::Value, String> {
+        tracing::info!("📰 Running weekly report job...");
+        let notification_service = self.notification_service.as_ref()
+            .ok_or_else(|| "Notification service not configured".to_string())?;
+
+        #[derive(serde::Deserialize)]
+        struct User {
+            id: String,
+        }
+        let users: Vec<User> = self.pb_client
+            .list_records("users", None, "created")
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut sent = 0;
+        let mut skipped = 0;
+        let mut errors = 0;
+        for user in users {
+            let holdings = match self.mover_holdings(&user.id).await {
+                Ok(holdings) => holdings,
+                Err(e) => {
+                    errors += 1;
+                    tracing::warn!("⚠️ Could not load holdings for {}: {}", user.id, e);
+                    continue;
+                }
+            };
+            if holdings.is_empty() {
+                skipped += 1;
+                continue;
+            }
+
+            let report = match compute_movers(&self.pb_client, &self.price_service, &user.id, &holdings, 3).await {
+                Ok(report) => report,
+                Err(e) => {
+                    errors += 1;
+                    tracing::warn!("⚠️ Movers failed for {}: {}", user.id, e);
+                    continue;
+                }
+            };
+            let summary = format_movers_summary(&report);
+            if summary.is_empty() {
+                skipped += 1;
+                continue;
+            }
+            match notification_service.send_report(&user.id, "Weekly portfolio report: top movers", &summary).await {
+                Ok(()) => sent += 1,
+                Err(e) => {
+                    errors += 1;
+                    tracing::warn!("⚠️ Failed to send weekly report to {}: {}", user.id, e);
+                }
+            }
+        }
+
+        tracing::info!("✅ Weekly report complete: {} sent, {} skipped, {} errors", sent, skipped, errors);
+        Ok(serde_json::json!({
+            "reports_sent": sent,
+            "skipped": skipped,
+            "errors": errors
+        }))
+    }
+
+    /// Non-cash holdings from the user's latest snapshot, at cached prices where available
+    async fn mover_holdings(&self, user_id: &str) -> Result<Vec<MoverHolding>, AppError> {
+        let snapshot_assets = latest_snapshot_holdings(&self.pb_client, user_id).await?;
+        let mut holdings = Vec::new();
+        for asset in snapshot_assets.iter().filter(|a| a.quantity.abs() > 0.0) {
+            let Ok(asset_type) = asset.asset_type.parse::<AssetType>() else { continue };
+            if asset_type == AssetType::Cash {
+                continue;
+            }
+            let market = asset.market.as_deref().and_then(|m| m.parse::<Market>().ok());
+            let current_price = match self.price_service.get_price(&asset.symbol, &asset_type, market.as_ref()).await {
+                Ok(entry) => entry.price,
+                Err(_) => asset.current_price,
+            };
+            holdings.push(MoverHolding {
+                symbol: asset.symbol.clone(),
+                asset_type,
+                market,
+                quantity: asset.quantity,
+                current_price,
+            });
+        }
+        Ok(holdings)
+    }
+
+    /// Daily digest - send each user who set a digest time their total value, day change, top
+    /// movers and upcoming TFEX expiries, once per local day after that time
+    async fn run_daily_digest_job(&self) -> Result<serde_json::Value, String> {
+        tracing::info!("🗞️ Running daily digest job...");
+        let notification_service = self.notification_service.as_ref()
+            .ok_or_else(|| "Notification service not configured".to_string())?;
+
+        let all_settings: Vec<UserSettings> = self.pb_client
+            .list_all_records(USER_SETTINGS_COLLECTION, Some("digest_time!=''".to_string()))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let now = Utc::now();
+        let mut sent = 0;
+        let mut skipped = 0;
+        let mut errors = 0;
+        for settings in all_settings {
+            let Some(local_date) = digest_due(&settings, now) else { continue };
+            let user_id = settings.user_id.clone();
+
+            let value = match value_change(&self.pb_client, &user_id).await {
+                Ok(value) => value,
+                Err(e) => {
+                    errors += 1;
+                    tracing::warn!("⚠️ Could not load snapshots for {}: {}", user_id, e);
+                    continue;
+                }
+            };
+            let holdings = match self.mover_holdings(&user_id).await {
+                Ok(holdings) => holdings,
+                Err(e) => {
+                    errors += 1;
+                    tracing::warn!("⚠️ Could not load holdings for {}: {}", user_id, e);
+                    continue;
+                }
+            };
+            let movers = if holdings.is_empty() {
+                None
+            } else {
+                match compute_movers(&self.pb_client, &self.price_service, &user_id, &holdings, 3).await {
+                    Ok(report) => Some(report),
+                    Err(e) => {
+                        tracing::warn!("⚠️ Movers failed for {}: {}", user_id, e);
+                        None
+                    }
+                }
+            };
+            let mut expiries: Vec<UpcomingExpiry> = holdings.iter()
+                .filter(|h| h.asset_type == AssetType::Tfex)
+                .filter_map(|h| {
+                    let expires_on = tfex_expiry(&h.symbol)?;
+                    let days_left = (expires_on - local_date).num_days();
+                    (0..=EXPIRY_WINDOW_DAYS).contains(&days_left).then(|| UpcomingExpiry {
+                        symbol: h.symbol.clone(),
+                        expires_on,
+                        days_left,
+                    })
+                })
+                .collect();
+            expiries.sort_by_key(|e| e.days_left);
+
+            let mut sections: Vec<String> = render_digest(value.as_ref(), movers.as_ref(), &expiries).into_iter().collect();
+            let ctx = ReportContext { db: &self.pb_client, user_id: &user_id, date: local_date };
+            for section in &self.report_sections {
+                match section.render(&ctx).await {
+                    Ok(Some(text)) => sections.push(format!("{}\n{}", section.title(), text)),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("⚠️ Digest section '{}' failed for {}: {}", section.title(), user_id, e),
+                }
+            }
+
+            // Marked sent even with nothing to report, so the user isn't retried all day
+            if let Some(body) = (!sections.is_empty()).then(|| sections.join("\n\n")) {
+                if let Err(e) = notification_service.send_report(&user_id, "Daily portfolio digest", &body).await {
+                    errors += 1;
+                    tracing::warn!("⚠️ Failed to send daily digest to {}: {}", user_id, e);
+                    continue;
+                }
+                sent += 1;
+            } else {
+                skipped += 1;
+            }
+            if let Err(e) = self.pb_client.update_record::<serde_json::Value>(
+                USER_SETTINGS_COLLECTION,
+                &settings.id,
+                &serde_json::json!({ "digest_sent_on": local_date.format("%Y-%m-%d").to_string() }),
+            ).await {
+                tracing::warn!("⚠️ Could not mark daily digest sent for {}: {}", user_id, e);
+            }
+        }
+
+        tracing::info!("✅ Daily digest complete: {} sent, {} skipped, {} errors", sent, skipped, errors);
+        Ok(serde_json::json!({
+            "digests_sent": sent,
+            "skipped": skipped,
+            "errors": errors
+        }))
+    }
+
+    /// Remove (or with ORPHAN_CLEANUP_DRY_RUN only report) records left behind by deleted users,
+    /// accounts, liabilities and alerts
+    async fn run_orphan_cleanup_job(&self) -> Result<serde_json::Value, String> {
+        let report = clean_orphans(&self.pb_client, self.config.orphan_cleanup_dry_run)
+            .await
+            .map_err(|e| e.to_string())?;
+        serde_json::to_value(&report).map_err(|e| e.to_string())
+    }
+
+    /// Store the latest daily closes of the benchmark indices (SET, S&P 500, BTC)
+    async fn run_benchmark_update_job(&self) -> Result<serde_json::Value, String> {
+        tracing::info!("📊 Running benchmark update job...");
+
+        let mut benchmarks = Vec::new();
+        let mut stored = 0;
+        let mut errors = 0;
+        for benchmark in BENCHMARKS {
+            let refresh = match refresh_levels(&self.pb_client, &self.price_service, benchmark).await {
+                Ok(refresh) => refresh,
+                Err(e) => {
+                    errors += 1;
+                    tracing::warn!("⚠️ Failed to update benchmark {}: {}", benchmark.code, e);
+                    crate::services::benchmarks::BenchmarkRefresh {
+                        benchmark: benchmark.code.to_string(),
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    }
+                }
+            };
+            stored += refresh.stored;
+            benchmarks.push(refresh);
+        }
+
+        tracing::info!("✅ Benchmark update complete: {} levels stored", stored);
+
+        Ok(serde_json::json!({
+            "stored": stored,
+            "errors": errors,
+            "benchmarks": benchmarks
+        }))
+    }
+
+    /// Vest due RSU/ESPP tranches for all users, creating acquisition transactions at the market price
+    async fn run_equity_vesting_job(&self) -> Result<serde_json::Value, String> {
+        tracing::info!("📜 Running equity vesting job...");
+
+        let grants: Vec<EquityGrant> = self.pb_client
+            .list_records(EQUITY_GRANTS_COLLECTION, None, "grant_date")
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut grants_vested = 0;
+        let mut tranches_vested = 0;
+        let mut errors = 0;
+
+        for mut grant in grants.into_iter().filter(|g| g.unvested_quantity() > 0.0) {
+            match vest_due_tranches(&self.pb_client, &self.price_service, &mut grant).await {
+                Ok(outcome) => {
+                    if outcome.vested > 0 {
+                        grants_vested += 1;
+                        tranches_vested += outcome.vested;
+                    }
+                    for error in &outcome.errors {
+                        errors += 1;
+                        tracing::warn!("⚠️ Failed to vest grant {} ({}): {}", grant.id, grant.symbol, error);
+                    }
+                }
+                Err(e) => {
+                    errors += 1;
+                    tracing::warn!("⚠️ Failed to vest grant {} ({}): {}", grant.id, grant.symbol, e);
+                }
+            }
+        }
+
+        tracing::info!("✅ Equity vesting complete: {} tranches across {} grants", tranches_vested, grants_vested);
+
+        Ok(serde_json::json!({
+            "grants_vested": grants_vested,
+            "tranches_vested": tranches_vested,
+            "errors": errors
+        }))
+    }
+
+    /// Accrue loan interest up to `today`, capitalizing it on each compounding date. Returns the amount capitalized.
+    /// Each charge is saved with its transaction and added to the stored balance rather than
+    /// overwriting it, so a failed run resumes after the last charge and repayments made
+    /// meanwhile are kept.
+    async fn accrue_liability_interest(&self, liability: &Liability, today: NaiveDate) -> Result<f64, String> {
+        let mut last_accrued = liability.last_accrued_date.as_deref()
+            .and_then(|s| NaiveDate::parse_from_str(s.get(..10).unwrap_or(s), "%Y-%m-%d").ok())
+            .unwrap_or(today);
+        if last_accrued >= today {
+            return Ok(0.0);
+        }
+
+        let daily_rate = liability.apr_percent / 100.0 / 365.0;
+        let mut balance = liability.balance;
+        let mut pending = liability.pending_interest;
+        let mut charged = 0.0;
+
+        while last_accrued < today {
+            let day = last_accrued + chrono::Duration::days(1);
+            pending += balance * daily_rate;
+
+            if Self::is_interest_credit_day(&liability.compounding, day, None) && pending > 0.0 {
+                let amount = round_money(pending);
+                let tx = LiabilityTransaction {
+                    id: String::new(),
+                    liability_id: liability.id.clone(),
+                    user_id: liability.user_id.clone(),
+                    kind: LiabilityTransactionKind::Interest,
+                    amount,
+                    timestamp: day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+                    notes: None,
+                };
+                let created = self.pb_client.create_liability_transaction(&tx).await.map_err(|e| e.to_string())?;
+                let update = self.pb_client.update_liability(&liability.id, serde_json::json!({
+                    "balance+": amount,
+                    "pending_interest": 0.0,
+                    "last_accrued_date": day.format("%Y-%m-%d").to_string(),
+                })).await;
+                match update {
+                    Ok(updated) => balance = updated.balance,
+                    Err(e) => {
+                        // Without the charge recorded the next run books the day again
+                        if let Err(undo) = self.pb_client.delete_record("liability_transactions", &created.id).await {
+                            tracing::error!("❌ Liability {} interest {} kept without its balance update: {}", liability.id, created.id, undo);
+                        }
+                        return Err(format!("interest of {} charged but not recorded: {}", amount, e));
+                    }
+                }
+                charged += amount;
+                pending = 0.0;
+            }
+
+            last_accrued = day;
+        }
+
+        self.pb_client.update_liability(&liability.id, serde_json::json!({
+            "pending_interest": pending,
+            "last_accrued_date": last_accrued.format("%Y-%m-%d").to_string(),
+        })).await.map_err(|e| e.to_string())?;
+
+        if charged > 0.0 {
+            tracing::info!("💸 Charged {:.2} {} interest on liability {}", charged, liability.currency, liability.id);
+        }
+        Ok(charged)
+    }
+
+    /// Bring an account's interest up to today under its current settings, before they change.
+    /// Returns the amount credited.
+    pub async fn settle_account_interest(&self, account_id: &str) -> Result<f64, AppError> {
+        let _accruing = self.interest_accrual.lock().await;
+        let account = self.pb_client.get_account(account_id).await?;
+        let apr = account.apr_percent.unwrap_or(0.0);
+        if account.account_type == AccountType::Investment || account.archived || apr <= 0.0 {
+            return Ok(0.0);
+        }
+        self.accrue_account_interest(&account, apr, Utc::now().date_naive()).await
+            .map_err(|e| AppError::Internal(format!("Failed to accrue interest for account {}: {}", account_id, e)))
+    }
+
+    /// Accrue interest for one account up to (and including) `today`. Returns the amount credited.
+    async fn accrue_account_interest(&self, account: &Account, apr: f64, today: NaiveDate) -> Result<f64, String> {
+        let parse_date = |s: &str| NaiveDate::parse_from_str(s.get(..10).unwrap_or(s), "%Y-%m-%d").ok();
+        let mut last_accrued = account.last_accrued_date.as_deref()
+            .and_then(parse_date)
+            .unwrap_or_else(|| account.created_at.date_naive());
+        let maturity = account.maturity_date.as_deref().and_then(parse_date);
+        let end = match maturity {
+            Some(m) if m < today => m,
+            _ => today,
+        };
+        if last_accrued >= end {
+            return Ok(0.0);
+        }
+
+        let mut cash_txs: Vec<_> = self.pb_client.get_transactions_by_account(&account.id).await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|t| t.asset_type == AssetType::Cash)
+            .collect();
+        cash_txs.sort_by_key(|t| t.timestamp);
+        let currency = cash_txs.first()
+            .map(|t| t.symbol.clone())
+            .unwrap_or_else(|| account.target_currency.to_uppercase());
+
+        let signed_amount = |t: &crate::models::Transaction| match t.action {
+            TradeAction::Deposit | TradeAction::Buy => t.quantity,
+            TradeAction::Withdraw | TradeAction::Sell => -t.quantity,
+            _ => 0.0,
+        };
+
+        let daily_rate = apr / 100.0 / 365.0;
+        let mut pending = account.pending_interest;
+        let mut credited = 0.0;
+        let mut balance = 0.0;
+        let mut next_tx = 0;
+
+        while last_accrued < end {
+            let day = last_accrued + chrono::Duration::days(1);
+            // Balance at the start of `day`
+            while next_tx < cash_txs.len() && cash_txs[next_tx].timestamp.date_naive() < day {
+                balance += signed_amount(&cash_txs[next_tx]);
+                next_tx += 1;
+            }
+
+            if balance > 0.0 {
+                pending += balance * daily_rate;
+            }
+
+            if Self::is_interest_credit_day(&account.compounding, day, maturity) && pending > 0.0 {
+                let amount = round_money(pending);
+                self.credit_interest(account, &currency, amount, day).await?;
+                balance += amount;
+                credited += amount;
+                pending = 0.0;
+                // Recorded with each credit, so a later failure doesn't credit these days again
+                self.pb_client
+                    .update_account_accrual(&account.id, pending, &day.format("%Y-%m-%d").to_string())
+                    .await
+                    .map_err(|e| format!("interest of {} credited but not recorded: {}", day, e))?;
+            }
+
+            last_accrued = day;
+        }
+
+        self.pb_client
+            .update_account_accrual(&account.id, pending, &last_accrued.format("%Y-%m-%d").to_string())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(credited)
+    }
+
+    fn is_interest_credit_day(compounding: &Compounding, day: NaiveDate, maturity: Option<NaiveDate>) -> bool {
+        if maturity == Some(day) {
+            return true;
+        }
+        match compounding {
+            Compounding::Daily => true,
+            Compounding::Monthly => day.day() == 1,
+            Compounding::Quarterly => day.day() == 1 && matches!(day.month(), 1 | 4 | 7 | 10),
+            Compounding::Annually => day.day() == 1 && day.month() == 1,
+            Compounding::AtMaturity => false,
+        }
+    }
+
+    /// Record credited interest as income (dividend) and add it to the cash balance (deposit)
+    async fn credit_interest(&self, account: &Account, currency: &str, amount: f64, day: NaiveDate) -> Result<(), String> {
+        let timestamp = day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+        let notes = format!("Interest ({})", account.name);
+
+        let income = CreateTransactionRequest {
+            asset_type: AssetType::Cash,
+            symbol: currency.to_string(),
+            symbol_name: None,
+            action: TradeAction::Dividend,
+            quantity: 1.0,
+            price: amount,
+            fees: 0.0,
+            network_fee: 0.0,
+            timestamp,
+            market: None,
+            currency: Some(currency.to_string()),
+            notes: Some(notes.clone()),
+            account_id: Some(account.id.clone()),
+            tags: vec![INTEREST_TAG.to_string()],
+            leverage: None,
+            initial_margin: None,
+            unit: None,
+            custom_fields: Default::default(),
+        };
+        let deposit = CreateTransactionRequest {
+            action: TradeAction::Deposit,
+            quantity: amount,
+            price: 1.0,
+            ..income.clone()
+        };
+
+        self.pb_client.create_transaction(income, &account.user_id).await.map_err(|e| e.to_string())?;
+        self.pb_client.create_transaction(deposit, &account.user_id).await.map_err(|e| e.to_string())?;
+        tracing::info!("💰 Credited {:.2} {} interest to account {}", amount, currency, account.id);
+        Ok(())
+    }
+
     /// Run portfolio snapshot job - capture daily portfolio performance for all users
     async fn run_portfolio_snapshot_job(&self) -> Result<serde_json::Value, String> {
-        tracing::info!("📸 Running portfolio snapshot job...");
+        self.snapshot_all_users(Utc::now().date_naive()).await
+    }
+
+    /// Snapshot every missed day, oldest first. Days that fail are kept for the retry run.
+    async fn run_snapshot_backfill(&self, job_id: &str, dates: Vec<NaiveDate>) -> Result<serde_json::Value, String> {
+        tracing::info!("⏪ Backfilling {} portfolio snapshot day(s) from {}", dates.len(), dates[0]);
+        let mut days = Vec::new();
+        let mut failed = Vec::new();
+        for date in dates {
+            match self.snapshot_all_users(date).await {
+                Ok(result) => days.push(result),
+                Err(e) => {
+                    tracing::warn!("⚠️ Could not backfill snapshots for {}: {}", date, e);
+                    failed.push((date, e));
+                }
+            }
+        }
+        if let Some((_, error)) = failed.first() {
+            let error = format!("{} of {} day(s) not backfilled, first error: {}", failed.len(), failed.len() + days.len(), error);
+            self.backfill_dates.write().await.insert(job_id.to_string(), failed.into_iter().map(|(date, _)| date).collect());
+            return Err(error);
+        }
+        Ok(serde_json::json!({
+            "backfilled_days": days.len(),
+            "days": days
+        }))
+    }
+
+    /// Snapshot every user's portfolio for `date`. Past days are valued as of their end, from
+    /// the transactions made by then and the recorded price history.
+    async fn snapshot_all_users(&self, date: NaiveDate) -> Result<serde_json::Value, String> {
+        tracing::info!("📸 Running portfolio snapshot job for {}...", date);
         
         let token = self.pb_client.get_token().await;
-        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let today = date.format("%Y-%m-%d").to_string();
         
         // Step 1: Get all users
         let users_url = format!("{}/api/collections/users/records?perPage=500", self.pocketbase_url);
@@ -934,14 +2269,24 @@ impl JobScheduler {
         let mut updated = 0;
         let mut errors = 0;
         
-        // Step 2: For each user, calculate portfolio and save snapshot
-        for user in &users {
-            match self.create_user_snapshot(&user.id, &today, &token).await {
+        // Step 2: For each user, calculate portfolio and save snapshot, a few users at a time
+        let user_ids: Vec<String> = users.iter().map(|user| user.id.clone()).collect();
+        let (today, token) = (&today, &token);
+        let outcomes: Vec<(String, Result<bool, String>)> = stream::iter(user_ids)
+            .map(|user_id| async move {
+                let outcome = self.create_user_snapshot(&user_id, date, token).await;
+                (user_id, outcome)
+            })
+            .buffer_unordered(self.config.snapshot_concurrency.max(1))
+            .collect()
+            .await;
+        for (user_id, outcome) in outcomes {
+            match outcome {
                 Ok(is_new) => {
                     if is_new { created += 1; } else { updated += 1; }
                 }
                 Err(e) => {
-                    tracing::warn!("⚠️ Failed to create snapshot for user {}: {}", user.id, e);
+                    tracing::warn!("⚠️ Failed to create snapshot for user {}: {}", user_id, e);
                     errors += 1;
                 }
             }
@@ -961,9 +2306,12 @@ impl JobScheduler {
     }
 
     /// Create snapshot for a single user
-    async fn create_user_snapshot(&self, user_id: &str, date: &str, token: &str) -> Result<bool, String> {
-        // Fetch transactions for user
-        let tx_filter = format!("user_id='{}'", user_id);
+    async fn create_user_snapshot(&self, user_id: &str, day: NaiveDate, token: &str) -> Result<bool, String> {
+        let date = day.format("%Y-%m-%d").to_string();
+        let end_of_day = (day + chrono::Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+        let historical = day < Utc::now().date_naive();
+        // Fetch transactions for user, leaving out soft-deleted ones
+        let tx_filter = format!("user_id='{}' && deleted_at=''", user_id);
         let tx_url = format!(
             "{}/api/collections/transactions/records?filter={}&perPage=500",
             self.pocketbase_url,
@@ -985,6 +2333,8 @@ impl JobScheduler {
             leverage: Option<f64>,
             #[allow(dead_code)]
             account_id: Option<String>,
+            #[serde(default, deserialize_with = "crate::models::transaction::deserialize_optional_date")]
+            timestamp: Option<DateTime<Utc>>,
         }
         
         #[derive(serde::Deserialize)]
@@ -995,7 +2345,7 @@ impl JobScheduler {
         let req = self.http_client.get(&tx_url);
         let req = if !token.is_empty() { req.header("Authorization", token) } else { req };
         
-        let transactions: Vec<Transaction> = match req.send().await {
+        let mut transactions: Vec<Transaction> = match req.send().await {
             Ok(resp) if resp.status().is_success() => {
                 resp.json::<TxResponse>().await
                     .map(|r| r.items)
@@ -1003,6 +2353,9 @@ impl JobScheduler {
             }
             _ => return Ok(true), // No transactions, skip
         };
+        if historical {
+            transactions.retain(|tx| tx.timestamp.is_some_and(|t| t < end_of_day));
+        }
         
         if transactions.is_empty() {
             return Ok(true); // No transactions
@@ -1065,6 +2418,45 @@ impl JobScheduler {
             
             let symbol = key.split(':').next().unwrap_or("");
             
+            if historical {
+                let (current_price, price_source, price_updated_at) = match asset_type.parse::<AssetType>() {
+                    Ok(parsed_type) => {
+                        let parsed_market = market.as_deref().and_then(|m| m.parse::<Market>().ok());
+                        match price_as_of(&self.pb_client, symbol, &parsed_type, parsed_market.as_ref(), end_of_day - chrono::Duration::seconds(1)).await {
+                            Ok(Some(point)) => (point.price, point.source.unwrap_or_else(|| "history".to_string()), point.recorded_at),
+                            Ok(None) => (*avg_cost, "avg_cost".to_string(), None),
+                            Err(e) => return Err(format!("price history of {}: {}", symbol, e)),
+                        }
+                    }
+                    Err(_) => (*avg_cost, "avg_cost".to_string(), None),
+                };
+                let current_value = quantity.abs() * current_price;
+                let cost_basis = quantity.abs() * avg_cost;
+                let unrealized_pnl = if *quantity > 0.0 { current_value - cost_basis } else { cost_basis - current_value };
+                total_invested += cost_basis;
+                total_current_value += current_value;
+                total_unrealized_pnl += unrealized_pnl;
+                let mut asset_obj = serde_json::json!({
+                    "symbol": symbol,
+                    "asset_type": asset_type,
+                    "quantity": quantity,
+                    "avg_cost": avg_cost,
+                    "current_price": current_price,
+                    "current_value": current_value,
+                    "unrealized_pnl": unrealized_pnl,
+                    "unrealized_pnl_percent": if cost_basis > 0.0 { unrealized_pnl / cost_basis * 100.0 } else { 0.0 },
+                    "price_source": price_source
+                });
+                if let Some(at) = price_updated_at {
+                    asset_obj["price_updated_at"] = serde_json::json!(at);
+                }
+                if let Some(m) = market {
+                    asset_obj["market"] = serde_json::json!(m);
+                }
+                assets_json.push(asset_obj);
+                continue;
+            }
+            
             // Try to fetch price from asset_prices collection
             let price_filter = format!("symbol='{}' && asset_type='{}'", symbol, asset_type);
             let price_url = format!(
@@ -1076,18 +2468,52 @@ impl JobScheduler {
             let req = self.http_client.get(&price_url);
             let req = if !token.is_empty() { req.header("Authorization", token) } else { req };
             
-            let current_price: f64 = match req.send().await {
-                Ok(resp) if resp.status().is_success() => {
-                    if let Ok(data) = resp.json::<serde_json::Value>().await {
-                        if let Some(items) = data.get("items").and_then(|i| i.as_array()) {
-                            if let Some(first) = items.first() {
-                                first.get("price").and_then(|p| p.as_f64()).unwrap_or(*avg_cost)
-                            } else { *avg_cost }
-                        } else { *avg_cost }
-                    } else { *avg_cost }
-                }
-                _ => *avg_cost
+            let stored_record = match req.send().await {
+                Ok(resp) if resp.status().is_success() => resp.json::<serde_json::Value>().await.ok()
+                    .and_then(|data| data.get("items")?.as_array()?.first().cloned()),
+                _ => None
             };
+            let stored = stored_record.as_ref()
+                .and_then(|first| Some((first.get("price")?.as_f64()?, stored_price_source(first))));
+            // Without a stored price the holding is valued at cost
+            let (mut current_price, (mut price_source, mut price_updated_at)) =
+                stored.unwrap_or((*avg_cost, ("avg_cost".to_string(), None)));
+            // Currency the holding is valued in: the stored price's, else that of its transactions
+            let holding_currency = stored_record.as_ref()
+                .and_then(|first| first.get("currency")?.as_str().filter(|c| !c.is_empty()).map(str::to_string))
+                .or_else(|| transactions.iter()
+                    .find(|tx| tx.symbol == symbol && tx.asset_type == *asset_type)
+                    .and_then(|tx| tx.currency.clone()));
+            
+            // High-value holdings: cross-check the price across providers
+            let mut consensus_info: Option<serde_json::Value> = None;
+            let consensus_min_value = self.config.price_consensus_min_value;
+            if consensus_min_value > 0.0 && quantity.abs() * current_price >= consensus_min_value {
+                if let Ok(parsed_type) = asset_type.parse::<AssetType>() {
+                    let parsed_market = market.as_deref().and_then(|m| m.parse::<Market>().ok());
+                    match self.price_service.get_consensus_price(symbol, &parsed_type, parsed_market.as_ref()).await {
+                        Ok(consensus) if holding_currency.as_deref()
+                            .is_some_and(|c| quote_currency(c) != quote_currency(&consensus.entry.currency)) => {
+                            tracing::warn!(
+                                "⚠️ Consensus price for {} is in {}, holding is valued in {}; keeping the stored price",
+                                symbol, consensus.entry.currency, holding_currency.as_deref().unwrap_or_default()
+                            );
+                        }
+                        Ok(consensus) => {
+                            current_price = consensus.entry.price;
+                            price_source = "consensus".to_string();
+                            price_updated_at = Some(consensus.entry.updated_at);
+                            consensus_info = Some(serde_json::json!({
+                                "providers": consensus.quotes.len(),
+                                "agreeing": consensus.agreeing,
+                                "divergence_percent": consensus.divergence_percent,
+                                "diverged": consensus.diverged
+                            }));
+                        }
+                        Err(e) => tracing::warn!("⚠️ Consensus price failed for {}: {}", symbol, e),
+                    }
+                }
+            }
             
             let current_value = quantity.abs() * current_price;
             let cost_basis = quantity.abs() * avg_cost;
@@ -1110,12 +2536,19 @@ impl JobScheduler {
                 "current_price": current_price,
                 "current_value": current_value,
                 "unrealized_pnl": unrealized_pnl,
-                "unrealized_pnl_percent": pnl_percent
+                "unrealized_pnl_percent": pnl_percent,
+                "price_source": price_source
             });
             
+            if let Some(at) = price_updated_at {
+                asset_obj["price_updated_at"] = serde_json::json!(at);
+            }
             if let Some(m) = market {
                 asset_obj["market"] = serde_json::json!(m);
             }
+            if let Some(info) = consensus_info {
+                asset_obj["price_consensus"] = info;
+            }
             
             assets_json.push(asset_obj);
         }
@@ -1155,13 +2588,15 @@ impl JobScheduler {
             "total_realized_pnl": 0.0, // TODO: Calculate from closed positions
             "assets_count": assets_json.len(),
             "currency": "THB",
-            "assets": assets_json
+            "assets": assets_json,
+            // Holdings are summed in their quoted prices without FX, so no rates were applied
+            "conversions": []
         });
         
         // Create or update snapshot
         let is_new = existing_id.is_none();
         
-        let result = if let Some(id) = existing_id {
+        let result = if let Some(id) = existing_id.clone() {
             let update_url = format!("{}/api/collections/portfolio_snapshots/records/{}", self.pocketbase_url, id);
             let req = self.http_client.patch(&update_url);
             let req = if !token.is_empty() { req.header("Authorization", token) } else { req };
@@ -1174,7 +2609,18 @@ impl JobScheduler {
         };
         
         match result {
-            Ok(resp) if resp.status().is_success() => Ok(is_new),
+            Ok(resp) if resp.status().is_success() => {
+                if let Some(cache) = &self.snapshot_cache {
+                    cache.invalidate(user_id).await;
+                }
+                let record = resp.json::<serde_json::Value>().await.unwrap_or_default();
+                let id = record.get("id").and_then(|i| i.as_str()).map(String::from).or(existing_id);
+                if let Some(id) = id {
+                    let op = if is_new { SyncOp::Created } else { SyncOp::Updated };
+                    self.pb_client.record_event(user_id, SyncEntity::Snapshot, &id, op, Some(&record));
+                }
+                Ok(is_new)
+            }
             Ok(resp) => {
                 let status = resp.status();
                 let body = resp.text().await.unwrap_or_default();
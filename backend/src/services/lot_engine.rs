@@ -0,0 +1,530 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::models::{AssetType, PortfolioAsset, Transaction, TradeAction};
+
+/// Per-asset-type quantities below which a reduced position counts as closed
+#[derive(Debug, Clone, Default)]
+pub struct DustPolicy {
+    thresholds: HashMap<String, f64>,
+}
+
+impl DustPolicy {
+    /// Parse a DUST_THRESHOLDS value such as "crypto=0.000001,gold=0.0001",
+    /// skipping (and logging) invalid entries
+    pub fn parse(value: &str) -> Self {
+        let mut thresholds = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let parsed = entry.split_once('=').and_then(|(asset_type, threshold)| {
+                let asset_type = asset_type.parse::<AssetType>().ok()?;
+                let threshold = threshold.trim().parse::<f64>().ok().filter(|t| *t >= 0.0)?;
+                Some((asset_type.to_string(), threshold))
+            });
+            match parsed {
+                Some((asset_type, threshold)) => {
+                    thresholds.insert(asset_type, threshold);
+                }
+                None => tracing::warn!("⚠️ Ignoring DUST_THRESHOLDS entry: '{}'", entry),
+            }
+        }
+        Self { thresholds }
+    }
+
+    pub fn threshold(&self, asset_type: &AssetType) -> f64 {
+        self.thresholds.get(&asset_type.to_string()).copied().unwrap_or(0.0)
+    }
+}
+
+/// A remainder that was treated as closed, its leftover cost booked as realized P&L
+#[derive(Debug, Clone, Serialize)]
+pub struct DustCleanup {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub position_type: String,
+    pub quantity: f64,
+    pub currency: String,
+    /// Realized P&L added for the remainder (minus its remaining cost basis)
+    pub realized_pnl: f64,
+    pub closed_at: DateTime<Utc>,
+}
+
+/// Gain or loss booked when (part of) a position was closed
+#[derive(Debug, Clone)]
+pub struct RealizedTrade {
+    pub timestamp: DateTime<Utc>,
+    pub currency: String,
+    /// Net of the closing fees and the share of opening fees
+    pub pnl: f64,
+    pub symbol: String,
+    pub asset_type: AssetType,
+    /// spot, long or short
+    pub position_type: String,
+    /// The closing transaction
+    pub transaction_id: String,
+    /// The transaction that opened the position (from flat)
+    pub opened_by: Option<String>,
+    /// Quantity-weighted entry time of the closed part
+    pub opened_at: Option<DateTime<Utc>>,
+    pub quantity: f64,
+    /// Cost of the closed part including its share of opening fees
+    pub cost_basis: f64,
+    /// Remainder closed by the dust policy rather than a trade
+    pub dust: bool,
+}
+
+/// When and by which transaction an open position was entered
+struct Entry {
+    opened_by: String,
+    /// Quantity-weighted Unix seconds of the increases since the position was flat
+    weighted_seconds: f64,
+}
+
+impl Entry {
+    fn opened_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.weighted_seconds.round() as i64, 0)
+    }
+}
+
+/// Positions and realized results from replaying a set of transactions
+pub struct LotReplay {
+    /// Keyed by asset_type:market:symbol:position_bucket
+    pub holdings: HashMap<String, PortfolioAsset>,
+    /// Sum of realized P&L in mixed currencies (see the breakdown)
+    pub realized_pnl: f64,
+    pub total_dividend: f64,
+    pub realized_pnl_breakdown: HashMap<String, f64>,
+    /// Every realizing trade, oldest first
+    pub realized_trades: Vec<RealizedTrade>,
+    /// Positions closed by the dust policy
+    pub dust_cleaned: Vec<DustCleanup>,
+}
+
+/// Replay transactions with weighted average cost per position (spot, long and short kept
+/// apart for hedge mode). Prices are not looked up; holdings carry cost and quantity only.
+pub fn replay(transactions: &[Transaction], dust: &DustPolicy) -> LotReplay {
+    // Sort transactions by timestamp ascending (oldest first) for correct P&L calculation
+    let mut sorted_transactions = transactions.to_vec();
+    sorted_transactions.sort_by_key(|a| a.timestamp);
+    
+    // Group transactions by symbol+type+market and calculate holdings
+    let mut holdings: HashMap<String, PortfolioAsset> = HashMap::new();
+    let mut realized_pnl = 0.0; // Keep for backward compatibility (sum of all raw values)
+    let mut total_dividend = 0.0; // Track total dividends across all assets (active + closed)
+    let mut realized_pnl_breakdown: HashMap<String, f64> = HashMap::new();
+    let mut realized_trades = Vec::new();
+    let mut dust_cleaned = Vec::new();
+    let mut entries: HashMap<String, Entry> = HashMap::new();
+    
+    for tx in &sorted_transactions {
+        // Determine position "bucket" to support Hedge Mode (separating Spot, Long, Short)
+        let position_bucket = match tx.action {
+            TradeAction::Buy | TradeAction::Sell => "spot",
+            TradeAction::Long | TradeAction::CloseLong | TradeAction::LiquidateLong => "long",
+            TradeAction::Short | TradeAction::CloseShort | TradeAction::LiquidateShort => "short",
+            TradeAction::Dividend => "spot",
+            TradeAction::Deposit | TradeAction::Withdraw => "spot",
+        };
+        
+        let market_key = tx.market.as_ref().map(|m| m.to_string()).unwrap_or_default();
+        // Key now includes position_bucket to separate distinct positions (Hedge Mode)
+        let key = format!("{}:{}:{}:{}", tx.asset_type, market_key, tx.symbol, position_bucket);
+        
+        // Normalize quantity and price to base unit (e.g. Oz for gold)
+        let (tx_quantity, base_unit) = crate::utils::units::normalize_quantity(tx.quantity, tx.unit.as_deref(), &tx.asset_type, &tx.symbol);
+        let tx_price = crate::utils::units::normalize_price(tx.price, tx.unit.as_deref(), &tx.asset_type, &tx.symbol);
+
+        let asset = holdings.entry(key.clone()).or_insert_with(|| {
+            let currency = tx.currency.clone()
+                .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
+                .unwrap_or_else(|| "THB".to_string());
+            let mut new_asset = PortfolioAsset::new(
+                tx.symbol.clone(), 
+                tx.asset_type.clone(),
+                tx.market.clone(),
+                currency,
+            );
+            new_asset.currency_explicit = tx.currency.is_some() || tx.market.is_some();
+            
+            // Set unit if not standard share
+            if base_unit != "share" {
+                new_asset.unit = Some(base_unit);
+            }
+
+            new_asset.position_type = position_bucket.to_string(); // Set the position type
+            
+            // Set leverage from first transaction
+            if let Some(lev) = tx.leverage {
+                new_asset.leverage = lev;
+            }
+            new_asset
+        });
+        
+        // Update leverage if transaction has one (take the latest)
+        if let Some(lev) = tx.leverage {
+            asset.leverage = lev;
+        }
+
+        let increases = matches!(tx.action, TradeAction::Buy | TradeAction::Long | TradeAction::Deposit | TradeAction::Short);
+        if increases && tx_quantity > 0.0 {
+            let seconds = tx.timestamp.timestamp() as f64;
+            let held = asset.quantity.abs();
+            match entries.get_mut(&key) {
+                Some(entry) if held > 0.0 => {
+                    entry.weighted_seconds = (entry.weighted_seconds * held + seconds * tx_quantity) / (held + tx_quantity);
+                }
+                _ => {
+                    entries.insert(key.clone(), Entry { opened_by: tx.id.clone(), weighted_seconds: seconds });
+                }
+            }
+        }
+        let entry = entries.get(&key);
+        
+        match tx.action {
+            TradeAction::Buy | TradeAction::Long | TradeAction::Deposit => {
+                // Buy, Long, Deposit - increase position
+                let new_quantity = asset.quantity + tx_quantity;
+                
+                // Update Weighted Average Cost (PRICE)
+                // We MUST use Price for avg_cost to ensure P/L calculations are correct.
+                let current_notional = asset.quantity.abs() * asset.avg_cost;
+                let new_notional = tx_quantity * tx_price;
+                
+                if new_quantity.abs() > 0.0 {
+                    asset.avg_cost = (current_notional + new_notional) / new_quantity.abs();
+                } else {
+                    asset.avg_cost = tx.price;
+                }
+                
+                // Update Total Cost (Invested Amount / Margin)
+                // Use initial_margin ONLY for Leveraged assets (TFEX, Futures)
+                let use_margin = tx.asset_type == AssetType::Tfex || 
+                               (tx.asset_type == AssetType::Crypto && tx.leverage.unwrap_or(1.0) > 1.0);
+
+                let invest_amount = match tx.initial_margin {
+                    Some(margin) if use_margin => margin,
+                    _ => tx_quantity * tx_price,
+                };
+                
+                // Add new investment + fees (network fees included) to total cost basis
+                asset.total_cost += invest_amount + tx.all_fees();
+                
+                // Track fees separately
+                asset.total_fees += tx.all_fees();
+                asset.quantity = new_quantity;
+            }
+            TradeAction::Short => {
+                // Open Short - create/increase negative position
+                let new_quantity = asset.quantity - tx_quantity;  // Goes more negative
+                
+                // Update Weighted Average Cost (PRICE)
+                let current_notional = asset.quantity.abs() * asset.avg_cost;
+                let new_notional = tx_quantity * tx_price;
+                
+                if new_quantity.abs() > 0.0 {
+                    asset.avg_cost = (current_notional + new_notional) / new_quantity.abs();
+                } else {
+                    asset.avg_cost = tx.price;
+                }
+                
+                // Update Total Cost (Invested Amount / Margin)
+                let use_margin = tx.asset_type == AssetType::Tfex || 
+                               (tx.asset_type == AssetType::Crypto && tx.leverage.unwrap_or(1.0) > 1.0);
+
+                let invest_amount = match tx.initial_margin {
+                    Some(margin) if use_margin => margin,
+                    _ => tx_quantity * tx_price,
+                };
+                
+                asset.total_cost += invest_amount + tx.all_fees();
+                
+                asset.total_fees += tx.all_fees();
+                asset.quantity = new_quantity;
+            }
+            TradeAction::Sell | TradeAction::CloseLong | TradeAction::LiquidateLong => {
+                // Sell, CloseLong - close long position
+                if asset.quantity > 0.0 {
+                    // Realized PnL Calculation
+                    // Realized PnL Calculation
+                    // For TFEX/Futures, we must apply the multiplier (stored in asset.leverage)
+                    let multiplier = if asset.asset_type == AssetType::Tfex { asset.leverage } else { 1.0 };
+                    
+                    // Value = Price * Qty * Multiplier
+                    let sell_value = (tx_quantity * tx_price * multiplier) - tx.all_fees();
+                    let cost_basis = tx_quantity * asset.avg_cost * multiplier; // Notional Cost
+                    
+                    // Reduce quantity
+                    let ratio = tx_quantity / asset.quantity;
+                    
+                    // Reduce Fees proportionally
+                    let fee_portion = asset.total_fees * ratio;
+                    asset.total_fees -= fee_portion;
+                    
+                    // Reduce Total Cost (Invested) proportionally
+                    // This correctly handles both Spot (Notional) and Futures (Margin)
+                    asset.total_cost -= asset.total_cost * ratio;
+                    
+                    // PnL = (Sell Value - Fees) - (Cost Basis + Historical Buy Fees)
+                    // Note: sell_value already extracted tx.fees. We subtract historical fees.
+                    // For Futures: PnL = (Exit - Entry) * Qty - Fees
+                    // This formula: (Price * Qty - SellFees) - (AvgPrice * Qty - BuyFees)
+                    // = (Price - AvgPrice) * Qty - (SellFees + BuyFees). Correct.
+                    let pnl = sell_value - cost_basis - fee_portion;
+
+                    realized_pnl += pnl;
+                    
+                    // Accumulate breakdown by currency
+                    let currency = tx.currency.clone()
+                        .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
+                        .unwrap_or_else(|| "THB".to_string());
+                    *realized_pnl_breakdown.entry(currency.clone()).or_insert(0.0) += pnl;
+                    realized_trades.push(RealizedTrade {
+                        timestamp: tx.timestamp,
+                        currency,
+                        pnl,
+                        symbol: asset.symbol.clone(),
+                        asset_type: asset.asset_type.clone(),
+                        position_type: asset.position_type.clone(),
+                        transaction_id: tx.id.clone(),
+                        opened_by: entry.map(|e| e.opened_by.clone()),
+                        opened_at: entry.and_then(Entry::opened_at),
+                        quantity: tx_quantity,
+                        cost_basis: cost_basis + fee_portion,
+                        dust: false,
+                    });
+                    
+                    asset.realized_pnl += pnl;
+                    asset.quantity -= ratio * asset.quantity.abs(); // reduce towards 0
+                }
+            }
+            TradeAction::Withdraw => {
+                // Withdraw - reduce position like Sell, but NO Realized PnL
+                if asset.quantity > 0.0 {
+                    // Reduce quantity
+                    let ratio = tx_quantity / asset.quantity;
+                    
+                    // Reduce Fees proportionally
+                    // Fees paid for withdrawal (network fees included) are expenses, not cost basis
+                    // However, we subtract them from total_fees to keep bookkeeping clean
+                    let fee_portion = asset.total_fees * ratio;
+                    asset.total_fees -= fee_portion;
+                    
+                    // Reduce Total Cost (Invested) proportionally
+                    // This is key: getting money OUT reduces your invested principal
+                    asset.total_cost -= asset.total_cost * ratio;
+                    
+                    // No PnL calculation for Withdraw
+                    // Just reduce the asset size
+                    asset.quantity -= ratio * asset.quantity.abs(); 
+                }
+            }
+            TradeAction::CloseShort | TradeAction::LiquidateShort => {
+                // CloseShort - buy to close short position
+                if asset.quantity < 0.0 {
+                    let multiplier = if asset.asset_type == AssetType::Tfex { asset.leverage } else { 1.0 };
+                    
+                    let buy_cost = (tx_quantity * tx_price * multiplier) + tx.all_fees(); // Cost to close
+                    let short_value = tx_quantity * asset.avg_cost * multiplier;  // Price we sold at * qty
+                    
+                    // Reduce negative quantity (towards 0)
+                    let ratio = tx_quantity / asset.quantity.abs();
+                    
+                    let fee_portion = asset.total_fees * ratio;
+                    asset.total_fees -= fee_portion;
+                    
+                    // Reduce Total Cost (Invested) proportionally
+                    asset.total_cost -= asset.total_cost * ratio;
+
+                    // PnL = (Short Value) - (Buy Cost + Fees) - Historical Fees
+                    // = (AvgPrice * Qty) - (ExitPrice * Qty + CloseFees) - BuyFees
+                    // = (AvgPrice - ExitPrice) * Qty - TotalFees. Correct.
+                    let pnl = short_value - buy_cost - fee_portion;
+
+                    realized_pnl += pnl;
+                    
+                    // Accumulate breakdown by currency
+                    let currency = tx.currency.clone()
+                        .or_else(|| tx.market.as_ref().map(|m| m.default_currency().to_string()))
+                        .unwrap_or_else(|| "THB".to_string());
+                    *realized_pnl_breakdown.entry(currency.clone()).or_insert(0.0) += pnl;
+                    realized_trades.push(RealizedTrade {
+                        timestamp: tx.timestamp,
+                        currency,
+                        pnl,
+                        symbol: asset.symbol.clone(),
+                        asset_type: asset.asset_type.clone(),
+                        position_type: asset.position_type.clone(),
+                        transaction_id: tx.id.clone(),
+                        opened_by: entry.map(|e| e.opened_by.clone()),
+                        opened_at: entry.and_then(Entry::opened_at),
+                        quantity: tx_quantity,
+                        cost_basis: short_value + fee_portion,
+                        dust: false,
+                    });
+ 
+                    asset.realized_pnl += pnl;
+                    asset.quantity += tx_quantity;
+                }
+            }
+            TradeAction::Dividend => {
+                // Dividend - pure income, does not affect position size or cost basis
+                // We use 'price' field to store the Dividend Amount
+                // We typically set quantity to 1.0 or ignore it
+                let amount = tx.price; 
+                
+                asset.realized_dividend += amount;
+                
+                // Dividends are technically realized gains, but we track them separate from Capital Gains PnL
+                // If we want total return, we sum them up in UI
+                total_dividend += amount;
+            }
+        }
+
+        // A reduction that leaves less than the dust threshold closes the position
+        let reduces = matches!(
+            tx.action,
+            TradeAction::Sell | TradeAction::CloseLong | TradeAction::LiquidateLong | TradeAction::Withdraw
+                | TradeAction::CloseShort | TradeAction::LiquidateShort
+        );
+        if reduces && asset.quantity != 0.0 && asset.quantity.abs() < dust.threshold(&asset.asset_type) {
+            let pnl = -asset.total_cost;
+            let currency = asset.currency.clone();
+            tracing::info!("🧹 Closing {} {} dust ({}), booking {} {} realized",
+                asset.symbol, asset.position_type, asset.quantity, pnl, currency);
+            dust_cleaned.push(DustCleanup {
+                symbol: asset.symbol.clone(),
+                asset_type: asset.asset_type.clone(),
+                position_type: asset.position_type.clone(),
+                quantity: asset.quantity,
+                currency: currency.clone(),
+                realized_pnl: pnl,
+                closed_at: tx.timestamp,
+            });
+            realized_pnl += pnl;
+            *realized_pnl_breakdown.entry(currency.clone()).or_insert(0.0) += pnl;
+            realized_trades.push(RealizedTrade {
+                timestamp: tx.timestamp,
+                currency,
+                pnl,
+                symbol: asset.symbol.clone(),
+                asset_type: asset.asset_type.clone(),
+                position_type: asset.position_type.clone(),
+                transaction_id: tx.id.clone(),
+                opened_by: entry.map(|e| e.opened_by.clone()),
+                opened_at: entry.and_then(Entry::opened_at),
+                quantity: asset.quantity.abs(),
+                cost_basis: asset.total_cost,
+                dust: true,
+            });
+            asset.realized_pnl += pnl;
+            asset.quantity = 0.0;
+            asset.total_cost = 0.0;
+            asset.total_fees = 0.0;
+        }
+        if asset.quantity == 0.0 {
+            entries.remove(&key);
+        }
+    }
+
+    LotReplay {
+        holdings,
+        realized_pnl,
+        total_dividend,
+        realized_pnl_breakdown,
+        realized_trades,
+        dust_cleaned,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(id: &str, day: u32, action: &str, quantity: f64, price: f64, fees: f64) -> Transaction {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "asset_type": "crypto",
+            "symbol": "BTC",
+            "action": action,
+            "quantity": quantity,
+            "price": price,
+            "fees": fees,
+            "currency": "USD",
+            "timestamp": format!("2024-01-{:02}T00:00:00Z", day),
+        }))
+        .expect("valid transaction")
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn sell_leaving_dust_closes_the_position() {
+        let dust = DustPolicy::parse("crypto=0.001");
+        let replay = replay(&[
+            tx("buy", 1, "buy", 1.0, 100.0, 1.0),
+            tx("sell", 2, "sell", 0.9995, 200.0, 0.0),
+        ], &dust);
+
+        let btc = &replay.holdings["crypto::BTC:spot"];
+        assert_eq!(btc.quantity, 0.0);
+        assert_eq!(btc.total_cost, 0.0);
+
+        // The 0.0005 left over carried 0.0505 of the cost (including its share of the buy fee)
+        assert_eq!(replay.dust_cleaned.len(), 1);
+        let cleanup = &replay.dust_cleaned[0];
+        assert_close(cleanup.quantity, 0.0005);
+        assert_close(cleanup.realized_pnl, -0.0505);
+        assert_eq!(cleanup.currency, "USD");
+
+        let trade = replay.realized_trades.last().unwrap();
+        assert!(trade.dust);
+        assert_eq!(trade.transaction_id, "sell");
+        assert_eq!(trade.opened_by.as_deref(), Some("buy"));
+        // 199.9 of proceeds against the full 101 paid
+        assert_close(replay.realized_pnl, 98.9);
+        assert_close(replay.realized_pnl_breakdown["USD"], 98.9);
+    }
+
+    #[test]
+    fn remainder_above_the_threshold_stays_open() {
+        let dust = DustPolicy::parse("crypto=0.001");
+        let replay = replay(&[
+            tx("buy", 1, "buy", 1.0, 100.0, 0.0),
+            tx("sell", 2, "sell", 0.5, 200.0, 0.0),
+        ], &dust);
+
+        assert_close(replay.holdings["crypto::BTC:spot"].quantity, 0.5);
+        assert!(replay.dust_cleaned.is_empty());
+        assert_close(replay.realized_pnl, 50.0);
+    }
+
+    #[test]
+    fn short_close_leaving_dust_closes_the_position() {
+        let dust = DustPolicy::parse("crypto=0.001");
+        let replay = replay(&[
+            tx("short", 1, "short", 1.0, 100.0, 0.0),
+            tx("cover", 2, "close_short", 0.9995, 50.0, 0.0),
+        ], &dust);
+
+        let btc = &replay.holdings["crypto::BTC:short"];
+        assert_eq!(btc.quantity, 0.0);
+        assert_eq!(replay.dust_cleaned.len(), 1);
+        let cleanup = &replay.dust_cleaned[0];
+        assert_eq!(cleanup.position_type, "short");
+        assert_close(cleanup.quantity, -0.0005);
+        assert_close(cleanup.realized_pnl, -0.05);
+        assert_close(replay.realized_pnl, 49.975 - 0.05);
+    }
+
+    #[test]
+    fn dust_thresholds_skip_invalid_entries() {
+        let dust = DustPolicy::parse("crypto=0.001, gold=-1, bogus=2, stock, foreign_stock=abc,, gold=0.0001");
+
+        assert_eq!(dust.threshold(&AssetType::Crypto), 0.001);
+        assert_eq!(dust.threshold(&AssetType::Gold), 0.0001);
+        assert_eq!(dust.threshold(&AssetType::Stock), 0.0);
+        assert_eq!(dust.threshold(&AssetType::ForeignStock), 0.0);
+        assert_eq!(DustPolicy::parse("").threshold(&AssetType::Crypto), 0.0);
+    }
+}
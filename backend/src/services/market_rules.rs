@@ -0,0 +1,167 @@
+use crate::models::{AssetType, CreateTransactionRequest, Market, TradeAction};
+
+/// Trading constraints of a market: lot size, price ticks and minimum order value
+#[derive(Debug, Clone, Copy)]
+pub struct MarketRules {
+    /// Regular-board lot; smaller multiples of `quantity_step` are odd lots
+    pub board_lot: f64,
+    /// Smallest quantity increment the venue accepts
+    pub quantity_step: f64,
+    pub tick: TickRule,
+    /// Minimum order value and the quote currency it is expressed in
+    pub min_notional: Option<(f64, &'static str)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TickRule {
+    None,
+    /// SET/mai price-dependent tick ladder
+    SetLadder,
+    /// US equities: one cent, 0.0001 below $1
+    UsEquity,
+}
+
+impl TickRule {
+    pub fn tick_for(&self, price: f64) -> Option<f64> {
+        match self {
+            TickRule::None => None,
+            TickRule::SetLadder => Some(match price {
+                p if p < 2.0 => 0.01,
+                p if p < 5.0 => 0.02,
+                p if p < 10.0 => 0.05,
+                p if p < 25.0 => 0.10,
+                p if p < 100.0 => 0.25,
+                p if p < 200.0 => 0.50,
+                p if p < 400.0 => 1.00,
+                _ => 2.00,
+            }),
+            TickRule::UsEquity => Some(if price < 1.0 { 0.0001 } else { 0.01 }),
+        }
+    }
+}
+
+/// Rules for an asset on a market. Unknown combinations get permissive defaults.
+pub fn rules_for(asset_type: &AssetType, market: Option<&Market>) -> MarketRules {
+    let permissive = MarketRules { board_lot: 0.0, quantity_step: 0.0, tick: TickRule::None, min_notional: None };
+    match (asset_type, market) {
+        (AssetType::Stock, Some(Market::Set) | Some(Market::Mai) | None) => MarketRules {
+            board_lot: 100.0,
+            quantity_step: 1.0,
+            tick: TickRule::SetLadder,
+            min_notional: None,
+        },
+        (AssetType::Stock, _) => MarketRules { board_lot: 1.0, quantity_step: 1.0, ..permissive },
+        (AssetType::Tfex, _) => MarketRules { board_lot: 1.0, quantity_step: 1.0, ..permissive },
+        (AssetType::ForeignStock, Some(Market::Nyse) | Some(Market::Nasdaq) | Some(Market::Amex)) => MarketRules {
+            board_lot: 1.0,
+            // Brokers allow fractional shares, so only the price is constrained
+            quantity_step: 0.0,
+            tick: TickRule::UsEquity,
+            min_notional: None,
+        },
+        (AssetType::ForeignStock, _) => MarketRules { board_lot: 1.0, ..permissive },
+        (AssetType::Crypto, Some(Market::Binance)) => MarketRules {
+            board_lot: 0.0,
+            quantity_step: 0.00000001,
+            tick: TickRule::None,
+            min_notional: Some((5.0, "USDT")),
+        },
+        (AssetType::Crypto, Some(Market::Bitkub)) => MarketRules {
+            board_lot: 0.0,
+            quantity_step: 0.00000001,
+            tick: TickRule::None,
+            min_notional: Some((10.0, "THB")),
+        },
+        (AssetType::Crypto, _) => MarketRules { quantity_step: 0.00000001, ..permissive },
+        _ => permissive,
+    }
+}
+
+/// Smallest step a rebalancing trade should be sized in
+pub fn trade_lot(asset_type: &AssetType, market: Option<&Market>, fractional_shares: bool) -> f64 {
+    let rules = rules_for(asset_type, market);
+    match asset_type {
+        AssetType::ForeignStock if fractional_shares => 0.0001,
+        AssetType::Gold | AssetType::Commodity => 0.0001,
+        AssetType::Cash => 0.01,
+        _ if rules.board_lot > 0.0 => rules.board_lot,
+        _ => rules.quantity_step,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleViolation {
+    pub message: String,
+    /// Odd lots are legal (odd-lot board) and are never rejected
+    pub blocking: bool,
+}
+
+/// `value` is a multiple of `step`, allowing for float noise
+fn is_multiple(value: f64, step: f64) -> bool {
+    let ratio = value / step;
+    (ratio - ratio.round()).abs() < 1e-6
+}
+
+fn snap(value: f64, step: f64) -> f64 {
+    let snapped = (value / step).round() * step;
+    // Trim representation noise like 35.250000000000004
+    (snapped * 1e8).round() / 1e8
+}
+
+/// Check a trade against its market's rules. Values within float noise of a valid
+/// step are rounded in place; anything else is reported.
+pub fn check_transaction(req: &mut CreateTransactionRequest) -> Vec<RuleViolation> {
+    let is_trade = !matches!(req.action, TradeAction::Dividend | TradeAction::Deposit | TradeAction::Withdraw);
+    if !is_trade || req.quantity <= 0.0 || req.price <= 0.0 {
+        return Vec::new();
+    }
+
+    let rules = rules_for(&req.asset_type, req.market.as_ref());
+    let market = req.market.as_ref().map(|m| m.to_string()).unwrap_or_else(|| req.asset_type.to_string());
+    let mut violations = Vec::new();
+
+    let mut valid_quantity = true;
+    if rules.quantity_step > 0.0 {
+        if is_multiple(req.quantity, rules.quantity_step) {
+            req.quantity = snap(req.quantity, rules.quantity_step);
+        } else {
+            valid_quantity = false;
+            violations.push(RuleViolation {
+                message: format!("Quantity {} is not a multiple of {} on {}", req.quantity, rules.quantity_step, market),
+                blocking: true,
+            });
+        }
+    }
+    if valid_quantity && rules.board_lot > 1.0 && !is_multiple(req.quantity, rules.board_lot) {
+        violations.push(RuleViolation {
+            message: format!("Quantity {} is an odd lot (board lot is {} on {})", req.quantity, rules.board_lot, market),
+            blocking: false,
+        });
+    }
+
+    if let Some(tick) = rules.tick.tick_for(req.price) {
+        if is_multiple(req.price, tick) {
+            req.price = snap(req.price, tick);
+        } else {
+            violations.push(RuleViolation {
+                message: format!("Price {} is not on the {} tick for this price range on {}", req.price, tick, market),
+                blocking: true,
+            });
+        }
+    }
+
+    if let Some((min, quote)) = rules.min_notional {
+        let currency = req.currency.as_deref().unwrap_or(quote).to_uppercase();
+        // USD-quoted pairs are treated as USDT for the minimum
+        let comparable = currency == quote || (quote == "USDT" && currency == "USD");
+        let notional = req.quantity * req.price;
+        if comparable && notional < min {
+            violations.push(RuleViolation {
+                message: format!("Order value {:.2} {} is below the {} {} minimum on {}", notional, currency, min, quote, market),
+                blocking: true,
+            });
+        }
+    }
+
+    violations
+}
@@ -1,4 +1,6 @@
 pub mod price_service;
+pub mod price_history;
+pub mod providers;
 pub mod pocketbase;
 pub mod exchange_rate;
 pub mod auth;
@@ -7,6 +9,43 @@ pub mod symbols;
 pub mod rate_limiter;
 pub mod notification;
 pub mod alert;
+pub mod provider_cache;
+pub mod symbol_heat;
+pub mod equity_vesting;
+pub mod crypto_import;
+pub mod broker_import;
+pub mod import_reconcile;
+pub mod movers;
+pub mod insights;
+pub mod rebalance;
+pub mod market_rules;
+pub mod valuation;
+pub mod snapshot_cache;
+pub mod price_refresher;
+pub mod order_watch;
+pub mod dividends;
+pub mod orphans;
+pub mod lot_engine;
+pub mod asset_chart;
+pub mod exports;
+pub mod export_format;
+pub mod object_storage;
+pub mod http_client;
+pub mod ticker_feeds;
+pub mod credentials;
+pub mod balance_sync;
+pub mod exchange_sync;
+pub mod trade_analytics;
+pub mod benchmarks;
+pub mod public_status;
+pub mod allocation;
+pub mod diagnostics;
+pub mod cache_revalidator;
+pub mod smtp;
+pub mod notification_channels;
+pub mod realtime_bridge;
+pub mod digest;
+pub mod client_modes;
 
 pub use price_service::PriceService;
 pub use pocketbase::PocketBaseClient;
@@ -17,4 +56,16 @@ pub use symbols::SymbolsService;
 pub use rate_limiter::RateLimiter;
 pub use notification::NotificationService;
 pub use alert::AlertService;
+pub use symbol_heat::SymbolHeat;
+pub use snapshot_cache::SnapshotCache;
+pub use price_refresher::PriceRefresher;
+pub use order_watch::OrderWatcher;
+pub use exports::ExportService;
+pub use credentials::CredentialStore;
+pub use balance_sync::BalanceSyncService;
+pub use exchange_sync::ExchangeSyncService;
+pub use public_status::PublicStatusService;
+pub use cache_revalidator::CacheRevalidator;
+pub use realtime_bridge::RealtimeBridge;
+pub use client_modes::ClientModes;
 
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::{PocketBaseClient, PriceService};
+
+/// Look-back windows (days) reported as movers
+pub const MOVER_WINDOWS: [i64; 3] = [1, 7, 30];
+
+/// A held position to rank; `current_price` is the end price of every window
+#[derive(Debug, Clone)]
+pub struct MoverHolding {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+    pub quantity: f64,
+    pub current_price: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Mover {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    pub start_price: f64,
+    pub end_price: f64,
+    pub change_percent: f64,
+    /// Position value change over the window, in the asset's currency
+    pub value_change: f64,
+    /// "snapshot" or "price_history"
+    pub source: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MoversWindow {
+    pub days: i64,
+    pub gainers: Vec<Mover>,
+    pub losers: Vec<Mover>,
+    /// Held symbols with no start price for this window
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unpriced: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MoversReport {
+    pub windows: Vec<MoversWindow>,
+    pub generated_at: chrono::DateTime<Utc>,
+}
+
+/// Only the fields of a portfolio_snapshots record needed for start prices
+#[derive(Debug, Deserialize)]
+struct SnapshotPrices {
+    #[serde(default)]
+    date: String,
+    #[serde(default)]
+    assets: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SnapshotAsset {
+    pub symbol: String,
+    pub asset_type: String,
+    #[serde(default)]
+    pub market: Option<String>,
+    #[serde(default)]
+    pub quantity: f64,
+    #[serde(default)]
+    pub current_price: f64,
+}
+
+impl SnapshotPrices {
+    fn day(&self) -> &str {
+        self.date.get(..10).unwrap_or(&self.date)
+    }
+
+    fn assets(&self) -> Vec<SnapshotAsset> {
+        self.assets.clone()
+            .and_then(|a| serde_json::from_value(a).ok())
+            .unwrap_or_default()
+    }
+}
+
+async fn recent_snapshots(db: &PocketBaseClient, user_id: &str, days: i64) -> Result<Vec<SnapshotPrices>, AppError> {
+    let from = (Utc::now() - Duration::days(days)).format("%Y-%m-%d");
+    db.list_records(
+        "portfolio_snapshots",
+        Some(format!("user_id='{}' && date >= '{}'", user_id, from)),
+        "date",
+    ).await
+}
+
+/// Holdings as of the user's latest snapshot, for background jobs that have no live portfolio
+pub async fn latest_snapshot_holdings(db: &PocketBaseClient, user_id: &str) -> Result<Vec<SnapshotAsset>, AppError> {
+    let snapshots = recent_snapshots(db, user_id, 7).await?;
+    Ok(snapshots.last().map(|s| s.assets()).unwrap_or_default())
+}
+
+/// Best and worst held assets over each window in MOVER_WINDOWS. Start prices come from the
+/// user's daily snapshots, falling back to price history when a snapshot is missing.
+pub async fn compute_movers(
+    db: &PocketBaseClient,
+    price_service: &PriceService,
+    user_id: &str,
+    holdings: &[MoverHolding],
+    top_n: usize,
+) -> Result<MoversReport, AppError> {
+    let longest = MOVER_WINDOWS.iter().copied().max().unwrap_or(30);
+    let snapshots = recent_snapshots(db, user_id, longest + 1).await.unwrap_or_else(|e| {
+        tracing::warn!("⚠️ Could not load snapshots for movers of {}: {}", user_id, e);
+        Vec::new()
+    });
+    let today = Utc::now().date_naive();
+    let mut histories: HashMap<String, Vec<crate::services::price_service::HistoryEntry>> = HashMap::new();
+
+    let mut windows = Vec::new();
+    for days in MOVER_WINDOWS {
+        let target = (today - Duration::days(days)).format("%Y-%m-%d").to_string();
+        // Latest snapshot taken on or before the start of the window
+        let start_assets = snapshots.iter()
+            .rev()
+            .find(|s| s.day() <= target.as_str())
+            .map(|s| s.assets())
+            .unwrap_or_default();
+
+        let mut movers = Vec::new();
+        let mut unpriced = Vec::new();
+        for holding in holdings.iter().filter(|h| h.current_price > 0.0) {
+            let asset_type = holding.asset_type.to_string();
+            let from_snapshot = start_assets.iter()
+                .find(|a| a.symbol.eq_ignore_ascii_case(&holding.symbol) && a.asset_type == asset_type)
+                .map(|a| a.current_price)
+                .filter(|p| *p > 0.0);
+
+            let (start_price, source) = match from_snapshot {
+                Some(price) => (price, "snapshot"),
+                None => {
+                    let key = format!("{}:{}", asset_type, holding.symbol);
+                    if !histories.contains_key(&key) {
+                        let history = price_service
+                            .get_price_history(&holding.symbol, &holding.asset_type, holding.market.as_ref(), (longest + 2) as u32)
+                            .await
+                            .unwrap_or_default();
+                        histories.insert(key.clone(), history);
+                    }
+                    let from_history = histories.get(&key)
+                        .and_then(|h| h.iter().filter(|e| e.date.as_str() <= target.as_str()).max_by(|a, b| a.date.cmp(&b.date)))
+                        .map(|e| e.price)
+                        .filter(|p| *p > 0.0);
+                    match from_history {
+                        Some(price) => (price, "price_history"),
+                        None => {
+                            unpriced.push(holding.symbol.clone());
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            movers.push(Mover {
+                symbol: holding.symbol.clone(),
+                asset_type: holding.asset_type.clone(),
+                market: holding.market.clone(),
+                start_price,
+                end_price: holding.current_price,
+                change_percent: (holding.current_price - start_price) / start_price * 100.0,
+                value_change: holding.quantity * (holding.current_price - start_price),
+                source,
+            });
+        }
+
+        movers.sort_by(|a, b| b.change_percent.partial_cmp(&a.change_percent).unwrap_or(std::cmp::Ordering::Equal));
+        let gainers: Vec<Mover> = movers.iter().filter(|m| m.change_percent > 0.0).take(top_n).cloned().collect();
+        let losers: Vec<Mover> = movers.iter().rev().filter(|m| m.change_percent < 0.0).take(top_n).cloned().collect();
+        windows.push(MoversWindow { days, gainers, losers, unpriced });
+    }
+
+    Ok(MoversReport { windows, generated_at: Utc::now() })
+}
+
+/// Short plain-text summary of a movers report, used by the weekly report notification
+pub fn format_movers_summary(report: &MoversReport) -> String {
+    let mut lines = Vec::new();
+    for window in &report.windows {
+        let fmt = |m: &Mover| format!("{} {:+.2}%", m.symbol, m.change_percent);
+        let gainers: Vec<String> = window.gainers.iter().map(fmt).collect();
+        let losers: Vec<String> = window.losers.iter().map(fmt).collect();
+        if gainers.is_empty() && losers.is_empty() {
+            continue;
+        }
+        lines.push(format!(
+            "{}d: ▲ {} | ▼ {}",
+            window.days,
+            if gainers.is_empty() { "-".to_string() } else { gainers.join(", ") },
+            if losers.is_empty() { "-".to_string() } else { losers.join(", ") },
+        ));
+    }
+    lines.join("\n")
+}
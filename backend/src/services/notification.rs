@@ -1,6 +1,7 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 
 use crate::config::Config;
 use crate::error::AppError;
@@ -8,6 +9,8 @@ use crate::models::{
     AlertRule, AlertHistory, Notification, NotificationType,
     NotificationChannel, PushSubscription,
 };
+use crate::plugins::MessageTransformer;
+use crate::services::notification_channels::ChannelDelivery;
 use crate::services::PocketBaseClient;
 
 /// Notification service for sending alerts through multiple channels
@@ -16,32 +19,70 @@ pub struct NotificationService {
     pb_client: PocketBaseClient,
     config: Config,
     // In-memory cache of push subscriptions
+    #[allow(dead_code)]
     push_subscriptions: Arc<RwLock<Vec<PushSubscription>>>,
+    // When each (user, title, body) was last delivered, for dedup
+    recent_deliveries: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+    // Email, Telegram and LINE delivery
+    channels: Arc<ChannelDelivery>,
 }
 
 impl NotificationService {
     pub fn new(config: Config, pb_client: PocketBaseClient) -> Self {
         Self {
+            channels: Arc::new(ChannelDelivery::new(&config, pb_client.clone())),
             pb_client,
             config,
             push_subscriptions: Arc::new(RwLock::new(Vec::new())),
+            recent_deliveries: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
-    /// Send notification through specified channels
+    /// Claim delivery of a notification. Returns false if the same notification was already
+    /// delivered to the user within the dedup window.
+    async fn claim_delivery(&self, user_id: &str, title: &str, body: &str) -> bool {
+        let window = Duration::seconds(self.config.notification_dedup_window_seconds as i64);
+        let now = Utc::now();
+        let key = format!("{}\u{1f}{}\u{1f}{}", user_id, title, body);
+
+        let mut recent = self.recent_deliveries.write().await;
+        recent.retain(|_, sent_at| now - *sent_at < window);
+        if recent.contains_key(&key) {
+            return false;
+        }
+        recent.insert(key, now);
+        true
+    }
+
+    /// Plugin rewrites for Email, Telegram and LINE messages; set before the service is cloned
+    pub fn set_message_transformers(&mut self, transformers: Vec<Arc<dyn MessageTransformer>>) {
+        Arc::make_mut(&mut self.channels).set_transformers(transformers);
+    }
+
+    /// Per-user targets for the external channels
+    pub fn channels(&self) -> &ChannelDelivery {
+        &self.channels
+    }
+
+    /// Send notification through specified channels.
+    /// Returns None when an identical notification was sent recently and this one was dropped.
     pub async fn send(
         &self,
         user_id: &str,
         alert: &AlertRule,
         current_value: f64,
-    ) -> Result<AlertHistory, AppError> {
+    ) -> Result<Option<AlertHistory>, AppError> {
         let message = self.format_alert_message(alert, current_value);
+        if !self.claim_delivery(user_id, &alert.name, &message).await {
+            tracing::info!("🔕 Suppressed duplicate notification for alert '{}'", alert.name);
+            return Ok(None);
+        }
         let mut channels_sent = Vec::new();
 
         for channel in &alert.channels {
             match channel {
                 NotificationChannel::InApp => {
-                    if let Err(e) = self.send_in_app(user_id, &alert.name, &message).await {
+                    if let Err(e) = self.send_in_app(user_id, &alert.name, &message, NotificationType::Alert).await {
                         tracing::error!("Failed to send in-app notification: {}", e);
                     } else {
                         channels_sent.push(NotificationChannel::InApp);
@@ -54,17 +95,16 @@ impl NotificationService {
                         channels_sent.push(NotificationChannel::WebPush);
                     }
                 }
-                NotificationChannel::Email => {
-                    // Email will be implemented later
-                    tracing::info!("Email notifications not yet implemented");
-                }
+                // Delivered together below
+                NotificationChannel::Email | NotificationChannel::Telegram | NotificationChannel::Line => {}
             }
         }
+        channels_sent.extend(self.channels.deliver(user_id, &alert.channels, &alert.name, &message).await);
 
         // Record alert history
         let history = self.record_alert_history(alert, &message, channels_sent.clone(), current_value).await?;
         
-        Ok(history)
+        Ok(Some(history))
     }
 
     /// Format alert message based on alert type
@@ -84,6 +124,14 @@ impl NotificationService {
                     symbol, direction, current_value, alert.threshold
                 )
             }
+            AlertType::PriceChangePercent => {
+                let symbol = alert.symbol.as_deref().unwrap_or("Unknown");
+                let direction = if current_value >= 0.0 { "เพิ่มขึ้น" } else { "ลดลง" };
+                format!(
+                    "{} ราคา{} {:.2}% จาก {:.2} (เกณฑ์: {:.2}%)",
+                    symbol, direction, current_value.abs(), alert.reference_price.unwrap_or_default(), alert.threshold.abs()
+                )
+            }
             AlertType::PnlThresholdPercent => {
                 let direction = if current_value >= 0.0 { "กำไร" } else { "ขาดทุน" };
                 format!(
@@ -111,19 +159,59 @@ impl NotificationService {
         }
     }
 
+    /// Send a scheduled report (in-app + web push + the user's external channels), not tied to
+    /// an alert rule
+    pub async fn send_report(&self, user_id: &str, title: &str, body: &str) -> Result<(), AppError> {
+        if !self.claim_delivery(user_id, title, body).await {
+            tracing::info!("🔕 Suppressed duplicate report '{}' for user {}", title, user_id);
+            return Ok(());
+        }
+        self.send_in_app(user_id, title, body, NotificationType::Info).await?;
+        if let Err(e) = self.send_web_push(user_id, title, body).await {
+            tracing::error!("Failed to send web push report: {}", e);
+        }
+        self.channels.deliver(user_id, &NotificationChannel::EXTERNAL, title, body).await;
+        Ok(())
+    }
+
+    /// Send an account security warning (in-app + web push + external channels), e.g. a login
+    /// from a new device. Never deduplicated: every occurrence should reach the user.
+    pub async fn send_security_alert(&self, user_id: &str, title: &str, body: &str) -> Result<(), AppError> {
+        self.send_in_app(user_id, title, body, NotificationType::Warning).await?;
+        if let Err(e) = self.send_web_push(user_id, title, body).await {
+            tracing::error!("Failed to send web push security alert: {}", e);
+        }
+        self.channels.deliver(user_id, &NotificationChannel::EXTERNAL, title, body).await;
+        Ok(())
+    }
+
+    /// Tell an admin a background job failed (in-app warning + external channels). Repeats of
+    /// the same failure inside the dedup window are dropped.
+    pub async fn send_job_failure(&self, user_id: &str, job_name: &str, error: &str) -> Result<(), AppError> {
+        let title = format!("Job failed: {}", job_name);
+        let body = crate::services::diagnostics::sanitize(error).chars().take(500).collect::<String>();
+        if !self.claim_delivery(user_id, &title, &body).await {
+            return Ok(());
+        }
+        self.send_in_app(user_id, &title, &body, NotificationType::Warning).await?;
+        self.channels.deliver(user_id, &NotificationChannel::EXTERNAL, &title, &body).await;
+        Ok(())
+    }
+
     /// Send in-app notification (stored in PocketBase)
     async fn send_in_app(
         &self,
         user_id: &str,
         title: &str,
         body: &str,
+        notification_type: NotificationType,
     ) -> Result<Notification, AppError> {
         let notification = Notification {
             id: uuid::Uuid::new_v4().to_string(),
             user_id: user_id.to_string(),
             title: title.to_string(),
             body: body.to_string(),
-            notification_type: NotificationType::Alert,
+            notification_type,
             is_read: false,
             metadata: None,
             created: Utc::now(),
@@ -133,7 +221,7 @@ impl NotificationService {
         let token = self.pb_client.get_token().await;
         let url = format!("{}/api/collections/notifications/records", self.config.pocketbase_url);
         
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let response = client
             .post(&url)
             .header("Authorization", token)
@@ -224,7 +312,7 @@ impl NotificationService {
             self.config.pocketbase_url, user_id
         );
 
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let response = client
             .get(&url)
             .header("Authorization", token)
@@ -265,7 +353,7 @@ impl NotificationService {
         let token = self.pb_client.get_token().await;
         let url = format!("{}/api/collections/push_subscriptions/records", self.config.pocketbase_url);
 
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let response = client
             .post(&url)
             .header("Authorization", token)
@@ -309,7 +397,7 @@ impl NotificationService {
         let token = self.pb_client.get_token().await;
         let url = format!("{}/api/collections/alert_history/records", self.config.pocketbase_url);
 
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let channels_str: Vec<String> = channels_sent.iter().map(|c| c.to_string()).collect();
         
         let response = client
@@ -348,7 +436,7 @@ impl NotificationService {
             self.config.pocketbase_url, user_id
         );
 
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let response = client
             .get(&url)
             .header("Authorization", token)
@@ -394,7 +482,7 @@ impl NotificationService {
             self.config.pocketbase_url, notification_id
         );
 
-        let client = reqwest::Client::new();
+        let client = self.pb_client.http();
         let response = client
             .patch(&url)
             .header("Authorization", token)
@@ -0,0 +1,323 @@
+//! Delivery outside the app: SMTP email, a Telegram bot and LINE Notify. Each user registers
+//! where they want each channel in notification_targets; the LINE token is sealed with
+//! CREDENTIALS_ENCRYPTION_KEY like exchange API keys. Failed sends are retried with exponential
+//! backoff (NOTIFICATION_RETRY_ATTEMPTS), honouring a provider's Retry-After.
+
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::{NotificationChannel, NotificationTarget, UpdateNotificationTargetRequest, NOTIFICATION_TARGETS_COLLECTION};
+use crate::plugins::{MessageTransformer, OutgoingMessage};
+use crate::services::credentials::CredentialCipher;
+use crate::services::smtp::SmtpMailer;
+use crate::services::PocketBaseClient;
+
+/// Wait before the first retry; doubled for each one after
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// Longest wait between attempts, whatever a provider asks for
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A failed attempt, and how soon (if at all) it is worth trying again
+#[derive(Debug)]
+struct DeliveryError {
+    message: String,
+    transient: bool,
+    retry_after: Option<Duration>,
+}
+
+impl DeliveryError {
+    fn permanent(message: impl Into<String>) -> Self {
+        Self { message: message.into(), transient: false, retry_after: None }
+    }
+
+    fn transient(message: impl Into<String>) -> Self {
+        Self { message: message.into(), transient: true, retry_after: None }
+    }
+
+    /// 429 and 5xx are retried, other statuses are not
+    fn http(provider: &str, status: reqwest::StatusCode, body: &str, retry_after: Option<Duration>) -> Self {
+        Self {
+            message: format!("{} answered {}: {}", provider, status, body.chars().take(200).collect::<String>()),
+            transient: status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+            retry_after,
+        }
+    }
+
+    /// Connection errors are retried; the URL is left out since Telegram's holds the bot token
+    fn request(provider: &str, error: reqwest::Error) -> Self {
+        Self::transient(format!("{} request failed: {}", provider, error.without_url()))
+    }
+}
+
+#[derive(Clone)]
+pub struct ChannelDelivery {
+    db: PocketBaseClient,
+    http: reqwest::Client,
+    mailer: Option<SmtpMailer>,
+    cipher: Option<CredentialCipher>,
+    telegram_bot_token: Option<String>,
+    telegram_api_url: String,
+    line_notify_api_url: String,
+    attempts: u32,
+    /// Plugin rewrites applied to every message before it is sent
+    transformers: Vec<Arc<dyn MessageTransformer>>,
+}
+
+impl ChannelDelivery {
+    pub fn new(config: &Config, db: PocketBaseClient) -> Self {
+        Self {
+            db,
+            http: crate::services::http_client::clients(config).external.clone(),
+            mailer: SmtpMailer::from_config(config),
+            cipher: CredentialCipher::from_config(config),
+            telegram_bot_token: config.telegram_bot_token.clone(),
+            telegram_api_url: config.telegram_api_url.trim_end_matches('/').to_string(),
+            line_notify_api_url: config.line_notify_api_url.clone(),
+            attempts: config.notification_retry_attempts.max(1),
+            transformers: Vec::new(),
+        }
+    }
+
+    pub fn set_transformers(&mut self, transformers: Vec<Arc<dyn MessageTransformer>>) {
+        self.transformers = transformers;
+    }
+
+    /// Whether the server is set up to send on a channel at all
+    pub fn is_available(&self, channel: NotificationChannel) -> bool {
+        match channel {
+            NotificationChannel::Email => self.mailer.is_some(),
+            NotificationChannel::Telegram => self.telegram_bot_token.is_some(),
+            NotificationChannel::Line => self.cipher.is_some(),
+            NotificationChannel::InApp | NotificationChannel::WebPush => false,
+        }
+    }
+
+    pub async fn targets(&self, user_id: &str) -> Result<Vec<NotificationTarget>, AppError> {
+        self.db.list_records(NOTIFICATION_TARGETS_COLLECTION, Some(format!("user_id='{}'", user_id)), "channel").await
+    }
+
+    async fn target(&self, user_id: &str, channel: NotificationChannel) -> Result<Option<NotificationTarget>, AppError> {
+        let filter = format!("user_id='{}' && channel='{}'", user_id, channel);
+        let targets: Vec<NotificationTarget> = self.db.list_records(NOTIFICATION_TARGETS_COLLECTION, Some(filter), "-created").await?;
+        Ok(targets.into_iter().next())
+    }
+
+    /// Register or change where a user receives a channel
+    pub async fn set_target(
+        &self,
+        user_id: &str,
+        channel: NotificationChannel,
+        req: UpdateNotificationTargetRequest,
+    ) -> Result<NotificationTarget, AppError> {
+        if !channel.is_external() {
+            return Err(AppError::BadRequest(format!("{} is not configured per user", channel)));
+        }
+        if !self.is_available(channel) {
+            return Err(AppError::BadRequest(format!("{} notifications are not set up on this server", channel)));
+        }
+        let existing = self.target(user_id, channel).await?;
+
+        let mut body = serde_json::json!({
+            "user_id": user_id,
+            "channel": channel,
+            "enabled": req.enabled,
+        });
+        let target = req.target.as_deref().map(str::trim).unwrap_or_default();
+        match channel {
+            NotificationChannel::Email => {
+                let valid = target.split_once('@').is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'));
+                if !valid || target.contains(['\r', '\n', '<', '>']) {
+                    return Err(AppError::BadRequest("target must be an email address".into()));
+                }
+                body["target"] = target.into();
+            }
+            NotificationChannel::Telegram => {
+                let valid = target.strip_prefix('-').unwrap_or(target).parse::<i64>().is_ok()
+                    || (target.starts_with('@') && target.len() > 1);
+                if !valid {
+                    return Err(AppError::BadRequest("target must be a Telegram chat ID (or @channel)".into()));
+                }
+                body["target"] = target.into();
+            }
+            NotificationChannel::Line => match req.token.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+                Some(token) => {
+                    let cipher = self.cipher.as_ref()
+                        .ok_or_else(|| AppError::Config("LINE tokens need CREDENTIALS_ENCRYPTION_KEY to be set".into()))?;
+                    body["secret"] = cipher.seal(token.as_bytes(), &seal_context(user_id))?.into();
+                    body["token_hint"] = token.chars().skip(token.chars().count().saturating_sub(4)).collect::<String>().into();
+                }
+                None if existing.is_some() => {}
+                None => return Err(AppError::BadRequest("token is required for LINE Notify".into())),
+            },
+            NotificationChannel::InApp | NotificationChannel::WebPush => unreachable!("checked above"),
+        }
+
+        match existing {
+            Some(existing) => self.db.update_record(NOTIFICATION_TARGETS_COLLECTION, &existing.id, &body).await,
+            None => self.db.create_record(NOTIFICATION_TARGETS_COLLECTION, &body).await,
+        }
+    }
+
+    pub async fn remove_target(&self, user_id: &str, channel: NotificationChannel) -> Result<(), AppError> {
+        let target = self.target(user_id, channel).await?
+            .ok_or_else(|| AppError::NotFound(format!("No {} target registered", channel)))?;
+        self.db.delete_record(NOTIFICATION_TARGETS_COLLECTION, &target.id).await
+    }
+
+    /// Send a test message to a registered target; the returned target carries the outcome
+    pub async fn send_test(&self, user_id: &str, channel: NotificationChannel, title: &str, body: &str) -> Result<NotificationTarget, AppError> {
+        let mut target = self.target(user_id, channel).await?
+            .ok_or_else(|| AppError::NotFound(format!("No {} target registered", channel)))?;
+        let outcome = self.deliver_to(&target, title, body).await;
+        self.record_outcome(&target, outcome.as_ref().err().map(String::as_str)).await;
+        match outcome {
+            Ok(()) => {
+                target.last_delivered_at = Some(Utc::now());
+                target.last_error = None;
+            }
+            Err(e) => target.last_error = Some(e),
+        }
+        Ok(target)
+    }
+
+    /// Deliver to the user's enabled targets among `channels`; returns those that got through
+    pub async fn deliver(&self, user_id: &str, channels: &[NotificationChannel], title: &str, body: &str) -> Vec<NotificationChannel> {
+        if !channels.iter().any(|c| c.is_external() && self.is_available(*c)) {
+            return Vec::new();
+        }
+        let targets = match self.targets(user_id).await {
+            Ok(targets) => targets,
+            Err(e) => {
+                tracing::warn!("⚠️ Could not load notification targets for user {}: {}", user_id, e);
+                return Vec::new();
+            }
+        };
+
+        let mut delivered = Vec::new();
+        for target in targets.iter().filter(|t| t.enabled && channels.contains(&t.channel) && self.is_available(t.channel)) {
+            let Some(message) = self.transform(target.channel, user_id, title, body) else {
+                tracing::info!("🔌 {} notification to user {} dropped by a plugin", target.channel, user_id);
+                continue;
+            };
+            let outcome = self.deliver_to(target, &message.title, &message.body).await;
+            if let Err(e) = &outcome {
+                tracing::error!("❌ {} notification to user {} failed: {}", target.channel, user_id, e);
+            } else {
+                delivered.push(target.channel);
+            }
+            self.record_outcome(target, outcome.as_ref().err().map(String::as_str)).await;
+        }
+        delivered
+    }
+
+    /// Run the message through the plugins' transformers in order; None once one drops it
+    fn transform(&self, channel: NotificationChannel, user_id: &str, title: &str, body: &str) -> Option<OutgoingMessage> {
+        let message = OutgoingMessage {
+            channel,
+            user_id: user_id.to_string(),
+            title: title.to_string(),
+            body: body.to_string(),
+        };
+        self.transformers.iter().try_fold(message, |message, transformer| transformer.transform(message))
+    }
+
+    /// One delivery, retried with exponential backoff while failures look transient
+    async fn deliver_to(&self, target: &NotificationTarget, title: &str, body: &str) -> Result<(), String> {
+        let mut backoff = INITIAL_BACKOFF;
+        for attempt in 1..=self.attempts {
+            let error = match self.send_once(target, title, body).await {
+                Ok(()) => return Ok(()),
+                Err(error) => error,
+            };
+            if !error.transient || attempt == self.attempts {
+                return Err(error.message);
+            }
+            let wait = error.retry_after.unwrap_or(backoff).min(MAX_BACKOFF);
+            tracing::warn!(
+                "⚠️ {} delivery attempt {}/{} failed ({}), retrying in {}s",
+                target.channel, attempt, self.attempts, error.message, wait.as_secs()
+            );
+            tokio::time::sleep(wait).await;
+            backoff *= 2;
+        }
+        Err("No delivery attempts made".to_string())
+    }
+
+    async fn send_once(&self, target: &NotificationTarget, title: &str, body: &str) -> Result<(), DeliveryError> {
+        match target.channel {
+            NotificationChannel::Email => {
+                let mailer = self.mailer.as_ref().ok_or_else(|| DeliveryError::permanent("SMTP is not configured"))?;
+                mailer.send(&target.target, title, body).await
+                    .map_err(|e| DeliveryError { message: e.message, transient: e.transient, retry_after: None })
+            }
+            NotificationChannel::Telegram => self.send_telegram(&target.target, &format!("{}\n\n{}", title, body)).await,
+            NotificationChannel::Line => {
+                let cipher = self.cipher.as_ref().ok_or_else(|| DeliveryError::permanent("CREDENTIALS_ENCRYPTION_KEY is not set"))?;
+                let token = cipher.open(&target.secret, &seal_context(&target.user_id))
+                    .ok()
+                    .and_then(|bytes| String::from_utf8(bytes).ok())
+                    .ok_or_else(|| DeliveryError::permanent("Stored LINE token could not be decrypted"))?;
+                self.send_line(&token, &format!("\n{}\n{}", title, body)).await
+            }
+            NotificationChannel::InApp | NotificationChannel::WebPush => {
+                Err(DeliveryError::permanent(format!("{} is not an external channel", target.channel)))
+            }
+        }
+    }
+
+    async fn send_telegram(&self, chat_id: &str, text: &str) -> Result<(), DeliveryError> {
+        let token = self.telegram_bot_token.as_deref().ok_or_else(|| DeliveryError::permanent("TELEGRAM_BOT_TOKEN is not set"))?;
+        let url = format!("{}/bot{}/sendMessage", self.telegram_api_url, token);
+        let response = self.http.post(&url)
+            .json(&serde_json::json!({ "chat_id": chat_id, "text": text, "disable_web_page_preview": true }))
+            .send()
+            .await
+            .map_err(|e| DeliveryError::request("Telegram", e))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        let retry_after = body.pointer("/parameters/retry_after").and_then(|v| v.as_u64()).map(Duration::from_secs);
+        let description = body.get("description").and_then(|v| v.as_str()).unwrap_or_default();
+        Err(DeliveryError::http("Telegram", status, description, retry_after))
+    }
+
+    async fn send_line(&self, token: &str, message: &str) -> Result<(), DeliveryError> {
+        let response = self.http.post(&self.line_notify_api_url)
+            .bearer_auth(token)
+            .form(&[("message", message)])
+            .send()
+            .await
+            .map_err(|e| DeliveryError::request("LINE Notify", e))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        // LINE sends the reset time of its hourly quota as epoch seconds
+        let retry_after = response.headers().get("X-RateLimit-Reset")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<i64>().ok())
+            .map(|reset| Duration::from_secs((reset - Utc::now().timestamp()).max(1) as u64));
+        let body = response.text().await.unwrap_or_default();
+        Err(DeliveryError::http("LINE Notify", status, &body, retry_after))
+    }
+
+    /// Remember when a target last worked, or why it didn't (best effort)
+    async fn record_outcome(&self, target: &NotificationTarget, error: Option<&str>) {
+        let body = match error {
+            None => serde_json::json!({ "last_delivered_at": Utc::now(), "last_error": "" }),
+            Some(error) => serde_json::json!({ "last_error": error.chars().take(500).collect::<String>() }),
+        };
+        if let Err(e) = self.db.update_record::<serde_json::Value>(NOTIFICATION_TARGETS_COLLECTION, &target.id, &body).await {
+            tracing::warn!("⚠️ Failed to update notification target {}: {}", target.id, e);
+        }
+    }
+}
+
+/// Associated data a LINE token is sealed with
+fn seal_context(user_id: &str) -> String {
+    format!("{}:line", user_id)
+}
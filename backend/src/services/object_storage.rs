@@ -0,0 +1,178 @@
+//! Minimal S3-compatible object storage client (AWS S3, MinIO, R2, ...) with SigV4 signing.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use crate::config::Config;
+use crate::error::AppError;
+
+/// Longest lifetime S3 accepts for a presigned URL
+const MAX_PRESIGN_SECONDS: u64 = 7 * 24 * 3600;
+
+const EMPTY_PAYLOAD_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+#[derive(Clone)]
+pub struct ObjectStorage {
+    http: reqwest::Client,
+    endpoint: Url,
+    region: String,
+    bucket: String,
+    access_key_id: String,
+    secret_access_key: String,
+    /// `endpoint/bucket/key` (MinIO) instead of `bucket.endpoint/key` (AWS)
+    path_style: bool,
+}
+
+impl ObjectStorage {
+    /// Client for the configured bucket, or None when S3 storage is not set up
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let endpoint = config.s3_endpoint.as_deref()?;
+        let bucket = config.s3_bucket.clone()?;
+        let access_key_id = config.s3_access_key_id.clone()?;
+        let secret_access_key = config.s3_secret_access_key.clone()?;
+        let endpoint = match Url::parse(endpoint) {
+            Ok(url) => url,
+            Err(e) => {
+                tracing::warn!("⚠️ Ignoring S3_ENDPOINT '{}': {}", endpoint, e);
+                return None;
+            }
+        };
+        tracing::info!("🪣 Object storage enabled: bucket {} at {}", bucket, endpoint);
+        Some(Self {
+            http: crate::services::http_client::clients(config).internal.clone(),
+            endpoint,
+            region: config.s3_region.clone(),
+            bucket,
+            access_key_id,
+            secret_access_key,
+            path_style: config.s3_path_style,
+        })
+    }
+
+    pub async fn put_object(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), AppError> {
+        let payload_hash = hex::encode(Sha256::digest(&bytes));
+        let (url, headers) = self.signed_request("PUT", key, &payload_hash, Utc::now());
+        let mut request = self.http.put(url).header("content-type", content_type).body(bytes);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await
+            .map_err(|e| AppError::ExternalApiError(format!("S3 upload of {} failed: {}", key, e)))?;
+        if !response.status().is_success() {
+            return Err(AppError::ExternalApiError(format!("S3 upload of {} failed: {}", key, response.status())));
+        }
+        Ok(())
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<(), AppError> {
+        let (url, headers) = self.signed_request("DELETE", key, EMPTY_PAYLOAD_SHA256, Utc::now());
+        let mut request = self.http.delete(url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await
+            .map_err(|e| AppError::ExternalApiError(format!("S3 delete of {} failed: {}", key, e)))?;
+        if !response.status().is_success() && response.status() != reqwest::StatusCode::NOT_FOUND {
+            return Err(AppError::ExternalApiError(format!("S3 delete of {} failed: {}", key, response.status())));
+        }
+        Ok(())
+    }
+
+    /// GET URL that works without credentials for `expires_seconds` (capped at 7 days).
+    /// `download_name` makes browsers save the object under that file name.
+    pub fn presigned_get_url(&self, key: &str, expires_seconds: u64, download_name: Option<&str>) -> String {
+        let now = Utc::now();
+        let (date, amz_date) = timestamps(now);
+        let mut query = vec![
+            ("X-Amz-Algorithm".to_string(), "AWS4-HMAC-SHA256".to_string()),
+            ("X-Amz-Credential".to_string(), format!("{}/{}", self.access_key_id, self.scope(&date))),
+            ("X-Amz-Date".to_string(), amz_date.clone()),
+            ("X-Amz-Expires".to_string(), expires_seconds.clamp(1, MAX_PRESIGN_SECONDS).to_string()),
+            ("X-Amz-SignedHeaders".to_string(), "host".to_string()),
+        ];
+        if let Some(name) = download_name {
+            query.push(("response-content-disposition".to_string(), format!("attachment; filename=\"{}\"", name)));
+        }
+        query.sort();
+        let canonical_query = canonical_query(&query);
+
+        let (host, path) = self.host_and_path(key);
+        let canonical_request = format!(
+            "GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD",
+            path, canonical_query, host
+        );
+        let signature = self.signature(&canonical_request, &date, &amz_date);
+        format!("{}://{}{}?{}&X-Amz-Signature={}", self.endpoint.scheme(), host, path, canonical_query, signature)
+    }
+
+    /// URL and auth headers for a header-signed request
+    fn signed_request(&self, method: &str, key: &str, payload_hash: &str, now: DateTime<Utc>) -> (String, Vec<(&'static str, String)>) {
+        let (date, amz_date) = timestamps(now);
+        let (host, path) = self.host_and_path(key);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let signature = self.signature(&canonical_request, &date, &amz_date);
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, self.scope(&date), signed_headers, signature
+        );
+        let url = format!("{}://{}{}", self.endpoint.scheme(), host, path);
+        (url, vec![
+            ("x-amz-content-sha256", payload_hash.to_string()),
+            ("x-amz-date", amz_date),
+            ("authorization", authorization),
+        ])
+    }
+
+    fn host_and_path(&self, key: &str) -> (String, String) {
+        let mut host = self.endpoint.host_str().unwrap_or_default().to_string();
+        if let Some(port) = self.endpoint.port() {
+            host = format!("{}:{}", host, port);
+        }
+        let key_path: Vec<String> = key.split('/').map(|s| urlencoding::encode(s).into_owned()).collect();
+        if self.path_style {
+            (host, format!("/{}/{}", self.bucket, key_path.join("/")))
+        } else {
+            (format!("{}.{}", self.bucket, host), format!("/{}", key_path.join("/")))
+        }
+    }
+
+    fn scope(&self, date: &str) -> String {
+        format!("{}/{}/s3/aws4_request", date, self.region)
+    }
+
+    fn signature(&self, canonical_request: &str, date: &str, amz_date: &str) -> String {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            self.scope(date),
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date.as_bytes());
+        let key = hmac_sha256(&key, self.region.as_bytes());
+        let key = hmac_sha256(&key, b"s3");
+        let key = hmac_sha256(&key, b"aws4_request");
+        hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()))
+    }
+}
+
+fn timestamps(now: DateTime<Utc>) -> (String, String) {
+    (now.format("%Y%m%d").to_string(), now.format("%Y%m%dT%H%M%SZ").to_string())
+}
+
+fn canonical_query(params: &[(String, String)]) -> String {
+    params.iter()
+        .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use chrono::Utc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::RwLock;
+use crate::error::AppError;
+use crate::models::{Order, OrderStatus, ORDERS_COLLECTION};
+use crate::services::price_refresher::HeldSymbol;
+use crate::services::price_service::PriceUpdate;
+use crate::services::{NotificationService, PocketBaseClient, PriceRefresher, PriceService};
+
+/// Open orders are re-read this often, picking up edits made outside the API
+const RELOAD_INTERVAL_SECONDS: u64 = 300;
+
+/// Watches incoming prices and flags open orders the market has reached
+#[derive(Clone)]
+pub struct OrderWatcher {
+    db: PocketBaseClient,
+    notification_service: NotificationService,
+    price_refresher: PriceRefresher,
+    open: Arc<RwLock<Vec<Order>>>,
+    /// Ids of orders with a fill being booked
+    filling: Arc<Mutex<HashSet<String>>>,
+}
+
+/// Marks an order as being filled while held; dropping it frees the order
+pub struct FillClaim {
+    filling: Arc<Mutex<HashSet<String>>>,
+    id: String,
+}
+
+impl Drop for FillClaim {
+    fn drop(&mut self) {
+        self.filling.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+    }
+}
+
+impl OrderWatcher {
+    pub fn new(
+        db: PocketBaseClient,
+        notification_service: NotificationService,
+        price_refresher: PriceRefresher,
+    ) -> Self {
+        Self {
+            db,
+            notification_service,
+            price_refresher,
+            open: Arc::new(RwLock::new(Vec::new())),
+            filling: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Lock one order for booking a fill; None while another fill of it is in progress
+    pub fn claim_fill(&self, id: &str) -> Option<FillClaim> {
+        let mut filling = self.filling.lock().unwrap_or_else(|e| e.into_inner());
+        filling.insert(id.to_string()).then(|| FillClaim {
+            filling: self.filling.clone(),
+            id: id.to_string(),
+        })
+    }
+
+    /// Re-read open orders; their symbols are kept warm so prices keep arriving
+    pub async fn reload(&self) -> Result<(), AppError> {
+        let orders: Vec<Order> = self.db
+            .list_all_records(ORDERS_COLLECTION, Some("status='open'".to_string()))
+            .await?;
+        let symbols = orders.iter()
+            .map(|o| HeldSymbol { symbol: o.symbol.clone(), asset_type: o.asset_type.clone(), market: o.market.clone() })
+            .collect();
+        self.price_refresher.track(symbols, Vec::new()).await;
+        *self.open.write().await = orders;
+        Ok(())
+    }
+
+    /// Start matching the price service's updates against open orders
+    pub fn start(&self, price_service: &PriceService) {
+        let watcher = self.clone();
+        let mut updates = price_service.subscribe();
+        tokio::spawn(async move {
+            let mut reload = tokio::time::interval(tokio::time::Duration::from_secs(RELOAD_INTERVAL_SECONDS));
+            tracing::info!("🎯 Order watcher started");
+            loop {
+                tokio::select! {
+                    _ = reload.tick() => {
+                        if let Err(e) = watcher.reload().await {
+                            tracing::warn!("⚠️ Failed to load open orders: {}", e);
+                        }
+                    }
+                    update = updates.recv() => match update {
+                        Ok(update) => watcher.check(&update).await,
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!("⚠️ Order watcher fell behind, skipped {} price updates", skipped);
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                }
+            }
+        });
+    }
+
+    async fn check(&self, update: &PriceUpdate) {
+        // Static fallback prices say nothing about the market
+        if update.entry.source.as_deref() == Some("mock") || update.entry.price <= 0.0 {
+            return;
+        }
+        let triggered: Vec<Order> = {
+            let mut open = self.open.write().await;
+            let (hit, rest): (Vec<Order>, Vec<Order>) = open.drain(..).partition(|order| {
+                order.asset_type == update.asset_type
+                    && order.symbol.eq_ignore_ascii_case(&update.entry.symbol)
+                    && (order.market.is_none() || update.market.is_none() || order.market == update.market)
+                    && order.currency.as_deref().is_none_or(|c| c.eq_ignore_ascii_case(&update.entry.currency))
+                    && order.is_triggered_by(update.entry.price)
+            });
+            *open = rest;
+            hit
+        };
+
+        for order in triggered {
+            self.trigger(order, update).await;
+        }
+    }
+
+    async fn trigger(&self, order: Order, update: &PriceUpdate) {
+        let body = serde_json::json!({
+            "status": OrderStatus::Triggered,
+            "triggered_price": update.entry.price,
+            "triggered_at": Utc::now().to_rfc3339(),
+        });
+        if let Err(e) = self.db.update_record::<Order>(ORDERS_COLLECTION, &order.id, &body).await {
+            tracing::warn!("⚠️ Failed to mark order {} triggered: {}", order.id, e);
+            return;
+        }
+        tracing::info!(
+            "🎯 Order {} ({:?} {} {} @ {}) triggered at {}",
+            order.id, order.action, order.quantity, order.symbol, order.price, update.entry.price
+        );
+
+        let title = format!("Order likely filled: {}", order.symbol);
+        let body = format!(
+            "{} reached {} {} (your {:?} {:?} order for {} at {}). Confirm the fill to record it as a transaction.",
+            order.symbol, update.entry.price, update.entry.currency,
+            order.order_type, order.action, order.quantity, order.price
+        );
+        if let Err(e) = self.notification_service.send_report(&order.user_id, &title, &body).await {
+            tracing::warn!("⚠️ Failed to notify {} about order {}: {}", order.user_id, order.id, e);
+        }
+    }
+}
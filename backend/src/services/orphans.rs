@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::error::AppError;
+use crate::services::PocketBaseClient;
+
+/// Orphaned ids listed per collection in a report
+const SAMPLE_IDS: usize = 20;
+
+/// A reference from `collection.field` to a record in `parent`
+struct OrphanRule {
+    collection: &'static str,
+    field: &'static str,
+    parent: &'static str,
+    /// Empty references are allowed (e.g. a transaction without an account)
+    optional: bool,
+}
+
+/// Checked in order, parents before children, so records whose parent is removed in the
+/// same run (accounts of a deleted user, then their transactions) are caught in one pass
+const RULES: &[OrphanRule] = &[
+    OrphanRule { collection: "accounts", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "transactions", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "transactions", field: "account_id", parent: "accounts", optional: true },
+    OrphanRule { collection: "orders", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "portfolio_snapshots", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "portfolio_snapshots", field: "account_id", parent: "accounts", optional: true },
+    OrphanRule { collection: "liabilities", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "liability_transactions", field: "liability_id", parent: "liabilities", optional: false },
+    OrphanRule { collection: "equity_grants", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "custom_field_definitions", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "alerts", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "alert_history", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "alert_history", field: "alert_id", parent: "alerts", optional: true },
+    OrphanRule { collection: "notifications", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "push_subscriptions", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "user_settings", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "sessions", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "exchange_keys", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "staged_trades", field: "user_id", parent: "users", optional: false },
+    OrphanRule { collection: "staged_trades", field: "exchange_key_id", parent: "exchange_keys", optional: false },
+];
+
+/// Orphans found for one reference
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanRuleReport {
+    pub collection: String,
+    pub field: String,
+    pub parent: String,
+    pub scanned: usize,
+    pub orphaned: usize,
+    pub removed: usize,
+    pub sample_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+/// Result of an orphan scan; with `dry_run` nothing was deleted
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanReport {
+    pub dry_run: bool,
+    pub scanned_at: DateTime<Utc>,
+    pub total_orphaned: usize,
+    pub total_removed: usize,
+    pub errors: usize,
+    pub rules: Vec<OrphanRuleReport>,
+}
+
+/// Find records whose owner or parent no longer exists and, unless `dry_run`, delete them
+pub async fn clean_orphans(pb: &PocketBaseClient, dry_run: bool) -> Result<OrphanReport, AppError> {
+    tracing::info!("🧹 Scanning for orphaned records (dry run: {})", dry_run);
+
+    // Ids per collection, loaded once and shrunk as orphans are found
+    let mut ids: HashMap<&'static str, HashSet<String>> = HashMap::new();
+    let mut records: HashMap<&'static str, Vec<serde_json::Value>> = HashMap::new();
+    let mut report = OrphanReport {
+        dry_run,
+        scanned_at: Utc::now(),
+        total_orphaned: 0,
+        total_removed: 0,
+        errors: 0,
+        rules: Vec::new(),
+    };
+
+    for rule in RULES {
+        let mut rule_report = OrphanRuleReport {
+            collection: rule.collection.to_string(),
+            field: rule.field.to_string(),
+            parent: rule.parent.to_string(),
+            scanned: 0,
+            orphaned: 0,
+            removed: 0,
+            sample_ids: Vec::new(),
+            skipped: None,
+        };
+
+        let loaded = match load_collection(pb, rule.parent, &mut ids, &mut records).await {
+            Ok(()) => load_collection(pb, rule.collection, &mut ids, &mut records).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = loaded {
+            tracing::warn!("⚠️ Orphan scan skipped {}.{}: {}", rule.collection, rule.field, e);
+            rule_report.skipped = Some(e.to_string());
+            report.rules.push(rule_report);
+            continue;
+        }
+
+        let children = &records[rule.collection];
+        let parents = &ids[rule.parent];
+        rule_report.scanned = children.len();
+
+        // An empty parent collection next to a populated child one is far more likely a
+        // permissions or connection problem than real data; never treat that as "all orphaned"
+        if parents.is_empty() && !children.is_empty() {
+            rule_report.skipped = Some(format!("{} returned no records", rule.parent));
+            report.rules.push(rule_report);
+            continue;
+        }
+
+        let orphan_ids: Vec<String> = children.iter()
+            .filter(|record| {
+                let reference = record.get(rule.field).and_then(|v| v.as_str()).unwrap_or("");
+                if reference.is_empty() {
+                    !rule.optional
+                } else {
+                    !parents.contains(reference)
+                }
+            })
+            .filter_map(|record| record.get("id").and_then(|v| v.as_str()).map(|s| s.to_string()))
+            .filter(|id| ids[rule.collection].contains(id))
+            .collect();
+
+        rule_report.orphaned = orphan_ids.len();
+        rule_report.sample_ids = orphan_ids.iter().take(SAMPLE_IDS).cloned().collect();
+
+        for id in &orphan_ids {
+            if !dry_run {
+                match pb.delete_record(rule.collection, id).await {
+                    Ok(()) => rule_report.removed += 1,
+                    Err(e) => {
+                        report.errors += 1;
+                        tracing::warn!("⚠️ Could not delete orphaned {} {}: {}", rule.collection, id, e);
+                        continue;
+                    }
+                }
+            }
+            // Children of this record are orphans too, including in a dry run
+            if let Some(set) = ids.get_mut(rule.collection) {
+                set.remove(id);
+            }
+        }
+
+        if rule_report.orphaned > 0 {
+            tracing::info!("🧹 {}.{}: {} orphaned, {} removed", rule.collection, rule.field, rule_report.orphaned, rule_report.removed);
+        }
+        report.total_orphaned += rule_report.orphaned;
+        report.total_removed += rule_report.removed;
+        report.rules.push(rule_report);
+    }
+
+    tracing::info!("✅ Orphan scan complete: {} orphaned, {} removed, {} errors",
+        report.total_orphaned, report.total_removed, report.errors);
+    Ok(report)
+}
+
+async fn load_collection(
+    pb: &PocketBaseClient,
+    collection: &'static str,
+    ids: &mut HashMap<&'static str, HashSet<String>>,
+    records: &mut HashMap<&'static str, Vec<serde_json::Value>>,
+) -> Result<(), AppError> {
+    if records.contains_key(collection) {
+        return Ok(());
+    }
+    let items: Vec<serde_json::Value> = pb.list_all_records(collection, None).await?;
+    ids.insert(collection, items.iter()
+        .filter_map(|item| item.get("id").and_then(|v| v.as_str()).map(|s| s.to_string()))
+        .collect());
+    records.insert(collection, items);
+    Ok(())
+}
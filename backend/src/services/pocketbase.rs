@@ -1,11 +1,53 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use crate::config::Config;
 use crate::error::AppError;
-use crate::models::{Transaction, CreateTransactionRequest, UpdateTransactionRequest, Account, CreateAccountRequest, UpdateAccountRequest};
+use crate::models::{Transaction, CreateTransactionRequest, UpdateTransactionRequest, Account, CreateAccountRequest, UpdateAccountRequest, TargetAllocation};
+use crate::models::{EventsPage, SyncEntity, SyncEvent, SyncOp, SYNC_EVENTS_COLLECTION};
+
+/// A change waiting to be appended to the events feed
+struct PendingEvent {
+    user_id: String,
+    entity: SyncEntity,
+    entity_id: String,
+    op: SyncOp,
+    data: serde_json::Value,
+}
+
+/// How long a locally written cache entry is trusted over what PocketBase returns
+pub const PENDING_WRITE_GRACE_SECONDS: i64 = 60;
+
+/// What a cache revalidation changed
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CacheChanges {
+    pub updated: usize,
+    pub removed: usize,
+}
+
+/// Cached records keyed by their PocketBase ID
+trait HasId {
+    fn id(&self) -> &str;
+}
+
+impl HasId for Transaction {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl HasId for Account {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// PocketBase filter for records changed at or after `since` (PocketBase's "updated" autodate)
+pub fn updated_since_filter(since: DateTime<Utc>) -> String {
+    format!("updated>='{}'", since.format("%Y-%m-%d %H:%M:%S%.3fZ"))
+}
 
 /// PocketBase client for database operations
 /// Syncs data to PocketBase API with in-memory cache for performance
@@ -22,6 +64,8 @@ pub struct PocketBaseClient {
     // Admin token
     token: Arc<RwLock<Option<String>>>,
     config: Config,
+    // Feeds the single writer that numbers sync events, started on first use
+    events: Arc<std::sync::OnceLock<tokio::sync::mpsc::UnboundedSender<PendingEvent>>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -55,16 +99,22 @@ impl PocketBaseClient {
     pub fn new(config: Config) -> Self {
         Self {
             pocketbase_url: config.pocketbase_url.clone(),
-            client: reqwest::Client::new(),
+            client: crate::services::http_client::clients(&config).internal.clone(),
             transactions: Arc::new(RwLock::new(HashMap::new())),
             accounts: Arc::new(RwLock::new(HashMap::new())),
             loaded_transactions: Arc::new(RwLock::new(false)),
             loaded_accounts: Arc::new(RwLock::new(false)),
             token: Arc::new(RwLock::new(None)),
             config,
+            events: Arc::new(std::sync::OnceLock::new()),
         }
     }
 
+    /// Pooled client for PocketBase requests made outside this type
+    pub fn http(&self) -> reqwest::Client {
+        self.client.clone()
+    }
+
     /// Authenticate as Admin to PocketBase
     async fn authenticate(&self) -> Result<String, AppError> {
         let email = self.config.pb_admin_email.as_deref().unwrap_or("");
@@ -125,6 +175,39 @@ impl PocketBaseClient {
         }
     }
 
+    /// Whether PocketBase answers its health check within two seconds
+    pub async fn is_healthy(&self) -> bool {
+        let url = format!("{}/api/health", self.pocketbase_url);
+        self.client.get(&url)
+            .timeout(std::time::Duration::from_secs(2))
+            .send()
+            .await
+            .is_ok_and(|resp| resp.status().is_success())
+    }
+
+    /// When any user's portfolio snapshot was last written
+    pub async fn latest_snapshot_at(&self) -> Result<Option<chrono::DateTime<Utc>>, AppError> {
+        let token = self.get_token().await;
+        let url = format!(
+            "{}/api/collections/portfolio_snapshots/records?sort=-updated&perPage=1&skipTotal=1&fields=updated",
+            self.pocketbase_url
+        );
+        let request = self.client.get(&url);
+        let request = if !token.is_empty() { request.header("Authorization", token) } else { request };
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch portfolio_snapshots: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(AppError::Internal(format!("Failed to fetch portfolio_snapshots: {}", response.status())));
+        }
+        let body: serde_json::Value = response.json().await
+            .map_err(|e| AppError::Internal(format!("Failed to parse portfolio_snapshots: {}", e)))?;
+        // PocketBase writes "2024-01-31 12:00:00.000Z"
+        Ok(body.pointer("/items/0/updated")
+            .and_then(|v| v.as_str())
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s.replacen(' ', "T", 1)).ok())
+            .map(|d| d.with_timezone(&Utc)))
+    }
+
     // ==================== Transaction Operations ====================
 
     /// Load transactions from PocketBase (called once on first access)
@@ -225,6 +308,7 @@ impl PocketBaseClient {
             }
         });
         
+        self.record_event(user_id, SyncEntity::Transaction, &transaction.id, SyncOp::Created, Some(&transaction));
         tracing::info!("Created transaction: {} for user: {}", transaction.id, user_id);
         Ok(transaction)
     }
@@ -237,6 +321,7 @@ impl PocketBaseClient {
         let cache = self.transactions.read().await;
         let mut list: Vec<Transaction> = cache
             .values()
+            .filter(|t| t.deleted_at.is_none())
             .filter(|t| {
                  let match_user = t.user_id == user_id;
                  if !match_user {
@@ -250,7 +335,7 @@ impl PocketBaseClient {
         tracing::info!("👤 User {} has {} transactions (out of {} total)", user_id, list.len(), cache.len());
         
         // Sort by timestamp descending (newest first)
-        list.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        list.sort_by_key(|t| std::cmp::Reverse(t.timestamp));
         
         Ok(list)
     }
@@ -262,6 +347,7 @@ impl PocketBaseClient {
         let cache = self.transactions.read().await;
         cache
             .get(id)
+            .filter(|t| t.deleted_at.is_none())
             .cloned()
             .ok_or_else(|| AppError::NotFound(format!("Transaction {} not found", id)))
     }
@@ -300,6 +386,9 @@ impl PocketBaseClient {
         if let Some(fees) = req.fees {
             transaction.fees = fees;
         }
+        if let Some(network_fee) = req.network_fee {
+            transaction.network_fee = network_fee;
+        }
         if let Some(currency) = req.currency {
             transaction.currency = Some(currency);
         }
@@ -324,6 +413,15 @@ impl PocketBaseClient {
         if let Some(unit) = req.unit {
             transaction.unit = Some(unit);
         }
+        if let Some(custom_fields) = req.custom_fields {
+            for (key, value) in custom_fields {
+                if value.is_null() {
+                    transaction.custom_fields.remove(&key);
+                } else {
+                    transaction.custom_fields.insert(key, value);
+                }
+            }
+        }
         
         transaction.updated_at = Utc::now();
         let updated = transaction.clone();
@@ -350,6 +448,7 @@ impl PocketBaseClient {
             }
         });
         
+        self.record_event(&updated.user_id, SyncEntity::Transaction, id, SyncOp::Updated, Some(&updated));
         tracing::info!("Updated transaction: {}", id);
         Ok(updated)
     }
@@ -358,9 +457,10 @@ impl PocketBaseClient {
     pub async fn delete_transaction(&self, id: &str) -> Result<(), AppError> {
         let mut cache = self.transactions.write().await;
         
-        if cache.remove(id).is_none() {
+        let Some(removed) = cache.remove(id) else {
             return Err(AppError::NotFound(format!("Transaction {} not found", id)));
-        }
+        };
+        self.record_event::<()>(&removed.user_id, SyncEntity::Transaction, id, SyncOp::Deleted, None);
 
         // Sync to PocketBase
         let url = format!("{}/api/collections/transactions/records/{}", self.pocketbase_url, id);
@@ -387,6 +487,79 @@ impl PocketBaseClient {
         Ok(())
     }
 
+    /// Soft-delete transactions as one batch. They disappear from every listing
+    /// but stay in PocketBase until the batch is restored or purged.
+    pub async fn soft_delete_transactions(&self, ids: &[String], batch_id: &str) -> Result<usize, AppError> {
+        self.load_transactions_from_pb().await?;
+        let now = Utc::now();
+        let mut changed = Vec::new();
+        {
+            let mut cache = self.transactions.write().await;
+            for id in ids {
+                if let Some(tx) = cache.get_mut(id).filter(|t| t.deleted_at.is_none()) {
+                    tx.deleted_at = Some(now);
+                    tx.deletion_batch = Some(batch_id.to_string());
+                    tx.updated_at = now;
+                    changed.push(tx.clone());
+                }
+            }
+        }
+        let count = changed.len();
+        for tx in &changed {
+            self.record_event::<()>(&tx.user_id, SyncEntity::Transaction, &tx.id, SyncOp::Deleted, None);
+        }
+        self.sync_transaction_patches(changed);
+        tracing::info!("🗑️ Soft-deleted {} transactions (batch {})", count, batch_id);
+        Ok(count)
+    }
+
+    /// Undo a bulk delete batch for its owner
+    pub async fn restore_transactions(&self, user_id: &str, batch_id: &str) -> Result<usize, AppError> {
+        self.load_transactions_from_pb().await?;
+        let mut changed = Vec::new();
+        {
+            let mut cache = self.transactions.write().await;
+            for tx in cache.values_mut() {
+                if tx.user_id == user_id && tx.deletion_batch.as_deref() == Some(batch_id) {
+                    tx.deleted_at = None;
+                    tx.deletion_batch = None;
+                    tx.updated_at = Utc::now();
+                    changed.push(tx.clone());
+                }
+            }
+        }
+        let count = changed.len();
+        for tx in &changed {
+            self.record_event(&tx.user_id, SyncEntity::Transaction, &tx.id, SyncOp::Created, Some(tx));
+        }
+        self.sync_transaction_patches(changed);
+        tracing::info!("♻️ Restored {} transactions (batch {})", count, batch_id);
+        Ok(count)
+    }
+
+    /// Push cached transaction changes to PocketBase in the background
+    fn sync_transaction_patches(&self, transactions: Vec<Transaction>) {
+        if transactions.is_empty() {
+            return;
+        }
+        let me = self.clone();
+        tokio::spawn(async move {
+            let token = me.get_token().await;
+            for tx in transactions {
+                let url = format!("{}/api/collections/transactions/records/{}", me.pocketbase_url, tx.id);
+                let req = me.client.patch(&url);
+                let req = if !token.is_empty() { req.header("Authorization", token.clone()) } else { req };
+                match req.json(&tx).send().await {
+                    Ok(resp) if !resp.status().is_success() => {
+                        tracing::warn!("⚠️ Failed to sync transaction {}: {}", tx.id, resp.status());
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("⚠️ Could not sync transaction {}: {}", tx.id, e),
+                }
+            }
+        });
+    }
+
     /// Get transactions filtered by asset type
     pub async fn get_transactions_by_type(
         &self,
@@ -397,7 +570,7 @@ impl PocketBaseClient {
         let cache = self.transactions.read().await;
         let filtered: Vec<Transaction> = cache
             .values()
-            .filter(|t| t.asset_type == *asset_type)
+            .filter(|t| t.asset_type == *asset_type && t.deleted_at.is_none())
             .cloned()
             .collect();
         
@@ -416,7 +589,7 @@ impl PocketBaseClient {
         
         let filtered: Vec<Transaction> = cache
             .values()
-            .filter(|t| t.symbol == symbol_upper)
+            .filter(|t| t.symbol == symbol_upper && t.deleted_at.is_none())
             .cloned()
             .collect();
         
@@ -506,6 +679,7 @@ impl PocketBaseClient {
             }
         });
         
+        self.record_event(user_id, SyncEntity::Account, &account.id, SyncOp::Created, Some(&account));
         tracing::info!("Created account: {} for user: {}", account.id, user_id);
         Ok(account)
     }
@@ -529,6 +703,44 @@ impl PocketBaseClient {
         Ok(list)
     }
 
+    /// Get every account across all users (for background jobs)
+    pub async fn list_all_accounts(&self) -> Result<Vec<Account>, AppError> {
+        self.load_accounts_from_pb().await?;
+        let cache = self.accounts.read().await;
+        Ok(cache.values().cloned().collect())
+    }
+
+    /// Persist interest accrual progress for an account
+    pub async fn update_account_accrual(
+        &self,
+        id: &str,
+        pending_interest: f64,
+        last_accrued_date: &str,
+    ) -> Result<(), AppError> {
+        // Written before the cache so a failed save is retried from the last saved day
+        let token = self.get_token().await;
+        let url = format!("{}/api/collections/accounts/records/{}", self.pocketbase_url, id);
+        let body = serde_json::json!({
+            "pending_interest": pending_interest,
+            "last_accrued_date": last_accrued_date,
+        });
+        let req = self.client.patch(&url);
+        let req = if !token.is_empty() { req.header("Authorization", token) } else { req };
+        let response = req.json(&body).send().await
+            .map_err(|e| AppError::Internal(format!("Failed to save interest accrual: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(AppError::Internal(format!("Failed to save interest accrual: {}", response.status())));
+        }
+
+        let mut cache = self.accounts.write().await;
+        if let Some(account) = cache.get_mut(id) {
+            account.pending_interest = pending_interest;
+            account.last_accrued_date = Some(last_accrued_date.to_string());
+            self.record_event(&account.user_id, SyncEntity::Account, id, SyncOp::Updated, Some(&*account));
+        }
+        Ok(())
+    }
+
     /// Get an account by ID
     pub async fn get_account(&self, id: &str) -> Result<Account, AppError> {
         self.load_accounts_from_pb().await?;
@@ -551,6 +763,8 @@ impl PocketBaseClient {
         let account = cache
             .get_mut(id)
             .ok_or_else(|| AppError::NotFound(format!("Account {} not found", id)))?;
+        // Interest starts accruing from the change; callers settle what the old terms owe first
+        let accrual_changed = req.changes_accrual(account);
 
         // Apply updates
         if let Some(name) = req.name {
@@ -568,9 +782,30 @@ impl PocketBaseClient {
         if let Some(target_currency) = req.target_currency {
             account.target_currency = target_currency;
         }
+        if let Some(base_currency) = req.base_currency {
+            account.base_currency = base_currency;
+        }
         if let Some(rank) = req.rank {
             account.rank = rank;
         }
+        if let Some(account_type) = req.account_type {
+            account.account_type = account_type;
+        }
+        if let Some(apr_percent) = req.apr_percent {
+            account.apr_percent = Some(apr_percent);
+        }
+        if accrual_changed {
+            account.last_accrued_date = Some(Utc::now().format("%Y-%m-%d").to_string());
+        }
+        if let Some(compounding) = req.compounding {
+            account.compounding = compounding;
+        }
+        if let Some(maturity_date) = req.maturity_date {
+            account.maturity_date = Some(maturity_date);
+        }
+        if let Some(benchmark) = req.benchmark {
+            account.benchmark = Some(benchmark).filter(|b| !b.is_empty());
+        }
         
         account.updated_at = Utc::now();
         let updated = account.clone();
@@ -597,17 +832,152 @@ impl PocketBaseClient {
             }
         });
         
+        self.record_event(&updated.user_id, SyncEntity::Account, id, SyncOp::Updated, Some(&updated));
         tracing::info!("Updated account: {}", id);
         Ok(updated)
     }
 
+    /// Apply new account ranks as one unit: every PATCH must succeed before the cache
+    /// changes, and ranks already written are put back if a later one fails.
+    pub async fn set_account_ranks(&self, ranks: &[(String, i32)]) -> Result<(), AppError> {
+        self.load_accounts_from_pb().await?;
+        // Held for the whole write so concurrent reorders cannot interleave
+        let mut cache = self.accounts.write().await;
+        let previous: Vec<(String, i32)> = ranks.iter()
+            .map(|(id, _)| cache.get(id)
+                .map(|a| (id.clone(), a.rank))
+                .ok_or_else(|| AppError::NotFound(format!("Account {} not found", id))))
+            .collect::<Result<_, _>>()?;
+
+        let token = self.get_token().await;
+        for (done, (id, rank)) in ranks.iter().enumerate() {
+            if let Err(e) = self.patch_account_rank(&token, id, *rank).await {
+                tracing::warn!("⚠️ Account reorder failed at {}: {}, rolling back {} account(s)", id, e, done);
+                for (prev_id, prev_rank) in &previous[..done] {
+                    if let Err(e) = self.patch_account_rank(&token, prev_id, *prev_rank).await {
+                        tracing::error!("❌ Could not restore rank of account {}: {}", prev_id, e);
+                    }
+                }
+                return Err(e);
+            }
+        }
+
+        let now = Utc::now();
+        for (id, rank) in ranks {
+            if let Some(account) = cache.get_mut(id) {
+                account.rank = *rank;
+                account.updated_at = now;
+                self.record_event(&account.user_id, SyncEntity::Account, id, SyncOp::Updated, Some(&*account));
+            }
+        }
+        tracing::info!("🔀 Reordered {} account(s)", ranks.len());
+        Ok(())
+    }
+
+    async fn patch_account_rank(&self, token: &str, id: &str, rank: i32) -> Result<(), AppError> {
+        let url = format!("{}/api/collections/accounts/records/{}", self.pocketbase_url, id);
+        let req = self.client.patch(&url);
+        let req = if !token.is_empty() { req.header("Authorization", token) } else { req };
+        let resp = req.json(&serde_json::json!({ "rank": rank })).send().await
+            .map_err(|e| AppError::ExternalApiError(format!("Could not update account rank: {}", e)))?;
+        if !resp.status().is_success() {
+            return Err(AppError::ExternalApiError(format!("Failed to update account rank: {}", resp.status())));
+        }
+        Ok(())
+    }
+
+    /// Archive or reopen an account
+    pub async fn set_account_archived(&self, id: &str, archived: bool) -> Result<Account, AppError> {
+        self.load_accounts_from_pb().await?;
+        let updated = {
+            let mut cache = self.accounts.write().await;
+            let account = cache
+                .get_mut(id)
+                .ok_or_else(|| AppError::NotFound(format!("Account {} not found", id)))?;
+            account.archived = archived;
+            account.archived_at = archived.then(Utc::now);
+            account.updated_at = Utc::now();
+            account.clone()
+        };
+
+        let url = format!("{}/api/collections/accounts/records/{}", self.pocketbase_url, id);
+        let body = serde_json::json!({
+            "archived": updated.archived,
+            "archived_at": updated.archived_at,
+        });
+        let me = self.clone();
+        tokio::spawn(async move {
+            let token = me.get_token().await;
+            let req = me.client.patch(&url);
+            let req = if !token.is_empty() { req.header("Authorization", token) } else { req };
+
+            match req.json(&body).send().await {
+                Ok(resp) => {
+                    if !resp.status().is_success() {
+                        tracing::warn!("⚠️ Failed to sync account archive state: {}", resp.status());
+                    }
+                }
+                Err(e) => tracing::warn!("⚠️ Could not sync account archive state: {}", e),
+            }
+        });
+
+        self.record_event(&updated.user_id, SyncEntity::Account, id, SyncOp::Updated, Some(&updated));
+        tracing::info!("{} account: {}", if archived { "Archived" } else { "Reopened" }, id);
+        Ok(updated)
+    }
+
+    /// Set or (with None) remove an account's target allocation model
+    pub async fn set_account_target_allocation(&self, id: &str, target_allocation: Option<TargetAllocation>) -> Result<Account, AppError> {
+        self.load_accounts_from_pb().await?;
+        let updated = {
+            let mut cache = self.accounts.write().await;
+            let account = cache
+                .get_mut(id)
+                .ok_or_else(|| AppError::NotFound(format!("Account {} not found", id)))?;
+            account.target_allocation = target_allocation;
+            account.updated_at = Utc::now();
+            account.clone()
+        };
+
+        let url = format!("{}/api/collections/accounts/records/{}", self.pocketbase_url, id);
+        let body = serde_json::json!({ "target_allocation": updated.target_allocation });
+        let me = self.clone();
+        tokio::spawn(async move {
+            let token = me.get_token().await;
+            let req = me.client.patch(&url);
+            let req = if !token.is_empty() { req.header("Authorization", token) } else { req };
+
+            match req.json(&body).send().await {
+                Ok(resp) => {
+                    if !resp.status().is_success() {
+                        tracing::warn!("⚠️ Failed to sync account target allocation: {}", resp.status());
+                    }
+                }
+                Err(e) => tracing::warn!("⚠️ Could not sync account target allocation: {}", e),
+            }
+        });
+
+        self.record_event(&updated.user_id, SyncEntity::Account, id, SyncOp::Updated, Some(&updated));
+        Ok(updated)
+    }
+
+    /// IDs of the user's archived accounts
+    pub async fn archived_account_ids(&self, user_id: &str) -> Result<std::collections::HashSet<String>, AppError> {
+        Ok(self.list_accounts(user_id).await?
+            .into_iter()
+            .filter(|a| a.archived)
+            .map(|a| a.id)
+            .collect())
+    }
+
     /// Delete an account
     pub async fn delete_account(&self, id: &str) -> Result<(), AppError> {
         let mut cache = self.accounts.write().await;
         
-        if cache.remove(id).is_none() {
+        let Some(removed) = cache.remove(id) else {
             return Err(AppError::NotFound(format!("Account {} not found", id)));
-        }
+        };
+        self.record_event::<()>(&removed.user_id, SyncEntity::Account, id, SyncOp::Deleted, None);
 
         // Sync to PocketBase
         let url = format!("{}/api/collections/accounts/records/{}", self.pocketbase_url, id);
@@ -644,13 +1014,245 @@ impl PocketBaseClient {
         let cache = self.transactions.read().await;
         let filtered: Vec<Transaction> = cache
             .values()
-            .filter(|t| t.account_id.as_deref() == Some(account_id))
+            .filter(|t| t.account_id.as_deref() == Some(account_id) && t.deleted_at.is_none())
             .cloned()
             .collect();
         
         Ok(filtered)
     }
 
+    // ==================== Cache Revalidation ====================
+
+    /// Merge transactions changed in PocketBase since `since` into the cache and drop those
+    /// deleted there, so writes made outside this API show up. Does nothing before the cache
+    /// is first loaded.
+    pub async fn revalidate_transactions(&self, since: DateTime<Utc>) -> Result<CacheChanges, AppError> {
+        if !*self.loaded_transactions.read().await {
+            return Ok(CacheChanges::default());
+        }
+        self.revalidate_cache("transactions", &self.transactions, since, |tx| tx.updated_at).await
+    }
+
+    /// Like [`revalidate_transactions`](Self::revalidate_transactions), for accounts
+    pub async fn revalidate_accounts(&self, since: DateTime<Utc>) -> Result<CacheChanges, AppError> {
+        if !*self.loaded_accounts.read().await {
+            return Ok(CacheChanges::default());
+        }
+        self.revalidate_cache("accounts", &self.accounts, since, |account| account.updated_at).await
+    }
+
+    /// Entries written here within PENDING_WRITE_GRACE_SECONDS are left alone either way: the
+    /// background sync of that write may not have reached PocketBase yet.
+    async fn revalidate_cache<T: serde::de::DeserializeOwned + HasId>(
+        &self,
+        collection: &str,
+        cache: &RwLock<HashMap<String, T>>,
+        since: DateTime<Utc>,
+        written_at: fn(&T) -> DateTime<Utc>,
+    ) -> Result<CacheChanges, AppError> {
+        let grace_start = Utc::now() - chrono::Duration::seconds(PENDING_WRITE_GRACE_SECONDS);
+        let changed: Vec<T> = self.list_all_records(collection, Some(updated_since_filter(since))).await?;
+        let ids = self.record_ids(collection).await?;
+
+        let mut cache = cache.write().await;
+        let mut changes = CacheChanges::default();
+        for record in changed {
+            if cache.get(record.id()).is_some_and(|cached| written_at(cached) > grace_start) {
+                continue;
+            }
+            cache.insert(record.id().to_string(), record);
+            changes.updated += 1;
+        }
+        let before = cache.len();
+        cache.retain(|id, cached| ids.contains(id) || written_at(cached) > grace_start);
+        changes.removed = before - cache.len();
+        Ok(changes)
+    }
+
+    /// Apply a transaction created, updated or deleted in PocketBase (pushed by its realtime
+    /// API). Returns whether the cache changed.
+    pub async fn apply_transaction_change(&self, record: serde_json::Value, deleted: bool) -> Result<bool, AppError> {
+        if !*self.loaded_transactions.read().await {
+            return Ok(false);
+        }
+        Self::apply_change(&self.transactions, record, deleted, |tx| tx.updated_at).await
+    }
+
+    /// Like [`apply_transaction_change`](Self::apply_transaction_change), for accounts
+    pub async fn apply_account_change(&self, record: serde_json::Value, deleted: bool) -> Result<bool, AppError> {
+        if !*self.loaded_accounts.read().await {
+            return Ok(false);
+        }
+        Self::apply_change(&self.accounts, record, deleted, |account| account.updated_at).await
+    }
+
+    /// Entries written here within PENDING_WRITE_GRACE_SECONDS are kept, as in
+    /// `revalidate_cache`: the event may be this API's own write, or an older one.
+    async fn apply_change<T: serde::de::DeserializeOwned + HasId>(
+        cache: &RwLock<HashMap<String, T>>,
+        record: serde_json::Value,
+        deleted: bool,
+        written_at: fn(&T) -> DateTime<Utc>,
+    ) -> Result<bool, AppError> {
+        let record: T = serde_json::from_value(record)
+            .map_err(|e| AppError::Internal(format!("Failed to parse realtime record: {}", e)))?;
+        let grace_start = Utc::now() - chrono::Duration::seconds(PENDING_WRITE_GRACE_SECONDS);
+        let mut cache = cache.write().await;
+        if cache.get(record.id()).is_some_and(|cached| written_at(cached) > grace_start) {
+            return Ok(false);
+        }
+        if deleted {
+            return Ok(cache.remove(record.id()).is_some());
+        }
+        cache.insert(record.id().to_string(), record);
+        Ok(true)
+    }
+
+    /// IDs of every record in a collection, read a page at a time without the record bodies
+    pub async fn record_ids(&self, collection: &str) -> Result<HashSet<String>, AppError> {
+        #[derive(Deserialize)]
+        struct IdOnly {
+            id: String,
+        }
+
+        let token = self.get_token().await;
+        let mut ids = HashSet::new();
+        let mut page = 1;
+        loop {
+            let url = format!(
+                "{}/api/collections/{}/records?perPage=500&page={}&fields=id",
+                self.pocketbase_url, collection, page
+            );
+            let request = self.client.get(&url);
+            let request = if !token.is_empty() { request.header("Authorization", &token) } else { request };
+            let response = request.send().await
+                .map_err(|e| AppError::Internal(format!("Failed to fetch {} ids: {}", collection, e)))?;
+            if !response.status().is_success() {
+                return Err(AppError::Internal(format!("Failed to fetch {} ids: {}", collection, response.status())));
+            }
+            let data: PBListResponse<IdOnly> = response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse {} ids: {}", collection, e)))?;
+            ids.extend(data.items.into_iter().map(|item| item.id));
+            if page >= data.total_pages {
+                return Ok(ids);
+            }
+            page += 1;
+        }
+    }
+
+    // ==================== Sync Event Operations ====================
+
+    /// Queue a change for the user's events feed. Events are numbered and written by a single
+    /// background task in the order they are queued, so `seq` follows the order of the changes.
+    pub fn record_event<T: Serialize>(
+        &self,
+        user_id: &str,
+        entity: SyncEntity,
+        entity_id: &str,
+        op: SyncOp,
+        data: Option<&T>,
+    ) {
+        let sender = self.events.get_or_init(|| {
+            let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+            tokio::spawn(self.clone().write_events(rx));
+            tx
+        });
+        let event = PendingEvent {
+            user_id: user_id.to_string(),
+            entity,
+            entity_id: entity_id.to_string(),
+            op,
+            data: data.and_then(|d| serde_json::to_value(d).ok()).unwrap_or_default(),
+        };
+        if sender.send(event).is_err() {
+            tracing::warn!("⚠️ Events feed writer stopped, dropped {:?} {} event", entity, entity_id);
+        }
+    }
+
+    async fn write_events(self, mut rx: tokio::sync::mpsc::UnboundedReceiver<PendingEvent>) {
+        let mut head = None;
+        while let Some(event) = rx.recv().await {
+            let seq = match head {
+                Some(seq) => seq,
+                None => match self.event_head().await {
+                    Ok(seq) => seq,
+                    Err(e) => {
+                        // Numbering without the stored head could reuse sequence numbers
+                        tracing::warn!("⚠️ Dropped {:?} {} event: {}", event.entity, event.entity_id, e);
+                        continue;
+                    }
+                },
+            } + 1;
+            let body = serde_json::json!({
+                "seq": seq,
+                "user_id": event.user_id,
+                "entity": event.entity,
+                "entity_id": event.entity_id,
+                "op": event.op,
+                "data": event.data,
+            });
+            match self.create_record::<serde_json::Value>(SYNC_EVENTS_COLLECTION, &body).await {
+                Ok(_) => head = Some(seq),
+                Err(e) => tracing::warn!("⚠️ Could not record {:?} {} event: {}", event.entity, event.entity_id, e),
+            }
+        }
+    }
+
+    /// Highest sequence number in the events feed (0 when empty)
+    pub async fn event_head(&self) -> Result<u64, AppError> {
+        let token = self.get_token().await;
+        let url = format!(
+            "{}/api/collections/{}/records?perPage=1&sort=-seq&skipTotal=1",
+            self.pocketbase_url, SYNC_EVENTS_COLLECTION
+        );
+        let request = self.client.get(&url);
+        let request = if !token.is_empty() { request.header("Authorization", token) } else { request };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch events head: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(AppError::Internal(format!("Failed to fetch events head: {}", response.status())));
+        }
+        let data: PBListResponse<SyncEvent> = response.json().await
+            .map_err(|e| AppError::Internal(format!("Failed to parse events head: {}", e)))?;
+        Ok(data.items.first().map(|e| e.seq).unwrap_or(0))
+    }
+
+    /// The user's events after `cursor`, oldest first. Without a cursor (or with one past the
+    /// head, e.g. after the feed was wiped) the page is a reset pointing at the current head.
+    pub async fn list_events(&self, user_id: &str, cursor: Option<u64>, limit: u32) -> Result<EventsPage, AppError> {
+        let head = self.event_head().await?;
+        let Some(cursor) = cursor.filter(|c| *c <= head) else {
+            return Ok(EventsPage { events: Vec::new(), next_cursor: head, has_more: false, reset: true });
+        };
+
+        let token = self.get_token().await;
+        let filter = format!("user_id='{}' && seq>{}", user_id, cursor);
+        let url = format!(
+            "{}/api/collections/{}/records?perPage={}&sort=seq&filter={}",
+            self.pocketbase_url, SYNC_EVENTS_COLLECTION, limit, urlencoding::encode(&filter)
+        );
+        let request = self.client.get(&url);
+        let request = if !token.is_empty() { request.header("Authorization", token) } else { request };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch events: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(AppError::Internal(format!("Failed to fetch events: {}", response.status())));
+        }
+        let data: PBListResponse<SyncEvent> = response.json().await
+            .map_err(|e| AppError::Internal(format!("Failed to parse events: {}", e)))?;
+
+        let has_more = data.total_items as usize > data.items.len();
+        // A full page continues from its last event; otherwise the user has seen everything up to the head
+        let next_cursor = if has_more {
+            data.items.last().map(|e| e.seq).unwrap_or(cursor)
+        } else {
+            data.items.last().map(|e| e.seq).unwrap_or(cursor).max(head)
+        };
+        Ok(EventsPage { events: data.items, next_cursor, has_more, reset: false })
+    }
+
     // ==================== API Provider Operations ====================
 
     /// Get all API providers for a market, sorted by priority
@@ -722,6 +1324,9 @@ impl PocketBaseClient {
             "priority": req.priority,
             "enabled": req.enabled.unwrap_or(true),
             "timeout_ms": req.timeout_ms.unwrap_or(10000),
+            "use_for_consensus": req.use_for_consensus.unwrap_or(false),
+            "cache_ttl_seconds": req.cache_ttl_seconds.unwrap_or(0),
+            "proxy_url": req.proxy_url.unwrap_or_default(),
         });
         
         let request = self.client.post(&url).json(&body);
@@ -770,6 +1375,15 @@ impl PocketBaseClient {
         if let Some(timeout_ms) = req.timeout_ms {
             body.insert("timeout_ms".to_string(), serde_json::Value::Number(timeout_ms.into()));
         }
+        if let Some(use_for_consensus) = req.use_for_consensus {
+            body.insert("use_for_consensus".to_string(), serde_json::Value::Bool(use_for_consensus));
+        }
+        if let Some(cache_ttl_seconds) = req.cache_ttl_seconds {
+            body.insert("cache_ttl_seconds".to_string(), serde_json::Value::Number(cache_ttl_seconds.into()));
+        }
+        if let Some(proxy_url) = req.proxy_url {
+            body.insert("proxy_url".to_string(), serde_json::Value::String(proxy_url.trim().to_string()));
+        }
         
         let request = self.client.patch(&url).json(&serde_json::Value::Object(body));
         let request = if !token.is_empty() {
@@ -818,33 +1432,479 @@ impl PocketBaseClient {
         }
     }
 
-    // ==================== Asset Price Operations ====================
+    // ==================== Generic Record Operations ====================
 
-    /// Delete asset price by symbol
-    pub async fn delete_asset_price(&self, symbol: &str) -> Result<(), AppError> {
+    /// List records of a collection (first 500), optionally filtered
+    pub async fn list_records<T: serde::de::DeserializeOwned>(
+        &self,
+        collection: &str,
+        filter: Option<String>,
+        sort: &str,
+    ) -> Result<Vec<T>, AppError> {
         let token = self.get_token().await;
-        
-        // 1. Find the record ID
-        let find_url = format!(
-            "{}/api/collections/asset_prices/records?filter=(symbol='{}')",
-            self.pocketbase_url,
-            symbol
+        let mut url = format!(
+            "{}/api/collections/{}/records?perPage=500&sort={}",
+            self.pocketbase_url, collection, sort
         );
-        
-        let request = self.client.get(&find_url);
+        if let Some(filter) = filter {
+            url.push_str(&format!("&filter={}", urlencoding::encode(&filter)));
+        }
+
+        let request = self.client.get(&url);
         let request = if !token.is_empty() {
-            request.header("Authorization", &token)
+            request.header("Authorization", token)
         } else {
             request
         };
-        
+
         let response = request.send().await
-            .map_err(|e| AppError::Internal(format!("Failed to find asset price: {}", e)))?;
-            
-        if !response.status().is_success() {
-             return Ok(()); // Ignore if not found or error
+            .map_err(|e| AppError::Internal(format!("Failed to fetch {}: {}", collection, e)))?;
+
+        if response.status().is_success() {
+            let data: PBListResponse<T> = response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse {}: {}", collection, e)))?;
+            Ok(data.items)
+        } else {
+            tracing::warn!("⚠️ Could not load {}: {}", collection, response.status());
+            Ok(vec![])
         }
-        
+    }
+
+    /// List every record of a collection, following pagination. Unlike `list_records`, a failed
+    /// request is an error rather than an empty list, so callers can tell "none" from "unknown".
+    pub async fn list_all_records<T: serde::de::DeserializeOwned>(
+        &self,
+        collection: &str,
+        filter: Option<String>,
+    ) -> Result<Vec<T>, AppError> {
+        let token = self.get_token().await;
+        let mut records = Vec::new();
+        let mut page = 1;
+        loop {
+            let mut url = format!(
+                "{}/api/collections/{}/records?perPage=500&page={}&sort=created",
+                self.pocketbase_url, collection, page
+            );
+            if let Some(filter) = &filter {
+                url.push_str(&format!("&filter={}", urlencoding::encode(filter)));
+            }
+
+            let request = self.client.get(&url);
+            let request = if !token.is_empty() {
+                request.header("Authorization", &token)
+            } else {
+                request
+            };
+
+            let response = request.send().await
+                .map_err(|e| AppError::Internal(format!("Failed to fetch {}: {}", collection, e)))?;
+            if !response.status().is_success() {
+                return Err(AppError::Internal(format!("Failed to fetch {}: {}", collection, response.status())));
+            }
+
+            let data: PBListResponse<T> = response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse {}: {}", collection, e)))?;
+            records.extend(data.items);
+            if page >= data.total_pages {
+                return Ok(records);
+            }
+            page += 1;
+        }
+    }
+
+    /// One page of a collection with the total number of matching records
+    pub async fn list_records_page<T: serde::de::DeserializeOwned>(
+        &self,
+        collection: &str,
+        filter: Option<String>,
+        sort: &str,
+        page: u32,
+        per_page: u32,
+    ) -> Result<(Vec<T>, u32), AppError> {
+        let token = self.get_token().await;
+        let mut url = format!(
+            "{}/api/collections/{}/records?page={}&perPage={}&sort={}",
+            self.pocketbase_url, collection, page, per_page, sort
+        );
+        if let Some(filter) = filter {
+            url.push_str(&format!("&filter={}", urlencoding::encode(&filter)));
+        }
+
+        let request = self.client.get(&url);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch {}: {}", collection, e)))?;
+        if !response.status().is_success() {
+            return Err(AppError::Internal(format!("Failed to fetch {}: {}", collection, response.status())));
+        }
+        let data: PBListResponse<T> = response.json().await
+            .map_err(|e| AppError::Internal(format!("Failed to parse {}: {}", collection, e)))?;
+        Ok((data.items, data.total_items))
+    }
+
+    /// Get a single record by ID
+    pub async fn get_record<T: serde::de::DeserializeOwned>(&self, collection: &str, id: &str) -> Result<T, AppError> {
+        let token = self.get_token().await;
+        let url = format!("{}/api/collections/{}/records/{}", self.pocketbase_url, collection, id);
+
+        let request = self.client.get(&url);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch {} record: {}", collection, e)))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse {} record: {}", collection, e)))
+        } else {
+            Err(AppError::NotFound(format!("Record {} not found", id)))
+        }
+    }
+
+    /// Create a record and return it as stored
+    pub async fn create_record<T: serde::de::DeserializeOwned>(
+        &self,
+        collection: &str,
+        body: &serde_json::Value,
+    ) -> Result<T, AppError> {
+        let token = self.get_token().await;
+        let url = format!("{}/api/collections/{}/records", self.pocketbase_url, collection);
+
+        let request = self.client.post(&url).json(body);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to create {} record: {}", collection, e)))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse {} record: {}", collection, e)))
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(AppError::Internal(format!("Failed to create {} record: {} - {}", collection, status, body)))
+        }
+    }
+
+    /// Patch a record with the given fields
+    pub async fn update_record<T: serde::de::DeserializeOwned>(
+        &self,
+        collection: &str,
+        id: &str,
+        body: &serde_json::Value,
+    ) -> Result<T, AppError> {
+        let token = self.get_token().await;
+        let url = format!("{}/api/collections/{}/records/{}", self.pocketbase_url, collection, id);
+
+        let request = self.client.patch(&url).json(body);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to update {} record: {}", collection, e)))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse {} record: {}", collection, e)))
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(AppError::Internal(format!("Failed to update {} record: {} - {}", collection, status, body)))
+        }
+    }
+
+    /// Delete a record
+    pub async fn delete_record(&self, collection: &str, id: &str) -> Result<(), AppError> {
+        let token = self.get_token().await;
+        let url = format!("{}/api/collections/{}/records/{}", self.pocketbase_url, collection, id);
+
+        let request = self.client.delete(&url);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to delete {} record: {}", collection, e)))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(AppError::Internal(format!("Failed to delete {} record: {} - {}", collection, status, body)))
+        }
+    }
+
+    // ==================== Liability Operations ====================
+
+    /// List liabilities, optionally restricted to one user
+    async fn fetch_liabilities(&self, filter: Option<String>) -> Result<Vec<crate::models::Liability>, AppError> {
+        let token = self.get_token().await;
+        let mut url = format!("{}/api/collections/liabilities/records?perPage=500&sort=name", self.pocketbase_url);
+        if let Some(filter) = filter {
+            url.push_str(&format!("&filter={}", urlencoding::encode(&filter)));
+        }
+
+        let request = self.client.get(&url);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch liabilities: {}", e)))?;
+
+        if response.status().is_success() {
+            let data: PBListResponse<crate::models::Liability> = response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse liabilities: {}", e)))?;
+            Ok(data.items)
+        } else {
+            tracing::warn!("⚠️ Could not load liabilities: {}", response.status());
+            Ok(vec![])
+        }
+    }
+
+    /// Get all liabilities of a user
+    pub async fn list_liabilities(&self, user_id: &str) -> Result<Vec<crate::models::Liability>, AppError> {
+        self.fetch_liabilities(Some(format!("user_id='{}'", user_id))).await
+    }
+
+    /// Get every liability across all users (for background jobs)
+    pub async fn list_all_liabilities(&self) -> Result<Vec<crate::models::Liability>, AppError> {
+        self.fetch_liabilities(None).await
+    }
+
+    /// Get a liability by ID
+    pub async fn get_liability(&self, id: &str) -> Result<crate::models::Liability, AppError> {
+        let token = self.get_token().await;
+        let url = format!("{}/api/collections/liabilities/records/{}", self.pocketbase_url, id);
+
+        let request = self.client.get(&url);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch liability: {}", e)))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse liability: {}", e)))
+        } else {
+            Err(AppError::NotFound(format!("Liability {} not found", id)))
+        }
+    }
+
+    /// Create a new liability for a user
+    pub async fn create_liability(
+        &self,
+        req: crate::models::CreateLiabilityRequest,
+        user_id: &str,
+    ) -> Result<crate::models::Liability, AppError> {
+        let token = self.get_token().await;
+        let url = format!("{}/api/collections/liabilities/records", self.pocketbase_url);
+
+        let body = serde_json::json!({
+            "user_id": user_id,
+            "name": req.name,
+            "liability_type": req.liability_type,
+            "currency": req.currency.to_uppercase(),
+            "principal": req.principal,
+            "balance": req.principal,
+            "apr_percent": req.apr_percent,
+            "compounding": req.compounding,
+            "account_id": req.account_id,
+            "notes": req.notes,
+            "pending_interest": 0.0,
+            "last_accrued_date": Utc::now().format("%Y-%m-%d").to_string(),
+        });
+
+        let request = self.client.post(&url).json(&body);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to create liability: {}", e)))?;
+
+        if response.status().is_success() {
+            let liability: crate::models::Liability = response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse liability: {}", e)))?;
+            tracing::info!("✅ Created liability: {} for user {}", liability.id, user_id);
+            Ok(liability)
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(AppError::Internal(format!("Failed to create liability: {} - {}", status, body)))
+        }
+    }
+
+    /// Patch a liability with the given fields
+    pub async fn update_liability(&self, id: &str, body: serde_json::Value) -> Result<crate::models::Liability, AppError> {
+        let token = self.get_token().await;
+        let url = format!("{}/api/collections/liabilities/records/{}", self.pocketbase_url, id);
+
+        let request = self.client.patch(&url).json(&body);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to update liability: {}", e)))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse liability: {}", e)))
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(AppError::Internal(format!("Failed to update liability: {} - {}", status, body)))
+        }
+    }
+
+    /// Delete a liability
+    pub async fn delete_liability(&self, id: &str) -> Result<(), AppError> {
+        let token = self.get_token().await;
+        let url = format!("{}/api/collections/liabilities/records/{}", self.pocketbase_url, id);
+
+        let request = self.client.delete(&url);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to delete liability: {}", e)))?;
+
+        if response.status().is_success() {
+            tracing::info!("✅ Deleted liability: {}", id);
+            Ok(())
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(AppError::Internal(format!("Failed to delete liability: {} - {}", status, body)))
+        }
+    }
+
+    /// Get draws, repayments and interest of a liability (newest first)
+    pub async fn list_liability_transactions(&self, liability_id: &str) -> Result<Vec<crate::models::LiabilityTransaction>, AppError> {
+        let token = self.get_token().await;
+        let filter = format!("liability_id='{}'", liability_id);
+        let url = format!(
+            "{}/api/collections/liability_transactions/records?filter={}&sort=-timestamp&perPage=500",
+            self.pocketbase_url,
+            urlencoding::encode(&filter)
+        );
+
+        let request = self.client.get(&url);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch liability transactions: {}", e)))?;
+
+        if response.status().is_success() {
+            let data: PBListResponse<crate::models::LiabilityTransaction> = response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse liability transactions: {}", e)))?;
+            Ok(data.items)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// Record a liability transaction
+    pub async fn create_liability_transaction(
+        &self,
+        tx: &crate::models::LiabilityTransaction,
+    ) -> Result<crate::models::LiabilityTransaction, AppError> {
+        let token = self.get_token().await;
+        let url = format!("{}/api/collections/liability_transactions/records", self.pocketbase_url);
+
+        let body = serde_json::json!({
+            "liability_id": tx.liability_id,
+            "user_id": tx.user_id,
+            "kind": tx.kind,
+            "amount": tx.amount,
+            "timestamp": tx.timestamp,
+            "notes": tx.notes,
+        });
+
+        let request = self.client.post(&url).json(&body);
+        let request = if !token.is_empty() {
+            request.header("Authorization", token)
+        } else {
+            request
+        };
+
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to create liability transaction: {}", e)))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| AppError::Internal(format!("Failed to parse liability transaction: {}", e)))
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(AppError::Internal(format!("Failed to create liability transaction: {} - {}", status, body)))
+        }
+    }
+
+    // ==================== Asset Price Operations ====================
+
+    /// Delete asset price by symbol
+    pub async fn delete_asset_price(&self, symbol: &str) -> Result<(), AppError> {
+        let token = self.get_token().await;
+        
+        // 1. Find the record ID
+        let find_url = format!(
+            "{}/api/collections/asset_prices/records?filter=(symbol='{}')",
+            self.pocketbase_url,
+            symbol
+        );
+        
+        let request = self.client.get(&find_url);
+        let request = if !token.is_empty() {
+            request.header("Authorization", &token)
+        } else {
+            request
+        };
+        
+        let response = request.send().await
+            .map_err(|e| AppError::Internal(format!("Failed to find asset price: {}", e)))?;
+            
+        if !response.status().is_success() {
+             return Ok(()); // Ignore if not found or error
+        }
+        
         #[derive(Deserialize)]
         struct PriceRecord { id: String }
         
@@ -883,6 +1943,9 @@ impl PocketBaseClient {
                 priority: Some(priority),
                 enabled: None,
                 timeout_ms: None,
+                use_for_consensus: None,
+                cache_ttl_seconds: None,
+                proxy_url: None,
             };
             self.update_provider(provider_id, req).await?;
         }
@@ -919,6 +1982,53 @@ impl PocketBaseClient {
         });
     }
 
+    /// Log a quarantined price incident (fire-and-forget)
+    pub fn log_price_incident(&self, incident: crate::services::price_service::PriceIncident) {
+        let url = format!("{}/api/collections/price_incidents/records", self.pocketbase_url);
+        let client = self.client.clone();
+        let me = self.clone();
+        
+        tokio::spawn(async move {
+            let token = me.get_token().await;
+            
+            let request = client.post(&url).json(&incident);
+            let request = if !token.is_empty() {
+                request.header("Authorization", token)
+            } else {
+                request
+            };
+            
+            match request.send().await {
+                Ok(resp) => {
+                    if !resp.status().is_success() {
+                        tracing::warn!("⚠️ Failed to log price incident: {}", resp.status());
+                    }
+                }
+                Err(e) => tracing::warn!("⚠️ Could not log price incident: {}", e),
+            }
+        });
+    }
+
+    /// Append a fetched price to asset_price_history (fire-and-forget)
+    pub fn record_price_point(&self, point: crate::models::PricePoint) {
+        let url = format!("{}/api/collections/{}/records", self.pocketbase_url, crate::models::PRICE_HISTORY_COLLECTION);
+        let client = self.client.clone();
+        let me = self.clone();
+
+        tokio::spawn(async move {
+            let token = me.get_token().await;
+            let request = client.post(&url).json(&point);
+            let request = if !token.is_empty() { request.header("Authorization", token) } else { request };
+            match request.send().await {
+                Ok(resp) if !resp.status().is_success() => {
+                    tracing::warn!("⚠️ Failed to record price history for {}: {}", point.symbol, resp.status());
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("⚠️ Could not record price history for {}: {}", point.symbol, e),
+            }
+        });
+    }
+
     /// Get recent API call logs (with pagination)
     pub async fn get_api_logs(&self, page: u32, per_page: u32) -> Result<(Vec<crate::models::ApiCallLog>, u32), AppError> {
         let token = self.get_token().await;
@@ -0,0 +1,204 @@
+//! OHLC candles built from the prices recorded in asset_price_history.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::models::{AssetType, Market, PricePoint, PRICE_HISTORY_COLLECTION};
+use crate::services::PocketBaseClient;
+
+/// Most candles one request may return
+const MAX_CANDLES: i64 = 2000;
+
+/// Recorded prices older than this are too stale to value a holding as of a date
+const AS_OF_LOOKBACK_DAYS: i64 = 31;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum CandleInterval {
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[default]
+    #[serde(rename = "1d")]
+    Day,
+    /// Weeks start on Monday
+    #[serde(rename = "1w")]
+    Week,
+}
+
+impl CandleInterval {
+    fn duration(&self) -> Duration {
+        match self {
+            CandleInterval::Hour => Duration::hours(1),
+            CandleInterval::FourHours => Duration::hours(4),
+            CandleInterval::Day => Duration::days(1),
+            CandleInterval::Week => Duration::weeks(1),
+        }
+    }
+
+    /// Range served when the request gives no `from`
+    pub fn default_span(&self) -> Duration {
+        match self {
+            CandleInterval::Hour => Duration::days(7),
+            CandleInterval::FourHours => Duration::days(30),
+            CandleInterval::Day => Duration::days(365),
+            CandleInterval::Week => Duration::weeks(260),
+        }
+    }
+
+    /// Start of the candle containing `at`
+    fn bucket_start(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let day = at.date_naive();
+        let midnight = |d: NaiveDate| d.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+        match self {
+            CandleInterval::Week => midnight(day - Duration::days(day.weekday().num_days_from_monday() as i64)),
+            CandleInterval::Day => midnight(day),
+            interval => {
+                let step = interval.duration().num_seconds();
+                let seconds = at.timestamp();
+                DateTime::from_timestamp(seconds - seconds.rem_euclid(step), 0).unwrap_or(at)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Candle {
+    /// Start of the interval
+    pub time: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Recorded prices the candle was built from
+    pub points: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CandleSeries {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    pub interval: CandleInterval,
+    /// Currency of the latest point; points in another currency are left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub candles: Vec<Candle>,
+}
+
+/// Group time-ordered points into candles; intervals without points are skipped
+pub fn build_candles(points: &[(DateTime<Utc>, f64)], interval: CandleInterval) -> Vec<Candle> {
+    let mut candles: Vec<Candle> = Vec::new();
+    for &(at, price) in points {
+        let start = interval.bucket_start(at);
+        match candles.last_mut() {
+            Some(candle) if candle.time == start => {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+                candle.points += 1;
+            }
+            _ => candles.push(Candle { time: start, open: price, high: price, low: price, close: price, points: 1 }),
+        }
+    }
+    candles
+}
+
+/// PocketBase date filter literal
+fn pb_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S%.3fZ").to_string()
+}
+
+/// Reject ranges that are reversed or would produce more than MAX_CANDLES candles
+pub fn check_range(interval: CandleInterval, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<(), AppError> {
+    if from > to {
+        return Err(AppError::BadRequest("from must not be after to".to_string()));
+    }
+    if (to - from).num_seconds() / interval.duration().num_seconds() > MAX_CANDLES {
+        return Err(AppError::BadRequest(format!(
+            "Range too long for this interval (at most {} candles); use a larger interval or a shorter range",
+            MAX_CANDLES
+        )));
+    }
+    Ok(())
+}
+
+/// Candles of one symbol between `from` and `to` (inclusive)
+pub async fn load_candles(
+    db: &PocketBaseClient,
+    symbol: &str,
+    asset_type: &AssetType,
+    market: Option<&Market>,
+    interval: CandleInterval,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<CandleSeries, AppError> {
+    check_range(interval, from, to)?;
+    let (currency, points) = load_points(db, symbol, asset_type, market, from, to).await?;
+    Ok(CandleSeries {
+        symbol: symbol.to_uppercase(),
+        asset_type: asset_type.clone(),
+        market: market.cloned(),
+        interval,
+        currency,
+        from,
+        to,
+        candles: build_candles(&points, interval),
+    })
+}
+
+/// Recorded prices of one symbol between `from` and `to` (inclusive), oldest first, with the
+/// currency of the latest point; points in another currency are left out
+pub async fn load_points(
+    db: &PocketBaseClient,
+    symbol: &str,
+    asset_type: &AssetType,
+    market: Option<&Market>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<(Option<String>, Vec<(DateTime<Utc>, f64)>), AppError> {
+    let mut filter = format!(
+        "symbol='{}' && asset_type='{}' && recorded_at >= '{}' && recorded_at <= '{}'",
+        symbol.to_uppercase(), asset_type, pb_time(from), pb_time(to)
+    );
+    if let Some(market) = market {
+        // Older points were stored without a market
+        filter.push_str(&format!(" && (market='' || market~'{}')", market.to_string().to_lowercase()));
+    }
+    let records: Vec<PricePoint> = db.list_all_records(PRICE_HISTORY_COLLECTION, Some(filter)).await?;
+
+    let mut points: Vec<(DateTime<Utc>, f64, Option<String>)> = records.into_iter()
+        .filter_map(|p| Some((p.recorded_at?, p.price, p.currency)))
+        .filter(|(_, price, _)| *price > 0.0)
+        .collect();
+    points.sort_by_key(|(at, _, _)| *at);
+
+    let currency = points.last().and_then(|(_, _, c)| c.clone());
+    let same_currency: Vec<(DateTime<Utc>, f64)> = points.into_iter()
+        .filter(|(_, _, c)| c.is_none() || currency.is_none() || *c == currency)
+        .map(|(at, price, _)| (at, price))
+        .collect();
+    Ok((currency, same_currency))
+}
+
+/// Last recorded price of a symbol at or before `at`, if one was recorded in the month before
+pub async fn price_as_of(
+    db: &PocketBaseClient,
+    symbol: &str,
+    asset_type: &AssetType,
+    market: Option<&Market>,
+    at: DateTime<Utc>,
+) -> Result<Option<PricePoint>, AppError> {
+    let mut filter = format!(
+        "symbol='{}' && asset_type='{}' && recorded_at >= '{}' && recorded_at <= '{}' && price > 0",
+        symbol.to_uppercase(), asset_type, pb_time(at - Duration::days(AS_OF_LOOKBACK_DAYS)), pb_time(at)
+    );
+    if let Some(market) = market {
+        filter.push_str(&format!(" && (market='' || market~'{}')", market.to_string().to_lowercase()));
+    }
+    let records: Vec<PricePoint> = db.list_records(PRICE_HISTORY_COLLECTION, Some(filter), "-recorded_at").await?;
+    Ok(records.into_iter().next())
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::RwLock;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::rate_limiter::RateLimitInfo;
+use crate::services::{PriceService, SymbolHeat};
+
+/// Finished refresh jobs are kept this long so clients can poll their result
+const JOB_RETENTION_SECONDS: i64 = 3600;
+
+/// A symbol whose price the refresher keeps warm
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeldSymbol {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+}
+
+impl HeldSymbol {
+    fn key(&self) -> String {
+        let market = self.market.as_ref().map(|m| m.to_string()).unwrap_or_default();
+        format!("{}:{}:{}", self.asset_type, market, self.symbol.to_uppercase())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshStatus {
+    Running,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RefreshFailure {
+    pub symbol: String,
+    pub error: String,
+    /// Set when the provider was rate limited, so clients can say when to retry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitInfo>,
+}
+
+/// Handle for an explicit "refresh now" request
+#[derive(Debug, Clone, Serialize)]
+pub struct RefreshJob {
+    pub id: String,
+    #[serde(skip)]
+    pub user_id: String,
+    pub status: RefreshStatus,
+    pub total: usize,
+    pub refreshed: usize,
+    pub failed: Vec<RefreshFailure>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Keeps the prices of held symbols warm in the provider cache so request handlers
+/// can read cached prices instead of calling providers inline
+#[derive(Clone)]
+pub struct PriceRefresher {
+    price_service: PriceService,
+    symbol_heat: SymbolHeat,
+    interval_seconds: u64,
+    held: Arc<RwLock<HashMap<String, HeldSymbol>>>,
+    jobs: Arc<RwLock<HashMap<String, RefreshJob>>>,
+}
+
+impl PriceRefresher {
+    pub fn new(config: &Config, price_service: PriceService, symbol_heat: SymbolHeat) -> Self {
+        Self {
+            price_service,
+            symbol_heat,
+            interval_seconds: config.price_refresh_interval_seconds.max(10),
+            held: Arc::new(RwLock::new(HashMap::new())),
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Register symbols seen in a portfolio load. Symbols without any cached price are
+    /// fetched right away in the background so the next load has them.
+    pub async fn track(&self, symbols: Vec<HeldSymbol>, missing: Vec<HeldSymbol>) {
+        {
+            let mut held = self.held.write().await;
+            for symbol in symbols {
+                held.entry(symbol.key()).or_insert(symbol);
+            }
+        }
+        if !missing.is_empty() {
+            let refresher = self.clone();
+            tokio::spawn(async move {
+                tracing::info!("🔥 Warming {} uncached prices", missing.len());
+                for symbol in missing {
+                    refresher.warm(&symbol).await;
+                }
+            });
+        }
+    }
+
+    /// Start the background refresh loop
+    pub fn start(&self) {
+        let refresher = self.clone();
+        tokio::spawn(async move {
+            tracing::info!("🔄 Price refresher started (every {}s)", refresher.interval_seconds);
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(refresher.interval_seconds));
+            loop {
+                interval.tick().await;
+                refresher.refresh_held().await;
+                refresher.prune_jobs().await;
+            }
+        });
+    }
+
+    /// One pass over all held symbols, hottest first. `get_price` only calls a provider
+    /// once a symbol's cached price has expired, so warm symbols (including those kept fresh
+    /// by a ticker feed) cost nothing. Symbols with
+    /// their own refresh interval are force-refreshed on that schedule and skipped in between.
+    async fn refresh_held(&self) {
+        let held: Vec<HeldSymbol> = self.held.read().await.values().cloned().collect();
+        if held.is_empty() {
+            return;
+        }
+        let streamed: Vec<(Market, String)> = held.iter()
+            .filter(|h| h.asset_type == AssetType::Crypto)
+            .filter_map(|h| Some((h.market.clone()?, h.symbol.to_uppercase())))
+            .collect();
+        self.price_service.stream_held(&streamed);
+        let mut ordered = Vec::with_capacity(held.len());
+        for symbol in held {
+            let heat_key = SymbolHeat::key(&symbol.symbol, &symbol.asset_type.to_string());
+            ordered.push((self.symbol_heat.score(&heat_key).await, heat_key, symbol));
+        }
+        ordered.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut failed = 0;
+        let mut not_due = 0;
+        for (_, heat_key, symbol) in &ordered {
+            let ok = if self.symbol_heat.refresh_override(heat_key).await.is_none() {
+                self.warm(symbol).await
+            } else if self.symbol_heat.should_refresh(heat_key).await {
+                self.force_refresh(symbol).await
+            } else {
+                not_due += 1;
+                continue;
+            };
+            if !ok {
+                failed += 1;
+            }
+        }
+        tracing::debug!("🔄 Refreshed {} held symbols ({} failed, {} not due)", ordered.len() - not_due, failed, not_due);
+    }
+
+    async fn force_refresh(&self, symbol: &HeldSymbol) -> bool {
+        match self.price_service.refresh_price(&symbol.symbol, &symbol.asset_type, symbol.market.as_ref()).await {
+            Ok(_) => {
+                self.symbol_heat.mark_refreshed(&symbol.symbol, &symbol.asset_type.to_string()).await;
+                true
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Scheduled refresh failed for {}: {}", symbol.symbol, e);
+                false
+            }
+        }
+    }
+
+    async fn warm(&self, symbol: &HeldSymbol) -> bool {
+        match self.price_service.get_price(&symbol.symbol, &symbol.asset_type, symbol.market.as_ref()).await {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::warn!("⚠️ Background refresh failed for {}: {}", symbol.symbol, e);
+                false
+            }
+        }
+    }
+
+    /// Force-fetch the given symbols in the background; poll the returned job for progress
+    pub async fn refresh_now(&self, user_id: &str, symbols: Vec<HeldSymbol>) -> RefreshJob {
+        let job = RefreshJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            status: RefreshStatus::Running,
+            total: symbols.len(),
+            refreshed: 0,
+            failed: Vec::new(),
+            created_at: Utc::now(),
+            finished_at: None,
+        };
+        self.jobs.write().await.insert(job.id.clone(), job.clone());
+        self.track(symbols.clone(), Vec::new()).await;
+
+        let refresher = self.clone();
+        let job_id = job.id.clone();
+        tokio::spawn(async move {
+            for symbol in symbols {
+                let result = refresher.price_service
+                    .refresh_price(&symbol.symbol, &symbol.asset_type, symbol.market.as_ref())
+                    .await;
+                let mut jobs = refresher.jobs.write().await;
+                let Some(job) = jobs.get_mut(&job_id) else { return };
+                match result {
+                    Ok(_) => job.refreshed += 1,
+                    Err(e) => job.failed.push(RefreshFailure {
+                        symbol: symbol.symbol.clone(),
+                        error: e.to_string(),
+                        rate_limit: match e {
+                            AppError::RateLimited(info) => Some(info),
+                            _ => None,
+                        },
+                    }),
+                }
+            }
+            if let Some(job) = refresher.jobs.write().await.get_mut(&job_id) {
+                job.status = RefreshStatus::Completed;
+                job.finished_at = Some(Utc::now());
+                tracing::info!("✅ Refresh job {} done: {}/{} refreshed", job.id, job.refreshed, job.total);
+            }
+        });
+
+        job
+    }
+
+    /// A refresh job, only visible to the user who started it
+    pub async fn get_job(&self, user_id: &str, job_id: &str) -> Option<RefreshJob> {
+        self.jobs.read().await
+            .get(job_id)
+            .filter(|job| job.user_id == user_id)
+            .cloned()
+    }
+
+    async fn prune_jobs(&self) {
+        let cutoff = Utc::now() - chrono::Duration::seconds(JOB_RETENTION_SECONDS);
+        self.jobs.write().await.retain(|_, job| job.finished_at.is_none_or(|at| at > cutoff));
+    }
+}
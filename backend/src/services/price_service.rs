@@ -1,14 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::RwLock;
+use futures_util::{stream, StreamExt};
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tracing::Instrument;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use crate::config::Config;
 use crate::error::AppError;
-use crate::models::{AssetType, Market, CreateApiCallLogRequest};
+use crate::models::{AssetType, ExchangeBalance, ExchangeCredentials, Market, PricePoint};
 use crate::services::rate_limiter::RateLimiter;
 use crate::services::pocketbase::PocketBaseClient;
+use crate::services::provider_cache::{EndpointClass, ProviderCache};
+use crate::services::ticker_feeds::{FeedStatus, StreamTick, TickerFeeds};
+use crate::services::providers::{self, goldapi, mock, thai_gold, yahoo, PriceProvider, PriceProviders, ProviderClient};
+use crate::services::providers::binance::AccountTrade;
+use crate::services::providers::thai_gold::ThaiGoldQuote;
 
 /// Cached price entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,1251 +23,1033 @@ pub struct PriceEntry {
     pub price: f64,
     pub currency: String,
     pub updated_at: DateTime<Utc>,
+    /// Provider that produced the price, e.g. "coingecko"; "mock" for static fallbacks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
-/// Historical price entry
+/// A price together with how it was obtained
+#[derive(Debug, Clone)]
+pub struct PriceLookup {
+    pub entry: PriceEntry,
+    /// Served from the provider cache without calling a provider
+    pub cache_hit: bool,
+    /// The entry is older than its cache TTL (last known price)
+    pub stale: bool,
+}
+
+/// A fetched price rejected because it deviated too far from the last cached value
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HistoryEntry {
-    pub date: String,
+pub struct PriceIncident {
+    pub cache_key: String,
+    pub symbol: String,
+    pub previous_price: f64,
+    pub rejected_price: f64,
+    pub currency: String,
+    pub deviation_percent: f64,
+    pub detected_at: DateTime<Utc>,
+    /// Provider of the rejected price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Fetches in a row that returned (about) the rejected price
+    #[serde(default)]
+    pub consistent_fetches: u32,
+    /// Accepted without an admin after PRICE_QUARANTINE_AUTO_ACCEPT consistent fetches
+    #[serde(default)]
+    pub auto_accepted: bool,
+}
+
+/// Price quoted by a single provider during a consensus fetch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderQuote {
+    pub provider_type: String,
     pub price: f64,
+    pub currency: String,
+}
+
+/// Result of fetching the same symbol from several providers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsensusPrice {
+    pub entry: PriceEntry,
+    pub quotes: Vec<ProviderQuote>,
+    /// Providers whose quote was within the allowed divergence of the median
+    pub agreeing: usize,
+    /// Largest deviation of any quote from the median, in percent
+    pub divergence_percent: f64,
+    pub diverged: bool,
+}
+
+/// One symbol asked of [`PriceService::get_prices_batch`]
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+    /// Call the provider even if a fresh price is cached
+    pub force: bool,
+}
+
+/// Prices from [`PriceService::get_prices_batch`], in request order
+pub struct PriceBatch {
+    pub results: Vec<Result<PriceEntry, AppError>>,
+    /// Multi-symbol provider requests made
+    pub batch_requests: usize,
+    /// Symbols priced by those requests
+    pub batched_symbols: usize,
 }
 
+/// Requests in a batch that one provider can answer together
+struct BatchGroup<'a> {
+    provider: &'a Arc<dyn PriceProvider>,
+    asset_type: &'a AssetType,
+    market: Option<&'a Market>,
+    indexes: Vec<usize>,
+}
 
-/// Response from api.chnwt.dev/thai-gold-api/latest
-#[derive(Debug, Clone, Deserialize)]
-#[allow(dead_code)]
-struct ThaiGoldResponse {
-    status: String,
-    response: ThaiGoldResponseData,
+/// Provider and fetch time of a price record in asset_prices. Records saved before the
+/// provider was stored are attributed to "stored".
+pub fn stored_price_source(record: &serde_json::Value) -> (String, Option<DateTime<Utc>>) {
+    let source = record.get("source")
+        .and_then(|s| s.as_str())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("stored");
+    let fetched_at = ["last_updated", "updated"].iter()
+        .find_map(|key| record.get(*key)?.as_str()?.trim().parse::<DateTime<Utc>>().ok());
+    (source.to_string(), fetched_at)
 }
 
-#[derive(Debug, Clone, Deserialize)]
-#[allow(dead_code)]
-struct ThaiGoldResponseData {
-    date: String,
-    update_time: String,
-    price: ThaiGoldPriceData,
+/// Currency code quotes are compared in; USDT counts as USD
+pub fn quote_currency(currency: &str) -> String {
+    if currency.eq_ignore_ascii_case("USDT") {
+        "USD".to_string()
+    } else {
+        currency.to_uppercase()
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
-#[allow(dead_code)]
-struct ThaiGoldPriceData {
-    gold: ThaiGoldBuySell,
-    gold_bar: ThaiGoldBuySell,
+/// A price that was just fetched and cached, pushed to /ws/prices subscribers
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceUpdate {
+    pub asset_type: AssetType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    #[serde(flatten)]
+    pub entry: PriceEntry,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-#[allow(dead_code)]
-struct ThaiGoldBuySell {
-    buy: String,
-    sell: String,
+// Updates a slow subscriber may fall behind by before it skips ahead
+const PRICE_UPDATE_BUFFER: usize = 256;
+
+// Sessions averaged for a symbol's average daily volume
+const AVERAGE_VOLUME_SESSIONS: usize = 20;
+
+// Streamed ticks waiting to be cached before the feeds wait for room
+const TICK_BUFFER: usize = 1024;
+
+/// Historical price entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub date: String,
+    pub price: f64,
 }
 
 /// Price service for fetching prices from external APIs with caching
 #[derive(Clone)]
 pub struct PriceService {
-    client: reqwest::Client,
+    providers: ProviderClient,
+    registry: PriceProviders,
     config: Config,
-    cache: Arc<RwLock<HashMap<String, PriceEntry>>>,
-    rate_limiter: Option<RateLimiter>,
+    provider_cache: ProviderCache,
+    // Anomalous prices held back from the cache, keyed by cache key
+    quarantine: Arc<RwLock<HashMap<String, PriceIncident>>>,
+    // Final prices of delisted symbols, keyed by "asset_type:SYMBOL"; never refreshed
+    frozen: Arc<RwLock<HashMap<String, PriceEntry>>>,
     pb_client: Option<PocketBaseClient>,
+    updates: broadcast::Sender<PriceUpdate>,
+    // When each cache key last went into asset_price_history
+    history_recorded: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+    ticker_feeds: TickerFeeds,
 }
 
 impl PriceService {
     pub fn new(config: Config) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            providers: ProviderClient::new(config.clone()),
+            registry: PriceProviders::default(),
             config,
-            cache: Arc::new(RwLock::new(HashMap::new())),
-            rate_limiter: None,
+            provider_cache: ProviderCache::new(),
+            quarantine: Arc::new(RwLock::new(HashMap::new())),
+            frozen: Arc::new(RwLock::new(HashMap::new())),
             pb_client: None,
+            updates: broadcast::channel(PRICE_UPDATE_BUFFER).0,
+            history_recorded: Arc::new(RwLock::new(HashMap::new())),
+            ticker_feeds: TickerFeeds::default(),
         }
     }
     
     /// Create PriceService with rate limiter
     pub fn with_rate_limiter(config: Config, rate_limiter: RateLimiter) -> Self {
-        Self {
-            client: reqwest::Client::new(),
-            config,
-            cache: Arc::new(RwLock::new(HashMap::new())),
-            rate_limiter: Some(rate_limiter),
-            pb_client: None,
-        }
+        let mut service = Self::new(config);
+        service.set_rate_limiter(rate_limiter);
+        service
     }
     
     /// Set PocketBase client for provider configuration and logging
     pub fn set_pb_client(&mut self, pb_client: PocketBaseClient) {
+        self.providers.pb_client = Some(pb_client.clone());
         self.pb_client = Some(pb_client);
     }
     
-    /// Set rate limiter after creation
-    pub fn set_rate_limiter(&mut self, rate_limiter: RateLimiter) {
-        self.rate_limiter = Some(rate_limiter);
+    /// Receive every price cached from here on, whether fetched on demand, by the refresher or by a job
+    pub fn subscribe(&self) -> broadcast::Receiver<PriceUpdate> {
+        self.updates.subscribe()
     }
-    
-    /// Log an API call to PocketBase (fire-and-forget)
-    fn log_api_call_async(
-        &self, 
-        provider_type: &str, 
-        market_id: Option<&str>,
-        symbol: &str,
-        status: &str,
-        response_time_ms: u64,
-        price: Option<f64>,
-        currency: Option<&str>,
-        error_message: Option<&str>,
-        request_url: Option<&str>,
-    ) {
-        if let Some(ref pb_client) = self.pb_client {
-            let log = CreateApiCallLogRequest {
-                provider_type: provider_type.to_string(),
-                market_id: market_id.map(|s| s.to_string()),
-                symbol: symbol.to_string(),
-                status: status.to_string(),
-                response_time_ms,
-                price,
-                currency: currency.map(|s| s.to_string()),
-                error_message: error_message.map(|s| s.to_string()),
-                request_url: request_url.map(|s| s.to_string()),
-            };
-            pb_client.log_api_call(log);
-        }
+
+    fn publish(&self, asset_type: &AssetType, market: Option<&Market>, entry: &PriceEntry) {
+        // Fails only when nobody is subscribed
+        let _ = self.updates.send(PriceUpdate {
+            asset_type: asset_type.clone(),
+            market: market.cloned(),
+            entry: entry.clone(),
+        });
     }
-    
-    /// Check rate limit before making API call
-    async fn check_rate_limit(&self, api_name: &str) -> Result<(), AppError> {
-        if let Some(ref limiter) = self.rate_limiter {
-            if !limiter.can_request(api_name).await {
-                return Err(AppError::ExternalApiError(format!(
-                    "Rate limit exceeded for {}. Please wait before retrying.",
-                    api_name
-                )));
-            }
+
+    /// Open the exchange ticker feeds (CRYPTO_PRICE_STREAMS). Streamed prices are cached like
+    /// fetched ones, so crypto lookups only reach the REST tickers while a feed is down.
+    pub fn start_ticker_feeds(&self) {
+        if !self.config.crypto_price_streams {
+            return;
         }
-        Ok(())
+        let (ticks, mut received) = mpsc::channel(TICK_BUFFER);
+        self.ticker_feeds.start(ticks);
+        let service = self.clone();
+        tokio::spawn(async move {
+            while let Some(tick) = received.recv().await {
+                service.cache_stream_tick(tick).await;
+            }
+        });
+        tracing::info!("📡 Crypto ticker feeds enabled");
     }
-    
-    /// Record successful API call
-    async fn record_api_call(&self, api_name: &str) {
-        if let Some(ref limiter) = self.rate_limiter {
-            limiter.record_request(api_name).await;
 
+    /// Point the ticker feeds at the held crypto symbols, as (exchange, symbol)
+    pub fn stream_held(&self, held: &[(Market, String)]) {
+        if self.config.crypto_price_streams {
+            self.ticker_feeds.watch(held);
         }
     }
-    
-    /// Record rate limit hit (429 response)
-    async fn record_rate_limit_hit(&self, api_name: &str, retry_after: Option<u64>) {
-        if let Some(ref limiter) = self.rate_limiter {
-            limiter.record_rate_limit_hit(api_name, retry_after).await;
+
+    /// Connection state of each exchange feed (empty when streaming is off)
+    pub async fn ticker_feed_status(&self) -> Vec<FeedStatus> {
+        if !self.config.crypto_price_streams {
+            return Vec::new();
         }
+        self.ticker_feeds.status().await
     }
 
-    /// Get price for a symbol, using cache if available and not expired
-    pub async fn get_price(
-        &self, 
-        symbol: &str, 
-        asset_type: &AssetType,
-        market: Option<&Market>,
-    ) -> Result<PriceEntry, AppError> {
-        let market_key = market.map(|m| m.to_string()).unwrap_or_default();
-        let cache_key = format!("{}:{}:{}", asset_type, market_key, symbol.to_uppercase());
-        
-        // Check cache first
+    /// Ticks pass the same anomaly check as polled prices; a quarantined tick is not cached,
+    /// published or recorded
+    async fn cache_stream_tick(&self, tick: StreamTick) {
+        let symbol = tick.entry.symbol.clone();
+        self.accept_fetched(&symbol, &AssetType::Crypto, Some(&tick.market), tick.entry).await;
+    }
+
+    /// Keep a fresh price as a history point unless the symbol got one within PRICE_HISTORY_INTERVAL_SECONDS
+    async fn record_history(&self, cache_key: &str, asset_type: &AssetType, market: Option<&Market>, entry: &PriceEntry) {
+        let interval = self.config.price_history_interval_seconds as i64;
+        let Some(ref pb_client) = self.pb_client else { return };
+        if interval == 0 || *asset_type == AssetType::Cash {
+            return;
+        }
         {
-            let cache = self.cache.read().await;
-            if let Some(entry) = cache.get(&cache_key) {
-                let age = Utc::now().signed_duration_since(entry.updated_at);
-                if age.num_seconds() < self.config.price_cache_ttl_seconds as i64 {
-                    tracing::debug!("Cache hit for {}", cache_key);
-                    return Ok(entry.clone());
-                }
+            let mut recorded = self.history_recorded.write().await;
+            let due = recorded.get(cache_key)
+                .is_none_or(|last| entry.updated_at.signed_duration_since(*last).num_seconds() >= interval);
+            if !due {
+                return;
             }
+            recorded.insert(cache_key.to_string(), entry.updated_at);
         }
+        pb_client.record_price_point(PricePoint {
+            symbol: entry.symbol.to_uppercase(),
+            asset_type: asset_type.to_string(),
+            market: market.map(|m| m.to_string().to_lowercase()),
+            price: entry.price,
+            currency: Some(entry.currency.clone()),
+            source: entry.source.clone(),
+            recorded_at: Some(entry.updated_at),
+        });
+    }
 
-        // Fetch fresh price based on asset type
-        let price_entry = match asset_type {
-            AssetType::Crypto => self.fetch_crypto_price(symbol, market).await?,
-            AssetType::Stock | AssetType::Tfex => self.fetch_thai_stock_price(symbol).await?,
-            AssetType::ForeignStock => self.fetch_foreign_stock_price(symbol, market).await?,
-            AssetType::Gold => self.fetch_gold_price(symbol).await?,
-            AssetType::Commodity => self.fetch_commodity_price(symbol).await?,
-        };
+    /// Shared response cache (also used by the exchange rate service)
+    pub fn provider_cache(&self) -> ProviderCache {
+        self.provider_cache.clone()
+    }
 
-        // Update cache
-        {
-            let mut cache = self.cache.write().await;
-            cache.insert(cache_key, price_entry.clone());
+    /// Reload per-provider cache TTLs and proxies from api_providers
+    pub async fn refresh_provider_settings(&self) {
+        if let Some(ref pb_client) = self.pb_client {
+            match pb_client.list_all_providers().await {
+                Ok(providers) => {
+                    self.provider_cache.load_ttls(&providers).await;
+                    self.providers.load_proxies(&providers).await;
+                }
+                Err(e) => tracing::warn!("Failed to load provider settings: {}", e),
+            }
         }
-
-        Ok(price_entry)
     }
 
-    /// Get prices for multiple symbols
-    pub async fn get_prices_batch(
-        &self, 
-        symbols: &[(String, AssetType, Option<Market>)]
-    ) -> HashMap<String, Result<PriceEntry, String>> {
-        let mut results = HashMap::new();
-        
-        for (symbol, asset_type, market) in symbols {
-            let result = self.get_price(symbol, asset_type, market.as_ref()).await;
-            results.insert(
-                symbol.clone(),
-                result.map_err(|e| e.to_string()),
-            );
+    /// Freeze every delisted symbol that has a final price
+    pub async fn load_delisted(&self, symbols: &[crate::services::symbols::Symbol]) {
+        let mut frozen = HashMap::new();
+        for symbol in symbols.iter().filter(|s| s.delisted) {
+            let (Some(price), Some(currency), Ok(asset_type)) = (
+                symbol.delisted_price,
+                symbol.delisted_currency.clone(),
+                symbol.asset_type.parse::<AssetType>(),
+            ) else {
+                continue;
+            };
+            frozen.insert(Self::frozen_key(&symbol.symbol, &asset_type), PriceEntry {
+                symbol: symbol.symbol.to_uppercase(),
+                price,
+                currency,
+                updated_at: symbol.delisted_at.unwrap_or_else(Utc::now),
+                source: Some("delisted".to_string()),
+            });
         }
-        
-        results
+        tracing::info!("🧊 Loaded {} delisted symbols with frozen prices", frozen.len());
+        *self.frozen.write().await = frozen;
     }
 
-    /// Get price history for a symbol
-    pub async fn get_price_history(
+    /// Serve `entry` for this symbol from now on instead of asking providers
+    pub async fn freeze_price(&self, asset_type: &AssetType, entry: PriceEntry) {
+        tracing::info!("🧊 Froze {} ({}) at {} {}", entry.symbol, asset_type, entry.price, entry.currency);
+        self.frozen.write().await.insert(Self::frozen_key(&entry.symbol, asset_type), entry);
+    }
+
+    pub async fn unfreeze_price(&self, symbol: &str, asset_type: &AssetType) {
+        self.frozen.write().await.remove(&Self::frozen_key(symbol, asset_type));
+    }
+
+    /// Final price of a delisted symbol
+    pub async fn frozen_price(&self, symbol: &str, asset_type: &AssetType) -> Option<PriceEntry> {
+        self.frozen.read().await.get(&Self::frozen_key(symbol, asset_type)).cloned()
+    }
+
+    fn frozen_key(symbol: &str, asset_type: &AssetType) -> String {
+        format!("{}:{}", asset_type, symbol.trim().to_uppercase())
+    }
+
+    /// Serve a provider_type with `provider` instead of the built-in one (or add a new one)
+    pub fn register_provider(&mut self, provider: Arc<dyn PriceProvider>) {
+        tracing::info!("🔌 Registered price provider {}", provider.provider_type());
+        self.registry.register(provider);
+    }
+
+    /// Fetch from a registered provider by `provider_type`
+    async fn fetch_with(
         &self,
+        provider_type: &str,
         symbol: &str,
         asset_type: &AssetType,
         market: Option<&Market>,
-        days: u32,
-    ) -> Result<Vec<HistoryEntry>, AppError> {
-        let history = match asset_type {
-            AssetType::Crypto => self.fetch_crypto_history(symbol, market, days).await?,
-            AssetType::Stock | AssetType::ForeignStock | AssetType::Gold | AssetType::Tfex | AssetType::Commodity => 
-                self.fetch_yahoo_history(symbol, asset_type, market, days).await?,
-        };
-        Ok(history)
+    ) -> Result<PriceEntry, AppError> {
+        let provider = self.registry.get(provider_type)
+            .ok_or_else(|| AppError::Internal(format!("Price provider {} is not registered", provider_type)))?;
+        provider.fetch_price(&self.providers, symbol, asset_type, market).await
     }
 
-    /// Fetch crypto history from Binance
-    async fn fetch_crypto_history(&self, symbol: &str, _market: Option<&Market>, days: u32) -> Result<Vec<HistoryEntry>, AppError> {
-        // Default to Binance Spot/Futures API
-        // Convert days to interval/limit
-        let interval = if days <= 1 { "5m" } else if days <= 7 { "1h" } else { "1d" };
-        let limit = if days <= 1 { 288 } else if days <= 7 { 168 } else { days };
-        
-        let symbol_upper = symbol.to_uppercase();
-        let pair = format!("{}USDT", symbol_upper); // Assuming USDT pairs for simplicity
-        
-        // Use Spot API by default
-        let url = format!(
-            "https://api.binance.com/api/v3/klines?symbol={}&interval={}&limit={}",
-            pair, interval, limit
-        );
-
-        let response = self.client.get(&url).send().await?;
-        if !response.status().is_success() {
-             return Err(AppError::ExternalApiError("Binance history failed".to_string()));
-        }
-
-        let data: Vec<serde_json::Value> = response.json().await?;
-        let mut history = Vec::new();
-
-        for item in data {
-            if let Some(arr) = item.as_array() {
-                if arr.len() > 4 {
-                    let timestamp = arr[0].as_u64().unwrap_or(0);
-                    let close_price: f64 = arr[4].as_str().unwrap_or("0").parse().unwrap_or(0.0);
-                    
-                    let date = DateTime::from_timestamp_millis(timestamp as i64)
-                        .unwrap_or_default()
-                        .format("%Y-%m-%d")
-                        .to_string();
-                        
-                    history.push(HistoryEntry { date, price: close_price });
-                }
-            }
-        }
-        
-        Ok(history)
+    /// Set rate limiter after creation
+    pub fn set_rate_limiter(&mut self, rate_limiter: RateLimiter) {
+        self.providers.rate_limiter = Some(rate_limiter);
     }
-
-    /// Fetch history from Yahoo Finance
-    async fn fetch_yahoo_history(
+    
+    /// Get price for a symbol, using cache if available and not expired
+    pub async fn get_price(
         &self, 
         symbol: &str, 
-        asset_type: &AssetType, 
-        _market: Option<&Market>, 
-        days: u32
-    ) -> Result<Vec<HistoryEntry>, AppError> {
-        // Determine Yahoo symbol
-        let y_symbol = match asset_type {
-            AssetType::Stock | AssetType::Tfex => format!("{}.BK", symbol.to_uppercase()),
-            AssetType::Gold => {
-                if symbol.to_uppercase() == "XAU" { "GC=F".to_string() } // Gold Futures
-                else { return Ok(vec![]) } // No history for local gold
-            }
-            AssetType::ForeignStock => symbol.to_uppercase(), // Usually direct symbol
-            AssetType::Commodity => {
-                // Map common commodity symbols
-               match symbol.to_uppercase().as_str() {
-                   "CL" => "CL=F".to_string(), // Crude Oil
-                   "NG" => "NG=F".to_string(), // Natural Gas
-                   _ => format!("{}=F", symbol.to_uppercase())
-               }
-            },
-             _ => symbol.to_uppercase()
-        };
+        asset_type: &AssetType,
+        market: Option<&Market>,
+    ) -> Result<PriceEntry, AppError> {
+        self.lookup_price(symbol, asset_type, market).await.map(|lookup| lookup.entry)
+    }
 
-        // Yahoo Chart API: https://query1.finance.yahoo.com/v8/finance/chart/AAPL?range=1mo&interval=1d
-        let range = if days <= 7 { "5d" } else if days <= 30 { "1mo" } else if days <= 90 { "3mo" } else if days <= 180 { "6mo" } else if days <= 365 { "1y" } else { "2y" };
-        let interval = "1d";
+    /// Like `get_price`, but also reports whether the cache served the request.
+    /// Runs inside a `price_fetch` span labelled with the symbol and the provider that was called.
+    pub async fn lookup_price(
+        &self,
+        symbol: &str,
+        asset_type: &AssetType,
+        market: Option<&Market>,
+    ) -> Result<PriceLookup, AppError> {
+        let span = tracing::info_span!(
+            "price_fetch",
+            symbol = %symbol.to_uppercase(),
+            asset_type = %asset_type,
+            market = market.map(|m| m.to_string()).unwrap_or_default(),
+            cache_hit = tracing::field::Empty,
+            provider = tracing::field::Empty,
+        );
+        self.lookup_price_in_span(symbol, asset_type, market, false).instrument(span).await
+    }
 
-        let url = format!(
-            "https://query1.finance.yahoo.com/v8/finance/chart/{}?range={}&interval={}",
-            y_symbol, range, interval
+    /// Fetch from the provider even if a fresh price is cached (explicit refreshes)
+    pub async fn refresh_price(
+        &self,
+        symbol: &str,
+        asset_type: &AssetType,
+        market: Option<&Market>,
+    ) -> Result<PriceEntry, AppError> {
+        let span = tracing::info_span!(
+            "price_refresh",
+            symbol = %symbol.to_uppercase(),
+            asset_type = %asset_type,
+            market = market.map(|m| m.to_string()).unwrap_or_default(),
+            cache_hit = tracing::field::Empty,
+            provider = tracing::field::Empty,
         );
+        self.lookup_price_in_span(symbol, asset_type, market, true)
+            .instrument(span)
+            .await
+            .map(|lookup| lookup.entry)
+    }
 
-        let response = self.client.get(&url).send().await?;
-         if !response.status().is_success() {
-             return Err(AppError::ExternalApiError("Yahoo history failed".to_string()));
+    /// Cached price only, never calling a provider; expired entries are returned marked stale
+    pub async fn cached_price(
+        &self,
+        symbol: &str,
+        asset_type: &AssetType,
+        market: Option<&Market>,
+    ) -> Option<PriceLookup> {
+        if let Some(entry) = self.frozen_price(symbol, asset_type).await {
+            return Some(PriceLookup { entry, cache_hit: true, stale: false });
         }
-
-        let data: serde_json::Value = response.json().await?;
-        
-        let mut history = Vec::new();
-        
-        if let Some(result) = data.get("chart").and_then(|c| c.get("result")).and_then(|r| r.as_array()).and_then(|a| a.first()) {
-             let timestamps = result.get("timestamp").and_then(|t| t.as_array());
-             let quotes = result.get("indicators").and_then(|i| i.get("quote")).and_then(|q| q.as_array()).and_then(|a| a.first()).and_then(|q| q.get("close")).and_then(|c| c.as_array());
-
-             if let (Some(ts), Some(qs)) = (timestamps, quotes) {
-                 for (t, q) in ts.iter().zip(qs.iter()) {
-                     if let (Some(timestamp), Some(price)) = (t.as_i64(), q.as_f64()) {
-                         let date = DateTime::from_timestamp(timestamp, 0)
-                            .unwrap_or_default()
-                            .format("%Y-%m-%d")
-                            .to_string();
-                         history.push(HistoryEntry { date, price });
-                     }
-                 }
-             }
+        let cache_key = Self::price_cache_key(symbol, asset_type, market);
+        let class = EndpointClass::for_asset_type(asset_type);
+        let cache_provider = Self::provider_market_id(asset_type);
+        if let Some(entry) = self.provider_cache.get::<PriceEntry>(cache_provider, class, &cache_key).await {
+            return Some(PriceLookup { entry, cache_hit: true, stale: false });
         }
-
-        Ok(history)
+        self.provider_cache
+            .get_stale::<PriceEntry>(cache_provider, class, &cache_key)
+            .await
+            .map(|entry| PriceLookup { entry, cache_hit: true, stale: true })
     }
 
-    /// Fetch cryptocurrency price - uses market-specific API when available
-    async fn fetch_crypto_price(&self, symbol: &str, market: Option<&Market>) -> Result<PriceEntry, AppError> {
-        tracing::debug!("fetch_crypto_price for {} with market: {:?}", symbol, market);
-        
-        if let Some(m) = market {
-            tracing::debug!("Checking market match for {:?}", m);
-            match m {
-                Market::Bitkub => {
-                    // Use Bitkub API for Bitkub market (THB pairs)
-                    tracing::info!("Using Bitkub API for {} (market: {:?})", symbol, m);
-                    match self.fetch_bitkub_price(symbol).await {
-                        Ok(entry) => {
-                            tracing::info!("Bitkub price for {}: {} {}", symbol, entry.price, entry.currency);
-                            return Ok(entry);
-                        }
-                        Err(e) => {
-                            tracing::warn!("Bitkub API failed for {}: {}, falling back to CoinGecko", symbol, e);
-                        }
-                    }
-                }
-                Market::Binance => {
-                    // Use Binance API for Binance market (USDT pairs)
-                    tracing::info!("Using Binance API for {} (market: {:?})", symbol, m);
-                    match self.fetch_binance_price(symbol).await {
-                        Ok(entry) => {
-                            tracing::info!("Binance price for {}: {} {}", symbol, entry.price, entry.currency);
-                            return Ok(entry);
-                        }
-                        Err(e) => {
-                            tracing::warn!("Binance Spot API failed for {}: {}, trying Futures...", symbol, e);
-                            // Try Futures API
-                            match self.fetch_binance_futures_price(symbol).await {
-                                Ok(entry) => {
-                                    tracing::info!("Binance Futures price for {}: {} {}", symbol, entry.price, entry.currency);
-                                    return Ok(entry);
-                                }
-                                Err(e2) => {
-                                    tracing::warn!("Binance Futures API failed for {}: {}, falling back to CoinGecko", symbol, e2);
-                                }
-                            }
-                        }
-                    }
-                }
-                Market::Okx => {
-                    // Use OKX API for OKX market (USDT pairs)
-                    tracing::info!("Using OKX API for {} (market: {:?})", symbol, m);
-                    match self.fetch_okx_price(symbol).await {
-                        Ok(entry) => {
-                            tracing::info!("OKX price for {}: {} {}", symbol, entry.price, entry.currency);
-                            return Ok(entry);
-                        }
-                        Err(e) => {
-                            tracing::warn!("OKX API failed for {}: {}, falling back to CoinGecko", symbol, e);
-                        }
-                    }
-                }
-                Market::Kucoin => {
-                    // Use KuCoin API for KuCoin market (USDT pairs)
-                    tracing::info!("Using KuCoin API for {} (market: {:?})", symbol, m);
-                    match self.fetch_kucoin_price(symbol).await {
-                        Ok(entry) => {
-                            tracing::info!("KuCoin price for {}: {} {}", symbol, entry.price, entry.currency);
-                            return Ok(entry);
-                        }
-                        Err(e) => {
-                            tracing::warn!("KuCoin API failed for {}: {}, falling back to CoinGecko", symbol, e);
-                        }
-                    }
-                }
-                Market::Htx => {
-                    // Use HTX (Huobi) API for HTX market (USDT pairs)
-                    tracing::info!("Using HTX API for {} (market: {:?})", symbol, m);
-                    match self.fetch_htx_price(symbol).await {
-                        Ok(entry) => {
-                            tracing::info!("HTX price for {}: {} {}", symbol, entry.price, entry.currency);
-                            return Ok(entry);
-                        }
-                        Err(e) => {
-                            tracing::warn!("HTX API failed for {}: {}, falling back to CoinGecko", symbol, e);
-                        }
-                    }
-                }
-                _ => {
-                    tracing::debug!("Market {:?} not using specific API", m);
-                }
+    /// Bring the cache in line with an asset_prices record changed in PocketBase: a price that
+    /// differs from the cached one replaces it (and goes out to /ws/prices), a deleted record
+    /// drops the cached price. Returns whether anything changed; the price job's own writes
+    /// match the cache and are ignored.
+    pub async fn apply_stored_price(&self, record: &serde_json::Value, deleted: bool) -> bool {
+        let field = |name: &str| record.get(name).and_then(|v| v.as_str()).map(str::trim).filter(|v| !v.is_empty());
+        let (Some(symbol), Some(asset_type)) = (field("symbol"), field("asset_type").and_then(|t| t.parse::<AssetType>().ok())) else {
+            return false;
+        };
+        let market = field("market").and_then(|m| m.parse::<Market>().ok());
+        let cache_key = Self::price_cache_key(symbol, &asset_type, market.as_ref());
+        let class = EndpointClass::for_asset_type(&asset_type);
+        let cache_provider = Self::provider_market_id(&asset_type);
+        let cached: Option<PriceEntry> = self.provider_cache.get_stale(cache_provider, class, &cache_key).await;
+
+        if deleted {
+            if cached.is_none() {
+                return false;
             }
+            self.provider_cache.remove(cache_provider, class, &cache_key).await;
+            return true;
         }
-        
-        // Default: CoinGecko API (returns THB)
-        tracing::debug!("Using CoinGecko API for {}", symbol);
-        self.fetch_coingecko_price(symbol).await
+        let Some(price) = record.get("price").and_then(|p| p.as_f64()).filter(|p| *p > 0.0) else {
+            return false;
+        };
+        if cached.as_ref().is_some_and(|entry| (entry.price - price).abs() < f64::EPSILON) {
+            return false;
+        }
+        let (source, fetched_at) = stored_price_source(record);
+        let entry = PriceEntry {
+            symbol: symbol.to_uppercase(),
+            price,
+            currency: field("currency").map(str::to_string)
+                .or_else(|| cached.map(|entry| entry.currency))
+                .unwrap_or_else(|| "THB".to_string()),
+            updated_at: fetched_at.unwrap_or_else(Utc::now),
+            source: Some(source),
+        };
+        self.provider_cache.put(cache_provider, class, &cache_key, &entry).await;
+        self.publish(&asset_type, market.as_ref(), &entry);
+        true
     }
 
-    /// Fetch price from Bitkub API (returns THB price)
-    async fn fetch_bitkub_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
-        // Check rate limit first
-        self.check_rate_limit("bitkub").await?;
-        
-        // Bitkub uses THB_BTC format
-        let pair = format!("THB_{}", symbol.to_uppercase());
-        let url = format!(
-            "https://api.bitkub.com/api/market/ticker?sym={}",
-            pair
-        );
-
-        tracing::info!("Fetching crypto price from Bitkub: {}", url);
-        
-        let start = Instant::now();
-
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        // Record the API call
-        self.record_api_call("bitkub").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
+    fn price_cache_key(symbol: &str, asset_type: &AssetType, market: Option<&Market>) -> String {
+        let market_key = market.map(|m| m.to_string()).unwrap_or_default();
+        format!("{}:{}:{}", asset_type, market_key, symbol.to_uppercase())
+    }
 
-        if response.status().as_u16() == 429 {
-            self.record_rate_limit_hit("bitkub", None).await;
-            self.log_api_call_async("bitkub", None, symbol, "error", elapsed_ms, None, None, Some("Rate limit exceeded"), Some(&url));
-            return Err(AppError::ExternalApiError("Bitkub rate limit exceeded".to_string()));
+    async fn lookup_price_in_span(
+        &self,
+        symbol: &str,
+        asset_type: &AssetType,
+        market: Option<&Market>,
+        force: bool,
+    ) -> Result<PriceLookup, AppError> {
+        // Delisted symbols have no live price; even explicit refreshes get the frozen one
+        if let Some(entry) = self.frozen_price(symbol, asset_type).await {
+            tracing::Span::current().record("cache_hit", true);
+            return Ok(PriceLookup { entry, cache_hit: true, stale: false });
         }
+        let cache_key = Self::price_cache_key(symbol, asset_type, market);
         
-        if !response.status().is_success() {
-            let error_msg = format!("Bitkub API error: {}", response.status());
-            self.log_api_call_async("bitkub", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-            return Err(AppError::ExternalApiError(error_msg));
+        // Prices are cached per market because the serving provider is only known after the fallback chain runs
+        let class = EndpointClass::for_asset_type(asset_type);
+        let cache_provider = Self::provider_market_id(asset_type);
+        let cached = if force {
+            None
+        } else {
+            self.provider_cache.get::<PriceEntry>(cache_provider, class, &cache_key).await
+        };
+        if let Some(entry) = cached {
+            tracing::debug!("Cache hit for {}", cache_key);
+            tracing::Span::current().record("cache_hit", true);
+            return Ok(PriceLookup { entry, cache_hit: true, stale: false });
         }
+        tracing::Span::current().record("cache_hit", false);
 
-        let data: serde_json::Value = response.json().await?;
-        
-        // Bitkub response format: { "THB_BTC": { "last": 2904027.00, ... } }
-        let thb_price = data
-            .get(&pair)
-            .and_then(|v| v.get("last"))
-            .and_then(|v| v.as_f64())
-            .ok_or_else(|| {
-                let error_msg = format!("Could not parse Bitkub price for {}", symbol);
-                self.log_api_call_async("bitkub", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                AppError::ExternalApiError(error_msg)
-            })?;
-
-        // Log successful API call
-        self.log_api_call_async("bitkub", None, symbol, "success", elapsed_ms, Some(thb_price), Some("THB"), None, Some(&url));
-
-        Ok(PriceEntry {
-            symbol: symbol.to_uppercase(),
-            price: thb_price,
-            currency: "THB".to_string(),
-            updated_at: Utc::now(),
-        })
+        // Fetch fresh price based on asset type
+        let price_entry = match asset_type {
+            AssetType::Crypto => self.fetch_crypto_price(symbol, market).await?,
+            AssetType::Stock | AssetType::Tfex => self.fetch_thai_stock_price(symbol).await?,
+            AssetType::ForeignStock => self.fetch_foreign_stock_price(symbol, market).await?,
+            AssetType::Gold => self.fetch_gold_price(symbol).await?,
+            AssetType::Commodity => self.fetch_commodity_price(symbol).await?,
+            // Cash balances are denominated in their own currency
+            AssetType::Cash => PriceEntry {
+                symbol: symbol.to_uppercase(),
+                price: 1.0,
+                currency: symbol.to_uppercase(),
+                updated_at: Utc::now(),
+                source: Some("identity".to_string()),
+            },
+        };
+
+        Ok(self.accept_fetched(symbol, asset_type, market, price_entry).await)
     }
 
-    /// Fetch price from Binance API (returns USDT price)
-    async fn fetch_binance_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
-        // Special case: Gold (XAU) and Silver (XAG) are only available on Binance Futures
-        // Redirect to futures API transparently
-        if symbol.eq_ignore_ascii_case("XAG") || symbol.eq_ignore_ascii_case("XAU") {
-            tracing::info!("Redirecting {} to Binance Futures (not available on Spot)", symbol);
-            return self.fetch_binance_futures_price(symbol).await;
+    /// Check a freshly fetched price against the last known one, then cache, publish and record it
+    async fn accept_fetched(
+        &self,
+        symbol: &str,
+        asset_type: &AssetType,
+        market: Option<&Market>,
+        price_entry: PriceEntry,
+    ) -> PriceLookup {
+        let cache_key = Self::price_cache_key(symbol, asset_type, market);
+        let class = EndpointClass::for_asset_type(asset_type);
+        let cache_provider = Self::provider_market_id(asset_type);
+        // Expired entries are kept as the anomaly baseline
+        let last_known: Option<PriceEntry> = self.provider_cache.get_stale(cache_provider, class, &cache_key).await;
+
+        // Sanity check against the last known price before trusting the new one
+        if let Some(previous) = last_known {
+            if let Some(mut incident) = self.detect_anomaly(&cache_key, &previous, &price_entry) {
+                // A move that every fetch confirms is genuine, not a bad quote
+                let mut quarantine = self.quarantine.write().await;
+                incident.consistent_fetches = quarantine.get(&cache_key)
+                    .filter(|earlier| self.confirms(earlier, &price_entry))
+                    .map_or(1, |earlier| earlier.consistent_fetches + 1);
+                let auto_accept = self.config.price_quarantine_auto_accept;
+                if auto_accept > 0 && incident.consistent_fetches >= auto_accept {
+                    incident.auto_accepted = true;
+                    quarantine.remove(&cache_key);
+                    drop(quarantine);
+                    tracing::info!(
+                        "✅ Accepted {} -> {} for {} after {} consistent fetches",
+                        previous.price, price_entry.price, cache_key, incident.consistent_fetches
+                    );
+                    if let Some(ref pb_client) = self.pb_client {
+                        pb_client.log_price_incident(incident);
+                    }
+                } else {
+                    tracing::warn!(
+                        "⚠️ Quarantined anomalous price for {}: {} -> {} ({:.1}% deviation)",
+                        cache_key, previous.price, price_entry.price, incident.deviation_percent
+                    );
+                    quarantine.insert(cache_key, incident.clone());
+                    if let Some(ref pb_client) = self.pb_client {
+                        pb_client.log_price_incident(incident);
+                    }
+                    // Keep serving the last known good price
+                    return PriceLookup { entry: previous, cache_hit: false, stale: true };
+                }
+            }
         }
 
-        // Check rate limit first
-        self.check_rate_limit("binance").await?;
-        
-        let symbol_upper = symbol.to_uppercase(); // Binance uses BTCUSDT format
-        let pair = format!("{}USDT", symbol_upper);
-        let url = format!(
-            "https://api.binance.com/api/v3/ticker/price?symbol={}",
-            pair
-        );
-
-        tracing::info!("Fetching crypto price from Binance: {}", url);
-        
-        let start = Instant::now();
+        // Update cache
+        self.provider_cache.put(cache_provider, class, &cache_key, &price_entry).await;
+        self.quarantine.write().await.remove(&cache_key);
+        self.publish(asset_type, market, &price_entry);
+        self.record_history(&cache_key, asset_type, market, &price_entry).await;
 
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        // Record the API call
-        self.record_api_call("binance").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
+        PriceLookup { entry: price_entry, cache_hit: false, stale: false }
+    }
 
-        if response.status().as_u16() == 429 {
-            self.record_rate_limit_hit("binance", None).await;
-            self.log_api_call_async("binance", None, symbol, "error", elapsed_ms, None, None, Some("Rate limit exceeded"), Some(&url));
-            return Err(AppError::ExternalApiError("Binance rate limit exceeded".to_string()));
+    /// Compare a freshly fetched price with the last cached one.
+    /// Returns an incident if the deviation exceeds PRICE_MAX_DEVIATION_PERCENT (0 disables the check).
+    /// Prices quoted in different currencies (e.g. after a provider fallback) aren't compared.
+    fn detect_anomaly(&self, cache_key: &str, previous: &PriceEntry, fresh: &PriceEntry) -> Option<PriceIncident> {
+        let max_deviation = self.config.price_max_deviation_percent;
+        if max_deviation <= 0.0 || previous.price <= 0.0 {
+            return None;
         }
-        
-        if !response.status().is_success() {
-            let error_msg = format!("Binance API error: {}", response.status());
-            self.log_api_call_async("binance", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-            return Err(AppError::ExternalApiError(error_msg));
+        if quote_currency(&previous.currency) != quote_currency(&fresh.currency) {
+            return None;
         }
 
-        let data: serde_json::Value = response.json().await?;
-        
-        // Binance response format: { "symbol": "BTCUSDT", "price": "94123.50" }
-        let usdt_price = data
-            .get("price")
-            .and_then(|v| v.as_str())
-            .and_then(|s| s.parse::<f64>().ok())
-            .ok_or_else(|| {
-                let error_msg = format!("Could not parse Binance price for {}", symbol);
-                self.log_api_call_async("binance", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                AppError::ExternalApiError(error_msg)
-            })?;
-
-        // Log successful API call
-        self.log_api_call_async("binance", None, symbol, "success", elapsed_ms, Some(usdt_price), Some("USDT"), None, Some(&url));
-
-        Ok(PriceEntry {
-            symbol: symbol.to_uppercase(),
-            price: usdt_price,
-            currency: "USDT".to_string(),
-            updated_at: Utc::now(),
-        })
-    }
-    
-    /// Fetch price from Binance Futures API (Perpetual contracts)
-    async fn fetch_binance_futures_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
-        // Check rate limit (use same binance limit)
-        self.check_rate_limit("binance").await?;
-        
-        // Binance Futures uses BTCUSDT format for perps
-        let symbol_upper = symbol.to_uppercase();
-        let pair = if symbol_upper.ends_with("USDT") {
-            symbol_upper.clone()
+        let deviation_percent = if fresh.price > 0.0 {
+            ((fresh.price - previous.price) / previous.price * 100.0).abs()
         } else {
-            format!("{}USDT", symbol_upper)
+            // Zero or negative prices are never plausible
+            100.0
         };
-        
-        let url = format!(
-            "https://fapi.binance.com/fapi/v1/ticker/price?symbol={}",
-            pair
-        );
-
-        tracing::info!("Fetching futures price from Binance Futures: {}", url);
-
-        let start = Instant::now();
 
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        // Record the API call
-        self.record_api_call("binance").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
-
-        if response.status().as_u16() == 429 {
-            self.record_rate_limit_hit("binance", None).await;
-            self.log_api_call_async("binance_futures", Some("FUTURES"), symbol, "error", elapsed_ms, None, None, Some("Rate limit exceeded"), Some(&url));
-            return Err(AppError::ExternalApiError("Binance Futures rate limit exceeded".to_string()));
+        if fresh.price > 0.0 && deviation_percent <= max_deviation {
+            return None;
         }
-        
-        if !response.status().is_success() {
-            let error_msg = format!("Binance Futures API error: {}", response.status());
-            self.log_api_call_async("binance_futures", Some("FUTURES"), symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-            return Err(AppError::ExternalApiError(error_msg));
-        }
-
-        let data: serde_json::Value = response.json().await?;
-        
-        // Binance Futures response: { "symbol": "XAGUSDT", "price": "76.9300", "time": 123... }
-        let usdt_price = data
-            .get("price")
-            .and_then(|v| v.as_str())
-            .and_then(|s| s.parse::<f64>().ok())
-            .ok_or_else(|| {
-                let error_msg = format!("Could not parse Binance Futures price for {}", symbol);
-                self.log_api_call_async("binance_futures", Some("FUTURES"), symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                AppError::ExternalApiError(error_msg)
-            })?;
-        
-        // Log successful API call
-        self.log_api_call_async("binance_futures", Some("FUTURES"), symbol, "success", elapsed_ms, Some(usdt_price), Some("USDT"), None, Some(&url));
 
-        tracing::info!("Binance Futures price for {}: {} USDT", symbol, usdt_price);
-
-        Ok(PriceEntry {
-            symbol: symbol.to_uppercase(),
-            price: usdt_price,
-            currency: "USDT".to_string(),
-            updated_at: Utc::now(),
+        Some(PriceIncident {
+            cache_key: cache_key.to_string(),
+            symbol: fresh.symbol.clone(),
+            previous_price: previous.price,
+            rejected_price: fresh.price,
+            currency: fresh.currency.clone(),
+            deviation_percent,
+            detected_at: Utc::now(),
+            source: fresh.source.clone(),
+            consistent_fetches: 1,
+            auto_accepted: false,
         })
     }
 
-    /// Fetch price from OKX API (returns USD price)
-    async fn fetch_okx_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
-        // Check rate limit first
-        self.check_rate_limit("okx").await?;
-        
-        // OKX uses BTC-USDT format
-        let inst_id = format!("{}-USDT", symbol.to_uppercase());
-        let url = format!(
-            "https://www.okx.com/api/v5/market/ticker?instId={}",
-            inst_id
-        );
+    /// Whether a fresh price repeats a quarantined one (same currency, within the deviation limit)
+    fn confirms(&self, incident: &PriceIncident, fresh: &PriceEntry) -> bool {
+        fresh.price > 0.0
+            && incident.rejected_price > 0.0
+            && quote_currency(&incident.currency) == quote_currency(&fresh.currency)
+            && ((fresh.price - incident.rejected_price) / incident.rejected_price * 100.0).abs()
+                <= self.config.price_max_deviation_percent
+    }
 
-        tracing::info!("Fetching crypto price from OKX: {}", url);
+    /// List prices currently held in quarantine
+    pub async fn get_quarantined(&self) -> Vec<PriceIncident> {
+        let quarantine = self.quarantine.read().await;
+        let mut incidents: Vec<PriceIncident> = quarantine.values().cloned().collect();
+        incidents.sort_by_key(|i| std::cmp::Reverse(i.detected_at));
+        incidents
+    }
 
-        let start = Instant::now();
+    /// Accept a quarantined price as genuine (e.g. after a split) and cache it
+    pub async fn release_quarantined(&self, cache_key: &str) -> Result<PriceEntry, AppError> {
+        let incident = self.quarantine.write().await.remove(cache_key)
+            .ok_or_else(|| AppError::NotFound(format!("No quarantined price for {}", cache_key)))?;
 
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        // Record the API call
-        self.record_api_call("okx").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
-
-        if response.status().as_u16() == 429 {
-            self.record_rate_limit_hit("okx", None).await;
-            self.log_api_call_async("okx", None, symbol, "error", elapsed_ms, None, None, Some("Rate limit exceeded"), Some(&url));
-            return Err(AppError::ExternalApiError("OKX rate limit exceeded".to_string()));
+        let entry = PriceEntry {
+            symbol: incident.symbol,
+            price: incident.rejected_price,
+            currency: incident.currency,
+            updated_at: Utc::now(),
+            source: incident.source,
+        };
+        // Cache keys are "asset_type:market:SYMBOL"
+        if let Some(asset_type) = cache_key.split(':').next().and_then(|t| serde_json::from_value::<AssetType>(serde_json::Value::String(t.to_string())).ok()) {
+            self.provider_cache
+                .put(Self::provider_market_id(&asset_type), EndpointClass::for_asset_type(&asset_type), cache_key, &entry)
+                .await;
+            let market = cache_key.split(':').nth(1).and_then(|m| m.parse::<Market>().ok());
+            self.publish(&asset_type, market.as_ref(), &entry);
         }
+        tracing::info!("✅ Released quarantined price for {}: {}", cache_key, entry.price);
 
-        if !response.status().is_success() {
-            let error_msg = format!("OKX API error: {}", response.status());
-            self.log_api_call_async("okx", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-            return Err(AppError::ExternalApiError(error_msg));
-        }
+        Ok(entry)
+    }
 
-        let data: serde_json::Value = response.json().await?;
-        
-        // OKX response format: { "code": "0", "data": [{ "last": "94123.5", ... }] }
-        if let Some(code) = data.get("code").and_then(|c| c.as_str()) {
-            if code != "0" {
-                let msg = data.get("msg").and_then(|m| m.as_str()).unwrap_or("Unknown error");
-                let error_msg = format!("OKX API error code {}: {}", code, msg);
-                self.log_api_call_async("okx", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                return Err(AppError::ExternalApiError(error_msg));
+    /// Fetch a price from every consensus-enabled provider of the market and take the median.
+    /// Falls back to `get_price` when fewer than two providers are configured or respond.
+    pub async fn get_consensus_price(
+        &self,
+        symbol: &str,
+        asset_type: &AssetType,
+        market: Option<&Market>,
+    ) -> Result<ConsensusPrice, AppError> {
+        let providers = match &self.pb_client {
+            Some(pb_client) => pb_client
+                .get_providers_by_market(Self::provider_market_id(asset_type))
+                .await
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        let mut quotes: Vec<ProviderQuote> = Vec::new();
+        for provider in providers.iter().filter(|p| p.enabled && p.use_for_consensus) {
+            match self.fetch_from_provider(&provider.provider_type, symbol, asset_type, market).await {
+                Some(Ok(entry)) if entry.price > 0.0 => quotes.push(ProviderQuote {
+                    provider_type: provider.provider_type.clone(),
+                    price: entry.price,
+                    currency: entry.currency,
+                }),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => tracing::warn!("⚠️ Consensus: {} failed for {}: {}", provider.provider_type, symbol, e),
+                None => tracing::debug!("Consensus: provider {} not supported for {}", provider.provider_type, asset_type),
             }
         }
-        let usd_price = data
-            .get("data")
-            .and_then(|d| d.as_array())
-            .and_then(|arr| arr.first())
-            .and_then(|item| item.get("last"))
-            .and_then(|v| v.as_str())
-            .and_then(|s| s.parse::<f64>().ok())
-            .ok_or_else(|| {
-                let error_msg = format!("Could not parse OKX price for {}", symbol);
-                self.log_api_call_async("okx", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                AppError::ExternalApiError(error_msg)
-            })?;
-
-        // Log successful API call
-        self.log_api_call_async("okx", None, symbol, "success", elapsed_ms, Some(usd_price), Some("USD"), None, Some(&url));
-
-        // Return USD price since OKX transactions are in USD
-        Ok(PriceEntry {
-            symbol: symbol.to_uppercase(),
-            price: usd_price,
-            currency: "USD".to_string(),
-            updated_at: Utc::now(),
-        })
-    }
 
-    /// Fetch price from KuCoin API (returns USDT price)
-    async fn fetch_kucoin_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
-        // Check rate limit first
-        self.check_rate_limit("kucoin").await?;
-        
-        // KuCoin uses BTC-USDT format
-        let pair = format!("{}-USDT", symbol.to_uppercase());
-        let url = format!(
-            "https://api.kucoin.com/api/v1/market/orderbook/level1?symbol={}",
-            pair
-        );
+        // Only quotes in the most common currency are comparable
+        let mut by_currency: HashMap<String, Vec<ProviderQuote>> = HashMap::new();
+        for quote in &quotes {
+            by_currency.entry(quote_currency(&quote.currency)).or_default().push(quote.clone());
+        }
+        let comparable = by_currency.into_values().max_by_key(|q| q.len()).unwrap_or_default();
+
+        if comparable.len() < 2 {
+            let entry = self.get_price(symbol, asset_type, market).await?;
+            return Ok(ConsensusPrice {
+                entry,
+                quotes,
+                agreeing: comparable.len(),
+                divergence_percent: 0.0,
+                diverged: false,
+            });
+        }
 
-        tracing::info!("Fetching crypto price from KuCoin: {}", url);
+        let median = Self::median(comparable.iter().map(|q| q.price).collect());
+        let max_divergence = self.config.price_consensus_max_divergence_percent;
+        let deviation = |price: f64| ((price - median) / median * 100.0).abs();
 
-        let start = Instant::now();
+        let divergence_percent = comparable.iter().map(|q| deviation(q.price)).fold(0.0, f64::max);
+        let agreeing: Vec<&ProviderQuote> = comparable.iter().filter(|q| deviation(q.price) <= max_divergence).collect();
+        let diverged = divergence_percent > max_divergence;
 
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        // Record the API call
-        self.record_api_call("kucoin").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
+        // Majority agrees: use the median of the agreeing quotes, otherwise the overall median
+        let price = if agreeing.len() * 2 > comparable.len() {
+            Self::median(agreeing.iter().map(|q| q.price).collect())
+        } else {
+            median
+        };
 
-        if response.status().as_u16() == 429 {
-            self.record_rate_limit_hit("kucoin", None).await;
-            self.log_api_call_async("kucoin", None, symbol, "error", elapsed_ms, None, None, Some("Rate limit exceeded"), Some(&url));
-            return Err(AppError::ExternalApiError("KuCoin rate limit exceeded".to_string()));
-        }
-        
-        if !response.status().is_success() {
-            let error_msg = format!("KuCoin API error: {}", response.status());
-            self.log_api_call_async("kucoin", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-            return Err(AppError::ExternalApiError(error_msg));
+        if diverged {
+            tracing::warn!(
+                "⚠️ Provider divergence for {}: {:.2}% (only {}/{} agree) - {:?}",
+                symbol, divergence_percent, agreeing.len(), comparable.len(),
+                comparable.iter().map(|q| format!("{}={}", q.provider_type, q.price)).collect::<Vec<_>>()
+            );
         }
 
-        let data: serde_json::Value = response.json().await?;
-        
-        // KuCoin response format: { "code": "200000", "data": { "price": "91136", ... } }
-        let usdt_price = data
-            .get("data")
-            .and_then(|d| d.get("price"))
-            .and_then(|v| v.as_str())
-            .and_then(|s| s.parse::<f64>().ok())
-            .ok_or_else(|| {
-                let error_msg = format!("Could not parse KuCoin price for {}", symbol);
-                self.log_api_call_async("kucoin", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                AppError::ExternalApiError(error_msg)
-            })?;
-
-        // Log successful API call
-        self.log_api_call_async("kucoin", None, symbol, "success", elapsed_ms, Some(usdt_price), Some("USDT"), None, Some(&url));
-
-        Ok(PriceEntry {
+        let currency = comparable.iter()
+            .find(|q| (q.price - price).abs() < f64::EPSILON)
+            .unwrap_or(&comparable[0])
+            .currency.clone();
+
+        // Same anomaly check as a single-provider fetch; a quarantined median serves the last known price
+        let entry = PriceEntry {
             symbol: symbol.to_uppercase(),
-            price: usdt_price,
-            currency: "USDT".to_string(),
+            price,
+            currency,
             updated_at: Utc::now(),
+            source: Some("consensus".to_string()),
+        };
+        let entry = self.accept_fetched(symbol, asset_type, market, entry).await.entry;
+
+        Ok(ConsensusPrice {
+            entry,
+            agreeing: agreeing.len(),
+            quotes,
+            divergence_percent,
+            diverged,
         })
     }
 
-    /// Fetch price from HTX (Huobi) API (returns USDT price)
-    async fn fetch_htx_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
-        // Check rate limit first
-        self.check_rate_limit("htx").await?;
-        
-        // HTX uses btcusdt format (lowercase)
-        let pair = format!("{}usdt", symbol.to_lowercase());
-        let url = format!(
-            "https://api.huobi.pro/market/detail/merged?symbol={}",
-            pair
-        );
+    /// Call a single provider by its api_providers `provider_type`. None if not supported.
+    async fn fetch_from_provider(
+        &self,
+        provider_type: &str,
+        symbol: &str,
+        asset_type: &AssetType,
+        market: Option<&Market>,
+    ) -> Option<Result<PriceEntry, AppError>> {
+        let provider = self.registry.get(provider_type).filter(|p| p.supports(asset_type))?;
+        Some(provider.fetch_price(&self.providers, symbol, asset_type, market).await)
+    }
 
-        tracing::info!("Fetching crypto price from HTX: {}", url);
+    /// Map an asset type to the `market_id` used in the api_providers collection
+    fn provider_market_id(asset_type: &AssetType) -> &'static str {
+        match asset_type {
+            AssetType::Stock => "thai_stock",
+            AssetType::Tfex => "tfex",
+            AssetType::Crypto => "crypto",
+            AssetType::ForeignStock => "us_stock",
+            AssetType::Gold => "gold",
+            AssetType::Commodity => "commodity",
+            AssetType::Cash => "fx",
+        }
+    }
 
-        let start = Instant::now();
+    fn median(mut values: Vec<f64>) -> f64 {
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let mid = values.len() / 2;
+        if values.len().is_multiple_of(2) {
+            (values[mid - 1] + values[mid]) / 2.0
+        } else {
+            values[mid]
+        }
+    }
 
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        // Record the API call
-        self.record_api_call("htx").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
+    /// Prices for many symbols. Those not served from the cache are grouped by provider, so
+    /// Binance, CoinGecko and the Yahoo Finance service are asked once per batch instead of once
+    /// per symbol. Symbols a batch doesn't answer (all of them when the request fails) take the
+    /// one-symbol path with its fallbacks, up to PRICE_FETCH_CONCURRENCY at a time.
+    pub async fn get_prices_batch(&self, requests: &[BatchRequest]) -> PriceBatch {
+        let mut results: Vec<Option<Result<PriceEntry, AppError>>> = requests.iter().map(|_| None).collect();
+        let mut groups: Vec<BatchGroup> = Vec::new();
+        for (i, request) in requests.iter().enumerate() {
+            let market = request.market.as_ref();
+            let cached = match request.force {
+                // Delisted symbols keep their frozen price even when forced
+                true => self.frozen_price(&request.symbol, &request.asset_type).await,
+                false => self.cached_price(&request.symbol, &request.asset_type, market).await
+                    .filter(|lookup| !lookup.stale)
+                    .map(|lookup| lookup.entry),
+            };
+            if let Some(entry) = cached {
+                results[i] = Some(Ok(entry));
+                continue;
+            }
+            let Some(provider) = self.batch_provider(&request.asset_type, market) else { continue };
+            match groups.iter_mut().find(|group| {
+                group.provider.provider_type() == provider.provider_type()
+                    && *group.asset_type == request.asset_type
+                    && group.market == market
+            }) {
+                Some(group) => group.indexes.push(i),
+                None => groups.push(BatchGroup { provider, asset_type: &request.asset_type, market, indexes: vec![i] }),
+            }
+        }
 
-        if response.status().as_u16() == 429 {
-            self.record_rate_limit_hit("htx", None).await;
-            self.log_api_call_async("htx", None, symbol, "error", elapsed_ms, None, None, Some("Rate limit exceeded"), Some(&url));
-            return Err(AppError::ExternalApiError("HTX rate limit exceeded".to_string()));
+        let mut batch_requests = 0;
+        let mut batched_symbols = 0;
+        // A lone symbol gains nothing from a batch and keeps the provider's richer single-symbol path
+        for BatchGroup { provider, asset_type, market, indexes } in groups.into_iter().filter(|group| group.indexes.len() > 1) {
+            for chunk in indexes.chunks(provider.batch_size()) {
+                let symbols: Vec<String> = chunk.iter().map(|&i| requests[i].symbol.to_uppercase()).collect();
+                batch_requests += 1;
+                let fetched = match provider.fetch_prices(&self.providers, &symbols, asset_type, market).await {
+                    Ok(fetched) => fetched,
+                    Err(e) => {
+                        tracing::warn!("⚠️ {} batch of {} symbols failed, fetching them one by one: {}", provider.provider_type(), symbols.len(), e);
+                        continue;
+                    }
+                };
+                for (&i, symbol) in chunk.iter().zip(&symbols) {
+                    if let Some(entry) = fetched.get(symbol) {
+                        let lookup = self.accept_fetched(symbol, asset_type, market, entry.clone()).await;
+                        results[i] = Some(Ok(lookup.entry));
+                        batched_symbols += 1;
+                    }
+                }
+            }
         }
-        
-        if !response.status().is_success() {
-            let error_msg = format!("HTX API error: {}", response.status());
-            self.log_api_call_async("htx", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-            return Err(AppError::ExternalApiError(error_msg));
+        if batch_requests > 0 {
+            tracing::info!("📦 Priced {} symbols with {} batch requests", batched_symbols, batch_requests);
         }
 
-        let data: serde_json::Value = response.json().await?;
-        
-        // HTX response format: { "status": "ok", "tick": { "close": 91116.86, ... } }
-        let usdt_price = data
-            .get("tick")
-            .and_then(|t| t.get("close"))
-            .and_then(|v| v.as_f64())
-            .ok_or_else(|| {
-                let error_msg = format!("Could not parse HTX price for {}", symbol);
-                self.log_api_call_async("htx", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                AppError::ExternalApiError(error_msg)
-            })?;
-
-        // Log successful API call
-        self.log_api_call_async("htx", None, symbol, "success", elapsed_ms, Some(usdt_price), Some("USDT"), None, Some(&url));
-
-        Ok(PriceEntry {
-            symbol: symbol.to_uppercase(),
-            price: usdt_price,
-            currency: "USDT".to_string(),
-            updated_at: Utc::now(),
-        })
+        // The rest are fetched a few at a time; every provider call still passes the rate limiter
+        let priced = stream::iter(results.into_iter().enumerate())
+            .map(|(i, result)| async move {
+                let request = &requests[i];
+                match result {
+                    Some(result) => result,
+                    None if request.force => self.refresh_price(&request.symbol, &request.asset_type, request.market.as_ref()).await,
+                    None => self.get_price(&request.symbol, &request.asset_type, request.market.as_ref()).await,
+                }
+            })
+            .buffered(self.config.price_fetch_concurrency.max(1))
+            .collect()
+            .await;
+        PriceBatch { results: priced, batch_requests, batched_symbols }
     }
 
-    /// Fetch cryptocurrency price from CoinGecko API
-    async fn fetch_coingecko_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
-        // Check rate limit first - CoinGecko Free tier is very strict!
-        self.check_rate_limit("coingecko").await?;
-        
-        let coin_id = self.get_coingecko_id(symbol);
-        let url = format!(
-            "{}/simple/price?ids={}&vs_currencies=thb,usd",
-            self.config.coingecko_api_url,
-            coin_id
-        );
+    /// Provider whose multi-symbol endpoint can price a symbol, following the same choice as
+    /// the one-symbol path (the market's exchange or CoinGecko for crypto, Yahoo for stocks)
+    fn batch_provider(&self, asset_type: &AssetType, market: Option<&Market>) -> Option<&Arc<dyn PriceProvider>> {
+        let provider = match asset_type {
+            AssetType::Crypto => match market.and_then(|m| self.registry.for_market(m)) {
+                Some(provider) => provider,
+                None => self.registry.get("coingecko")?,
+            },
+            AssetType::Stock | AssetType::Tfex | AssetType::ForeignStock => self.registry.get("yahoo_finance")?,
+            _ => return None,
+        };
+        (provider.batch_size() > 0).then_some(provider)
+    }
 
-        tracing::info!("Fetching crypto price from CoinGecko: {}", url);
+    /// Get price history for a symbol
+    pub async fn get_price_history(
+        &self,
+        symbol: &str,
+        asset_type: &AssetType,
+        market: Option<&Market>,
+        days: u32,
+    ) -> Result<Vec<HistoryEntry>, AppError> {
+        let provider = match asset_type {
+            AssetType::Crypto => "binance",
+            _ => "yahoo_finance",
+        };
+        let market_key = market.map(|m| m.to_string()).unwrap_or_default();
+        let cache_key = format!("{}:{}:{}:{}", asset_type, market_key, symbol.to_uppercase(), days);
+        if let Some(history) = self.provider_cache.get(provider, EndpointClass::History, &cache_key).await {
+            tracing::debug!("History cache hit for {}", cache_key);
+            return Ok(history);
+        }
 
-        let start = Instant::now();
+        let history = match asset_type {
+            AssetType::Cash => vec![],
+            AssetType::Crypto => providers::binance::fetch_history(&self.providers, symbol, days).await?,
+            AssetType::Stock | AssetType::ForeignStock | AssetType::Gold | AssetType::Tfex | AssetType::Commodity => 
+                yahoo::fetch_history(&self.providers, symbol, asset_type, days).await?,
+        };
+        if !history.is_empty() {
+            self.provider_cache.put(provider, EndpointClass::History, &cache_key, &history).await;
+        }
+        Ok(history)
+    }
 
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        // Record the API call
-        self.record_api_call("coingecko").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
+    /// Fetch cryptocurrency price - uses the market's exchange when it has a provider, else CoinGecko
+    async fn fetch_crypto_price(&self, symbol: &str, market: Option<&Market>) -> Result<PriceEntry, AppError> {
+        tracing::debug!("fetch_crypto_price for {} with market: {:?}", symbol, market);
 
-        if response.status().as_u16() == 429 {
-            // CoinGecko rate limit - block for 60 seconds
-            self.record_rate_limit_hit("coingecko", Some(60)).await;
-            self.log_api_call_async("coingecko", None, symbol, "error", elapsed_ms, None, None, Some("Rate limit exceeded"), Some(&url));
-            return Err(AppError::ExternalApiError("CoinGecko rate limit exceeded. Please wait 60 seconds.".to_string()));
-        }
-        
-        if !response.status().is_success() {
-            let error_msg = format!("CoinGecko API error: {}", response.status());
-            self.log_api_call_async("coingecko", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-            return Err(AppError::ExternalApiError(error_msg));
+        if let Some(provider) = market.and_then(|m| self.registry.for_market(m)) {
+            tracing::info!("Using {} API for {} (market: {:?})", provider.provider_type(), symbol, market);
+            match provider.fetch_price(&self.providers, symbol, &AssetType::Crypto, market).await {
+                Ok(entry) => return Ok(entry),
+                Err(e) => {
+                    tracing::warn!("{} API failed for {}: {}, falling back to CoinGecko", provider.provider_type(), symbol, e);
+                }
+            }
         }
 
-        let data: serde_json::Value = response.json().await?;
-        
-        let price = data
-            .get(&coin_id)
-            .and_then(|v| v.get("thb"))
-            .and_then(|v| v.as_f64())
-            .ok_or_else(|| {
-                let error_msg = format!("Could not parse price for {}", symbol);
-                self.log_api_call_async("coingecko", None, symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                AppError::ExternalApiError(error_msg)
-            })?;
-        
-        // Log successful API call
-        self.log_api_call_async("coingecko", None, symbol, "success", elapsed_ms, Some(price), Some("THB"), None, Some(&url));
-
-        Ok(PriceEntry {
-            symbol: symbol.to_uppercase(),
-            price,
-            currency: "THB".to_string(),
-            updated_at: Utc::now(),
-        })
+        // Default: CoinGecko API (returns THB)
+        tracing::debug!("Using CoinGecko API for {}", symbol);
+        self.fetch_with("coingecko", symbol, &AssetType::Crypto, market).await
     }
 
-    /// Fetch Thai stock price from Yahoo Finance API
-    /// Uses symbol.BK format (e.g., PTT.BK, ADVANC.BK)
+    /// Fetch Thai stock or TFEX price from the Yahoo Finance service, falling back to mock
     async fn fetch_thai_stock_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
-        // Check rate limit first
-        self.check_rate_limit("yahoo_finance").await?;
-        
-        // Determine if it's a stock or futures/derivatives
         let symbol_upper = symbol.to_uppercase();
-        
-        // TFEX symbols (Futures) - use mock prices as Yahoo doesn't have them
-        if symbol_upper.starts_with("S50") || symbol_upper.starts_with("GF") || 
-           symbol_upper.starts_with("GD") || symbol_upper.starts_with("SV") ||
-           symbol_upper.starts_with("USD") || symbol_upper.starts_with("BRN") ||
-           symbol_upper.starts_with("TSR") || symbol_upper.starts_with("BANK") ||
-           symbol_upper.starts_with("ENRG") || symbol_upper.ends_with("H24") ||
-           symbol_upper.ends_with("M24") || symbol_upper.ends_with("U24") ||
-           symbol_upper.ends_with("Z24") || symbol_upper.ends_with("H25") ||
-           symbol_upper.ends_with("M25") || symbol_upper.ends_with("U25") ||
-           symbol_upper.ends_with("Z25") || symbol_upper.ends_with("H26") ||
-           symbol_upper.ends_with("M26") {
-            return self.fetch_tfex_mock_price(&symbol_upper).await;
-        }
-        
-        // For SET stocks, use Yahoo Finance with .BK suffix
-        let yahoo_symbol = format!("{}.BK", symbol_upper);
-        let url = format!(
-            "https://query1.finance.yahoo.com/v8/finance/chart/{}?interval=1d&range=1d",
-            yahoo_symbol
-        );
-
-        tracing::info!("Fetching Thai stock price from Yahoo Finance: {}", url);
-
-        let start = Instant::now();
-
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .send()
-            .await?;
-        
-        // Record the API call
-        self.record_api_call("yahoo_finance").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
-
-        if response.status().as_u16() == 429 {
-            self.record_rate_limit_hit("yahoo_finance", Some(60)).await;
-            self.log_api_call_async("yahoo_finance", Some("SET"), symbol, "error", elapsed_ms, None, None, Some("Rate limit exceeded"), Some(&url));
-            return Err(AppError::ExternalApiError("Yahoo Finance rate limit exceeded".to_string()));
+        match self.fetch_with("yahoo_finance", &symbol_upper, &AssetType::Stock, None).await {
+            Ok(entry) => Ok(entry),
+            Err(e) => {
+                tracing::warn!("Yahoo Finance Service failed for {}: {}, using mock", symbol, e);
+                self.mock_fallback(&symbol_upper, mock::tfex_price(&symbol_upper))
+            }
         }
+    }
 
-        // For SET stocks, use Yahoo Finance with .BK suffix
-        let yahoo_symbol = format!("{}.BK", symbol_upper);
-        let url = format!(
-            "{}/api/price-history/{}?period=1d&interval=1d",
-            self.config.yahoo_finance_service_url,
-            yahoo_symbol
-        );
-
-        tracing::info!("Fetching Thai stock price from Yahoo Finance Service: {}", url);
-        
-        let start = Instant::now();
-
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        // Record the API call
-        self.record_api_call("yahoo_finance").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
-
-        if !response.status().is_success() {
-            let error_msg = format!("Yahoo Finance Service failed for {}, using mock", symbol);
-            tracing::warn!("{}", error_msg);
-            self.log_api_call_async("yahoo_finance", Some("SET"), symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-            return self.fetch_tfex_mock_price(&symbol_upper).await;
+    /// Fetch foreign stock price: Yahoo Finance service, then Finnhub, then mock (USD, US market mostly)
+    async fn fetch_foreign_stock_price(
+        &self,
+        symbol: &str,
+        market: Option<&Market>,
+    ) -> Result<PriceEntry, AppError> {
+        let symbol_upper = symbol.to_uppercase();
+        match self.fetch_with("yahoo_finance", &symbol_upper, &AssetType::ForeignStock, market).await {
+            Ok(entry) => return Ok(entry),
+            Err(e) => tracing::warn!("Yahoo Finance Service failed for {}: {}", symbol, e),
         }
 
-        let data: serde_json::Value = response.json().await?;
-        
-        // Yahoo Finance Service response format: { "data": [ { "close": ... } ] }
-        let price = data
-            .get("data")
-            .and_then(|d| d.as_array())
-            .and_then(|arr| arr.last())
-            .and_then(|item| item.get("close"))
-            .and_then(|v| v.as_f64())
-            .ok_or_else(|| {
-                let error_msg = format!("Could not parse Yahoo Finance price for {}", symbol);
-                tracing::warn!("{}", error_msg);
-                self.log_api_call_async("yahoo_finance", Some("SET"), symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                AppError::ExternalApiError(format!("Could not parse price for {}", symbol))
-            })?;
-
-        tracing::info!("Yahoo Finance price for {}: {} THB", symbol, price);
-        
-        // Log successful API call
-        self.log_api_call_async("yahoo_finance", Some("SET"), symbol, "success", elapsed_ms, Some(price), Some("THB"), None, Some(&url));
-
-        Ok(PriceEntry {
-            symbol: symbol_upper,
-            price,
-            currency: "THB".to_string(),
-            updated_at: Utc::now(),
-        })
-    }
-    
-    /// Fetch TFEX prices - tries Yahoo Finance first, then falls back to mock
-    async fn fetch_tfex_mock_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
-        // Try to fetch from Yahoo Finance first for supported symbols
-        if let Some((yahoo_symbol, currency)) = self.get_tfex_yahoo_symbol(symbol) {
-            if let Ok(entry) = self.fetch_yahoo_futures_price(symbol, &yahoo_symbol, currency).await {
-                return Ok(entry);
+        if self.config.finnhub_api_key.is_some() {
+            match self.fetch_with("finnhub", &symbol_upper, &AssetType::ForeignStock, market).await {
+                Ok(entry) => return Ok(entry),
+                Err(e) => tracing::warn!("Finnhub failed for {}: {}", symbol, e),
             }
         }
-        
-        // Fallback to mock prices
-        tracing::debug!("Using mock price for TFEX symbol: {}", symbol);
-        
-        // Mock prices for TFEX symbols
-        let mock_prices: HashMap<&str, f64> = [
-            // SET50 Index Futures (S50) - trades around 880-930, multiplier 200
-            ("S50", 925.00), 
-            ("S50H24", 920.00), ("S50M24", 922.00), ("S50U24", 924.00), ("S50Z24", 926.00),
-            ("S50H25", 925.00), ("S50M25", 927.00), ("S50U25", 929.00), ("S50Z25", 931.00),
-            ("S50H26", 928.00), ("S50M26", 930.00), ("S50U26", 932.00), ("S50Z26", 934.00),
-            // SET50 Value Futures (SVF) - trades around 40-100, multiplier 3000
-            // ("SVF", 95.00),
-            // ("SVFH24", 45.00), ("SVFM24", 50.00), ("SVFU24", 55.00), ("SVFZ24", 60.00),
-            // ("SVFH25", 70.00), ("SVFM25", 75.00), ("SVFU25", 80.00), ("SVFZ25", 85.00),
-            // ("SVFH26", 95.00), ("SVFM26", 98.00),
-            // Gold Futures (10 Baht)
-            ("GFH24", 35200.0), ("GFM24", 35300.0), ("GFU24", 35400.0), ("GFZ24", 35500.0),
-            ("GFH25", 35600.0), ("GFM25", 35700.0), ("GFU25", 35800.0), ("GFZ25", 35900.0),
-            ("GFH26", 36000.0), ("GFM26", 36100.0),
-            // Gold-D (50 Baht)
-            ("GDH24", 176000.0), ("GDM24", 176500.0), ("GDU24", 177000.0), ("GDZ24", 177500.0),
-            ("GDH25", 178000.0), ("GDM25", 178500.0), ("GDU25", 179000.0), ("GDZ25", 179500.0),
-            ("GDH26", 180000.0), ("GDM26", 180500.0),
-            // Silver Futures (SV) - TFEX Silver, trades in THB
-            // ("SVH24", 955.0), ("SVM24", 960.0), ("SVU24", 965.0), ("SVZ24", 970.0),
-            // ("SVH25", 975.0), ("SVM25", 980.0), ("SVH26", 990.0),
-            // USD Futures
-            ("USDH24", 34.50), ("USDM24", 34.55), ("USDU24", 34.60), ("USDZ24", 34.65),
-            ("USDH25", 34.70), ("USDM25", 34.75), ("USDU25", 34.80), ("USDZ25", 34.85),
-            ("USDH26", 34.90), ("USDM26", 34.95),
-            // Sector Futures
-            ("BANKH24", 480.0), ("BANKM24", 482.0), ("ENRGH24", 1850.0), ("ENRGM24", 1855.0),
-            // Brent Crude Oil Futures
-            ("BRNH24", 2750.0), ("BRNM24", 2760.0), ("BRNU24", 2770.0), ("BRNZ24", 2780.0),
-            ("BRNH25", 2790.0), ("BRNM25", 2800.0), ("BRNH26", 2820.0), ("BRNM26", 2830.0),
-            // Rubber Futures
-            ("TSRH24", 56.0), ("TSRM24", 56.5), ("TSRU24", 57.0), ("TSRZ24", 57.5),
-            ("TSRH25", 58.0), ("TSRM25", 58.5),
-        ].into_iter().collect();
-
-        let price = mock_prices
-            .get(symbol)
-            .copied()
-            .ok_or_else(|| AppError::ExternalApiError(format!("Price data not available for {}", symbol)))?;
-
-        Ok(PriceEntry {
-            symbol: symbol.to_string(),
-            price,
-            currency: "THB".to_string(),
-            updated_at: Utc::now(),
-        })
+
+        tracing::warn!("No live price for {}, using mock (set FINNHUB_API_KEY for a second provider)", symbol);
+        self.mock_fallback(&symbol_upper, Ok(mock::foreign_stock_price(&symbol_upper, market)))
     }
-    
-    /// Map TFEX symbols to Yahoo Finance symbols
-    fn get_tfex_yahoo_symbol(&self, symbol: &str) -> Option<(String, &'static str)> {
-        // Map TFEX symbols to Yahoo Finance equivalents
-        match symbol {
-            // SET50 Index Futures (S50) - use SET50 Index
-            s if s.starts_with("S50") => Some(("^SET50.BK".to_string(), "THB")),
-            // SVF (SET50 Value Futures) - use Silver Futures as proxy, price in THB
-            s if s.starts_with("SVF") => Some(("SI=F".to_string(), "THB")),
-            // Gold Futures - use international gold futures
-            s if s.starts_with("GF") || s.starts_with("GD") => Some(("GC=F".to_string(), "USD")),
-            // Silver Futures (SV but NOT SVF)
-            s if s.starts_with("SV") && !s.starts_with("SVF") => Some(("SI=F".to_string(), "USD")),
-            // Brent Crude Oil
-            s if s.starts_with("BRN") => Some(("BZ=F".to_string(), "USD")),
-            // USD/THB - no direct equivalent, fallback to mock
-            _ => None,
+
+    /// Wallet balances on an exchange read with a user's key. Not cached: balances change
+    /// with every trade and each call is made on the user's behalf.
+    pub async fn account_balances(&self, exchange: &Market, credentials: &ExchangeCredentials) -> Result<Vec<ExchangeBalance>, AppError> {
+        match exchange {
+            Market::Binance => providers::binance::fetch_balances(&self.providers, credentials).await,
+            Market::Bitkub => providers::bitkub::fetch_balances(&self.providers, credentials).await,
+            other => Err(AppError::BadRequest(format!("Balances can't be read from {}", other))),
         }
     }
-    
-    /// Fetch futures price from Yahoo Finance
-    async fn fetch_yahoo_futures_price(
-        &self, 
-        original_symbol: &str,
-        yahoo_symbol: &str, 
-        currency: &str
-    ) -> Result<PriceEntry, AppError> {
-        self.check_rate_limit("yahoo_finance").await?;
-        
-        let url = format!(
-            "{}/api/price-history/{}?period=1d&interval=1d",
-            self.config.yahoo_finance_service_url,
-            yahoo_symbol
-        );
 
-        tracing::info!("Fetching TFEX price from Yahoo Finance Service: {} -> {}", original_symbol, url);
-        
-        let start = Instant::now();
+    /// Spot pairs listed on Binance, for finding the ones a user may have traded
+    pub async fn binance_spot_pairs(&self) -> Result<HashSet<String>, AppError> {
+        providers::binance::fetch_spot_pairs(&self.providers).await
+    }
 
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        self.record_api_call("yahoo_finance").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
+    /// One page of a user's Binance trades in a pair, from trade id `from_id` on
+    pub async fn binance_account_trades(&self, credentials: &ExchangeCredentials, pair: &str, from_id: u64) -> Result<Vec<AccountTrade>, AppError> {
+        providers::binance::fetch_account_trades(&self.providers, credentials, pair, from_id).await
+    }
 
-        if !response.status().is_success() {
-            let error_msg = format!("Yahoo Finance Service error: {}", response.status());
-            self.log_api_call_async("yahoo_finance", Some("TFEX"), original_symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-            return Err(AppError::ExternalApiError(error_msg));
+    /// Average daily volume in shares over the last AVERAGE_VOLUME_SESSIONS sessions, cached
+    /// for a day. None for asset types without exchange volume to size a position against.
+    pub async fn average_daily_volume(&self, symbol: &str, asset_type: &AssetType) -> Result<Option<f64>, AppError> {
+        if !matches!(asset_type, AssetType::Stock | AssetType::ForeignStock) {
+            return Ok(None);
         }
-
-        let data: serde_json::Value = response.json().await?;
-        
-        let price = data
-            .get("data")
-            .and_then(|d| d.as_array())
-            .and_then(|arr| arr.last())
-            .and_then(|item| item.get("close"))
-            .and_then(|v| v.as_f64())
-            .ok_or_else(|| {
-                                let error_msg = format!("Could not parse price for {}", original_symbol);
-                self.log_api_call_async("yahoo_finance", Some("TFEX"), original_symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                AppError::ExternalApiError(error_msg)
-            })?;
-
-        tracing::info!("Yahoo Finance {} price for {}: {} {}", yahoo_symbol, original_symbol, price, currency);
-        
-        // Log successful API call
-        self.log_api_call_async("yahoo_finance", Some("TFEX"), original_symbol, "success", elapsed_ms, Some(price), Some(currency), None, Some(&url));
-
-        Ok(PriceEntry {
-            symbol: original_symbol.to_string(),
-            price,
-            currency: currency.to_string(),
-            updated_at: Utc::now(),
-        })
+        let cache_key = Self::volume_cache_key(symbol, asset_type);
+        if let Some(volume) = self.provider_cache.get("yahoo_finance", EndpointClass::Fundamentals, &cache_key).await {
+            return Ok(Some(volume));
+        }
+        let volume = yahoo::fetch_average_volume(&self.providers, &symbol.to_uppercase(), asset_type, AVERAGE_VOLUME_SESSIONS).await?;
+        self.provider_cache.put("yahoo_finance", EndpointClass::Fundamentals, &cache_key, &volume).await;
+        Ok(Some(volume))
     }
 
-    /// Fetch foreign stock price from Yahoo Finance
-    async fn fetch_foreign_stock_price(
-        &self, 
-        symbol: &str,
-        market: Option<&Market>,
-    ) -> Result<PriceEntry, AppError> {
-        // Check rate limit first
-        self.check_rate_limit("yahoo_finance").await?;
-        
-        let symbol_upper = symbol.to_uppercase();
-        
-        // Try Yahoo Finance Service first
-        let url = format!(
-            "{}/api/price-history/{}?period=1d&interval=1d",
-            self.config.yahoo_finance_service_url,
-            symbol_upper
-        );
-
-        tracing::info!("Fetching foreign stock price from Yahoo Finance Service: {}", url);
-        
-        let start = Instant::now();
+    /// Average daily volume if cached, never calling a provider
+    pub async fn cached_average_daily_volume(&self, symbol: &str, asset_type: &AssetType) -> Option<f64> {
+        self.provider_cache
+            .get("yahoo_finance", EndpointClass::Fundamentals, &Self::volume_cache_key(symbol, asset_type))
+            .await
+    }
 
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        // Record the API call
-        self.record_api_call("yahoo_finance").await;
-        
-        let elapsed_ms = start.elapsed().as_millis() as u64;
+    fn volume_cache_key(symbol: &str, asset_type: &AssetType) -> String {
+        format!("adv:{}:{}", asset_type, symbol.to_uppercase())
+    }
 
-        if !response.status().is_success() {
-            let error_msg = format!("Yahoo Finance Service failed for {}, using mock", symbol);
-            tracing::warn!("{}", error_msg);
-            self.log_api_call_async("yahoo_finance", Some("Foreign"), symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-            return self.fetch_foreign_stock_mock(&symbol_upper, market);
+    /// Thai 96.5% bar buy/sell prices per baht-weight, shared by the three Thai gold symbols so
+    /// the association page is fetched once per cache period. GoldAPI backs it up when configured.
+    pub async fn thai_gold_quote(&self) -> Result<ThaiGoldQuote, AppError> {
+        if let Some(quote) = self.provider_cache.get("goldtraders", EndpointClass::Gold, "quote").await {
+            return Ok(quote);
         }
 
-        let data: serde_json::Value = response.json().await?;
-        
-        // Yahoo Finance Service response
-        let price_opt = data
-            .get("data")
-            .and_then(|d| d.as_array())
-            .and_then(|arr| arr.last())
-            .and_then(|item| item.get("close"))
-            .and_then(|v| v.as_f64());
-            
-        if let Some(price) = price_opt {
-            let currency = "USD"; // Default to USD for foreign stocks (US market mostly)
-                
-            tracing::info!("Yahoo Finance price for {}: {} {}", symbol, price, currency);
-            
-            // Log successful API call
-            self.log_api_call_async("yahoo_finance", Some("Foreign"), symbol, "success", elapsed_ms, Some(price), Some(currency), None, Some(&url));
-            
-            return Ok(PriceEntry {
-                symbol: symbol_upper,
-                price,
-                currency: currency.to_string(),
-                updated_at: Utc::now(),
-            });
-        }
-        
-        // Fallback to mock if parsing fails
-        tracing::warn!("Could not parse Yahoo Finance response for {}, using mock", symbol);
-        self.fetch_foreign_stock_mock(&symbol_upper, market)
-    }
-    
-    /// Fallback mock prices for foreign stocks
-    fn fetch_foreign_stock_mock(&self, symbol: &str, market: Option<&Market>) -> Result<PriceEntry, AppError> {
-        tracing::debug!("Using mock price for foreign stock: {}", symbol);
-        
-        // Mock prices for popular stocks
-        let mock_prices: HashMap<&str, (f64, &str)> = [
-            ("AAPL", (175.50, "USD")), ("MSFT", (378.25, "USD")),
-            ("GOOGL", (141.80, "USD")), ("NVDA", (495.00, "USD")),
-            ("TSLA", (248.50, "USD")), ("META", (355.20, "USD")),
-        ].into_iter().collect();
-
-        let (price, currency) = mock_prices
-            .get(symbol)
-            .copied()
-            .unwrap_or((100.0, market.map(|m| m.default_currency()).unwrap_or("USD")));
-
-        Ok(PriceEntry {
-            symbol: symbol.to_string(),
-            price,
-            currency: currency.to_string(),
-            updated_at: Utc::now(),
-        })
+        let quote = match thai_gold::fetch_quote(&self.providers).await {
+            Ok(quote) => quote,
+            Err(e) if self.config.goldapi_api_key.is_some() => {
+                tracing::warn!("Thai gold association quote failed: {}, trying GoldAPI", e);
+                goldapi::fetch_quote(&self.providers).await?
+            }
+            Err(e) => return Err(e),
+        };
+        self.provider_cache.put("goldtraders", EndpointClass::Gold, "quote", &quote).await;
+        Ok(quote)
     }
 
-    /// Fetch gold price (Thai Gold API or Yahoo Finance)
+    /// Fetch gold price (Thai gold association or Yahoo Finance)
     async fn fetch_gold_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
         let symbol_upper = symbol.to_uppercase();
-        
+
         // Thai Gold (Baht/Baht-weight)
         if symbol_upper == "GOLD" || symbol_upper == "GOLD96.5" || symbol_upper == "GOLD99.99" {
-            return self.fetch_thai_gold_price(&symbol_upper).await;
+            return Ok(self.thai_gold_quote().await?.entry(&symbol_upper));
         }
-        
+
         // International Gold (USD/oz)
         if symbol_upper == "XAU" || symbol_upper == "XAUUSD" || symbol_upper == "GC" || symbol_upper == "GC=F" {
-            // Try Yahoo Finance first
-            match self.fetch_yahoo_gold_price("GC=F", symbol).await {
+            match yahoo::fetch_quote(&self.providers, &symbol_upper, "GC=F", "COMEX", "USD").await {
                 Ok(entry) => return Ok(entry),
                 Err(e) => {
                     tracing::warn!("Yahoo Finance failed for Gold: {}, falling back to mock", e);
@@ -1269,236 +1057,46 @@ impl PriceService {
             }
         }
 
-        // Fallback to mock if API fails or for other symbols
-        tracing::warn!(
-            "Using mock price for gold {}. Real API fetch failed or not supported.", 
-            symbol
-        );
-
-        // Mock gold prices (XAU = per troy oz, others per gram/baht)
-        let mock_prices: HashMap<&str, (f64, &str)> = [
-            // International gold (per troy oz)
-            ("XAU", (2025.50, "USD")),      // Gold spot USD
-            ("XAUUSD", (2025.50, "USD")),   // Gold vs USD
-            ("XAUTHB", (72500.00, "THB")),  // Gold vs THB (per oz)
-            // Thai gold (per baht weight = 15.244 grams)
-            ("GOLD", (35450.00, "THB")),    // Gold general
-            ("GOLD96.5", (35450.00, "THB")), // 96.5% purity bar
-            ("GOLD99.99", (42500.00, "THB")), // 99.99% purity
-            // Other precious metals
-            ("XAG", (23.85, "USD")),        // Silver spot
-            ("XPT", (920.00, "USD")),       // Platinum
-            ("XPD", (1050.00, "USD")),      // Palladium
-        ].into_iter().collect();
-
-        let (price, currency) = mock_prices
-            .get(symbol.to_uppercase().as_str())
-            .copied()
-            .unwrap_or((2000.0, "USD"));
-
-        Ok(PriceEntry {
-            symbol: symbol.to_uppercase(),
-            price,
-            currency: currency.to_string(),
-            updated_at: Utc::now(),
-        })
+        self.mock_fallback(&symbol_upper, Ok(mock::gold_price(&symbol_upper)))
     }
 
-    /// Fetch Thai Gold price from api.chnwt.dev
-    async fn fetch_thai_gold_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
-        // No strict rate limit specified, but let's be polite (use general limit)
-        if let Some(ref limiter) = self.rate_limiter {
-             if !limiter.can_request("thaigold").await {
-                  return Err(AppError::ExternalApiError("Rate limit exceeded for Thai Gold API".to_string()));
-             }
-        }
-
-        let url = "https://api.chnwt.dev/thai-gold-api/latest";
-        tracing::info!("Fetching Thai Gold price from: {}", url);
-
-        let start = Instant::now();
-
-        let response = self.client
-            .get(url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        self.record_api_call("thaigold").await;
-        let elapsed_ms = start.elapsed().as_millis() as u64;
-
-        if !response.status().is_success() {
-             let error_msg = format!("Thai Gold API error: {}", response.status());
-             self.log_api_call_async("thaigold", Some("local"), symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(url));
-             return Err(AppError::ExternalApiError(error_msg));
-        }
-
-        let data: ThaiGoldResponse = response.json().await?;
-        
-        // Parse price string (remove commas) - e.g. "67,950.00" -> 67950.00
-        let parse_price = |s: &str| -> Result<f64, AppError> {
-            let clean = s.replace(',', "");
-            clean.parse::<f64>().map_err(|_| AppError::ExternalApiError(format!("Invalid price format: {}", s)))
-        };
-
-        // Get 96.5% Bar Sell Price as baseline
-        let price_965 = parse_price(&data.response.price.gold_bar.sell)?;
-        
-        let final_price = if symbol == "GOLD99.99" {
-            // Estimate 99.99% price based on 96.5%
-            price_965 * (99.99 / 96.5)
-        } else {
-            price_965
-        };
-
-        tracing::info!("Thai Gold Price for {}: {} THB", symbol, final_price);
-        
-        self.log_api_call_async("thaigold", Some("local"), symbol, "success", elapsed_ms, Some(final_price), Some("THB"), None, Some(url));
-
-        Ok(PriceEntry {
-            symbol: symbol.to_string(),
-            price: final_price,
-            currency: "THB".to_string(),
-            updated_at: Utc::now(),
-        })
-    }
-
-    /// Fetch Gold/Silver Futures from Yahoo Finance
-    async fn fetch_yahoo_gold_price(&self, yahoo_symbol: &str, original_symbol: &str) -> Result<PriceEntry, AppError> {
-        self.check_rate_limit("yahoo_finance").await?;
-        
-        let url = format!(
-            "{}/api/price-history/{}?period=1d&interval=1d",
-            self.config.yahoo_finance_service_url,
-            yahoo_symbol
-        );
-
-        tracing::info!("Fetching Precious Metals price from Yahoo Finance Service: {}", url);
-        let start = Instant::now();
-
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-        
-        self.record_api_call("yahoo_finance").await;
-        let elapsed_ms = start.elapsed().as_millis() as u64;
-
-        if !response.status().is_success() {
-             let error_msg = format!("Yahoo Finance Service error: {}", response.status());
-             self.log_api_call_async("yahoo_finance", Some("COMEX"), original_symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-             return Err(AppError::ExternalApiError(error_msg));
-        }
-
-        let data: serde_json::Value = response.json().await?;
-        
-        let price = data
-            .get("data")
-            .and_then(|d| d.as_array())
-            .and_then(|arr| arr.last())
-            .and_then(|item| item.get("close"))
-            .and_then(|v| v.as_f64())
-            .ok_or_else(|| {
-                let error_msg = format!("Could not parse Yahoo Finance price for {}", original_symbol);
-                self.log_api_call_async("yahoo_finance", Some("COMEX"), original_symbol, "error", elapsed_ms, None, None, Some(&error_msg), Some(&url));
-                AppError::ExternalApiError(error_msg)
-            })?;
-
-        let currency = "USD"; // Usually USD for these futures
-
-        self.log_api_call_async("yahoo_finance", Some("COMEX"), original_symbol, "success", elapsed_ms, Some(price), Some(currency), None, Some(&url));
-
-        Ok(PriceEntry {
-            symbol: original_symbol.to_string(),
-            price,
-            currency: currency.to_string(),
-            updated_at: Utc::now(),
-        })
-    }
-
-    /// Fetch commodity price
+    /// Fetch commodity price (precious metal futures from Yahoo Finance, mock otherwise)
     async fn fetch_commodity_price(&self, symbol: &str) -> Result<PriceEntry, AppError> {
         let symbol_upper = symbol.to_uppercase();
-        
-        // Try Yahoo Finance for Precious Metals
-        if symbol_upper == "XAG" || symbol_upper == "SI" || symbol_upper == "SI=F" {
-             if let Ok(entry) = self.fetch_yahoo_gold_price("SI=F", symbol).await {
-                 return Ok(entry);
-             }
-        }
-        if symbol_upper == "GC" || symbol_upper == "GC=F" {
-             if let Ok(entry) = self.fetch_yahoo_gold_price("GC=F", symbol).await {
-                 return Ok(entry);
-             }
-        }
 
-        tracing::warn!(
-            "Using mock price for commodity {}.", 
-            symbol
-        );
+        let yahoo_symbol = match symbol_upper.as_str() {
+            "XAG" | "SI" | "SI=F" => Some("SI=F"),
+            "GC" | "GC=F" => Some("GC=F"),
+            _ => None,
+        };
+        if let Some(yahoo_symbol) = yahoo_symbol {
+            if let Ok(entry) = yahoo::fetch_quote(&self.providers, &symbol_upper, yahoo_symbol, "COMEX", "USD").await {
+                return Ok(entry);
+            }
+        }
 
-        let mock_prices: HashMap<&str, (f64, &str)> = [
-            ("CL", (75.50, "USD")),   // Crude Oil
-            ("NG", (2.85, "USD")),    // Natural Gas
-            ("GC", (2025.00, "USD")), // Gold Futures
-            ("SI", (23.50, "USD")),   // Silver Futures
-            ("HG", (3.85, "USD")),    // Copper
-            ("ZC", (485.00, "USD")),  // Corn
-            ("ZS", (1250.00, "USD")), // Soybeans
-            ("ZW", (625.00, "USD")),  // Wheat
-        ].into_iter().collect();
-
-        let (price, currency) = mock_prices
-            .get(symbol.to_uppercase().as_str())
-            .copied()
-            .unwrap_or((100.0, "USD"));
-
-        Ok(PriceEntry {
-            symbol: symbol.to_uppercase(),
-            price,
-            currency: currency.to_string(),
-            updated_at: Utc::now(),
-        })
+        self.mock_fallback(&symbol_upper, Ok(mock::commodity_price(&symbol_upper)))
     }
 
-
-
-    /// Map common crypto symbols to CoinGecko IDs
-    fn get_coingecko_id(&self, symbol: &str) -> String {
-        let symbol_upper = symbol.to_uppercase();
-        match symbol_upper.as_str() {
-            "BTC" => "bitcoin".to_string(),
-            "ETH" => "ethereum".to_string(),
-            "BNB" => "binancecoin".to_string(),
-            "XRP" => "ripple".to_string(),
-            "ADA" => "cardano".to_string(),
-            "SOL" => "solana".to_string(),
-            "DOGE" => "dogecoin".to_string(),
-            "DOT" => "polkadot".to_string(),
-            "MATIC" => "matic-network".to_string(),
-            "AVAX" => "avalanche-2".to_string(),
-            "LINK" => "chainlink".to_string(),
-            "UNI" => "uniswap".to_string(),
-            "ATOM" => "cosmos".to_string(),
-            "LTC" => "litecoin".to_string(),
-            "ETC" => "ethereum-classic".to_string(),
-            "XLM" => "stellar".to_string(),
-            "NEAR" => "near".to_string(),
-            "APT" => "aptos".to_string(),
-            "ARB" => "arbitrum".to_string(),
-            "OP" => "optimism".to_string(),
-            "SUI" => "sui".to_string(),
-            "SEI" => "sei-network".to_string(),
-            "TIA" => "celestia".to_string(),
-            _ => symbol.to_lowercase(),
+    /// Serve a static mock price, or refuse to under STRICT_PRICE_DATA
+    fn mock_fallback(&self, symbol: &str, mock: Result<PriceEntry, AppError>) -> Result<PriceEntry, AppError> {
+        if self.config.strict_price_data {
+            return Err(AppError::ExternalApiError(format!(
+                "No live price for {} and mock prices are disabled (STRICT_PRICE_DATA)", symbol
+            )));
         }
+        mock
     }
 
     /// Clear all cached prices
     pub async fn clear_cache(&self) {
-        let mut cache = self.cache.write().await;
-        cache.clear();
+        self.provider_cache.clear_classes(&[
+            EndpointClass::Crypto,
+            EndpointClass::Stock,
+            EndpointClass::Gold,
+            EndpointClass::Commodity,
+            EndpointClass::History,
+        ]).await;
         tracing::info!("Price cache cleared");
         
         // Also try to clear potentially stale data from PocketBase (specifically for GOLD96.5 which had unit issues)
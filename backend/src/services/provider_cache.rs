@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use crate::models::{ApiProvider, AssetType};
+
+/// Kind of data an upstream endpoint returns; each class has its own cache lifetime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+    Fx,
+    Crypto,
+    Stock,
+    Gold,
+    Commodity,
+    History,
+    Fundamentals,
+}
+
+impl EndpointClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndpointClass::Fx => "fx",
+            EndpointClass::Crypto => "crypto",
+            EndpointClass::Stock => "stock",
+            EndpointClass::Gold => "gold",
+            EndpointClass::Commodity => "commodity",
+            EndpointClass::History => "history",
+            EndpointClass::Fundamentals => "fundamentals",
+        }
+    }
+
+    /// Default cache lifetime when no provider overrides it
+    pub fn default_ttl_seconds(&self) -> u64 {
+        match self {
+            EndpointClass::Fx => 3600,
+            EndpointClass::Crypto => 60,
+            EndpointClass::Stock => 300,
+            EndpointClass::Gold => 300,
+            EndpointClass::Commodity => 300,
+            EndpointClass::History => 3600,
+            EndpointClass::Fundamentals => 86400,
+        }
+    }
+
+    /// Endpoint class used for spot prices of an asset type
+    pub fn for_asset_type(asset_type: &AssetType) -> Self {
+        match asset_type {
+            AssetType::Crypto => EndpointClass::Crypto,
+            AssetType::Stock | AssetType::Tfex | AssetType::ForeignStock => EndpointClass::Stock,
+            AssetType::Gold => EndpointClass::Gold,
+            AssetType::Commodity => EndpointClass::Commodity,
+            // Cash is never fetched; classed with FX as the closest volatility
+            AssetType::Cash => EndpointClass::Fx,
+        }
+    }
+
+    /// Endpoint class served by an api_providers market
+    pub fn for_market_id(market_id: &str) -> Option<Self> {
+        match market_id {
+            "fx" => Some(EndpointClass::Fx),
+            "crypto" => Some(EndpointClass::Crypto),
+            "thai_stock" | "us_stock" | "tfex" => Some(EndpointClass::Stock),
+            "gold" => Some(EndpointClass::Gold),
+            "commodity" => Some(EndpointClass::Commodity),
+            "history" => Some(EndpointClass::History),
+            "fundamentals" => Some(EndpointClass::Fundamentals),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedValue {
+    value: serde_json::Value,
+    stored_at: DateTime<Utc>,
+}
+
+/// Response cache keyed by provider + endpoint class, with per-class TTLs
+/// that can be overridden per provider through `api_providers.cache_ttl_seconds`
+#[derive(Clone, Default)]
+pub struct ProviderCache {
+    entries: Arc<RwLock<HashMap<String, CachedValue>>>,
+    // "provider:class" -> TTL seconds, loaded from api_providers
+    ttl_overrides: Arc<RwLock<HashMap<String, u64>>>,
+}
+
+impl ProviderCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entry_key(provider: &str, class: EndpointClass, key: &str) -> String {
+        format!("{}:{}:{}", provider, class.as_str(), key)
+    }
+
+    /// Effective TTL for a provider and endpoint class
+    pub async fn ttl_for(&self, provider: &str, class: EndpointClass) -> u64 {
+        let overrides = self.ttl_overrides.read().await;
+        overrides
+            .get(&format!("{}:{}", provider, class.as_str()))
+            .copied()
+            .unwrap_or_else(|| class.default_ttl_seconds())
+    }
+
+    /// Get a cached value if it has not expired
+    pub async fn get<T: DeserializeOwned>(&self, provider: &str, class: EndpointClass, key: &str) -> Option<T> {
+        let ttl = self.ttl_for(provider, class).await as i64;
+        let entries = self.entries.read().await;
+        let cached = entries.get(&Self::entry_key(provider, class, key))?;
+        if Utc::now().signed_duration_since(cached.stored_at).num_seconds() >= ttl {
+            return None;
+        }
+        serde_json::from_value(cached.value.clone()).ok()
+    }
+
+    /// Get a cached value regardless of age
+    pub async fn get_stale<T: DeserializeOwned>(&self, provider: &str, class: EndpointClass, key: &str) -> Option<T> {
+        let entries = self.entries.read().await;
+        let cached = entries.get(&Self::entry_key(provider, class, key))?;
+        serde_json::from_value(cached.value.clone()).ok()
+    }
+
+    pub async fn put<T: Serialize>(&self, provider: &str, class: EndpointClass, key: &str, value: &T) {
+        let Ok(value) = serde_json::to_value(value) else {
+            return;
+        };
+        let mut entries = self.entries.write().await;
+        entries.insert(
+            Self::entry_key(provider, class, key),
+            CachedValue { value, stored_at: Utc::now() },
+        );
+    }
+
+    pub async fn remove(&self, provider: &str, class: EndpointClass, key: &str) {
+        self.entries.write().await.remove(&Self::entry_key(provider, class, key));
+    }
+
+    /// Drop every cached entry of the given classes
+    pub async fn clear_classes(&self, classes: &[EndpointClass]) {
+        let prefixes: Vec<String> = classes.iter().map(|c| format!(":{}:", c.as_str())).collect();
+        let mut entries = self.entries.write().await;
+        entries.retain(|k, _| !prefixes.iter().any(|p| k.contains(p.as_str())));
+    }
+
+    /// Rebuild TTL overrides from provider configuration.
+    /// Each enabled provider with a TTL sets `provider_type:class`; the market itself
+    /// (used when the serving provider is not known up front) takes the shortest TTL of its providers.
+    pub async fn load_ttls(&self, providers: &[ApiProvider]) {
+        let mut overrides: HashMap<String, u64> = HashMap::new();
+        for provider in providers.iter().filter(|p| p.enabled && p.cache_ttl_seconds > 0) {
+            let Some(class) = EndpointClass::for_market_id(&provider.market_id) else {
+                continue;
+            };
+            overrides.insert(
+                format!("{}:{}", provider.provider_type, class.as_str()),
+                provider.cache_ttl_seconds,
+            );
+            let market_ttl = overrides
+                .entry(format!("{}:{}", provider.market_id, class.as_str()))
+                .or_insert(provider.cache_ttl_seconds);
+            *market_ttl = (*market_ttl).min(provider.cache_ttl_seconds);
+        }
+
+        tracing::info!("🗄️ Loaded {} provider cache TTL overrides", overrides.len());
+        *self.ttl_overrides.write().await = overrides;
+    }
+}
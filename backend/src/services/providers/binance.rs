@@ -0,0 +1,313 @@
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use crate::error::AppError;
+use crate::models::{AssetType, ExchangeBalance, ExchangeCredentials, Market};
+use crate::services::price_service::{HistoryEntry, PriceEntry};
+use super::{sign_hmac_sha256, PriceProvider, ProviderCall, ProviderClient, TickerStream};
+
+const RATE_LIMIT_KEY: &str = "binance";
+
+/// Binance spot exchange, falling back to the perpetual futures market
+pub struct Binance;
+
+impl PriceProvider for Binance {
+    fn provider_type(&self) -> &'static str {
+        "binance"
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::Crypto]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(async move {
+            match fetch_spot_price(client, symbol).await {
+                Ok(entry) => Ok(entry),
+                Err(e) => {
+                    tracing::warn!("Binance Spot API failed for {}: {}, trying Futures...", symbol, e);
+                    fetch_futures_price(client, symbol).await
+                }
+            }
+        })
+    }
+
+    /// One request returns every spot ticker, so any number of symbols fits
+    fn batch_size(&self) -> usize {
+        1000
+    }
+
+    fn fetch_prices<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbols: &'a [String],
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<HashMap<String, PriceEntry>, AppError>> {
+        Box::pin(fetch_spot_prices(client, symbols))
+    }
+}
+
+impl TickerStream for Binance {
+    fn market(&self) -> Market {
+        Market::Binance
+    }
+
+    fn source(&self) -> &'static str {
+        "binance_ws"
+    }
+
+    fn currency(&self) -> &'static str {
+        "USDT"
+    }
+
+    fn url(&self, _symbols: &[String]) -> String {
+        "wss://stream.binance.com:9443/ws".to_string()
+    }
+
+    fn subscribe(&self, symbols: &[String]) -> Vec<String> {
+        let params: Vec<String> = symbols.iter()
+            .map(|s| format!("{}usdt@miniTicker", s.to_lowercase()))
+            .collect();
+        vec![serde_json::json!({ "method": "SUBSCRIBE", "params": params, "id": 1 }).to_string()]
+    }
+
+    fn parse(&self, message: &serde_json::Value) -> Option<(String, f64)> {
+        // Mini ticker: { "e": "24hrMiniTicker", "s": "BTCUSDT", "c": "94123.50", ... }
+        if message.get("e")?.as_str()? != "24hrMiniTicker" {
+            return None;
+        }
+        let symbol = message.get("s")?.as_str()?.strip_suffix("USDT")?;
+        let price = message.get("c")?.as_str()?.parse::<f64>().ok()?;
+        Some((symbol.to_string(), price))
+    }
+}
+
+/// Spot price on Binance (USDT pairs)
+pub async fn fetch_spot_price(client: &ProviderClient, symbol: &str) -> Result<PriceEntry, AppError> {
+    // Special case: Gold (XAU) and Silver (XAG) are only available on Binance Futures
+    // Redirect to futures API transparently
+    if symbol.eq_ignore_ascii_case("XAG") || symbol.eq_ignore_ascii_case("XAU") {
+        tracing::info!("Redirecting {} to Binance Futures (not available on Spot)", symbol);
+        return fetch_futures_price(client, symbol).await;
+    }
+
+    // Binance uses BTCUSDT format
+    let pair = format!("{}USDT", symbol.to_uppercase());
+    let url = format!("https://api.binance.com/api/v3/ticker/price?symbol={}", pair);
+    let response = client.get_json(ProviderCall::new("Binance", RATE_LIMIT_KEY, symbol), url, &[]).await?;
+
+    // Binance response format: { "symbol": "BTCUSDT", "price": "94123.50" }
+    let price = parse_ticker_price(&response.data).ok_or_else(|| response.unparsable())?;
+    Ok(response.priced(price, "USDT"))
+}
+
+/// Spot prices of several symbols from the all-tickers endpoint. Symbols without a USDT spot
+/// pair (including XAU/XAG, which only trade as futures) are left out.
+pub async fn fetch_spot_prices(client: &ProviderClient, symbols: &[String]) -> Result<HashMap<String, PriceEntry>, AppError> {
+    // Unlike ?symbols=[...], the full list isn't rejected when one pair doesn't exist
+    let url = "https://api.binance.com/api/v3/ticker/price".to_string();
+    let label = symbols.join(",");
+    let response = client.get_json(ProviderCall::new("Binance", RATE_LIMIT_KEY, &label), url, &[]).await?;
+
+    // Binance response format: [ { "symbol": "BTCUSDT", "price": "94123.50" }, ... ]
+    let tickers = response.data.as_array().ok_or_else(|| response.unparsable())?;
+    let wanted: HashSet<String> = symbols.iter()
+        .map(|s| s.to_uppercase())
+        .filter(|s| s != "XAU" && s != "XAG")
+        .collect();
+    let mut prices = HashMap::new();
+    for ticker in tickers {
+        let Some(symbol) = ticker.get("symbol").and_then(|s| s.as_str()).and_then(|s| s.strip_suffix("USDT")) else { continue };
+        if !wanted.contains(symbol) {
+            continue;
+        }
+        if let Some(price) = parse_ticker_price(ticker) {
+            prices.insert(symbol.to_string(), response.entry_for(symbol, price, "USDT"));
+        }
+    }
+    response.completed();
+    Ok(prices)
+}
+
+/// Perpetual contract price on Binance Futures (shares the spot rate limit)
+pub async fn fetch_futures_price(client: &ProviderClient, symbol: &str) -> Result<PriceEntry, AppError> {
+    let symbol_upper = symbol.to_uppercase();
+    let pair = if symbol_upper.ends_with("USDT") {
+        symbol_upper
+    } else {
+        format!("{}USDT", symbol_upper)
+    };
+    let url = format!("https://fapi.binance.com/fapi/v1/ticker/price?symbol={}", pair);
+    let call = ProviderCall::new("Binance Futures", RATE_LIMIT_KEY, symbol)
+        .logged_as("binance_futures", Some("FUTURES"));
+    let response = client.get_json(call, url, &[]).await?;
+
+    // Binance Futures response: { "symbol": "XAGUSDT", "price": "76.9300", "time": 123... }
+    let price = parse_ticker_price(&response.data).ok_or_else(|| response.unparsable())?;
+    Ok(response.priced(price, "USDT"))
+}
+
+fn parse_ticker_price(data: &serde_json::Value) -> Option<f64> {
+    data.get("price")
+        .and_then(|v| v.as_str())
+        .and_then(|s| s.parse::<f64>().ok())
+}
+
+/// Daily (or intraday for short ranges) closes from Binance Spot klines
+pub async fn fetch_history(client: &ProviderClient, symbol: &str, days: u32) -> Result<Vec<HistoryEntry>, AppError> {
+    // Convert days to interval/limit
+    let interval = if days <= 1 { "5m" } else if days <= 7 { "1h" } else { "1d" };
+    let limit = if days <= 1 { 288 } else if days <= 7 { 168 } else { days };
+
+    let pair = format!("{}USDT", symbol.to_uppercase()); // Assuming USDT pairs for simplicity
+    let url = format!(
+        "https://api.binance.com/api/v3/klines?symbol={}&interval={}&limit={}",
+        pair, interval, limit
+    );
+
+    let response = client.http_for(&[RATE_LIMIT_KEY]).await.get(&url).send().await?;
+    if !response.status().is_success() {
+        return Err(AppError::ExternalApiError("Binance history failed".to_string()));
+    }
+
+    let data: Vec<serde_json::Value> = response.json().await?;
+    let mut history = Vec::new();
+
+    for item in data {
+        if let Some(arr) = item.as_array() {
+            if arr.len() > 4 {
+                let timestamp = arr[0].as_u64().unwrap_or(0);
+                let close_price: f64 = arr[4].as_str().unwrap_or("0").parse().unwrap_or(0.0);
+
+                let date = DateTime::from_timestamp_millis(timestamp as i64)
+                    .unwrap_or_default()
+                    .format("%Y-%m-%d")
+                    .to_string();
+
+                history.push(HistoryEntry { date, price: close_price });
+            }
+        }
+    }
+
+    Ok(history)
+}
+
+/// Spot wallet balances for a user's key (signed USER_DATA endpoint; a read-only key is enough)
+pub async fn fetch_balances(client: &ProviderClient, credentials: &ExchangeCredentials) -> Result<Vec<ExchangeBalance>, AppError> {
+    let query = format!("omitZeroBalances=true&recvWindow=10000&timestamp={}", Utc::now().timestamp_millis());
+    let signature = sign_hmac_sha256(&credentials.api_secret, &query);
+    let url = format!("https://api.binance.com/api/v3/account?{}&signature={}", query, signature);
+    let call = ProviderCall::new("Binance", RATE_LIMIT_KEY, "balances").logged_as("binance_account", None);
+    let response = client.get_json(call, url, &[("X-MBX-APIKEY", &credentials.api_key)]).await?;
+
+    // Binance response format: { "balances": [{ "asset": "BTC", "free": "0.5", "locked": "0.1" }, ...] }
+    let Some(rows) = response.data.get("balances").and_then(|b| b.as_array()) else {
+        return Err(response.fail("Binance account response has no balances".to_string()));
+    };
+    let amount = |row: &serde_json::Value, field: &str| row.get(field)
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(0.0);
+    let balances = rows.iter()
+        .filter_map(|row| Some(ExchangeBalance {
+            asset: row.get("asset")?.as_str()?.to_uppercase(),
+            free: amount(row, "free"),
+            locked: amount(row, "locked"),
+        }))
+        .filter(|b| b.total() > 0.0)
+        .collect();
+    response.completed();
+    Ok(balances)
+}
+
+/// Spot pairs listed on Binance, e.g. "ETHBTC" (every pair's ticker in one request)
+pub async fn fetch_spot_pairs(client: &ProviderClient) -> Result<HashSet<String>, AppError> {
+    let url = "https://api.binance.com/api/v3/ticker/price".to_string();
+    let response = client.get_json(ProviderCall::new("Binance", RATE_LIMIT_KEY, "pairs"), url, &[]).await?;
+
+    // Binance response format: [{ "symbol": "ETHBTC", "price": "0.0345" }, ...]
+    let Some(tickers) = response.data.as_array() else {
+        return Err(response.fail("Binance ticker list is not an array".to_string()));
+    };
+    let pairs = tickers.iter()
+        .filter_map(|t| t.get("symbol").and_then(|s| s.as_str()).map(str::to_string))
+        .collect();
+    response.completed();
+    Ok(pairs)
+}
+
+/// Most trades Binance returns per myTrades request
+pub const ACCOUNT_TRADES_PAGE: usize = 1000;
+
+/// One fill from the account's trade history
+#[derive(Debug, Clone)]
+pub struct AccountTrade {
+    pub id: u64,
+    pub price: f64,
+    pub quantity: f64,
+    pub quote_quantity: f64,
+    pub commission: f64,
+    pub commission_asset: String,
+    pub time: DateTime<Utc>,
+    pub is_buyer: bool,
+}
+
+/// The account's trades in a pair with ids from `from_id` on, oldest first (signed endpoint)
+pub async fn fetch_account_trades(
+    client: &ProviderClient,
+    credentials: &ExchangeCredentials,
+    pair: &str,
+    from_id: u64,
+) -> Result<Vec<AccountTrade>, AppError> {
+    let query = format!(
+        "symbol={}&fromId={}&limit={}&recvWindow=10000&timestamp={}",
+        pair, from_id, ACCOUNT_TRADES_PAGE, Utc::now().timestamp_millis()
+    );
+    let signature = sign_hmac_sha256(&credentials.api_secret, &query);
+    let url = format!("https://api.binance.com/api/v3/myTrades?{}&signature={}", query, signature);
+    let call = ProviderCall::new("Binance", RATE_LIMIT_KEY, pair).logged_as("binance_account", None);
+    let response = client.get_json(call, url, &[("X-MBX-APIKEY", &credentials.api_key)]).await?;
+
+    // Binance response format: [{ "id": 28457, "price": "4.00", "qty": "12.0", "quoteQty": "48.00",
+    // "commission": "10.1", "commissionAsset": "BNB", "time": 1499865549590, "isBuyer": true, ... }]
+    let Some(rows) = response.data.as_array() else {
+        return Err(response.fail(format!("Binance trades for {} are not an array", pair)));
+    };
+    let mut trades = Vec::with_capacity(rows.len());
+    for row in rows {
+        match parse_account_trade(row) {
+            Some(trade) => trades.push(trade),
+            None => return Err(response.fail(format!("Unexpected Binance trade for {}: {}", pair, row))),
+        }
+    }
+    trades.sort_by_key(|t| t.id);
+    response.completed();
+    Ok(trades)
+}
+
+fn parse_account_trade(row: &serde_json::Value) -> Option<AccountTrade> {
+    let amount = |field: &str| row.get(field)
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<f64>().ok());
+    Some(AccountTrade {
+        id: row.get("id")?.as_u64()?,
+        price: amount("price")?,
+        quantity: amount("qty")?,
+        quote_quantity: amount("quoteQty")?,
+        commission: amount("commission").unwrap_or(0.0),
+        commission_asset: row.get("commissionAsset").and_then(|v| v.as_str()).unwrap_or_default().to_uppercase(),
+        time: DateTime::from_timestamp_millis(row.get("time")?.as_i64()?)?,
+        is_buyer: row.get("isBuyer")?.as_bool()?,
+    })
+}
@@ -0,0 +1,122 @@
+use chrono::Utc;
+use futures_util::future::BoxFuture;
+use crate::error::AppError;
+use crate::models::{AssetType, ExchangeBalance, ExchangeCredentials, Market};
+use crate::services::price_service::PriceEntry;
+use super::{sign_hmac_sha256, PriceProvider, ProviderCall, ProviderClient, TickerStream};
+
+const RATE_LIMIT_KEY: &str = "bitkub";
+
+/// Bitkub spot exchange (THB pairs)
+pub struct Bitkub;
+
+impl PriceProvider for Bitkub {
+    fn provider_type(&self) -> &'static str {
+        "bitkub"
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::Crypto]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(fetch_price(client, symbol))
+    }
+}
+
+impl TickerStream for Bitkub {
+    fn market(&self) -> Market {
+        Market::Bitkub
+    }
+
+    fn source(&self) -> &'static str {
+        "bitkub_ws"
+    }
+
+    fn currency(&self) -> &'static str {
+        "THB"
+    }
+
+    /// Streams are named in the path, e.g. .../websocket-api/market.ticker.thb_btc,market.ticker.thb_eth
+    fn url(&self, symbols: &[String]) -> String {
+        let streams: Vec<String> = symbols.iter()
+            .map(|s| format!("market.ticker.thb_{}", s.to_lowercase()))
+            .collect();
+        format!("wss://api.bitkub.com/websocket-api/{}", streams.join(","))
+    }
+
+    fn subscribe(&self, _symbols: &[String]) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn parse(&self, message: &serde_json::Value) -> Option<(String, f64)> {
+        // Ticker: { "stream": "market.ticker.thb_btc", "last": 2904027, ... }
+        let symbol = message.get("stream")?.as_str()?.strip_prefix("market.ticker.thb_")?;
+        let price = message.get("last")?.as_f64()?;
+        Some((symbol.to_uppercase(), price))
+    }
+}
+
+/// Spot price on Bitkub (THB pairs)
+pub async fn fetch_price(client: &ProviderClient, symbol: &str) -> Result<PriceEntry, AppError> {
+    // Bitkub uses THB_BTC format
+    let pair = format!("THB_{}", symbol.to_uppercase());
+    let url = format!("https://api.bitkub.com/api/market/ticker?sym={}", pair);
+    let response = client.get_json(ProviderCall::new("Bitkub", RATE_LIMIT_KEY, symbol), url, &[]).await?;
+
+    // Bitkub response format: { "THB_BTC": { "last": 2904027.00, ... } }
+    let price = response.data
+        .get(&pair)
+        .and_then(|v| v.get("last"))
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| response.unparsable())?;
+
+    Ok(response.priced(price, "THB"))
+}
+
+/// Wallet balances for a user's key (secure v3 endpoint)
+pub async fn fetch_balances(client: &ProviderClient, credentials: &ExchangeCredentials) -> Result<Vec<ExchangeBalance>, AppError> {
+    const PATH: &str = "/api/v3/market/balances";
+    let body = "{}".to_string();
+    let timestamp = Utc::now().timestamp_millis().to_string();
+    // Signed payload: timestamp + method + path + body
+    let signature = sign_hmac_sha256(&credentials.api_secret, &format!("{}POST{}{}", timestamp, PATH, body));
+    let url = format!("https://api.bitkub.com{}", PATH);
+    let call = ProviderCall::new("Bitkub", RATE_LIMIT_KEY, "balances").logged_as("bitkub_account", None);
+    let headers = [
+        ("X-BTK-APIKEY", credentials.api_key.as_str()),
+        ("X-BTK-TIMESTAMP", timestamp.as_str()),
+        ("X-BTK-SIGN", signature.as_str()),
+    ];
+    let response = client.post_json(call, url, &headers, body).await?;
+
+    // Bitkub response format: { "error": 0, "result": { "THB": { "available": 1000.5, "reserved": 0 }, ... } }
+    let error = response.data.get("error").and_then(|e| e.as_i64()).unwrap_or(-1);
+    if error != 0 {
+        return Err(response.fail(format!("Bitkub balances error code {}", error)));
+    }
+    let Some(rows) = response.data.get("result").and_then(|r| r.as_object()) else {
+        return Err(response.fail("Bitkub balances response has no result".to_string()));
+    };
+    let amount = |row: &serde_json::Value, field: &str| row.get(field).and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let balances = rows.iter()
+        .map(|(asset, row)| ExchangeBalance {
+            asset: asset.to_uppercase(),
+            free: amount(row, "available"),
+            locked: amount(row, "reserved"),
+        })
+        .filter(|b| b.total() > 0.0)
+        .collect();
+    response.completed();
+    Ok(balances)
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use futures_util::future::BoxFuture;
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::price_service::PriceEntry;
+use super::{PriceProvider, ProviderCall, ProviderClient};
+
+const RATE_LIMIT_KEY: &str = "coingecko";
+
+/// CoinGecko aggregated prices
+pub struct CoinGecko;
+
+impl PriceProvider for CoinGecko {
+    fn provider_type(&self) -> &'static str {
+        "coingecko"
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::Crypto]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(fetch_price(client, symbol))
+    }
+
+    fn batch_size(&self) -> usize {
+        BATCH_SIZE
+    }
+
+    fn fetch_prices<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbols: &'a [String],
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<HashMap<String, PriceEntry>, AppError>> {
+        Box::pin(fetch_prices(client, symbols))
+    }
+}
+
+/// Coin ids per simple/price request, well within the URL length CoinGecko accepts
+const BATCH_SIZE: usize = 100;
+
+/// THB price from CoinGecko's simple price endpoint
+pub async fn fetch_price(client: &ProviderClient, symbol: &str) -> Result<PriceEntry, AppError> {
+    let coin_id = coin_id(symbol);
+    let url = format!(
+        "{}/simple/price?ids={}&vs_currencies=thb,usd",
+        client.config.coingecko_api_url,
+        coin_id
+    );
+    // CoinGecko Free tier is very strict - block for 60 seconds on a 429
+    let call = ProviderCall::new("CoinGecko", RATE_LIMIT_KEY, symbol).retry_after(60);
+    let response = client.get_json(call, url, &[]).await?;
+
+    let price = response.data
+        .get(&coin_id)
+        .and_then(|v| v.get("thb"))
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| response.unparsable())?;
+
+    Ok(response.priced(price, "THB"))
+}
+
+/// THB prices of several coins from one simple/price request
+pub async fn fetch_prices(client: &ProviderClient, symbols: &[String]) -> Result<HashMap<String, PriceEntry>, AppError> {
+    let ids: Vec<(String, String)> = symbols.iter().map(|s| (s.to_uppercase(), coin_id(s))).collect();
+    let mut unique_ids: Vec<&str> = ids.iter().map(|(_, id)| id.as_str()).collect();
+    unique_ids.sort_unstable();
+    unique_ids.dedup();
+    let url = format!(
+        "{}/simple/price?ids={}&vs_currencies=thb,usd",
+        client.config.coingecko_api_url,
+        unique_ids.join(",")
+    );
+    let label = symbols.join(",");
+    let call = ProviderCall::new("CoinGecko", RATE_LIMIT_KEY, &label).retry_after(60);
+    let response = client.get_json(call, url, &[]).await?;
+
+    let prices = ids.iter()
+        .filter_map(|(symbol, id)| {
+            let price = response.data.get(id)?.get("thb")?.as_f64()?;
+            Some((symbol.clone(), response.entry_for(symbol, price, "THB")))
+        })
+        .collect();
+    response.completed();
+    Ok(prices)
+}
+
+/// Map common crypto symbols to CoinGecko IDs
+pub fn coin_id(symbol: &str) -> String {
+    let symbol_upper = symbol.to_uppercase();
+    match symbol_upper.as_str() {
+        "BTC" => "bitcoin".to_string(),
+        "ETH" => "ethereum".to_string(),
+        "BNB" => "binancecoin".to_string(),
+        "XRP" => "ripple".to_string(),
+        "ADA" => "cardano".to_string(),
+        "SOL" => "solana".to_string(),
+        "DOGE" => "dogecoin".to_string(),
+        "DOT" => "polkadot".to_string(),
+        "MATIC" => "matic-network".to_string(),
+        "AVAX" => "avalanche-2".to_string(),
+        "LINK" => "chainlink".to_string(),
+        "UNI" => "uniswap".to_string(),
+        "ATOM" => "cosmos".to_string(),
+        "LTC" => "litecoin".to_string(),
+        "ETC" => "ethereum-classic".to_string(),
+        "XLM" => "stellar".to_string(),
+        "NEAR" => "near".to_string(),
+        "APT" => "aptos".to_string(),
+        "ARB" => "arbitrum".to_string(),
+        "OP" => "optimism".to_string(),
+        "SUI" => "sui".to_string(),
+        "SEI" => "sei-network".to_string(),
+        "TIA" => "celestia".to_string(),
+        _ => symbol.to_lowercase(),
+    }
+}
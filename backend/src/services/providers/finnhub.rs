@@ -0,0 +1,56 @@
+use futures_util::future::BoxFuture;
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::price_service::PriceEntry;
+use super::{PriceProvider, ProviderCall, ProviderClient};
+
+const RATE_LIMIT_KEY: &str = "finnhub";
+
+/// Finnhub quote API for foreign stocks (needs FINNHUB_API_KEY)
+pub struct Finnhub;
+
+impl PriceProvider for Finnhub {
+    fn provider_type(&self) -> &'static str {
+        "finnhub"
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::ForeignStock]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        _asset_type: &'a AssetType,
+        market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(fetch_price(client, symbol, market))
+    }
+}
+
+/// Latest trade price from Finnhub. Quotes carry no currency, so the market's is used (USD by default).
+pub async fn fetch_price(client: &ProviderClient, symbol: &str, market: Option<&Market>) -> Result<PriceEntry, AppError> {
+    let api_key = client.config.finnhub_api_key.as_deref()
+        .ok_or_else(|| AppError::ExternalApiError("Finnhub is not configured (FINNHUB_API_KEY)".to_string()))?;
+    let symbol_upper = symbol.to_uppercase();
+    // The key goes in a header so it never shows up in logged URLs
+    let url = format!("{}/quote?symbol={}", client.config.finnhub_api_url, urlencoding::encode(&symbol_upper));
+    let call = ProviderCall::new("Finnhub", RATE_LIMIT_KEY, symbol)
+        .logged_as(RATE_LIMIT_KEY, Some("Foreign"));
+    let response = client.get_json(call, url, &[("X-Finnhub-Token", api_key)]).await?;
+
+    // Finnhub response format: { "c": 189.84, "d": 1.2, "dp": 0.64, "pc": 188.64, "t": 1700000000 }
+    // Unknown symbols come back as all zeros rather than an error
+    let price = response.data
+        .get("c")
+        .and_then(|v| v.as_f64())
+        .filter(|p| *p > 0.0)
+        .ok_or_else(|| response.fail(format!("Finnhub has no quote for {}", symbol_upper)))?;
+
+    Ok(response.priced(price, market.map(|m| m.default_currency()).unwrap_or("USD")))
+}
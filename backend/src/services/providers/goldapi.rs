@@ -0,0 +1,74 @@
+use chrono::Utc;
+use futures_util::future::BoxFuture;
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::price_service::PriceEntry;
+use super::thai_gold::ThaiGoldQuote;
+use super::{PriceProvider, ProviderCall, ProviderClient};
+
+const RATE_LIMIT_KEY: &str = "goldapi";
+
+/// Grams of gold in one baht-weight of a Thai gold bar
+const GRAMS_PER_BAHT: f64 = 15.244;
+
+const TROY_OUNCE_GRAMS: f64 = 31.1034768;
+
+/// Purity of Thai standard gold
+const THAI_PURITY: f64 = 0.965;
+
+/// GoldAPI.io spot prices converted to Thai bar prices (needs GOLDAPI_API_KEY)
+pub struct GoldApi;
+
+impl PriceProvider for GoldApi {
+    fn provider_type(&self) -> &'static str {
+        "goldapi"
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::Gold]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(async move { Ok(fetch_quote(client).await?.entry(symbol)) })
+    }
+}
+
+/// THB spot price of 96.5% gold per baht-weight. This is the metal value only: shop prices
+/// carry a premium of a few hundred baht, so it is a fallback for the association quote.
+pub async fn fetch_quote(client: &ProviderClient) -> Result<ThaiGoldQuote, AppError> {
+    let api_key = client.config.goldapi_api_key.as_deref()
+        .ok_or_else(|| AppError::ExternalApiError("GoldAPI is not configured (GOLDAPI_API_KEY)".to_string()))?;
+    let url = format!("{}/XAU/THB", client.config.goldapi_api_url.trim_end_matches('/'));
+    let call = ProviderCall::new("GoldAPI", RATE_LIMIT_KEY, "GOLD96.5").logged_as(RATE_LIMIT_KEY, Some("gold"));
+    let response = client.get_json(call, url, &[("x-access-token", api_key)]).await?;
+
+    // GoldAPI response format: { "price": 85000.0, "bid": 84990.0, "ask": 85010.0, "price_gram_24k": 2732.8, ... }
+    // with prices per troy ounce except the per-gram fields
+    let per_ounce = |field: &str| response.data.get(field).and_then(|v| v.as_f64()).filter(|p| *p > 0.0);
+    let Some(price) = per_ounce("price") else {
+        return Err(response.unparsable());
+    };
+    let per_baht = |ounce_price: f64| ounce_price / TROY_OUNCE_GRAMS * GRAMS_PER_BAHT * THAI_PURITY;
+    let bar_buy = per_baht(per_ounce("bid").unwrap_or(price));
+    let bar_sell = per_baht(per_ounce("ask").unwrap_or(price));
+
+    response.succeeded(bar_sell, "THB");
+    Ok(ThaiGoldQuote {
+        bar_buy,
+        bar_sell,
+        ornament_buy: None,
+        ornament_sell: None,
+        source: RATE_LIMIT_KEY.to_string(),
+        updated_at: Utc::now(),
+    })
+}
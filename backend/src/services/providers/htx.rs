@@ -0,0 +1,51 @@
+use futures_util::future::BoxFuture;
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::price_service::PriceEntry;
+use super::{PriceProvider, ProviderCall, ProviderClient};
+
+const RATE_LIMIT_KEY: &str = "htx";
+
+/// HTX (formerly Huobi) spot exchange
+pub struct Htx;
+
+impl PriceProvider for Htx {
+    fn provider_type(&self) -> &'static str {
+        "htx"
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::Crypto]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(fetch_price(client, symbol))
+    }
+}
+
+/// Spot price on HTX, formerly Huobi (USDT pairs)
+pub async fn fetch_price(client: &ProviderClient, symbol: &str) -> Result<PriceEntry, AppError> {
+    // HTX uses btcusdt format (lowercase)
+    let pair = format!("{}usdt", symbol.to_lowercase());
+    let url = format!("https://api.huobi.pro/market/detail/merged?symbol={}", pair);
+    let response = client.get_json(ProviderCall::new("HTX", RATE_LIMIT_KEY, symbol), url, &[]).await?;
+
+    // HTX response format: { "status": "ok", "tick": { "close": 91116.86, ... } }
+    let price = response.data
+        .get("tick")
+        .and_then(|t| t.get("close"))
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| response.unparsable())?;
+
+    Ok(response.priced(price, "USDT"))
+}
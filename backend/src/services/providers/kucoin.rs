@@ -0,0 +1,52 @@
+use futures_util::future::BoxFuture;
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::price_service::PriceEntry;
+use super::{PriceProvider, ProviderCall, ProviderClient};
+
+const RATE_LIMIT_KEY: &str = "kucoin";
+
+/// KuCoin spot exchange
+pub struct Kucoin;
+
+impl PriceProvider for Kucoin {
+    fn provider_type(&self) -> &'static str {
+        "kucoin"
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::Crypto]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(fetch_price(client, symbol))
+    }
+}
+
+/// Spot price on KuCoin (USDT pairs)
+pub async fn fetch_price(client: &ProviderClient, symbol: &str) -> Result<PriceEntry, AppError> {
+    // KuCoin uses BTC-USDT format
+    let pair = format!("{}-USDT", symbol.to_uppercase());
+    let url = format!("https://api.kucoin.com/api/v1/market/orderbook/level1?symbol={}", pair);
+    let response = client.get_json(ProviderCall::new("KuCoin", RATE_LIMIT_KEY, symbol), url, &[]).await?;
+
+    // KuCoin response format: { "code": "200000", "data": { "price": "91136", ... } }
+    let price = response.data
+        .get("data")
+        .and_then(|d| d.get("price"))
+        .and_then(|v| v.as_str())
+        .and_then(|s| s.parse::<f64>().ok())
+        .ok_or_else(|| response.unparsable())?;
+
+    Ok(response.priced(price, "USDT"))
+}
@@ -0,0 +1,136 @@
+//! Static fallback prices for when no provider can serve a symbol
+
+use std::collections::HashMap;
+use chrono::Utc;
+use crate::error::AppError;
+use crate::models::Market;
+use crate::services::price_service::PriceEntry;
+
+fn entry(symbol: &str, price: f64, currency: &str) -> PriceEntry {
+    PriceEntry {
+        symbol: symbol.to_uppercase(),
+        price,
+        currency: currency.to_string(),
+        updated_at: Utc::now(),
+        source: Some("mock".to_string()),
+    }
+}
+
+/// TFEX contracts (THB)
+pub fn tfex_price(symbol: &str) -> Result<PriceEntry, AppError> {
+    tracing::debug!("Using mock price for TFEX symbol: {}", symbol);
+
+    let mock_prices: HashMap<&str, f64> = [
+        // SET50 Index Futures (S50) - trades around 880-930, multiplier 200
+        ("S50", 925.00),
+        ("S50H24", 920.00), ("S50M24", 922.00), ("S50U24", 924.00), ("S50Z24", 926.00),
+        ("S50H25", 925.00), ("S50M25", 927.00), ("S50U25", 929.00), ("S50Z25", 931.00),
+        ("S50H26", 928.00), ("S50M26", 930.00), ("S50U26", 932.00), ("S50Z26", 934.00),
+        // SET50 Value Futures (SVF) - trades around 40-100, multiplier 3000
+        // ("SVF", 95.00),
+        // ("SVFH24", 45.00), ("SVFM24", 50.00), ("SVFU24", 55.00), ("SVFZ24", 60.00),
+        // ("SVFH25", 70.00), ("SVFM25", 75.00), ("SVFU25", 80.00), ("SVFZ25", 85.00),
+        // ("SVFH26", 95.00), ("SVFM26", 98.00),
+        // Gold Futures (10 Baht)
+        ("GFH24", 35200.0), ("GFM24", 35300.0), ("GFU24", 35400.0), ("GFZ24", 35500.0),
+        ("GFH25", 35600.0), ("GFM25", 35700.0), ("GFU25", 35800.0), ("GFZ25", 35900.0),
+        ("GFH26", 36000.0), ("GFM26", 36100.0),
+        // Gold-D (50 Baht)
+        ("GDH24", 176000.0), ("GDM24", 176500.0), ("GDU24", 177000.0), ("GDZ24", 177500.0),
+        ("GDH25", 178000.0), ("GDM25", 178500.0), ("GDU25", 179000.0), ("GDZ25", 179500.0),
+        ("GDH26", 180000.0), ("GDM26", 180500.0),
+        // Silver Futures (SV) - TFEX Silver, trades in THB
+        // ("SVH24", 955.0), ("SVM24", 960.0), ("SVU24", 965.0), ("SVZ24", 970.0),
+        // ("SVH25", 975.0), ("SVM25", 980.0), ("SVH26", 990.0),
+        // USD Futures
+        ("USDH24", 34.50), ("USDM24", 34.55), ("USDU24", 34.60), ("USDZ24", 34.65),
+        ("USDH25", 34.70), ("USDM25", 34.75), ("USDU25", 34.80), ("USDZ25", 34.85),
+        ("USDH26", 34.90), ("USDM26", 34.95),
+        // Sector Futures
+        ("BANKH24", 480.0), ("BANKM24", 482.0), ("ENRGH24", 1850.0), ("ENRGM24", 1855.0),
+        // Brent Crude Oil Futures
+        ("BRNH24", 2750.0), ("BRNM24", 2760.0), ("BRNU24", 2770.0), ("BRNZ24", 2780.0),
+        ("BRNH25", 2790.0), ("BRNM25", 2800.0), ("BRNH26", 2820.0), ("BRNM26", 2830.0),
+        // Rubber Futures
+        ("TSRH24", 56.0), ("TSRM24", 56.5), ("TSRU24", 57.0), ("TSRZ24", 57.5),
+        ("TSRH25", 58.0), ("TSRM25", 58.5),
+    ].into_iter().collect();
+
+    let price = mock_prices
+        .get(symbol)
+        .copied()
+        .ok_or_else(|| AppError::ExternalApiError(format!("Price data not available for {}", symbol)))?;
+
+    Ok(entry(symbol, price, "THB"))
+}
+
+/// Popular foreign stocks; anything else gets a placeholder in the market's currency
+pub fn foreign_stock_price(symbol: &str, market: Option<&Market>) -> PriceEntry {
+    tracing::debug!("Using mock price for foreign stock: {}", symbol);
+
+    let mock_prices: HashMap<&str, (f64, &str)> = [
+        ("AAPL", (175.50, "USD")), ("MSFT", (378.25, "USD")),
+        ("GOOGL", (141.80, "USD")), ("NVDA", (495.00, "USD")),
+        ("TSLA", (248.50, "USD")), ("META", (355.20, "USD")),
+    ].into_iter().collect();
+
+    let (price, currency) = mock_prices
+        .get(symbol)
+        .copied()
+        .unwrap_or((100.0, market.map(|m| m.default_currency()).unwrap_or("USD")));
+
+    entry(symbol, price, currency)
+}
+
+/// Gold and other precious metals (XAU = per troy oz, Thai gold per baht weight)
+pub fn gold_price(symbol: &str) -> PriceEntry {
+    tracing::warn!(
+        "Using mock price for gold {}. Real API fetch failed or not supported.",
+        symbol
+    );
+
+    let mock_prices: HashMap<&str, (f64, &str)> = [
+        // International gold (per troy oz)
+        ("XAU", (2025.50, "USD")),      // Gold spot USD
+        ("XAUUSD", (2025.50, "USD")),   // Gold vs USD
+        ("XAUTHB", (72500.00, "THB")),  // Gold vs THB (per oz)
+        // Thai gold (per baht weight = 15.244 grams)
+        ("GOLD", (35450.00, "THB")),    // Gold general
+        ("GOLD96.5", (35450.00, "THB")), // 96.5% purity bar
+        ("GOLD99.99", (42500.00, "THB")), // 99.99% purity
+        // Other precious metals
+        ("XAG", (23.85, "USD")),        // Silver spot
+        ("XPT", (920.00, "USD")),       // Platinum
+        ("XPD", (1050.00, "USD")),      // Palladium
+    ].into_iter().collect();
+
+    let (price, currency) = mock_prices
+        .get(symbol.to_uppercase().as_str())
+        .copied()
+        .unwrap_or((2000.0, "USD"));
+
+    entry(symbol, price, currency)
+}
+
+/// Commodity futures (USD)
+pub fn commodity_price(symbol: &str) -> PriceEntry {
+    tracing::warn!("Using mock price for commodity {}.", symbol);
+
+    let mock_prices: HashMap<&str, (f64, &str)> = [
+        ("CL", (75.50, "USD")),   // Crude Oil
+        ("NG", (2.85, "USD")),    // Natural Gas
+        ("GC", (2025.00, "USD")), // Gold Futures
+        ("SI", (23.50, "USD")),   // Silver Futures
+        ("HG", (3.85, "USD")),    // Copper
+        ("ZC", (485.00, "USD")),  // Corn
+        ("ZS", (1250.00, "USD")), // Soybeans
+        ("ZW", (625.00, "USD")),  // Wheat
+    ].into_iter().collect();
+
+    let (price, currency) = mock_prices
+        .get(symbol.to_uppercase().as_str())
+        .copied()
+        .unwrap_or((100.0, "USD"));
+
+    entry(symbol, price, currency)
+}
@@ -0,0 +1,543 @@
+//! Upstream price providers.
+//!
+//! Each module only knows its API's URL scheme and response format. Rate limiting,
+//! timing, 429 handling, conditional requests and api_call_logs entries are shared through
+//! [`ProviderClient`]. Providers that quote a single price per symbol implement
+//! [`PriceProvider`] and are looked up by their api_providers `provider_type` in
+//! [`PriceProviders`]; those with a multi-symbol endpoint also answer [`PriceProvider::fetch_prices`]
+//! for the price job's batches. Exchanges with a public websocket ticker feed also implement
+//! [`TickerStream`]; those with a private account API also fetch balances with a user's key.
+
+pub mod bitkub;
+pub mod binance;
+pub mod okx;
+pub mod kucoin;
+pub mod htx;
+pub mod coingecko;
+pub mod yahoo;
+pub mod finnhub;
+pub mod thai_gold;
+pub mod goldapi;
+pub mod mock;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use chrono::Utc;
+use futures_util::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use sha2::Sha256;
+use tokio::sync::RwLock;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::{ApiProvider, AssetType, CreateApiCallLogRequest, Market};
+use crate::services::pocketbase::PocketBaseClient;
+use crate::services::price_service::PriceEntry;
+use crate::services::rate_limiter::{RateLimitInfo, RateLimiter};
+
+/// A source of current prices
+pub trait PriceProvider: Send + Sync {
+    /// `provider_type` in api_providers, e.g. "binance"
+    fn provider_type(&self) -> &'static str;
+
+    /// Other api_providers types served by this provider
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType];
+
+    /// Rate limiter bucket its requests count against
+    fn rate_limit_key(&self) -> &'static str;
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        asset_type: &'a AssetType,
+        market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>>;
+
+    fn supports(&self, asset_type: &AssetType) -> bool {
+        self.supported_asset_types().contains(asset_type)
+    }
+
+    /// Most symbols one [`fetch_prices`](Self::fetch_prices) request takes; 0 when the API has
+    /// no multi-symbol endpoint
+    fn batch_size(&self) -> usize {
+        0
+    }
+
+    /// Prices of several symbols in one request, by upper-case symbol. Symbols the answer
+    /// doesn't cover are left out; callers fetch those one at a time.
+    fn fetch_prices<'a>(
+        &'a self,
+        _client: &'a ProviderClient,
+        _symbols: &'a [String],
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<HashMap<String, PriceEntry>, AppError>> {
+        Box::pin(async { Ok(HashMap::new()) })
+    }
+}
+
+/// A public websocket ticker feed. Connections, reconnects and symbol changes are handled
+/// by `ticker_feeds`; implementations only describe the exchange's protocol.
+pub trait TickerStream: Send + Sync {
+    /// Market whose prices the feed carries
+    fn market(&self) -> Market;
+
+    /// Written to `PriceEntry::source`, e.g. "binance_ws"
+    fn source(&self) -> &'static str;
+
+    /// Quote currency of the streamed pairs
+    fn currency(&self) -> &'static str;
+
+    /// URL to connect to; some exchanges take the streams in the URL
+    fn url(&self, symbols: &[String]) -> String;
+
+    /// Text frames sent after connecting to subscribe to the symbols
+    fn subscribe(&self, symbols: &[String]) -> Vec<String>;
+
+    /// (symbol, last price) from a ticker message; None for acks and other events
+    fn parse(&self, message: &serde_json::Value) -> Option<(String, f64)>;
+
+    /// Text frame the exchange expects periodically to keep the connection open
+    fn keepalive(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+/// Exchanges with a websocket ticker feed
+pub fn ticker_streams() -> Vec<Arc<dyn TickerStream>> {
+    vec![Arc::new(binance::Binance), Arc::new(okx::Okx), Arc::new(bitkub::Bitkub)]
+}
+
+/// Registered price providers by `provider_type`
+#[derive(Clone)]
+pub struct PriceProviders {
+    providers: Vec<Arc<dyn PriceProvider>>,
+}
+
+impl Default for PriceProviders {
+    fn default() -> Self {
+        Self {
+            providers: vec![
+                Arc::new(binance::Binance),
+                Arc::new(bitkub::Bitkub),
+                Arc::new(okx::Okx),
+                Arc::new(kucoin::Kucoin),
+                Arc::new(htx::Htx),
+                Arc::new(coingecko::CoinGecko),
+                Arc::new(yahoo::YahooFinance),
+                Arc::new(finnhub::Finnhub),
+                Arc::new(thai_gold::ThaiGold),
+                Arc::new(goldapi::GoldApi),
+            ],
+        }
+    }
+}
+
+impl PriceProviders {
+    /// Add a provider, replacing any registered under the same `provider_type`
+    pub fn register(&mut self, provider: Arc<dyn PriceProvider>) {
+        self.providers.retain(|p| p.provider_type() != provider.provider_type());
+        self.providers.push(provider);
+    }
+
+    pub fn get(&self, provider_type: &str) -> Option<&Arc<dyn PriceProvider>> {
+        self.providers.iter()
+            .find(|p| p.provider_type() == provider_type || p.aliases().contains(&provider_type))
+    }
+
+    /// Exchange-specific provider for a crypto market
+    pub fn for_market(&self, market: &Market) -> Option<&Arc<dyn PriceProvider>> {
+        let provider_type = match market {
+            Market::Bitkub => "bitkub",
+            Market::Binance => "binance",
+            Market::Okx => "okx",
+            Market::Kucoin => "kucoin",
+            Market::Htx => "htx",
+            _ => return None,
+        };
+        self.get(provider_type)
+    }
+}
+
+/// HTTP client shared by all providers, with the rate limiter and call log attached
+#[derive(Clone)]
+pub struct ProviderClient {
+    pub(crate) http: reqwest::Client,
+    pub(crate) config: Config,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) pb_client: Option<PocketBaseClient>,
+    validators: Arc<RwLock<HashMap<String, CachedValidators>>>,
+    /// Clients for providers with their own `proxy_url`, by provider_type
+    proxied: Arc<RwLock<HashMap<String, reqwest::Client>>>,
+}
+
+/// Most URLs remembered for conditional requests before the store is reset
+const MAX_CACHED_VALIDATORS: usize = 2000;
+
+/// ETag / Last-Modified from the last 200 for a URL, with the body to reuse on a 304
+#[derive(Clone)]
+struct CachedValidators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: serde_json::Value,
+}
+
+/// How a provider response body is read
+#[derive(Clone, Copy)]
+enum BodyFormat {
+    Json,
+    Text,
+}
+
+impl BodyFormat {
+    fn accept(&self) -> &'static str {
+        match self {
+            BodyFormat::Json => "application/json",
+            BodyFormat::Text => "text/html,text/plain;q=0.9,*/*;q=0.8",
+        }
+    }
+}
+
+/// Identifies one provider request for rate limiting and logging
+pub struct ProviderCall<'a> {
+    /// Name used in log lines and error messages, e.g. "Binance Futures"
+    pub name: &'a str,
+    /// Rate limiter bucket
+    pub api: &'a str,
+    /// `provider_type` written to api_call_logs
+    pub log_as: &'a str,
+    pub market_id: Option<&'a str>,
+    pub symbol: &'a str,
+    /// Block time after a 429 without a Retry-After header (rate limiter default otherwise)
+    pub retry_after: Option<u64>,
+}
+
+impl<'a> ProviderCall<'a> {
+    /// Call logged under the rate limiter bucket name with no market
+    pub fn new(name: &'a str, api: &'a str, symbol: &'a str) -> Self {
+        Self { name, api, log_as: api, market_id: None, symbol, retry_after: None }
+    }
+
+    pub fn logged_as(mut self, log_as: &'a str, market_id: Option<&'a str>) -> Self {
+        self.log_as = log_as;
+        self.market_id = market_id;
+        self
+    }
+
+    pub fn retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+}
+
+/// Successful response, still attached to its call so parse results can be logged
+pub struct ProviderResponse<'a> {
+    client: &'a ProviderClient,
+    call: ProviderCall<'a>,
+    url: String,
+    elapsed_ms: u64,
+    pub data: serde_json::Value,
+}
+
+impl ProviderClient {
+    pub fn new(config: Config) -> Self {
+        Self {
+            http: crate::services::http_client::clients(&config).providers.clone(),
+            config,
+            rate_limiter: None,
+            pb_client: None,
+            validators: Arc::new(RwLock::new(HashMap::new())),
+            proxied: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Rebuild per-provider proxy routing from api_providers. When several enabled providers
+    /// share a provider_type, the highest-priority one's proxy wins.
+    pub async fn load_proxies(&self, providers: &[ApiProvider]) {
+        let mut ordered: Vec<&ApiProvider> = providers.iter().filter(|p| p.enabled).collect();
+        ordered.sort_by_key(|p| p.priority);
+
+        let mut proxied = HashMap::new();
+        for provider in ordered {
+            let Some(proxy_url) = provider.proxy_url.as_deref() else { continue };
+            if proxied.contains_key(&provider.provider_type) {
+                continue;
+            }
+            match crate::services::http_client::proxied(&self.config, proxy_url) {
+                Ok(client) => {
+                    proxied.insert(provider.provider_type.clone(), client);
+                }
+                Err(e) => tracing::warn!("⚠️ Ignoring proxy of provider {}: {}", provider.provider_name, e),
+            }
+        }
+
+        tracing::info!("🌐 Loaded {} per-provider proxies", proxied.len());
+        *self.proxied.write().await = proxied;
+    }
+
+    /// Client for a provider: its own proxy when it has one, else the shared providers client
+    pub(crate) async fn http_for(&self, provider_types: &[&str]) -> reqwest::Client {
+        let proxied = self.proxied.read().await;
+        provider_types.iter()
+            .find_map(|provider_type| proxied.get(*provider_type))
+            .cloned()
+            .unwrap_or_else(|| self.http.clone())
+    }
+
+    /// GET a JSON document: checks the rate limit, times the request, records it with the
+    /// limiter, and turns 429s, error statuses and malformed bodies into logged errors.
+    ///
+    /// When an earlier response for the same URL carried an ETag or Last-Modified header the
+    /// request is made conditional; a 304 reuses the stored body and is not counted against
+    /// the local rate limit.
+    pub async fn get_json<'a>(
+        &'a self,
+        call: ProviderCall<'a>,
+        url: String,
+        headers: &[(&str, &str)],
+    ) -> Result<ProviderResponse<'a>, AppError> {
+        self.send(call, url, headers, None, BodyFormat::Json).await
+    }
+
+    /// Like [`get_json`](Self::get_json) for pages that are scraped rather than parsed as JSON.
+    /// The body is returned as a JSON string in `data`.
+    pub async fn get_text<'a>(
+        &'a self,
+        call: ProviderCall<'a>,
+        url: String,
+        headers: &[(&str, &str)],
+    ) -> Result<ProviderResponse<'a>, AppError> {
+        self.send(call, url, headers, None, BodyFormat::Text).await
+    }
+
+    /// POST a JSON body (signed exchange endpoints). Handled like [`get_json`](Self::get_json)
+    /// except that the request is never made conditional.
+    pub async fn post_json<'a>(
+        &'a self,
+        call: ProviderCall<'a>,
+        url: String,
+        headers: &[(&str, &str)],
+        body: String,
+    ) -> Result<ProviderResponse<'a>, AppError> {
+        self.send(call, url, headers, Some(body), BodyFormat::Json).await
+    }
+
+    /// GET, or POST when there is a body
+    async fn send<'a>(
+        &'a self,
+        call: ProviderCall<'a>,
+        url: String,
+        headers: &[(&str, &str)],
+        body: Option<String>,
+        format: BodyFormat,
+    ) -> Result<ProviderResponse<'a>, AppError> {
+        // Label the enclosing price_fetch span; in a fallback chain the last provider tried wins
+        tracing::Span::current().record("provider", call.log_as);
+        self.check_rate_limit(call.api).await?;
+        tracing::info!("Fetching {} price from {}: {}", call.symbol, call.name, crate::services::diagnostics::redact_url(&url));
+
+        let start = Instant::now();
+        let http = self.http_for(&[call.log_as, call.api]).await;
+        let conditional = body.is_none();
+        let mut request = match body {
+            Some(body) => http.post(&url).header("Content-Type", "application/json").body(body),
+            None => http.get(&url),
+        };
+        request = request
+            .header("Accept", format.accept())
+            .timeout(self.config.provider_timeout(call.api));
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let cached = match conditional {
+            true => self.validators.read().await.get(&url).cloned(),
+            false => None,
+        };
+        if let Some(ref cached) = cached {
+            if let Some(ref etag) = cached.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(ref last_modified) = cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let result = request.send().await;
+        let not_modified = matches!(&result, Ok(r) if r.status() == reqwest::StatusCode::NOT_MODIFIED);
+        // A 304 does not count against the quota and a failed send never reached the API
+        if not_modified || result.is_err() {
+            self.release_api_call(call.api).await;
+        } else {
+            self.record_api_call(call.api).await;
+        }
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+
+        let mut response = ProviderResponse { client: self, call, url, elapsed_ms, data: serde_json::Value::Null };
+        let http_response = match result {
+            Ok(r) => r,
+            Err(e) => return Err(response.fail(format!("{} request failed: {}", response.call.name, e))),
+        };
+
+        if http_response.status().as_u16() == 429 {
+            let retry_after = http_response.headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .or(response.call.retry_after);
+            self.record_rate_limit_hit(response.call.api, retry_after).await;
+            response.log("error", None, None, Some("Rate limit exceeded"));
+            tracing::warn!("🚫 {} returned 429 for {}", response.call.name, response.call.symbol);
+            // The limiter blocks the bucket for 60s when the provider does not say otherwise
+            let resets_at = Utc::now() + chrono::Duration::seconds(retry_after.unwrap_or(60) as i64);
+            return Err(AppError::RateLimited(RateLimitInfo::new(response.call.api, None, resets_at)));
+        }
+
+        if not_modified {
+            if let Some(cached) = cached {
+                tracing::debug!("♻️ {} not modified for {}", response.call.name, response.call.symbol);
+                response.data = cached.body;
+                return Ok(response);
+            }
+        }
+
+        if !http_response.status().is_success() {
+            let status = http_response.status();
+            return Err(response.fail(format!("{} API error: {}", response.call.name, status)));
+        }
+
+        let header = |name| http_response.headers().get(name)
+            .and_then(|v: &reqwest::header::HeaderValue| v.to_str().ok())
+            .map(|v| v.to_string());
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+
+        let body = match format {
+            BodyFormat::Json => http_response.json::<serde_json::Value>().await,
+            BodyFormat::Text => http_response.text().await.map(serde_json::Value::String),
+        };
+        match body {
+            Ok(data) => {
+                if conditional && (etag.is_some() || last_modified.is_some()) {
+                    self.store_validators(&response.url, CachedValidators { etag, last_modified, body: data.clone() }).await;
+                }
+                response.data = data;
+                Ok(response)
+            }
+            Err(e) => Err(response.fail(format!("Invalid {} response for {}: {}", response.call.name, response.call.symbol, e))),
+        }
+    }
+
+    async fn store_validators(&self, url: &str, validators: CachedValidators) {
+        let mut store = self.validators.write().await;
+        if store.len() >= MAX_CACHED_VALIDATORS && !store.contains_key(url) {
+            store.clear();
+        }
+        store.insert(url.to_string(), validators);
+    }
+
+    /// Check rate limit and reserve a slot before making API call
+    async fn check_rate_limit(&self, api_name: &str) -> Result<(), AppError> {
+        if let Some(ref limiter) = self.rate_limiter {
+            limiter.acquire(api_name).await.map_err(AppError::RateLimited)?;
+        }
+        Ok(())
+    }
+
+    /// Record successful API call
+    async fn record_api_call(&self, api_name: &str) {
+        if let Some(ref limiter) = self.rate_limiter {
+            limiter.record_request(api_name).await;
+        }
+    }
+
+    /// Give back the slot of an API call that did not count
+    async fn release_api_call(&self, api_name: &str) {
+        if let Some(ref limiter) = self.rate_limiter {
+            limiter.release(api_name).await;
+        }
+    }
+
+    /// Record rate limit hit (429 response)
+    async fn record_rate_limit_hit(&self, api_name: &str, retry_after: Option<u64>) {
+        if let Some(ref limiter) = self.rate_limiter {
+            limiter.record_rate_limit_hit(api_name, retry_after).await;
+        }
+    }
+}
+
+impl ProviderResponse<'_> {
+    /// Log the parsed price and build the cache entry
+    pub fn priced(&self, price: f64, currency: &str) -> PriceEntry {
+        self.succeeded(price, currency);
+        PriceEntry {
+            symbol: self.call.symbol.to_uppercase(),
+            price,
+            currency: currency.to_string(),
+            updated_at: Utc::now(),
+            source: Some(self.call.log_as.to_string()),
+        }
+    }
+
+    /// Log the parsed price when the caller builds its own result from the response
+    pub fn succeeded(&self, price: f64, currency: &str) {
+        tracing::info!("{} price for {}: {} {}", self.call.name, self.call.symbol, price, currency);
+        self.log("success", Some(price), Some(currency), None);
+    }
+
+    /// Cache entry for one symbol of a batch response; the call is logged once with `completed`
+    pub fn entry_for(&self, symbol: &str, price: f64, currency: &str) -> PriceEntry {
+        PriceEntry {
+            symbol: symbol.to_uppercase(),
+            price,
+            currency: currency.to_string(),
+            updated_at: Utc::now(),
+            source: Some(self.call.log_as.to_string()),
+        }
+    }
+
+    /// Log a successful call that returns something other than a price (e.g. account balances)
+    pub fn completed(&self) {
+        tracing::info!("{} {} fetched", self.call.name, self.call.symbol);
+        self.log("success", None, None, None);
+    }
+
+    /// Log a failure for this call and turn it into an error
+    pub fn fail(&self, error_msg: String) -> AppError {
+        self.log("error", None, None, Some(&error_msg));
+        AppError::ExternalApiError(error_msg)
+    }
+
+    /// Error for a response that did not contain a usable price
+    pub fn unparsable(&self) -> AppError {
+        self.fail(format!("Could not parse {} price for {}", self.call.name, self.call.symbol))
+    }
+
+    /// Log the call to PocketBase (fire-and-forget)
+    fn log(&self, status: &str, price: Option<f64>, currency: Option<&str>, error_message: Option<&str>) {
+        if let Some(ref pb_client) = self.client.pb_client {
+            pb_client.log_api_call(CreateApiCallLogRequest {
+                provider_type: self.call.log_as.to_string(),
+                market_id: self.call.market_id.map(|s| s.to_string()),
+                symbol: self.call.symbol.to_string(),
+                status: status.to_string(),
+                response_time_ms: self.elapsed_ms,
+                price,
+                currency: currency.map(|s| s.to_string()),
+                error_message: error_message.map(|s| s.to_string()),
+                request_url: Some(self.url.clone()),
+            });
+        }
+    }
+}
+
+/// Hex HMAC-SHA256 signature, as the exchanges' private endpoints expect
+pub(crate) fn sign_hmac_sha256(secret: &str, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
@@ -0,0 +1,98 @@
+use futures_util::future::BoxFuture;
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::price_service::PriceEntry;
+use super::{PriceProvider, ProviderCall, ProviderClient, TickerStream};
+
+const RATE_LIMIT_KEY: &str = "okx";
+
+/// OKX spot exchange
+pub struct Okx;
+
+impl PriceProvider for Okx {
+    fn provider_type(&self) -> &'static str {
+        "okx"
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::Crypto]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(fetch_price(client, symbol))
+    }
+}
+
+impl TickerStream for Okx {
+    fn market(&self) -> Market {
+        Market::Okx
+    }
+
+    fn source(&self) -> &'static str {
+        "okx_ws"
+    }
+
+    fn currency(&self) -> &'static str {
+        "USD"
+    }
+
+    fn url(&self, _symbols: &[String]) -> String {
+        "wss://ws.okx.com:8443/ws/v5/public".to_string()
+    }
+
+    fn subscribe(&self, symbols: &[String]) -> Vec<String> {
+        let args: Vec<serde_json::Value> = symbols.iter()
+            .map(|s| serde_json::json!({ "channel": "tickers", "instId": format!("{}-USDT", s.to_uppercase()) }))
+            .collect();
+        vec![serde_json::json!({ "op": "subscribe", "args": args }).to_string()]
+    }
+
+    fn parse(&self, message: &serde_json::Value) -> Option<(String, f64)> {
+        // Ticker push: { "arg": { "channel": "tickers", ... }, "data": [{ "instId": "BTC-USDT", "last": "94123.5" }] }
+        let item = message.get("data")?.as_array()?.first()?;
+        let symbol = item.get("instId")?.as_str()?.strip_suffix("-USDT")?;
+        let price = item.get("last")?.as_str()?.parse::<f64>().ok()?;
+        Some((symbol.to_string(), price))
+    }
+
+    /// OKX drops connections that stay silent for 30 seconds
+    fn keepalive(&self) -> Option<&'static str> {
+        Some("ping")
+    }
+}
+
+/// Spot price on OKX; USDT pairs are reported as USD since OKX transactions are booked in USD
+pub async fn fetch_price(client: &ProviderClient, symbol: &str) -> Result<PriceEntry, AppError> {
+    // OKX uses BTC-USDT format
+    let inst_id = format!("{}-USDT", symbol.to_uppercase());
+    let url = format!("https://www.okx.com/api/v5/market/ticker?instId={}", inst_id);
+    let response = client.get_json(ProviderCall::new("OKX", RATE_LIMIT_KEY, symbol), url, &[]).await?;
+
+    // OKX response format: { "code": "0", "data": [{ "last": "94123.5", ... }] }
+    if let Some(code) = response.data.get("code").and_then(|c| c.as_str()) {
+        if code != "0" {
+            let msg = response.data.get("msg").and_then(|m| m.as_str()).unwrap_or("Unknown error");
+            return Err(response.fail(format!("OKX API error code {}: {}", code, msg)));
+        }
+    }
+    let price = response.data
+        .get("data")
+        .and_then(|d| d.as_array())
+        .and_then(|arr| arr.first())
+        .and_then(|item| item.get("last"))
+        .and_then(|v| v.as_str())
+        .and_then(|s| s.parse::<f64>().ok())
+        .ok_or_else(|| response.unparsable())?;
+
+    Ok(response.priced(price, "USD"))
+}
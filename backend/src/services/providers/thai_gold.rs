@@ -0,0 +1,178 @@
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::price_service::PriceEntry;
+use super::{PriceProvider, ProviderCall, ProviderClient};
+
+const RATE_LIMIT_KEY: &str = "thaigold";
+
+/// Symbol the association quote is fetched and logged under
+const QUOTE_SYMBOL: &str = "GOLD96.5";
+
+/// Community JSON mirror of the association prices, used when the page can't be scraped
+const MIRROR_URL: &str = "https://api.chnwt.dev/thai-gold-api/latest";
+
+/// Thai gold association prices (GOLD, GOLD96.5, GOLD99.99)
+pub struct ThaiGold;
+
+impl PriceProvider for ThaiGold {
+    fn provider_type(&self) -> &'static str {
+        "goldtraders"
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::Gold]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        _asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(async move { Ok(fetch_quote(client).await?.entry(symbol)) })
+    }
+}
+
+/// Buy/sell prices of 96.5% gold in THB per baht-weight (15.244 g)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThaiGoldQuote {
+    /// Price the shop pays for a 96.5% bar
+    pub bar_buy: f64,
+    /// Price the shop sells a 96.5% bar at
+    pub bar_sell: f64,
+    /// Buy-back price for ornaments; sold at the bar price plus a making charge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ornament_buy: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ornament_sell: Option<f64>,
+    /// Provider the quote came from, e.g. "goldtraders"
+    pub source: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ThaiGoldQuote {
+    /// Price for a Thai gold symbol: the bar sell price, with GOLD99.99 estimated from it
+    pub fn price_for(&self, symbol: &str) -> f64 {
+        if symbol.eq_ignore_ascii_case("GOLD99.99") {
+            self.bar_sell * (99.99 / 96.5)
+        } else {
+            self.bar_sell
+        }
+    }
+
+    pub fn entry(&self, symbol: &str) -> PriceEntry {
+        PriceEntry {
+            symbol: symbol.to_uppercase(),
+            price: self.price_for(symbol),
+            currency: "THB".to_string(),
+            updated_at: self.updated_at,
+            source: Some(self.source.clone()),
+        }
+    }
+}
+
+/// Current association quote: scraped from goldtraders.or.th, or the JSON mirror when the
+/// page is down or its layout changed
+pub async fn fetch_quote(client: &ProviderClient) -> Result<ThaiGoldQuote, AppError> {
+    match fetch_goldtraders(client).await {
+        Ok(quote) => Ok(quote),
+        Err(AppError::RateLimited(info)) => Err(AppError::RateLimited(info)),
+        Err(e) => {
+            tracing::warn!("GoldTraders page failed: {}, trying the mirror API", e);
+            fetch_mirror(client).await
+        }
+    }
+}
+
+/// Element ids of the price labels on the goldtraders.or.th home page
+const BAR_BUY_ID: &str = "DetailPlace_uc_goldprices1_lblBLBuy";
+const BAR_SELL_ID: &str = "DetailPlace_uc_goldprices1_lblBLSell";
+const ORNAMENT_BUY_ID: &str = "DetailPlace_uc_goldprices1_lblOMBuy";
+const ORNAMENT_SELL_ID: &str = "DetailPlace_uc_goldprices1_lblOMSell";
+
+async fn fetch_goldtraders(client: &ProviderClient) -> Result<ThaiGoldQuote, AppError> {
+    let url = format!("{}/", client.config.goldtraders_url.trim_end_matches('/'));
+    let call = ProviderCall::new("GoldTraders", RATE_LIMIT_KEY, QUOTE_SYMBOL).logged_as("goldtraders", Some("local"));
+    let response = client.get_text(call, url, &[]).await?;
+    let html = response.data.as_str().unwrap_or_default();
+
+    let (Some(bar_buy), Some(bar_sell)) = (label_price(html, BAR_BUY_ID), label_price(html, BAR_SELL_ID)) else {
+        return Err(response.unparsable());
+    };
+    response.succeeded(bar_sell, "THB");
+    Ok(ThaiGoldQuote {
+        bar_buy,
+        bar_sell,
+        ornament_buy: label_price(html, ORNAMENT_BUY_ID),
+        ornament_sell: label_price(html, ORNAMENT_SELL_ID),
+        source: "goldtraders".to_string(),
+        updated_at: Utc::now(),
+    })
+}
+
+/// Number inside the element with the given id, e.g. `<span id="...">41,250.00</span>`
+fn label_price(html: &str, id: &str) -> Option<f64> {
+    let start = html.find(&format!("id=\"{}\"", id))?;
+    let rest = &html[start..];
+    let text = &rest[rest.find('>')? + 1..];
+    let text = &text[..text.find('<')?];
+    parse_baht(text)
+}
+
+/// "41,250.00" -> 41250.0
+fn parse_baht(text: &str) -> Option<f64> {
+    text.trim().replace(',', "").parse::<f64>().ok().filter(|p| *p > 0.0)
+}
+
+/// Response from api.chnwt.dev/thai-gold-api/latest
+#[derive(Debug, Clone, Deserialize)]
+struct MirrorResponse {
+    response: MirrorResponseData,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MirrorResponseData {
+    price: MirrorPriceData,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MirrorPriceData {
+    gold: MirrorBuySell,
+    gold_bar: MirrorBuySell,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MirrorBuySell {
+    buy: String,
+    sell: String,
+}
+
+async fn fetch_mirror(client: &ProviderClient) -> Result<ThaiGoldQuote, AppError> {
+    let call = ProviderCall::new("Thai Gold", RATE_LIMIT_KEY, QUOTE_SYMBOL).logged_as(RATE_LIMIT_KEY, Some("local"));
+    let response = client.get_json(call, MIRROR_URL.to_string(), &[]).await?;
+
+    let data: MirrorResponse = serde_json::from_value(response.data.clone())
+        .map_err(|_| response.unparsable())?;
+    let price = data.response.price;
+    let (Some(bar_buy), Some(bar_sell)) = (parse_baht(&price.gold_bar.buy), parse_baht(&price.gold_bar.sell)) else {
+        return Err(response.fail(format!("Invalid price format: {}", price.gold_bar.sell)));
+    };
+
+    response.succeeded(bar_sell, "THB");
+    Ok(ThaiGoldQuote {
+        bar_buy,
+        bar_sell,
+        ornament_buy: parse_baht(&price.gold.buy),
+        ornament_sell: parse_baht(&price.gold.sell),
+        source: RATE_LIMIT_KEY.to_string(),
+        updated_at: Utc::now(),
+    })
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use chrono::DateTime;
+use futures_util::future::BoxFuture;
+use crate::error::AppError;
+use crate::models::{AssetType, Market};
+use crate::services::price_service::{HistoryEntry, PriceEntry};
+use super::{PriceProvider, ProviderCall, ProviderClient};
+
+const RATE_LIMIT_KEY: &str = "yahoo_finance";
+
+/// Yahoo Finance service for Thai (.BK) and foreign stocks, with proxy symbols for TFEX
+pub struct YahooFinance;
+
+impl PriceProvider for YahooFinance {
+    fn provider_type(&self) -> &'static str {
+        "yahoo_finance"
+    }
+
+    /// SET quotes configured as set_marketdata are served from Yahoo's .BK listings
+    fn aliases(&self) -> &'static [&'static str] {
+        &["set_marketdata"]
+    }
+
+    fn supported_asset_types(&self) -> &'static [AssetType] {
+        &[AssetType::Stock, AssetType::Tfex, AssetType::ForeignStock]
+    }
+
+    fn rate_limit_key(&self) -> &'static str {
+        RATE_LIMIT_KEY
+    }
+
+    fn fetch_price<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbol: &'a str,
+        asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<PriceEntry, AppError>> {
+        Box::pin(async move {
+            let symbol_upper = symbol.to_uppercase();
+            match asset_type {
+                AssetType::ForeignStock => fetch_quote(client, &symbol_upper, &symbol_upper, "Foreign", "USD").await,
+                _ => fetch_thai_quote(client, &symbol_upper).await,
+            }
+        })
+    }
+
+    fn batch_size(&self) -> usize {
+        BATCH_SIZE
+    }
+
+    fn fetch_prices<'a>(
+        &'a self,
+        client: &'a ProviderClient,
+        symbols: &'a [String],
+        asset_type: &'a AssetType,
+        _market: Option<&'a Market>,
+    ) -> BoxFuture<'a, Result<HashMap<String, PriceEntry>, AppError>> {
+        Box::pin(fetch_quotes(client, symbols, asset_type))
+    }
+}
+
+/// Symbols per /api/quotes request (the service's own limit)
+const BATCH_SIZE: usize = 50;
+
+/// Latest daily close from the Yahoo Finance service for `yahoo_symbol`, reported under `symbol`.
+/// `market_id` tags the api_call_logs entry (SET, TFEX, COMEX, Foreign).
+pub async fn fetch_quote(
+    client: &ProviderClient,
+    symbol: &str,
+    yahoo_symbol: &str,
+    market_id: &str,
+    currency: &str,
+) -> Result<PriceEntry, AppError> {
+    let url = format!(
+        "{}/api/price-history/{}?period=1d&interval=1d",
+        client.config.yahoo_finance_service_url,
+        yahoo_symbol
+    );
+    let call = ProviderCall::new("Yahoo Finance Service", RATE_LIMIT_KEY, symbol)
+        .logged_as(RATE_LIMIT_KEY, Some(market_id))
+        .retry_after(60);
+    let response = client.get_json(call, url, &[]).await?;
+
+    // Yahoo Finance Service response format: { "data": [ { "close": ... } ] }
+    let price = response.data
+        .get("data")
+        .and_then(|d| d.as_array())
+        .and_then(|arr| arr.last())
+        .and_then(|item| item.get("close"))
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| response.unparsable())?;
+
+    Ok(response.priced(price, currency))
+}
+
+/// Latest closes of several SET (.BK) or foreign stocks from the service's /api/quotes. TFEX
+/// contracts, which are priced through proxy symbols, are left to `fetch_thai_quote`.
+pub async fn fetch_quotes(
+    client: &ProviderClient,
+    symbols: &[String],
+    asset_type: &AssetType,
+) -> Result<HashMap<String, PriceEntry>, AppError> {
+    let (market_id, currency) = match asset_type {
+        AssetType::ForeignStock => ("Foreign", "USD"),
+        _ => ("SET", "THB"),
+    };
+    let yahoo_symbols: Vec<(String, String)> = symbols.iter()
+        .map(|s| s.to_uppercase())
+        .filter(|s| *asset_type == AssetType::ForeignStock || !is_tfex_symbol(s))
+        .map(|s| {
+            let yahoo_symbol = if *asset_type == AssetType::ForeignStock { s.clone() } else { format!("{}.BK", s) };
+            (s, yahoo_symbol)
+        })
+        .collect();
+    if yahoo_symbols.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let query: Vec<&str> = yahoo_symbols.iter().map(|(_, y)| y.as_str()).collect();
+    let url = format!(
+        "{}/api/quotes?symbols={}",
+        client.config.yahoo_finance_service_url,
+        urlencoding::encode(&query.join(","))
+    );
+    let label = symbols.join(",");
+    let call = ProviderCall::new("Yahoo Finance Service", RATE_LIMIT_KEY, &label)
+        .logged_as(RATE_LIMIT_KEY, Some(market_id))
+        .retry_after(60);
+    let response = client.get_json(call, url, &[]).await?;
+
+    // Yahoo Finance Service response format: { "data": { "PTT.BK": { "close": ..., "date": ... } } }
+    let quotes = response.data.get("data").and_then(|d| d.as_object()).ok_or_else(|| response.unparsable())?;
+    let prices = yahoo_symbols.iter()
+        .filter_map(|(symbol, yahoo_symbol)| {
+            let price = quotes.get(yahoo_symbol)?.get("close")?.as_f64()?;
+            Some((symbol.clone(), response.entry_for(symbol, price, currency)))
+        })
+        .collect();
+    response.completed();
+    Ok(prices)
+}
+
+/// Average traded volume (shares) over the last `sessions` daily bars from the Yahoo Finance
+/// service, for SET (.BK) and foreign stocks
+pub async fn fetch_average_volume(
+    client: &ProviderClient,
+    symbol_upper: &str,
+    asset_type: &AssetType,
+    sessions: usize,
+) -> Result<f64, AppError> {
+    let (yahoo_symbol, market_id) = match asset_type {
+        AssetType::Stock => (format!("{}.BK", symbol_upper), "SET"),
+        AssetType::ForeignStock => (symbol_upper.to_string(), "Foreign"),
+        _ => return Err(AppError::BadRequest(format!("No volume data for {} assets", asset_type))),
+    };
+    let url = format!(
+        "{}/api/price-history/{}?period=3mo&interval=1d",
+        client.config.yahoo_finance_service_url,
+        yahoo_symbol
+    );
+    let call = ProviderCall::new("Yahoo Finance Service", RATE_LIMIT_KEY, symbol_upper)
+        .logged_as(RATE_LIMIT_KEY, Some(market_id))
+        .retry_after(60);
+    let response = client.get_json(call, url, &[]).await?;
+
+    // Yahoo Finance Service response format: { "data": [ { "close": ..., "volume": ... } ] }
+    let volumes: Vec<f64> = response.data
+        .get("data")
+        .and_then(|d| d.as_array())
+        .map(|bars| bars.iter().filter_map(|bar| bar.get("volume").and_then(|v| v.as_f64())).collect())
+        .unwrap_or_default();
+    let recent = &volumes[volumes.len().saturating_sub(sessions)..];
+    if recent.is_empty() {
+        return Err(response.fail(format!("No volume data for {}", symbol_upper)));
+    }
+    Ok(recent.iter().sum::<f64>() / recent.len() as f64)
+}
+
+/// Thai stock from its .BK listing (e.g. PTT.BK); TFEX contracts go through their proxy symbol
+pub async fn fetch_thai_quote(client: &ProviderClient, symbol_upper: &str) -> Result<PriceEntry, AppError> {
+    if !is_tfex_symbol(symbol_upper) {
+        let yahoo_symbol = format!("{}.BK", symbol_upper);
+        return fetch_quote(client, symbol_upper, &yahoo_symbol, "SET", "THB").await;
+    }
+    match tfex_proxy_symbol(symbol_upper) {
+        Some((yahoo_symbol, currency)) => fetch_quote(client, symbol_upper, yahoo_symbol, "TFEX", currency).await,
+        None => Err(AppError::ExternalApiError(format!("No Yahoo Finance proxy symbol for TFEX contract {}", symbol_upper))),
+    }
+}
+
+/// TFEX contracts are futures/derivatives that Yahoo has no .BK listing for
+pub fn is_tfex_symbol(symbol_upper: &str) -> bool {
+    const PREFIXES: &[&str] = &["S50", "GF", "GD", "SV", "USD", "BRN", "TSR", "BANK", "ENRG"];
+    const SUFFIXES: &[&str] = &["H24", "M24", "U24", "Z24", "H25", "M25", "U25", "Z25", "H26", "M26"];
+    PREFIXES.iter().any(|p| symbol_upper.starts_with(p)) || SUFFIXES.iter().any(|s| symbol_upper.ends_with(s))
+}
+
+/// Map TFEX symbols to the Yahoo Finance symbol used as a proxy, with its quote currency
+pub fn tfex_proxy_symbol(symbol: &str) -> Option<(&'static str, &'static str)> {
+    match symbol {
+        // SET50 Index Futures (S50) - use SET50 Index
+        s if s.starts_with("S50") => Some(("^SET50.BK", "THB")),
+        // SVF (SET50 Value Futures) - use Silver Futures as proxy, price in THB
+        s if s.starts_with("SVF") => Some(("SI=F", "THB")),
+        // Gold Futures - use international gold futures
+        s if s.starts_with("GF") || s.starts_with("GD") => Some(("GC=F", "USD")),
+        // Silver Futures (SV but NOT SVF)
+        s if s.starts_with("SV") => Some(("SI=F", "USD")),
+        // Brent Crude Oil
+        s if s.starts_with("BRN") => Some(("BZ=F", "USD")),
+        // USD/THB - no direct equivalent, fallback to mock
+        _ => None,
+    }
+}
+
+/// Daily closes from the Yahoo Finance chart API
+pub async fn fetch_history(
+    client: &ProviderClient,
+    symbol: &str,
+    asset_type: &AssetType,
+    days: u32,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    // Determine Yahoo symbol
+    let y_symbol = match asset_type {
+        AssetType::Stock | AssetType::Tfex => format!("{}.BK", symbol.to_uppercase()),
+        AssetType::Gold => {
+            if symbol.to_uppercase() == "XAU" { "GC=F".to_string() } // Gold Futures
+            else { return Ok(vec![]) } // No history for local gold
+        }
+        AssetType::ForeignStock => symbol.to_uppercase(), // Usually direct symbol
+        AssetType::Commodity => {
+            // Map common commodity symbols
+            match symbol.to_uppercase().as_str() {
+                "CL" => "CL=F".to_string(), // Crude Oil
+                "NG" => "NG=F".to_string(), // Natural Gas
+                _ => format!("{}=F", symbol.to_uppercase())
+            }
+        },
+        _ => symbol.to_uppercase()
+    };
+
+    // Yahoo Chart API: https://query1.finance.yahoo.com/v8/finance/chart/AAPL?range=1mo&interval=1d
+    let range = if days <= 7 { "5d" } else if days <= 30 { "1mo" } else if days <= 90 { "3mo" } else if days <= 180 { "6mo" } else if days <= 365 { "1y" } else { "2y" };
+    let interval = "1d";
+
+    let url = format!(
+        "https://query1.finance.yahoo.com/v8/finance/chart/{}?range={}&interval={}",
+        y_symbol, range, interval
+    );
+
+    let response = client.http_for(&[RATE_LIMIT_KEY]).await.get(&url).send().await?;
+    if !response.status().is_success() {
+        return Err(AppError::ExternalApiError("Yahoo history failed".to_string()));
+    }
+
+    let data: serde_json::Value = response.json().await?;
+
+    let mut history = Vec::new();
+
+    if let Some(result) = data.get("chart").and_then(|c| c.get("result")).and_then(|r| r.as_array()).and_then(|a| a.first()) {
+        let timestamps = result.get("timestamp").and_then(|t| t.as_array());
+        let quotes = result.get("indicators").and_then(|i| i.get("quote")).and_then(|q| q.as_array()).and_then(|a| a.first()).and_then(|q| q.get("close")).and_then(|c| c.as_array());
+
+        if let (Some(ts), Some(qs)) = (timestamps, quotes) {
+            for (t, q) in ts.iter().zip(qs.iter()) {
+                if let (Some(timestamp), Some(price)) = (t.as_i64(), q.as_f64()) {
+                    let date = DateTime::from_timestamp(timestamp, 0)
+                        .unwrap_or_default()
+                        .format("%Y-%m-%d")
+                        .to_string();
+                    history.push(HistoryEntry { date, price });
+                }
+            }
+        }
+    }
+
+    Ok(history)
+}
@@ -0,0 +1,88 @@
+//! Instance health for external status pages (GET /api/status/public). It needs no login, so
+//! the summary holds no URLs, error messages or user data, is rebuilt at most every
+//! CACHE_SECONDS and each client IP is limited to PUBLIC_STATUS_RATE_LIMIT requests a minute.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::Serialize;
+use tokio::sync::RwLock;
+use crate::config::Config;
+use crate::services::rate_limiter::RateLimitInfo;
+
+/// How long a built summary is served before the checks run again
+const CACHE_SECONDS: i64 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Operational,
+    /// Serving, but some price providers are down or snapshots are late
+    Degraded,
+    /// The database can't be reached
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicStatus {
+    pub status: HealthState,
+    pub api: HealthState,
+    pub database: HealthState,
+    /// Names of price providers that failed their last check or are rate limited
+    pub degraded_providers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_snapshot_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_snapshot_age_seconds: Option<i64>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Requests one client made in the current minute
+struct RequestWindow {
+    minute: DateTime<Utc>,
+    count: u32,
+}
+
+/// Cached summary plus the per-IP request counters in front of it
+#[derive(Clone)]
+pub struct PublicStatusService {
+    cached: Arc<RwLock<Option<PublicStatus>>>,
+    requests: Arc<RwLock<HashMap<Option<IpAddr>, RequestWindow>>>,
+    limit_per_minute: u32,
+}
+
+impl PublicStatusService {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            cached: Arc::new(RwLock::new(None)),
+            requests: Arc::new(RwLock::new(HashMap::new())),
+            limit_per_minute: config.public_status_rate_limit.max(1),
+        }
+    }
+
+    /// Count a request from `ip`, refusing it once the client is over the limit for this minute
+    pub async fn check_rate(&self, ip: Option<IpAddr>) -> Result<(), RateLimitInfo> {
+        let now = Utc::now();
+        let minute = now.duration_trunc(Duration::minutes(1)).unwrap_or(now);
+        let mut requests = self.requests.write().await;
+        // Counters from earlier minutes are no longer needed
+        requests.retain(|_, window| window.minute == minute);
+        let window = requests.entry(ip).or_insert(RequestWindow { minute, count: 0 });
+        if window.count >= self.limit_per_minute {
+            return Err(RateLimitInfo::new("public_status", Some(self.limit_per_minute as i32), minute + Duration::minutes(1)));
+        }
+        window.count += 1;
+        Ok(())
+    }
+
+    /// The last summary, while it is fresh
+    pub async fn cached(&self) -> Option<PublicStatus> {
+        self.cached.read().await.clone()
+            .filter(|status| Utc::now() - status.checked_at < Duration::seconds(CACHE_SECONDS))
+    }
+
+    pub async fn store(&self, status: PublicStatus) {
+        *self.cached.write().await = Some(status);
+    }
+}
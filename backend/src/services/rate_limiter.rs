@@ -1,8 +1,11 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+use crate::client_ip::ClientIp;
+use crate::error::AppError;
 use crate::services::pocketbase::PocketBaseClient;
 
 /// Rate limit configuration for an API
@@ -42,10 +45,89 @@ pub struct RateLimitConfig {
     pub updated: Option<String>,
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
+    /// Temporary admin override (in-memory only, restored automatically)
+    #[serde(skip)]
+    pub override_state: Option<RateLimitOverride>,
 }
 
 fn default_rpm() -> i32 { 30 }
 
+/// Temporary raise of a provider's limits and/or one-time burst budget
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitOverride {
+    pub original_requests_per_minute: i32,
+    pub original_requests_per_hour: Option<i32>,
+    pub original_requests_per_day: Option<i32>,
+    /// Requests allowed past the limits until used up or expired
+    pub burst_remaining: u32,
+    pub expires_at: DateTime<Utc>,
+    pub granted_by: String,
+    pub reason: Option<String>,
+}
+
+/// Admin request to override a provider's limits
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitOverrideRequest {
+    pub requests_per_minute: Option<i32>,
+    pub requests_per_hour: Option<i32>,
+    pub requests_per_day: Option<i32>,
+    pub burst: Option<u32>,
+    pub duration_minutes: Option<i64>,
+    pub reason: Option<String>,
+}
+
+/// A request declined by the rate limiter, with enough detail for clients to show
+/// when prices can be refreshed again
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitInfo {
+    pub provider: String,
+    /// Limit of the exhausted window; None when blocked after an upstream 429
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i32>,
+    pub remaining: i32,
+    pub resets_at: DateTime<Utc>,
+    pub retry_after_seconds: u64,
+}
+
+impl RateLimitInfo {
+    pub fn new(provider: &str, limit: Option<i32>, resets_at: DateTime<Utc>) -> Self {
+        let retry_after_seconds = (resets_at - Utc::now()).num_seconds().max(1) as u64;
+        Self {
+            provider: provider.to_string(),
+            limit,
+            remaining: 0,
+            resets_at,
+            retry_after_seconds,
+        }
+    }
+
+    /// X-RateLimit-* and Retry-After headers describing this limit
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let mut insert = |name: &'static str, value: String| {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(HeaderName::from_static(name), value);
+            }
+        };
+        if let Some(limit) = self.limit {
+            insert("x-ratelimit-limit", limit.to_string());
+        }
+        insert("x-ratelimit-remaining", self.remaining.to_string());
+        insert("x-ratelimit-reset", self.resets_at.timestamp().to_string());
+        insert("x-ratelimit-provider", self.provider.clone());
+        insert("retry-after", self.retry_after_seconds.to_string());
+        headers
+    }
+}
+
+/// Parse a stored reset timestamp, assuming the next minute if it is missing or invalid
+fn parse_reset_at(reset_at: &Option<String>) -> DateTime<Utc> {
+    reset_at.as_deref()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc::now() + Duration::minutes(1))
+}
+
 /// Rate limiter service
 #[derive(Clone)]
 pub struct RateLimiter {
@@ -54,6 +136,8 @@ pub struct RateLimiter {
     http_client: reqwest::Client,
     // In-memory cache for fast lookups
     cache: Arc<RwLock<HashMap<String, RateLimitConfig>>>,
+    // Serializes counter writes so an older PATCH never lands after a newer one
+    persist_lock: Arc<Mutex<()>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,10 +148,11 @@ struct PocketBaseResponse {
 impl RateLimiter {
     pub fn new(pb_client: PocketBaseClient, pocketbase_url: String) -> Self {
         Self {
+            http_client: pb_client.http(),
             pb_client,
             pocketbase_url,
-            http_client: reqwest::Client::new(),
             cache: Arc::new(RwLock::new(HashMap::new())),
+            persist_lock: Arc::new(Mutex::new(())),
         }
     }
 
@@ -115,7 +200,9 @@ impl RateLimiter {
             ("kucoin", 100, None, None),           // KuCoin: ~100 req/min for public API
             ("htx", 100, None, None),               // HTX (Huobi): ~100 req/min
             ("yahoo_finance", 60, Some(2000), None),
+            ("finnhub", 60, None, None),            // Finnhub free tier: 60 req/min
             ("thaigold", 60, None, Some(10)),       // Thai Gold: 10 req/hour
+            ("goldapi", 10, Some(100), None),       // GoldAPI: metered per month on the free plan
         ];
         
         let token = self.pb_client.get_token().await;
@@ -186,6 +273,7 @@ impl RateLimiter {
                         created: None,
                         updated: None,
                         extra: HashMap::new(),
+                        override_state: None,
                     });
                 }
                 Ok(resp) => {
@@ -201,20 +289,28 @@ impl RateLimiter {
         Ok(())
     }
 
-    /// Check if we can make a request to this API
-    pub async fn can_request(&self, api_name: &str) -> bool {
+    /// Reserve a request slot for this API; if none is left, say which limit was hit and when it resets
+    pub async fn acquire(&self, api_name: &str) -> Result<(), RateLimitInfo> {
         let mut cache = self.cache.write().await;
         
         if let Some(config) = cache.get_mut(api_name) {
             let now = Utc::now();
             
+            // Restore original limits once an override expires
+            if config.override_state.as_ref().is_some_and(|o| now >= o.expires_at) {
+                Self::restore_override(config);
+                self.audit(api_name, "override_expired", "system", ClientIp(None), serde_json::json!({
+                    "requests_per_minute": config.requests_per_minute,
+                }));
+            }
+            
             // Check if blocked
             if config.is_blocked {
                 if let Some(blocked_until) = &config.blocked_until {
                     if let Ok(until) = DateTime::parse_from_rfc3339(blocked_until) {
                         if now < until.with_timezone(&Utc) {
                             tracing::warn!("🚫 {} is blocked until {}", api_name, blocked_until);
-                            return false;
+                            return Err(RateLimitInfo::new(api_name, None, until.with_timezone(&Utc)));
                         }
                         // Unblock
                         config.is_blocked = false;
@@ -256,55 +352,107 @@ impl RateLimiter {
             }
             
             // Check minute limit
+            let mut limit_reached = None;
             if config.current_minute_count >= config.requests_per_minute {
-                tracing::warn!("⚠️ {} rate limit reached: {}/{} per minute", 
-                    api_name, config.current_minute_count, config.requests_per_minute);
-                return false;
+                limit_reached = Some((
+                    format!("{}/{} per minute", config.current_minute_count, config.requests_per_minute),
+                    config.requests_per_minute,
+                    &config.minute_reset_at,
+                ));
             }
             
             // Check hour limit
             if let Some(hour_limit) = config.requests_per_hour {
                 if hour_limit > 0 && config.current_hour_count >= hour_limit {
-                    tracing::warn!("⚠️ {} hourly limit reached: {}/{}", 
-                        api_name, config.current_hour_count, hour_limit);
-                    return false;
+                    limit_reached = Some((
+                        format!("{}/{} per hour", config.current_hour_count, hour_limit),
+                        hour_limit,
+                        &config.hour_reset_at,
+                    ));
                 }
             }
             
-            
             // Check day limit (0 means unlimited)
             if let Some(day_limit) = config.requests_per_day {
                 if day_limit > 0 && config.current_day_count >= day_limit {
-                    tracing::warn!("⚠️ {} daily limit reached: {}/{}", 
-                        api_name, config.current_day_count, day_limit);
-                    return false;
+                    limit_reached = Some((
+                        format!("{}/{} per day", config.current_day_count, day_limit),
+                        day_limit,
+                        &config.day_reset_at,
+                    ));
                 }
             }
             
-            true
+            if let Some((reached, limit, reset_at)) = limit_reached {
+                let info = RateLimitInfo::new(api_name, Some(limit), parse_reset_at(reset_at));
+                // Spend a burst token if an admin granted some
+                if let Some(ov) = config.override_state.as_mut().filter(|o| o.burst_remaining > 0) {
+                    ov.burst_remaining -= 1;
+                    tracing::info!("🎟️ {} limit reached ({}), using burst token ({} left)", 
+                        api_name, reached, ov.burst_remaining);
+                    Self::reserve(config, now);
+                    return Ok(());
+                }
+                tracing::warn!("⚠️ {} rate limit reached: {}", api_name, reached);
+                return Err(info);
+            }
+            
+            // Count the request now so concurrent callers see it before it is sent
+            Self::reserve(config, now);
+            Ok(())
         } else {
             // Unknown API - allow but log warning
             tracing::warn!("⚠️ Unknown API for rate limiting: {}", api_name);
-            true
+            Ok(())
         }
     }
 
-    /// Record that a request was made
-    pub async fn record_request(&self, api_name: &str) {
+    fn reserve(config: &mut RateLimitConfig, now: DateTime<Utc>) {
+        config.current_minute_count += 1;
+        config.current_hour_count += 1;
+        config.current_day_count += 1;
+        config.last_request_at = Some(now.to_rfc3339());
+    }
+
+    /// Give back a slot reserved by `acquire` for a request that never reached the API
+    pub async fn release(&self, api_name: &str) {
         let mut cache = self.cache.write().await;
         
         if let Some(config) = cache.get_mut(api_name) {
-            let now = Utc::now();
-            config.current_minute_count += 1;
-            config.current_hour_count += 1;
-            config.current_day_count += 1;
-            config.last_request_at = Some(now.to_rfc3339());
+            config.current_minute_count = (config.current_minute_count - 1).max(0);
+            config.current_hour_count = (config.current_hour_count - 1).max(0);
+            config.current_day_count = (config.current_day_count - 1).max(0);
             
-            // Update in PocketBase (fire and forget)
-            if !config.id.is_empty() {
-                let url = format!("{}/api/collections/api_rate_limits/records/{}", self.pocketbase_url, config.id);
-                let token = self.pb_client.get_token().await;
-                let update = serde_json::json!({
+            tracing::debug!("📊 {} request released: {}/{} this minute", 
+                api_name, config.current_minute_count, config.requests_per_minute);
+        }
+        drop(cache);
+        
+        self.persist_counts(api_name);
+    }
+
+    /// Record that a request reserved by `acquire` was made
+    pub async fn record_request(&self, api_name: &str) {
+        if let Some(config) = self.cache.read().await.get(api_name) {
+            tracing::debug!("📊 {} request recorded: {}/{} this minute", 
+                api_name, config.current_minute_count, config.requests_per_minute);
+        }
+        
+        self.persist_counts(api_name);
+    }
+
+    /// Update the counters in PocketBase (fire and forget)
+    ///
+    /// Writes go out one at a time and read the cache only once they hold the lock,
+    /// so each PATCH carries counts at least as new as the one before it.
+    fn persist_counts(&self, api_name: &str) {
+        let limiter = self.clone();
+        let api_name = api_name.to_string();
+        tokio::spawn(async move {
+            let _guard = limiter.persist_lock.lock().await;
+            
+            let (id, update) = match limiter.cache.read().await.get(&api_name) {
+                Some(config) if !config.id.is_empty() => (config.id.clone(), serde_json::json!({
                     "current_minute_count": config.current_minute_count,
                     "current_hour_count": config.current_hour_count,
                     "current_day_count": config.current_day_count,
@@ -312,19 +460,16 @@ impl RateLimiter {
                     "minute_reset_at": config.minute_reset_at,
                     "hour_reset_at": config.hour_reset_at,
                     "day_reset_at": config.day_reset_at
-                });
-                
-                let http_client = self.http_client.clone();
-                tokio::spawn(async move {
-                    let req = http_client.patch(&url);
-                    let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
-                    let _ = req.json(&update).send().await;
-                });
-            }
+                })),
+                _ => return,
+            };
             
-            tracing::debug!("📊 {} request recorded: {}/{} this minute", 
-                api_name, config.current_minute_count, config.requests_per_minute);
-        }
+            let url = format!("{}/api/collections/api_rate_limits/records/{}", limiter.pocketbase_url, id);
+            let token = limiter.pb_client.get_token().await;
+            let req = limiter.http_client.patch(&url);
+            let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
+            let _ = req.json(&update).send().await;
+        });
     }
 
     /// Record that we hit a rate limit (e.g., got 429 response)
@@ -360,6 +505,114 @@ impl RateLimiter {
         }
     }
 
+    /// Temporarily override an API's limits and/or grant a burst budget (admin)
+    pub async fn apply_override(
+        &self,
+        api_name: &str,
+        req: RateLimitOverrideRequest,
+        granted_by: &str,
+        client_ip: ClientIp,
+    ) -> Result<RateLimitConfig, AppError> {
+        let mut cache = self.cache.write().await;
+        let config = cache.get_mut(api_name)
+            .ok_or_else(|| AppError::NotFound(format!("No rate limit configured for {}", api_name)))?;
+
+        let duration_minutes = req.duration_minutes.unwrap_or(60);
+        if duration_minutes <= 0 {
+            return Err(AppError::BadRequest("duration_minutes must be positive".to_string()));
+        }
+
+        // Keep the originals from an earlier, still active override
+        let (orig_rpm, orig_rph, orig_rpd) = match &config.override_state {
+            Some(ov) => (ov.original_requests_per_minute, ov.original_requests_per_hour, ov.original_requests_per_day),
+            None => (config.requests_per_minute, config.requests_per_hour, config.requests_per_day),
+        };
+
+        if let Some(rpm) = req.requests_per_minute {
+            config.requests_per_minute = rpm;
+        }
+        if req.requests_per_hour.is_some() {
+            config.requests_per_hour = req.requests_per_hour;
+        }
+        if req.requests_per_day.is_some() {
+            config.requests_per_day = req.requests_per_day;
+        }
+
+        let expires_at = Utc::now() + Duration::minutes(duration_minutes);
+        config.override_state = Some(RateLimitOverride {
+            original_requests_per_minute: orig_rpm,
+            original_requests_per_hour: orig_rph,
+            original_requests_per_day: orig_rpd,
+            burst_remaining: req.burst.unwrap_or(0),
+            expires_at,
+            granted_by: granted_by.to_string(),
+            reason: req.reason.clone(),
+        });
+
+        self.audit(api_name, "override_applied", granted_by, client_ip, serde_json::json!({
+            "requests_per_minute": config.requests_per_minute,
+            "requests_per_hour": config.requests_per_hour,
+            "requests_per_day": config.requests_per_day,
+            "burst": req.burst.unwrap_or(0),
+            "expires_at": expires_at.to_rfc3339(),
+            "reason": req.reason,
+        }));
+
+        Ok(config.clone())
+    }
+
+    /// Remove an active override and restore the original limits (admin)
+    pub async fn clear_override(&self, api_name: &str, cleared_by: &str, client_ip: ClientIp) -> Result<RateLimitConfig, AppError> {
+        let mut cache = self.cache.write().await;
+        let config = cache.get_mut(api_name)
+            .ok_or_else(|| AppError::NotFound(format!("No rate limit configured for {}", api_name)))?;
+
+        if config.override_state.is_none() {
+            return Err(AppError::NotFound(format!("No active override for {}", api_name)));
+        }
+
+        Self::restore_override(config);
+        self.audit(api_name, "override_cleared", cleared_by, client_ip, serde_json::json!({
+            "requests_per_minute": config.requests_per_minute,
+        }));
+
+        Ok(config.clone())
+    }
+
+    fn restore_override(config: &mut RateLimitConfig) {
+        if let Some(ov) = config.override_state.take() {
+            config.requests_per_minute = ov.original_requests_per_minute;
+            config.requests_per_hour = ov.original_requests_per_hour;
+            config.requests_per_day = ov.original_requests_per_day;
+            tracing::info!("🔙 Restored original rate limits for {}", config.api_name);
+        }
+    }
+
+    /// Record an override action in the rate_limit_audit collection (fire-and-forget)
+    fn audit(&self, api_name: &str, action: &str, actor: &str, client_ip: ClientIp, details: serde_json::Value) {
+        tracing::info!("📝 Rate limit audit: {} {} by {} from {}", api_name, action, actor, client_ip);
+
+        let url = format!("{}/api/collections/rate_limit_audit/records", self.pocketbase_url);
+        let entry = serde_json::json!({
+            "api_name": api_name,
+            "action": action,
+            "actor": actor,
+            "ip": client_ip.0.map(|ip| ip.to_string()).unwrap_or_default(),
+            "details": details,
+        });
+        let http_client = self.http_client.clone();
+        let pb_client = self.pb_client.clone();
+
+        tokio::spawn(async move {
+            let token = pb_client.get_token().await;
+            let req = http_client.post(&url);
+            let req = if !token.is_empty() { req.header("Authorization", &token) } else { req };
+            if let Err(e) = req.json(&entry).send().await {
+                tracing::warn!("⚠️ Could not write rate limit audit: {}", e);
+            }
+        });
+    }
+
     /// Get all rate limit statuses
     pub async fn get_all_limits(&self) -> Vec<RateLimitConfig> {
         let cache = self.cache.read().await;
@@ -0,0 +1,229 @@
+//! Follows PocketBase's realtime API (server-sent events on /api/realtime) for transactions,
+//! accounts and asset_prices, so edits made in the PocketBase admin UI reach the in-memory
+//! caches and GET /api/ws/changes clients straight away. The connection is re-opened with
+//! backoff when it drops; CacheRevalidator still catches anything missed in between.
+
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use crate::config::Config;
+use crate::services::diagnostics::sanitize;
+use crate::services::{PocketBaseClient, PriceService};
+
+/// Collections subscribed to, as topics for all of their records
+pub const REALTIME_COLLECTIONS: [&str; 3] = ["transactions", "accounts", "asset_prices"];
+
+/// Wait before the first reconnect; doubled for each failure after it
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+/// A connection that lasted this long resets the reconnect delay
+const STABLE_CONNECTION: Duration = Duration::from_secs(60);
+/// PocketBase drops clients idle for 5 minutes; a silent stream past this is treated as dead
+const IDLE_TIMEOUT: Duration = Duration::from_secs(330);
+/// Changes a slow /ws/changes client may fall behind by before it skips ahead
+const CHANGE_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// A record changed in PocketBase, as pushed to /ws/changes clients
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordChange {
+    pub collection: &'static str,
+    pub action: RecordAction,
+    pub id: String,
+    /// Owner of the record; None for shared records such as prices
+    #[serde(skip)]
+    pub user_id: Option<String>,
+}
+
+impl RecordChange {
+    /// Whether a client signed in as `user_id` should hear about this change
+    pub fn visible_to(&self, user_id: &str) -> bool {
+        self.user_id.as_deref().is_none_or(|owner| owner == user_id)
+    }
+}
+
+/// `data` of a record event
+#[derive(Debug, Deserialize)]
+struct RealtimeMessage {
+    action: RecordAction,
+    record: serde_json::Value,
+}
+
+/// One server-sent event being read
+#[derive(Debug, Default)]
+struct SseEvent {
+    name: String,
+    data: String,
+}
+
+#[derive(Clone)]
+pub struct RealtimeBridge {
+    db: PocketBaseClient,
+    price_service: PriceService,
+    pocketbase_url: String,
+    enabled: bool,
+    changes: broadcast::Sender<RecordChange>,
+}
+
+impl RealtimeBridge {
+    pub fn new(config: &Config, db: PocketBaseClient, price_service: PriceService) -> Self {
+        Self {
+            db,
+            price_service,
+            pocketbase_url: config.pocketbase_url.trim_end_matches('/').to_string(),
+            enabled: config.pocketbase_realtime,
+            changes: broadcast::channel(CHANGE_BUFFER).0,
+        }
+    }
+
+    /// Receive every change applied from here on
+    pub fn subscribe(&self) -> broadcast::Receiver<RecordChange> {
+        self.changes.subscribe()
+    }
+
+    pub fn start(&self) {
+        if !self.enabled {
+            tracing::info!("📻 PocketBase realtime disabled (POCKETBASE_REALTIME=false)");
+            return;
+        }
+        let bridge = self.clone();
+        tokio::spawn(async move {
+            let mut delay = INITIAL_RECONNECT_DELAY;
+            loop {
+                let connected_at = Instant::now();
+                match bridge.listen().await {
+                    Ok(()) => tracing::info!("📻 PocketBase realtime connection closed, reconnecting"),
+                    Err(e) => tracing::warn!("⚠️ PocketBase realtime: {}", sanitize(&e)),
+                }
+                if connected_at.elapsed() >= STABLE_CONNECTION {
+                    delay = INITIAL_RECONNECT_DELAY;
+                }
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        });
+    }
+
+    /// Read one realtime connection until it ends
+    async fn listen(&self) -> Result<(), String> {
+        let url = format!("{}/api/realtime", self.pocketbase_url);
+        let mut response = self.db.http()
+            .get(&url)
+            .header("Accept", "text/event-stream")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to connect: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Failed to connect: {}", response.status()));
+        }
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut event = SseEvent::default();
+        loop {
+            let chunk = tokio::time::timeout(IDLE_TIMEOUT, response.chunk())
+                .await
+                .map_err(|_| format!("No events for {}s", IDLE_TIMEOUT.as_secs()))?
+                .map_err(|e| format!("Stream failed: {}", e))?;
+            let Some(chunk) = chunk else { return Ok(()) };
+            buffer.extend_from_slice(&chunk);
+
+            while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim_end_matches(['\r', '\n']);
+                if line.is_empty() {
+                    // A blank line ends the event
+                    self.handle_event(std::mem::take(&mut event)).await?;
+                } else if let Some(name) = line.strip_prefix("event:") {
+                    event.name = name.trim().to_string();
+                } else if let Some(data) = line.strip_prefix("data:") {
+                    if !event.data.is_empty() {
+                        event.data.push('\n');
+                    }
+                    event.data.push_str(data.trim_start());
+                }
+            }
+        }
+    }
+
+    async fn handle_event(&self, event: SseEvent) -> Result<(), String> {
+        if event.name == "PB_CONNECT" {
+            let client_id = serde_json::from_str::<serde_json::Value>(&event.data)
+                .ok()
+                .and_then(|data| data.get("clientId")?.as_str().map(str::to_string))
+                .ok_or_else(|| "PB_CONNECT without a client ID".to_string())?;
+            return self.subscribe_collections(&client_id).await;
+        }
+
+        // Topics are "<collection>/*"
+        let topic = event.name.split('/').next().unwrap_or_default();
+        let Some(collection) = REALTIME_COLLECTIONS.into_iter().find(|c| *c == topic) else {
+            return Ok(());
+        };
+        match serde_json::from_str::<RealtimeMessage>(&event.data) {
+            Ok(message) => self.apply(collection, message).await,
+            Err(e) => tracing::warn!("⚠️ Unreadable {} realtime event: {}", collection, e),
+        }
+        Ok(())
+    }
+
+    /// Point the new connection at the collections, as the admin so every record is visible
+    async fn subscribe_collections(&self, client_id: &str) -> Result<(), String> {
+        let token = self.db.get_token().await;
+        let subscriptions: Vec<String> = REALTIME_COLLECTIONS.iter().map(|c| format!("{}/*", c)).collect();
+        let request = self.db.http()
+            .post(format!("{}/api/realtime", self.pocketbase_url))
+            .json(&serde_json::json!({ "clientId": client_id, "subscriptions": subscriptions }));
+        let request = if !token.is_empty() { request.header("Authorization", &token) } else { request };
+        let response = request.send().await.map_err(|e| format!("Failed to subscribe: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Failed to subscribe: {} - {}", status, body));
+        }
+        tracing::info!("📻 Following PocketBase realtime changes to {}", REALTIME_COLLECTIONS.join(", "));
+        Ok(())
+    }
+
+    async fn apply(&self, collection: &'static str, message: RealtimeMessage) {
+        let field = |name: &str| message.record.get(name)
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.is_empty())
+            .map(str::to_string);
+        let change = RecordChange {
+            collection,
+            action: message.action,
+            id: field("id").unwrap_or_default(),
+            user_id: field("user_id"),
+        };
+        let deleted = message.action == RecordAction::Delete;
+        let applied = match collection {
+            "transactions" => self.db.apply_transaction_change(message.record, deleted).await,
+            "accounts" => self.db.apply_account_change(message.record, deleted).await,
+            _ => Ok(self.price_service.apply_stored_price(&message.record, deleted).await),
+        };
+        let cache_changed = match applied {
+            Ok(changed) => changed,
+            Err(e) => {
+                tracing::warn!("⚠️ Could not apply {} {} change: {}", collection, change.id, e);
+                false
+            }
+        };
+        if cache_changed {
+            tracing::debug!("📻 Applied {:?} of {} {}", change.action, collection, change.id);
+        }
+        // Users' own records go out even when this API wrote them, for their other devices;
+        // price records only when an outside edit changed the cached price
+        if cache_changed || change.user_id.is_some() {
+            // Fails only when nobody is subscribed
+            let _ = self.changes.send(change);
+        }
+    }
+}
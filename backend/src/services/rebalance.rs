@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::models::{AssetType, Market};
+use crate::services::export_format::ExportLocale;
+use crate::services::market_rules::trade_lot;
+
+/// Rough broker fee as a fraction of notional, used when the request gives no override
+pub fn default_fee_rate(asset_type: &AssetType) -> f64 {
+    match asset_type {
+        AssetType::Stock => 0.00157,
+        AssetType::Tfex => 0.0005,
+        AssetType::ForeignStock => 0.001,
+        AssetType::Crypto => 0.001,
+        AssetType::Gold | AssetType::Commodity => 0.002,
+        AssetType::Cash => 0.0,
+    }
+}
+
+/// Desired weight for one symbol, as % of total portfolio value
+#[derive(Debug, Clone, Deserialize)]
+pub struct RebalanceTarget {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+    pub weight_percent: f64,
+    /// Contract multiplier for futures not yet held (held ones use the recorded multiplier)
+    pub multiplier: Option<f64>,
+}
+
+/// A position the planner may trade, priced in its own currency
+#[derive(Debug, Clone)]
+pub struct PlanPosition {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub market: Option<Market>,
+    pub currency: String,
+    pub quantity: f64,
+    pub price: f64,
+    /// Asset currency -> base currency
+    pub fx: f64,
+    /// Contract multiplier for futures, 1 otherwise
+    pub multiplier: f64,
+    /// None leaves the position untouched
+    pub target_percent: Option<f64>,
+}
+
+impl PlanPosition {
+    /// Exposure of one unit (share, coin, contract) in the base currency
+    fn unit_value(&self) -> f64 {
+        self.price * self.multiplier * self.fx
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RebalanceTrade {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<Market>,
+    pub side: TradeSide,
+    pub quantity: f64,
+    pub lot_size: f64,
+    pub price: f64,
+    pub currency: String,
+    /// Trade value in the base currency
+    pub notional: f64,
+    /// Estimated fees in the base currency
+    pub estimated_fees: f64,
+    pub current_weight: f64,
+    pub target_weight: f64,
+    pub resulting_weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RebalancePlan {
+    pub base_currency: String,
+    pub total_value: f64,
+    pub trades: Vec<RebalanceTrade>,
+    pub total_fees: f64,
+    /// Sells minus buys minus fees; negative means cash must be added
+    pub net_cash_flow: f64,
+    pub cash_before: f64,
+    pub cash_after: f64,
+    /// Targeted symbols no trade could move (no price, or difference below one lot)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unchanged: Vec<String>,
+}
+
+/// Round a quantity toward zero to a multiple of `lot`
+fn round_to_lot(quantity: f64, lot: f64) -> f64 {
+    if lot <= 0.0 {
+        return quantity;
+    }
+    let lots = (quantity.abs() / lot + 1e-9).floor();
+    // Re-round so crypto steps don't print as 0.30000000000000004
+    let rounded = ((lots * lot) * 1e8).round() / 1e8;
+    rounded * quantity.signum()
+}
+
+/// Turn target weights into a lot-aligned trade list. `cash` is the spendable cash
+/// already in the portfolio (base currency), counted in the total but never traded.
+pub fn plan_rebalance(
+    base_currency: &str,
+    positions: &[PlanPosition],
+    cash: f64,
+    fee_rates: &HashMap<String, f64>,
+    fractional_shares: bool,
+) -> RebalancePlan {
+    let invested: f64 = positions.iter().map(|p| p.quantity * p.unit_value()).sum();
+    let total_value = invested + cash;
+    let weight = |value: f64| if total_value > 0.0 { value / total_value * 100.0 } else { 0.0 };
+
+    let mut trades = Vec::new();
+    let mut unchanged = Vec::new();
+    for position in positions {
+        let Some(target) = position.target_percent else { continue };
+        let unit_value = position.unit_value();
+        if unit_value <= 0.0 {
+            unchanged.push(position.symbol.clone());
+            continue;
+        }
+
+        let lot = trade_lot(&position.asset_type, position.market.as_ref(), fractional_shares);
+        let current_value = position.quantity * unit_value;
+        let delta = if target <= 0.0 {
+            // Closing out sells everything, odd lots included
+            -position.quantity
+        } else {
+            round_to_lot((total_value * target / 100.0 - current_value) / unit_value, lot)
+        };
+        if delta == 0.0 {
+            unchanged.push(position.symbol.clone());
+            continue;
+        }
+
+        let notional = delta.abs() * unit_value;
+        let fee_rate = fee_rates.get(&position.asset_type.to_string())
+            .copied()
+            .unwrap_or_else(|| default_fee_rate(&position.asset_type));
+        trades.push(RebalanceTrade {
+            symbol: position.symbol.clone(),
+            asset_type: position.asset_type.clone(),
+            market: position.market.clone(),
+            side: if delta > 0.0 { TradeSide::Buy } else { TradeSide::Sell },
+            quantity: delta.abs(),
+            lot_size: lot,
+            price: position.price,
+            currency: position.currency.clone(),
+            notional,
+            estimated_fees: notional * fee_rate,
+            current_weight: weight(current_value),
+            target_weight: target,
+            resulting_weight: 0.0,
+        });
+    }
+
+    let total_fees: f64 = trades.iter().map(|t| t.estimated_fees).sum();
+    let net_cash_flow: f64 = trades.iter()
+        .map(|t| if t.side == TradeSide::Sell { t.notional } else { -t.notional })
+        .sum::<f64>() - total_fees;
+
+    // Fees leave the portfolio; everything else moves between positions and cash
+    let total_after = total_value - total_fees;
+    for trade in &mut trades {
+        let position = positions.iter()
+            .find(|p| p.symbol == trade.symbol && p.asset_type == trade.asset_type)
+            .map(|p| p.quantity * p.unit_value())
+            .unwrap_or(0.0);
+        let change = if trade.side == TradeSide::Buy { trade.notional } else { -trade.notional };
+        trade.resulting_weight = if total_after > 0.0 { (position + change) / total_after * 100.0 } else { 0.0 };
+    }
+    trades.sort_by(|a, b| {
+        // Sells first so their proceeds fund the buys
+        (a.side == TradeSide::Buy).cmp(&(b.side == TradeSide::Buy))
+            .then(b.notional.partial_cmp(&a.notional).unwrap_or(std::cmp::Ordering::Equal))
+    });
+
+    RebalancePlan {
+        base_currency: base_currency.to_string(),
+        total_value,
+        trades,
+        total_fees,
+        net_cash_flow,
+        cash_before: cash,
+        cash_after: cash + net_cash_flow,
+        unchanged,
+    }
+}
+
+/// Execution checklist: one row per trade with an empty "done" column to tick off
+pub fn plan_to_csv(plan: &RebalancePlan, locale: &ExportLocale) -> String {
+    let mut out = locale.header(
+        "done,side,symbol,asset_type,market,quantity,lot_size,price,currency,notional,estimated_fees,current_weight,target_weight,resulting_weight\n",
+    );
+    for t in &plan.trades {
+        out.push_str(&locale.csv_line(&[
+            String::new(),
+            if t.side == TradeSide::Buy { "BUY" } else { "SELL" }.to_string(),
+            t.symbol.clone(),
+            t.asset_type.to_string(),
+            t.market.as_ref().map(|m| m.to_string()).unwrap_or_default(),
+            locale.number(t.quantity),
+            locale.number(t.lot_size),
+            locale.number(t.price),
+            t.currency.clone(),
+            locale.fixed(t.notional, 2),
+            locale.fixed(t.estimated_fees, 2),
+            locale.fixed(t.current_weight, 2),
+            locale.fixed(t.target_weight, 2),
+            locale.fixed(t.resulting_weight, 2),
+        ]));
+    }
+    out
+}
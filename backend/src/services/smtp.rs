@@ -0,0 +1,195 @@
+//! Minimal SMTP submission client for notification emails: implicit TLS or STARTTLS, AUTH PLAIN,
+//! one plain-text UTF-8 message per connection.
+
+use std::time::Duration;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Utc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use crate::config::Config;
+
+/// Connecting and each command/reply round trip must finish within this
+const SMTP_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Security {
+    /// TLS from the first byte (usually port 465)
+    Tls,
+    /// Plain connection upgraded with STARTTLS (usually port 587)
+    StartTls,
+    None,
+}
+
+/// A failed send, and whether trying again later could help
+#[derive(Debug)]
+pub struct SmtpError {
+    pub message: String,
+    /// 4xx replies and connection problems are worth a retry; 5xx replies are not
+    pub transient: bool,
+}
+
+impl SmtpError {
+    fn transient(message: impl Into<String>) -> Self {
+        Self { message: message.into(), transient: true }
+    }
+
+    fn reply(code: u16, text: &str) -> Self {
+        Self { message: format!("SMTP {} {}", code, text.trim()), transient: code < 500 }
+    }
+}
+
+#[derive(Clone)]
+pub struct SmtpMailer {
+    host: String,
+    port: u16,
+    security: Security,
+    credentials: Option<(String, String)>,
+    from: String,
+}
+
+impl SmtpMailer {
+    /// Mailer for SMTP_HOST / SMTP_FROM, or None when email isn't configured
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let host = config.smtp_host.clone()?;
+        let from = config.smtp_from.clone()?;
+        let security = match config.smtp_security.as_str() {
+            "tls" | "ssl" => Security::Tls,
+            "none" => Security::None,
+            _ => Security::StartTls,
+        };
+        let credentials = config.smtp_username.clone().map(|user| (user, config.smtp_password.clone().unwrap_or_default()));
+        Some(Self { host, port: config.smtp_port, security, credentials, from })
+    }
+
+    pub async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), SmtpError> {
+        tokio::time::timeout(SMTP_TIMEOUT * 3, self.send_inner(to, subject, body))
+            .await
+            .map_err(|_| SmtpError::transient("SMTP session timed out"))?
+    }
+
+    async fn send_inner(&self, to: &str, subject: &str, body: &str) -> Result<(), SmtpError> {
+        let tcp = tokio::time::timeout(SMTP_TIMEOUT, TcpStream::connect((self.host.as_str(), self.port)))
+            .await
+            .map_err(|_| SmtpError::transient(format!("Timed out connecting to {}:{}", self.host, self.port)))?
+            .map_err(|e| SmtpError::transient(format!("Could not connect to {}:{}: {}", self.host, self.port, e)))?;
+
+        match self.security {
+            Security::Tls => {
+                let mut session = BufReader::new(self.tls(tcp).await?);
+                expect_reply(&mut session, 220).await?;
+                self.session(&mut session, to, subject, body).await
+            }
+            Security::None => {
+                let mut session = BufReader::new(tcp);
+                expect_reply(&mut session, 220).await?;
+                self.session(&mut session, to, subject, body).await
+            }
+            Security::StartTls => {
+                let mut plain = BufReader::new(tcp);
+                expect_reply(&mut plain, 220).await?;
+                command(&mut plain, &format!("EHLO {}", self.helo_name()), 250).await?;
+                command(&mut plain, "STARTTLS", 220).await?;
+                let mut session = BufReader::new(self.tls(plain.into_inner()).await?);
+                self.session(&mut session, to, subject, body).await
+            }
+        }
+    }
+
+    async fn tls(&self, tcp: TcpStream) -> Result<tokio_native_tls::TlsStream<TcpStream>, SmtpError> {
+        let connector = tokio_native_tls::native_tls::TlsConnector::new()
+            .map_err(|e| SmtpError::transient(format!("TLS setup failed: {}", e)))?;
+        tokio_native_tls::TlsConnector::from(connector)
+            .connect(&self.host, tcp)
+            .await
+            .map_err(|e| SmtpError { message: format!("TLS handshake with {} failed: {}", self.host, e), transient: false })
+    }
+
+    /// From EHLO to QUIT, after the greeting (and any TLS upgrade)
+    async fn session<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        session: &mut BufReader<S>,
+        to: &str,
+        subject: &str,
+        body: &str,
+    ) -> Result<(), SmtpError> {
+        command(session, &format!("EHLO {}", self.helo_name()), 250).await?;
+        if let Some((user, password)) = &self.credentials {
+            let token = BASE64.encode(format!("\0{}\0{}", user, password));
+            command(session, &format!("AUTH PLAIN {}", token), 235).await?;
+        }
+        command(session, &format!("MAIL FROM:<{}>", address(&self.from)), 250).await?;
+        command(session, &format!("RCPT TO:<{}>", address(to)), 250).await?;
+        command(session, "DATA", 354).await?;
+        command(session, &format!("{}\r\n.", self.message(to, subject, body)), 250).await?;
+        // The message is accepted; a failed QUIT doesn't matter
+        let _ = command(session, "QUIT", 221).await;
+        Ok(())
+    }
+
+    fn helo_name(&self) -> &str {
+        address(&self.from).rsplit_once('@').map(|(_, domain)| domain).unwrap_or("localhost")
+    }
+
+    /// Headers plus a base64 body, so no line of it can start with "." or run too long
+    fn message(&self, to: &str, subject: &str, body: &str) -> String {
+        let encoded = BASE64.encode(body.replace("\r\n", "\n").replace('\n', "\r\n"));
+        let lines: Vec<&str> = encoded.as_bytes()
+            .chunks(76)
+            .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+            .collect();
+        format!(
+            "From: {}\r\nTo: {}\r\nSubject: =?UTF-8?B?{}?=\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\nMIME-Version: 1.0\r\n\
+             Content-Type: text/plain; charset=UTF-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+            self.from,
+            to,
+            BASE64.encode(subject),
+            Utc::now().to_rfc2822(),
+            uuid::Uuid::new_v4(),
+            self.helo_name(),
+            lines.join("\r\n"),
+        )
+    }
+}
+
+/// The bare address of "Name <user@example.com>" or "user@example.com"
+fn address(mailbox: &str) -> &str {
+    match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+        _ => mailbox.trim(),
+    }
+}
+
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(session: &mut BufReader<S>, line: &str, expect: u16) -> Result<String, SmtpError> {
+    let write = async {
+        session.get_mut().write_all(format!("{}\r\n", line).as_bytes()).await?;
+        session.get_mut().flush().await
+    };
+    tokio::time::timeout(SMTP_TIMEOUT, write)
+        .await
+        .map_err(|_| SmtpError::transient("Timed out writing to the SMTP server"))?
+        .map_err(|e| SmtpError::transient(format!("SMTP write failed: {}", e)))?;
+    expect_reply(session, expect).await
+}
+
+/// Read a (possibly multi-line) reply and check its code
+async fn expect_reply<S: AsyncRead + Unpin>(session: &mut BufReader<S>, expect: u16) -> Result<String, SmtpError> {
+    let mut text = String::new();
+    loop {
+        let mut line = String::new();
+        let read = tokio::time::timeout(SMTP_TIMEOUT, session.read_line(&mut line))
+            .await
+            .map_err(|_| SmtpError::transient("Timed out waiting for the SMTP server"))?
+            .map_err(|e| SmtpError::transient(format!("SMTP read failed: {}", e)))?;
+        if read == 0 {
+            return Err(SmtpError::transient("SMTP server closed the connection"));
+        }
+        let code: u16 = line.get(..3).and_then(|c| c.parse().ok())
+            .ok_or_else(|| SmtpError::transient(format!("Unexpected SMTP reply: {}", line.trim())))?;
+        text.push_str(line.get(4..).unwrap_or_default());
+        // "250-..." continues, "250 ..." ends the reply
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return if code == expect { Ok(text) } else { Err(SmtpError::reply(code, &text)) };
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use chrono::{DateTime, Duration, Utc};
+use crate::config::Config;
+use crate::models::PortfolioSnapshot;
+
+/// A user's full snapshot series, sorted by date
+#[derive(Clone)]
+pub struct CachedSeries {
+    pub snapshots: Arc<Vec<PortfolioSnapshot>>,
+    /// Changes every time the series is reloaded; part of the ETag
+    pub generation: u64,
+    loaded_at: DateTime<Utc>,
+}
+
+impl CachedSeries {
+    /// Snapshots whose day falls within [from, to] (YYYY-MM-DD, both inclusive)
+    pub fn range(&self, from: Option<&str>, to: Option<&str>) -> &[PortfolioSnapshot] {
+        let start = from.map_or(0, |f| self.snapshots.partition_point(|s| s.day() < f));
+        let end = to.map_or(self.snapshots.len(), |t| self.snapshots.partition_point(|s| s.day() <= t));
+        &self.snapshots[start..end.max(start)]
+    }
+
+    /// Strong validator for one slice of this series
+    pub fn etag(&self, user_id: &str, from: Option<&str>, to: Option<&str>) -> String {
+        let mut hasher = DefaultHasher::new();
+        (user_id, self.generation, from, to).hash(&mut hasher);
+        format!("\"{:016x}\"", hasher.finish())
+    }
+}
+
+/// Per-user snapshot series kept in memory so dashboards don't re-read every
+/// record from PocketBase. Entries are dropped when the snapshot job writes for
+/// the user, and expire after a TTL to pick up edits made directly in PocketBase.
+#[derive(Clone)]
+pub struct SnapshotCache {
+    series: Arc<RwLock<HashMap<String, CachedSeries>>>,
+    generation: Arc<AtomicU64>,
+    ttl: Duration,
+}
+
+impl SnapshotCache {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            series: Arc::new(RwLock::new(HashMap::new())),
+            generation: Arc::new(AtomicU64::new(1)),
+            ttl: Duration::seconds(config.snapshot_cache_ttl_seconds as i64),
+        }
+    }
+
+    pub async fn get(&self, user_id: &str) -> Option<CachedSeries> {
+        let series = self.series.read().await;
+        series.get(user_id)
+            .filter(|s| Utc::now() - s.loaded_at < self.ttl)
+            .cloned()
+    }
+
+    pub async fn put(&self, user_id: &str, mut snapshots: Vec<PortfolioSnapshot>) -> CachedSeries {
+        snapshots.sort_by(|a, b| a.date.cmp(&b.date));
+        let entry = CachedSeries {
+            snapshots: Arc::new(snapshots),
+            generation: self.generation.fetch_add(1, Ordering::Relaxed),
+            loaded_at: Utc::now(),
+        };
+        self.series.write().await.insert(user_id.to_string(), entry.clone());
+        entry
+    }
+
+    pub async fn invalidate(&self, user_id: &str) {
+        if self.series.write().await.remove(user_id).is_some() {
+            tracing::debug!("🗑️ Snapshot cache invalidated for {}", user_id);
+        }
+    }
+}
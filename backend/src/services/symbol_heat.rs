@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::config::Config;
+use crate::models::AssetType;
+
+// Heat halves every day without new activity
+const HEAT_HALF_LIFE_HOURS: f64 = 24.0;
+const HOT_SCORE: f64 = 3.0;
+const WARM_SCORE: f64 = 0.5;
+const VIEW_WEIGHT: f64 = 1.0;
+const HOLD_WEIGHT: f64 = 0.25;
+// Entries below this score (about a week after a single view) are dropped
+const EVICT_SCORE: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeatTier {
+    Hot,
+    Warm,
+    Cold,
+}
+
+/// Activity ranking for a single symbol
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolHeatEntry {
+    pub symbol: String,
+    pub asset_type: String,
+    pub score: f64,
+    pub tier: HeatTier,
+    pub views: u64,
+    pub last_activity: DateTime<Utc>,
+    pub last_refreshed: Option<DateTime<Utc>>,
+    /// Fixed refresh interval set on the symbol, replacing the tier's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_interval_seconds: Option<i64>,
+}
+
+/// Tracks which symbols users view or hold so background price refreshes
+/// can prioritise hot symbols and only touch cold ones daily
+#[derive(Clone)]
+pub struct SymbolHeat {
+    entries: Arc<RwLock<HashMap<String, SymbolHeatEntry>>>,
+    // Per-symbol refresh intervals from the symbols collection, by heat key
+    overrides: Arc<RwLock<HashMap<String, i64>>>,
+    warm_refresh_seconds: i64,
+    cold_refresh_seconds: i64,
+}
+
+impl SymbolHeat {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            overrides: Arc::new(RwLock::new(HashMap::new())),
+            warm_refresh_seconds: config.price_warm_refresh_seconds as i64,
+            cold_refresh_seconds: config.price_cold_refresh_seconds as i64,
+        }
+    }
+
+    /// Key format shared with the price fetch job
+    pub fn key(symbol: &str, asset_type: &str) -> String {
+        format!("{}-{}", symbol.to_uppercase(), asset_type.to_lowercase())
+    }
+
+    fn decayed(score: f64, since: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+        let hours = now.signed_duration_since(since).num_seconds().max(0) as f64 / 3600.0;
+        score * 0.5_f64.powf(hours / HEAT_HALF_LIFE_HOURS)
+    }
+
+    fn tier_for(score: f64) -> HeatTier {
+        if score >= HOT_SCORE {
+            HeatTier::Hot
+        } else if score >= WARM_SCORE {
+            HeatTier::Warm
+        } else {
+            HeatTier::Cold
+        }
+    }
+
+    async fn bump(&self, symbol: &str, asset_type: &AssetType, weight: f64, is_view: bool) {
+        let now = Utc::now();
+        let asset_type = asset_type.to_string();
+        let key = Self::key(symbol, &asset_type);
+        let mut entries = self.entries.write().await;
+        if !entries.contains_key(&key) {
+            self.evict_cold(&mut entries, now);
+        }
+        let entry = entries
+            .entry(key)
+            .or_insert_with(|| SymbolHeatEntry {
+                symbol: symbol.to_uppercase(),
+                asset_type,
+                score: 0.0,
+                tier: HeatTier::Cold,
+                views: 0,
+                last_activity: now,
+                last_refreshed: None,
+                refresh_interval_seconds: None,
+            });
+        entry.score = Self::decayed(entry.score, entry.last_activity, now) + weight;
+        entry.tier = Self::tier_for(entry.score);
+        entry.last_activity = now;
+        if is_view {
+            entry.views += 1;
+        }
+    }
+
+    /// Drop entries whose heat has decayed away. Entries refreshed within the cold interval
+    /// are kept, since forgetting them would make should_refresh fetch them again right away.
+    fn evict_cold(&self, entries: &mut HashMap<String, SymbolHeatEntry>, now: DateTime<Utc>) {
+        entries.retain(|_, e| {
+            Self::decayed(e.score, e.last_activity, now) >= EVICT_SCORE
+                || e.last_refreshed.is_some_and(|at| now.signed_duration_since(at).num_seconds() < self.cold_refresh_seconds)
+        });
+    }
+
+    /// Record a user looking at a symbol's price or chart
+    pub async fn record_view(&self, symbol: &str, asset_type: &AssetType) {
+        self.bump(symbol, asset_type, VIEW_WEIGHT, true).await;
+    }
+
+    /// Record a symbol shown as a holding in someone's portfolio
+    pub async fn record_holding(&self, symbol: &str, asset_type: &AssetType) {
+        self.bump(symbol, asset_type, HOLD_WEIGHT, false).await;
+    }
+
+    /// Current decayed score of a symbol (0 if never seen)
+    pub async fn score(&self, key: &str) -> f64 {
+        let entries = self.entries.read().await;
+        entries
+            .get(key)
+            .map(|e| Self::decayed(e.score, e.last_activity, Utc::now()))
+            .unwrap_or(0.0)
+    }
+
+    /// Replace the per-symbol refresh intervals with those set in the symbols collection
+    pub async fn load_refresh_overrides(&self, symbols: &[crate::services::symbols::Symbol]) {
+        let overrides: HashMap<String, i64> = symbols.iter()
+            .filter_map(|s| Some((Self::key(&s.symbol, &s.asset_type), s.refresh_interval_seconds? as i64)))
+            .collect();
+        tracing::info!("⏱️ Loaded {} per-symbol refresh intervals", overrides.len());
+        *self.overrides.write().await = overrides;
+    }
+
+    /// Set (or with `None` clear) one symbol's refresh interval
+    pub async fn set_refresh_override(&self, symbol: &str, asset_type: &str, seconds: Option<u64>) {
+        let key = Self::key(symbol, asset_type);
+        let mut overrides = self.overrides.write().await;
+        match seconds {
+            Some(seconds) => overrides.insert(key, seconds as i64),
+            None => overrides.remove(&key),
+        };
+    }
+
+    /// Fixed refresh interval of a symbol, if one is set
+    pub async fn refresh_override(&self, key: &str) -> Option<i64> {
+        self.overrides.read().await.get(key).copied()
+    }
+
+    /// Whether the background job should refresh this symbol now.
+    /// A symbol with its own refresh interval refreshes on that schedule. Otherwise hot
+    /// symbols refresh on every run, warm ones every PRICE_WARM_REFRESH_SECONDS,
+    /// cold (or never seen) ones every PRICE_COLD_REFRESH_SECONDS.
+    pub async fn should_refresh(&self, key: &str) -> bool {
+        let now = Utc::now();
+        let fixed = self.refresh_override(key).await;
+        let entries = self.entries.read().await;
+        let Some(entry) = entries.get(key) else {
+            // Unknown symbols are tracked once they have been refreshed
+            return true;
+        };
+        let Some(last_refreshed) = entry.last_refreshed else {
+            return true;
+        };
+
+        let interval = match (fixed, Self::tier_for(Self::decayed(entry.score, entry.last_activity, now))) {
+            (Some(seconds), _) => seconds,
+            (None, HeatTier::Hot) => return true,
+            (None, HeatTier::Warm) => self.warm_refresh_seconds,
+            (None, HeatTier::Cold) => self.cold_refresh_seconds,
+        };
+        now.signed_duration_since(last_refreshed).num_seconds() >= interval
+    }
+
+    pub async fn mark_refreshed(&self, symbol: &str, asset_type: &str) {
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let entry = entries
+            .entry(Self::key(symbol, asset_type))
+            .or_insert_with(|| SymbolHeatEntry {
+                symbol: symbol.to_uppercase(),
+                asset_type: asset_type.to_lowercase(),
+                score: 0.0,
+                tier: HeatTier::Cold,
+                views: 0,
+                last_activity: now,
+                last_refreshed: None,
+                refresh_interval_seconds: None,
+            });
+        entry.last_refreshed = Some(now);
+    }
+
+    /// Symbols ranked by current heat, hottest first
+    pub async fn ranking(&self, limit: usize) -> Vec<SymbolHeatEntry> {
+        let now = Utc::now();
+        let overrides = self.overrides.read().await;
+        let entries = self.entries.read().await;
+        let mut ranked: Vec<SymbolHeatEntry> = entries
+            .iter()
+            .map(|(key, e)| {
+                let score = Self::decayed(e.score, e.last_activity, now);
+                SymbolHeatEntry {
+                    score,
+                    tier: Self::tier_for(score),
+                    refresh_interval_seconds: overrides.get(key).copied(),
+                    ..e.clone()
+                }
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        ranked.truncate(limit);
+        ranked
+    }
+}
@@ -2,12 +2,13 @@
 
 use crate::error::AppError;
 use crate::services::PocketBaseClient;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 /// Symbol stored in PocketBase
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Symbol {
     #[serde(default)]
     pub id: String,
@@ -22,6 +23,36 @@ pub struct Symbol {
     pub sector: Option<String>,
     #[serde(default)]
     pub icon_url: Option<String>,
+    /// No longer traded: valued at `delisted_price` and never refreshed from providers
+    #[serde(default)]
+    pub delisted: bool,
+    #[serde(default, deserialize_with = "deserialize_price", skip_serializing_if = "Option::is_none")]
+    pub delisted_price: Option<f64>,
+    #[serde(default, deserialize_with = "crate::models::account::deserialize_optional_text", skip_serializing_if = "Option::is_none")]
+    pub delisted_currency: Option<String>,
+    #[serde(default, deserialize_with = "crate::models::transaction::deserialize_optional_date", skip_serializing_if = "Option::is_none")]
+    pub delisted_at: Option<DateTime<Utc>>,
+    /// Background refresh interval for this symbol, replacing the heat-based schedule
+    #[serde(default, deserialize_with = "deserialize_interval", skip_serializing_if = "Option::is_none")]
+    pub refresh_interval_seconds: Option<u64>,
+}
+
+/// PocketBase stores an unset number as 0
+fn deserialize_price<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<f64>::deserialize(deserializer)?;
+    Ok(value.filter(|v| *v > 0.0))
+}
+
+/// PocketBase stores an unset number as 0
+fn deserialize_interval<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<f64>::deserialize(deserializer)?;
+    Ok(value.filter(|v| *v >= 1.0).map(|v| v as u64))
 }
 
 /// PocketBase list response
@@ -44,7 +75,7 @@ impl SymbolsService {
     pub fn new(pocketbase_url: String, pb_client: PocketBaseClient) -> Self {
         Self {
             pocketbase_url,
-            http_client: reqwest::Client::new(),
+            http_client: pb_client.http(),
             pb_client,
             cache: Arc::new(RwLock::new(Vec::new())),
             loaded: Arc::new(RwLock::new(false)),
@@ -160,6 +191,104 @@ impl SymbolsService {
             .cloned()
     }
 
+    /// Symbols currently marked delisted
+    pub async fn delisted_symbols(&self) -> Vec<Symbol> {
+        let _ = self.load_symbols().await;
+        self.cache.read().await.iter().filter(|s| s.delisted).cloned().collect()
+    }
+
+    /// Symbols with their own refresh interval
+    pub async fn refresh_overrides(&self) -> Vec<Symbol> {
+        let _ = self.load_symbols().await;
+        self.cache.read().await.iter().filter(|s| s.refresh_interval_seconds.is_some()).cloned().collect()
+    }
+
+    /// Cached symbol by exact symbol and asset type
+    async fn find(&self, symbol: &str, asset_type: &str) -> Result<Symbol, AppError> {
+        let _ = self.load_symbols().await;
+        let target = symbol.trim().to_uppercase();
+        self.cache.read().await
+            .iter()
+            .find(|s| s.symbol.trim().to_uppercase() == target && s.asset_type == asset_type)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("Symbol {} ({}) not found", target, asset_type)))
+    }
+
+    /// Whether the symbols collection lists this symbol and asset type
+    pub async fn is_listed(&self, symbol: &str, asset_type: &str) -> bool {
+        self.find(symbol, asset_type).await.is_ok()
+    }
+
+    /// Give a symbol its own background refresh interval, or clear it with `None`
+    pub async fn set_refresh_interval(&self, symbol: &str, asset_type: &str, seconds: Option<u64>) -> Result<Symbol, AppError> {
+        let mut updated = self.find(symbol, asset_type).await?;
+        updated.refresh_interval_seconds = seconds;
+        self.save(updated).await
+    }
+
+    /// Mark a symbol delisted with its final price, or clear the flag with `None`
+    pub async fn set_delisted(&self, symbol: &str, asset_type: &str, final_price: Option<(f64, String)>) -> Result<Symbol, AppError> {
+        let mut updated = self.find(symbol, asset_type).await?;
+
+        match final_price {
+            Some((price, currency)) => {
+                updated.delisted = true;
+                updated.delisted_price = Some(price);
+                updated.delisted_currency = Some(currency);
+                updated.delisted_at = Some(Utc::now());
+            }
+            None => {
+                updated.delisted = false;
+                updated.delisted_price = None;
+                updated.delisted_currency = None;
+                updated.delisted_at = None;
+            }
+        }
+        self.save(updated).await
+    }
+
+    /// Write a symbol and update the cache. Built-in symbols that only exist in the static
+    /// lists get a PocketBase record first.
+    async fn save(&self, updated: Symbol) -> Result<Symbol, AppError> {
+        let target = updated.symbol.trim().to_uppercase();
+        let payload = serde_json::json!({
+            "symbol": updated.symbol,
+            "name": updated.name,
+            "asset_type": updated.asset_type,
+            "market": updated.market,
+            "category": updated.category,
+            "sector": updated.sector,
+            "icon_url": updated.icon_url,
+            "delisted": updated.delisted,
+            "delisted_price": updated.delisted_price.unwrap_or(0.0),
+            "delisted_currency": updated.delisted_currency.clone().unwrap_or_default(),
+            "delisted_at": updated.delisted_at.map(|at| at.to_rfc3339()).unwrap_or_default(),
+            "refresh_interval_seconds": updated.refresh_interval_seconds.unwrap_or(0),
+        });
+        let token = self.pb_client.get_token().await;
+        let request = if updated.id.is_empty() {
+            self.http_client.post(format!("{}/api/collections/symbols/records", self.pocketbase_url))
+        } else {
+            self.http_client.patch(format!("{}/api/collections/symbols/records/{}", self.pocketbase_url, updated.id))
+        };
+        let request = if !token.is_empty() { request.header("Authorization", token) } else { request };
+        let response = request.json(&payload).send().await
+            .map_err(|e| AppError::Internal(format!("Failed to update symbol {}: {}", target, e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::Internal(format!("Failed to update symbol {}: {} - {}", target, status, body)));
+        }
+        let saved: Symbol = response.json().await
+            .map_err(|e| AppError::Internal(format!("Failed to parse symbol {}: {}", target, e)))?;
+
+        let mut cache = self.cache.write().await;
+        if let Some(entry) = cache.iter_mut().find(|s| s.symbol.trim().to_uppercase() == target && s.asset_type == updated.asset_type) {
+            *entry = saved.clone();
+        }
+        Ok(saved)
+    }
+
     /// Check if symbols are loaded
     pub async fn has_symbols(&self) -> bool {
         let _ = self.load_symbols().await;
@@ -245,6 +374,7 @@ impl SymbolsService {
              category: Some(c.to_string()),
              sector: None,
              icon_url: None,
+             ..Default::default()
         }).collect()
     }
 
@@ -276,7 +406,7 @@ impl SymbolsService {
             ("TKN", "Thai Krungthai Capital"), ("EPG", "Eastern Polymer Group"), ("BPP", "Banpu Power"), ("STARK", "Stark Corporation")
         ].into_iter().map(|(s, n)| Symbol {
             id: String::new(), symbol: s.to_string(), name: n.to_string(), asset_type: "stock".to_string(),
-            market: Some("SET".to_string()), category: None, sector: None, icon_url: None,
+            market: Some("SET".to_string()), category: None, sector: None, icon_url: None, ..Default::default()
         }).collect()
     }
 
@@ -308,7 +438,7 @@ impl SymbolsService {
             ("XLE", "Energy Select Sector SPDR")
         ].into_iter().map(|(s, n)| Symbol {
             id: String::new(), symbol: s.to_string(), name: n.to_string(), asset_type: "foreign_stock".to_string(),
-            market: Some("US".to_string()), category: None, sector: None, icon_url: None,
+            market: Some("US".to_string()), category: None, sector: None, icon_url: None, ..Default::default()
         }).collect()
     }
 
@@ -341,7 +471,7 @@ impl SymbolsService {
             ("USDH26", "USD Futures Mar 2026", "Currency Futures"), ("USDM26", "USD Futures Jun 2026", "Currency Futures")
          ].into_iter().map(|(s, n, c)| Symbol {
             id: String::new(), symbol: s.to_string(), name: n.to_string(), asset_type: "tfex".to_string(),
-            market: None, category: Some(c.to_string()), sector: None, icon_url: None,
+            market: None, category: Some(c.to_string()), sector: None, icon_url: None, ..Default::default()
         }).collect()
     }
 }
@@ -0,0 +1,226 @@
+//! Websocket ticker feeds for crypto exchanges (CRYPTO_PRICE_STREAMS).
+//!
+//! Each feed keeps one connection for the held symbols on its exchange and hands the ticks to
+//! the price service, which caches them like fetched prices. While a feed is down nothing
+//! refreshes those cache entries, so they expire and lookups go back to the REST providers.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio::sync::{mpsc, watch, RwLock};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+use crate::models::Market;
+use crate::services::price_service::PriceEntry;
+use crate::services::providers::{self, TickerStream};
+
+/// Reconnect when nothing arrives for this long
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(20);
+
+const MIN_BACKOFF_SECONDS: u64 = 5;
+const MAX_BACKOFF_SECONDS: u64 = 300;
+
+/// Ticks for a symbol arriving sooner than this after the last one passed on are dropped
+const MIN_TICK_GAP_MS: i64 = 1000;
+
+/// A streamed price on its way to the cache
+pub struct StreamTick {
+    pub market: Market,
+    pub entry: PriceEntry,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedStatus {
+    pub market: Market,
+    pub connected: bool,
+    /// Symbols the feed is subscribed to (the held ones on this exchange)
+    pub symbols: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_tick_at: Option<DateTime<Utc>>,
+    pub reconnects: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+struct Feed {
+    stream: Arc<dyn TickerStream>,
+    symbols: watch::Sender<Vec<String>>,
+    status: RwLock<FeedStatus>,
+}
+
+/// One websocket feed per exchange in `providers::ticker_streams`
+#[derive(Clone)]
+pub struct TickerFeeds {
+    feeds: Vec<Arc<Feed>>,
+}
+
+impl Default for TickerFeeds {
+    fn default() -> Self {
+        let feeds = providers::ticker_streams().into_iter()
+            .map(|stream| Arc::new(Feed {
+                status: RwLock::new(FeedStatus {
+                    market: stream.market(),
+                    connected: false,
+                    symbols: Vec::new(),
+                    last_tick_at: None,
+                    reconnects: 0,
+                    last_error: None,
+                }),
+                symbols: watch::channel(Vec::new()).0,
+                stream,
+            }))
+            .collect();
+        Self { feeds }
+    }
+}
+
+impl TickerFeeds {
+    /// Connect every feed; each stays idle until it has symbols to watch
+    pub fn start(&self, ticks: mpsc::Sender<StreamTick>) {
+        for feed in &self.feeds {
+            tokio::spawn(run(feed.clone(), ticks.clone()));
+        }
+    }
+
+    /// Subscribe each feed to the held symbols on its exchange. Feeds whose set changed
+    /// reconnect with the new one.
+    pub fn watch(&self, held: &[(Market, String)]) {
+        for feed in &self.feeds {
+            let market = feed.stream.market();
+            let mut symbols: Vec<String> = held.iter()
+                .filter(|(m, _)| *m == market)
+                .map(|(_, s)| s.to_uppercase())
+                .collect();
+            symbols.sort();
+            symbols.dedup();
+            feed.symbols.send_if_modified(|current| {
+                if *current == symbols {
+                    return false;
+                }
+                *current = symbols;
+                true
+            });
+        }
+    }
+
+    pub async fn status(&self) -> Vec<FeedStatus> {
+        let mut statuses = Vec::with_capacity(self.feeds.len());
+        for feed in &self.feeds {
+            statuses.push(feed.status.read().await.clone());
+        }
+        statuses
+    }
+}
+
+/// Keep a feed connected for as long as the price service listens, backing off after failures
+async fn run(feed: Arc<Feed>, ticks: mpsc::Sender<StreamTick>) {
+    let market = feed.stream.market();
+    let mut symbols_rx = feed.symbols.subscribe();
+    let mut backoff = MIN_BACKOFF_SECONDS;
+    while !ticks.is_closed() {
+        let symbols = symbols_rx.borrow_and_update().clone();
+        if symbols.is_empty() {
+            // Nothing held on this exchange
+            if symbols_rx.changed().await.is_err() {
+                return;
+            }
+            continue;
+        }
+
+        if let Err(e) = stream(&feed, &symbols, &mut symbols_rx, &ticks, &mut backoff).await {
+            tracing::warn!("📡 {} ticker feed down: {}, reconnecting in {}s", market, e, backoff);
+            {
+                let mut status = feed.status.write().await;
+                status.connected = false;
+                status.reconnects += 1;
+                status.last_error = Some(e);
+            }
+            tokio::time::sleep(Duration::from_secs(backoff)).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF_SECONDS);
+        }
+    }
+}
+
+/// One connection: subscribe, then pass ticks on until it fails or the symbol set changes
+/// (which returns Ok so the caller reconnects right away)
+async fn stream(
+    feed: &Feed,
+    symbols: &[String],
+    symbols_rx: &mut watch::Receiver<Vec<String>>,
+    ticks: &mpsc::Sender<StreamTick>,
+    backoff: &mut u64,
+) -> Result<(), String> {
+    let market = feed.stream.market();
+    let (mut socket, _) = tokio_tungstenite::connect_async(feed.stream.url(symbols))
+        .await
+        .map_err(|e| format!("connect failed: {}", e))?;
+    for frame in feed.stream.subscribe(symbols) {
+        socket.send(Message::text(frame)).await.map_err(|e| format!("subscribe failed: {}", e))?;
+    }
+    {
+        let mut status = feed.status.write().await;
+        status.connected = true;
+        status.symbols = symbols.to_vec();
+        status.last_error = None;
+    }
+    tracing::info!("📡 {} ticker feed connected for {} symbols", market, symbols.len());
+
+    let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+    let mut last_message = Instant::now();
+    let mut last_passed: HashMap<String, DateTime<Utc>> = HashMap::new();
+    loop {
+        tokio::select! {
+            changed = symbols_rx.changed() => {
+                let _ = socket.close(None).await;
+                feed.status.write().await.connected = false;
+                return changed.map_err(|_| "feed stopped".to_string());
+            }
+            _ = keepalive.tick() => {
+                if let Some(frame) = feed.stream.keepalive() {
+                    socket.send(Message::text(frame)).await.map_err(|e| format!("keepalive failed: {}", e))?;
+                }
+            }
+            _ = tokio::time::sleep_until(last_message + IDLE_TIMEOUT) => {
+                return Err(format!("no data for {}s", IDLE_TIMEOUT.as_secs()));
+            }
+            message = socket.next() => {
+                let message = match message {
+                    Some(Ok(message)) => message,
+                    Some(Err(e)) => return Err(e.to_string()),
+                    None => return Err("connection closed".to_string()),
+                };
+                last_message = Instant::now();
+                // Pings are answered by tungstenite; "pong" replies to keepalives aren't JSON
+                let Message::Text(text) = message else { continue };
+                let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) else { continue };
+                let Some((symbol, price)) = feed.stream.parse(&value) else { continue };
+
+                let now = Utc::now();
+                let due = last_passed.get(&symbol)
+                    .is_none_or(|at| (now - *at).num_milliseconds() >= MIN_TICK_GAP_MS);
+                if !due || price <= 0.0 {
+                    continue;
+                }
+                last_passed.insert(symbol.clone(), now);
+                *backoff = MIN_BACKOFF_SECONDS;
+                feed.status.write().await.last_tick_at = Some(now);
+
+                let entry = PriceEntry {
+                    symbol,
+                    price,
+                    currency: feed.stream.currency().to_string(),
+                    updated_at: now,
+                    source: Some(feed.stream.source().to_string()),
+                };
+                ticks.send(StreamTick { market: market.clone(), entry })
+                    .await
+                    .map_err(|_| "price service stopped".to_string())?;
+            }
+        }
+    }
+}
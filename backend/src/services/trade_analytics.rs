@@ -0,0 +1,192 @@
+//! Statistics over closed trades from the lot engine: win rate, average win and loss,
+//! expectancy, profit factor and how long positions were held.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::models::{AssetType, Transaction};
+use crate::services::lot_engine::RealizedTrade;
+
+/// Custom field holding the strategy a trade was made under
+pub const STRATEGY_FIELD: &str = "strategy";
+
+/// Holding time buckets as (label, upper bound in hours)
+const HOLDING_BUCKETS: &[(&str, f64)] = &[
+    ("< 1 day", 24.0),
+    ("1-7 days", 7.0 * 24.0),
+    ("1-4 weeks", 28.0 * 24.0),
+    ("1-3 months", 91.0 * 24.0),
+    ("3-12 months", 365.0 * 24.0),
+    ("> 1 year", f64::INFINITY),
+];
+
+/// A closed trade with its P&L converted to the report currency
+#[derive(Debug, Clone, Serialize)]
+pub struct ClosedTrade {
+    pub transaction_id: String,
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub position_type: String,
+    pub closed_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opened_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub holding_hours: Option<f64>,
+    pub quantity: f64,
+    pub pnl: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_percent: Option<f64>,
+}
+
+impl ClosedTrade {
+    /// `fx` converts the trade's currency to the report currency
+    pub fn new(trade: &RealizedTrade, fx: f64) -> Self {
+        let holding_hours = trade.opened_at
+            .map(|opened| (trade.timestamp - opened).num_seconds().max(0) as f64 / 3600.0);
+        Self {
+            transaction_id: trade.transaction_id.clone(),
+            symbol: trade.symbol.clone(),
+            asset_type: trade.asset_type.clone(),
+            position_type: trade.position_type.clone(),
+            closed_at: trade.timestamp,
+            opened_at: trade.opened_at,
+            holding_hours,
+            quantity: trade.quantity,
+            pnl: trade.pnl * fx,
+            return_percent: (trade.cost_basis > 0.0).then(|| trade.pnl / trade.cost_basis * 100.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HoldingBucket {
+    pub label: String,
+    pub trades: usize,
+    pub wins: usize,
+    pub win_rate: f64,
+    pub net_pnl: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HoldingTime {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_hours: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub median_hours: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_winner_hours: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_loser_hours: Option<f64>,
+    pub buckets: Vec<HoldingBucket>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeStats {
+    pub trades: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub breakeven: usize,
+    /// Winning share of all closed trades, in percent
+    pub win_rate: f64,
+    pub gross_profit: f64,
+    /// Sum of the losing trades (negative)
+    pub gross_loss: f64,
+    pub net_pnl: f64,
+    pub average_win: f64,
+    /// Negative, like the losses it averages
+    pub average_loss: f64,
+    pub largest_win: f64,
+    pub largest_loss: f64,
+    /// Average P&L per trade: win rate × average win + loss rate × average loss
+    pub expectancy: f64,
+    /// Gross profit over gross loss; None without losing trades
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profit_factor: Option<f64>,
+    /// Average win over average loss; None without losing trades
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payoff_ratio: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_return_percent: Option<f64>,
+    pub holding_time: HoldingTime,
+}
+
+/// Whether a transaction carries the tag and strategy asked for (either may be unset)
+pub fn matches_filter(tx: &Transaction, tag: Option<&str>, strategy: Option<&str>) -> bool {
+    let tagged = tag.is_none_or(|tag| tx.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+    let in_strategy = strategy.is_none_or(|strategy| {
+        tx.custom_fields.get(STRATEGY_FIELD)
+            .and_then(|v| v.as_str())
+            .is_some_and(|s| s.trim().eq_ignore_ascii_case(strategy.trim()))
+    });
+    tagged && in_strategy
+}
+
+pub fn trade_stats(trades: &[ClosedTrade]) -> TradeStats {
+    let wins: Vec<&ClosedTrade> = trades.iter().filter(|t| t.pnl > 0.0).collect();
+    let losses: Vec<&ClosedTrade> = trades.iter().filter(|t| t.pnl < 0.0).collect();
+    let gross_profit: f64 = wins.iter().map(|t| t.pnl).sum();
+    let gross_loss: f64 = losses.iter().map(|t| t.pnl).sum();
+    let average = |sum: f64, count: usize| if count > 0 { sum / count as f64 } else { 0.0 };
+    let average_win = average(gross_profit, wins.len());
+    let average_loss = average(gross_loss, losses.len());
+    let returns: Vec<f64> = trades.iter().filter_map(|t| t.return_percent).collect();
+
+    TradeStats {
+        trades: trades.len(),
+        wins: wins.len(),
+        losses: losses.len(),
+        breakeven: trades.len() - wins.len() - losses.len(),
+        win_rate: average(wins.len() as f64 * 100.0, trades.len()),
+        gross_profit,
+        gross_loss,
+        net_pnl: gross_profit + gross_loss,
+        average_win,
+        average_loss,
+        largest_win: wins.iter().map(|t| t.pnl).fold(0.0, f64::max),
+        largest_loss: losses.iter().map(|t| t.pnl).fold(0.0, f64::min),
+        expectancy: average(gross_profit + gross_loss, trades.len()),
+        profit_factor: (gross_loss < 0.0).then(|| gross_profit / -gross_loss),
+        payoff_ratio: (average_loss < 0.0).then(|| average_win / -average_loss),
+        average_return_percent: (!returns.is_empty()).then(|| average(returns.iter().sum(), returns.len())),
+        holding_time: holding_time(trades),
+    }
+}
+
+fn holding_time(trades: &[ClosedTrade]) -> HoldingTime {
+    let mean = |hours: &[f64]| (!hours.is_empty()).then(|| hours.iter().sum::<f64>() / hours.len() as f64);
+    let hours_where = |won: bool| -> Vec<f64> {
+        trades.iter().filter(|t| (t.pnl > 0.0) == won && t.pnl != 0.0).filter_map(|t| t.holding_hours).collect()
+    };
+    let mut hours: Vec<f64> = trades.iter().filter_map(|t| t.holding_hours).collect();
+    hours.sort_by(|a, b| a.total_cmp(b));
+    let median_hours = match hours.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(hours[n / 2]),
+        n => Some((hours[n / 2 - 1] + hours[n / 2]) / 2.0),
+    };
+
+    let mut lower = 0.0;
+    let buckets = HOLDING_BUCKETS.iter()
+        .map(|(label, upper)| {
+            let in_bucket: Vec<&ClosedTrade> = trades.iter()
+                .filter(|t| t.holding_hours.is_some_and(|h| h >= lower && h < *upper))
+                .collect();
+            lower = *upper;
+            let wins = in_bucket.iter().filter(|t| t.pnl > 0.0).count();
+            HoldingBucket {
+                label: label.to_string(),
+                trades: in_bucket.len(),
+                wins,
+                win_rate: if in_bucket.is_empty() { 0.0 } else { wins as f64 * 100.0 / in_bucket.len() as f64 },
+                net_pnl: in_bucket.iter().map(|t| t.pnl).sum(),
+            }
+        })
+        .collect();
+
+    HoldingTime {
+        average_hours: mean(&hours),
+        median_hours,
+        average_winner_hours: mean(&hours_where(true)),
+        average_loser_hours: mean(&hours_where(false)),
+        buckets,
+    }
+}
@@ -0,0 +1,131 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use crate::models::PortfolioAsset;
+use crate::services::exchange_rate::ConversionTrail;
+use crate::services::ExchangeRateService;
+
+/// Decimal places of money figures. The portfolio engine carries full precision and rounds
+/// only where an amount is booked or shown, half away from zero, so a total is always the sum
+/// of its unrounded parts.
+pub const MONEY_DECIMALS: i32 = 2;
+
+/// Round a money figure to MONEY_DECIMALS
+pub fn round_money(value: f64) -> f64 {
+    let scale = 10f64.powi(MONEY_DECIMALS);
+    (value * scale).round() / scale
+}
+
+/// Largest gap rounding alone can open between a rounded total and the sum of its `parts`
+/// rounded one by one: half a unit of the last decimal for each part and for the total
+pub fn rounding_tolerance(parts: usize) -> f64 {
+    (parts as f64 + 1.0) * 0.5 * 10f64.powi(-MONEY_DECIMALS)
+}
+
+/// Stablecoins valued 1:1 against the fiat they track
+const STABLECOIN_ALIASES: &[(&str, &str)] = &[
+    ("USDT", "USD"),
+    ("USDC", "USD"),
+    ("BUSD", "USD"),
+    ("FDUSD", "USD"),
+    ("TUSD", "USD"),
+    ("DAI", "USD"),
+];
+
+/// Currency code with stablecoins mapped to the fiat they track
+pub fn canonical_currency(code: &str) -> String {
+    let upper = code.trim().to_uppercase();
+    STABLECOIN_ALIASES.iter()
+        .find(|(alias, _)| *alias == upper)
+        .map(|(_, fiat)| fiat.to_string())
+        .unwrap_or(upper)
+}
+
+pub fn same_currency(a: &str, b: &str) -> bool {
+    canonical_currency(a) == canonical_currency(b)
+}
+
+/// Express a quoted price in the holding's cost currency so value and cost are comparable.
+/// Holdings whose currency was only a fallback adopt the quote currency instead, since their
+/// cost figures were most likely entered in it.
+pub async fn price_in_cost_currency(
+    exchange_rates: &ExchangeRateService,
+    asset: &mut PortfolioAsset,
+    price: f64,
+    price_currency: &str,
+    conversions: &mut ConversionTrail,
+) -> f64 {
+    convert_to_cost_currency(exchange_rates, asset, price, price_currency, None, conversions).await
+}
+
+/// Like `price_in_cost_currency` for a price recorded on `date`, converted at that day's rate
+pub async fn price_in_cost_currency_on(
+    exchange_rates: &ExchangeRateService,
+    asset: &mut PortfolioAsset,
+    price: f64,
+    price_currency: &str,
+    date: NaiveDate,
+    conversions: &mut ConversionTrail,
+) -> f64 {
+    convert_to_cost_currency(exchange_rates, asset, price, price_currency, Some(date), conversions).await
+}
+
+async fn convert_to_cost_currency(
+    exchange_rates: &ExchangeRateService,
+    asset: &mut PortfolioAsset,
+    price: f64,
+    price_currency: &str,
+    date: Option<NaiveDate>,
+    conversions: &mut ConversionTrail,
+) -> f64 {
+    if same_currency(&asset.currency, price_currency) {
+        return price;
+    }
+    if !asset.currency_explicit {
+        asset.currency = price_currency.to_uppercase();
+        return price;
+    }
+
+    let from = canonical_currency(price_currency);
+    let to = canonical_currency(&asset.currency);
+    let rate = match date {
+        Some(date) => exchange_rates.get_historical_quote(&from, &to, date).await.map(|quote| {
+            conversions.record(&quote);
+            quote.rate
+        }),
+        None => exchange_rates.get_rate_recorded(&from, &to, conversions).await,
+    };
+    match rate {
+        Ok(rate) => {
+            tracing::debug!("💱 {} priced in {}, converted to {} at {}", asset.symbol, from, to, rate);
+            asset.price_currency = Some(price_currency.to_uppercase());
+            price * rate
+        }
+        Err(e) => {
+            // Without a rate, keep value and cost apart rather than mixing currencies
+            tracing::warn!("⚠️ No {}->{} rate for {}: {}, valuing in {}", from, to, asset.symbol, e, from);
+            asset.currency = price_currency.to_uppercase();
+            price
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MismatchKind {
+    /// Transactions of one holding are booked in different currencies, so the cost basis mixes them
+    MixedTransactionCurrencies,
+    /// The price source quotes another currency; the price is converted into the cost currency
+    PriceCurrencyConverted,
+    /// Transactions carry no currency; the price source's currency is assumed for the cost basis
+    ImplicitCurrency,
+    /// A stablecoin is treated as the fiat it tracks
+    StablecoinAlias,
+    /// No exchange rate exists between the price and cost currencies
+    NoExchangeRate,
+}
+
+impl MismatchKind {
+    pub fn is_problem(&self) -> bool {
+        matches!(self, MismatchKind::MixedTransactionCurrencies | MismatchKind::ImplicitCurrency | MismatchKind::NoExchangeRate)
+    }
+}
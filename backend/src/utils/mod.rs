@@ -1 +1,2 @@
 pub mod units;
+pub mod stats;
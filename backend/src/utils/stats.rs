@@ -0,0 +1,152 @@
+//! Return and risk statistics over plain `f64` columns.
+//!
+//! Everything here is synchronous and CPU-bound; handlers run it on the blocking pool.
+//! Kept free of crate imports so `benches/stats.rs` can include it directly.
+
+use std::collections::BTreeMap;
+use rayon::prelude::*;
+use serde::Serialize;
+
+/// Pearson correlation of two equally long series (None if too short or flat)
+pub fn pearson(a: &[f64], b: &[f64]) -> Option<f64> {
+    let n = a.len().min(b.len());
+    if n < 3 {
+        return None;
+    }
+    let (a, b) = (&a[..n], &b[..n]);
+    let mean_a = a.iter().sum::<f64>() / n as f64;
+    let mean_b = b.iter().sum::<f64>() / n as f64;
+
+    // Zipped iteration lets the compiler drop bounds checks and vectorize the sums
+    let (cov, var_a, var_b) = a.iter().zip(b).fold((0.0, 0.0, 0.0), |(cov, va, vb), (x, y)| {
+        let da = x - mean_a;
+        let db = y - mean_b;
+        (cov + da * db, va + da * da, vb + db * db)
+    });
+    if var_a <= f64::EPSILON || var_b <= f64::EPSILON {
+        return None;
+    }
+    Some((cov / (var_a.sqrt() * var_b.sqrt())).clamp(-1.0, 1.0))
+}
+
+/// Daily closes as two parallel columns sorted by date
+#[derive(Debug, Clone, Default)]
+pub struct CloseSeries {
+    pub dates: Vec<String>,
+    pub closes: Vec<f64>,
+}
+
+impl From<&BTreeMap<String, f64>> for CloseSeries {
+    fn from(daily: &BTreeMap<String, f64>) -> Self {
+        let (dates, closes) = daily.iter()
+            .filter(|(_, price)| **price > 0.0)
+            .map(|(date, price)| (date.clone(), *price))
+            .unzip();
+        Self { dates, closes }
+    }
+}
+
+/// Simple returns of two close series over the dates both have a price for, so markets
+/// with different trading calendars (crypto vs stocks) are compared over the same spans
+pub fn aligned_returns(a: &CloseSeries, b: &CloseSeries) -> (Vec<f64>, Vec<f64>) {
+    // Merge-join on the sorted date columns
+    let mut common = Vec::with_capacity(a.closes.len().min(b.closes.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < a.dates.len() && j < b.dates.len() {
+        match a.dates[i].cmp(&b.dates[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                common.push((a.closes[i], b.closes[j]));
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    common.windows(2)
+        .map(|w| (w[1].0 / w[0].0 - 1.0, w[1].1 / w[0].1 - 1.0))
+        .unzip()
+}
+
+/// Symmetric pairwise correlation matrix; pairs are computed in parallel
+pub fn correlation_matrix(series: &[CloseSeries]) -> Vec<Vec<Option<f64>>> {
+    let n = series.len();
+    let pairs: Vec<(usize, usize)> = (0..n)
+        .flat_map(|i| ((i + 1)..n).map(move |j| (i, j)))
+        .collect();
+    let values: Vec<Option<f64>> = pairs.par_iter()
+        .map(|&(i, j)| {
+            let (ra, rb) = aligned_returns(&series[i], &series[j]);
+            pearson(&ra, &rb)
+        })
+        .collect();
+
+    let mut matrix = vec![vec![None; n]; n];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[i] = Some(1.0);
+    }
+    for ((i, j), value) in pairs.into_iter().zip(values) {
+        matrix[i][j] = value;
+        matrix[j][i] = value;
+    }
+    matrix
+}
+
+/// Period-over-period simple returns; non-positive values break the chain and are skipped
+pub fn simple_returns(values: &[f64]) -> Vec<f64> {
+    values.windows(2)
+        .filter(|w| w[0] > 0.0 && w[1] > 0.0)
+        .map(|w| w[1] / w[0] - 1.0)
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskStats {
+    pub observations: usize,
+    pub total_return_percent: f64,
+    pub annualized_return_percent: f64,
+    pub annualized_volatility_percent: f64,
+    /// Annualized return over volatility, with a zero risk-free rate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sharpe_ratio: Option<f64>,
+    pub max_drawdown_percent: f64,
+    pub best_period_percent: f64,
+    pub worst_period_percent: f64,
+}
+
+/// Risk figures for a value series (NAV, price, equity curve) sampled `periods_per_year` times a year
+pub fn risk_stats(values: &[f64], periods_per_year: f64) -> Option<RiskStats> {
+    let returns = simple_returns(values);
+    if returns.len() < 2 {
+        return None;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r - mean) * (r - mean)).sum::<f64>() / (n - 1.0);
+    let volatility = variance.sqrt() * periods_per_year.sqrt();
+
+    let growth = returns.iter().fold(1.0, |acc, r| acc * (1.0 + r));
+    let annualized = growth.powf(periods_per_year / n) - 1.0;
+
+    let (max_drawdown, _) = values.iter()
+        .filter(|v| **v > 0.0)
+        .fold((0.0_f64, f64::MIN), |(dd, peak), &v| {
+            let peak = peak.max(v);
+            (dd.max(1.0 - v / peak), peak)
+        });
+
+    let (best, worst) = returns.iter()
+        .fold((f64::MIN, f64::MAX), |(best, worst), &r| (best.max(r), worst.min(r)));
+
+    Some(RiskStats {
+        observations: returns.len(),
+        total_return_percent: (growth - 1.0) * 100.0,
+        annualized_return_percent: annualized * 100.0,
+        annualized_volatility_percent: volatility * 100.0,
+        sharpe_ratio: (volatility > f64::EPSILON).then(|| annualized / volatility),
+        max_drawdown_percent: max_drawdown * 100.0,
+        best_period_percent: best * 100.0,
+        worst_period_percent: worst * 100.0,
+    })
+}
@@ -0,0 +1,111 @@
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use chrono::NaiveDate;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::AppState;
+
+/// Latest (and currently only) public API version
+pub const CURRENT_API_VERSION: u32 = 1;
+const SUPPORTED_VERSIONS: &[u32] = &[1];
+
+const VERSION_HEADER: &str = "api-version";
+const VENDOR_MEDIA_PREFIX: &str = "application/vnd.portfolio.v";
+
+/// Deprecation metadata attached to responses served from the unversioned /api paths
+#[derive(Clone)]
+struct LegacyPolicy {
+    /// HTTP-date the legacy paths stop working
+    sunset: Option<HeaderValue>,
+}
+
+/// Mount the API routes under /api/v1 and keep /api/... as deprecated aliases
+pub fn versioned_api(api: Router<AppState>, config: &Config) -> Router<AppState> {
+    let sunset = NaiveDate::parse_from_str(&config.api_legacy_sunset, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(23, 59, 59))
+        .and_then(|dt| HeaderValue::from_str(&dt.and_utc().format("%a, %d %b %Y %H:%M:%S GMT").to_string()).ok());
+    if sunset.is_none() && !config.api_legacy_sunset.is_empty() {
+        tracing::warn!("⚠️ API_LEGACY_SUNSET '{}' is not YYYY-MM-DD, Sunset header disabled", config.api_legacy_sunset);
+    }
+
+    let legacy = LegacyPolicy { sunset };
+    Router::new()
+        .nest(&format!("/api/v{}", CURRENT_API_VERSION), api.clone().layer(middleware::from_fn(negotiate_version)))
+        .nest("/api", api.layer(middleware::from_fn_with_state(legacy, deprecate_legacy)))
+}
+
+/// Version requested via `API-Version: 1` or `Accept: application/vnd.portfolio.v1+json`
+fn requested_version(headers: &HeaderMap) -> Result<Option<u32>, AppError> {
+    if let Some(value) = headers.get(VERSION_HEADER).and_then(|h| h.to_str().ok()) {
+        let version = value.trim().trim_start_matches(['v', 'V']).parse::<u32>()
+            .map_err(|_| AppError::BadRequest(format!("Invalid API-Version header '{}'", value)))?;
+        return Ok(Some(version));
+    }
+
+    let accept = headers.get("accept").and_then(|h| h.to_str().ok()).unwrap_or_default();
+    for media in accept.split(',') {
+        if let Some(rest) = media.trim().strip_prefix(VENDOR_MEDIA_PREFIX) {
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            return digits.parse::<u32>()
+                .map(Some)
+                .map_err(|_| AppError::BadRequest(format!("Invalid API version in Accept header '{}'", media.trim())));
+        }
+    }
+    Ok(None)
+}
+
+fn check_version(headers: &HeaderMap, path_version: u32) -> Result<(), AppError> {
+    match requested_version(headers)? {
+        Some(v) if !SUPPORTED_VERSIONS.contains(&v) => Err(AppError::BadRequest(format!(
+            "API version {} is not supported (supported: {:?})",
+            v, SUPPORTED_VERSIONS
+        ))),
+        Some(v) if v != path_version => Err(AppError::BadRequest(format!(
+            "Requested API version {} does not match the /api/v{} path",
+            v, path_version
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn set_version_header(response: &mut Response) {
+    response.headers_mut().insert(VERSION_HEADER, HeaderValue::from(CURRENT_API_VERSION));
+}
+
+async fn negotiate_version(request: Request, next: Next) -> Response {
+    if let Err(e) = check_version(request.headers(), CURRENT_API_VERSION) {
+        return e.into_response();
+    }
+    let mut response = next.run(request).await;
+    set_version_header(&mut response);
+    response
+}
+
+async fn deprecate_legacy(State(policy): State<LegacyPolicy>, request: Request, next: Next) -> Response {
+    // Legacy paths serve the current version; a client asking for another one gets a clear error
+    if let Err(e) = check_version(request.headers(), CURRENT_API_VERSION) {
+        return e.into_response();
+    }
+
+    // Inside the nest the path has /api stripped, so this is the suffix for the successor link
+    let path = request.uri().path().to_string();
+    let mut response = next.run(request).await;
+    set_version_header(&mut response);
+
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Some(sunset) = &policy.sunset {
+        headers.insert("sunset", sunset.clone());
+    }
+    let link = format!("</api/v{}{}>; rel=\"successor-version\"", CURRENT_API_VERSION, path);
+    if let Ok(link) = HeaderValue::from_str(&link) {
+        headers.append("link", link);
+    }
+    response
+}
@@ -6,6 +6,14 @@ import Header from '@/components/Header';
 import { useSettings } from '@/contexts/SettingsContext';
 import { getApiBaseUrl } from '@/lib/api';
 
+type CatchUpPolicy = 'skip' | 'run_once_late' | 'backfill';
+
+// Mirrors JobConfig::catch_up_policy on the backend
+const effectiveCatchUp = (job: JobConfig): CatchUpPolicy =>
+    job.catch_up ?? (job.job_type === 'portfolio_snapshot'
+        ? 'backfill'
+        : job.schedule_times ? 'skip' : 'run_once_late');
+
 interface JobConfig {
     id: string;
     name: string;
@@ -17,7 +25,23 @@ interface JobConfig {
     last_run: string | null;
     next_run: string | null;
     schedule_times: string[] | null;
+    catch_up?: CatchUpPolicy | null;
     last_result: any | null;
+    skipped_runs?: number;
+    last_skipped_at?: string | null;
+}
+
+interface JobRun {
+    id: string;
+    trigger: 'schedule' | 'manual' | 'retry' | 'catch_up';
+    attempt: number;
+    started_at: string;
+    finished_at: string | null;
+    duration_ms: number | null;
+    status: string;
+    result: any | null;
+    error: string | null;
+    retry_at: string | null;
 }
 
 interface ApiStatusResult {
@@ -44,13 +68,18 @@ export default function JobsPage() {
     const [selectedJob, setSelectedJob] = useState<JobConfig | null>(null);
     const [isModalOpen, setIsModalOpen] = useState(false);
     const [isRunning, setIsRunning] = useState<Record<string, boolean>>({});
+    const [runs, setRuns] = useState<JobRun[]>([]);
+    const [expandedRun, setExpandedRun] = useState<string | null>(null);
 
 
 
     // Fetch jobs
     const fetchJobs = async () => {
+        const token = localStorage.getItem('auth_token');
         try {
-            const response = await fetch(`${getApiBaseUrl()}/api/jobs`);
+            const response = await fetch(`${getApiBaseUrl()}/api/jobs`, {
+                headers: { 'Authorization': `Bearer ${token}` },
+            });
             if (response.ok) {
                 const data = await response.json();
                 setJobs(data.jobs || []);
@@ -66,6 +95,20 @@ export default function JobsPage() {
         fetchJobs();
     }, []);
 
+    // Run history of the job shown in the modal
+    const selectedJobId = isModalOpen ? selectedJob?.id : undefined;
+    useEffect(() => {
+        setRuns([]);
+        setExpandedRun(null);
+        if (!selectedJobId) return;
+        fetch(`${getApiBaseUrl()}/api/jobs/${selectedJobId}/runs?per_page=20`, {
+            headers: { 'Authorization': `Bearer ${localStorage.getItem('auth_token')}` },
+        })
+            .then(response => response.ok ? response.json() : { runs: [] })
+            .then(data => setRuns(data.runs || []))
+            .catch(error => console.error('Failed to fetch job runs:', error));
+    }, [selectedJobId]);
+
     // Update job
     const updateJob = async (id: string, updates: Partial<JobConfig>) => {
         try {
@@ -77,7 +120,10 @@ export default function JobsPage() {
 
             const response = await fetch(`${getApiBaseUrl()}/api/jobs/${id}`, {
                 method: 'PUT',
-                headers: { 'Content-Type': 'application/json' },
+                headers: {
+                    'Content-Type': 'application/json',
+                    'Authorization': `Bearer ${localStorage.getItem('auth_token')}`,
+                },
                 body: JSON.stringify(updates),
             });
             if (response.ok) {
@@ -102,6 +148,7 @@ export default function JobsPage() {
         try {
             const response = await fetch(`${getApiBaseUrl()}/api/jobs/${id}/run`, {
                 method: 'POST',
+                headers: { 'Authorization': `Bearer ${localStorage.getItem('auth_token')}` },
             });
             if (response.ok) {
                 await fetchJobs(); // Refresh to get latest status
@@ -235,6 +282,12 @@ export default function JobsPage() {
                                             <span className="text-gray-500">{t('รันครั้งต่อไป', 'Next Run')}:</span>
                                             <span className="ml-2 text-white">{job.enabled ? formatDateTime(job.next_run) : '-'}</span>
                                         </div>
+                                        {!!job.skipped_runs && (
+                                            <div title={formatDateTime(job.last_skipped_at ?? null)}>
+                                                <span className="text-gray-500">{t('ข้ามรอบ', 'Skipped')}:</span>
+                                                <span className="ml-2 text-amber-400">⏭️ {job.skipped_runs}</span>
+                                            </div>
+                                        )}
                                     </div>
 
                                     {/* Actions */}
@@ -388,6 +441,33 @@ export default function JobsPage() {
                                 </div>
                             )}
 
+                            <div>
+                                <label className="block text-sm text-gray-400 mb-2">
+                                    {t('เมื่อพลาดรอบการทำงาน', 'Missed Runs')}
+                                </label>
+                                <div className="flex gap-4">
+                                    {([
+                                        ['skip', t('ข้าม', 'Skip')],
+                                        ['run_once_late', t('รันทันทีหนึ่งครั้ง', 'Run Once')],
+                                        ['backfill', t('ย้อนเติมทุกรอบ', 'Backfill')],
+                                    ] as [CatchUpPolicy, string][]).map(([policy, label]) => (
+                                        <button
+                                            key={policy}
+                                            onClick={() => updateJob(selectedJob.id, { catch_up: policy })}
+                                            className={`px-4 py-2 rounded-lg text-sm transition-colors border ${effectiveCatchUp(selectedJob) === policy
+                                                ? 'bg-blue-600 border-blue-500 text-white'
+                                                : 'bg-gray-700 border-gray-600 text-gray-300 hover:bg-gray-600'
+                                                }`}
+                                        >
+                                            {label}
+                                        </button>
+                                    ))}
+                                </div>
+                                <p className="text-xs text-gray-500 mt-2">
+                                    {t('สิ่งที่ทำเมื่อเซิร์ฟเวอร์เริ่มใหม่หลังพลาดรอบที่กำหนดไว้', 'What to do at startup for runs missed while the server was down')}
+                                </p>
+                            </div>
+
                             <div className="flex items-center justify-between p-4 bg-gray-700/30 rounded-lg">
                                 <div>
                                     <div className="text-white font-medium">{t('เปิดใช้งาน', 'Enabled')}</div>
@@ -477,6 +557,46 @@ export default function JobsPage() {
                             </div>
                         )}
 
+                        {/* Run History Section */}
+                        {runs.length > 0 && (
+                            <div className="mt-6">
+                                <h4 className="text-white font-medium mb-3 flex items-center gap-2">
+                                    🕘 {t('ประวัติการรัน', 'Run History')}
+                                </h4>
+                                <div className="space-y-2 max-h-72 overflow-y-auto">
+                                    {runs.map(run => (
+                                        <div key={run.id} className="p-3 bg-gray-700/30 rounded-lg">
+                                            <button
+                                                onClick={() => setExpandedRun(expandedRun === run.id ? null : run.id)}
+                                                className="w-full flex items-center justify-between text-sm text-left"
+                                            >
+                                                <span className="flex items-center gap-2">
+                                                    {getStatusBadge(run.status)}
+                                                    <span className="text-gray-300">{formatDateTime(run.started_at)}</span>
+                                                    <span className="text-xs text-gray-500">
+                                                        {run.trigger}{run.attempt > 1 ? ` #${run.attempt}` : ''}
+                                                    </span>
+                                                </span>
+                                                <span className="text-xs text-gray-500 font-mono">
+                                                    {run.duration_ms != null ? `${(run.duration_ms / 1000).toFixed(1)}s` : '-'}
+                                                </span>
+                                            </button>
+                                            {run.retry_at && (
+                                                <div className="text-xs text-amber-400 mt-1">
+                                                    🔁 {t('จะลองใหม่', 'Retrying at')} {formatDateTime(run.retry_at)}
+                                                </div>
+                                            )}
+                                            {expandedRun === run.id && (
+                                                <pre className="mt-2 p-3 bg-gray-900 rounded-lg text-xs text-gray-300 overflow-x-auto">
+                                                    {run.error ?? JSON.stringify(run.result, null, 2)}
+                                                </pre>
+                                            )}
+                                        </div>
+                                    ))}
+                                </div>
+                            </div>
+                        )}
+
                         {/* Close Button */}
                         <div className="flex justify-end mt-6">
                             <button
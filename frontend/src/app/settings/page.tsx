@@ -680,7 +680,10 @@ function SystemSettings() {
         try {
             const response = await fetch(`${getApiBaseUrl()}/api/seed/upload`, {
                 method: 'POST',
-                headers: { 'Content-Type': 'application/json' },
+                headers: {
+                    'Content-Type': 'application/json',
+                    'Authorization': `Bearer ${localStorage.getItem('auth_token')}`,
+                },
                 body: JSON.stringify(seedData),
             });
 
@@ -713,7 +716,9 @@ function SystemSettings() {
         setExporting(true);
         setExportResult(null);
         try {
-            const response = await fetch(`${getApiBaseUrl()}/api/seed/export`);
+            const response = await fetch(`${getApiBaseUrl()}/api/seed/export`, {
+                headers: { 'Authorization': `Bearer ${localStorage.getItem('auth_token')}` },
+            });
             const result = await response.json();
 
             if (result.success && result.data) {
@@ -170,8 +170,9 @@ export default function PricesSettingsPage() {
 
     const handleRefreshAll = async () => {
         setRefreshing(true);
+        const authHeaders = { 'Authorization': `Bearer ${localStorage.getItem('auth_token')}` };
         try {
-            const jobsResponse = await fetch(`${getApiBaseUrl()}/api/jobs`);
+            const jobsResponse = await fetch(`${getApiBaseUrl()}/api/jobs`, { headers: authHeaders });
             if (!jobsResponse.ok) throw new Error('Failed to fetch jobs');
 
             const jobsData = await jobsResponse.json();
@@ -183,7 +184,8 @@ export default function PricesSettingsPage() {
             }
 
             const runResponse = await fetch(`${getApiBaseUrl()}/api/jobs/${priceJob.id}/run`, {
-                method: 'POST'
+                method: 'POST',
+                headers: authHeaders,
             });
 
             if (runResponse.ok) {
@@ -257,6 +257,16 @@ function AlertCard({
                                 Email
                             </span>
                         )}
+                        {alert.channels.includes('telegram') && (
+                            <span className="px-2 py-0.5 text-xs bg-sky-100 dark:bg-sky-900/30 text-sky-700 dark:text-sky-300 rounded">
+                                Telegram
+                            </span>
+                        )}
+                        {alert.channels.includes('line') && (
+                            <span className="px-2 py-0.5 text-xs bg-emerald-100 dark:bg-emerald-900/30 text-emerald-700 dark:text-emerald-300 rounded">
+                                LINE
+                            </span>
+                        )}
                     </div>
 
                     {/* Actions */}
@@ -311,6 +321,7 @@ function AlertFormModal({
     const alertTypes: { value: AlertType; label: string }[] = [
         { value: 'price_above', label: '📈 ราคาสูงกว่า' },
         { value: 'price_below', label: '📉 ราคาต่ำกว่า' },
+        { value: 'price_change_percent', label: '↕️ ราคาเปลี่ยนแปลง (%)' },
         { value: 'pnl_threshold_percent', label: '💰 กำไร/ขาดทุน (%)' },
         { value: 'portfolio_change_percent', label: '📊 พอร์ตเปลี่ยนแปลง (%)' },
         { value: 'daily_pnl_report', label: '📅 สรุปรายวัน' },
@@ -350,7 +361,7 @@ function AlertFormModal({
         }
     };
 
-    const needsSymbol = ['price_above', 'price_below'].includes(form.alert_type);
+    const needsSymbol = ['price_above', 'price_below', 'price_change_percent'].includes(form.alert_type);
 
     return (
         <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/50">
@@ -493,6 +504,26 @@ function AlertFormModal({
                                 >
                                     ✉️ Email
                                 </button>
+                                <button
+                                    type="button"
+                                    onClick={() => toggleChannel('telegram')}
+                                    className={`px-4 py-2 rounded-lg border transition-colors ${form.channels.includes('telegram')
+                                        ? 'bg-sky-600 text-white border-sky-600'
+                                        : 'bg-white dark:bg-gray-700 text-gray-700 dark:text-gray-300 border-gray-300 dark:border-gray-600'
+                                        }`}
+                                >
+                                    ✈️ Telegram
+                                </button>
+                                <button
+                                    type="button"
+                                    onClick={() => toggleChannel('line')}
+                                    className={`px-4 py-2 rounded-lg border transition-colors ${form.channels.includes('line')
+                                        ? 'bg-emerald-600 text-white border-emerald-600'
+                                        : 'bg-white dark:bg-gray-700 text-gray-700 dark:text-gray-300 border-gray-300 dark:border-gray-600'
+                                        }`}
+                                >
+                                    💬 LINE
+                                </button>
                             </div>
                         </div>
 
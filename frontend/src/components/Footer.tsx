@@ -6,7 +6,6 @@ import { getApiBaseUrl } from '@/lib/api';
 interface StatusResponse {
     status: string;
     pocketbase: {
-        url: string;
         connected: boolean;
     };
     timestamp: string;
@@ -149,6 +149,7 @@ export default function TransactionForm({ onSuccess, onClose, defaultAccountId,
                 quantity: editTransaction.quantity,
                 price: editTransaction.price,
                 fees: editTransaction.fees,
+                network_fee: editTransaction.network_fee,
                 market: editTransaction.market,
                 currency: editTransaction.currency,
                 timestamp: toLocalDateTimeFormat(editTransaction.timestamp),
@@ -221,6 +222,7 @@ export default function TransactionForm({ onSuccess, onClose, defaultAccountId,
                 quantity: editTransaction.quantity,
                 price: editTransaction.price,
                 fees: editTransaction.fees,
+                network_fee: editTransaction.network_fee,
                 market: editTransaction.market,
                 currency: editTransaction.currency,
                 timestamp: toLocalDateTimeFormat(editTransaction.timestamp),
@@ -1325,6 +1327,24 @@ export default function TransactionForm({ onSuccess, onClose, defaultAccountId,
                             />
                         </div>
 
+                        {/* Network (gas) fee - crypto only */}
+                        {formData.asset_type === 'crypto' && (
+                            <div>
+                                <label className="block text-sm font-medium text-gray-400 mb-2">
+                                    ⛽ {t('ค่าธรรมเนียมเครือข่าย (Gas)', 'Network / Gas Fee')} ({formData.currency || 'THB'})
+                                </label>
+                                <input
+                                    type="number"
+                                    step="any"
+                                    min="0"
+                                    value={formData.network_fee || ''}
+                                    onChange={(e) => setFormData({ ...formData, network_fee: parseFloat(e.target.value) || 0 })}
+                                    placeholder="0.00"
+                                    className="w-full px-4 py-3 bg-gray-700/50 border border-gray-600/50 rounded-lg text-white placeholder-gray-500 focus:outline-none focus:ring-2 focus:ring-emerald-500/50 focus:border-emerald-500 transition-all font-mono"
+                                />
+                            </div>
+                        )}
+
                         {/* Date/Time */}
                         <div>
                             <label className="block text-sm font-medium text-gray-400 mb-2">
@@ -174,11 +174,21 @@ export default function TransactionImportModal({ onClose, onSuccess }: Transacti
             const result = await createTransactionsBulk(payload);
 
             if (result.success) {
-                alert(`Successfully imported ${result.count} transactions!`);
+                const duplicates = result.summary.duplicates > 0
+                    ? ` (${result.summary.duplicates} look like duplicates)`
+                    : '';
+                alert(`Successfully imported ${result.count} transactions!${duplicates}`);
                 onSuccess();
                 onClose();
             } else {
-                setImportErrors(result.errors || ["Unknown error during import"]);
+                // Rows that passed are already saved; keep the modal open to show the rejected ones
+                if (result.count > 0) {
+                    onSuccess();
+                }
+                setImportErrors([
+                    `Imported ${result.count} of ${result.summary.total} transactions`,
+                    ...(result.errors.length > 0 ? result.errors : ["Unknown error during import"]),
+                ]);
             }
         } catch (error: any) {
             setImportErrors([error.message || "Network error"]);
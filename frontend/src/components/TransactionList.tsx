@@ -253,6 +253,14 @@ export default function TransactionList({ transactions, portfolio, isLoading, on
                                         )}
                                     </div>
                                 )}
+                                {(tx.network_fee ?? 0) > 0 && (
+                                    <div className="text-xs text-gray-500">
+                                        ⛽ {t('ค่าเครือข่าย', 'Network fee')}: {formatCurrency(
+                                            convertToDisplayCurrency ? convertToDisplayCurrency(tx.network_fee ?? 0, tx.currency) : (tx.network_fee ?? 0),
+                                            displayCurrency
+                                        )}
+                                    </div>
+                                )}
                             </div>
 
                             {/* Actions */}
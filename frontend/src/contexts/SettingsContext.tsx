@@ -1,6 +1,7 @@
 'use client';
 
 import React, { createContext, useContext, useState, useEffect, ReactNode } from 'react';
+import { setClientMode } from '@/lib/api';
 
 // Types
 export type Theme = 'dark' | 'light';
@@ -176,6 +177,24 @@ export function SettingsProvider({ children }: { children: ReactNode }) {
         setIsLoaded(true);
     }, []);
 
+    // Tell the backend when this tab is hidden so it stops streaming live and heating symbols.
+    // The declaration lapses after a while, so a hidden tab repeats it.
+    useEffect(() => {
+        if (!localStorage.getItem('auth_token')) return;
+        const declare = () => {
+            setClientMode(document.hidden ? 'background' : 'foreground').catch(() => {});
+        };
+        declare();
+        document.addEventListener('visibilitychange', declare);
+        const renew = window.setInterval(() => {
+            if (document.hidden) declare();
+        }, 10 * 60 * 1000);
+        return () => {
+            document.removeEventListener('visibilitychange', declare);
+            window.clearInterval(renew);
+        };
+    }, []);
+
     // Save settings to localStorage
     useEffect(() => {
         if (isLoaded) {
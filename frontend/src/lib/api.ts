@@ -37,6 +37,19 @@ function getAuthToken(): string | null {
     return localStorage.getItem(TOKEN_KEY);
 }
 
+const CLIENT_ID_KEY = 'client_id';
+
+// Random id of this tab, under which it declares foreground/background to the backend
+export function getClientId(): string | null {
+    if (typeof window === 'undefined') return null;
+    let id = sessionStorage.getItem(CLIENT_ID_KEY);
+    if (!id) {
+        id = `web-${Math.random().toString(36).slice(2, 12)}${Date.now().toString(36)}`;
+        sessionStorage.setItem(CLIENT_ID_KEY, id);
+    }
+    return id;
+}
+
 // Generic fetch wrapper with error handling and auth
 async function fetchApi<T>(
     endpoint: string,
@@ -53,6 +66,10 @@ async function fetchApi<T>(
     if (token) {
         headers['Authorization'] = `Bearer ${token}`;
     }
+    const clientId = getClientId();
+    if (clientId) {
+        headers['X-Client-Id'] = clientId;
+    }
 
     const response = await fetch(`${getApiBaseUrl()}${endpoint}`, {
         headers,
@@ -94,10 +111,35 @@ export async function createTransaction(
     });
 }
 
+export interface BulkItemResult {
+    index: number;
+    status: 'created' | 'invalid' | 'failed';
+    id?: string;
+    error?: string;
+    warnings?: string[];
+    duplicate_of?: string;
+}
+
+export interface BulkCreateResponse {
+    success: boolean;
+    summary: {
+        total: number;
+        created: number;
+        invalid: number;
+        failed: number;
+        duplicates: number;
+        chunks: number;
+    };
+    results: BulkItemResult[];
+    count: number;
+    errors: string[];
+    warnings: string[];
+}
+
 export async function createTransactionsBulk(
     data: CreateTransactionRequest[]
-): Promise<{ success: boolean; count: number; errors: string[] }> {
-    return fetchApi<{ success: boolean; count: number; errors: string[] }>('/api/transactions/bulk', {
+): Promise<BulkCreateResponse> {
+    return fetchApi<BulkCreateResponse>('/api/transactions/bulk', {
         method: 'POST',
         body: JSON.stringify(data),
     });
@@ -615,6 +657,7 @@ export async function getApiStats(): Promise<ApiCallStats[]> {
 export type AlertType =
     | 'price_above'
     | 'price_below'
+    | 'price_change_percent'
     | 'pnl_threshold_percent'
     | 'pnl_threshold_absolute'
     | 'portfolio_change_percent'
@@ -622,7 +665,7 @@ export type AlertType =
 
 export type Comparison = 'above' | 'below' | 'equals';
 
-export type NotificationChannel = 'email' | 'web_push' | 'in_app';
+export type NotificationChannel = 'email' | 'web_push' | 'in_app' | 'telegram' | 'line';
 
 export interface AlertRule {
     id: string;
@@ -630,6 +673,8 @@ export interface AlertRule {
     name: string;
     alert_type: AlertType;
     symbol?: string;
+    asset_type?: string;
+    reference_price?: number;
     threshold: number;
     comparison: Comparison;
     channels: NotificationChannel[];
@@ -644,6 +689,7 @@ export interface CreateAlertRequest {
     name: string;
     alert_type: AlertType;
     symbol?: string;
+    asset_type?: string;
     threshold: number;
     comparison: Comparison;
     channels: NotificationChannel[];
@@ -744,6 +790,53 @@ export async function sendTestNotification(): Promise<{ success: boolean; messag
     });
 }
 
+// ==================== Notification Channel API ====================
+
+export type ExternalChannel = 'email' | 'telegram' | 'line';
+
+export interface NotificationTarget {
+    id: string;
+    user_id: string;
+    channel: ExternalChannel;
+    target: string;
+    token_hint?: string;
+    enabled: boolean;
+    last_delivered_at?: string;
+    last_error?: string;
+}
+
+export interface NotificationChannelStatus {
+    channel: ExternalChannel;
+    available: boolean;
+    target: NotificationTarget | null;
+}
+
+export async function getNotificationChannels(): Promise<{ channels: NotificationChannelStatus[] }> {
+    return fetchApi('/api/notifications/channels');
+}
+
+export async function setNotificationChannel(
+    channel: ExternalChannel,
+    data: { target?: string; token?: string; enabled?: boolean },
+): Promise<NotificationTarget> {
+    return fetchApi<NotificationTarget>(`/api/notifications/channels/${channel}`, {
+        method: 'PUT',
+        body: JSON.stringify(data),
+    });
+}
+
+export async function deleteNotificationChannel(channel: ExternalChannel): Promise<void> {
+    await fetchApi(`/api/notifications/channels/${channel}`, {
+        method: 'DELETE',
+    });
+}
+
+export async function testNotificationChannel(channel: ExternalChannel): Promise<{ success: boolean; target: NotificationTarget }> {
+    return fetchApi(`/api/notifications/channels/${channel}/test`, {
+        method: 'POST',
+    });
+}
+
 // ==================== Push Subscription API ====================
 
 export interface PushSubscriptionRequest {
@@ -759,11 +852,93 @@ export async function subscribePush(data: PushSubscriptionRequest): Promise<{ id
     });
 }
 
+// ==================== Dashboard API ====================
+
+export interface DashboardWidget {
+    id: string;
+    widget_type: string;
+    title?: string;
+    layout: { x: number; y: number; w: number; h: number };
+    // The API call filling the card: a path under /api plus query parameters
+    query: { endpoint: string; params?: Record<string, string | number | boolean> };
+    options?: Record<string, unknown>;
+}
+
+export interface Dashboard {
+    id: string;
+    name: string;
+    is_default: boolean;
+    widgets: DashboardWidget[];
+    created?: string;
+    updated?: string;
+}
+
+export interface UpdateDashboardRequest {
+    name?: string;
+    is_default?: boolean;
+    widgets?: DashboardWidget[];
+    // `updated` of the version being edited; the server answers 409 if another device saved since
+    expected_updated?: string;
+}
+
+export async function getDashboards(): Promise<Dashboard[]> {
+    return fetchApi<Dashboard[]>('/api/dashboards');
+}
+
+export async function getDashboard(id: string): Promise<Dashboard> {
+    return fetchApi<Dashboard>(`/api/dashboards/${id}`);
+}
+
+export async function createDashboard(data: { name: string; is_default?: boolean; widgets?: DashboardWidget[] }): Promise<Dashboard> {
+    return fetchApi<Dashboard>('/api/dashboards', {
+        method: 'POST',
+        body: JSON.stringify(data),
+    });
+}
+
+export async function updateDashboard(id: string, data: UpdateDashboardRequest): Promise<Dashboard> {
+    return fetchApi<Dashboard>(`/api/dashboards/${id}`, {
+        method: 'PUT',
+        body: JSON.stringify(data),
+    });
+}
+
+export async function deleteDashboard(id: string): Promise<void> {
+    await fetchApi(`/api/dashboards/${id}`, {
+        method: 'DELETE',
+    });
+}
+
+// ==================== Client Mode API ====================
+
+export type ClientMode = 'foreground' | 'background' | 'mobile-low-power';
+
+export interface ClientModeResponse {
+    client_id: string;
+    mode: ClientMode;
+    expires_at: string;
+    ttl_seconds: number;
+    polling: {
+        portfolio_seconds: number;
+        prices_seconds: number;
+        // 0 when the WebSocket streams push live
+        push_batch_seconds: number;
+    };
+}
+
+export async function setClientMode(mode: ClientMode): Promise<ClientModeResponse> {
+    return fetchApi<ClientModeResponse>('/api/clients/mode', {
+        method: 'PUT',
+        body: JSON.stringify({ client_id: getClientId(), mode }),
+    });
+}
+
 // Helper to get alert type display name
 export function getAlertTypeName(type: AlertType, language: string = 'th'): string {
     const names: Record<AlertType, { th: string; en: string }> = {
         price_above: { th: 'ราคาสูงกว่า', en: 'Price Above' },
         price_below: { th: 'ราคาต่ำกว่า', en: 'Price Below' },
+        price_change_percent: { th: 'ราคาเปลี่ยนแปลง (%)', en: 'Price Move (%)' },
         pnl_threshold_percent: { th: 'กำไร/ขาดทุน (%)', en: 'P&L Threshold (%)' },
         pnl_threshold_absolute: { th: 'กำไร/ขาดทุน (฿)', en: 'P&L Threshold (฿)' },
         portfolio_change_percent: { th: 'พอร์ตเปลี่ยนแปลง (%)', en: 'Portfolio Change (%)' },
@@ -778,6 +953,8 @@ export function getChannelName(channel: NotificationChannel, language: string =
         email: { th: 'อีเมล', en: 'Email' },
         web_push: { th: 'Browser Push', en: 'Browser Push' },
         in_app: { th: 'In-App', en: 'In-App' },
+        telegram: { th: 'Telegram', en: 'Telegram' },
+        line: { th: 'LINE', en: 'LINE' },
     };
     return names[channel]?.[language === 'th' ? 'th' : 'en'] || channel;
 }
@@ -63,6 +63,7 @@ export interface Transaction {
   quantity: number;
   price: number;
   fees: number;
+  network_fee?: number; // On-chain gas / network fee, separate from trading fees
   timestamp: string;
   market?: Market;
   currency?: string;
@@ -84,6 +85,7 @@ export interface CreateTransactionRequest {
   quantity: number;
   price: number;
   fees?: number;
+  network_fee?: number;
   timestamp?: string;
   market?: Market;
   currency?: string;
@@ -103,6 +105,7 @@ export interface UpdateTransactionRequest {
   quantity?: number;
   price?: number;
   fees?: number;
+  network_fee?: number;
   timestamp?: string;
   market?: Market;
   currency?: string;
@@ -15,6 +15,7 @@ from yahoo_finance_server.helper import (
     get_ticker_news,
     search_yahoo_finance,
     get_price_history,
+    get_latest_closes,
     get_top_entities
 )
 
@@ -82,6 +83,21 @@ async def price_history(
         logger.error(f"Error fetching price history for {symbol}: {e}")
         raise HTTPException(status_code=500, detail=str(e))
 
+# Most symbols accepted by one /api/quotes call
+MAX_QUOTE_SYMBOLS = 50
+
+@app.get("/api/quotes")
+async def quotes(symbols: str):
+    # Comma-separated, e.g. ?symbols=PTT.BK,AOT.BK,AAPL
+    requested = [s.strip() for s in symbols.split(",") if s.strip()]
+    if not requested or len(requested) > MAX_QUOTE_SYMBOLS:
+        raise HTTPException(status_code=400, detail=f"Pass 1-{MAX_QUOTE_SYMBOLS} comma-separated symbols")
+    try:
+        return await get_latest_closes(requested)
+    except Exception as e:
+        logger.error(f"Error fetching quotes for {requested}: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
 if __name__ == "__main__":
     import uvicorn
     uvicorn.run(app, host=YAHOO_FINANCE_HOST, port=YAHOO_FINANCE_PORT)
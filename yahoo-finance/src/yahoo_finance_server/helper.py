@@ -791,6 +791,59 @@ async def get_price_history(
         raise Exception(f"Failed to get price history: {str(e)}")
 
 
+async def get_latest_closes(symbols: List[str]) -> Dict[str, Any]:
+    """
+    Fetch the latest daily close of several symbols with a single download.
+
+    Args:
+        symbols: Stock symbols (e.g. PTT.BK, AAPL)
+
+    Returns:
+        Dictionary with a "data" map of symbol -> {"close", "date"}; symbols without a close are left out
+    """
+    try:
+
+        def _get_closes():
+            # A few sessions back, so symbols that didn't trade today still have a close
+            hist = yf.download(
+                tickers=symbols,
+                period="5d",
+                interval="1d",
+                group_by="ticker",
+                auto_adjust=False,
+                progress=False,
+                threads=False,
+            )
+            closes = {}
+            if hist.empty:
+                return {"data": closes, "count": 0}
+
+            for symbol in symbols:
+                try:
+                    series = hist[symbol]["Close"] if len(symbols) > 1 else hist["Close"]
+                except KeyError:
+                    continue
+                if isinstance(series, pd.DataFrame):
+                    series = series.iloc[:, 0]
+                series = series.dropna()
+                if series.empty:
+                    continue
+                closes[symbol] = {
+                    "close": float(series.iloc[-1]),
+                    "date": series.index[-1].isoformat(),
+                }
+
+            return {"data": closes, "count": len(closes)}
+
+        # Run in thread pool to avoid blocking
+        loop = asyncio.get_event_loop()
+        return await loop.run_in_executor(None, _get_closes)
+
+    except Exception as e:
+        logger.error(f"Error getting latest closes for {symbols}: {e}")
+        raise Exception(f"Failed to get latest closes: {str(e)}")
+
+
 async def get_ticker_option_chain(
     symbol: str, option_type: str = "both", date: Optional[str] = None
 ) -> Dict[str, Any]: